@@ -6,6 +6,7 @@ use std::mem::transmute;
 use std::ops::{Deref, DerefMut};
 use std::sync::RwLock;
 
+use serde::{Deserialize, Serialize};
 use static_rc::StaticRc;
 
 // pub const INITIAL_SIZE: usize = 100;
@@ -54,7 +55,7 @@ impl<T> std::ops::IndexMut<usize> for AddOnlyVec<T> {
     }
 }
 
-#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, PartialOrd, Ord)]
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct AddOnlyId(pub AddOnlyVecIDType);
 
 struct AddOnlyIdMapInner<T>
@@ -114,6 +115,14 @@ impl<T> AddOnlyIdMap<T>
         unsafe { transmute::<&T, &'l T>(res) } //this is safe b/c we never free any boxes until self goes out of scope
     }
 
+    pub fn len(&self) -> usize {
+        self.inner.read().unwrap().map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
         Self { inner: RwLock::new(AddOnlyIdMapInner { map: Default::default(), owner: AddOnlyVec::new() }) }
@@ -330,6 +330,9 @@ impl IRInstr {
                         "RunSpecialNativeNew"
                     }
                     IRVMExitType::RunInterpreted { .. } => { "RunInterpreted" }
+                    IRVMExitType::Deoptimize { .. } => { "Deoptimize" }
+                    IRVMExitType::DeoptimizeReturn => { "DeoptimizeReturn" }
+                    IRVMExitType::SafePointPoll { .. } => { "SafePointPoll" }
                     IRVMExitType::AssertInstanceOf { .. } => { "AssertInstanceOf" }
                     IRVMExitType::NewClassRegister { .. } => { "NewClassRegister" }
                     IRVMExitType::MonitorEnterRegister { .. } => { "MonitorEnterRegister" }
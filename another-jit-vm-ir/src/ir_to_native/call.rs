@@ -78,8 +78,10 @@ pub fn ir_call(assembler: &mut CodeAssembler, temp_register_1: Register, temp_re
     };
     assembler.jmp(temp_register).unwrap();
     assembler.set_label(&mut after_call_label).unwrap();
-    if let Some(return_value) = return_value {
-        assembler.mov(rbp - return_value.0, rax).unwrap();
+    //the return address is always an instruction of this call, not whatever comes next
+    match return_value {
+        Some(return_value) => assembler.mov(rbp - return_value.0, rax).unwrap(),
+        None => assembler.nop().unwrap(),
     }
     let (mov_position, method_id) = mov_position_and_method_id?;
     let modification_target = AssemblerRuntimeModificationTarget::MovQ { instruction_number: mov_position };
@@ -21,12 +21,13 @@ use another_jit_vm::saved_registers_utils::{SavedRegistersWithIPDiff, SavedRegis
 use compiler::{IRInstr, LabelName, RestartPointID};
 use gc_memory_layout_common::frame_layout::FRAME_HEADER_END_OFFSET;
 use ir_stack::OPAQUE_FRAME_SIZE;
-use rust_jvm_common::MethodId;
+use rust_jvm_common::{ByteCodeOffset, MethodId};
 use rust_jvm_common::opaque_id_table::OpaqueID;
 
 use crate::compiler::{BitwiseLogicType, FloatCompareMode, IRCallTarget, Signed, Size};
 use crate::ir_stack::OwnedIRStack;
 use crate::ir_to_native::single_ir_to_native;
+use crate::validate::validate_function;
 use crate::vm_exit_abi::IRVMExitType;
 use crate::vm_exit_abi::register_structs::InvokeVirtualResolve;
 use crate::vm_exit_abi::runtime_input::RuntimeVMExitInput;
@@ -37,6 +38,8 @@ pub mod compiler;
 pub mod vm_exit_abi;
 pub mod ir_stack;
 pub mod ir_to_native;
+pub mod text;
+pub mod validate;
 
 //todo this trait is a hack should really move this into main crate or something
 pub trait HasRBPAndRSP {
@@ -58,6 +61,8 @@ pub struct IRVMStateInner<'vm, HandlerExtraData: HasRBPAndRSP> {
     frame_sizes_by_ir_method_id: HashMap<IRMethodID, usize>,
     method_ir_offsets_range: HashMap<IRMethodID, BTreeMap<IRInstructNativeOffset, IRInstructIndex>>,
     method_ir_offsets_at_index: HashMap<IRMethodID, HashMap<IRInstructIndex, IRInstructNativeOffset>>,
+    // return address offsets of each ir call, recorded when assembling so frames can be mapped back to their call exactly
+    method_ir_call_return_offsets: HashMap<IRMethodID, HashMap<IRInstructNativeOffset, IRInstructIndex>>,
     _method_ir: HashMap<IRMethodID, Vec<IRInstr>>,
     // index
     opaque_method_to_or_method_id: HashMap<OpaqueID, IRMethodID>,
@@ -65,6 +70,8 @@ pub struct IRVMStateInner<'vm, HandlerExtraData: HasRBPAndRSP> {
     pub handler: OnceCell<ExitHandlerType<'vm, HandlerExtraData>>,
 
     reserved_ir_method_id: HashSet<IRMethodID>,
+    // run validate_function on everything passed to add_function
+    validate_ir: bool,
 }
 
 impl<'vm, HandlerExtraData: HasRBPAndRSP> IRVMStateInner<'vm, HandlerExtraData> {
@@ -77,16 +84,19 @@ impl<'vm, HandlerExtraData: HasRBPAndRSP> IRVMStateInner<'vm, HandlerExtraData>
             frame_sizes_by_ir_method_id: Default::default(),
             method_ir_offsets_range: Default::default(),
             method_ir_offsets_at_index: Default::default(),
+            method_ir_call_return_offsets: Default::default(),
             _method_ir: Default::default(),
             opaque_method_to_or_method_id: Default::default(),
             handler: Default::default(),
             reserved_ir_method_id: Default::default(),
+            validate_ir: cfg!(debug_assertions),
         }
     }
 
     pub fn add_function_ir_offsets(&mut self, current_ir_id: IRMethodID,
                                    new_instruction_offsets: &Vec<IRInstructNativeOffset>,
-                                   ir_instruct_index_to_assembly_index: Vec<(IRInstructIndex, AssemblyInstructionIndex)>) {
+                                   ir_instruct_index_to_assembly_index: Vec<(IRInstructIndex, AssemblyInstructionIndex)>,
+                                   call_return_points: Vec<(IRInstructIndex, AssemblyInstructionIndex)>) {
         let mut offsets_range = BTreeMap::new();
         let mut offsets_at_index = HashMap::new();
         for ((i, instruction_offset), (ir_instruction_index, assembly_instruction_index_2)) in new_instruction_offsets.into_iter().enumerate().zip(ir_instruct_index_to_assembly_index.into_iter()) {
@@ -104,6 +114,10 @@ impl<'vm, HandlerExtraData: HasRBPAndRSP> IRVMStateInner<'vm, HandlerExtraData>
         assert_eq!(indexes.iter().max().unwrap().0 + 1, indexes.len());
         self.method_ir_offsets_range.insert(current_ir_id, offsets_range);
         self.method_ir_offsets_at_index.insert(current_ir_id, offsets_at_index);
+        let call_return_offsets = call_return_points.into_iter()
+            .map(|(ir_instruction_index, AssemblyInstructionIndex(return_point))| (new_instruction_offsets[return_point], ir_instruction_index))
+            .collect();
+        self.method_ir_call_return_offsets.insert(current_ir_id, call_return_offsets);
     }
 }
 
@@ -175,6 +189,17 @@ impl<'vm, HandlerExtraData: HasRBPAndRSP> IRVMState<'vm, HandlerExtraData> {
         (ir_method_id, ir_instruct_index)
     }
 
+    // None if return_address isn't where an ir call returns to
+    pub fn lookup_call_return_address(&self, return_address: *const c_void) -> Option<(IRMethodID, IRInstructIndex)> {
+        let implementation_id = self.native_vm.lookup_ip(return_address);
+        let method_start = self.native_vm.lookup_method_addresses(implementation_id).start;
+        let native_offset = IRInstructNativeOffset(unsafe { method_start.offset_from(return_address).abs() } as usize);
+        let guard = self.inner.read().unwrap();
+        let ir_method_id = *guard.implementation_id_to_ir_method_id.get(&implementation_id).unwrap();
+        let ir_instruct_index = *guard.method_ir_call_return_offsets.get(&ir_method_id)?.get(&native_offset)?;
+        Some((ir_method_id, ir_instruct_index))
+    }
+
     pub fn lookup_ir_method_id_pointer(&self, ir_method_id: IRMethodID) -> NonNull<c_void> {
         let guard = self.inner.read().unwrap();
         let current_implementation = &guard.current_implementation;
@@ -309,16 +334,25 @@ impl<'vm, HandlerExtraData: HasRBPAndRSP> IRVMState<'vm, HandlerExtraData> {
         current_ir_id
     }
 
-    pub fn add_function(&'vm self, instructions: Vec<IRInstr>, frame_size: usize, ir_method_id: IRMethodID, code_modification_handle: CodeModificationHandle) -> (IRMethodID, HashMap<RestartPointID, IRInstructIndex>, HashMap<MethodId, Vec<FunctionCallTarget>>) {
+    pub fn set_validate_ir(&self, validate_ir: bool) {
+        self.inner.write().unwrap().validate_ir = validate_ir;
+    }
+
+    pub fn add_function(&'vm self, instructions: Vec<IRInstr>, frame_size: usize, ir_method_id: IRMethodID, ir_index_to_java_pc: Option<&HashMap<IRInstructIndex, ByteCodeOffset>>, code_modification_handle: CodeModificationHandle) -> (IRMethodID, HashMap<RestartPointID, IRInstructIndex>, HashMap<MethodId, Vec<FunctionCallTarget>>) {
         assert!(frame_size >= FRAME_HEADER_END_OFFSET);
         let mut inner_guard = self.inner.write().unwrap();
-        let (code_assembler, assembly_index_to_ir_instruct_index, restart_points, call_modification_points) = add_function_from_ir(&instructions);
+        if inner_guard.validate_ir {
+            if let Err(errors) = validate_function(instructions.as_slice(), frame_size, ir_index_to_java_pc) {
+                panic!("invalid IR for {:?}:\n{}", ir_method_id, errors.iter().join("\n"));
+            }
+        }
+        let (code_assembler, assembly_index_to_ir_instruct_index, restart_points, call_modification_points, call_return_points) = add_function_from_ir(&instructions);
         let base_address = self.native_vm.get_new_base_address();
         let block = InstructionBlock::new(code_assembler.instructions(), base_address.0 as u64);
         let result = BlockEncoder::encode(64, block, BlockEncoderOptions::RETURN_NEW_INSTRUCTION_OFFSETS | BlockEncoderOptions::RETURN_CONSTANT_OFFSETS/*| BlockEncoderOptions::DONT_FIX_BRANCHES*/).unwrap();//issue here is probably that labels aren't being defined but are being jumped to.
         let new_instruction_offsets = result.new_instruction_offsets.into_iter().map(|new_instruction_offset| IRInstructNativeOffset(new_instruction_offset as usize)).collect_vec();
         Self::debug_print_instructions(&code_assembler, &new_instruction_offsets, base_address, &assembly_index_to_ir_instruct_index, &instructions);
-        inner_guard.add_function_ir_offsets(ir_method_id, &new_instruction_offsets, assembly_index_to_ir_instruct_index);
+        inner_guard.add_function_ir_offsets(ir_method_id, &new_instruction_offsets, assembly_index_to_ir_instruct_index, call_return_points);
         inner_guard.frame_sizes_by_ir_method_id.insert(ir_method_id, frame_size);
         let code = result.code_buffer;
         let method_implementation_id = self.native_vm.add_method_implementation(code, base_address, code_modification_handle);
@@ -350,9 +384,10 @@ impl<'vm, HandlerExtraData: HasRBPAndRSP> IRVMState<'vm, HandlerExtraData> {
 }
 
 
-fn add_function_from_ir(instructions: &Vec<IRInstr>) -> (CodeAssembler, Vec<(IRInstructIndex, AssemblyInstructionIndex)>, HashMap<RestartPointID, IRInstructIndex>, Vec<AssemblerFunctionCallTarget>) {
+fn add_function_from_ir(instructions: &Vec<IRInstr>) -> (CodeAssembler, Vec<(IRInstructIndex, AssemblyInstructionIndex)>, HashMap<RestartPointID, IRInstructIndex>, Vec<AssemblerFunctionCallTarget>, Vec<(IRInstructIndex, AssemblyInstructionIndex)>) {
     let mut assembler = CodeAssembler::new(64).unwrap();
     let mut ir_instruct_index_to_assembly_instruction_index = Vec::new();
+    let mut call_return_points = vec![];
     let mut labels = HashMap::new();
     let mut restart_points = HashMap::new();
     let mut assembler_function_call_modification_points = vec![];
@@ -366,8 +401,12 @@ fn add_function_from_ir(instructions: &Vec<IRInstr>) -> (CodeAssembler, Vec<(IRI
         for assembly_index in assembly_instruction_index_start..assembly_instruction_index_end {
             ir_instruct_index_to_assembly_instruction_index.push((ir_instruction_index, assembly_index));
         }
+        if let IRInstr::IRCall { .. } = instruction {
+            //ir_call returns to its last instruction
+            call_return_points.push((ir_instruction_index, AssemblyInstructionIndex(assembly_instruction_index_end.0 - 1)));
+        }
     }
-    (assembler, ir_instruct_index_to_assembly_instruction_index, restart_points, assembler_function_call_modification_points)
+    (assembler, ir_instruct_index_to_assembly_instruction_index, restart_points, assembler_function_call_modification_points, call_return_points)
 }
 
 fn gen_vm_exit(assembler: &mut CodeAssembler, exit_type: &IRVMExitType) {
@@ -0,0 +1,57 @@
+// A line based text format for IR, so that codegen bugs can be captured as files instead of
+// hand built Vec<IRInstr>.
+//
+//     function frame_size=64
+//         Const64bit to=r1 const_=10
+//         StoreFPRelative from=r1 to=fp[48] size=qword
+//     L0:
+//         LoadFPRelative from=fp[48] to=r2 size=qword
+//         BranchEqualVal a=r2 const_=0 label=L1 size=qword
+//         VMExit2 exit_type=SafePointPoll{java_pc=0}
+//     L1:
+//         Return return_val=r2 temp_register_1=r1 temp_register_2=r3 temp_register_3=r4 temp_register_4=r5 frame_size=64
+//     end
+//
+// Each instruction is the IRInstr variant name followed by its fields as key=value. Registers are
+// r<n>, f<n>, d<n> and mm<n>, frame pointer offsets are fp[<n>], labels are L<n>, sizes are
+// byte/word/dword/qword and absent optional values are none. Exit types and call targets are
+// written as Name{key=value,...}. Everything after a # is a comment.
+//
+// Instructions which embed runtime pointers or runtime ids (allocation, instanceof, vtable lookups,
+// intrinsic helpers) have no text form.
+
+use std::fmt::{Display, Formatter};
+
+use crate::compiler::IRInstr;
+
+pub mod parser;
+pub mod printer;
+pub mod runner;
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone)]
+pub struct TextIRFunction {
+    pub frame_size: usize,
+    pub instructions: Vec<IRInstr>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum TextIRError {
+    Parse {
+        line: usize,
+        message: String,
+    },
+    Unsupported {
+        instruction: String
+    },
+}
+
+impl Display for TextIRError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TextIRError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            TextIRError::Unsupported { instruction } => write!(f, "{} has no text form", instruction),
+        }
+    }
+}
@@ -0,0 +1,448 @@
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::str::FromStr;
+
+use another_jit_vm::{DoubleRegister, FloatRegister, FramePointerOffset, IRMethodID, MMRegister, Register};
+use rust_jvm_common::ByteCodeOffset;
+
+use crate::compiler::{BitwiseLogicType, FloatCompareMode, IRCallTarget, IRInstr, IRLabel, LabelName, RestartPointID, Signed, Size};
+use crate::text::{TextIRError, TextIRFunction};
+use crate::vm_exit_abi::IRVMExitType;
+
+pub fn parse_function(text: &str) -> Result<TextIRFunction, TextIRError> {
+    let mut lines = text.lines().enumerate()
+        .map(|(i, line)| (i + 1, strip_comment(line).trim()))
+        .filter(|(_, line)| !line.is_empty());
+    let (header_line, header) = lines.next().ok_or(TextIRError::Parse { line: 0, message: "empty input".to_string() })?;
+    let frame_size = parse_header(header).map_err(|message| TextIRError::Parse { line: header_line, message })?;
+    let mut instructions = vec![];
+    let mut ended = false;
+    for (line_number, line) in lines {
+        if ended {
+            return Err(TextIRError::Parse { line: line_number, message: "trailing input after end".to_string() });
+        }
+        if line == "end" {
+            ended = true;
+            continue;
+        }
+        instructions.push(parse_line(line_number, line)?);
+    }
+    if !ended {
+        return Err(TextIRError::Parse { line: text.lines().count(), message: "missing end".to_string() });
+    }
+    Ok(TextIRFunction { frame_size, instructions })
+}
+
+pub fn parse_instr(text: &str) -> Result<IRInstr, TextIRError> {
+    parse_line(0, strip_comment(text).trim())
+}
+
+fn strip_comment(line: &str) -> &str {
+    match line.split_once('#') {
+        None => line,
+        Some((before, _)) => before,
+    }
+}
+
+fn parse_header(header: &str) -> Result<usize, String> {
+    let tokens = split_top_level(header, |c| c.is_whitespace());
+    match tokens.split_first() {
+        Some((&"function", rest)) => {
+            let mut fields = Fields::parse(rest)?;
+            let frame_size = fields.take("frame_size", number)?;
+            fields.finish()?;
+            Ok(frame_size)
+        }
+        _ => Err(format!("expected function header, found: {}", header)),
+    }
+}
+
+fn parse_line(line_number: usize, line: &str) -> Result<IRInstr, TextIRError> {
+    if let Some(label) = line.strip_suffix(':') {
+        return label_name(label)
+            .map(|name| IRInstr::Label(IRLabel { name }))
+            .map_err(|message| TextIRError::Parse { line: line_number, message });
+    }
+    let tokens = split_top_level(line, |c| c.is_whitespace());
+    let (name, rest) = tokens.split_first().ok_or(TextIRError::Parse { line: line_number, message: "empty instruction".to_string() })?;
+    match *name {
+        "VTableLookupOrExit" | "ITableLookupOrExit" | "GetClassOrExit" | "InstanceOfClass" | "InstanceOfInterface" | "AllocateConstantSize" | "CallIntrinsicHelper" => {
+            return Err(TextIRError::Unsupported { instruction: name.to_string() });
+        }
+        _ => {}
+    }
+    let mut fields = Fields::parse(rest).map_err(|message| TextIRError::Parse { line: line_number, message })?;
+    parse_instr_fields(name, &mut fields)
+        .and_then(|instr| fields.finish().map(|()| instr))
+        .map_err(|message| TextIRError::Parse { line: line_number, message })
+}
+
+fn parse_instr_fields(name: &str, fields: &mut Fields) -> Result<IRInstr, String> {
+    Ok(match name {
+        "LoadFPRelative" => IRInstr::LoadFPRelative { from: fields.take("from", fp)?, to: fields.take("to", reg)?, size: fields.take("size", size)? },
+        "LoadFPRelativeFloat" => IRInstr::LoadFPRelativeFloat { from: fields.take("from", fp)?, to: fields.take("to", float_reg)? },
+        "LoadFPRelativeDouble" => IRInstr::LoadFPRelativeDouble { from: fields.take("from", fp)?, to: fields.take("to", double_reg)? },
+        "StoreFPRelative" => IRInstr::StoreFPRelative { from: fields.take("from", reg)?, to: fields.take("to", fp)?, size: fields.take("size", size)? },
+        "StoreFPRelativeFloat" => IRInstr::StoreFPRelativeFloat { from: fields.take("from", float_reg)?, to: fields.take("to", fp)? },
+        "StoreFPRelativeDouble" => IRInstr::StoreFPRelativeDouble { from: fields.take("from", double_reg)?, to: fields.take("to", fp)? },
+        "FloatToIntegerConvert" => IRInstr::FloatToIntegerConvert { from: fields.take("from", float_reg)?, temp: fields.take("temp", mm_reg)?, to: fields.take("to", reg)? },
+        "DoubleToIntegerConvert" => IRInstr::DoubleToIntegerConvert { from: fields.take("from", double_reg)?, temp: fields.take("temp", mm_reg)?, to: fields.take("to", reg)? },
+        "DoubleToLongConvert" => IRInstr::DoubleToLongConvert { from: fields.take("from", double_reg)?, to: fields.take("to", reg)? },
+        "FloatToDoubleConvert" => IRInstr::FloatToDoubleConvert { from: fields.take("from", float_reg)?, to: fields.take("to", double_reg)? },
+        "DoubleToFloatConvert" => IRInstr::DoubleToFloatConvert { from: fields.take("from", double_reg)?, to: fields.take("to", float_reg)? },
+        "IntegerToFloatConvert" => IRInstr::IntegerToFloatConvert { to: fields.take("to", float_reg)?, temp: fields.take("temp", mm_reg)?, from: fields.take("from", reg)? },
+        "LongToFloatConvert" => IRInstr::LongToFloatConvert { to: fields.take("to", float_reg)?, from: fields.take("from", reg)? },
+        "LongToDoubleConvert" => IRInstr::LongToDoubleConvert { to: fields.take("to", double_reg)?, from: fields.take("from", reg)? },
+        "IntegerToDoubleConvert" => IRInstr::IntegerToDoubleConvert { to: fields.take("to", double_reg)?, temp: fields.take("temp", mm_reg)?, from: fields.take("from", reg)? },
+        "Load" => IRInstr::Load { to: fields.take("to", reg)?, from_address: fields.take("from_address", reg)?, size: fields.take("size", size)? },
+        "Store" => IRInstr::Store { to_address: fields.take("to_address", reg)?, from: fields.take("from", reg)?, size: fields.take("size", size)? },
+        "CopyRegister" => IRInstr::CopyRegister { from: fields.take("from", reg)?, to: fields.take("to", reg)? },
+        "Add" => IRInstr::Add { res: fields.take("res", reg)?, a: fields.take("a", reg)?, size: fields.take("size", size)? },
+        "CompareAndSwapAtomic" => IRInstr::CompareAndSwapAtomic {
+            ptr: fields.take("ptr", reg)?,
+            old: fields.take("old", reg)?,
+            new: fields.take("new", reg)?,
+            res: fields.take("res", reg)?,
+            rax: fields.take("rax", reg)?,
+            size: fields.take("size", size)?,
+        },
+        "AddConst" => IRInstr::AddConst { res: fields.take("res", reg)?, a: fields.take("a", number)? },
+        "IntCompare" => IRInstr::IntCompare {
+            res: fields.take("res", reg)?,
+            value1: fields.take("value1", reg)?,
+            value2: fields.take("value2", reg)?,
+            temp1: fields.take("temp1", reg)?,
+            temp2: fields.take("temp2", reg)?,
+            temp3: fields.take("temp3", reg)?,
+            size: fields.take("size", size)?,
+        },
+        "AddFloat" => IRInstr::AddFloat { res: fields.take("res", float_reg)?, a: fields.take("a", float_reg)? },
+        "SubFloat" => IRInstr::SubFloat { res: fields.take("res", float_reg)?, a: fields.take("a", float_reg)? },
+        "SubDouble" => IRInstr::SubDouble { res: fields.take("res", double_reg)?, a: fields.take("a", double_reg)? },
+        "AddDouble" => IRInstr::AddDouble { res: fields.take("res", double_reg)?, a: fields.take("a", double_reg)? },
+        "Sub" => IRInstr::Sub { res: fields.take("res", reg)?, to_subtract: fields.take("to_subtract", reg)?, size: fields.take("size", size)? },
+        "Div" => IRInstr::Div {
+            res: fields.take("res", reg)?,
+            divisor: fields.take("divisor", reg)?,
+            must_be_rax: fields.take("must_be_rax", reg)?,
+            must_be_rbx: fields.take("must_be_rbx", reg)?,
+            must_be_rcx: fields.take("must_be_rcx", reg)?,
+            must_be_rdx: fields.take("must_be_rdx", reg)?,
+            size: fields.take("size", size)?,
+            signed: fields.take("signed", signed)?,
+        },
+        "DivFloat" => IRInstr::DivFloat { res: fields.take("res", float_reg)?, divisor: fields.take("divisor", float_reg)? },
+        "DivDouble" => IRInstr::DivDouble { res: fields.take("res", double_reg)?, divisor: fields.take("divisor", double_reg)? },
+        "Mod" => IRInstr::Mod {
+            res: fields.take("res", reg)?,
+            divisor: fields.take("divisor", reg)?,
+            must_be_rax: fields.take("must_be_rax", reg)?,
+            must_be_rbx: fields.take("must_be_rbx", reg)?,
+            must_be_rcx: fields.take("must_be_rcx", reg)?,
+            must_be_rdx: fields.take("must_be_rdx", reg)?,
+            size: fields.take("size", size)?,
+            signed: fields.take("signed", signed)?,
+        },
+        "Mul" => IRInstr::Mul {
+            res: fields.take("res", reg)?,
+            a: fields.take("a", reg)?,
+            must_be_rax: fields.take("must_be_rax", reg)?,
+            must_be_rbx: fields.take("must_be_rbx", reg)?,
+            must_be_rcx: fields.take("must_be_rcx", reg)?,
+            must_be_rdx: fields.take("must_be_rdx", reg)?,
+            size: fields.take("size", size)?,
+            signed: fields.take("signed", signed)?,
+        },
+        "MulFloat" => IRInstr::MulFloat { res: fields.take("res", float_reg)?, a: fields.take("a", float_reg)? },
+        "NegFloat" => IRInstr::NegFloat { res: fields.take("res", float_reg)?, temp: fields.take("temp", float_reg)?, temp_normal: fields.take("temp_normal", reg)? },
+        "MulDouble" => IRInstr::MulDouble { res: fields.take("res", double_reg)?, a: fields.take("a", double_reg)? },
+        "NegDouble" => IRInstr::NegDouble { res: fields.take("res", double_reg)?, temp: fields.take("temp", double_reg)?, temp_normal: fields.take("temp_normal", reg)? },
+        "MulConst" => IRInstr::MulConst { res: fields.take("res", reg)?, a: fields.take("a", number)?, size: fields.take("size", size)?, signed: fields.take("signed", signed)? },
+        "ShiftLeft" => IRInstr::ShiftLeft {
+            res: fields.take("res", reg)?,
+            a: fields.take("a", reg)?,
+            cl_aka_register_2: fields.take("cl_aka_register_2", reg)?,
+            size: fields.take("size", size)?,
+            signed: fields.take("signed", bitwise_logic_type)?,
+        },
+        "ShiftRight" => IRInstr::ShiftRight {
+            res: fields.take("res", reg)?,
+            a: fields.take("a", reg)?,
+            cl_aka_register_2: fields.take("cl_aka_register_2", reg)?,
+            size: fields.take("size", size)?,
+            signed: fields.take("signed", bitwise_logic_type)?,
+        },
+        "BinaryBitAnd" => IRInstr::BinaryBitAnd { res: fields.take("res", reg)?, a: fields.take("a", reg)?, size: fields.take("size", size)? },
+        "BinaryBitXor" => IRInstr::BinaryBitXor { res: fields.take("res", reg)?, a: fields.take("a", reg)?, size: fields.take("size", size)? },
+        "BinaryBitOr" => IRInstr::BinaryBitOr { res: fields.take("res", reg)?, a: fields.take("a", reg)?, size: fields.take("size", size)? },
+        "Const16bit" => IRInstr::Const16bit { to: fields.take("to", reg)?, const_: fields.take("const_", number)? },
+        "Const32bit" => IRInstr::Const32bit { to: fields.take("to", reg)?, const_: fields.take("const_", number)? },
+        "Const64bit" => IRInstr::Const64bit { to: fields.take("to", reg)?, const_: fields.take("const_", number)? },
+        "ConstFloat" => IRInstr::ConstFloat { to: fields.take("to", float_reg)?, temp: fields.take("temp", reg)?, const_: fields.take("const_", number)? },
+        "ConstDouble" => IRInstr::ConstDouble { to: fields.take("to", double_reg)?, temp: fields.take("temp", reg)?, const_: fields.take("const_", number)? },
+        "SignExtend" => IRInstr::SignExtend { from: fields.take("from", reg)?, to: fields.take("to", reg)?, from_size: fields.take("from_size", size)?, to_size: fields.take("to_size", size)? },
+        "ZeroExtend" => IRInstr::ZeroExtend { from: fields.take("from", reg)?, to: fields.take("to", reg)?, from_size: fields.take("from_size", size)?, to_size: fields.take("to_size", size)? },
+        "BranchToLabel" => IRInstr::BranchToLabel { label: fields.take("label", label_name)? },
+        "LoadLabel" => IRInstr::LoadLabel { label: fields.take("label", label_name)?, to: fields.take("to", reg)? },
+        "LoadRBP" => IRInstr::LoadRBP { to: fields.take("to", reg)? },
+        "WriteRBP" => IRInstr::WriteRBP { from: fields.take("from", reg)? },
+        "FloatCompare" => IRInstr::FloatCompare {
+            value1: fields.take("value1", float_reg)?,
+            value2: fields.take("value2", float_reg)?,
+            res: fields.take("res", reg)?,
+            temp1: fields.take("temp1", reg)?,
+            temp2: fields.take("temp2", reg)?,
+            temp3: fields.take("temp3", reg)?,
+            compare_mode: fields.take("compare_mode", compare_mode)?,
+        },
+        "DoubleCompare" => IRInstr::DoubleCompare {
+            value1: fields.take("value1", double_reg)?,
+            value2: fields.take("value2", double_reg)?,
+            res: fields.take("res", reg)?,
+            temp1: fields.take("temp1", reg)?,
+            temp2: fields.take("temp2", reg)?,
+            temp3: fields.take("temp3", reg)?,
+            compare_mode: fields.take("compare_mode", compare_mode)?,
+        },
+        "BranchEqual" => IRInstr::BranchEqual { a: fields.take("a", reg)?, b: fields.take("b", reg)?, label: fields.take("label", label_name)?, size: fields.take("size", size)? },
+        "BranchNotEqual" => IRInstr::BranchNotEqual { a: fields.take("a", reg)?, b: fields.take("b", reg)?, label: fields.take("label", label_name)?, size: fields.take("size", size)? },
+        "AssertEqual" => IRInstr::AssertEqual { a: fields.take("a", reg)?, b: fields.take("b", reg)?, size: fields.take("size", size)? },
+        "BranchAGreaterB" => IRInstr::BranchAGreaterB { a: fields.take("a", reg)?, b: fields.take("b", reg)?, label: fields.take("label", label_name)?, size: fields.take("size", size)? },
+        "BranchAGreaterEqualB" => IRInstr::BranchAGreaterEqualB { a: fields.take("a", reg)?, b: fields.take("b", reg)?, label: fields.take("label", label_name)?, size: fields.take("size", size)? },
+        "BranchALessB" => IRInstr::BranchALessB { a: fields.take("a", reg)?, b: fields.take("b", reg)?, label: fields.take("label", label_name)?, size: fields.take("size", size)? },
+        "BranchEqualVal" => IRInstr::BranchEqualVal { a: fields.take("a", reg)?, const_: fields.take("const_", number)?, label: fields.take("label", label_name)?, size: fields.take("size", size)? },
+        "BoundsCheck" => IRInstr::BoundsCheck { length: fields.take("length", reg)?, index: fields.take("index", reg)?, size: fields.take("size", size)?, exit: fields.take("exit", exit_type)? },
+        "Return" => IRInstr::Return {
+            return_val: fields.take("return_val", |value| optional(value, reg))?,
+            temp_register_1: fields.take("temp_register_1", reg)?,
+            temp_register_2: fields.take("temp_register_2", reg)?,
+            temp_register_3: fields.take("temp_register_3", reg)?,
+            temp_register_4: fields.take("temp_register_4", reg)?,
+            frame_size: fields.take("frame_size", number)?,
+        },
+        "MemCopyForward" => IRInstr::MemCopyForward {
+            src_base_addr: fields.take("src_base_addr", reg)?,
+            dst_base_addr: fields.take("dst_base_addr", reg)?,
+            len: fields.take("len", reg)?,
+            temp_register_1: fields.take("temp_register_1", reg)?,
+            temp_register_2: fields.take("temp_register_2", reg)?,
+            temp_register_3: fields.take("temp_register_3", reg)?,
+            vector_temp_register: fields.take("vector_temp_register", float_reg)?,
+        },
+        "RestartPoint" => IRInstr::RestartPoint(RestartPointID(fields.take("id", number)?)),
+        "VMExit2" => IRInstr::VMExit2 { exit_type: fields.take("exit_type", exit_type)? },
+        "NPECheck" => IRInstr::NPECheck { possibly_null: fields.take("possibly_null", reg)?, temp_register: fields.take("temp_register", reg)?, npe_exit_type: fields.take("npe_exit_type", exit_type)? },
+        "IRCall" => IRInstr::IRCall {
+            temp_register_1: fields.take("temp_register_1", reg)?,
+            temp_register_2: fields.take("temp_register_2", reg)?,
+            arg_from_to_offsets: fields.take("arg_from_to_offsets", arg_from_to_offsets)?,
+            return_value: fields.take("return_value", |value| optional(value, fp))?,
+            target_address: fields.take("target_address", call_target)?,
+            current_frame_size: fields.take("current_frame_size", number)?,
+        },
+        "IRStart" => IRInstr::IRStart {
+            temp_register: fields.take("temp_register", reg)?,
+            ir_method_id: IRMethodID(fields.take("ir_method_id", number)?),
+            method_id: fields.take("method_id", number)?,
+            frame_size: fields.take("frame_size", number)?,
+            num_locals: fields.take("num_locals", number)?,
+        },
+        "NOP" => IRInstr::NOP,
+        "DebuggerBreakpoint" => IRInstr::DebuggerBreakpoint,
+        name => return Err(format!("unknown instruction: {}", name)),
+    })
+}
+
+fn exit_type(value: &str) -> Result<IRVMExitType, String> {
+    let (name, mut fields) = nested(value)?;
+    let res = match name {
+        "NPE" => IRVMExitType::NPE { java_pc: fields.take("java_pc", java_pc)? },
+        "CheckcastFailure" => IRVMExitType::CheckcastFailure { java_pc: fields.take("java_pc", java_pc)? },
+        "ArrayOutOfBounds" => IRVMExitType::ArrayOutOfBounds { java_pc: fields.take("java_pc", java_pc)?, index: fields.take("index", fp)? },
+        "CompileFunctionAndRecompileCurrent" => IRVMExitType::CompileFunctionAndRecompileCurrent {
+            current_method_id: fields.take("current_method_id", number)?,
+            target_method_id: fields.take("target_method_id", number)?,
+            restart_point_id: RestartPointID(fields.take("restart_point_id", number)?),
+            java_pc: fields.take("java_pc", java_pc)?,
+        },
+        "TopLevelReturn" => IRVMExitType::TopLevelReturn,
+        "LogWholeFrame" => IRVMExitType::LogWholeFrame { java_pc: fields.take("java_pc", java_pc)? },
+        "TraceInstructionBefore" => IRVMExitType::TraceInstructionBefore { method_id: fields.take("method_id", number)?, offset: fields.take("offset", java_pc)?, java_pc: fields.take("java_pc", java_pc)? },
+        "TraceInstructionAfter" => IRVMExitType::TraceInstructionAfter { method_id: fields.take("method_id", number)?, offset: fields.take("offset", java_pc)?, java_pc: fields.take("java_pc", java_pc)? },
+        "MonitorEnter" => IRVMExitType::MonitorEnter { obj: fields.take("obj", fp)?, java_pc: fields.take("java_pc", java_pc)? },
+        "MonitorExit" => IRVMExitType::MonitorExit { obj: fields.take("obj", fp)?, java_pc: fields.take("java_pc", java_pc)? },
+        "MonitorEnterRegister" => IRVMExitType::MonitorEnterRegister { obj: fields.take("obj", reg)?, java_pc: fields.take("java_pc", java_pc)? },
+        "MonitorExitRegister" => IRVMExitType::MonitorExitRegister { obj: fields.take("obj", reg)?, java_pc: fields.take("java_pc", java_pc)? },
+        "Throw" => IRVMExitType::Throw { to_throw_obj_offset: fields.take("to_throw_obj_offset", fp)?, java_pc: fields.take("java_pc", java_pc)? },
+        "RunInterpreted" => IRVMExitType::RunInterpreted { method_id: fields.take("method_id", number)? },
+        "Deoptimize" => IRVMExitType::Deoptimize { method_id: fields.take("method_id", number)?, java_pc: fields.take("java_pc", java_pc)?, stack_depth: fields.take("stack_depth", number)? },
+        "DeoptimizeReturn" => IRVMExitType::DeoptimizeReturn,
+        "SafePointPoll" => IRVMExitType::SafePointPoll { java_pc: fields.take("java_pc", java_pc)? },
+        name => return Err(format!("unknown or unsupported exit type: {}", name)),
+    };
+    fields.finish()?;
+    Ok(res)
+}
+
+fn call_target(value: &str) -> Result<IRCallTarget, String> {
+    let (name, mut fields) = nested(value)?;
+    let res = match name {
+        "Constant" => IRCallTarget::Constant { address: fields.take("address", address)?, method_id: fields.take("method_id", number)? },
+        "Variable" => IRCallTarget::Variable { address: fields.take("address", reg)? },
+        name => return Err(format!("unknown call target: {}", name)),
+    };
+    fields.finish()?;
+    Ok(res)
+}
+
+fn arg_from_to_offsets(value: &str) -> Result<Vec<(FramePointerOffset, FramePointerOffset)>, String> {
+    let inner = value.strip_prefix('[').and_then(|value| value.strip_suffix(']')).ok_or(format!("expected list: {}", value))?;
+    split_top_level(inner, |c| c == ',').into_iter().map(|arg| {
+        let (from, to) = arg.split_once("->").ok_or(format!("expected from->to: {}", arg))?;
+        Ok((fp(from)?, fp(to)?))
+    }).collect()
+}
+
+// Name or Name{key=value,...}
+fn nested(value: &str) -> Result<(&str, Fields), String> {
+    match value.split_once('{') {
+        None => Ok((value, Fields::default())),
+        Some((name, rest)) => {
+            let inner = rest.strip_suffix('}').ok_or(format!("unclosed brace: {}", value))?;
+            Ok((name, Fields::parse(split_top_level(inner, |c| c == ',').as_slice())?))
+        }
+    }
+}
+
+fn split_top_level(text: &str, is_separator: impl Fn(char) -> bool) -> Vec<&str> {
+    let mut res = vec![];
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '[' | '{' => depth += 1,
+            ']' | '}' => depth = depth.saturating_sub(1),
+            c if depth == 0 && is_separator(c) => {
+                res.push(&text[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    res.push(&text[start..]);
+    res.into_iter().map(|token| token.trim()).filter(|token| !token.is_empty()).collect()
+}
+
+#[derive(Default)]
+struct Fields {
+    values: HashMap<String, String>,
+}
+
+impl Fields {
+    fn parse(tokens: &[&str]) -> Result<Self, String> {
+        let mut values = HashMap::new();
+        for token in tokens {
+            let (key, value) = token.split_once('=').ok_or(format!("expected key=value: {}", token))?;
+            if values.insert(key.to_string(), value.to_string()).is_some() {
+                return Err(format!("duplicate field: {}", key));
+            }
+        }
+        Ok(Self { values })
+    }
+
+    fn take<T>(&mut self, key: &str, parse: impl Fn(&str) -> Result<T, String>) -> Result<T, String> {
+        let value = self.values.remove(key).ok_or(format!("missing field: {}", key))?;
+        parse(value.as_str()).map_err(|err| format!("{}: {}", key, err))
+    }
+
+    fn finish(self) -> Result<(), String> {
+        match self.values.keys().next() {
+            None => Ok(()),
+            Some(key) => Err(format!("unexpected field: {}", key)),
+        }
+    }
+}
+
+fn number<T: FromStr>(value: &str) -> Result<T, String> {
+    value.parse::<T>().map_err(|_| format!("invalid number: {}", value))
+}
+
+fn prefixed<T: FromStr>(value: &str, prefix: &str) -> Result<T, String> {
+    value.strip_prefix(prefix).ok_or(format!("expected {}<n>: {}", prefix, value)).and_then(number)
+}
+
+fn optional<T>(value: &str, parse: impl Fn(&str) -> Result<T, String>) -> Result<Option<T>, String> {
+    if value == "none" {
+        Ok(None)
+    } else {
+        parse(value).map(Some)
+    }
+}
+
+fn address(value: &str) -> Result<*const c_void, String> {
+    let hex = value.strip_prefix("0x").ok_or(format!("expected hex address: {}", value))?;
+    u64::from_str_radix(hex, 16).map(|address| address as *const c_void).map_err(|_| format!("invalid address: {}", value))
+}
+
+fn reg(value: &str) -> Result<Register, String> {
+    prefixed(value, "r").map(Register)
+}
+
+fn float_reg(value: &str) -> Result<FloatRegister, String> {
+    prefixed(value, "f").map(FloatRegister)
+}
+
+fn double_reg(value: &str) -> Result<DoubleRegister, String> {
+    prefixed(value, "d").map(DoubleRegister)
+}
+
+fn mm_reg(value: &str) -> Result<MMRegister, String> {
+    prefixed(value, "mm").map(MMRegister)
+}
+
+fn fp(value: &str) -> Result<FramePointerOffset, String> {
+    let offset = value.strip_prefix("fp[").and_then(|value| value.strip_suffix(']')).ok_or(format!("expected fp[<n>]: {}", value))?;
+    number(offset).map(FramePointerOffset)
+}
+
+fn label_name(value: &str) -> Result<LabelName, String> {
+    prefixed(value, "L").map(LabelName)
+}
+
+fn java_pc(value: &str) -> Result<ByteCodeOffset, String> {
+    number(value).map(ByteCodeOffset)
+}
+
+fn size(value: &str) -> Result<Size, String> {
+    Ok(match value {
+        "byte" => Size::Byte,
+        "word" => Size::X86Word,
+        "dword" => Size::X86DWord,
+        "qword" => Size::X86QWord,
+        value => return Err(format!("invalid size: {}", value)),
+    })
+}
+
+fn signed(value: &str) -> Result<Signed, String> {
+    Ok(match value {
+        "signed" => Signed::Signed,
+        "unsigned" => Signed::Unsigned,
+        value => return Err(format!("invalid signedness: {}", value)),
+    })
+}
+
+fn bitwise_logic_type(value: &str) -> Result<BitwiseLogicType, String> {
+    Ok(match value {
+        "arithmetic" => BitwiseLogicType::Arithmetic,
+        "logical" => BitwiseLogicType::Logical,
+        value => return Err(format!("invalid shift type: {}", value)),
+    })
+}
+
+fn compare_mode(value: &str) -> Result<FloatCompareMode, String> {
+    Ok(match value {
+        "g" => FloatCompareMode::G,
+        "l" => FloatCompareMode::L,
+        value => return Err(format!("invalid compare mode: {}", value)),
+    })
+}
@@ -0,0 +1,378 @@
+use another_jit_vm::{DoubleRegister, FloatRegister, FramePointerOffset, MMRegister, Register};
+use itertools::Itertools;
+
+use crate::compiler::{BitwiseLogicType, FloatCompareMode, IRCallTarget, IRInstr, LabelName, Signed, Size};
+use crate::text::{TextIRError, TextIRFunction};
+use crate::vm_exit_abi::IRVMExitType;
+
+pub fn print_function(function: &TextIRFunction) -> Result<String, TextIRError> {
+    let mut res = format!("function frame_size={}\n", function.frame_size);
+    for instr in function.instructions.iter() {
+        match instr {
+            IRInstr::Label(label) => {
+                res.push_str(format!("{}:\n", label_name(label.name)).as_str());
+            }
+            instr => {
+                res.push_str("    ");
+                res.push_str(print_instr(instr)?.as_str());
+                res.push('\n');
+            }
+        }
+    }
+    res.push_str("end\n");
+    Ok(res)
+}
+
+pub fn print_instr(instr: &IRInstr) -> Result<String, TextIRError> {
+    Ok(match instr {
+        IRInstr::LoadFPRelative { from, to, size } => {
+            format!("LoadFPRelative from={} to={} size={}", fp(*from), reg(*to), size_name(*size))
+        }
+        IRInstr::LoadFPRelativeFloat { from, to } => {
+            format!("LoadFPRelativeFloat from={} to={}", fp(*from), float_reg(*to))
+        }
+        IRInstr::LoadFPRelativeDouble { from, to } => {
+            format!("LoadFPRelativeDouble from={} to={}", fp(*from), double_reg(*to))
+        }
+        IRInstr::StoreFPRelative { from, to, size } => {
+            format!("StoreFPRelative from={} to={} size={}", reg(*from), fp(*to), size_name(*size))
+        }
+        IRInstr::StoreFPRelativeFloat { from, to } => {
+            format!("StoreFPRelativeFloat from={} to={}", float_reg(*from), fp(*to))
+        }
+        IRInstr::StoreFPRelativeDouble { from, to } => {
+            format!("StoreFPRelativeDouble from={} to={}", double_reg(*from), fp(*to))
+        }
+        IRInstr::FloatToIntegerConvert { from, temp, to } => {
+            format!("FloatToIntegerConvert from={} temp={} to={}", float_reg(*from), mm_reg(*temp), reg(*to))
+        }
+        IRInstr::DoubleToIntegerConvert { from, temp, to } => {
+            format!("DoubleToIntegerConvert from={} temp={} to={}", double_reg(*from), mm_reg(*temp), reg(*to))
+        }
+        IRInstr::DoubleToLongConvert { from, to } => {
+            format!("DoubleToLongConvert from={} to={}", double_reg(*from), reg(*to))
+        }
+        IRInstr::FloatToDoubleConvert { from, to } => {
+            format!("FloatToDoubleConvert from={} to={}", float_reg(*from), double_reg(*to))
+        }
+        IRInstr::DoubleToFloatConvert { from, to } => {
+            format!("DoubleToFloatConvert from={} to={}", double_reg(*from), float_reg(*to))
+        }
+        IRInstr::IntegerToFloatConvert { to, temp, from } => {
+            format!("IntegerToFloatConvert to={} temp={} from={}", float_reg(*to), mm_reg(*temp), reg(*from))
+        }
+        IRInstr::LongToFloatConvert { to, from } => {
+            format!("LongToFloatConvert to={} from={}", float_reg(*to), reg(*from))
+        }
+        IRInstr::LongToDoubleConvert { to, from } => {
+            format!("LongToDoubleConvert to={} from={}", double_reg(*to), reg(*from))
+        }
+        IRInstr::IntegerToDoubleConvert { to, temp, from } => {
+            format!("IntegerToDoubleConvert to={} temp={} from={}", double_reg(*to), mm_reg(*temp), reg(*from))
+        }
+        IRInstr::Load { to, from_address, size } => {
+            format!("Load to={} from_address={} size={}", reg(*to), reg(*from_address), size_name(*size))
+        }
+        IRInstr::Store { to_address, from, size } => {
+            format!("Store to_address={} from={} size={}", reg(*to_address), reg(*from), size_name(*size))
+        }
+        IRInstr::CopyRegister { from, to } => {
+            format!("CopyRegister from={} to={}", reg(*from), reg(*to))
+        }
+        IRInstr::Add { res, a, size } => {
+            format!("Add res={} a={} size={}", reg(*res), reg(*a), size_name(*size))
+        }
+        IRInstr::CompareAndSwapAtomic { ptr, old, new, res, rax, size } => {
+            format!("CompareAndSwapAtomic ptr={} old={} new={} res={} rax={} size={}", reg(*ptr), reg(*old), reg(*new), reg(*res), reg(*rax), size_name(*size))
+        }
+        IRInstr::AddConst { res, a } => {
+            format!("AddConst res={} a={}", reg(*res), a)
+        }
+        IRInstr::IntCompare { res, value1, value2, temp1, temp2, temp3, size } => {
+            format!("IntCompare res={} value1={} value2={} temp1={} temp2={} temp3={} size={}", reg(*res), reg(*value1), reg(*value2), reg(*temp1), reg(*temp2), reg(*temp3), size_name(*size))
+        }
+        IRInstr::AddFloat { res, a } => {
+            format!("AddFloat res={} a={}", float_reg(*res), float_reg(*a))
+        }
+        IRInstr::SubFloat { res, a } => {
+            format!("SubFloat res={} a={}", float_reg(*res), float_reg(*a))
+        }
+        IRInstr::SubDouble { res, a } => {
+            format!("SubDouble res={} a={}", double_reg(*res), double_reg(*a))
+        }
+        IRInstr::AddDouble { res, a } => {
+            format!("AddDouble res={} a={}", double_reg(*res), double_reg(*a))
+        }
+        IRInstr::Sub { res, to_subtract, size } => {
+            format!("Sub res={} to_subtract={} size={}", reg(*res), reg(*to_subtract), size_name(*size))
+        }
+        IRInstr::Div { res, divisor, must_be_rax, must_be_rbx, must_be_rcx, must_be_rdx, size, signed } => {
+            format!("Div res={} divisor={} must_be_rax={} must_be_rbx={} must_be_rcx={} must_be_rdx={} size={} signed={}", reg(*res), reg(*divisor), reg(*must_be_rax), reg(*must_be_rbx), reg(*must_be_rcx), reg(*must_be_rdx), size_name(*size), signed_name(*signed))
+        }
+        IRInstr::DivFloat { res, divisor } => {
+            format!("DivFloat res={} divisor={}", float_reg(*res), float_reg(*divisor))
+        }
+        IRInstr::DivDouble { res, divisor } => {
+            format!("DivDouble res={} divisor={}", double_reg(*res), double_reg(*divisor))
+        }
+        IRInstr::Mod { res, divisor, must_be_rax, must_be_rbx, must_be_rcx, must_be_rdx, size, signed } => {
+            format!("Mod res={} divisor={} must_be_rax={} must_be_rbx={} must_be_rcx={} must_be_rdx={} size={} signed={}", reg(*res), reg(*divisor), reg(*must_be_rax), reg(*must_be_rbx), reg(*must_be_rcx), reg(*must_be_rdx), size_name(*size), signed_name(*signed))
+        }
+        IRInstr::Mul { res, a, must_be_rax, must_be_rbx, must_be_rcx, must_be_rdx, size, signed } => {
+            format!("Mul res={} a={} must_be_rax={} must_be_rbx={} must_be_rcx={} must_be_rdx={} size={} signed={}", reg(*res), reg(*a), reg(*must_be_rax), reg(*must_be_rbx), reg(*must_be_rcx), reg(*must_be_rdx), size_name(*size), signed_name(*signed))
+        }
+        IRInstr::MulFloat { res, a } => {
+            format!("MulFloat res={} a={}", float_reg(*res), float_reg(*a))
+        }
+        IRInstr::NegFloat { res, temp, temp_normal } => {
+            format!("NegFloat res={} temp={} temp_normal={}", float_reg(*res), float_reg(*temp), reg(*temp_normal))
+        }
+        IRInstr::MulDouble { res, a } => {
+            format!("MulDouble res={} a={}", double_reg(*res), double_reg(*a))
+        }
+        IRInstr::NegDouble { res, temp, temp_normal } => {
+            format!("NegDouble res={} temp={} temp_normal={}", double_reg(*res), double_reg(*temp), reg(*temp_normal))
+        }
+        IRInstr::MulConst { res, a, size, signed } => {
+            format!("MulConst res={} a={} size={} signed={}", reg(*res), a, size_name(*size), signed_name(*signed))
+        }
+        IRInstr::ShiftLeft { res, a, cl_aka_register_2, size, signed } => {
+            format!("ShiftLeft res={} a={} cl_aka_register_2={} size={} signed={}", reg(*res), reg(*a), reg(*cl_aka_register_2), size_name(*size), bitwise_logic_type_name(*signed))
+        }
+        IRInstr::ShiftRight { res, a, cl_aka_register_2, size, signed } => {
+            format!("ShiftRight res={} a={} cl_aka_register_2={} size={} signed={}", reg(*res), reg(*a), reg(*cl_aka_register_2), size_name(*size), bitwise_logic_type_name(*signed))
+        }
+        IRInstr::BinaryBitAnd { res, a, size } => {
+            format!("BinaryBitAnd res={} a={} size={}", reg(*res), reg(*a), size_name(*size))
+        }
+        IRInstr::BinaryBitXor { res, a, size } => {
+            format!("BinaryBitXor res={} a={} size={}", reg(*res), reg(*a), size_name(*size))
+        }
+        IRInstr::BinaryBitOr { res, a, size } => {
+            format!("BinaryBitOr res={} a={} size={}", reg(*res), reg(*a), size_name(*size))
+        }
+        IRInstr::Const16bit { to, const_ } => {
+            format!("Const16bit to={} const_={}", reg(*to), const_)
+        }
+        IRInstr::Const32bit { to, const_ } => {
+            format!("Const32bit to={} const_={}", reg(*to), const_)
+        }
+        IRInstr::Const64bit { to, const_ } => {
+            format!("Const64bit to={} const_={}", reg(*to), const_)
+        }
+        IRInstr::ConstFloat { to, temp, const_ } => {
+            format!("ConstFloat to={} temp={} const_={:?}", float_reg(*to), reg(*temp), const_)
+        }
+        IRInstr::ConstDouble { to, temp, const_ } => {
+            format!("ConstDouble to={} temp={} const_={:?}", double_reg(*to), reg(*temp), const_)
+        }
+        IRInstr::SignExtend { from, to, from_size, to_size } => {
+            format!("SignExtend from={} to={} from_size={} to_size={}", reg(*from), reg(*to), size_name(*from_size), size_name(*to_size))
+        }
+        IRInstr::ZeroExtend { from, to, from_size, to_size } => {
+            format!("ZeroExtend from={} to={} from_size={} to_size={}", reg(*from), reg(*to), size_name(*from_size), size_name(*to_size))
+        }
+        IRInstr::BranchToLabel { label } => {
+            format!("BranchToLabel label={}", label_name(*label))
+        }
+        IRInstr::LoadLabel { label, to } => {
+            format!("LoadLabel label={} to={}", label_name(*label), reg(*to))
+        }
+        IRInstr::LoadRBP { to } => {
+            format!("LoadRBP to={}", reg(*to))
+        }
+        IRInstr::WriteRBP { from } => {
+            format!("WriteRBP from={}", reg(*from))
+        }
+        IRInstr::FloatCompare { value1, value2, res, temp1, temp2, temp3, compare_mode } => {
+            format!("FloatCompare value1={} value2={} res={} temp1={} temp2={} temp3={} compare_mode={}", float_reg(*value1), float_reg(*value2), reg(*res), reg(*temp1), reg(*temp2), reg(*temp3), compare_mode_name(*compare_mode))
+        }
+        IRInstr::DoubleCompare { value1, value2, res, temp1, temp2, temp3, compare_mode } => {
+            format!("DoubleCompare value1={} value2={} res={} temp1={} temp2={} temp3={} compare_mode={}", double_reg(*value1), double_reg(*value2), reg(*res), reg(*temp1), reg(*temp2), reg(*temp3), compare_mode_name(*compare_mode))
+        }
+        IRInstr::BranchEqual { a, b, label, size } => {
+            format!("BranchEqual a={} b={} label={} size={}", reg(*a), reg(*b), label_name(*label), size_name(*size))
+        }
+        IRInstr::BranchNotEqual { a, b, label, size } => {
+            format!("BranchNotEqual a={} b={} label={} size={}", reg(*a), reg(*b), label_name(*label), size_name(*size))
+        }
+        IRInstr::AssertEqual { a, b, size } => {
+            format!("AssertEqual a={} b={} size={}", reg(*a), reg(*b), size_name(*size))
+        }
+        IRInstr::BranchAGreaterB { a, b, label, size } => {
+            format!("BranchAGreaterB a={} b={} label={} size={}", reg(*a), reg(*b), label_name(*label), size_name(*size))
+        }
+        IRInstr::BranchAGreaterEqualB { a, b, label, size } => {
+            format!("BranchAGreaterEqualB a={} b={} label={} size={}", reg(*a), reg(*b), label_name(*label), size_name(*size))
+        }
+        IRInstr::BranchALessB { a, b, label, size } => {
+            format!("BranchALessB a={} b={} label={} size={}", reg(*a), reg(*b), label_name(*label), size_name(*size))
+        }
+        IRInstr::BranchEqualVal { a, const_, label, size } => {
+            format!("BranchEqualVal a={} const_={} label={} size={}", reg(*a), const_, label_name(*label), size_name(*size))
+        }
+        IRInstr::BoundsCheck { length, index, size, exit } => {
+            format!("BoundsCheck length={} index={} size={} exit={}", reg(*length), reg(*index), size_name(*size), exit_type(exit)?)
+        }
+        IRInstr::Return { return_val, temp_register_1, temp_register_2, temp_register_3, temp_register_4, frame_size } => {
+            format!("Return return_val={} temp_register_1={} temp_register_2={} temp_register_3={} temp_register_4={} frame_size={}", optional(return_val.map(reg)), reg(*temp_register_1), reg(*temp_register_2), reg(*temp_register_3), reg(*temp_register_4), frame_size)
+        }
+        IRInstr::MemCopyForward { src_base_addr, dst_base_addr, len, temp_register_1, temp_register_2, temp_register_3, vector_temp_register } => {
+            format!("MemCopyForward src_base_addr={} dst_base_addr={} len={} temp_register_1={} temp_register_2={} temp_register_3={} vector_temp_register={}", reg(*src_base_addr), reg(*dst_base_addr), reg(*len), reg(*temp_register_1), reg(*temp_register_2), reg(*temp_register_3), float_reg(*vector_temp_register))
+        }
+        IRInstr::RestartPoint(restart_point_id) => {
+            format!("RestartPoint id={}", restart_point_id.0)
+        }
+        IRInstr::VMExit2 { exit_type: exit } => {
+            format!("VMExit2 exit_type={}", exit_type(exit)?)
+        }
+        IRInstr::NPECheck { possibly_null, temp_register, npe_exit_type } => {
+            format!("NPECheck possibly_null={} temp_register={} npe_exit_type={}", reg(*possibly_null), reg(*temp_register), exit_type(npe_exit_type)?)
+        }
+        IRInstr::IRCall { temp_register_1, temp_register_2, arg_from_to_offsets, return_value, target_address, current_frame_size } => {
+            let args = arg_from_to_offsets.iter().map(|(from, to)| format!("{}->{}", fp(*from), fp(*to))).join(",");
+            format!("IRCall temp_register_1={} temp_register_2={} arg_from_to_offsets=[{}] return_value={} target_address={} current_frame_size={}", reg(*temp_register_1), reg(*temp_register_2), args, optional(return_value.map(fp)), call_target(target_address), current_frame_size)
+        }
+        IRInstr::IRStart { temp_register, ir_method_id, method_id, frame_size, num_locals } => {
+            format!("IRStart temp_register={} ir_method_id={} method_id={} frame_size={} num_locals={}", reg(*temp_register), ir_method_id.0, method_id, frame_size, num_locals)
+        }
+        IRInstr::NOP => "NOP".to_string(),
+        IRInstr::DebuggerBreakpoint => "DebuggerBreakpoint".to_string(),
+        IRInstr::Label(label) => {
+            format!("{}:", label_name(label.name))
+        }
+        IRInstr::VTableLookupOrExit { .. } |
+        IRInstr::ITableLookupOrExit { .. } |
+        IRInstr::GetClassOrExit { .. } |
+        IRInstr::InstanceOfClass { .. } |
+        IRInstr::InstanceOfInterface { .. } |
+        IRInstr::AllocateConstantSize { .. } |
+        IRInstr::CallIntrinsicHelper { .. } => {
+            return Err(TextIRError::Unsupported { instruction: instr.debug_string() });
+        }
+    })
+}
+
+fn exit_type(exit_type: &IRVMExitType) -> Result<String, TextIRError> {
+    Ok(match exit_type {
+        IRVMExitType::NPE { java_pc } => {
+            format!("NPE{{java_pc={}}}", java_pc.0)
+        }
+        IRVMExitType::CheckcastFailure { java_pc } => {
+            format!("CheckcastFailure{{java_pc={}}}", java_pc.0)
+        }
+        IRVMExitType::ArrayOutOfBounds { java_pc, index } => {
+            format!("ArrayOutOfBounds{{java_pc={},index={}}}", java_pc.0, fp(*index))
+        }
+        IRVMExitType::CompileFunctionAndRecompileCurrent { current_method_id, target_method_id, restart_point_id, java_pc } => {
+            format!("CompileFunctionAndRecompileCurrent{{current_method_id={},target_method_id={},restart_point_id={},java_pc={}}}", current_method_id, target_method_id, restart_point_id.0, java_pc.0)
+        }
+        IRVMExitType::TopLevelReturn => "TopLevelReturn".to_string(),
+        IRVMExitType::LogWholeFrame { java_pc } => {
+            format!("LogWholeFrame{{java_pc={}}}", java_pc.0)
+        }
+        IRVMExitType::TraceInstructionBefore { method_id, offset, java_pc } => {
+            format!("TraceInstructionBefore{{method_id={},offset={},java_pc={}}}", method_id, offset.0, java_pc.0)
+        }
+        IRVMExitType::TraceInstructionAfter { method_id, offset, java_pc } => {
+            format!("TraceInstructionAfter{{method_id={},offset={},java_pc={}}}", method_id, offset.0, java_pc.0)
+        }
+        IRVMExitType::MonitorEnter { obj, java_pc } => {
+            format!("MonitorEnter{{obj={},java_pc={}}}", fp(*obj), java_pc.0)
+        }
+        IRVMExitType::MonitorExit { obj, java_pc } => {
+            format!("MonitorExit{{obj={},java_pc={}}}", fp(*obj), java_pc.0)
+        }
+        IRVMExitType::MonitorEnterRegister { obj, java_pc } => {
+            format!("MonitorEnterRegister{{obj={},java_pc={}}}", reg(*obj), java_pc.0)
+        }
+        IRVMExitType::MonitorExitRegister { obj, java_pc } => {
+            format!("MonitorExitRegister{{obj={},java_pc={}}}", reg(*obj), java_pc.0)
+        }
+        IRVMExitType::Throw { to_throw_obj_offset, java_pc } => {
+            format!("Throw{{to_throw_obj_offset={},java_pc={}}}", fp(*to_throw_obj_offset), java_pc.0)
+        }
+        IRVMExitType::RunInterpreted { method_id } => {
+            format!("RunInterpreted{{method_id={}}}", method_id)
+        }
+        IRVMExitType::Deoptimize { method_id, java_pc, stack_depth } => {
+            format!("Deoptimize{{method_id={},java_pc={},stack_depth={}}}", method_id, java_pc.0, stack_depth)
+        }
+        IRVMExitType::DeoptimizeReturn => "DeoptimizeReturn".to_string(),
+        IRVMExitType::SafePointPoll { java_pc } => {
+            format!("SafePointPoll{{java_pc={}}}", java_pc.0)
+        }
+        exit_type => {
+            return Err(TextIRError::Unsupported { instruction: format!("{:?}", exit_type) });
+        }
+    })
+}
+
+fn call_target(call_target: &IRCallTarget) -> String {
+    match call_target {
+        IRCallTarget::Constant { address, method_id } => {
+            format!("Constant{{address={:#x},method_id={}}}", *address as usize, method_id)
+        }
+        IRCallTarget::Variable { address } => {
+            format!("Variable{{address={}}}", reg(*address))
+        }
+    }
+}
+
+fn optional(value: Option<String>) -> String {
+    value.unwrap_or_else(|| "none".to_string())
+}
+
+fn reg(register: Register) -> String {
+    format!("r{}", register.0)
+}
+
+fn float_reg(register: FloatRegister) -> String {
+    format!("f{}", register.0)
+}
+
+fn double_reg(register: DoubleRegister) -> String {
+    format!("d{}", register.0)
+}
+
+fn mm_reg(register: MMRegister) -> String {
+    format!("mm{}", register.0)
+}
+
+fn fp(offset: FramePointerOffset) -> String {
+    format!("fp[{}]", offset.0)
+}
+
+fn label_name(label: LabelName) -> String {
+    format!("L{}", label.0)
+}
+
+fn size_name(size: Size) -> &'static str {
+    match size {
+        Size::Byte => "byte",
+        Size::X86Word => "word",
+        Size::X86DWord => "dword",
+        Size::X86QWord => "qword",
+    }
+}
+
+fn signed_name(signed: Signed) -> &'static str {
+    match signed {
+        Signed::Signed => "signed",
+        Signed::Unsigned => "unsigned",
+    }
+}
+
+fn bitwise_logic_type_name(bitwise_logic_type: BitwiseLogicType) -> &'static str {
+    match bitwise_logic_type {
+        BitwiseLogicType::Arithmetic => "arithmetic",
+        BitwiseLogicType::Logical => "logical",
+    }
+}
+
+fn compare_mode_name(compare_mode: FloatCompareMode) -> &'static str {
+    match compare_mode {
+        FloatCompareMode::G => "g",
+        FloatCompareMode::L => "l",
+    }
+}
@@ -0,0 +1,156 @@
+use std::ffi::c_void;
+use std::ptr::{null, null_mut, NonNull};
+use std::sync::Arc;
+
+use another_jit_vm::code_modification::GlobalCodeEditingLock;
+use another_jit_vm::intrinsic_helpers::ExtraIntrinsicHelpers;
+use gc_memory_layout_common::frame_layout::FRAME_HEADER_END_OFFSET;
+
+use crate::{ExitHandlerType, HasRBPAndRSP, IRVMExitAction, IRVMExitEvent, IRVMState, RBPAndRSP};
+use crate::compiler::IRInstr;
+use crate::ir_stack::OwnedIRStack;
+use crate::text::{TextIRError, TextIRFunction};
+use crate::text::parser::parse_function;
+use crate::vm_exit_abi::IRVMExitType;
+use crate::vm_exit_abi::runtime_input::RuntimeVMExitInput;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TextIRRun {
+    // None if the function stopped at an exit the runner has no stub for
+    pub return_value: Option<u64>,
+    // every exit taken other than the final top level return, as Name@java_pc
+    pub exits: Vec<String>,
+}
+
+pub struct RunnerStack {
+    ir_stack: OwnedIRStack,
+    rbp: NonNull<c_void>,
+    rsp: NonNull<c_void>,
+    exits: Vec<String>,
+    stopped: bool,
+    stop_at_safepoint_polls: bool,
+}
+
+impl HasRBPAndRSP for RunnerStack {
+    fn notify_guest_exit(&mut self, rbp: NonNull<c_void>, rsp: NonNull<c_void>) {
+        self.rbp = rbp;
+        self.rsp = rsp;
+    }
+
+    fn notify_guest_enter(&mut self) {}
+
+    fn rsp(&self) -> NonNull<c_void> {
+        self.rsp
+    }
+
+    fn rbp(&self) -> NonNull<c_void> {
+        self.rbp
+    }
+
+    fn ir_stack_ref(&self) -> &OwnedIRStack {
+        &self.ir_stack
+    }
+
+    fn ir_stack_mut(&mut self) -> &mut OwnedIRStack {
+        &mut self.ir_stack
+    }
+}
+
+pub fn run_text(text: &str) -> Result<TextIRRun, TextIRError> {
+    Ok(run_function(&parse_function(text)?))
+}
+
+// Assembles function into a fresh IRVMState and runs it on a fresh stack, below a frame which
+// returns to a TopLevelReturn exit. Safepoint polls, monitor and logging exits are recorded and
+// resumed. Any other exit is recorded and ends the run, since it would need a real jvm to service.
+//
+// The IRVMState is leaked, so this is only meant for tests.
+pub fn run_function(function: &TextIRFunction) -> TextIRRun {
+    run_function_impl(function, false)
+}
+
+// Like run_function, but a safepoint poll which is taken ends the run, the way a thread stopping at a
+// safepoint would leave compiled code.
+pub fn run_function_until_safepoint(function: &TextIRFunction) -> TextIRRun {
+    run_function_impl(function, true)
+}
+
+fn run_function_impl(function: &TextIRFunction, stop_at_safepoint_polls: bool) -> TextIRRun {
+    let ir_vm_state: &'static IRVMState<'static, RunnerStack> = Box::leak(Box::new(IRVMState::new()));
+    ir_vm_state.inner.write().unwrap().handler.get_or_init(|| {
+        let handler: ExitHandlerType<'static, RunnerStack> = Arc::new(|event: &IRVMExitEvent, runner_stack: &mut RunnerStack, rbp_and_rsp: RBPAndRSP, _ir_vm_state: &IRVMState<'static, RunnerStack>| {
+            runner_stack.notify_guest_exit(rbp_and_rsp.rbp, rbp_and_rsp.rsp);
+            stub_exit_handler(&event.exit_type, runner_stack)
+        });
+        handler
+    });
+    let modification_lock = GlobalCodeEditingLock::new();
+
+    let top_level_ir_method_id = ir_vm_state.reserve_method_id();
+    ir_vm_state.add_function(vec![IRInstr::VMExit2 { exit_type: IRVMExitType::TopLevelReturn }], FRAME_HEADER_END_OFFSET, top_level_ir_method_id, None, modification_lock.acquire());
+    ir_vm_state.init_top_level_exit_id(top_level_ir_method_id);
+
+    let ir_method_id = ir_vm_state.reserve_method_id();
+    let (ir_method_id, _restart_points, _call_targets) = ir_vm_state.add_function(function.instructions.clone(), function.frame_size, ir_method_id, None, modification_lock.acquire());
+
+    let ir_stack = OwnedIRStack::new().unwrap();
+    let top = ir_stack.native.mmaped_top;
+    let rbp = unsafe { NonNull::new(top.as_ptr().sub(FRAME_HEADER_END_OFFSET)).unwrap() };
+    let rsp = unsafe { NonNull::new(rbp.as_ptr().sub(function.frame_size)).unwrap() };
+    let data = vec![0u64; (function.frame_size - FRAME_HEADER_END_OFFSET) / 8];
+    unsafe {
+        ir_stack.write_frame(top, null(), null_mut(), Some(top_level_ir_method_id), -1, &[]);
+        ir_stack.write_frame(rbp, ir_vm_state.get_top_level_return_ir_pointer().as_ptr(), top.as_ptr(), Some(ir_method_id), -1, data.as_slice());
+    }
+    let mut runner_stack = RunnerStack {
+        ir_stack,
+        rbp,
+        rsp,
+        exits: vec![],
+        stopped: false,
+        stop_at_safepoint_polls,
+    };
+    let extra_intrinsics = ExtraIntrinsicHelpers { constant_size_allocation: null() };
+    let return_value = match ir_vm_state.run_method(extra_intrinsics, ir_method_id, RBPAndRSP { rbp, rsp }, &mut runner_stack) {
+        Ok(return_value) => return_value,
+        Err(_) => panic!("stub exit handlers never throw"),
+    };
+    TextIRRun {
+        return_value: if runner_stack.stopped { None } else { Some(return_value) },
+        exits: runner_stack.exits,
+    }
+}
+
+fn stub_exit_handler(exit_type: &RuntimeVMExitInput, runner_stack: &mut RunnerStack) -> IRVMExitAction {
+    if let RuntimeVMExitInput::TopLevelReturn { return_value } = exit_type {
+        return IRVMExitAction::ExitVMCompletely { return_data: *return_value };
+    }
+    runner_stack.exits.push(exit_name(exit_type));
+    match exit_type {
+        RuntimeVMExitInput::SafePointPoll { return_to_ptr, .. } if !runner_stack.stop_at_safepoint_polls => {
+            IRVMExitAction::RestartAtPtr { ptr: *return_to_ptr }
+        }
+        RuntimeVMExitInput::MonitorEnter { return_to_ptr, .. } |
+        RuntimeVMExitInput::MonitorExit { return_to_ptr, .. } |
+        RuntimeVMExitInput::MonitorEnterRegister { return_to_ptr, .. } |
+        RuntimeVMExitInput::MonitorExitRegister { return_to_ptr, .. } |
+        RuntimeVMExitInput::LogWholeFrame { return_to_ptr, .. } |
+        RuntimeVMExitInput::TraceInstructionBefore { return_to_ptr, .. } |
+        RuntimeVMExitInput::TraceInstructionAfter { return_to_ptr, .. } => {
+            IRVMExitAction::RestartAtPtr { ptr: *return_to_ptr }
+        }
+        _ => {
+            runner_stack.stopped = true;
+            IRVMExitAction::ExitVMCompletely { return_data: 0 }
+        }
+    }
+}
+
+fn exit_name(exit_type: &RuntimeVMExitInput) -> String {
+    let debug = format!("{:?}", exit_type);
+    let name = debug.split(|c: char| c == ' ' || c == '{').next().unwrap_or("").to_string();
+    match exit_type.exiting_pc() {
+        None => name,
+        Some(java_pc) => format!("{}@{}", name, java_pc.0),
+    }
+}
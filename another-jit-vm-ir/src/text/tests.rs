@@ -0,0 +1,102 @@
+use std::fs::read_dir;
+use std::path::PathBuf;
+
+use crate::{add_function_from_ir, IRInstructIndex};
+use crate::text::parser::{parse_function, parse_instr};
+use crate::text::printer::{print_function, print_instr};
+use crate::text::runner::run_function;
+use crate::text::TextIRError;
+
+fn ir_test_files() -> Vec<PathBuf> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("ir-tests");
+    let mut files = read_dir(dir).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map(|extension| extension == "ir").unwrap_or(false))
+        .collect::<Vec<_>>();
+    files.sort();
+    files
+}
+
+#[test]
+fn round_trip_instructions() {
+    let lines = [
+        "LoadFPRelative from=fp[48] to=r1 size=qword",
+        "StoreFPRelativeDouble from=d2 to=fp[56]",
+        "FloatToIntegerConvert from=f1 temp=mm0 to=r3",
+        "Div res=r1 divisor=r2 must_be_rax=r0 must_be_rbx=r4 must_be_rcx=r5 must_be_rdx=r3 size=dword signed=signed",
+        "ShiftRight res=r1 a=r2 cl_aka_register_2=r3 size=qword signed=logical",
+        "ConstFloat to=f0 temp=r1 const_=-1.5",
+        "ConstDouble to=d0 temp=r1 const_=NaN",
+        "DoubleCompare value1=d0 value2=d1 res=r1 temp1=r2 temp2=r3 temp3=r4 compare_mode=g",
+        "BoundsCheck length=r1 index=r2 size=dword exit=ArrayOutOfBounds{java_pc=3,index=fp[64]}",
+        "Return return_val=none temp_register_1=r1 temp_register_2=r2 temp_register_3=r3 temp_register_4=r4 frame_size=64",
+        "VMExit2 exit_type=Deoptimize{method_id=12,java_pc=9,stack_depth=2}",
+        "VMExit2 exit_type=TopLevelReturn",
+        "IRCall temp_register_1=r1 temp_register_2=r2 arg_from_to_offsets=[fp[48]->fp[16],fp[56]->fp[8]] return_value=fp[64] target_address=Constant{address=0x7f0012345678,method_id=4} current_frame_size=72",
+        "IRCall temp_register_1=r1 temp_register_2=r2 arg_from_to_offsets=[] return_value=none target_address=Variable{address=r3} current_frame_size=48",
+        "RestartPoint id=5",
+        "NOP",
+    ];
+    for line in lines.iter() {
+        let instr = parse_instr(line).unwrap();
+        assert_eq!(print_instr(&instr).unwrap(), *line);
+    }
+}
+
+#[test]
+fn parse_errors() {
+    assert!(matches!(parse_instr("LoadFPRelative from=fp[48] to=r1"), Err(TextIRError::Parse { .. })));
+    assert!(matches!(parse_instr("LoadFPRelative from=fp[48] to=r1 size=qword extra=r2"), Err(TextIRError::Parse { .. })));
+    assert!(matches!(parse_instr("CopyRegister from=f1 to=r2"), Err(TextIRError::Parse { .. })));
+    assert!(matches!(parse_instr("NotAnInstruction"), Err(TextIRError::Parse { .. })));
+    assert!(matches!(parse_instr("AllocateConstantSize"), Err(TextIRError::Unsupported { .. })));
+    assert_eq!(parse_function("function frame_size=48\n    NOP\n    Bogus\nend\n").unwrap_err(), TextIRError::Parse { line: 3, message: "unknown instruction: Bogus".to_string() });
+}
+
+#[test]
+fn call_return_points() {
+    let calls = [
+        "IRCall temp_register_1=r1 temp_register_2=r2 arg_from_to_offsets=[fp[48]->fp[16]] return_value=fp[64] target_address=Constant{address=0x7f0012345678,method_id=4} current_frame_size=72",
+        "IRCall temp_register_1=r1 temp_register_2=r2 arg_from_to_offsets=[] return_value=none target_address=Variable{address=r3} current_frame_size=48",
+    ];
+    for call in calls {
+        let instructions = vec![parse_instr(call).unwrap(), parse_instr("NOP").unwrap()];
+        let (_, ir_index_to_assembly_index, _, _, call_return_points) = add_function_from_ir(&instructions);
+        let call_assembly = ir_index_to_assembly_index.iter().filter(|(ir_index, _)| *ir_index == IRInstructIndex(0)).map(|(_, assembly_index)| *assembly_index).collect::<Vec<_>>();
+        //the call returns to its own last instruction, even without a return value to store
+        assert_eq!(call_return_points, vec![(IRInstructIndex(0), *call_assembly.last().unwrap())], "{}", call);
+    }
+}
+
+#[test]
+fn round_trip_ir_test_files() {
+    for path in ir_test_files() {
+        let text = std::fs::read_to_string(&path).unwrap();
+        let printed = print_function(&parse_function(text.as_str()).unwrap()).unwrap();
+        let reprinted = print_function(&parse_function(printed.as_str()).unwrap()).unwrap();
+        assert_eq!(printed, reprinted, "{}", path.display());
+    }
+}
+
+// Each file states what it should do with lines of the form:
+//     # expect return=<n>|none
+//     # expect exits=<Name@pc>,...
+#[test]
+fn run_ir_test_files() {
+    for path in ir_test_files() {
+        let text = std::fs::read_to_string(&path).unwrap();
+        let mut expected_return = None;
+        let mut expected_exits = vec![];
+        for expectation in text.lines().filter_map(|line| line.trim().strip_prefix("# expect ")) {
+            match expectation.split_once('=').unwrap() {
+                ("return", "none") => expected_return = Some(None),
+                ("return", value) => expected_return = Some(Some(value.parse::<u64>().unwrap())),
+                ("exits", value) => expected_exits = value.split(',').map(|exit| exit.to_string()).collect(),
+                (key, _) => panic!("{}: unknown expectation {}", path.display(), key),
+            }
+        }
+        let run = run_function(&parse_function(text.as_str()).unwrap());
+        assert_eq!(run.return_value, expected_return.expect("missing # expect return="), "{}", path.display());
+        assert_eq!(run.exits, expected_exits, "{}", path.display());
+    }
+}
@@ -0,0 +1,376 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use another_jit_vm::{FramePointerOffset, Register};
+use rust_jvm_common::ByteCodeOffset;
+
+use crate::compiler::{IRCallTarget, IRInstr, LabelName, Size};
+use crate::IRInstructIndex;
+
+#[cfg(test)]
+mod tests;
+
+// Catches IR which would otherwise assemble fine and then misbehave at runtime. Only checks things
+// which are wrong regardless of what the surrounding code intends, since stage0 deliberately widens
+// and narrows values between producers and consumers in plenty of places (array indices, field
+// stores). Widening is only reported where the producer left the upper bits stale.
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct IRValidationError {
+    pub ir_index: IRInstructIndex,
+    pub java_pc: Option<ByteCodeOffset>,
+    pub instruction: String,
+    pub message: String,
+}
+
+impl Display for IRValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.java_pc {
+            None => write!(f, "ir[{}] {}: {}", self.ir_index.0, self.instruction, self.message),
+            Some(java_pc) => write!(f, "ir[{}] {} (java pc {}): {}", self.ir_index.0, self.instruction, java_pc.0, self.message),
+        }
+    }
+}
+
+pub fn validate_function(instructions: &[IRInstr], frame_size: usize, ir_index_to_java_pc: Option<&HashMap<IRInstructIndex, ByteCodeOffset>>) -> Result<(), Vec<IRValidationError>> {
+    let mut validator = Validator {
+        instructions,
+        ir_index_to_java_pc,
+        errors: vec![],
+    };
+    validator.check_labels();
+    validator.check_frame(frame_size);
+    validator.check_fixed_registers();
+    validator.check_address_widths();
+    validator.check_operand_widths();
+    if validator.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(validator.errors)
+    }
+}
+
+struct Validator<'l> {
+    instructions: &'l [IRInstr],
+    ir_index_to_java_pc: Option<&'l HashMap<IRInstructIndex, ByteCodeOffset>>,
+    errors: Vec<IRValidationError>,
+}
+
+impl Validator<'_> {
+    fn error(&mut self, index: usize, message: String) {
+        let ir_index = IRInstructIndex(index);
+        self.errors.push(IRValidationError {
+            ir_index,
+            java_pc: self.ir_index_to_java_pc.and_then(|java_pcs| java_pcs.get(&ir_index).cloned()),
+            instruction: self.instructions[index].debug_string(),
+            message,
+        });
+    }
+
+    fn check_labels(&mut self) {
+        let instructions = self.instructions;
+        let mut defined: HashMap<LabelName, usize> = HashMap::new();
+        for (i, instr) in instructions.iter().enumerate() {
+            if let IRInstr::Label(label) = instr {
+                if let Some(prev) = defined.insert(label.name, i) {
+                    self.error(i, format!("label L{} already defined at ir[{}]", label.name.0, prev));
+                }
+            }
+        }
+        for (i, instr) in instructions.iter().enumerate() {
+            if let Some(label) = referenced_label(instr) {
+                if !defined.contains_key(&label) {
+                    self.error(i, format!("branch to undefined label L{}", label.0));
+                }
+            }
+        }
+    }
+
+    fn check_frame(&mut self, frame_size: usize) {
+        let instructions = self.instructions;
+        let mut has_frame = false;
+        let mut restart_points = HashMap::new();
+        for (i, instr) in instructions.iter().enumerate() {
+            match instr {
+                IRInstr::IRStart { frame_size: start_frame_size, .. } => {
+                    if i != 0 {
+                        self.error(i, "IRStart must be the first instruction".to_string());
+                    }
+                    if *start_frame_size != frame_size {
+                        self.error(i, format!("frame size {} does not match function frame size {}", start_frame_size, frame_size));
+                    }
+                    has_frame = true;
+                }
+                IRInstr::RestartPoint(restart_point_id) => {
+                    if !has_frame {
+                        self.error(i, "restart point before the frame is set up by IRStart".to_string());
+                    }
+                    if let Some(prev) = restart_points.insert(*restart_point_id, i) {
+                        self.error(i, format!("restart point {:?} already defined at ir[{}]", restart_point_id, prev));
+                    }
+                }
+                IRInstr::Return { frame_size: return_frame_size, .. } => {
+                    if *return_frame_size != frame_size {
+                        self.error(i, format!("frame size {} does not match function frame size {}", return_frame_size, frame_size));
+                    }
+                }
+                IRInstr::IRCall { current_frame_size, .. } => {
+                    if *current_frame_size != frame_size {
+                        self.error(i, format!("current frame size {} does not match function frame size {}", current_frame_size, frame_size));
+                    }
+                }
+                _ => {}
+            }
+            if let Some(offset) = frame_offset(instr) {
+                if offset.0 > frame_size {
+                    self.error(i, format!("fp[{}] is outside of the frame of size {}", offset.0, frame_size));
+                }
+            }
+        }
+    }
+
+    fn check_fixed_registers(&mut self) {
+        let instructions = self.instructions;
+        for (i, instr) in instructions.iter().enumerate() {
+            match instr {
+                IRInstr::Div { divisor: operand, must_be_rax, must_be_rbx, must_be_rcx, must_be_rdx, .. } |
+                IRInstr::Mod { divisor: operand, must_be_rax, must_be_rbx, must_be_rcx, must_be_rdx, .. } |
+                IRInstr::Mul { a: operand, must_be_rax, must_be_rbx, must_be_rcx, must_be_rdx, .. } => {
+                    let fixed = [(*must_be_rax, RAX, "must_be_rax"), (*must_be_rbx, RBX, "must_be_rbx"), (*must_be_rcx, RCX, "must_be_rcx"), (*must_be_rdx, RDX, "must_be_rdx")];
+                    for (register, expected, name) in fixed.iter() {
+                        if register != expected {
+                            self.error(i, format!("{} is r{} but must be r{}", name, register.0, expected.0));
+                        }
+                    }
+                    if [RAX, RBX, RCX, RDX].contains(operand) {
+                        self.error(i, format!("operand r{} is clobbered before use, it can not be one of r0-r3", operand.0));
+                    }
+                }
+                IRInstr::CompareAndSwapAtomic { rax, .. } => {
+                    if *rax != RAX {
+                        self.error(i, format!("rax is r{} but must be r{}", rax.0, RAX.0));
+                    }
+                }
+                IRInstr::ShiftLeft { res, cl_aka_register_2, .. } |
+                IRInstr::ShiftRight { res, cl_aka_register_2, .. } => {
+                    if *cl_aka_register_2 != RCX {
+                        self.error(i, format!("cl_aka_register_2 is r{} but must be r{}", cl_aka_register_2.0, RCX.0));
+                    }
+                    if *res == RCX {
+                        self.error(i, format!("res r{} is clobbered by the shift amount", res.0));
+                    }
+                }
+                IRInstr::Return { return_val: Some(_), temp_register_1, temp_register_2, temp_register_3, temp_register_4, .. } => {
+                    if [temp_register_1, temp_register_2, temp_register_3, temp_register_4].contains(&&RAX) {
+                        self.error(i, format!("temp registers can not be r{} when returning a value", RAX.0));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // A pointer loaded or computed at less than qword width is truncated, and dereferencing it
+    // segfaults. Widths are only tracked within straight line code, and any instruction not modeled
+    // here forgets everything, so this never reports a width it is unsure of.
+    fn check_address_widths(&mut self) {
+        let instructions = self.instructions;
+        let mut widths: HashMap<Register, (Size, usize)> = HashMap::new();
+        for (i, instr) in instructions.iter().enumerate() {
+            for address in address_registers(instr) {
+                if let Some((size, producer)) = widths.get(&address).cloned() {
+                    if size != Size::X86QWord {
+                        self.error(i, format!("r{} is used as an address but was last written at {:?} width by ir[{}]", address.0, size, producer));
+                    }
+                }
+            }
+            match written_width(instr, &widths) {
+                Some(written) => {
+                    for (register, size) in written {
+                        match size {
+                            None => widths.remove(&register),
+                            Some(size) => widths.insert(register, (size, i)),
+                        };
+                    }
+                }
+                None => widths.clear(),
+            }
+        }
+    }
+
+    // Byte and word writes leave the rest of the register as it was, so a consumer reading it wider
+    // than that sees stale bits. Dword writes and loads zero the rest, which makes reading them at any
+    // width fine. Tracked the same way as address widths.
+    fn check_operand_widths(&mut self) {
+        let instructions = self.instructions;
+        let mut defined: HashMap<Register, (Size, usize)> = HashMap::new();
+        for (i, instr) in instructions.iter().enumerate() {
+            for (register, size) in read_widths(instr) {
+                if let Some((defined_size, producer)) = defined.get(&register).cloned() {
+                    if size_bits(size) > size_bits(defined_size) {
+                        self.error(i, format!("r{} is read at {:?} width but ir[{}] only wrote its low {:?}", register.0, size, producer, defined_size));
+                    }
+                }
+            }
+            match defined_width(instr, &defined) {
+                Some(written) => {
+                    for (register, size) in written {
+                        match size {
+                            None => defined.remove(&register),
+                            Some(size) => defined.insert(register, (size, i)),
+                        };
+                    }
+                }
+                None => defined.clear(),
+            }
+        }
+    }
+}
+
+const RAX: Register = Register(0);
+const RBX: Register = Register(1);
+const RCX: Register = Register(2);
+const RDX: Register = Register(3);
+
+fn referenced_label(instr: &IRInstr) -> Option<LabelName> {
+    match instr {
+        IRInstr::BranchToLabel { label } |
+        IRInstr::LoadLabel { label, .. } |
+        IRInstr::BranchEqual { label, .. } |
+        IRInstr::BranchNotEqual { label, .. } |
+        IRInstr::BranchAGreaterB { label, .. } |
+        IRInstr::BranchAGreaterEqualB { label, .. } |
+        IRInstr::BranchALessB { label, .. } |
+        IRInstr::BranchEqualVal { label, .. } => Some(*label),
+        _ => None,
+    }
+}
+
+fn frame_offset(instr: &IRInstr) -> Option<FramePointerOffset> {
+    match instr {
+        IRInstr::LoadFPRelative { from, .. } |
+        IRInstr::LoadFPRelativeFloat { from, .. } |
+        IRInstr::LoadFPRelativeDouble { from, .. } => Some(*from),
+        IRInstr::StoreFPRelative { to, .. } |
+        IRInstr::StoreFPRelativeFloat { to, .. } |
+        IRInstr::StoreFPRelativeDouble { to, .. } => Some(*to),
+        _ => None,
+    }
+}
+
+fn address_registers(instr: &IRInstr) -> Vec<Register> {
+    match instr {
+        IRInstr::Load { from_address, .. } => vec![*from_address],
+        IRInstr::Store { to_address, .. } => vec![*to_address],
+        IRInstr::CompareAndSwapAtomic { ptr, .. } => vec![*ptr],
+        IRInstr::NPECheck { possibly_null, .. } => vec![*possibly_null],
+        IRInstr::MemCopyForward { src_base_addr, dst_base_addr, .. } => vec![*src_base_addr, *dst_base_addr],
+        IRInstr::WriteRBP { from } => vec![*from],
+        IRInstr::IRCall { target_address: IRCallTarget::Variable { address }, .. } => vec![*address],
+        _ => vec![],
+    }
+}
+
+// The registers written by instr and the width they were written at, None for a register whose
+// width is not known afterwards. None overall if instr is not modeled.
+fn written_width(instr: &IRInstr, widths: &HashMap<Register, (Size, usize)>) -> Option<Vec<(Register, Option<Size>)>> {
+    Some(match instr {
+        IRInstr::LoadFPRelative { to, size, .. } |
+        IRInstr::Load { to, size, .. } => vec![(*to, Some(*size))],
+        IRInstr::SignExtend { to, to_size, .. } |
+        IRInstr::ZeroExtend { to, to_size, .. } => vec![(*to, Some(*to_size))],
+        IRInstr::Add { res, size, .. } |
+        IRInstr::Sub { res, size, .. } |
+        IRInstr::BinaryBitAnd { res, size, .. } |
+        IRInstr::BinaryBitOr { res, size, .. } |
+        IRInstr::BinaryBitXor { res, size, .. } |
+        IRInstr::MulConst { res, size, .. } => vec![(*res, Some(*size))],
+        IRInstr::Const16bit { to, .. } => vec![(*to, Some(Size::X86Word))],
+        IRInstr::Const32bit { to, .. } => vec![(*to, Some(Size::X86DWord))],
+        IRInstr::Const64bit { to, .. } |
+        IRInstr::LoadRBP { to } |
+        IRInstr::LoadLabel { to, .. } => vec![(*to, Some(Size::X86QWord))],
+        IRInstr::AddConst { res, .. } => vec![(*res, Some(Size::X86QWord))],
+        IRInstr::CopyRegister { from, to } => vec![(*to, widths.get(from).map(|(size, _)| *size))],
+        IRInstr::StoreFPRelative { .. } |
+        IRInstr::StoreFPRelativeFloat { .. } |
+        IRInstr::StoreFPRelativeDouble { .. } |
+        IRInstr::Store { .. } |
+        IRInstr::LoadFPRelativeFloat { .. } |
+        IRInstr::LoadFPRelativeDouble { .. } |
+        IRInstr::AddFloat { .. } |
+        IRInstr::SubFloat { .. } |
+        IRInstr::MulFloat { .. } |
+        IRInstr::DivFloat { .. } |
+        IRInstr::AddDouble { .. } |
+        IRInstr::SubDouble { .. } |
+        IRInstr::MulDouble { .. } |
+        IRInstr::DivDouble { .. } |
+        IRInstr::NOP => vec![],
+        IRInstr::NPECheck { temp_register, .. } => vec![(*temp_register, None)],
+        _ => return None,
+    })
+}
+
+
+fn size_bits(size: Size) -> usize {
+    match size {
+        Size::Byte => 8,
+        Size::X86Word => 16,
+        Size::X86DWord => 32,
+        Size::X86QWord => 64,
+    }
+}
+
+// The registers instr reads as integers and the width it reads them at.
+fn read_widths(instr: &IRInstr) -> Vec<(Register, Size)> {
+    match instr {
+        IRInstr::StoreFPRelative { from, size, .. } |
+        IRInstr::Store { from, size, .. } |
+        IRInstr::SignExtend { from, from_size: size, .. } |
+        IRInstr::ZeroExtend { from, from_size: size, .. } => vec![(*from, *size)],
+        IRInstr::Add { res, a, size } |
+        IRInstr::BinaryBitAnd { res, a, size } |
+        IRInstr::BinaryBitOr { res, a, size } |
+        IRInstr::BinaryBitXor { res, a, size } |
+        IRInstr::Sub { res, to_subtract: a, size } => vec![(*res, *size), (*a, *size)],
+        IRInstr::IntCompare { value1: a, value2: b, size, .. } |
+        IRInstr::BranchEqual { a, b, size, .. } |
+        IRInstr::BranchNotEqual { a, b, size, .. } |
+        IRInstr::BranchAGreaterB { a, b, size, .. } |
+        IRInstr::BranchAGreaterEqualB { a, b, size, .. } |
+        IRInstr::BranchALessB { a, b, size, .. } |
+        IRInstr::AssertEqual { a, b, size } |
+        IRInstr::BoundsCheck { length: a, index: b, size, .. } |
+        IRInstr::CompareAndSwapAtomic { old: a, new: b, size, .. } => vec![(*a, *size), (*b, *size)],
+        IRInstr::BranchEqualVal { a, size, .. } => vec![(*a, *size)],
+        _ => vec![],
+    }
+}
+
+// Like written_width, but the width of each register which holds what was written rather than the
+// width of the value.
+fn defined_width(instr: &IRInstr, defined: &HashMap<Register, (Size, usize)>) -> Option<Vec<(Register, Option<Size>)>> {
+    let zeroes_upper = |size: Size| if size_bits(size) >= 32 { Size::X86QWord } else { size };
+    Some(match instr {
+        IRInstr::LoadFPRelative { to, .. } |
+        IRInstr::Load { to, .. } |
+        IRInstr::Const16bit { to, .. } |
+        IRInstr::Const32bit { to, .. } |
+        IRInstr::Const64bit { to, .. } |
+        IRInstr::LoadRBP { to } |
+        IRInstr::LoadLabel { to, .. } => vec![(*to, Some(Size::X86QWord))],
+        IRInstr::SignExtend { to, to_size, .. } |
+        IRInstr::ZeroExtend { to, to_size, .. } => vec![(*to, Some(zeroes_upper(*to_size)))],
+        IRInstr::Add { res, size, .. } |
+        IRInstr::Sub { res, size, .. } |
+        IRInstr::BinaryBitAnd { res, size, .. } |
+        IRInstr::BinaryBitOr { res, size, .. } |
+        IRInstr::BinaryBitXor { res, size, .. } |
+        IRInstr::MulConst { res, size, .. } => vec![(*res, Some(zeroes_upper(*size)))],
+        IRInstr::AddConst { res, .. } => vec![(*res, Some(Size::X86QWord))],
+        IRInstr::CopyRegister { from, to } => vec![(*to, defined.get(from).map(|(size, _)| *size))],
+        IRInstr::NPECheck { temp_register, .. } => vec![(*temp_register, None)],
+        _ => written_width(instr, &HashMap::new())?.into_iter().map(|(register, _)| (register, None)).collect(),
+    })
+}
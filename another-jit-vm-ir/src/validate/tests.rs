@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use rust_jvm_common::ByteCodeOffset;
+
+use crate::IRInstructIndex;
+use crate::text::parser::parse_function;
+use crate::validate::validate_function;
+
+fn messages(text: &str) -> Vec<String> {
+    let function = parse_function(text).unwrap();
+    match validate_function(function.instructions.as_slice(), function.frame_size, None) {
+        Ok(()) => vec![],
+        Err(errors) => errors.into_iter().map(|error| error.message).collect(),
+    }
+}
+
+#[test]
+fn valid_function() {
+    let text = "function frame_size=64
+        IRStart temp_register=r1 ir_method_id=0 method_id=0 frame_size=64 num_locals=1
+        RestartPoint id=0
+        LoadFPRelative from=fp[48] to=r2 size=qword
+        NPECheck possibly_null=r2 temp_register=r3 npe_exit_type=NPE{java_pc=0}
+        Load to=r1 from_address=r2 size=dword
+        BranchEqualVal a=r1 const_=0 label=L0 size=dword
+    L0:
+        Return return_val=r1 temp_register_1=r2 temp_register_2=r3 temp_register_3=r4 temp_register_4=r5 frame_size=64
+    end";
+    assert_eq!(messages(text), Vec::<String>::new());
+}
+
+#[test]
+fn undefined_and_duplicate_labels() {
+    let text = "function frame_size=48
+        BranchToLabel label=L3
+    L1:
+    L1:
+        NOP
+    end";
+    assert_eq!(messages(text), vec!["label L1 already defined at ir[1]".to_string(), "branch to undefined label L3".to_string()]);
+}
+
+#[test]
+fn restart_point_without_frame() {
+    let text = "function frame_size=48
+        RestartPoint id=1
+        NOP
+    end";
+    assert_eq!(messages(text), vec!["restart point before the frame is set up by IRStart".to_string()]);
+}
+
+#[test]
+fn frame_sizes_must_agree() {
+    let text = "function frame_size=64
+        IRStart temp_register=r1 ir_method_id=0 method_id=0 frame_size=56 num_locals=1
+        StoreFPRelative from=r1 to=fp[72] size=qword
+        Return return_val=none temp_register_1=r1 temp_register_2=r2 temp_register_3=r3 temp_register_4=r4 frame_size=64
+    end";
+    assert_eq!(messages(text), vec![
+        "frame size 56 does not match function frame size 64".to_string(),
+        "fp[72] is outside of the frame of size 64".to_string(),
+    ]);
+}
+
+#[test]
+fn fixed_registers() {
+    let text = "function frame_size=48
+        Div res=r5 divisor=r3 must_be_rax=r0 must_be_rbx=r1 must_be_rcx=r4 must_be_rdx=r3 size=dword signed=signed
+        ShiftLeft res=r2 a=r1 cl_aka_register_2=r2 size=dword signed=logical
+        Return return_val=r1 temp_register_1=r0 temp_register_2=r2 temp_register_3=r3 temp_register_4=r4 frame_size=48
+    end";
+    assert_eq!(messages(text), vec![
+        "must_be_rcx is r4 but must be r2".to_string(),
+        "operand r3 is clobbered before use, it can not be one of r0-r3".to_string(),
+        "res r2 is clobbered by the shift amount".to_string(),
+        "temp registers can not be r0 when returning a value".to_string(),
+    ]);
+}
+
+#[test]
+fn truncated_address() {
+    let text = "function frame_size=56
+        LoadFPRelative from=fp[48] to=r2 size=dword
+        CopyRegister from=r2 to=r3
+        Load to=r1 from_address=r3 size=qword
+    L0:
+        Load to=r1 from_address=r3 size=qword
+    end";
+    assert_eq!(messages(text), vec!["r3 is used as an address but was last written at X86DWord width by ir[1]".to_string()]);
+}
+
+#[test]
+fn operand_wider_than_producer() {
+    let text = "function frame_size=56
+        LoadFPRelative from=fp[48] to=r1 size=word
+        Add res=r1 a=r1 size=word
+        CopyRegister from=r1 to=r2
+        StoreFPRelative from=r2 to=fp[48] size=dword
+        LoadFPRelative from=fp[40] to=r3 size=dword
+        Add res=r3 a=r3 size=dword
+        StoreFPRelative from=r3 to=fp[40] size=qword
+        StoreFPRelative from=r1 to=fp[48] size=byte
+    end";
+    assert_eq!(messages(text), vec!["r2 is read at X86DWord width but ir[2] only wrote its low X86Word".to_string()]);
+}
+
+#[test]
+fn diagnostics_include_java_pc() {
+    let function = parse_function("function frame_size=48
+        BranchToLabel label=L0
+    end").unwrap();
+    let mut java_pcs = HashMap::new();
+    java_pcs.insert(IRInstructIndex(0), ByteCodeOffset(17));
+    let errors = validate_function(function.instructions.as_slice(), function.frame_size, Some(&java_pcs)).unwrap_err();
+    assert_eq!(errors[0].to_string(), "ir[0] BranchToLabel (java pc 17): branch to undefined label L0");
+}
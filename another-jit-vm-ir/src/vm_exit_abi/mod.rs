@@ -14,7 +14,7 @@ use rust_jvm_common::method_shape::MethodShapeID;
 use sketch_jvm_version_of_utf8::wtf8_pool::CompressedWtf8String;
 
 use crate::compiler::RestartPointID;
-use crate::vm_exit_abi::register_structs::{AllocateObject, AllocateObjectArray, AllocateObjectArrayIntrinsic, ArrayOutOfBounds, AssertInstanceOf, CheckCast, CheckCastFailure, CompileFunctionAndRecompileCurrent, ExitRegisterStruct, GetStatic, InitClassAndRecompile, InstanceOf, InvokeInterfaceResolve, InvokeVirtualResolve, LoadClassAndRecompile, LogFramePointerOffsetValue, LogWholeFrame, MonitorEnter, MonitorEnterRegister, MonitorExit, MonitorExitRegister, MultiAllocateArray, NewClass, NewClassRegister, NewString, NPE, PutStatic, RunInterpreted, RunNativeSpecial, RunNativeVirtual, RunSpecialNativeNew, RunStaticNative, RunStaticNativeNew, Throw, Todo, TopLevelReturn, TraceInstructionAfter, TraceInstructionBefore};
+use crate::vm_exit_abi::register_structs::{AllocateObject, AllocateObjectArray, AllocateObjectArrayIntrinsic, ArrayOutOfBounds, AssertInstanceOf, CheckCast, CheckCastFailure, CompileFunctionAndRecompileCurrent, Deoptimize, DeoptimizeReturn, ExitRegisterStruct, GetStatic, InitClassAndRecompile, InstanceOf, InvokeInterfaceResolve, InvokeVirtualResolve, LoadClassAndRecompile, LogFramePointerOffsetValue, LogWholeFrame, MonitorEnter, MonitorEnterRegister, MonitorExit, MonitorExitRegister, MultiAllocateArray, NewClass, NewClassRegister, NewString, NPE, PutStatic, RunInterpreted, RunNativeSpecial, RunNativeVirtual, RunSpecialNativeNew, RunStaticNative, RunStaticNativeNew, SafePointPoll, Throw, Todo, TopLevelReturn, TraceInstructionAfter, TraceInstructionBefore};
 use crate::vm_exit_abi::runtime_input::{RawVMExitType, TodoCase};
 
 pub mod register_structs;
@@ -214,6 +214,15 @@ pub enum IRVMExitType {
     RunInterpreted {
         method_id: MethodId
     },
+    Deoptimize {
+        method_id: MethodId,
+        java_pc: ByteCodeOffset,
+        stack_depth: u16,
+    },
+    DeoptimizeReturn,
+    SafePointPoll {
+        java_pc: ByteCodeOffset,
+    },
 }
 
 impl IRVMExitType {
@@ -479,6 +488,21 @@ impl IRVMExitType {
                 assembler.mov(RunInterpreted::METHOD_ID.to_native_64(), *method_id as u64).unwrap();
                 assembler.lea(RunInterpreted::RESTART_IP.to_native_64(), qword_ptr(*after_exit_label)).unwrap();
             }
+            IRVMExitType::Deoptimize { method_id, java_pc, stack_depth } => {
+                assembler.mov(rax, RawVMExitType::Deoptimize as u64).unwrap();
+                assembler.mov(Deoptimize::METHOD_ID.to_native_64(), *method_id as u64).unwrap();
+                assembler.mov(Deoptimize::JAVA_PC.to_native_64(), java_pc.0 as u64).unwrap();
+                assembler.mov(Deoptimize::STACK_DEPTH.to_native_64(), *stack_depth as u64).unwrap();
+            }
+            IRVMExitType::DeoptimizeReturn => {
+                assembler.mov(DeoptimizeReturn::RES.to_native_64(), rax).unwrap();
+                assembler.mov(rax, RawVMExitType::DeoptimizeReturn as u64).unwrap();
+            }
+            IRVMExitType::SafePointPoll { java_pc } => {
+                assembler.mov(rax, RawVMExitType::SafePointPoll as u64).unwrap();
+                assembler.lea(SafePointPoll::RESTART_IP.to_native_64(), qword_ptr(*after_exit_label)).unwrap();
+                assembler.mov(SafePointPoll::JAVA_PC.to_native_64(), java_pc.0 as u64).unwrap();
+            }
             IRVMExitType::AssertInstanceOf { value, res, cpdtype, java_pc, expected } => {
                 assembler.mov(rax, RawVMExitType::AssertInstanceOf as u64).unwrap();
                 assembler.mov(AssertInstanceOf::FAST_INSTANCE_OF_RES.to_native_64(), expected.to_native_64()).unwrap();
@@ -609,6 +633,15 @@ impl IRVMExitType {
             IRVMExitType::RunInterpreted { .. } => {
                 todo!()
             }
+            IRVMExitType::Deoptimize { .. } => {
+                todo!()
+            }
+            IRVMExitType::DeoptimizeReturn => {
+                todo!()
+            }
+            IRVMExitType::SafePointPoll { .. } => {
+                todo!()
+            }
             IRVMExitType::AssertInstanceOf { .. } => {
                 todo!()
             }
@@ -725,6 +758,15 @@ impl IRVMExitType {
             IRVMExitType::RunInterpreted { .. } => {
                 RunInterpreted::all_registers()
             }
+            IRVMExitType::Deoptimize { .. } => {
+                Deoptimize::all_registers()
+            }
+            IRVMExitType::DeoptimizeReturn => {
+                DeoptimizeReturn::all_registers()
+            }
+            IRVMExitType::SafePointPoll { .. } => {
+                SafePointPoll::all_registers()
+            }
             IRVMExitType::AssertInstanceOf { .. } => {
                 AssertInstanceOf::all_registers()
             }
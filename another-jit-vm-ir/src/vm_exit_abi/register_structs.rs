@@ -598,4 +598,44 @@ impl ExitRegisterStruct for RunInterpreted {
     fn all_registers() -> HashSet<Register> {
         HashSet::from([Register(0), Self::METHOD_ID, Self::RESTART_IP])
     }
-}
\ No newline at end of file
+}
+
+
+pub struct Deoptimize;
+
+impl Deoptimize {
+    pub const METHOD_ID: Register = Register(2);
+    pub const JAVA_PC: Register = Register(3);
+    pub const STACK_DEPTH: Register = Register(4);
+}
+
+impl ExitRegisterStruct for Deoptimize {
+    fn all_registers() -> HashSet<Register> {
+        HashSet::from([Register(0), Self::METHOD_ID, Self::JAVA_PC, Self::STACK_DEPTH])
+    }
+}
+
+pub struct DeoptimizeReturn;
+
+impl DeoptimizeReturn {
+    pub const RES: Register = Register(2);
+}
+
+impl ExitRegisterStruct for DeoptimizeReturn {
+    fn all_registers() -> HashSet<Register> {
+        HashSet::from([Register(0), Self::RES])
+    }
+}
+
+pub struct SafePointPoll;
+
+impl SafePointPoll {
+    pub const RESTART_IP: Register = Register(2);
+    pub const JAVA_PC: Register = Register(3);
+}
+
+impl ExitRegisterStruct for SafePointPoll {
+    fn all_registers() -> HashSet<Register> {
+        HashSet::from([Register(0), Self::RESTART_IP, Self::JAVA_PC])
+    }
+}
@@ -21,7 +21,7 @@ use rust_jvm_common::method_shape::MethodShapeID;
 use sketch_jvm_version_of_utf8::wtf8_pool::CompressedWtf8String;
 
 use crate::RestartPointID;
-use crate::vm_exit_abi::register_structs::{AllocateObject, AllocateObjectArray, AllocateObjectArrayIntrinsic, ArrayOutOfBounds, AssertInstanceOf, CheckCast, CheckCastFailure, CompileFunctionAndRecompileCurrent, GetStatic, InitClassAndRecompile, InstanceOf, InvokeInterfaceResolve, InvokeVirtualResolve, LogFramePointerOffsetValue, LogWholeFrame, MonitorEnter, MonitorEnterRegister, MonitorExit, MultiAllocateArray, NewClass, NewClassRegister, NewString, NPE, PutStatic, RunInterpreted, RunNativeSpecial, RunNativeVirtual, RunStaticNative, RunStaticNativeNew, Throw, Todo, TopLevelReturn, TraceInstructionAfter, TraceInstructionBefore};
+use crate::vm_exit_abi::register_structs::{AllocateObject, AllocateObjectArray, AllocateObjectArrayIntrinsic, ArrayOutOfBounds, AssertInstanceOf, CheckCast, CheckCastFailure, CompileFunctionAndRecompileCurrent, Deoptimize, DeoptimizeReturn, GetStatic, InitClassAndRecompile, InstanceOf, InvokeInterfaceResolve, InvokeVirtualResolve, LogFramePointerOffsetValue, LogWholeFrame, MonitorEnter, MonitorEnterRegister, MonitorExit, MultiAllocateArray, NewClass, NewClassRegister, NewString, NPE, PutStatic, RunInterpreted, RunNativeSpecial, RunNativeVirtual, RunStaticNative, RunStaticNativeNew, SafePointPoll, Throw, Todo, TopLevelReturn, TraceInstructionAfter, TraceInstructionBefore};
 
 #[derive(FromPrimitive)]
 #[repr(u64)]
@@ -63,6 +63,9 @@ pub enum RawVMExitType {
     RunSpecialNativeNew,
     RunInterpreted,
     AllocateObjectArrayIntrinsic,
+    Deoptimize,
+    DeoptimizeReturn,
+    SafePointPoll,
 }
 
 
@@ -296,6 +299,18 @@ pub enum RuntimeVMExitInput {
         method_id: MethodId,
         return_to_ptr: *const c_void,
     },
+    Deoptimize {
+        method_id: MethodId,
+        stack_depth: u16,
+        pc: ByteCodeOffset,
+    },
+    DeoptimizeReturn {
+        return_value: u64,
+    },
+    SafePointPoll {
+        return_to_ptr: *const c_void,
+        pc: ByteCodeOffset,
+    },
     Todo {
         pc: ByteCodeOffset,
         todo_case: TodoCase,
@@ -566,6 +581,24 @@ impl RuntimeVMExitInput {
                     return_to_ptr: register_state.saved_registers_without_ip.get_register(RunInterpreted::RESTART_IP) as *const c_void,
                 }
             }
+            RawVMExitType::Deoptimize => {
+                RuntimeVMExitInput::Deoptimize {
+                    method_id: register_state.saved_registers_without_ip.get_register(Deoptimize::METHOD_ID) as MethodId,
+                    stack_depth: register_state.saved_registers_without_ip.get_register(Deoptimize::STACK_DEPTH) as u16,
+                    pc: ByteCodeOffset(register_state.saved_registers_without_ip.get_register(Deoptimize::JAVA_PC) as u16),
+                }
+            }
+            RawVMExitType::DeoptimizeReturn => {
+                RuntimeVMExitInput::DeoptimizeReturn {
+                    return_value: register_state.saved_registers_without_ip.get_register(DeoptimizeReturn::RES),
+                }
+            }
+            RawVMExitType::SafePointPoll => {
+                RuntimeVMExitInput::SafePointPoll {
+                    return_to_ptr: register_state.saved_registers_without_ip.get_register(SafePointPoll::RESTART_IP) as *const c_void,
+                    pc: ByteCodeOffset(register_state.saved_registers_without_ip.get_register(SafePointPoll::JAVA_PC) as u16),
+                }
+            }
             RawVMExitType::AssertInstanceOf => {
                 RuntimeVMExitInput::AssertInstanceOf {
                     res: register_state.saved_registers_without_ip.get_register(AssertInstanceOf::RES_VALUE_PTR) as *mut c_void,
@@ -627,6 +660,9 @@ impl RuntimeVMExitInput {
             RuntimeVMExitInput::RunNativeSpecialNew { .. } => None,
             RuntimeVMExitInput::RunNativeStaticNew { .. } => None,
             RuntimeVMExitInput::RunInterpreted { .. } => None,
+            RuntimeVMExitInput::Deoptimize { pc, .. } => Some(*pc),
+            RuntimeVMExitInput::DeoptimizeReturn { .. } => None,
+            RuntimeVMExitInput::SafePointPoll { pc, .. } => Some(*pc),
             RuntimeVMExitInput::AssertInstanceOf { pc, .. } => Some(*pc),
             RuntimeVMExitInput::NewClassRegister { pc, .. } => Some(*pc),
             RuntimeVMExitInput::MonitorEnterRegister { pc, .. } => Some(*pc),
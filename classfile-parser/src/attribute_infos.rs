@@ -1,5 +1,5 @@
 use rust_jvm_common::ByteCodeOffset;
-use rust_jvm_common::classfile::{Annotation, AnnotationDefault, AnnotationValue, AppendFrame, ArrayValue, AttributeInfo, AttributeType, BootstrapMethod, BootstrapMethods, ChopFrame, ClassInfoIndex, Code, ConstantKind, ConstantValue, Deprecated, ElementValue, ElementValuePair, EnumConstValue, Exceptions, ExceptionTableElem, FullFrame, InnerClass, InnerClasses, LineNumber, LineNumberTable, LineNumberTableEntry, LocalVariableTable, LocalVariableTableEntry, LocalVariableTypeTable, LocalVariableTypeTableEntry, LocalVarTargetTableEntry, MethodParameter, MethodParameters, NestHost, NestMembers, RuntimeInvisibleAnnotations, RuntimeVisibleAnnotations, RuntimeVisibleParameterAnnotations, RuntimeVisibleTypeAnnotations, SameFrame, SameFrameExtended, SameLocals1StackItemFrame, SameLocals1StackItemFrameExtended, Signature, SourceDebugExtension, SourceFile, StackMapFrame, StackMapTable, Synthetic, TargetInfo, TypeAnnotation, TypePath, TypePathEntry, UninitializedVariableInfo};
+use rust_jvm_common::classfile::{Annotation, AnnotationDefault, AnnotationValue, AppendFrame, ArrayValue, AttributeInfo, AttributeType, BootstrapMethod, BootstrapMethods, ChopFrame, ClassInfoIndex, Code, ConstantKind, ConstantValue, Deprecated, ElementValue, ElementValuePair, EnumConstValue, Exceptions, ExceptionTableElem, FullFrame, InnerClass, InnerClasses, LineNumber, LineNumberTable, LineNumberTableEntry, LocalVariableTable, LocalVariableTableEntry, LocalVariableTypeTable, LocalVariableTypeTableEntry, LocalVarTargetTableEntry, MethodParameter, MethodParameters, Module, ModuleExports, ModuleMainClass, ModuleOpens, ModulePackages, ModuleProvides, ModuleRequires, NestHost, NestMembers, PermittedSubclasses, Record, RecordComponentInfo, RuntimeInvisibleAnnotations, RuntimeVisibleAnnotations, RuntimeVisibleParameterAnnotations, RuntimeVisibleTypeAnnotations, SameFrame, SameFrameExtended, SameLocals1StackItemFrame, SameLocals1StackItemFrameExtended, Signature, SourceDebugExtension, SourceFile, StackMapFrame, StackMapTable, Synthetic, TargetInfo, TypeAnnotation, TypePath, TypePathEntry, UninitializedVariableInfo};
 use rust_jvm_common::classfile::AttributeType::Unknown;
 use rust_jvm_common::classfile::EnclosingMethod;
 use rust_jvm_common::classnames::ClassName;
@@ -45,6 +45,11 @@ pub fn parse_attribute(p: &mut dyn ParsingContext) -> Result<AttributeInfo, Clas
         //java 9+ but gets parsed anyway:
         "NestMembers" => parse_nest_members(p),
         "NestHost" => parse_nest_host(p),
+        "Module" => parse_module(p),
+        "ModulePackages" => parse_module_packages(p),
+        "ModuleMainClass" => parse_module_main_class(p),
+        "Record" => parse_record(p),
+        "PermittedSubclasses" => parse_permitted_subclasses(p),
         _ => {
             for _ in 0..attribute_length {
                 p.read8()?;
@@ -97,6 +102,82 @@ fn parse_nest_members(p: &mut dyn ParsingContext) -> Result<AttributeType, Class
     Ok(AttributeType::NestMembers(NestMembers { classes }))
 }
 
+fn parse_index_table(p: &mut dyn ParsingContext) -> Result<Vec<u16>, ClassfileParsingError> {
+    let count = p.read16()?;
+    let mut indices = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        indices.push(p.read16()?);
+    }
+    Ok(indices)
+}
+
+fn parse_module(p: &mut dyn ParsingContext) -> Result<AttributeType, ClassfileParsingError> {
+    let module_name_index = p.read16()?;
+    let module_flags = p.read16()?;
+    let module_version_index = p.read16()?;
+    let requires_count = p.read16()?;
+    let mut requires = Vec::with_capacity(requires_count as usize);
+    for _ in 0..requires_count {
+        let requires_index = p.read16()?;
+        let requires_flags = p.read16()?;
+        let requires_version_index = p.read16()?;
+        requires.push(ModuleRequires { requires_index, requires_flags, requires_version_index });
+    }
+    let exports_count = p.read16()?;
+    let mut exports = Vec::with_capacity(exports_count as usize);
+    for _ in 0..exports_count {
+        let exports_index = p.read16()?;
+        let exports_flags = p.read16()?;
+        let exports_to_index = parse_index_table(p)?;
+        exports.push(ModuleExports { exports_index, exports_flags, exports_to_index });
+    }
+    let opens_count = p.read16()?;
+    let mut opens = Vec::with_capacity(opens_count as usize);
+    for _ in 0..opens_count {
+        let opens_index = p.read16()?;
+        let opens_flags = p.read16()?;
+        let opens_to_index = parse_index_table(p)?;
+        opens.push(ModuleOpens { opens_index, opens_flags, opens_to_index });
+    }
+    let uses_index = parse_index_table(p)?;
+    let provides_count = p.read16()?;
+    let mut provides = Vec::with_capacity(provides_count as usize);
+    for _ in 0..provides_count {
+        let provides_index = p.read16()?;
+        let provides_with_index = parse_index_table(p)?;
+        provides.push(ModuleProvides { provides_index, provides_with_index });
+    }
+    Ok(AttributeType::Module(Module { module_name_index, module_flags, module_version_index, requires, exports, opens, uses_index, provides }))
+}
+
+fn parse_module_packages(p: &mut dyn ParsingContext) -> Result<AttributeType, ClassfileParsingError> {
+    let package_index = parse_index_table(p)?;
+    Ok(AttributeType::ModulePackages(ModulePackages { package_index }))
+}
+
+fn parse_module_main_class(p: &mut dyn ParsingContext) -> Result<AttributeType, ClassfileParsingError> {
+    let main_class_index = p.read16()?;
+    Ok(AttributeType::ModuleMainClass(ModuleMainClass { main_class_index }))
+}
+
+fn parse_record(p: &mut dyn ParsingContext) -> Result<AttributeType, ClassfileParsingError> {
+    let components_count = p.read16()?;
+    let mut components = Vec::with_capacity(components_count as usize);
+    for _ in 0..components_count {
+        let name_index = p.read16()?;
+        let descriptor_index = p.read16()?;
+        let attributes_count = p.read16()?;
+        let attributes = parse_attributes(p, attributes_count)?;
+        components.push(RecordComponentInfo { name_index, descriptor_index, attributes });
+    }
+    Ok(AttributeType::Record(Record { components }))
+}
+
+fn parse_permitted_subclasses(p: &mut dyn ParsingContext) -> Result<AttributeType, ClassfileParsingError> {
+    let classes = parse_index_table(p)?;
+    Ok(AttributeType::PermittedSubclasses(PermittedSubclasses { classes }))
+}
+
 fn parse_constant_value_index(p: &mut dyn ParsingContext) -> Result<AttributeType, ClassfileParsingError> {
     let constant_value_index = p.read16()?;
     Ok(AttributeType::ConstantValue(ConstantValue { constant_value_index }))
@@ -1,5 +1,5 @@
 use rust_jvm_common::classfile::*;
-use rust_jvm_common::classfile::{Class, ConstantInfo, ConstantKind, Fieldref, Float, Integer, InterfaceMethodref, InvalidConstant, InvokeDynamic, Long, MethodHandle, Methodref, MethodType, NameAndType, String_, Utf8};
+use rust_jvm_common::classfile::{Class, ConstantInfo, ConstantKind, Dynamic, Fieldref, Float, Integer, InterfaceMethodref, InvalidConstant, InvokeDynamic, Long, MethodHandle, Methodref, MethodType, Module_, NameAndType, Package_, String_, Utf8};
 use rust_jvm_common::classfile::Double;
 use rust_jvm_common::classfile::ReferenceKind::{GetField, GetStatic, InvokeInterface, InvokeSpecial, InvokeStatic, InvokeVirtual, NewInvokeSpecial, PutField, PutStatic};
 use sketch_jvm_version_of_utf8::PossiblyJVMString;
@@ -27,7 +27,10 @@ const INTERFACE_METHODREF_CONST_NUM: u8 = 11;
 const NAME_AND_TYPE_CONST_NUM: u8 = 12;
 const METHOD_HANDLE_CONST_NUM: u8 = 15;
 const METHOD_TYPE_CONST_NUM: u8 = 16;
+const DYNAMIC_CONST_NUM: u8 = 17;
 const INVOKE_DYNAMIC_CONST_NUM: u8 = 18;
+const MODULE_CONST_NUM: u8 = 19;
+const PACKAGE_CONST_NUM: u8 = 20;
 
 pub fn parse_constant_info(p: &mut dyn ParsingContext, _debug: bool) -> Result<ConstantInfo, ClassfileParsingError> {
     let kind = p.read8()?;
@@ -121,11 +124,24 @@ pub fn parse_constant_info(p: &mut dyn ParsingContext, _debug: bool) -> Result<C
             let descriptor_index = p.read16()?;
             ConstantKind::MethodType(MethodType { descriptor_index })
         }
+        DYNAMIC_CONST_NUM => {
+            let bootstrap_method_attr_index = p.read16()?;
+            let name_and_type_index = p.read16()?;
+            ConstantKind::Dynamic(Dynamic { bootstrap_method_attr_index, name_and_type_index })
+        }
         INVOKE_DYNAMIC_CONST_NUM => {
             let bootstrap_method_attr_index = p.read16()?;
             let name_and_type_index = p.read16()?;
             ConstantKind::InvokeDynamic(InvokeDynamic { bootstrap_method_attr_index, name_and_type_index })
         }
+        MODULE_CONST_NUM => {
+            let name_index = p.read16()?;
+            ConstantKind::Module(Module_ { name_index })
+        }
+        PACKAGE_CONST_NUM => {
+            let name_index = p.read16()?;
+            ConstantKind::Package(Package_ { name_index })
+        }
         _ => {
             return Err(ClassfileParsingError::WrongTag);
         }
@@ -2,7 +2,7 @@
 
 use std::io::{BufReader, Read};
 
-use rust_jvm_common::classfile::{Classfile, FieldInfo, MethodInfo};
+use rust_jvm_common::classfile::{AttributeType, Classfile, ConstantKind, FieldInfo, MethodInfo};
 use rust_jvm_common::EXPECTED_CLASSFILE_MAGIC;
 use rust_jvm_common::loading::ClassfileParsingError;
 use rust_jvm_common::loading::ClassfileParsingError::WrongMagic;
@@ -64,9 +64,30 @@ pub fn parse_class_file(read: &mut dyn Read) -> Result<Classfile, ClassfileParsi
     let mut p = ReadParsingContext { constant_pool: None, read: &mut BufReader::new(read) };
     let mut class_file = parse_from_context(&mut p)?;
     class_file.constant_pool = p.constant_pool();
+    check_bootstrap_method_indices(&class_file)?;
     Ok(class_file)
 }
 
+// views resolve call sites and dynamic constants through the BootstrapMethods attribute, so reject classes where
+// it is missing or too short rather than failing at link time
+fn check_bootstrap_method_indices(class_file: &Classfile) -> Result<(), ClassfileParsingError> {
+    let num_bootstrap_methods = class_file.attributes.iter().find_map(|attr| match &attr.attribute_type {
+        AttributeType::BootstrapMethods(bootstrap_methods) => Some(bootstrap_methods.bootstrap_methods.len()),
+        _ => None,
+    }).unwrap_or(0);
+    for constant in &class_file.constant_pool {
+        let bootstrap_method_attr_index = match &constant.kind {
+            ConstantKind::InvokeDynamic(invoke_dynamic) => invoke_dynamic.bootstrap_method_attr_index,
+            ConstantKind::Dynamic(dynamic) => dynamic.bootstrap_method_attr_index,
+            _ => continue,
+        };
+        if bootstrap_method_attr_index as usize >= num_bootstrap_methods {
+            return Err(ClassfileParsingError::MissingBootstrapMethod);
+        }
+    }
+    Ok(())
+}
+
 fn parse_from_context(p: &mut dyn ParsingContext) -> Result<Classfile, ClassfileParsingError> {
     let magic: u32 = p.read32()?;
     if magic != EXPECTED_CLASSFILE_MAGIC {
@@ -103,4 +124,213 @@ fn parse_from_context(p: &mut dyn ParsingContext) -> Result<Classfile, Classfile
     })
 }
 
-pub mod parse_validation;
\ No newline at end of file
+pub mod parse_validation;
+#[cfg(test)]
+pub mod test {
+    use rust_jvm_common::classfile::{AttributeInfo, AttributeType, BootstrapMethod, BootstrapMethods, ConstantKind, Dynamic, Module, Module_, ModuleExports, ModuleOpens, ModuleProvides, ModuleRequires, Package_, PermittedSubclasses, Record, RecordComponentInfo, Signature};
+    use rust_jvm_common::loading::ClassfileParsingError;
+
+    use crate::parse_class_file;
+
+    const ACC_MODULE: u16 = 0x8000;
+    const ACC_MANDATED: u16 = 0x8000;
+
+    fn u16s(values: &[u16]) -> Vec<u8> {
+        values.iter().flat_map(|value| value.to_be_bytes()).collect()
+    }
+
+    // assembles a class file with no fields or methods, constant pool indices are handed out in order
+    struct TestClass {
+        constants: Vec<u8>,
+        next_index: u16,
+        attributes: Vec<u8>,
+        attributes_count: u16,
+    }
+
+    impl TestClass {
+        fn new() -> Self {
+            Self { constants: vec![], next_index: 1, attributes: vec![], attributes_count: 0 }
+        }
+
+        fn constant(&mut self, tag: u8, body: &[u8]) -> u16 {
+            self.constants.push(tag);
+            self.constants.extend_from_slice(body);
+            let index = self.next_index;
+            self.next_index += 1;
+            index
+        }
+
+        fn utf8(&mut self, string: &str) -> u16 {
+            let mut body = u16s(&[string.len() as u16]);
+            body.extend_from_slice(string.as_bytes());
+            self.constant(1, body.as_slice())
+        }
+
+        fn class(&mut self, name: &str) -> u16 {
+            let name_index = self.utf8(name);
+            self.constant(7, u16s(&[name_index]).as_slice())
+        }
+
+        fn module(&mut self, name: &str) -> u16 {
+            let name_index = self.utf8(name);
+            self.constant(19, u16s(&[name_index]).as_slice())
+        }
+
+        fn package(&mut self, name: &str) -> u16 {
+            let name_index = self.utf8(name);
+            self.constant(20, u16s(&[name_index]).as_slice())
+        }
+
+        fn name_and_type(&mut self, name: &str, descriptor: &str) -> u16 {
+            let name_index = self.utf8(name);
+            let descriptor_index = self.utf8(descriptor);
+            self.constant(12, u16s(&[name_index, descriptor_index]).as_slice())
+        }
+
+        fn attribute(&mut self, name: &str, body: Vec<u8>) -> (u16, u32) {
+            let name_index = self.utf8(name);
+            self.attributes.extend(u16s(&[name_index]));
+            self.attributes.extend((body.len() as u32).to_be_bytes());
+            self.attributes.extend(body.iter());
+            self.attributes_count += 1;
+            (name_index, body.len() as u32)
+        }
+
+        fn bytes(&self, access_flags: u16, this_class: u16, super_class: u16) -> Vec<u8> {
+            let mut res = vec![0xCA, 0xFE, 0xBA, 0xBE];
+            res.extend(u16s(&[0, 61, self.next_index]));
+            res.extend(self.constants.iter());
+            res.extend(u16s(&[access_flags, this_class, super_class, 0, 0, 0, self.attributes_count]));
+            res.extend(self.attributes.iter());
+            res
+        }
+    }
+
+    fn only_attribute(bytes: Vec<u8>) -> AttributeInfo {
+        let mut classfile = parse_class_file(&mut bytes.as_slice()).unwrap();
+        assert_eq!(classfile.attributes.len(), 1);
+        classfile.attributes.remove(0)
+    }
+
+    #[test]
+    pub fn module_attribute() {
+        let mut class = TestClass::new();
+        let this_class = class.class("module-info");
+        let module_name_index = class.module("test.module");
+        let module_version_index = class.utf8("1.0");
+        let java_base = class.module("java.base");
+        let other_module = class.module("other.module");
+        let package = class.package("test/pkg");
+        let service = class.class("test/pkg/Service");
+        let service_impl = class.class("test/pkg/ServiceImpl");
+        let mut body = u16s(&[module_name_index, 0, module_version_index]);
+        body.extend(u16s(&[1, java_base, ACC_MANDATED, 0]));
+        body.extend(u16s(&[1, package, 0, 1, other_module]));
+        body.extend(u16s(&[1, package, 0, 0]));
+        body.extend(u16s(&[1, service]));
+        body.extend(u16s(&[1, service, 1, service_impl]));
+        let (attribute_name_index, attribute_length) = class.attribute("Module", body);
+        let bytes = class.bytes(ACC_MODULE, this_class, 0);
+        let classfile = parse_class_file(&mut bytes.as_slice()).unwrap();
+        assert_eq!(classfile.constant_pool[java_base as usize].kind, ConstantKind::Module(Module_ { name_index: java_base - 1 }));
+        assert_eq!(classfile.constant_pool[package as usize].kind, ConstantKind::Package(Package_ { name_index: package - 1 }));
+        assert_eq!(classfile.attributes, vec![AttributeInfo {
+            attribute_name_index,
+            attribute_length,
+            attribute_type: AttributeType::Module(Module {
+                module_name_index,
+                module_flags: 0,
+                module_version_index,
+                requires: vec![ModuleRequires { requires_index: java_base, requires_flags: ACC_MANDATED, requires_version_index: 0 }],
+                exports: vec![ModuleExports { exports_index: package, exports_flags: 0, exports_to_index: vec![other_module] }],
+                opens: vec![ModuleOpens { opens_index: package, opens_flags: 0, opens_to_index: vec![] }],
+                uses_index: vec![service],
+                provides: vec![ModuleProvides { provides_index: service, provides_with_index: vec![service_impl] }],
+            }),
+        }]);
+    }
+
+    #[test]
+    pub fn record_attribute() {
+        let mut class = TestClass::new();
+        let this_class = class.class("test/Point");
+        let super_class = class.class("java/lang/Record");
+        let name_index = class.utf8("values");
+        let descriptor_index = class.utf8("Ljava/util/List;");
+        let signature_name_index = class.utf8("Signature");
+        let signature_index = class.utf8("Ljava/util/List<Ljava/lang/Integer;>;");
+        let mut body = u16s(&[1, name_index, descriptor_index, 1]);
+        body.extend(u16s(&[signature_name_index]));
+        body.extend(2u32.to_be_bytes());
+        body.extend(u16s(&[signature_index]));
+        let (attribute_name_index, attribute_length) = class.attribute("Record", body);
+        let attribute = only_attribute(class.bytes(0, this_class, super_class));
+        assert_eq!(attribute, AttributeInfo {
+            attribute_name_index,
+            attribute_length,
+            attribute_type: AttributeType::Record(Record {
+                components: vec![RecordComponentInfo {
+                    name_index,
+                    descriptor_index,
+                    attributes: vec![AttributeInfo {
+                        attribute_name_index: signature_name_index,
+                        attribute_length: 2,
+                        attribute_type: AttributeType::Signature(Signature { signature_index }),
+                    }],
+                }]
+            }),
+        });
+    }
+
+    #[test]
+    pub fn permitted_subclasses_attribute() {
+        let mut class = TestClass::new();
+        let this_class = class.class("test/Shape");
+        let super_class = class.class("java/lang/Object");
+        let circle = class.class("test/Circle");
+        let square = class.class("test/Square");
+        let (attribute_name_index, attribute_length) = class.attribute("PermittedSubclasses", u16s(&[2, circle, square]));
+        let attribute = only_attribute(class.bytes(0, this_class, super_class));
+        assert_eq!(attribute, AttributeInfo {
+            attribute_name_index,
+            attribute_length,
+            attribute_type: AttributeType::PermittedSubclasses(PermittedSubclasses { classes: vec![circle, square] }),
+        });
+    }
+
+    // a CONSTANT_Dynamic with its bootstrap method, returns the class, the dynamic constant and the method handle
+    fn dynamic_constant_class(with_bootstrap_methods: bool) -> (TestClass, u16, u16, u16, u16) {
+        let mut class = TestClass::new();
+        let this_class = class.class("test/Constants");
+        let super_class = class.class("java/lang/Object");
+        let bootstrap_nt = class.name_and_type("bootstrap", "(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/Class;I)I");
+        let bootstrap_ref = class.constant(10, u16s(&[this_class, bootstrap_nt]).as_slice());
+        let method_handle = class.constant(15, &[6, (bootstrap_ref >> 8) as u8, bootstrap_ref as u8]);
+        let argument = class.constant(3, &42u32.to_be_bytes());
+        let constant_nt = class.name_and_type("CONSTANT", "I");
+        let dynamic = class.constant(17, u16s(&[0, constant_nt]).as_slice());
+        if with_bootstrap_methods {
+            class.attribute("BootstrapMethods", u16s(&[1, method_handle, 1, argument]));
+        }
+        (class, this_class, super_class, dynamic, method_handle)
+    }
+
+    #[test]
+    pub fn dynamic_constant() {
+        let (class, this_class, super_class, dynamic, method_handle) = dynamic_constant_class(true);
+        let bytes = class.bytes(0, this_class, super_class);
+        let classfile = parse_class_file(&mut bytes.as_slice()).unwrap();
+        assert_eq!(classfile.constant_pool[dynamic as usize].kind, ConstantKind::Dynamic(Dynamic { bootstrap_method_attr_index: 0, name_and_type_index: dynamic - 1 }));
+        assert_eq!(classfile.attributes.len(), 1);
+        assert_eq!(classfile.attributes[0].attribute_type, AttributeType::BootstrapMethods(BootstrapMethods {
+            bootstrap_methods: vec![BootstrapMethod { bootstrap_method_ref: method_handle, bootstrap_arguments: vec![method_handle + 1] }]
+        }));
+    }
+
+    #[test]
+    pub fn dynamic_constant_without_bootstrap_methods() {
+        let (class, this_class, super_class, _, _) = dynamic_constant_class(false);
+        let bytes = class.bytes(0, this_class, super_class);
+        assert!(matches!(parse_class_file(&mut bytes.as_slice()), Err(ClassfileParsingError::MissingBootstrapMethod)));
+    }
+}
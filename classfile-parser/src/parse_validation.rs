@@ -2,8 +2,8 @@ use std::collections::HashMap;
 use std::ops::Range;
 
 use rust_jvm_common::classfile::{
-    ACC_ABSTRACT, ACC_ANNOTATION, ACC_ENUM, ACC_FINAL, ACC_INTERFACE, ACC_PRIVATE, ACC_PROTECTED, ACC_PUBLIC, ACC_SUPER, ACC_VOLATILE, Annotation, AnnotationDefault, AnnotationValue, ArrayValue, AttributeInfo, AttributeType, BootstrapMethod, BootstrapMethods, Class, Classfile, ClassInfoIndex, Code, ConstantInfo, ConstantKind, ElementValue, ElementValuePair, EnclosingMethod, EnumConstValue, Exceptions, FieldInfo, Fieldref, InterfaceMethodref, InvokeDynamic,
-    LocalVariableTableEntry, LocalVariableTypeTableEntry, LocalVarTargetTableEntry, MethodInfo, MethodParameter, MethodParameters, Methodref, NameAndType, ReferenceKind, String_, TargetInfo, TypeAnnotation, TypePath, TypePathEntry, Utf8,
+    ACC_ABSTRACT, ACC_ANNOTATION, ACC_ENUM, ACC_FINAL, ACC_INTERFACE, ACC_MODULE, ACC_PRIVATE, ACC_PROTECTED, ACC_PUBLIC, ACC_SUPER, ACC_VOLATILE, Annotation, AnnotationDefault, AnnotationValue, ArrayValue, AttributeInfo, AttributeType, BootstrapMethod, BootstrapMethods, Class, Classfile, ClassInfoIndex, Code, ConstantInfo, ConstantKind, Dynamic, ElementValue, ElementValuePair, EnclosingMethod, EnumConstValue, Exceptions, FieldInfo, Fieldref, InterfaceMethodref, InvokeDynamic,
+    LocalVariableTableEntry, LocalVariableTypeTableEntry, LocalVarTargetTableEntry, MethodInfo, MethodParameter, MethodParameters, Methodref, Module, Module_, ModuleExports, ModuleMainClass, ModuleOpens, ModulePackages, ModuleProvides, ModuleRequires, NameAndType, NestHost, NestMembers, Package_, PermittedSubclasses, Record, RecordComponentInfo, ReferenceKind, String_, TargetInfo, TypeAnnotation, TypePath, TypePathEntry, Utf8,
 };
 use rust_jvm_common::EXPECTED_CLASSFILE_MAGIC;
 use sketch_jvm_version_of_utf8::ValidationError;
@@ -12,11 +12,29 @@ use crate::parse_validation::ClassfileError::{BadConstantPoolEntry, ExpectedClas
 
 pub const MAX_ARRAY_DIMENSIONS: usize = 255;
 
+pub const JAVA_1_1_MAJOR_VERSION: u16 = 45;
+pub const JAVA_9_MAJOR_VERSION: u16 = 53;
+pub const JAVA_11_MAJOR_VERSION: u16 = 55;
+pub const JAVA_12_MAJOR_VERSION: u16 = 56;
+pub const JAVA_16_MAJOR_VERSION: u16 = 60;
+pub const JAVA_17_MAJOR_VERSION: u16 = 61;
+
 pub struct ValidatorSettings {
     allowable_major_versions: Range<u16>,
     allowable_minor_versions: HashMap<u16, Range<u16>>,
 }
 
+impl Default for ValidatorSettings {
+    fn default() -> Self {
+        let allowable_major_versions = JAVA_1_1_MAJOR_VERSION..(JAVA_17_MAJOR_VERSION + 1);
+        //from java 12 on the only non-zero minor version marks preview features, which aren't supported
+        let allowable_minor_versions = allowable_major_versions.clone()
+            .map(|major_version| (major_version, if major_version < JAVA_12_MAJOR_VERSION { 0..u16::MAX } else { 0..1 }))
+            .collect();
+        ValidatorSettings { allowable_major_versions, allowable_minor_versions }
+    }
+}
+
 pub enum ClassfileError {
     WrongMagic,
     BadMajorVersion,
@@ -41,6 +59,8 @@ pub enum ClassfileError {
     ExpectedClassEntry,
     InvalidConstant,
     Java9FeatureNotSupported,
+    FeatureNotInClassfileVersion,
+    ModuleConstantOutsideModule,
     ExpectedNameAndType,
     TooManyOfSameAttribute,
     AttributeOnWrongType,
@@ -71,6 +91,7 @@ pub enum AttributeEnclosingType<'l> {
     Code(&'l Code),
     Class(&'l Classfile),
     Field,
+    RecordComponent,
 }
 
 impl ValidatorSettings {
@@ -250,6 +271,18 @@ impl ValidatorSettings {
                     return Err(BadConstantPoolEntry);
                 }
             }
+            ConstantKind::Dynamic(dynamic) => {
+                ValidatorSettings::require_major_version(c, JAVA_11_MAJOR_VERSION)?;
+                self.validate_dynamic(c, dynamic)?;
+            }
+            ConstantKind::Module(Module_ { name_index }) | ConstantKind::Package(Package_ { name_index }) => {
+                ValidatorSettings::require_major_version(c, JAVA_9_MAJOR_VERSION)?;
+                //from the spec: only allowed in the constant pool of a class file which declares a module
+                if c.access_flags & ACC_MODULE == 0 {
+                    return Err(ClassfileError::ModuleConstantOutsideModule);
+                }
+                self.is_utf8_check(*name_index, c)?;
+            }
             ConstantKind::InvalidConstant(_) => {
                 return Result::Err(ClassfileError::InvalidConstant);
             }
@@ -261,6 +294,30 @@ impl ValidatorSettings {
         Result::Ok(())
     }
 
+    fn validate_dynamic(&self, c: &Classfile, dynamic: &Dynamic) -> Result<(), ClassfileError> {
+        let nt = self.is_name_and_type_check(dynamic.name_and_type_index, c)?;
+        self.validate_name_and_type(c, nt)?;
+        let bootstrap_attribute = c
+            .attributes
+            .iter()
+            .find_map(|attr| match &attr.attribute_type {
+                AttributeType::BootstrapMethods(attr) => Some(attr),
+                _ => None,
+            })
+            .ok_or(ClassfileError::BadConstantPoolEntry)?;
+        if dynamic.bootstrap_method_attr_index as usize >= bootstrap_attribute.bootstrap_methods.len() {
+            return Err(BadConstantPoolEntry);
+        }
+        Ok(())
+    }
+
+    fn require_major_version(c: &Classfile, min_major_version: u16) -> Result<(), ClassfileError> {
+        if c.major_version < min_major_version {
+            return Err(ClassfileError::FeatureNotInClassfileVersion);
+        }
+        Ok(())
+    }
+
     pub fn validate_string(&self, c: &Classfile, string: &String_) -> Result<(), ClassfileError> {
         self.is_utf8_check(string.string_index, c)?;
         Result::Ok(())
@@ -472,7 +529,7 @@ impl ValidatorSettings {
                     for bootstrap_arg in bootstrap_arguments {
                         self.index_check(*bootstrap_arg, c)?;
                         match c.constant_pool[*bootstrap_arg as usize].kind {
-                            ConstantKind::Integer(_) | ConstantKind::Float(_) | ConstantKind::Long(_) | ConstantKind::Double(_) | ConstantKind::Class(_) | ConstantKind::String(_) | ConstantKind::MethodHandle(_) | ConstantKind::MethodType(_) => {}
+                            ConstantKind::Integer(_) | ConstantKind::Float(_) | ConstantKind::Long(_) | ConstantKind::Double(_) | ConstantKind::Class(_) | ConstantKind::String(_) | ConstantKind::MethodHandle(_) | ConstantKind::MethodType(_) | ConstantKind::Dynamic(_) => {}
                             _ => return Err(ClassfileError::BadConstantPoolEntry),
                         }
                     }
@@ -482,8 +539,54 @@ impl ValidatorSettings {
                 }
                 attribute_validation_context.has_been_bootstrap_methods = true;
             }
-            AttributeType::NestHost(_) => {}
-            AttributeType::NestMembers(_) => {}
+            AttributeType::NestHost(NestHost { host_class_index }) => {
+                ValidatorSettings::require_major_version(c, JAVA_11_MAJOR_VERSION)?;
+                self.validate_class_attribute(&mut attribute_validation_context.has_been_nest_host, attr)?;
+                self.validate_class_info(c, *host_class_index)?;
+            }
+            AttributeType::NestMembers(NestMembers { classes }) => {
+                ValidatorSettings::require_major_version(c, JAVA_11_MAJOR_VERSION)?;
+                self.validate_class_attribute(&mut attribute_validation_context.has_been_nest_members, attr)?;
+                for class in classes {
+                    self.validate_class_info(c, *class)?;
+                }
+            }
+            AttributeType::Module(module) => {
+                ValidatorSettings::require_major_version(c, JAVA_9_MAJOR_VERSION)?;
+                self.validate_class_attribute(&mut attribute_validation_context.has_been_module, attr)?;
+                self.validate_module(c, module)?;
+            }
+            AttributeType::ModulePackages(ModulePackages { package_index }) => {
+                ValidatorSettings::require_major_version(c, JAVA_9_MAJOR_VERSION)?;
+                self.validate_class_attribute(&mut attribute_validation_context.has_been_module_packages, attr)?;
+                for package in package_index {
+                    self.validate_package(c, *package)?;
+                }
+            }
+            AttributeType::ModuleMainClass(ModuleMainClass { main_class_index }) => {
+                ValidatorSettings::require_major_version(c, JAVA_9_MAJOR_VERSION)?;
+                self.validate_class_attribute(&mut attribute_validation_context.has_been_module_main_class, attr)?;
+                self.validate_class_info(c, *main_class_index)?;
+            }
+            AttributeType::Record(Record { components }) => {
+                ValidatorSettings::require_major_version(c, JAVA_16_MAJOR_VERSION)?;
+                self.validate_class_attribute(&mut attribute_validation_context.has_been_record, attr)?;
+                for RecordComponentInfo { name_index, descriptor_index, attributes } in components {
+                    self.validate_utf8(c, *name_index)?;
+                    self.validate_utf8(c, *descriptor_index)?;
+                    let mut component_attribute_validation_context = AttributeValidationContext::default();
+                    for attribute in attributes {
+                        self.validate_attribute(&mut component_attribute_validation_context, attribute, c, &AttributeEnclosingType::RecordComponent)?;
+                    }
+                }
+            }
+            AttributeType::PermittedSubclasses(PermittedSubclasses { classes }) => {
+                ValidatorSettings::require_major_version(c, JAVA_17_MAJOR_VERSION)?;
+                self.validate_class_attribute(&mut attribute_validation_context.has_been_permitted_subclasses, attr)?;
+                for class in classes {
+                    self.validate_class_info(c, *class)?;
+                }
+            }
             AttributeType::ConstantValue(_) => {
                 ValidatorSettings::validate_constant_value(attribute_validation_context)?;
             }
@@ -724,6 +827,73 @@ impl ValidatorSettings {
         Ok(())
     }
 
+    fn validate_class_attribute(&self, has_been: &mut bool, attr: &AttributeEnclosingType) -> Result<(), ClassfileError> {
+        match attr {
+            AttributeEnclosingType::Class(_) => {}
+            _ => return Err(ClassfileError::AttributeOnWrongType),
+        }
+        if *has_been {
+            return Err(ClassfileError::TooManyOfSameAttribute);
+        }
+        *has_been = true;
+        Ok(())
+    }
+
+    fn validate_module(&self, c: &Classfile, module: &Module) -> Result<(), ClassfileError> {
+        let Module { module_name_index, module_version_index, requires, exports, opens, uses_index, provides, .. } = module;
+        self.validate_module_constant(c, *module_name_index)?;
+        self.validate_optional_utf8(c, *module_version_index)?;
+        for ModuleRequires { requires_index, requires_version_index, .. } in requires {
+            self.validate_module_constant(c, *requires_index)?;
+            self.validate_optional_utf8(c, *requires_version_index)?;
+        }
+        for ModuleExports { exports_index, exports_to_index, .. } in exports {
+            self.validate_package(c, *exports_index)?;
+            for to_module in exports_to_index {
+                self.validate_module_constant(c, *to_module)?;
+            }
+        }
+        for ModuleOpens { opens_index, opens_to_index, .. } in opens {
+            self.validate_package(c, *opens_index)?;
+            for to_module in opens_to_index {
+                self.validate_module_constant(c, *to_module)?;
+            }
+        }
+        for service in uses_index {
+            self.validate_class_info(c, *service)?;
+        }
+        for ModuleProvides { provides_index, provides_with_index } in provides {
+            self.validate_class_info(c, *provides_index)?;
+            for implementation in provides_with_index {
+                self.validate_class_info(c, *implementation)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_module_constant(&self, c: &Classfile, index: u16) -> Result<(), ClassfileError> {
+        self.index_check(index, c)?;
+        match &c.constant_pool[index as usize].kind {
+            ConstantKind::Module(_) => Ok(()),
+            _ => Err(BadConstantPoolEntry),
+        }
+    }
+
+    fn validate_package(&self, c: &Classfile, index: u16) -> Result<(), ClassfileError> {
+        self.index_check(index, c)?;
+        match &c.constant_pool[index as usize].kind {
+            ConstantKind::Package(_) => Ok(()),
+            _ => Err(BadConstantPoolEntry),
+        }
+    }
+
+    fn validate_optional_utf8(&self, c: &Classfile, index: u16) -> Result<(), ClassfileError> {
+        if index == 0 {
+            return Ok(());
+        }
+        self.validate_utf8(c, index)
+    }
+
     fn validate_synthetic(attr: &AttributeEnclosingType) -> Result<(), ClassfileError> {
         //doesn't say anything about only one
         match attr {
@@ -860,6 +1030,13 @@ pub struct AttributeValidationContext {
     has_been_annotation_default: bool,
     has_been_bootstrap_methods: bool,
     has_been_method_parameters: bool,
+    has_been_nest_host: bool,
+    has_been_nest_members: bool,
+    has_been_module: bool,
+    has_been_module_packages: bool,
+    has_been_module_main_class: bool,
+    has_been_record: bool,
+    has_been_permitted_subclasses: bool,
 }
 
 impl Default for AttributeValidationContext {
@@ -879,6 +1056,13 @@ impl Default for AttributeValidationContext {
             has_been_annotation_default: false,
             has_been_bootstrap_methods: false,
             has_been_method_parameters: false,
+            has_been_nest_host: false,
+            has_been_nest_members: false,
+            has_been_module: false,
+            has_been_module_packages: false,
+            has_been_module_main_class: false,
+            has_been_record: false,
+            has_been_permitted_subclasses: false,
         }
     }
 }
\ No newline at end of file
@@ -2,15 +2,15 @@ use std::sync::Arc;
 
 use wtf8::Wtf8Buf;
 
-use rust_jvm_common::classfile::{AttributeType, BootstrapMethod, CPIndex, EnclosingMethod, InnerClass, InnerClasses, SourceFile};
+use rust_jvm_common::classfile::{AttributeType, BootstrapMethod, CPIndex, EnclosingMethod, InnerClass, InnerClasses, Module, ModuleExports, ModuleOpens, ModuleProvides, ModuleRequires, PermittedSubclasses, Record, RecordComponentInfo, SourceFile};
 use rust_jvm_common::compressed_classfile::class_names::{CClassName, CompressedClassName};
-use rust_jvm_common::compressed_classfile::compressed_types::CPRefType;
+use rust_jvm_common::compressed_classfile::compressed_types::{CPDType, CPRefType};
 use rust_jvm_common::compressed_classfile::method_names::MethodName;
 use rust_jvm_common::compressed_classfile::string_pool::{CCString, CompressedClassfileStringPool};
-use rust_jvm_common::descriptor_parser::parse_class_name;
+use rust_jvm_common::descriptor_parser::{parse_class_name, parse_field_descriptor};
 
 use crate::view::{ClassBackedView, ClassView};
-use crate::view::constant_info_view::{ConstantInfoView, DoubleView, FloatView, IntegerView, LongView, MethodHandleView, MethodTypeView, StringView};
+use crate::view::constant_info_view::{ConstantInfoView, DoubleView, DynamicView, FloatView, IntegerView, LongView, MethodHandleView, MethodTypeView, StringView};
 
 #[derive(Clone)]
 pub struct BootstrapMethodIterator<'cl> {
@@ -94,6 +94,10 @@ impl<'cl> Iterator for BootstrapArgViewIterator<'cl> {
         let arg = *self.bootstrap_args.get(self.i)?;
         let res = match self.backing_class.constant_pool_view(arg as usize) {
             ConstantInfoView::Integer(i) => BootstrapArgView::Integer(i),
+            ConstantInfoView::Long(l) => BootstrapArgView::Long(l),
+            ConstantInfoView::Float(f) => BootstrapArgView::Float(f),
+            ConstantInfoView::Double(d) => BootstrapArgView::Double(d),
+            ConstantInfoView::Dynamic(dynamic) => BootstrapArgView::Dynamic(dynamic),
             ConstantInfoView::MethodType(mt) => BootstrapArgView::MethodType(mt),
             ConstantInfoView::MethodHandle(mh) => BootstrapArgView::MethodHandle(mh),
             ConstantInfoView::String(s) => BootstrapArgView::String(s),
@@ -108,7 +112,8 @@ impl<'cl> Iterator for BootstrapArgViewIterator<'cl> {
 }
 
 //CONSTANT_String_info,  CONSTANT_Class_info,CONSTANT_Integer_info, CONSTANT_Long_info,
-// CONSTANT_Float_info, CONSTANT_Double_info,CONSTANT_MethodHandle_info, or CONSTANT_MethodType_info
+// CONSTANT_Float_info, CONSTANT_Double_info,CONSTANT_MethodHandle_info, CONSTANT_MethodType_info
+// or CONSTANT_Dynamic_info
 pub enum BootstrapArgView<'cl> {
     String(StringView<'cl>),
     Class(Arc<ClassBackedView>),
@@ -118,6 +123,7 @@ pub enum BootstrapArgView<'cl> {
     Double(DoubleView),
     MethodHandle(MethodHandleView<'cl>),
     MethodType(MethodTypeView<'cl>),
+    Dynamic(DynamicView<'cl>),
 }
 
 #[allow(dead_code)]
@@ -224,4 +230,175 @@ impl SourceFileView<'_> {
         let si = self.source_file_attr().sourcefile_index;
         self.backing_class.underlying_class.constant_pool[si as usize].extract_string_from_utf8()
     }
-}
\ No newline at end of file
+}
+fn class_name_at(backing_class: &ClassBackedView, class_index: CPIndex, pool: &CompressedClassfileStringPool) -> CClassName {
+    backing_class.constant_pool_view(class_index as usize).unwrap_class().class_ref_type(pool).unwrap_name()
+}
+
+fn optional_utf8_at(backing_class: &ClassBackedView, index: CPIndex) -> Option<Wtf8Buf> {
+    if index == 0 {
+        return None;
+    }
+    Some(backing_class.underlying_class.constant_pool[index as usize].extract_string_from_utf8())
+}
+
+fn pool_elem_name_at(backing_class: &ClassBackedView, index: CPIndex) -> Wtf8Buf {
+    match backing_class.constant_pool_view(index as usize) {
+        ConstantInfoView::Module(module) => module.name(),
+        ConstantInfoView::Package(package) => package.name(),
+        _ => panic!(),
+    }
+}
+
+pub struct ModuleView<'l> {
+    pub(crate) backing_class: &'l ClassBackedView,
+    pub(crate) i: usize,
+}
+
+pub struct ModuleRequiresView {
+    pub module: Wtf8Buf,
+    pub flags: u16,
+    pub version: Option<Wtf8Buf>,
+}
+
+/// An exports or opens entry: a package and the modules it is granted to, all of them when empty.
+pub struct ModulePackageGrantView {
+    pub package: Wtf8Buf,
+    pub flags: u16,
+    pub to_modules: Vec<Wtf8Buf>,
+}
+
+pub struct ModuleProvidesView {
+    pub service: CClassName,
+    pub implementations: Vec<CClassName>,
+}
+
+impl ModuleView<'_> {
+    fn raw(&self) -> &Module {
+        match &self.backing_class.underlying_class.attributes[self.i].attribute_type {
+            AttributeType::Module(module) => module,
+            _ => panic!(),
+        }
+    }
+
+    pub fn name(&self) -> Wtf8Buf {
+        pool_elem_name_at(self.backing_class, self.raw().module_name_index)
+    }
+
+    pub fn flags(&self) -> u16 {
+        self.raw().module_flags
+    }
+
+    pub fn version(&self) -> Option<Wtf8Buf> {
+        optional_utf8_at(self.backing_class, self.raw().module_version_index)
+    }
+
+    pub fn requires(&self) -> Vec<ModuleRequiresView> {
+        self.raw().requires.iter().map(|ModuleRequires { requires_index, requires_flags, requires_version_index }| ModuleRequiresView {
+            module: pool_elem_name_at(self.backing_class, *requires_index),
+            flags: *requires_flags,
+            version: optional_utf8_at(self.backing_class, *requires_version_index),
+        }).collect()
+    }
+
+    pub fn exports(&self) -> Vec<ModulePackageGrantView> {
+        self.raw().exports.iter().map(|ModuleExports { exports_index, exports_flags, exports_to_index }| ModulePackageGrantView {
+            package: pool_elem_name_at(self.backing_class, *exports_index),
+            flags: *exports_flags,
+            to_modules: exports_to_index.iter().map(|to_module| pool_elem_name_at(self.backing_class, *to_module)).collect(),
+        }).collect()
+    }
+
+    pub fn opens(&self) -> Vec<ModulePackageGrantView> {
+        self.raw().opens.iter().map(|ModuleOpens { opens_index, opens_flags, opens_to_index }| ModulePackageGrantView {
+            package: pool_elem_name_at(self.backing_class, *opens_index),
+            flags: *opens_flags,
+            to_modules: opens_to_index.iter().map(|to_module| pool_elem_name_at(self.backing_class, *to_module)).collect(),
+        }).collect()
+    }
+
+    pub fn uses(&self, pool: &CompressedClassfileStringPool) -> Vec<CClassName> {
+        self.raw().uses_index.iter().map(|service| class_name_at(self.backing_class, *service, pool)).collect()
+    }
+
+    pub fn provides(&self, pool: &CompressedClassfileStringPool) -> Vec<ModuleProvidesView> {
+        self.raw().provides.iter().map(|ModuleProvides { provides_index, provides_with_index }| ModuleProvidesView {
+            service: class_name_at(self.backing_class, *provides_index, pool),
+            implementations: provides_with_index.iter().map(|implementation| class_name_at(self.backing_class, *implementation, pool)).collect(),
+        }).collect()
+    }
+
+    //ModulePackages and ModuleMainClass only ever accompany a Module attribute, so they are looked up from here
+    pub fn packages(&self) -> Vec<Wtf8Buf> {
+        self.backing_class.underlying_class.attributes.iter().find_map(|attr| match &attr.attribute_type {
+            AttributeType::ModulePackages(packages) => Some(packages.package_index.iter().map(|package| pool_elem_name_at(self.backing_class, *package)).collect()),
+            _ => None,
+        }).unwrap_or_default()
+    }
+
+    pub fn main_class(&self, pool: &CompressedClassfileStringPool) -> Option<CClassName> {
+        self.backing_class.underlying_class.attributes.iter().find_map(|attr| match &attr.attribute_type {
+            AttributeType::ModuleMainClass(main_class) => Some(class_name_at(self.backing_class, main_class.main_class_index, pool)),
+            _ => None,
+        })
+    }
+}
+
+pub struct RecordView<'l> {
+    pub(crate) backing_class: &'l ClassBackedView,
+    pub(crate) i: usize,
+}
+
+impl RecordView<'_> {
+    fn raw(&self) -> &Record {
+        match &self.backing_class.underlying_class.attributes[self.i].attribute_type {
+            AttributeType::Record(record) => record,
+            _ => panic!(),
+        }
+    }
+
+    pub fn components(&self) -> impl Iterator<Item=RecordComponentView> {
+        self.raw().components.iter().map(move |component| RecordComponentView { backing_class: self.backing_class, component })
+    }
+}
+
+pub struct RecordComponentView<'l> {
+    backing_class: &'l ClassBackedView,
+    component: &'l RecordComponentInfo,
+}
+
+impl RecordComponentView<'_> {
+    pub fn name(&self) -> Wtf8Buf {
+        self.backing_class.underlying_class.constant_pool[self.component.name_index as usize].extract_string_from_utf8()
+    }
+
+    pub fn type_(&self, pool: &CompressedClassfileStringPool) -> CPDType {
+        let desc_str = self.backing_class.underlying_class.constant_pool[self.component.descriptor_index as usize].extract_string_from_utf8().into_string().expect("should have validated this earlier maybe todo");
+        CPDType::from_ptype(&parse_field_descriptor(desc_str.as_str()).unwrap().field_type, pool)
+    }
+
+    pub fn signature(&self) -> Option<Wtf8Buf> {
+        self.component.attributes.iter().find_map(|attr| match &attr.attribute_type {
+            AttributeType::Signature(signature) => Some(self.backing_class.underlying_class.constant_pool[signature.signature_index as usize].extract_string_from_utf8()),
+            _ => None,
+        })
+    }
+}
+
+pub struct PermittedSubclassesView<'l> {
+    pub(crate) backing_class: &'l ClassBackedView,
+    pub(crate) i: usize,
+}
+
+impl PermittedSubclassesView<'_> {
+    fn raw(&self) -> &PermittedSubclasses {
+        match &self.backing_class.underlying_class.attributes[self.i].attribute_type {
+            AttributeType::PermittedSubclasses(permitted_subclasses) => permitted_subclasses,
+            _ => panic!(),
+        }
+    }
+
+    pub fn classes(&self, pool: &CompressedClassfileStringPool) -> Vec<CClassName> {
+        self.raw().classes.iter().map(|class| class_name_at(self.backing_class, *class, pool)).collect()
+    }
+}
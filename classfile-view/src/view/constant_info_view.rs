@@ -1,12 +1,12 @@
 use wtf8::Wtf8Buf;
 
-use rust_jvm_common::classfile::{Classfile, ConstantKind, CPIndex, Fieldref, InterfaceMethodref, MethodHandle, Methodref, MethodType, NameAndType, ReferenceKind};
+use rust_jvm_common::classfile::{Classfile, ConstantKind, CPIndex, Dynamic, Fieldref, InterfaceMethodref, MethodHandle, Methodref, MethodType, NameAndType, ReferenceKind};
 use rust_jvm_common::compressed_classfile::code::LiveObjectIndex;
 use rust_jvm_common::compressed_classfile::compressed_types::{CMethodDescriptor, CPDType, CPRefType};
 use rust_jvm_common::compressed_classfile::string_pool::{CCString, CompressedClassfileStringPool};
 
 
-use rust_jvm_common::descriptor_parser::{parse_class_name, parse_method_descriptor};
+use rust_jvm_common::descriptor_parser::{parse_class_name, parse_field_descriptor, parse_method_descriptor};
 use rust_jvm_common::ptype::PType;
 
 use crate::view::{ClassBackedView, ClassView};
@@ -282,12 +282,67 @@ impl InvokeDynamicView<'_> {
     //todo this is wrong, there are multiple bootstrap methods.
     pub fn bootstrap_method(&self) -> BootstrapMethodView {
         BootstrapMethodView {
-            backing: self.class_view.bootstrap_methods_attr().unwrap_or_else(|| todo!()),
+            backing: self.class_view.bootstrap_methods_attr().expect("checked when parsing"),
             i: self.bootstrap_method_attr_index as usize,
         }
     }
 }
 
+pub struct DynamicView<'cl> {
+    pub(crate) class_view: &'cl ClassBackedView,
+    pub i: usize,
+}
+
+impl DynamicView<'_> {
+    fn get_raw(&self) -> &Dynamic {
+        match &self.class_view.underlying_class.constant_pool[self.i].kind {
+            ConstantKind::Dynamic(dynamic) => dynamic,
+            _ => panic!(),
+        }
+    }
+
+    pub fn name_and_type(&self) -> NameAndTypeView {
+        NameAndTypeView { class_view: self.class_view, i: self.get_raw().name_and_type_index as usize }
+    }
+
+    pub fn bootstrap_method(&self) -> BootstrapMethodView {
+        BootstrapMethodView {
+            backing: self.class_view.bootstrap_methods_attr().expect("checked when parsing"),
+            i: self.get_raw().bootstrap_method_attr_index as usize,
+        }
+    }
+
+    pub fn field_type(&self, pool: &CompressedClassfileStringPool) -> CPDType {
+        let desc_str = self.class_view.underlying_class.constant_pool[self.name_and_type().name_and_type().descriptor_index as usize].extract_string_from_utf8().into_string().expect("should have validated this earlier maybe todo");
+        CPDType::from_ptype(&parse_field_descriptor(desc_str.as_str()).unwrap().field_type, pool)
+    }
+}
+
+#[derive(Debug)]
+pub struct ModulePoolElemView<'l> {
+    pub(crate) underlying_class: &'l Classfile,
+    pub(crate) name_index: usize,
+}
+
+impl ModulePoolElemView<'_> {
+    pub fn name(&self) -> Wtf8Buf {
+        self.underlying_class.constant_pool[self.name_index].extract_string_from_utf8()
+    }
+}
+
+#[derive(Debug)]
+pub struct PackagePoolElemView<'l> {
+    pub(crate) underlying_class: &'l Classfile,
+    pub(crate) name_index: usize,
+}
+
+impl PackagePoolElemView<'_> {
+    //in internal form, so java/lang rather than java.lang
+    pub fn name(&self) -> Wtf8Buf {
+        self.underlying_class.constant_pool[self.name_index].extract_string_from_utf8()
+    }
+}
+
 pub enum ConstantInfoView<'cl> {
     Utf8(Utf8View),
     Integer(IntegerView),
@@ -303,6 +358,9 @@ pub enum ConstantInfoView<'cl> {
     MethodHandle(MethodHandleView<'cl>),
     MethodType(MethodTypeView<'cl>),
     InvokeDynamic(InvokeDynamicView<'cl>),
+    Dynamic(DynamicView<'cl>),
+    Module(ModulePoolElemView<'cl>),
+    Package(PackagePoolElemView<'cl>),
     LiveObject(LiveObjectIndex),
 }
 
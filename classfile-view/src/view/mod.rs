@@ -16,8 +16,8 @@ use rust_jvm_common::compressed_classfile::string_pool::CompressedClassfileStrin
 
 use rust_jvm_common::descriptor_parser::MethodDescriptor;
 
-use crate::view::attribute_view::{BootstrapMethodsView, EnclosingMethodView, InnerClassesView, SourceFileView};
-use crate::view::constant_info_view::{ClassPoolElemView, ConstantInfoView, DoubleView, FieldrefView, FloatView, IntegerView, InterfaceMethodrefView, InvokeDynamicView, LongView, MethodHandleView, MethodrefView, MethodTypeView, NameAndTypeView, StringView, Utf8View};
+use crate::view::attribute_view::{BootstrapMethodsView, EnclosingMethodView, InnerClassesView, ModuleView, PermittedSubclassesView, RecordView, SourceFileView};
+use crate::view::constant_info_view::{ClassPoolElemView, ConstantInfoView, DoubleView, DynamicView, FieldrefView, FloatView, IntegerView, InterfaceMethodrefView, InvokeDynamicView, LongView, MethodHandleView, MethodrefView, MethodTypeView, ModulePoolElemView, NameAndTypeView, PackagePoolElemView, StringView, Utf8View};
 use crate::view::field_view::{FieldIterator, FieldView};
 use crate::view::interface_view::InterfaceIterator;
 use crate::view::method_view::{MethodIterator, MethodView};
@@ -81,7 +81,11 @@ pub trait ClassView: HasAccessFlags {
     fn signature_attr(&self) -> Option<Wtf8Buf>;
     fn enclosing_method_view(&self) -> Option<EnclosingMethodView>;
     fn inner_classes_view(&self) -> Option<InnerClassesView>;
+    fn module_view(&self) -> Option<ModuleView>;
+    fn record_view(&self) -> Option<RecordView>;
+    fn permitted_subclasses_view(&self) -> Option<PermittedSubclassesView>;
     fn annotations(&self) -> Option<Vec<u8>>;
+    fn major_version(&self) -> u16;
 
     fn lookup_field(&self, name: FieldName) -> Option<FieldView>;
 
@@ -148,6 +152,9 @@ impl ClassView for ClassBackedView {
                 bootstrap_method_attr_index: id.bootstrap_method_attr_index,
                 name_and_type_index: id.name_and_type_index,
             }),
+            ConstantKind::Dynamic(_) => ConstantInfoView::Dynamic(DynamicView { class_view: self, i }),
+            ConstantKind::Module(m) => ConstantInfoView::Module(ModulePoolElemView { underlying_class, name_index: m.name_index as usize }),
+            ConstantKind::Package(p) => ConstantInfoView::Package(PackagePoolElemView { underlying_class, name_index: p.name_index as usize }),
             ConstantKind::InvalidConstant(_) => panic!(),
             ConstantKind::LiveObject(idx) => ConstantInfoView::LiveObject(*idx),
         }
@@ -215,6 +222,18 @@ impl ClassView for ClassBackedView {
         }).map(|(i, _)| { InnerClassesView { backing_class: self, i } })
     }
 
+    fn module_view(&self) -> Option<ModuleView> {
+        self.underlying_class.attributes.iter().position(|attr| matches!(attr.attribute_type, AttributeType::Module(_))).map(|i| ModuleView { backing_class: self, i })
+    }
+
+    fn record_view(&self) -> Option<RecordView> {
+        self.underlying_class.attributes.iter().position(|attr| matches!(attr.attribute_type, AttributeType::Record(_))).map(|i| RecordView { backing_class: self, i })
+    }
+
+    fn permitted_subclasses_view(&self) -> Option<PermittedSubclassesView> {
+        self.underlying_class.attributes.iter().position(|attr| matches!(attr.attribute_type, AttributeType::PermittedSubclasses(_))).map(|i| PermittedSubclassesView { backing_class: self, i })
+    }
+
     fn annotations(&self) -> Option<Vec<u8>> {
         self.underlying_class.attributes.iter().find_map(|attr| match &attr.attribute_type {
             AttributeType::RuntimeVisibleAnnotations(RuntimeVisibleAnnotations { annotations }) => Some(runtime_annotations_to_bytes(annotations.clone())),
@@ -222,6 +241,10 @@ impl ClassView for ClassBackedView {
         })
     }
 
+    fn major_version(&self) -> u16 {
+        self.backing_class.major_version
+    }
+
     fn lookup_field(&self, name: FieldName) -> Option<FieldView> {
         self.backing_class.fields.iter().enumerate().filter(|(_, field)| FieldName(field.name) == name).map(|(i, _)| FieldView::from(self, i)).exactly_one().ok()
     }
@@ -357,10 +380,26 @@ impl ClassView for PrimitiveView {
         None
     }
 
+    fn module_view(&self) -> Option<ModuleView> {
+        None
+    }
+
+    fn record_view(&self) -> Option<RecordView> {
+        None
+    }
+
+    fn permitted_subclasses_view(&self) -> Option<PermittedSubclassesView> {
+        None
+    }
+
     fn annotations(&self) -> Option<Vec<u8>> {
         todo!()
     }
 
+    fn major_version(&self) -> u16 {
+        todo!()
+    }
+
     fn lookup_field(&self, _name: FieldName) -> Option<FieldView> {
         todo!()
     }
@@ -462,10 +501,26 @@ impl ClassView for ArrayView {
         None
     }
 
+    fn module_view(&self) -> Option<ModuleView> {
+        None
+    }
+
+    fn record_view(&self) -> Option<RecordView> {
+        None
+    }
+
+    fn permitted_subclasses_view(&self) -> Option<PermittedSubclassesView> {
+        None
+    }
+
     fn annotations(&self) -> Option<Vec<u8>> {
         todo!()
     }
 
+    fn major_version(&self) -> u16 {
+        todo!()
+    }
+
     fn lookup_field(&self, _name: FieldName) -> Option<FieldView> {
         todo!()
     }
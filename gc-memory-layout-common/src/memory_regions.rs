@@ -15,7 +15,7 @@ use array_memory_layout::layout::ArrayMemoryLayout;
 use inheritance_tree::ClassID;
 use inheritance_tree::paths::BitPath256;
 use interface_vtable::ITableRaw;
-use jvmti_jni_bindings::jclass;
+use jvmti_jni_bindings::{jclass, jint};
 use rust_jvm_common::compressed_classfile::compressed_types::CPDType;
 use vtable::RawNativeVTable;
 
@@ -200,9 +200,9 @@ impl MemoryRegions {
     }
 
     pub fn lookup_or_add_type(&mut self, type_: &AllocatedObjectTypeWithSize) -> AllocatedTypeID {
-        let new_id = AllocatedTypeID(self.types_reverse.len() as u64);
+        let new_id = AllocatedTypeID(self.types.len() as u64);
         let object_size = type_.size;
-        assert_eq!(self.types.len(), self.types_reverse.len());
+        assert!(self.types_reverse.len() <= self.types.len());
         match self.types_reverse.get(&type_.allocated_object_type) {
             None => {
                 self.types.push(type_.allocated_object_type.clone());
@@ -212,7 +212,7 @@ impl MemoryRegions {
                 self.type_to_region_datas.push(vec![]);
                 self.current_region_header.push(Arc::new(AtomicPtr::new(null_mut())));
                 self.types_reverse.insert(type_.allocated_object_type.clone(), new_id);
-                assert_eq!(self.types.len(), self.types_reverse.len());
+                assert!(self.types_reverse.len() <= self.types.len());
                 assert!(self.types_reverse.contains_key(&type_.allocated_object_type));
                 assert!(self.types_reverse.len() > 0);
                 new_id
@@ -281,6 +281,36 @@ impl MemoryRegions {
         self.allocate_with_size(to_allocate_type).0
     }
 
+    /// Every object allocated so far with its size. Nothing may allocate while the regions are walked,
+    /// so other threads should be held at a safepoint.
+    pub fn allocated_objects(&self) -> Vec<(NonNull<c_void>, NonZeroUsize)> {
+        let mut res = vec![];
+        for (region, index) in self.type_to_region_datas.iter().flatten() {
+            let region_header_ptr = self.region_header_at(*region, *index, true);
+            let region_header = unsafe { region_header_ptr.as_ref() };
+            let mut current = unsafe { region_header_ptr.as_ptr().add(1) as *mut c_void };
+            let end = region_header.current_ptr.load(Ordering::SeqCst);
+            while current < end {
+                let size = match region_header.region_elem_size {
+                    Some(size) => size,
+                    None => {
+                        let len = unsafe { current.add(region_header.array_len_offset).cast::<jint>().read_unaligned() };
+                        ArrayMemoryLayout::from_cpdtype(region_header.array_elem_type.unwrap()).array_size(len)
+                    }
+                };
+                res.push((NonNull::new(current).unwrap(), size));
+                current = unsafe { current.add(size.get()) };
+            }
+        }
+        res
+    }
+
+    /// Stops allocating into types matching `retire`. Their vtables and itables have been freed, so the
+    /// same pointers could otherwise come back as types of unrelated classes.
+    pub fn retire_types(&mut self, retire: impl Fn(&AllocatedObjectType) -> bool) {
+        self.types_reverse.retain(|allocated_object_type, _| !retire(allocated_object_type));
+    }
+
     #[inline(never)]
     fn region_header_at(&self, region: Region, index: usize, assert: bool) -> NonNull<RegionHeader> {
         let regions_base = self.early_mmaped_regions.base_regions_address(region);
@@ -482,7 +512,7 @@ impl MemoryRegions {
     pub fn find_object_allocated_type(&self, ptr: NonNull<c_void>) -> &AllocatedObjectType {
         let header = MemoryRegions::find_object_region_header(ptr);
         let allocated_type_id = header.region_type;
-        assert_eq!(self.types.len(), self.types_reverse.len());
+        assert!(self.types_reverse.len() <= self.types.len());
         &self.types[allocated_type_id.0 as usize]
     }
 
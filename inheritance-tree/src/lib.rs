@@ -33,6 +33,10 @@ impl InheritanceTree {
     pub fn max_bit_depth(&self) -> usize {
         self.inner.read().unwrap().max_bit_depth()
     }
+
+    pub fn sub_class_ids(&self, class_id_path: &InheritanceClassIDPath) -> Vec<ClassID> {
+        self.inner.read().unwrap().sub_class_ids(class_id_path)
+    }
 }
 
 #[derive(Debug)]
@@ -64,6 +68,15 @@ impl InheritanceTreeInner {
     pub fn max_bit_depth(&self) -> usize {
         self.top_node.max_bit_depth()
     }
+
+    //all classes below class_id_path, not including the class itself
+    pub fn sub_class_ids(&self, class_id_path: &InheritanceClassIDPath) -> Vec<ClassID> {
+        let mut res = vec![];
+        if let Some(node) = self.top_node.lookup_node(class_id_path) {
+            node.all_sub_class_ids(&mut res);
+        }
+        res
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -90,6 +103,10 @@ impl SubClassLocations {
     pub fn insert(&mut self, class_id: ClassID, path: InheritanceTreePath) {
         self.inner.insert(class_id, path.to_owned());
     }
+
+    pub fn iter(&self) -> impl Iterator<Item=(ClassID, InheritanceTreePath<'_>)> + '_ {
+        self.inner.iter().map(|(class_id, path)| (*class_id, InheritanceTreePath::Borrowed { inner: path.as_slice() }))
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -136,6 +153,23 @@ impl InheritanceTreeNode {
         None
     }
 
+    pub fn lookup_node(&self, class_id_path: &InheritanceClassIDPath) -> Option<&InheritanceTreeNode> {
+        let (class_id, rest) = class_id_path.split_1();
+        let path = self.subclass_locations.lookup_class_id_non_recursive(class_id)?;
+        let next_node = self.sub_classes.inheritance_tree_node_at_path_ref(&path);
+        if rest.is_empty() {
+            Some(next_node)
+        } else {
+            next_node.lookup_node(&rest)
+        }
+    }
+
+    pub fn all_sub_class_ids(&self, res: &mut Vec<ClassID>) {
+        for (class_id, path) in self.subclass_locations.iter() {
+            res.push(class_id);
+            self.sub_classes.inheritance_tree_node_at_path_ref(&path).all_sub_class_ids(res);
+        }
+    }
 
     pub fn insert(&mut self, class_id_path: &InheritanceClassIDPath) {
         let (class_id, rest) = class_id_path.split_1();
@@ -66,6 +66,25 @@ pub fn inheritance_tree_build_up() {
     inheritance_tree.top_node.lookup_class_id_path(&a_a_path);
 }
 
+#[test]
+pub fn inheritance_tree_sub_class_ids() {
+    let object_class = ClassID(0);
+    let mut inheritance_tree = InheritanceTreeInner::new(object_class);
+    let class_a = ClassID(1);
+    let class_a_a = ClassID(2);
+    let class_a_a_a = ClassID(3);
+    let class_b = ClassID(4);
+    inheritance_tree.insert(&vec![object_class, class_a].into());
+    inheritance_tree.insert(&vec![object_class, class_a, class_a_a].into());
+    inheritance_tree.insert(&vec![object_class, class_a, class_a_a, class_a_a_a].into());
+    inheritance_tree.insert(&vec![object_class, class_b].into());
+    let mut a_sub_classes = inheritance_tree.sub_class_ids(&vec![object_class, class_a].into());
+    a_sub_classes.sort();
+    assert_eq!(a_sub_classes, vec![class_a_a, class_a_a_a]);
+    assert!(inheritance_tree.sub_class_ids(&vec![object_class, class_b].into()).is_empty());
+    assert!(inheritance_tree.sub_class_ids(&vec![object_class, ClassID(5)].into()).is_empty());
+}
+
 
 #[test]
 pub fn inheritance_tree_bug_0_maybe() {
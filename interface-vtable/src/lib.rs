@@ -267,6 +267,23 @@ impl<'gc> ITables<'gc> {
         )
     }
 
+    /// Frees the itable of an unloaded class, returning it so that nothing is left pointing at it.
+    pub fn unload_class(&mut self, rc: &Arc<RuntimeClass<'gc>>) -> Option<NonNull<ITableRaw>> {
+        let by_address = ByAddress(rc.clone());
+        let itable = self.inner.remove(&by_address)?;
+        self.resolved_to_entry.retain(|_, entries| {
+            entries.retain(|(entry_rc, _, _)| entry_rc != &by_address);
+            !entries.is_empty()
+        });
+        unsafe {
+            let ITableRaw { itable_ptr, len, capacity } = *Box::from_raw(itable.as_ptr());
+            for entry in Vec::from_raw_parts(itable_ptr, len, capacity) {
+                drop(Vec::from_raw_parts(entry.vtable_ptr, entry.vtable_len, entry.vtable_capacity));
+            }
+        }
+        Some(itable)
+    }
+
     pub fn update(&mut self, past_address: InterfaceVTableEntry, new_address: InterfaceVTableEntry) {
         if let Some(entries) = self.resolved_to_entry.remove(&past_address.address.unwrap()) {
             for (rc, interface, method_number) in entries.iter() {
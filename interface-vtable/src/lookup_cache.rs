@@ -41,4 +41,17 @@ impl<'gc> InvokeInterfaceLookupCache<'gc> {
         self.method_id_to_method.entry(resolved.method_id).or_default().insert((ByAddress(rc.clone()), method_name, desc.clone()));
         self.inner.insert((ByAddress(rc), method_name, desc), resolved);
     }
-}
+
+    pub fn unload_class(&mut self, rc: &Arc<RuntimeClass<'gc>>, method_ids: &[MethodId]) {
+        let by_address = ByAddress(rc.clone());
+        for method_id in method_ids {
+            for inner_key in self.method_id_to_method.remove(method_id).into_iter().flatten() {
+                self.inner.remove(&inner_key);
+            }
+        }
+        self.inner.retain(|(key_rc, _, _), _| key_rc != &by_address);
+        for inner_keys in self.method_id_to_method.values_mut() {
+            inner_keys.retain(|(key_rc, _, _)| key_rc != &by_address);
+        }
+    }
+}
\ No newline at end of file
@@ -1,4 +1,5 @@
 use std::{fmt, io};
+use std::collections::HashMap;
 use std::error::Error;
 use std::fmt::Formatter;
 use std::fs::File;
@@ -6,6 +7,7 @@ use std::io::Read;
 use std::path::Path;
 use std::sync::Arc;
 
+use zip::result::ZipError;
 use zip::ZipArchive;
 
 use classfile_parser::parse_class_file;
@@ -38,12 +40,93 @@ impl JarHandle<File> {
         Ok(JarHandle { path, zip_archive })
     }
 
-    pub fn lookup(&mut self, pool: &CompressedClassfileStringPool, class_name: &CClassName) -> Result<Arc<Classfile>, Box<dyn Error>> {
-        let lookup_res = &mut self.zip_archive.by_name(format!("{}.class", class_name.0.to_str(pool)).as_str())?;
+    /// Ok(None) if the jar has no entry for the class, an error if the entry exists but can't be read or parsed.
+    pub fn lookup(&mut self, pool: &CompressedClassfileStringPool, class_name: &CClassName) -> Result<Option<Arc<Classfile>>, Box<dyn Error>> {
+        self.lookup_by_name(class_name.0.to_str(pool).as_str())
+    }
+
+    /// Like lookup, for an internal class name like java/lang/Object.
+    pub fn lookup_by_name(&mut self, class_name: &str) -> Result<Option<Arc<Classfile>>, Box<dyn Error>> {
+        let lookup_res = &mut match self.zip_archive.by_name(format!("{}.class", class_name).as_str()) {
+            Ok(lookup_res) => lookup_res,
+            Err(ZipError::FileNotFound) => return Ok(None),
+            Err(err) => return Err(Box::new(err)),
+        };
         if lookup_res.is_file() {
-            Result::Ok(Arc::new(parse_class_file(lookup_res)?))
+            Result::Ok(Some(Arc::new(parse_class_file(lookup_res)?)))
         } else {
             Result::Err(Box::new(NoClassFoundInJarError {}))
         }
     }
-}
\ No newline at end of file
+
+    pub fn manifest(&mut self) -> Result<Option<Manifest>, Box<dyn Error>> {
+        let mut manifest_file = match self.zip_archive.by_name(MANIFEST_NAME) {
+            Ok(manifest_file) => manifest_file,
+            Err(ZipError::FileNotFound) => return Ok(None),
+            Err(err) => return Err(Box::new(err)),
+        };
+        let mut bytes = vec![];
+        manifest_file.read_to_end(&mut bytes)?;
+        Ok(Some(Manifest::parse(String::from_utf8_lossy(bytes.as_slice()).as_ref())))
+    }
+}
+
+pub const MANIFEST_NAME: &str = "META-INF/MANIFEST.MF";
+
+/// Only the main section of the manifest, per jar file spec the per entry sections come after the first blank line.
+#[derive(Debug, Clone, Default)]
+pub struct Manifest {
+    pub main_attributes: HashMap<String, String>,
+}
+
+impl Manifest {
+    pub fn parse(text: &str) -> Manifest {
+        let mut main_attributes = HashMap::new();
+        let mut current: Option<(String, String)> = None;
+        for line in text.split("\r\n").flat_map(|line| line.split(['\r', '\n'])) {
+            if let Some(continuation) = line.strip_prefix(' ') {
+                if let Some((_, value)) = current.as_mut() {
+                    value.push_str(continuation);
+                }
+                continue;
+            }
+            if let Some((name, value)) = current.take() {
+                main_attributes.insert(name, value);
+            }
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                current = Some((name.trim().to_string(), value.strip_prefix(' ').unwrap_or(value).to_string()));
+            }
+        }
+        if let Some((name, value)) = current.take() {
+            main_attributes.insert(name, value);
+        }
+        Manifest { main_attributes }
+    }
+
+    /// Attribute names are case insensitive.
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.main_attributes.iter().find(|(attribute_name, _)| attribute_name.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str())
+    }
+
+    /// Relative urls, resolved against the directory containing the jar.
+    pub fn class_path(&self) -> Vec<String> {
+        self.attribute("Class-Path").map(|class_path| class_path.split_ascii_whitespace().map(|url| url.to_string()).collect()).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::Manifest;
+
+    const SAMPLE_MANIFEST: &str = "Manifest-Version: 1.0\r\nMain-Class: com.example.Main\r\nClass-Path: lib/first.jar lib/sec\r\n ond.jar\r\n  third.jar\r\n\r\nName: com/example/\r\nClass-Path: ignored.jar\r\n";
+
+    #[test]
+    pub fn test_parse() {
+        let parsed = Manifest::parse(SAMPLE_MANIFEST);
+        assert_eq!(parsed.attribute("main-class"), Some("com.example.Main"));
+        assert_eq!(parsed.class_path(), vec!["lib/first.jar".to_string(), "lib/second.jar".to_string(), "third.jar".to_string()]);
+    }
+}
@@ -1,17 +1,19 @@
 #![feature(box_patterns)]
 
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt::{Display, Formatter};
 
 use itertools::{Either, Itertools};
+use serde::{Deserialize, Serialize};
 
-use classfile_view::view::HasAccessFlags;
+use classfile_view::view::{ClassView, HasAccessFlags};
+use classfile_view::view::constant_info_view::ConstantInfoView;
 use classfile_view::view::method_view::MethodView;
 use rust_jvm_common::{ByteCodeIndex, ByteCodeOffset};
-use rust_jvm_common::classfile::{LookupSwitch, TableSwitch};
+use rust_jvm_common::classfile::{IInc, LookupSwitch, TableSwitch, Wide, WideAload, WideAstore, WideDload, WideDstore, WideFload, WideFstore, WideIload, WideIstore, WideLload, WideLstore, WideRet};
 use rust_jvm_common::compressed_classfile::code::{CInstruction, CompressedExceptionTableElem, CompressedInstructionInfo, CompressedLdc2W, CompressedLdcW};
 use rust_jvm_common::compressed_classfile::compressed_types::{CMethodDescriptor, CPDType};
-
-
+use rust_jvm_common::compressed_classfile::string_pool::CompressedClassfileStringPool;
 use rust_jvm_common::vtype::VType;
 
 pub enum ConstrainedInference {
@@ -19,7 +21,7 @@ pub enum ConstrainedInference {
     BackwardsAssignable(VType),
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub enum SimplifiedVType {
     OneWord,
     TwoWord,
@@ -50,15 +52,60 @@ impl SimplifiedVType {
             SimplifiedVType::Top => None
         }
     }
+
+    fn words(&self) -> usize {
+        match self {
+            SimplifiedVType::OneWord => 1,
+            SimplifiedVType::TwoWord => 2,
+            SimplifiedVType::Top => panic!()
+        }
+    }
 }
 
+/// Code type_infer can't make sense of, which the verifier would have rejected.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum InferenceError {
+    IllegalBranchTarget(ByteCodeOffset),
+    StackUnderflow,
+    MismatchedType { expected: SimplifiedVType, actual: SimplifiedVType },
+    SplitTwoWord,
+    InconsistentStackHeight(usize, usize),
+    MismatchedStackTypes,
+    IllegalLocal(u16),
+    RetOutsideSubroutine(ByteCodeOffset),
+    ReturnFromNonVoid,
+    NotInvokeDynamic(u16),
+    FallsOffEnd,
+}
+
+impl Display for InferenceError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InferenceError::IllegalBranchTarget(offset) => write!(f, "Illegal target of jump or branch: {}", offset.0),
+            InferenceError::StackUnderflow => write!(f, "Operand stack underflow"),
+            InferenceError::MismatchedType { expected, actual } => write!(f, "Expected {:?} on the operand stack, found {:?}", expected, actual),
+            InferenceError::SplitTwoWord => write!(f, "Attempt to split a two word value"),
+            InferenceError::InconsistentStackHeight(current, incoming) => write!(f, "Inconsistent stack height {} != {}", current, incoming),
+            InferenceError::MismatchedStackTypes => write!(f, "Mismatched stack types"),
+            InferenceError::IllegalLocal(i) => write!(f, "Illegal local variable number {}", i),
+            InferenceError::RetOutsideSubroutine(offset) => write!(f, "ret outside of a subroutine at {}", offset.0),
+            InferenceError::ReturnFromNonVoid => write!(f, "Method expects a return value"),
+            InferenceError::NotInvokeDynamic(cp) => write!(f, "Illegal constant pool index {} for invokedynamic", cp),
+            InferenceError::FallsOffEnd => write!(f, "Falling off the end of the code"),
+        }
+    }
+}
+
+/// The frame before each instruction of a method, indexed by ByteCodeIndex. None until the instruction
+/// is found to be reachable.
 pub struct MethodFrames {
-    frames: Vec<Frame>,
+    frames: Vec<Option<Frame>>,
     offset_to_index: HashMap<ByteCodeOffset, ByteCodeIndex>,
+    max_locals: usize,
 }
 
 impl MethodFrames {
-    pub fn new(method: &MethodView) -> MethodFrames {
+    pub fn new(method: &MethodView) -> Result<MethodFrames, InferenceError> {
         let code = method.code_attribute().unwrap();
         let offset_to_index = code.instructions
             .iter()
@@ -66,77 +113,69 @@ impl MethodFrames {
             .enumerate()
             .map(|(i, (offset, _))| (*offset, ByteCodeIndex(i as u16)))
             .collect();
-        let is_static = method.is_static();
         let max_locals = code.max_locals as usize;
         let num_instructs = code.instructions.len();
-        let arg_types = method.desc().arg_types.clone();
-        let mut initial_local_vars = vec![Some(SimplifiedVType::Top); max_locals];
-        let initial_local_var_i = if is_static {
+        let mut initial_local_vars = vec![SimplifiedVType::Top; max_locals];
+        let mut local_var_i = if method.is_static() {
             0
         } else {
-            initial_local_vars[0] = Some(SimplifiedVType::OneWord);
+            initial_local_vars[0] = SimplifiedVType::OneWord;
             1
         };
-        for (i, cpdtype) in arg_types.iter().enumerate() {
+        for cpdtype in method.desc().arg_types.iter() {
             let v_type = cpdtype_to_simplified_vtype(cpdtype);
-            initial_local_vars[initial_local_var_i + i] = Some(v_type);
+            if local_var_i + v_type.words() > max_locals {
+                return Err(InferenceError::IllegalLocal(local_var_i as u16));
+            }
+            initial_local_vars[local_var_i] = v_type;
+            local_var_i += v_type.words();
         }
-        let start_frame = Frame {
+        let mut frames = vec![None; num_instructs];
+        frames[0] = Some(Frame {
             local_vars: initial_local_vars,
-            operand_stack: Some(vec![]),
-        };
-        let mut all_frames = (0..num_instructs).map(|_| Frame {
-            local_vars: vec![None; max_locals],
-            operand_stack: None,
-        }).collect_vec();
-        all_frames[0] = start_frame;
-        let mut res = MethodFrames {
-            frames: all_frames,
+            operand_stack: vec![],
+        });
+        Ok(MethodFrames {
+            frames,
             offset_to_index,
-        };
-        for exception_table_entry in code.exception_table.iter() {
-            res.apply_exception_table(exception_table_entry);
-        }
-        res
+            max_locals,
+        })
     }
 
-    pub fn apply_exception_table(&mut self, exception_table_elem: &CompressedExceptionTableElem) {
-        let CompressedExceptionTableElem {
-            start_pc: _,
-            end_pc: _,
-            handler_pc,
-            catch_type: _
-        } = exception_table_elem;
-        let handler = *handler_pc;
-        let handle_frame = self.nth_frame_mut(handler);
-        handle_frame.assert_operand_stack_is(vec![Some(SimplifiedVType::OneWord)]);
+    fn index_of(&self, offset: ByteCodeOffset) -> Result<ByteCodeIndex, InferenceError> {
+        self.offset_to_index.get(&offset).copied().ok_or(InferenceError::IllegalBranchTarget(offset))
     }
 
-    pub fn nth_frame_mut(&mut self, offset: ByteCodeOffset) -> &mut Frame {
-        self.nth_frame_and_next_mut_offset(offset).0
-    }
-
-    pub fn nth_frame_and_next_mut_offset(&mut self, offset: ByteCodeOffset) -> (&mut Frame, Option<&mut Frame>) {
-        let index = *self.offset_to_index.get(&offset).unwrap();
-        self.nth_frame_and_next_mut(index)
-    }
-
-    pub fn nth_frame_and_next_mut(&mut self, n: ByteCodeIndex) -> (&mut Frame, Option<&mut Frame>) {
-        let (below, above) = self.frames.split_at_mut((n.0 + 1) as usize);
-        (below.last_mut().unwrap(), above.first_mut())
+    /// Merges a frame flowing into the instruction at index, returning true if that changed its frame.
+    fn merge_into(&mut self, index: ByteCodeIndex, incoming: &Frame) -> Result<bool, InferenceError> {
+        match self.frames[index.0 as usize].as_mut() {
+            None => {
+                self.frames[index.0 as usize] = Some(incoming.clone());
+                Ok(true)
+            }
+            Some(frame) => frame.merge(incoming),
+        }
     }
 
     pub fn inferred_frames(&self) -> HashMap<ByteCodeOffset, InferredFrame> {
         let mut res = HashMap::new();
         let index_to_offset = self.offset_to_index.iter().map(|(offset, index)| (*index, *offset)).collect::<HashMap<_, _>>();
         for (i, frame) in self.frames.iter().enumerate() {
-            res.insert(*index_to_offset.get(&ByteCodeIndex(i as u16)).unwrap(), frame.to_inferred_frame());
+            let inferred_frame = match frame {
+                Some(frame) => frame.to_inferred_frame(),
+                //unreachable code, nothing is live
+                None => InferredFrame {
+                    local_vars: vec![SimplifiedVType::Top; self.max_locals],
+                    operand_stack: vec![],
+                },
+            };
+            res.insert(*index_to_offset.get(&ByteCodeIndex(i as u16)).unwrap(), inferred_frame);
         }
         res
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct InferredFrame {
     pub local_vars: Vec<SimplifiedVType>,
     pub operand_stack: Vec<SimplifiedVType>,
@@ -155,70 +194,86 @@ impl InferredFrame {
     }
 }
 
-#[derive(Clone)]
+/// Locals are indexed by slot, with the slot after a TwoWord being Top. The operand stack has one entry
+/// per value, so a TwoWord is a single entry.
+#[derive(Clone, Eq, PartialEq, Debug)]
 pub struct Frame {
-    local_vars: Vec<Option<SimplifiedVType>>,
-    pub operand_stack: Option<Vec<Option<SimplifiedVType>>>,
+    local_vars: Vec<SimplifiedVType>,
+    pub operand_stack: Vec<SimplifiedVType>,
 }
 
 impl Frame {
-    pub fn assert_local_is(&mut self, i: u16, vtype: SimplifiedVType) {
-        match self.local_vars[i as usize].as_ref() {
-            Some(existing) => {
-                assert_eq!(*existing, vtype)
-            }
-            None => {
-                self.local_vars[i as usize] = Some(vtype);
-            }
-        };
+    pub fn push(&mut self, vtype: SimplifiedVType) {
+        self.operand_stack.push(vtype);
     }
 
-    pub fn assert_operand_stack_is(&mut self, mut operand_stack: Vec<Option<SimplifiedVType>>) {
-        if let Some(current_operand_stack) = self.operand_stack.as_mut() {
-            if current_operand_stack.len() != operand_stack.len() {
-                panic!()
-            }
-            current_operand_stack.iter_mut().zip(operand_stack.iter_mut()).for_each(|(current, expected)| {
-                match current {
-                    None => {
-                        if let Some(expected) = expected {
-                            *current = Some(*expected);
-                        }
-                    }
-                    Some(current) => {
-                        if let Some(expected) = expected {
-                            if current != expected {
-                                panic!()
-                            }
-                        }
-                    }
-                }
-            })
-        } else {
-            self.operand_stack = Some(operand_stack)
+    pub fn pop(&mut self, vtype: SimplifiedVType) -> Result<(), InferenceError> {
+        let popped = self.operand_stack.pop().ok_or(InferenceError::StackUnderflow)?;
+        if popped != vtype {
+            return Err(InferenceError::MismatchedType { expected: vtype, actual: popped });
         }
+        Ok(())
     }
 
-    pub fn assert_operand_stack_entry_is(&mut self, from_end: u16, vtype: SimplifiedVType) {
-        let operand_stack = self.operand_stack.as_mut().unwrap();
-        match operand_stack.iter().rev().nth(from_end as usize).unwrap() {
-            None => {
-                *operand_stack.iter_mut().rev().nth(from_end as usize).unwrap() = Some(vtype);
-            }
-            Some(existing) => {
-                assert_eq!(*existing, vtype)
+    /// Pops values making up exactly words words, returning them bottom first.
+    fn pop_words(&mut self, words: usize) -> Result<Vec<SimplifiedVType>, InferenceError> {
+        let mut res = vec![];
+        let mut popped_words = 0;
+        while popped_words < words {
+            let popped = self.operand_stack.pop().ok_or(InferenceError::StackUnderflow)?;
+            popped_words += popped.words();
+            res.push(popped);
+        }
+        if popped_words != words {
+            return Err(InferenceError::SplitTwoWord);
+        }
+        res.reverse();
+        Ok(res)
+    }
+
+    pub fn store_local(&mut self, i: u16, vtype: SimplifiedVType) -> Result<(), InferenceError> {
+        let local = i as usize;
+        if local + vtype.words() > self.local_vars.len() {
+            return Err(InferenceError::IllegalLocal(i));
+        }
+        if local > 0 && self.local_vars[local - 1].is_two_word() {
+            self.local_vars[local - 1] = SimplifiedVType::Top;
+        }
+        self.local_vars[local] = vtype;
+        if vtype.is_two_word() {
+            self.local_vars[local + 1] = SimplifiedVType::Top;
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, incoming: &Frame) -> Result<bool, InferenceError> {
+        if self.operand_stack.len() != incoming.operand_stack.len() {
+            return Err(InferenceError::InconsistentStackHeight(self.operand_stack.len(), incoming.operand_stack.len()));
+        }
+        if self.operand_stack != incoming.operand_stack {
+            return Err(InferenceError::MismatchedStackTypes);
+        }
+        let mut changed = false;
+        for (current, incoming) in self.local_vars.iter_mut().zip(incoming.local_vars.iter()) {
+            if current != incoming && *current != SimplifiedVType::Top {
+                *current = SimplifiedVType::Top;
+                changed = true;
             }
         }
+        Ok(changed)
     }
 
-    pub fn operand_stack(&self) -> Vec<Option<SimplifiedVType>> {
-        self.operand_stack.as_ref().unwrap().clone()
+    fn exception_frame(&self) -> Frame {
+        Frame {
+            local_vars: self.local_vars.clone(),
+            operand_stack: vec![SimplifiedVType::OneWord],
+        }
     }
 
     pub fn to_inferred_frame(&self) -> InferredFrame {
         InferredFrame {
-            local_vars: self.local_vars.iter().map(|local_var| local_var.unwrap_or(SimplifiedVType::Top)).collect(),
-            operand_stack: self.operand_stack.as_ref().unwrap().iter().map(|svtype| svtype.unwrap()).collect(),
+            local_vars: self.local_vars.clone(),
+            operand_stack: self.operand_stack.clone(),
         }
     }
 }
@@ -241,1010 +296,1071 @@ fn cpdtype_to_simplified_vtype(cpdtype: &CPDType) -> SimplifiedVType {
     }
 }
 
-pub fn type_infer(method_view: &MethodView) -> MethodFrames {
-    let mut method_frames = MethodFrames::new(method_view);
+/// Where control can go after an instruction, ignoring exceptions.
+enum Flow {
+    Next,
+    Branch(Vec<ByteCodeOffset>),
+    BranchOrNext(ByteCodeOffset),
+    Exit,
+    Jsr(ByteCodeOffset),
+    Ret,
+}
+
+fn branch_target(current_offset: ByteCodeOffset, offset: i32) -> ByteCodeOffset {
+    ByteCodeOffset((current_offset.0 as i32 + offset) as u16)
+}
+
+fn flow(instruct: &CInstruction) -> Flow {
+    let current_offset = instruct.offset;
+    match &instruct.info {
+        CompressedInstructionInfo::goto_(offset) => Flow::Branch(vec![branch_target(current_offset, *offset as i32)]),
+        CompressedInstructionInfo::goto_w(offset) => Flow::Branch(vec![branch_target(current_offset, *offset)]),
+        CompressedInstructionInfo::if_acmpeq(offset) |
+        CompressedInstructionInfo::if_acmpne(offset) |
+        CompressedInstructionInfo::if_icmpeq(offset) |
+        CompressedInstructionInfo::if_icmpne(offset) |
+        CompressedInstructionInfo::if_icmplt(offset) |
+        CompressedInstructionInfo::if_icmpge(offset) |
+        CompressedInstructionInfo::if_icmpgt(offset) |
+        CompressedInstructionInfo::if_icmple(offset) |
+        CompressedInstructionInfo::ifeq(offset) |
+        CompressedInstructionInfo::ifne(offset) |
+        CompressedInstructionInfo::iflt(offset) |
+        CompressedInstructionInfo::ifge(offset) |
+        CompressedInstructionInfo::ifgt(offset) |
+        CompressedInstructionInfo::ifle(offset) |
+        CompressedInstructionInfo::ifnonnull(offset) |
+        CompressedInstructionInfo::ifnull(offset) => Flow::BranchOrNext(branch_target(current_offset, *offset as i32)),
+        CompressedInstructionInfo::lookupswitch(LookupSwitch { pairs, default }) => {
+            Flow::Branch(pairs.iter().map(|(_, offset)| *offset).chain(std::iter::once(*default)).map(|offset| branch_target(current_offset, offset)).collect())
+        }
+        CompressedInstructionInfo::tableswitch(box TableSwitch { default, low: _, high: _, offsets }) => {
+            Flow::Branch(offsets.iter().chain(std::iter::once(default)).map(|offset| branch_target(current_offset, *offset)).collect())
+        }
+        CompressedInstructionInfo::jsr(offset) => Flow::Jsr(branch_target(current_offset, *offset as i32)),
+        CompressedInstructionInfo::jsr_w(offset) => Flow::Jsr(branch_target(current_offset, *offset)),
+        CompressedInstructionInfo::ret(_) => Flow::Ret,
+        CompressedInstructionInfo::wide(Wide::Ret(WideRet { .. })) => Flow::Ret,
+        CompressedInstructionInfo::areturn |
+        CompressedInstructionInfo::dreturn |
+        CompressedInstructionInfo::freturn |
+        CompressedInstructionInfo::ireturn |
+        CompressedInstructionInfo::lreturn |
+        CompressedInstructionInfo::return_ |
+        CompressedInstructionInfo::athrow => Flow::Exit,
+        _ => Flow::Next,
+    }
+}
+
+fn next_offset(instruct: &CInstruction) -> ByteCodeOffset {
+    ByteCodeOffset(instruct.offset.0 + instruct.instruction_size)
+}
+
+fn handlers_for(exception_table: &[CompressedExceptionTableElem], offset: ByteCodeOffset) -> impl Iterator<Item=ByteCodeOffset> + '_ {
+    exception_table.iter()
+        .filter(move |CompressedExceptionTableElem { start_pc, end_pc, .. }| *start_pc <= offset && offset < *end_pc)
+        .map(|elem| elem.handler_pc)
+}
+
+/// The jsr/ret subroutines of a method. A subroutine is identified by its jsr target, and consists of the
+/// instructions reachable from it without following nested jsrs or rets.
+struct Subroutines {
+    rets: HashMap<ByteCodeOffset, Vec<ByteCodeIndex>>,
+    callers: HashMap<ByteCodeOffset, Vec<ByteCodeOffset>>,
+    modified_locals: HashMap<ByteCodeOffset, HashSet<u16>>,
+    ret_to_subroutine: HashMap<ByteCodeIndex, ByteCodeOffset>,
+}
+
+impl Subroutines {
+    fn new(method_frames: &MethodFrames, instructions: &[&CInstruction], exception_table: &[CompressedExceptionTableElem]) -> Result<Subroutines, InferenceError> {
+        let mut callers: HashMap<ByteCodeOffset, Vec<ByteCodeOffset>> = HashMap::new();
+        for instruct in instructions {
+            if let Flow::Jsr(target) = flow(instruct) {
+                callers.entry(target).or_default().push(instruct.offset);
+            }
+        }
+        let mut rets: HashMap<ByteCodeOffset, Vec<ByteCodeIndex>> = HashMap::new();
+        let mut modified_locals: HashMap<ByteCodeOffset, HashSet<u16>> = HashMap::new();
+        let mut nested: HashMap<ByteCodeOffset, Vec<ByteCodeOffset>> = HashMap::new();
+        let mut ret_to_subroutine = HashMap::new();
+        for subroutine in callers.keys() {
+            let mut visited = HashSet::new();
+            let mut to_visit = vec![method_frames.index_of(*subroutine)?];
+            while let Some(index) = to_visit.pop() {
+                if !visited.insert(index) {
+                    continue;
+                }
+                let instruct = instructions[index.0 as usize];
+                if let Some(local) = stored_local(instruct) {
+                    modified_locals.entry(*subroutine).or_default().insert(local);
+                }
+                let mut successors = handlers_for(exception_table, instruct.offset).collect_vec();
+                match flow(instruct) {
+                    Flow::Next => successors.push(next_offset(instruct)),
+                    Flow::Branch(targets) => successors.extend(targets),
+                    Flow::BranchOrNext(target) => successors.extend(vec![target, next_offset(instruct)]),
+                    Flow::Exit => {}
+                    Flow::Jsr(target) => {
+                        nested.entry(*subroutine).or_default().push(target);
+                        successors.push(next_offset(instruct));
+                    }
+                    Flow::Ret => {
+                        rets.entry(*subroutine).or_default().push(index);
+                        ret_to_subroutine.insert(index, *subroutine);
+                    }
+                }
+                for offset in successors {
+                    to_visit.push(method_frames.index_of(offset)?);
+                }
+            }
+        }
+        //a subroutine also modifies whatever the subroutines it calls modify
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (subroutine, nested_subroutines) in nested.iter() {
+                for nested_subroutine in nested_subroutines {
+                    let nested_modified = modified_locals.get(nested_subroutine).cloned().unwrap_or_default();
+                    let modified = modified_locals.entry(*subroutine).or_default();
+                    for local in nested_modified {
+                        changed |= modified.insert(local);
+                    }
+                }
+            }
+        }
+        Ok(Subroutines { rets, callers, modified_locals, ret_to_subroutine })
+    }
+
+    /// The frame after the jsr at jsr_frame's instruction, once its subroutine returns with ret_frame.
+    fn return_frame(&self, subroutine: ByteCodeOffset, jsr_frame: &Frame, ret_frame: &Frame) -> Frame {
+        let modified = self.modified_locals.get(&subroutine);
+        let local_vars = jsr_frame.local_vars.iter().zip(ret_frame.local_vars.iter()).enumerate().map(|(i, (jsr_local, ret_local))| {
+            if modified.map(|modified| modified.contains(&(i as u16))).unwrap_or(false) {
+                *ret_local
+            } else {
+                *jsr_local
+            }
+        }).collect();
+        Frame {
+            local_vars,
+            operand_stack: ret_frame.operand_stack.clone(),
+        }
+    }
+}
+
+fn stored_local(instruct: &CInstruction) -> Option<u16> {
+    Some(match &instruct.info {
+        CompressedInstructionInfo::astore(n) |
+        CompressedInstructionInfo::dstore(n) |
+        CompressedInstructionInfo::fstore(n) |
+        CompressedInstructionInfo::istore(n) |
+        CompressedInstructionInfo::lstore(n) => *n as u16,
+        CompressedInstructionInfo::astore_0 |
+        CompressedInstructionInfo::dstore_0 |
+        CompressedInstructionInfo::fstore_0 |
+        CompressedInstructionInfo::istore_0 |
+        CompressedInstructionInfo::lstore_0 => 0,
+        CompressedInstructionInfo::astore_1 |
+        CompressedInstructionInfo::dstore_1 |
+        CompressedInstructionInfo::fstore_1 |
+        CompressedInstructionInfo::istore_1 |
+        CompressedInstructionInfo::lstore_1 => 1,
+        CompressedInstructionInfo::astore_2 |
+        CompressedInstructionInfo::dstore_2 |
+        CompressedInstructionInfo::fstore_2 |
+        CompressedInstructionInfo::istore_2 |
+        CompressedInstructionInfo::lstore_2 => 2,
+        CompressedInstructionInfo::astore_3 |
+        CompressedInstructionInfo::dstore_3 |
+        CompressedInstructionInfo::fstore_3 |
+        CompressedInstructionInfo::istore_3 |
+        CompressedInstructionInfo::lstore_3 => 3,
+        CompressedInstructionInfo::wide(Wide::Astore(WideAstore { index })) |
+        CompressedInstructionInfo::wide(Wide::Dstore(WideDstore { index })) |
+        CompressedInstructionInfo::wide(Wide::Fstore(WideFstore { index })) |
+        CompressedInstructionInfo::wide(Wide::Istore(WideIstore { index })) |
+        CompressedInstructionInfo::wide(Wide::Lstore(WideLstore { index })) => *index,
+        _ => return None,
+    })
+}
+
+/// Infers the frame before every instruction of a method, for classes without stack map frames. This is a
+/// dataflow pass over the method, including exception handler and jsr/ret edges, in which locals that disagree
+/// become Top. It assumes code the verifier would accept, and fails on code it can't make sense of.
+pub fn type_infer(method_view: &MethodView, pool: &CompressedClassfileStringPool) -> Result<MethodFrames, InferenceError> {
+    let mut method_frames = MethodFrames::new(method_view)?;
     let code = method_view.code_attribute().unwrap();
     let return_type = method_view.desc().return_type;
     let instructions = code.instructions.iter().sorted_by_key(|(offset, _)| **offset)
-        .map(|(offset, instr)| (*offset, instr)).collect_vec();//todo do this a lot dup
-    for (i, (_offset, instruct)) in instructions.iter().enumerate() {
-        infer_single_instruct(&mut method_frames, return_type, instruct, i);
+        .map(|(_offset, instr)| instr).collect_vec();
+    let subroutines = Subroutines::new(&method_frames, instructions.as_slice(), code.exception_table.as_slice())?;
+    let mut changed = BTreeSet::new();
+    changed.insert(ByteCodeIndex(0));
+    while let Some(current_index) = changed.iter().next().cloned() {
+        changed.remove(&current_index);
+        let instruct = instructions[current_index.0 as usize];
+        let frame = method_frames.frames[current_index.0 as usize].clone().unwrap();
+        let mut next_frame = frame.clone();
+        infer_single_instruct(&mut next_frame, method_view, pool, return_type, instruct)?;
+        let mut successors = vec![];
+        for handler in handlers_for(code.exception_table.as_slice(), instruct.offset) {
+            successors.push((handler, frame.exception_frame()));
+            successors.push((handler, next_frame.exception_frame()));
+        }
+        match flow(instruct) {
+            Flow::Next => {
+                successors.push((next_offset(instruct), next_frame));
+            }
+            Flow::Branch(targets) => {
+                successors.extend(targets.into_iter().map(|target| (target, next_frame.clone())));
+            }
+            Flow::BranchOrNext(target) => {
+                successors.push((target, next_frame.clone()));
+                successors.push((next_offset(instruct), next_frame));
+            }
+            Flow::Exit => {}
+            Flow::Jsr(target) => {
+                successors.push((target, next_frame));
+                //the frames after this subroutine's rets depend on this jsr's frame
+                for ret_index in subroutines.rets.get(&target).into_iter().flatten() {
+                    if method_frames.frames[ret_index.0 as usize].is_some() {
+                        changed.insert(*ret_index);
+                    }
+                }
+            }
+            Flow::Ret => {
+                let subroutine = *subroutines.ret_to_subroutine.get(&current_index).ok_or(InferenceError::RetOutsideSubroutine(instruct.offset))?;
+                for jsr_offset in subroutines.callers.get(&subroutine).into_iter().flatten() {
+                    let jsr_index = method_frames.index_of(*jsr_offset)?;
+                    if let Some(jsr_frame) = method_frames.frames[jsr_index.0 as usize].as_ref() {
+                        let return_frame = subroutines.return_frame(subroutine, jsr_frame, &next_frame);
+                        successors.push((next_offset(instructions[jsr_index.0 as usize]), return_frame));
+                    }
+                }
+            }
+        }
+        for (offset, successor_frame) in successors {
+            if !method_frames.offset_to_index.contains_key(&offset) && offset == next_offset(instructions.last().unwrap()) {
+                return Err(InferenceError::FallsOffEnd);
+            }
+            let index = method_frames.index_of(offset)?;
+            if method_frames.merge_into(index, &successor_frame)? {
+                changed.insert(index);
+            }
+        }
     }
-    method_frames
+    Ok(method_frames)
 }
 
-fn infer_single_instruct(method_frames: &mut MethodFrames, return_type: CPDType, instruct: &CInstruction, i: usize) {
-    let current_offset = instruct.offset;
-    let current_index = ByteCodeIndex(i as u16);
-    assert_eq!(*method_frames.offset_to_index.get(&current_offset).unwrap(), current_index);
+/// Applies the operand stack and local variable effects of an instruction to frame.
+fn infer_single_instruct(frame: &mut Frame, method_view: &MethodView, pool: &CompressedClassfileStringPool, return_type: CPDType, instruct: &CInstruction) -> Result<(), InferenceError> {
     match &instruct.info {
         CompressedInstructionInfo::aaload => {
-            two_one_word_in_one_word_out(method_frames, current_index);
+            two_one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::aastore => {
-            three_one_word_in_zero_out(method_frames, current_index);
+            three_one_word_in_zero_out(frame)?;
         }
         CompressedInstructionInfo::aconst_null => {
-            one_word_const(method_frames, current_index);
+            one_word_const(frame);
         }
-        CompressedInstructionInfo::aload(n) => {
-            one_word_variable_load(method_frames, *n, current_index);
+        CompressedInstructionInfo::aload(_) => {
+            one_word_variable_load(frame);
         }
         CompressedInstructionInfo::aload_0 => {
-            one_word_variable_load(method_frames, 0, current_index);
+            one_word_variable_load(frame);
         }
         CompressedInstructionInfo::aload_1 => {
-            one_word_variable_load(method_frames, 1, current_index);
+            one_word_variable_load(frame);
         }
         CompressedInstructionInfo::aload_2 => {
-            one_word_variable_load(method_frames, 2, current_index);
+            one_word_variable_load(frame);
         }
         CompressedInstructionInfo::aload_3 => {
-            one_word_variable_load(method_frames, 3, current_index);
+            one_word_variable_load(frame);
         }
         CompressedInstructionInfo::anewarray(_) => {
-            one_word_in_one_word_out(method_frames, current_index);
+            one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::areturn => {
-            top_operand_is_one_word_and_exit(method_frames, current_offset);
+            one_word_in_zero_out(frame)?;
         }
         CompressedInstructionInfo::arraylength => {
-            one_word_in_one_word_out(method_frames, current_index);
+            one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::astore(n) => {
-            one_word_variable_store(method_frames, *n, current_index);
+            one_word_variable_store(frame, *n as u16)?;
         }
         CompressedInstructionInfo::astore_0 => {
-            one_word_variable_store(method_frames, 0, current_index);
+            one_word_variable_store(frame, 0)?;
         }
         CompressedInstructionInfo::astore_1 => {
-            one_word_variable_store(method_frames, 1, current_index);
+            one_word_variable_store(frame, 1)?;
         }
         CompressedInstructionInfo::astore_2 => {
-            one_word_variable_store(method_frames, 2, current_index);
+            one_word_variable_store(frame, 2)?;
         }
         CompressedInstructionInfo::astore_3 => {
-            one_word_variable_store(method_frames, 3, current_index);
+            one_word_variable_store(frame, 3)?;
         }
         CompressedInstructionInfo::athrow => {
-            top_operand_is_one_word_and_exit(method_frames, current_offset);
+            one_word_in_zero_out(frame)?;
         }
         CompressedInstructionInfo::baload => {
-            two_one_word_in_one_word_out(method_frames, current_index);
+            two_one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::bastore => {
-            three_one_word_in_zero_out(method_frames, current_index);
+            three_one_word_in_zero_out(frame)?;
         }
         CompressedInstructionInfo::bipush(_) => {
-            one_word_const(method_frames, current_index);
+            one_word_const(frame);
         }
         CompressedInstructionInfo::caload => {
-            two_one_word_in_one_word_out(method_frames, current_index);
+            two_one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::castore => {
-            three_one_word_in_zero_out(method_frames, current_index);
+            three_one_word_in_zero_out(frame)?;
         }
         CompressedInstructionInfo::checkcast(_) => {
-            one_word_in_one_word_out(method_frames, current_index)
+            one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::d2f => {
-            one_two_word_in_one_word_out(method_frames, current_index)
+            one_two_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::d2i => {
-            one_two_word_in_one_word_out(method_frames, current_index)
+            one_two_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::d2l => {
-            two_word_in_two_word_out(method_frames, current_index);
+            two_word_in_two_word_out(frame)?;
         }
         CompressedInstructionInfo::dadd => {
-            todo!()
+            two_two_word_in_two_word_out(frame)?;
         }
         CompressedInstructionInfo::daload => {
-            todo!()
+            two_one_word_in_two_word_out(frame)?;
         }
         CompressedInstructionInfo::dastore => {
-            todo!()
+            two_one_word_and_two_word_in_zero_out(frame)?;
         }
         CompressedInstructionInfo::dcmpg => {
-            two_two_word_in_one_word_out(method_frames, current_index)
+            two_two_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::dcmpl => {
-            two_two_word_in_one_word_out(method_frames, current_index)
+            two_two_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::dconst_0 => {
-            two_word_const(method_frames, current_index)
+            two_word_const(frame);
         }
         CompressedInstructionInfo::dconst_1 => {
-            two_word_const(method_frames, current_index)
+            two_word_const(frame);
         }
         CompressedInstructionInfo::ddiv => {
-            todo!()
+            two_two_word_in_two_word_out(frame)?;
         }
-        CompressedInstructionInfo::dload(n) => {
-            two_word_variable_load(method_frames, *n, current_index);
+        CompressedInstructionInfo::dload(_) => {
+            two_word_variable_load(frame);
         }
         CompressedInstructionInfo::dload_0 => {
-            two_word_variable_load(method_frames, 0, current_index);
+            two_word_variable_load(frame);
         }
         CompressedInstructionInfo::dload_1 => {
-            two_word_variable_load(method_frames, 1, current_index);
+            two_word_variable_load(frame);
         }
         CompressedInstructionInfo::dload_2 => {
-            two_word_variable_load(method_frames, 2, current_index);
+            two_word_variable_load(frame);
         }
         CompressedInstructionInfo::dload_3 => {
-            two_word_variable_load(method_frames, 3, current_index);
+            two_word_variable_load(frame);
         }
         CompressedInstructionInfo::dmul => {
-            two_two_word_in_two_word_out(method_frames, current_index);
+            two_two_word_in_two_word_out(frame)?;
         }
         CompressedInstructionInfo::dneg => {
-            two_word_in_two_word_out(method_frames, current_index);
+            two_word_in_two_word_out(frame)?;
         }
         CompressedInstructionInfo::drem => {
-            todo!()
+            two_two_word_in_two_word_out(frame)?;
         }
         CompressedInstructionInfo::dreturn => {
-            top_operand_is_two_word_and_exit(method_frames, current_offset);
+            two_word_in_zero_out(frame)?;
         }
         CompressedInstructionInfo::dstore(n) => {
-            two_word_variable_store(method_frames, *n, current_index)
+            two_word_variable_store(frame, *n as u16)?;
         }
         CompressedInstructionInfo::dstore_0 => {
-            two_word_variable_store(method_frames, 0, current_index)
+            two_word_variable_store(frame, 0)?;
         }
         CompressedInstructionInfo::dstore_1 => {
-            two_word_variable_store(method_frames, 1, current_index)
+            two_word_variable_store(frame, 1)?;
         }
         CompressedInstructionInfo::dstore_2 => {
-            two_word_variable_store(method_frames, 2, current_index)
+            two_word_variable_store(frame, 2)?;
         }
         CompressedInstructionInfo::dstore_3 => {
-            two_word_variable_store(method_frames, 3, current_index)
+            two_word_variable_store(frame, 3)?;
         }
         CompressedInstructionInfo::dsub => {
-            todo!()
+            two_two_word_in_two_word_out(frame)?;
         }
         CompressedInstructionInfo::dup => {
-            let (current_frame, mut next_frame) = method_frames.nth_frame_and_next_mut(current_index);
-            current_frame.assert_operand_stack_entry_is(0, SimplifiedVType::OneWord);
-            let mut operand_stack = current_frame.operand_stack();
-            let _ = operand_stack.pop().unwrap().unwrap();
-            operand_stack.push(Some(SimplifiedVType::OneWord));
-            operand_stack.push(Some(SimplifiedVType::OneWord));
-            next_frame.as_mut().unwrap().assert_operand_stack_is(operand_stack);
+            dup_words(frame, 1, 0)?;
         }
         CompressedInstructionInfo::dup_x1 => {
-            let (current_frame, mut next_frame) = method_frames.nth_frame_and_next_mut(current_index);
-            current_frame.assert_operand_stack_entry_is(0, SimplifiedVType::OneWord);
-            current_frame.assert_operand_stack_entry_is(1, SimplifiedVType::OneWord);
-            let mut operand_stack = current_frame.operand_stack();
-            operand_stack.pop().unwrap().unwrap();
-            operand_stack.pop().unwrap().unwrap();
-            operand_stack.push(Some(SimplifiedVType::OneWord));
-            operand_stack.push(Some(SimplifiedVType::OneWord));
-            operand_stack.push(Some(SimplifiedVType::OneWord));
-            next_frame.as_mut().unwrap().assert_operand_stack_is(operand_stack);
+            dup_words(frame, 1, 1)?;
         }
         CompressedInstructionInfo::dup_x2 => {
-            todo!()
+            dup_words(frame, 1, 2)?;
         }
         CompressedInstructionInfo::dup2 => {
-            todo!()
+            dup_words(frame, 2, 0)?;
         }
         CompressedInstructionInfo::dup2_x1 => {
-            todo!()
+            dup_words(frame, 2, 1)?;
         }
         CompressedInstructionInfo::dup2_x2 => {
-            todo!()
+            dup_words(frame, 2, 2)?;
         }
         CompressedInstructionInfo::f2d => {
-            one_word_in_two_word_out(method_frames, current_index)
+            one_word_in_two_word_out(frame)?;
         }
         CompressedInstructionInfo::f2i => {
-            one_word_in_one_word_out(method_frames, current_index)
+            one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::f2l => {
-            one_word_in_two_word_out(method_frames, current_index)
+            one_word_in_two_word_out(frame)?;
         }
         CompressedInstructionInfo::fadd => {
-            two_one_word_in_one_word_out(method_frames, current_index);
+            two_one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::faload => {
-            two_one_word_in_one_word_out(method_frames, current_index);
+            two_one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::fastore => {
-            three_one_word_in_zero_out(method_frames, current_index);
+            three_one_word_in_zero_out(frame)?;
         }
         CompressedInstructionInfo::fcmpg => {
-            two_one_word_in_one_word_out(method_frames, current_index);
+            two_one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::fcmpl => {
-            two_one_word_in_one_word_out(method_frames, current_index);
+            two_one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::fconst_0 => {
-            one_word_const(method_frames, current_index);
+            one_word_const(frame);
         }
         CompressedInstructionInfo::fconst_1 => {
-            one_word_const(method_frames, current_index);
+            one_word_const(frame);
         }
         CompressedInstructionInfo::fconst_2 => {
-            one_word_const(method_frames, current_index);
+            one_word_const(frame);
         }
         CompressedInstructionInfo::fdiv => {
-            two_one_word_in_one_word_out(method_frames, current_index);
+            two_one_word_in_one_word_out(frame)?;
         }
-        CompressedInstructionInfo::fload(n) => {
-            one_word_variable_load(method_frames, *n, current_index);
+        CompressedInstructionInfo::fload(_) => {
+            one_word_variable_load(frame);
         }
         CompressedInstructionInfo::fload_0 => {
-            one_word_variable_load(method_frames, 0, current_index);
+            one_word_variable_load(frame);
         }
         CompressedInstructionInfo::fload_1 => {
-            one_word_variable_load(method_frames, 1, current_index);
+            one_word_variable_load(frame);
         }
         CompressedInstructionInfo::fload_2 => {
-            one_word_variable_load(method_frames, 2, current_index);
+            one_word_variable_load(frame);
         }
         CompressedInstructionInfo::fload_3 => {
-            one_word_variable_load(method_frames, 3, current_index);
+            one_word_variable_load(frame);
         }
         CompressedInstructionInfo::fmul => {
-            two_one_word_in_one_word_out(method_frames, current_index);
+            two_one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::fneg => {
-            one_word_in_one_word_out(method_frames, current_index)
+            one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::frem => {
-            two_one_word_in_one_word_out(method_frames, current_index);
+            two_one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::freturn => {
-            top_operand_is_one_word_and_exit(method_frames, current_offset);
+            one_word_in_zero_out(frame)?;
         }
         CompressedInstructionInfo::fstore(n) => {
-            one_word_variable_store(method_frames, *n, current_index);
+            one_word_variable_store(frame, *n as u16)?;
         }
         CompressedInstructionInfo::fstore_0 => {
-            one_word_variable_store(method_frames, 0, current_index);
+            one_word_variable_store(frame, 0)?;
         }
         CompressedInstructionInfo::fstore_1 => {
-            one_word_variable_store(method_frames, 1, current_index);
+            one_word_variable_store(frame, 1)?;
         }
         CompressedInstructionInfo::fstore_2 => {
-            one_word_variable_store(method_frames, 2, current_index);
+            one_word_variable_store(frame, 2)?;
         }
         CompressedInstructionInfo::fstore_3 => {
-            one_word_variable_store(method_frames, 3, current_index);
+            one_word_variable_store(frame, 3)?;
         }
         CompressedInstructionInfo::fsub => {
-            two_one_word_in_one_word_out(method_frames, current_index);
-        }
-        CompressedInstructionInfo::getfield { name: _, desc, target_class: _ } => {
-            let (current_frame, mut next_frame) = method_frames.nth_frame_and_next_mut(current_index);
-            current_frame.assert_operand_stack_entry_is(0, SimplifiedVType::OneWord);
-            match cpdtype_to_simplified_vtype(&desc.0) {
-                SimplifiedVType::OneWord => {
-                    let operand_stack = current_frame.operand_stack();
-                    next_frame.as_mut().unwrap().assert_operand_stack_is(operand_stack);
-                }
-                SimplifiedVType::TwoWord => {
-                    let mut operand_stack = current_frame.operand_stack();
-                    operand_stack.pop().unwrap().unwrap();
-                    operand_stack.push(Some(SimplifiedVType::TwoWord));
-                    next_frame.as_mut().unwrap().assert_operand_stack_is(operand_stack);
-                }
-                SimplifiedVType::Top => {
-                    panic!()
-                }
-            }
-        }
-        CompressedInstructionInfo::getstatic { name: _, desc, target_class: _ } => {
-            match cpdtype_to_simplified_vtype(&desc.0) {
-                SimplifiedVType::OneWord => {
-                    one_word_const(method_frames, current_index);
-                }
-                SimplifiedVType::TwoWord => {
-                    two_word_const(method_frames, current_index);
-                }
-                SimplifiedVType::Top => {
-                    panic!()
-                }
-            }
+            two_one_word_in_one_word_out(frame)?;
         }
-        CompressedInstructionInfo::goto_(offset) => {
-            let res_offset = (current_offset.0 as i32 + *offset as i32) as u16;
-            let current_frame = method_frames.nth_frame_mut(current_offset);
-            let operand_stack = current_frame.operand_stack();
-            let target_frame = method_frames.nth_frame_mut(ByteCodeOffset(res_offset));
-            target_frame.assert_operand_stack_is(operand_stack);
+        CompressedInstructionInfo::getfield { desc, .. } => {
+            frame.pop(SimplifiedVType::OneWord)?;
+            frame.push(cpdtype_to_simplified_vtype(&desc.0));
         }
-        CompressedInstructionInfo::goto_w(_) => {
-            todo!()
+        CompressedInstructionInfo::getstatic { desc, .. } => {
+            frame.push(cpdtype_to_simplified_vtype(&desc.0));
         }
+        CompressedInstructionInfo::goto_(_) => {}
+        CompressedInstructionInfo::goto_w(_) => {}
         CompressedInstructionInfo::i2b => {
-            one_word_in_one_word_out(method_frames, current_index)
+            one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::i2c => {
-            one_word_in_one_word_out(method_frames, current_index)
+            one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::i2d => {
-            one_word_in_two_word_out(method_frames, current_index)
+            one_word_in_two_word_out(frame)?;
         }
         CompressedInstructionInfo::i2f => {
-            one_word_in_one_word_out(method_frames, current_index)
+            one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::i2l => {
-            one_word_in_two_word_out(method_frames, current_index)
+            one_word_in_two_word_out(frame)?;
         }
         CompressedInstructionInfo::i2s => {
-            one_word_in_one_word_out(method_frames, current_index)
+            one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::iadd => {
-            two_one_word_in_one_word_out(method_frames, current_index);
+            two_one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::iaload => {
-            two_one_word_in_one_word_out(method_frames, current_index);
+            two_one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::iand => {
-            two_one_word_in_one_word_out(method_frames, current_index);
+            two_one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::iastore => {
-            three_one_word_in_zero_out(method_frames, current_index);
+            three_one_word_in_zero_out(frame)?;
         }
         CompressedInstructionInfo::iconst_m1 => {
-            one_word_const(method_frames, current_index);
+            one_word_const(frame);
         }
         CompressedInstructionInfo::iconst_0 => {
-            one_word_const(method_frames, current_index);
+            one_word_const(frame);
         }
         CompressedInstructionInfo::iconst_1 => {
-            one_word_const(method_frames, current_index);
+            one_word_const(frame);
         }
         CompressedInstructionInfo::iconst_2 => {
-            one_word_const(method_frames, current_index);
+            one_word_const(frame);
         }
         CompressedInstructionInfo::iconst_3 => {
-            one_word_const(method_frames, current_index);
+            one_word_const(frame);
         }
         CompressedInstructionInfo::iconst_4 => {
-            one_word_const(method_frames, current_index);
+            one_word_const(frame);
         }
         CompressedInstructionInfo::iconst_5 => {
-            one_word_const(method_frames, current_index);
+            one_word_const(frame);
         }
         CompressedInstructionInfo::idiv => {
-            two_one_word_in_one_word_out(method_frames, current_index);
+            two_one_word_in_one_word_out(frame)?;
         }
-        CompressedInstructionInfo::if_acmpeq(offset) => {
-            if_two_one_word(method_frames, current_offset, *offset)
+        CompressedInstructionInfo::if_acmpeq(_) => {
+            two_one_word_in_zero_out(frame)?;
         }
-        CompressedInstructionInfo::if_acmpne(offset) => {
-            if_two_one_word(method_frames, current_offset, *offset)
+        CompressedInstructionInfo::if_acmpne(_) => {
+            two_one_word_in_zero_out(frame)?;
         }
-        CompressedInstructionInfo::if_icmpeq(offset) => {
-            if_two_one_word(method_frames, current_offset, *offset)
+        CompressedInstructionInfo::if_icmpeq(_) => {
+            two_one_word_in_zero_out(frame)?;
         }
-        CompressedInstructionInfo::if_icmpne(offset) => {
-            if_two_one_word(method_frames, current_offset, *offset)
+        CompressedInstructionInfo::if_icmpne(_) => {
+            two_one_word_in_zero_out(frame)?;
         }
-        CompressedInstructionInfo::if_icmplt(offset) => {
-            if_two_one_word(method_frames, current_offset, *offset)
+        CompressedInstructionInfo::if_icmplt(_) => {
+            two_one_word_in_zero_out(frame)?;
         }
-        CompressedInstructionInfo::if_icmpge(offset) => {
-            if_two_one_word(method_frames, current_offset, *offset)
+        CompressedInstructionInfo::if_icmpge(_) => {
+            two_one_word_in_zero_out(frame)?;
         }
-        CompressedInstructionInfo::if_icmpgt(offset) => {
-            if_two_one_word(method_frames, current_offset, *offset)
+        CompressedInstructionInfo::if_icmpgt(_) => {
+            two_one_word_in_zero_out(frame)?;
         }
-        CompressedInstructionInfo::if_icmple(offset) => {
-            if_two_one_word(method_frames, current_offset, *offset)
+        CompressedInstructionInfo::if_icmple(_) => {
+            two_one_word_in_zero_out(frame)?;
         }
-        CompressedInstructionInfo::ifeq(offset) => {
-            if_one_word(method_frames, current_offset, *offset);
+        CompressedInstructionInfo::ifeq(_) => {
+            one_word_in_zero_out(frame)?;
         }
-        CompressedInstructionInfo::ifne(offset) => {
-            if_one_word(method_frames, current_offset, *offset);
+        CompressedInstructionInfo::ifne(_) => {
+            one_word_in_zero_out(frame)?;
         }
-        CompressedInstructionInfo::iflt(offset) => {
-            if_one_word(method_frames, current_offset, *offset);
+        CompressedInstructionInfo::iflt(_) => {
+            one_word_in_zero_out(frame)?;
         }
-        CompressedInstructionInfo::ifge(offset) => {
-            if_one_word(method_frames, current_offset, *offset);
+        CompressedInstructionInfo::ifge(_) => {
+            one_word_in_zero_out(frame)?;
         }
-        CompressedInstructionInfo::ifgt(offset) => {
-            if_one_word(method_frames, current_offset, *offset);
+        CompressedInstructionInfo::ifgt(_) => {
+            one_word_in_zero_out(frame)?;
         }
-        CompressedInstructionInfo::ifle(offset) => {
-            if_one_word(method_frames, current_offset, *offset);
+        CompressedInstructionInfo::ifle(_) => {
+            one_word_in_zero_out(frame)?;
         }
-        CompressedInstructionInfo::ifnonnull(offset) => {
-            if_one_word(method_frames, current_offset, *offset);
+        CompressedInstructionInfo::ifnonnull(_) => {
+            one_word_in_zero_out(frame)?;
         }
-        CompressedInstructionInfo::ifnull(offset) => {
-            if_one_word(method_frames, current_offset, *offset);
+        CompressedInstructionInfo::ifnull(_) => {
+            one_word_in_zero_out(frame)?;
         }
-        CompressedInstructionInfo::iinc(n) => {
-            let (current_frame, next_frame) = method_frames.nth_frame_and_next_mut_offset(current_offset);
-            current_frame.assert_local_is(n.index, SimplifiedVType::OneWord);
-            let operand_stack = current_frame.operand_stack();
-            next_frame.unwrap().assert_operand_stack_is(operand_stack);
-        }
-        CompressedInstructionInfo::iload(n) => {
-            one_word_variable_load(method_frames, *n, current_index);
+        CompressedInstructionInfo::iinc(IInc { .. }) => {}
+        CompressedInstructionInfo::iload(_) => {
+            one_word_variable_load(frame);
         }
         CompressedInstructionInfo::iload_0 => {
-            one_word_variable_load(method_frames, 0, current_index);
+            one_word_variable_load(frame);
         }
         CompressedInstructionInfo::iload_1 => {
-            one_word_variable_load(method_frames, 1, current_index);
+            one_word_variable_load(frame);
         }
         CompressedInstructionInfo::iload_2 => {
-            one_word_variable_load(method_frames, 2, current_index);
+            one_word_variable_load(frame);
         }
         CompressedInstructionInfo::iload_3 => {
-            one_word_variable_load(method_frames, 3, current_index);
+            one_word_variable_load(frame);
         }
         CompressedInstructionInfo::imul => {
-            two_one_word_in_one_word_out(method_frames, current_index);
+            two_one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::ineg => {
-            one_word_in_one_word_out(method_frames, current_index);
+            one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::instanceof(_) => {
-            one_word_in_one_word_out(method_frames, current_index);
+            one_word_in_one_word_out(frame)?;
         }
-        CompressedInstructionInfo::invokedynamic(_) => {
-            todo!()
+        CompressedInstructionInfo::invokedynamic(cp) => {
+            let descriptor = match method_view.classview().constant_pool_view(*cp as usize) {
+                ConstantInfoView::InvokeDynamic(invoke_dynamic) => invoke_dynamic.name_and_type().desc_method(pool),
+                _ => return Err(InferenceError::NotInvokeDynamic(*cp)),
+            };
+            invoke(frame, &descriptor, false)?;
         }
         CompressedInstructionInfo::invokeinterface { method_name: _, descriptor, classname_ref_type: _, count: _ } => {
-            invoke(method_frames, i, descriptor, true);
+            invoke(frame, descriptor, true)?;
         }
         CompressedInstructionInfo::invokespecial { method_name: _, descriptor, classname_ref_type: _ } => {
-            invoke(method_frames, i, descriptor, true);
+            invoke(frame, descriptor, true)?;
         }
         CompressedInstructionInfo::invokestatic { method_name: _, descriptor, classname_ref_type: _ } => {
-            invoke(method_frames, i, descriptor, false);
+            invoke(frame, descriptor, false)?;
         }
         CompressedInstructionInfo::invokevirtual { method_name: _, descriptor, classname_ref_type: _ } => {
-            invoke(method_frames, i, descriptor, true);
+            invoke(frame, descriptor, true)?;
         }
         CompressedInstructionInfo::ior => {
-            two_one_word_in_one_word_out(method_frames, current_index);
+            two_one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::irem => {
-            two_one_word_in_one_word_out(method_frames, current_index);
+            two_one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::ireturn => {
-            top_operand_is_one_word_and_exit(method_frames, current_offset);
+            one_word_in_zero_out(frame)?;
         }
         CompressedInstructionInfo::ishl => {
-            two_one_word_in_one_word_out(method_frames, current_index);
+            two_one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::ishr => {
-            two_one_word_in_one_word_out(method_frames, current_index);
+            two_one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::istore(n) => {
-            one_word_variable_store(method_frames, *n, current_index);
+            one_word_variable_store(frame, *n as u16)?;
         }
         CompressedInstructionInfo::istore_0 => {
-            one_word_variable_store(method_frames, 0, current_index);
+            one_word_variable_store(frame, 0)?;
         }
         CompressedInstructionInfo::istore_1 => {
-            one_word_variable_store(method_frames, 1, current_index);
+            one_word_variable_store(frame, 1)?;
         }
         CompressedInstructionInfo::istore_2 => {
-            one_word_variable_store(method_frames, 2, current_index);
+            one_word_variable_store(frame, 2)?;
         }
         CompressedInstructionInfo::istore_3 => {
-            one_word_variable_store(method_frames, 3, current_index);
+            one_word_variable_store(frame, 3)?;
         }
         CompressedInstructionInfo::isub => {
-            two_one_word_in_one_word_out(method_frames, current_index);
+            two_one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::iushr => {
-            two_one_word_in_one_word_out(method_frames, current_index);
+            two_one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::ixor => {
-            two_one_word_in_one_word_out(method_frames, current_index);
+            two_one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::jsr(_) => {
-            todo!()
+            //the return address
+            one_word_const(frame);
         }
         CompressedInstructionInfo::jsr_w(_) => {
-            todo!()
+            one_word_const(frame);
         }
         CompressedInstructionInfo::l2d => {
-            two_word_in_two_word_out(method_frames, current_index);
+            two_word_in_two_word_out(frame)?;
         }
         CompressedInstructionInfo::l2f => {
-            one_two_word_in_one_word_out(method_frames, current_index);
+            one_two_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::l2i => {
-            one_two_word_in_one_word_out(method_frames, current_index);
+            one_two_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::ladd => {
-            two_two_word_in_two_word_out(method_frames, current_index);
+            two_two_word_in_two_word_out(frame)?;
         }
         CompressedInstructionInfo::laload => {
-            todo!()
+            two_one_word_in_two_word_out(frame)?;
         }
         CompressedInstructionInfo::land => {
-            two_two_word_in_two_word_out(method_frames, current_index);
+            two_two_word_in_two_word_out(frame)?;
         }
         CompressedInstructionInfo::lastore => {
-            todo!()
+            two_one_word_and_two_word_in_zero_out(frame)?;
         }
         CompressedInstructionInfo::lcmp => {
-            two_two_word_in_one_word_out(method_frames, current_index)
+            two_two_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::lconst_0 => {
-            two_word_const(method_frames, current_index)
+            two_word_const(frame);
         }
         CompressedInstructionInfo::lconst_1 => {
-            two_word_const(method_frames, current_index)
+            two_word_const(frame);
         }
         CompressedInstructionInfo::ldc(ldc_either) => {
             match ldc_either {
                 Either::Left(ldc_left) => {
-                    match ldc_left {
-                        CompressedLdcW::String { .. } => {
-                            one_word_const(method_frames, current_index);
-                        }
-                        CompressedLdcW::Class { .. } => {
-                            one_word_const(method_frames, current_index);
-                        }
-                        CompressedLdcW::Float { .. } => {
-                            one_word_const(method_frames, current_index);
-                        }
-                        CompressedLdcW::Integer { .. } => {
-                            one_word_const(method_frames, current_index);
-                        }
-                        CompressedLdcW::MethodType { .. } => {
-                            todo!()
-                        }
-                        CompressedLdcW::MethodHandle { .. } => {
-                            todo!()
-                        }
-                        CompressedLdcW::LiveObject(_) => {
-                            todo!()
-                        }
-                    }
+                    ldc_w(frame, ldc_left);
                 }
                 Either::Right(ldc_right) => {
-                    match ldc_right {
-                        CompressedLdc2W::Long(_) => {
-                            todo!()
-                        }
-                        CompressedLdc2W::Double(_) => {
-                            todo!()
-                        }
-                    }
+                    ldc2_w(frame, ldc_right);
                 }
             }
         }
         CompressedInstructionInfo::ldc_w(wide) => {
-            match wide {
-                CompressedLdcW::String { .. } => {
-                    one_word_const(method_frames, current_index);
-                }
-                CompressedLdcW::Class { .. } => {
-                    one_word_const(method_frames, current_index);
-                }
-                CompressedLdcW::Float { .. } => {
-                    one_word_const(method_frames, current_index);
-                }
-                CompressedLdcW::Integer { .. } => {
-                    one_word_const(method_frames, current_index);
-                }
-                CompressedLdcW::MethodType { .. } => todo!(),
-                CompressedLdcW::MethodHandle { .. } => todo!(),
-                CompressedLdcW::LiveObject(_) => todo!(),
-            }
+            ldc_w(frame, wide);
         }
         CompressedInstructionInfo::ldc2_w(wide) => {
-            match wide {
-                CompressedLdc2W::Long(_) => {
-                    two_word_const(method_frames, current_index);
-                }
-                CompressedLdc2W::Double(_) => {
-                    two_word_const(method_frames, current_index);
-                }
-            }
+            ldc2_w(frame, wide);
         }
         CompressedInstructionInfo::ldiv => {
-            todo!()
+            two_two_word_in_two_word_out(frame)?;
         }
-        CompressedInstructionInfo::lload(n) => {
-            two_word_variable_load(method_frames, *n, current_index);
+        CompressedInstructionInfo::lload(_) => {
+            two_word_variable_load(frame);
         }
         CompressedInstructionInfo::lload_0 => {
-            two_word_variable_load(method_frames, 0, current_index);
+            two_word_variable_load(frame);
         }
         CompressedInstructionInfo::lload_1 => {
-            two_word_variable_load(method_frames, 1, current_index);
+            two_word_variable_load(frame);
         }
         CompressedInstructionInfo::lload_2 => {
-            two_word_variable_load(method_frames, 2, current_index);
+            two_word_variable_load(frame);
         }
         CompressedInstructionInfo::lload_3 => {
-            two_word_variable_load(method_frames, 3, current_index);
+            two_word_variable_load(frame);
         }
         CompressedInstructionInfo::lmul => {
-            two_two_word_in_two_word_out(method_frames, current_index);
+            two_two_word_in_two_word_out(frame)?;
         }
         CompressedInstructionInfo::lneg => {
-            two_word_in_two_word_out(method_frames, current_index);
+            two_word_in_two_word_out(frame)?;
         }
-        CompressedInstructionInfo::lookupswitch(LookupSwitch { pairs, default }) => {
-            let frame = method_frames.nth_frame_mut(current_offset);
-            frame.assert_operand_stack_entry_is(0, SimplifiedVType::OneWord);
-            let mut operand_stack = frame.operand_stack();
-            operand_stack.pop().unwrap().unwrap();
-            let default = ByteCodeOffset((current_offset.0 as i32 + *default) as u16);
-            method_frames.nth_frame_mut(default).assert_operand_stack_is(operand_stack.clone());
-            for (_, offset) in pairs {
-                let offset = ByteCodeOffset((current_offset.0 as i32 + *offset) as u16);
-                method_frames.nth_frame_mut(offset).assert_operand_stack_is(operand_stack.clone());
-            }
+        CompressedInstructionInfo::lookupswitch(_) => {
+            one_word_in_zero_out(frame)?;
         }
         CompressedInstructionInfo::lor => {
-            todo!()
+            two_two_word_in_two_word_out(frame)?;
         }
         CompressedInstructionInfo::lrem => {
-            todo!()
+            two_two_word_in_two_word_out(frame)?;
         }
         CompressedInstructionInfo::lreturn => {
-            top_operand_is_two_word_and_exit(method_frames, current_offset);
+            two_word_in_zero_out(frame)?;
         }
         CompressedInstructionInfo::lshl => {
-            todo!()
+            one_word_and_two_word_in_two_word_out(frame)?;
         }
         CompressedInstructionInfo::lshr => {
-            todo!()
+            one_word_and_two_word_in_two_word_out(frame)?;
         }
         CompressedInstructionInfo::lstore(n) => {
-            two_word_variable_store(method_frames, *n, current_index)
+            two_word_variable_store(frame, *n as u16)?;
         }
         CompressedInstructionInfo::lstore_0 => {
-            two_word_variable_store(method_frames, 0, current_index)
+            two_word_variable_store(frame, 0)?;
         }
         CompressedInstructionInfo::lstore_1 => {
-            two_word_variable_store(method_frames, 1, current_index)
+            two_word_variable_store(frame, 1)?;
         }
         CompressedInstructionInfo::lstore_2 => {
-            two_word_variable_store(method_frames, 2, current_index)
+            two_word_variable_store(frame, 2)?;
         }
         CompressedInstructionInfo::lstore_3 => {
-            two_word_variable_store(method_frames, 3, current_index)
+            two_word_variable_store(frame, 3)?;
         }
         CompressedInstructionInfo::lsub => {
-            two_two_word_in_two_word_out(method_frames, current_index);
+            two_two_word_in_two_word_out(frame)?;
         }
         CompressedInstructionInfo::lushr => {
-            one_word_and_two_word_in_two_word_out(method_frames, current_index);
+            one_word_and_two_word_in_two_word_out(frame)?;
         }
         CompressedInstructionInfo::lxor => {
-            two_two_word_in_two_word_out(method_frames, current_index);
+            two_two_word_in_two_word_out(frame)?;
         }
         CompressedInstructionInfo::monitorenter => {
-            one_word_in_zero_out(method_frames, current_index);
+            one_word_in_zero_out(frame)?;
         }
         CompressedInstructionInfo::monitorexit => {
-            one_word_in_zero_out(method_frames, current_index);
+            one_word_in_zero_out(frame)?;
         }
-        CompressedInstructionInfo::multianewarray { .. } => {
-            todo!()
+        CompressedInstructionInfo::multianewarray { type_: _, dimensions } => {
+            for _ in 0..dimensions.get() {
+                frame.pop(SimplifiedVType::OneWord)?;
+            }
+            frame.push(SimplifiedVType::OneWord);
         }
         CompressedInstructionInfo::new(_) => {
-            one_word_const(method_frames, current_index);
+            one_word_const(frame);
         }
         CompressedInstructionInfo::newarray(_) => {
-            one_word_in_one_word_out(method_frames, current_index);
-        }
-        CompressedInstructionInfo::nop => {
-            todo!()
+            one_word_in_one_word_out(frame)?;
         }
+        CompressedInstructionInfo::nop => {}
         CompressedInstructionInfo::pop => {
-            one_word_in_zero_out(method_frames, current_index);
+            one_word_in_zero_out(frame)?;
         }
         CompressedInstructionInfo::pop2 => {
-            todo!()
+            frame.pop_words(2)?;
         }
         CompressedInstructionInfo::putfield { desc, .. } => {
-            let field_type = cpdtype_to_simplified_vtype(&desc.0);
-            let (frame, mut next_frame) = method_frames.nth_frame_and_next_mut(current_index);
-            frame.assert_operand_stack_entry_is(0, field_type);
-            frame.assert_operand_stack_entry_is(1, SimplifiedVType::OneWord);
-            let mut operand_stack = frame.operand_stack();
-            operand_stack.pop().unwrap();
-            operand_stack.pop().unwrap();
-            next_frame.as_mut().unwrap().assert_operand_stack_is(operand_stack);
+            frame.pop(cpdtype_to_simplified_vtype(&desc.0))?;
+            frame.pop(SimplifiedVType::OneWord)?;
         }
         CompressedInstructionInfo::putstatic { name: _, desc, target_class: _ } => {
-            match cpdtype_to_simplified_vtype(&desc.0) {
-                SimplifiedVType::OneWord => {
-                    one_word_in_zero_out(method_frames, current_index);
-                }
-                SimplifiedVType::TwoWord => {
-                    two_word_in_zero_out(method_frames, current_index);
-                }
-                SimplifiedVType::Top => {
-                    panic!()
-                }
-            }
-        }
-        CompressedInstructionInfo::ret(_) => {
-            todo!()
+            frame.pop(cpdtype_to_simplified_vtype(&desc.0))?;
         }
+        CompressedInstructionInfo::ret(_) => {}
         CompressedInstructionInfo::return_ => {
             if let CPDType::VoidType = return_type {} else {
-                panic!()
+                return Err(InferenceError::ReturnFromNonVoid);
             }
         }
         CompressedInstructionInfo::saload => {
-            todo!()
+            two_one_word_in_one_word_out(frame)?;
         }
         CompressedInstructionInfo::sastore => {
-            todo!()
+            three_one_word_in_zero_out(frame)?;
         }
         CompressedInstructionInfo::sipush(_) => {
-            one_word_const(method_frames, current_index);
+            one_word_const(frame);
         }
         CompressedInstructionInfo::swap => {
-            let (frame, mut next_frame) = method_frames.nth_frame_and_next_mut(current_index);
-            frame.assert_operand_stack_entry_is(0, SimplifiedVType::OneWord);
-            frame.assert_operand_stack_entry_is(1, SimplifiedVType::OneWord);
-            let operand_stack = frame.operand_stack();
-            next_frame.as_mut().unwrap().assert_operand_stack_is(operand_stack);
+            frame.pop(SimplifiedVType::OneWord)?;
+            frame.pop(SimplifiedVType::OneWord)?;
+            frame.push(SimplifiedVType::OneWord);
+            frame.push(SimplifiedVType::OneWord);
         }
-        CompressedInstructionInfo::tableswitch(box TableSwitch { default, low: _, high: _, offsets }) => {
-            let frame = method_frames.nth_frame_mut(current_offset);
-            frame.assert_operand_stack_entry_is(0, SimplifiedVType::OneWord);
-            let mut operand_stack = frame.operand_stack();
-            operand_stack.pop().unwrap().unwrap();
-            let default = ByteCodeOffset((current_offset.0 as i32 + *default) as u16);
-            method_frames.nth_frame_mut(default).assert_operand_stack_is(operand_stack.clone());
-            for offset in offsets {
-                let offset = ByteCodeOffset((current_offset.0 as i32 + *offset) as u16);
-                method_frames.nth_frame_mut(offset).assert_operand_stack_is(operand_stack.clone());
-            }
+        CompressedInstructionInfo::tableswitch(_) => {
+            one_word_in_zero_out(frame)?;
         }
-        CompressedInstructionInfo::wide(_) => {
-            todo!()
+        CompressedInstructionInfo::wide(wide) => {
+            match wide {
+                Wide::Iload(WideIload { .. }) |
+                Wide::Fload(WideFload { .. }) |
+                Wide::Aload(WideAload { .. }) => {
+                    one_word_variable_load(frame);
+                }
+                Wide::Lload(WideLload { .. }) |
+                Wide::Dload(WideDload { .. }) => {
+                    two_word_variable_load(frame);
+                }
+                Wide::Istore(WideIstore { index }) |
+                Wide::Fstore(WideFstore { index }) |
+                Wide::Astore(WideAstore { index }) => {
+                    one_word_variable_store(frame, *index)?;
+                }
+                Wide::Lstore(WideLstore { index }) |
+                Wide::Dstore(WideDstore { index }) => {
+                    two_word_variable_store(frame, *index)?;
+                }
+                Wide::Ret(WideRet { .. }) => {}
+                Wide::IInc(IInc { .. }) => {}
+            }
         }
         CompressedInstructionInfo::EndOfCode => {
-            todo!()
+            return Err(InferenceError::FallsOffEnd);
+        }
+    }
+    Ok(())
+}
+
+fn ldc_w(frame: &mut Frame, ldc: &CompressedLdcW) {
+    match ldc {
+        CompressedLdcW::String { .. } |
+        CompressedLdcW::Class { .. } |
+        CompressedLdcW::Float { .. } |
+        CompressedLdcW::Integer { .. } |
+        CompressedLdcW::MethodType { .. } |
+        CompressedLdcW::MethodHandle { .. } |
+        CompressedLdcW::LiveObject(_) |
+        CompressedLdcW::Dynamic { .. } => {
+            one_word_const(frame);
+        }
+    }
+}
+
+fn ldc2_w(frame: &mut Frame, ldc: &CompressedLdc2W) {
+    match ldc {
+        CompressedLdc2W::Long(_) |
+        CompressedLdc2W::Double(_) |
+        CompressedLdc2W::Dynamic { .. } => {
+            two_word_const(frame);
         }
     }
 }
 
-fn one_word_and_two_word_in_two_word_out(method_frames: &mut MethodFrames, current_index: ByteCodeIndex) {
-    let (current_frame, mut next_frame) = method_frames.nth_frame_and_next_mut(current_index);
-    current_frame.assert_operand_stack_entry_is(0, SimplifiedVType::OneWord);
-    current_frame.assert_operand_stack_entry_is(1, SimplifiedVType::TwoWord);
-    let mut operand_stack = current_frame.operand_stack();
-    let _ = operand_stack.pop().unwrap().unwrap();
-    let _ = operand_stack.pop().unwrap().unwrap();
-    operand_stack.push(Some(SimplifiedVType::TwoWord));
-    next_frame.as_mut().unwrap().assert_operand_stack_is(operand_stack);
+/// The dup family: duplicates the top words_to_dup words, inserting them words_below words down.
+fn dup_words(frame: &mut Frame, words_to_dup: usize, words_below: usize) -> Result<(), InferenceError> {
+    let to_dup = frame.pop_words(words_to_dup)?;
+    let below = frame.pop_words(words_below)?;
+    frame.operand_stack.extend(to_dup.iter().cloned());
+    frame.operand_stack.extend(below);
+    frame.operand_stack.extend(to_dup);
+    Ok(())
 }
 
-fn invoke(method_frames: &mut MethodFrames, i: usize, descriptor: &CMethodDescriptor, include_obj_ref: bool) {
-    let (current_frame, mut next_frame) = method_frames.nth_frame_and_next_mut(ByteCodeIndex(i as u16));
-    let mut operand_stack = current_frame.operand_stack();
+fn invoke(frame: &mut Frame, descriptor: &CMethodDescriptor, include_obj_ref: bool) -> Result<(), InferenceError> {
     for arg_type in descriptor.arg_types.iter().rev() {
-        match cpdtype_to_simplified_vtype(arg_type) {
-            SimplifiedVType::OneWord => {
-                let should_be_one_word = operand_stack.pop().unwrap().unwrap();
-                if let SimplifiedVType::OneWord = should_be_one_word {} else {
-                    panic!()
-                }
-            }
-            SimplifiedVType::TwoWord => {
-                operand_stack.pop().unwrap();
-            }
-            SimplifiedVType::Top => {
-                panic!()
-            }
-        }
+        frame.pop(cpdtype_to_simplified_vtype(arg_type))?;
     }
     if include_obj_ref {
-        let should_be_one_word = operand_stack.pop().unwrap().unwrap();
-        if let SimplifiedVType::OneWord = should_be_one_word {} else {
-            panic!()
-        }
+        frame.pop(SimplifiedVType::OneWord)?;
     }
     if let CPDType::VoidType = descriptor.return_type {} else {
-        match cpdtype_to_simplified_vtype(&descriptor.return_type) {
-            SimplifiedVType::OneWord => {
-                operand_stack.push(Some(SimplifiedVType::OneWord));
-            }
-            SimplifiedVType::TwoWord => {
-                operand_stack.push(Some(SimplifiedVType::TwoWord));
-            }
-            SimplifiedVType::Top => {
-                panic!()
-            }
-        }
+        frame.push(cpdtype_to_simplified_vtype(&descriptor.return_type));
     }
-    next_frame.as_mut().unwrap().assert_operand_stack_is(operand_stack);
+    Ok(())
 }
 
-fn three_one_word_in_zero_out(method_frames: &mut MethodFrames, current_index: ByteCodeIndex) {
-    let (current_frame, mut next_frame) = method_frames.nth_frame_and_next_mut(current_index);
-    current_frame.assert_operand_stack_entry_is(0, SimplifiedVType::OneWord);
-    current_frame.assert_operand_stack_entry_is(1, SimplifiedVType::OneWord);
-    current_frame.assert_operand_stack_entry_is(2, SimplifiedVType::OneWord);
-    let mut operand_stack = current_frame.operand_stack();
-    let _ = operand_stack.pop().unwrap().unwrap();
-    let _ = operand_stack.pop().unwrap().unwrap();
-    let _ = operand_stack.pop().unwrap().unwrap();
-    next_frame.as_mut().unwrap().assert_operand_stack_is(operand_stack);
+fn one_word_and_two_word_in_two_word_out(frame: &mut Frame) -> Result<(), InferenceError> {
+    frame.pop(SimplifiedVType::OneWord)?;
+    frame.pop(SimplifiedVType::TwoWord)?;
+    frame.push(SimplifiedVType::TwoWord);
+    Ok(())
 }
 
-fn two_one_word_in_one_word_out(method_frames: &mut MethodFrames, current_index: ByteCodeIndex) {
-    let (current_frame, mut next_frame) = method_frames.nth_frame_and_next_mut(current_index);
-    current_frame.assert_operand_stack_entry_is(0, SimplifiedVType::OneWord);
-    current_frame.assert_operand_stack_entry_is(1, SimplifiedVType::OneWord);
-    let mut operand_stack = current_frame.operand_stack();
-    let _ = operand_stack.pop().unwrap().unwrap();
-    let _ = operand_stack.pop().unwrap().unwrap();
-    operand_stack.push(Some(SimplifiedVType::OneWord));
-    next_frame.as_mut().unwrap().assert_operand_stack_is(operand_stack);
+fn three_one_word_in_zero_out(frame: &mut Frame) -> Result<(), InferenceError> {
+    frame.pop(SimplifiedVType::OneWord)?;
+    frame.pop(SimplifiedVType::OneWord)?;
+    frame.pop(SimplifiedVType::OneWord)?;
+    Ok(())
 }
 
-fn two_two_word_in_one_word_out(method_frames: &mut MethodFrames, current_index: ByteCodeIndex) {
-    let (current_frame, mut next_frame) = method_frames.nth_frame_and_next_mut(current_index);
-    current_frame.assert_operand_stack_entry_is(0, SimplifiedVType::TwoWord);
-    current_frame.assert_operand_stack_entry_is(1, SimplifiedVType::TwoWord);
-    let mut operand_stack = current_frame.operand_stack();
-    let _ = operand_stack.pop().unwrap().unwrap();
-    let _ = operand_stack.pop().unwrap().unwrap();
-    operand_stack.push(Some(SimplifiedVType::OneWord));
-    next_frame.as_mut().unwrap().assert_operand_stack_is(operand_stack);
+fn two_one_word_and_two_word_in_zero_out(frame: &mut Frame) -> Result<(), InferenceError> {
+    frame.pop(SimplifiedVType::TwoWord)?;
+    frame.pop(SimplifiedVType::OneWord)?;
+    frame.pop(SimplifiedVType::OneWord)?;
+    Ok(())
 }
 
-fn two_two_word_in_two_word_out(method_frames: &mut MethodFrames, current_index: ByteCodeIndex) {
-    let (current_frame, mut next_frame) = method_frames.nth_frame_and_next_mut(current_index);
-    current_frame.assert_operand_stack_entry_is(0, SimplifiedVType::TwoWord);
-    current_frame.assert_operand_stack_entry_is(1, SimplifiedVType::TwoWord);
-    let mut operand_stack = current_frame.operand_stack();
-    let _ = operand_stack.pop().unwrap().unwrap();
-    let _ = operand_stack.pop().unwrap().unwrap();
-    operand_stack.push(Some(SimplifiedVType::TwoWord));
-    next_frame.as_mut().unwrap().assert_operand_stack_is(operand_stack);
+fn two_one_word_in_zero_out(frame: &mut Frame) -> Result<(), InferenceError> {
+    frame.pop(SimplifiedVType::OneWord)?;
+    frame.pop(SimplifiedVType::OneWord)?;
+    Ok(())
 }
 
-fn one_two_word_in_one_word_out(method_frames: &mut MethodFrames, current_index: ByteCodeIndex) {
-    let (current_frame, mut next_frame) = method_frames.nth_frame_and_next_mut(current_index);
-    current_frame.assert_operand_stack_entry_is(0, SimplifiedVType::TwoWord);
-    let mut operand_stack = current_frame.operand_stack();
-    let _ = operand_stack.pop().unwrap().unwrap();
-    operand_stack.push(Some(SimplifiedVType::OneWord));
-    next_frame.as_mut().unwrap().assert_operand_stack_is(operand_stack);
+fn two_one_word_in_one_word_out(frame: &mut Frame) -> Result<(), InferenceError> {
+    frame.pop(SimplifiedVType::OneWord)?;
+    frame.pop(SimplifiedVType::OneWord)?;
+    frame.push(SimplifiedVType::OneWord);
+    Ok(())
 }
 
-
-fn top_operand_is_one_word_and_exit(method_frames: &mut MethodFrames, current_offset: ByteCodeOffset) {
-    let current_frame = method_frames.nth_frame_mut(current_offset);
-    current_frame.assert_operand_stack_entry_is(0, SimplifiedVType::OneWord);
+fn two_one_word_in_two_word_out(frame: &mut Frame) -> Result<(), InferenceError> {
+    frame.pop(SimplifiedVType::OneWord)?;
+    frame.pop(SimplifiedVType::OneWord)?;
+    frame.push(SimplifiedVType::TwoWord);
+    Ok(())
 }
 
-fn top_operand_is_two_word_and_exit(method_frames: &mut MethodFrames, current_offset: ByteCodeOffset) {
-    let current_frame = method_frames.nth_frame_mut(current_offset);
-    current_frame.assert_operand_stack_entry_is(0, SimplifiedVType::TwoWord);
+fn two_two_word_in_one_word_out(frame: &mut Frame) -> Result<(), InferenceError> {
+    frame.pop(SimplifiedVType::TwoWord)?;
+    frame.pop(SimplifiedVType::TwoWord)?;
+    frame.push(SimplifiedVType::OneWord);
+    Ok(())
 }
 
-fn one_word_in_one_word_out(method_frames: &mut MethodFrames, current_index: ByteCodeIndex) {
-    let (current_frame, mut next_frame) = method_frames.nth_frame_and_next_mut(current_index);
-    current_frame.assert_operand_stack_entry_is(0, SimplifiedVType::OneWord);
-    let operand_stack = current_frame.operand_stack();
-    next_frame.as_mut().unwrap().assert_operand_stack_is(operand_stack);
+fn two_two_word_in_two_word_out(frame: &mut Frame) -> Result<(), InferenceError> {
+    frame.pop(SimplifiedVType::TwoWord)?;
+    frame.pop(SimplifiedVType::TwoWord)?;
+    frame.push(SimplifiedVType::TwoWord);
+    Ok(())
 }
 
-fn two_word_in_two_word_out(method_frames: &mut MethodFrames, current_index: ByteCodeIndex) {
-    let (current_frame, mut next_frame) = method_frames.nth_frame_and_next_mut(current_index);
-    current_frame.assert_operand_stack_entry_is(0, SimplifiedVType::TwoWord);
-    let operand_stack = current_frame.operand_stack();
-    next_frame.as_mut().unwrap().assert_operand_stack_is(operand_stack);
+fn one_two_word_in_one_word_out(frame: &mut Frame) -> Result<(), InferenceError> {
+    frame.pop(SimplifiedVType::TwoWord)?;
+    frame.push(SimplifiedVType::OneWord);
+    Ok(())
 }
 
-fn one_word_in_two_word_out(method_frames: &mut MethodFrames, current_index: ByteCodeIndex) {
-    let (current_frame, mut next_frame) = method_frames.nth_frame_and_next_mut(current_index);
-    current_frame.assert_operand_stack_entry_is(0, SimplifiedVType::OneWord);
-    let mut operand_stack = current_frame.operand_stack();
-    operand_stack.pop().unwrap().unwrap();
-    operand_stack.push(Some(SimplifiedVType::TwoWord));
-    next_frame.as_mut().unwrap().assert_operand_stack_is(operand_stack);
+fn one_word_in_one_word_out(frame: &mut Frame) -> Result<(), InferenceError> {
+    frame.pop(SimplifiedVType::OneWord)?;
+    frame.push(SimplifiedVType::OneWord);
+    Ok(())
 }
 
-fn one_word_in_zero_out(method_frames: &mut MethodFrames, current_index: ByteCodeIndex) {
-    let (current_frame, mut next_frame) = method_frames.nth_frame_and_next_mut(current_index);
-    current_frame.assert_operand_stack_entry_is(0, SimplifiedVType::OneWord);
-    let mut operand_stack = current_frame.operand_stack();
-    operand_stack.pop().unwrap().unwrap();
-    next_frame.as_mut().unwrap().assert_operand_stack_is(operand_stack);
+fn two_word_in_two_word_out(frame: &mut Frame) -> Result<(), InferenceError> {
+    frame.pop(SimplifiedVType::TwoWord)?;
+    frame.push(SimplifiedVType::TwoWord);
+    Ok(())
 }
 
-fn two_word_in_zero_out(method_frames: &mut MethodFrames, current_index: ByteCodeIndex) {
-    let (current_frame, mut next_frame) = method_frames.nth_frame_and_next_mut(current_index);
-    current_frame.assert_operand_stack_entry_is(0, SimplifiedVType::TwoWord);
-    let mut operand_stack = current_frame.operand_stack();
-    operand_stack.pop().unwrap().unwrap();
-    next_frame.as_mut().unwrap().assert_operand_stack_is(operand_stack);
+fn one_word_in_two_word_out(frame: &mut Frame) -> Result<(), InferenceError> {
+    frame.pop(SimplifiedVType::OneWord)?;
+    frame.push(SimplifiedVType::TwoWord);
+    Ok(())
 }
 
-fn if_one_word(method_frames: &mut MethodFrames, current_offset: ByteCodeOffset, offset: i16) {
-    let res_offset = (current_offset.0 as i32 + offset as i32) as u16;
-    let (current_frame, mut next_frame) = method_frames.nth_frame_and_next_mut_offset(current_offset);
-    current_frame.assert_operand_stack_entry_is(0, SimplifiedVType::OneWord);
-    let mut operand_stack = current_frame.operand_stack();
-    let _ = operand_stack.pop().unwrap().unwrap();
-    next_frame.as_mut().unwrap().assert_operand_stack_is(operand_stack.clone());
-    let target_frame = method_frames.nth_frame_mut(ByteCodeOffset(res_offset));
-    target_frame.assert_operand_stack_is(operand_stack);
+fn one_word_in_zero_out(frame: &mut Frame) -> Result<(), InferenceError> {
+    frame.pop(SimplifiedVType::OneWord)?;
+    Ok(())
 }
 
-
-fn if_two_one_word(method_frames: &mut MethodFrames, current_offset: ByteCodeOffset, offset: i16) {
-    let res_offset = (current_offset.0 as i32 + offset as i32) as u16;
-    let (current_frame, mut next_frame) = method_frames.nth_frame_and_next_mut_offset(current_offset);
-    current_frame.assert_operand_stack_entry_is(0, SimplifiedVType::OneWord);
-    current_frame.assert_operand_stack_entry_is(1, SimplifiedVType::OneWord);
-    let mut operand_stack = current_frame.operand_stack();
-    let _ = operand_stack.pop().unwrap().unwrap();
-    let _ = operand_stack.pop().unwrap().unwrap();
-    next_frame.as_mut().unwrap().assert_operand_stack_is(operand_stack.clone());
-    let target_frame = method_frames.nth_frame_mut(ByteCodeOffset(res_offset));
-    target_frame.assert_operand_stack_is(operand_stack);
+fn two_word_in_zero_out(frame: &mut Frame) -> Result<(), InferenceError> {
+    frame.pop(SimplifiedVType::TwoWord)?;
+    Ok(())
 }
 
-fn one_word_const(method_frames: &mut MethodFrames, current_index: ByteCodeIndex) {
-    let (frame, mut next_frame) = method_frames.nth_frame_and_next_mut(current_index);
-    let mut operand_stack = frame.operand_stack();
-    operand_stack.push(None);
-    next_frame.as_mut().unwrap().assert_operand_stack_is(operand_stack);
-    next_frame.as_mut().unwrap().assert_operand_stack_entry_is(0, SimplifiedVType::OneWord);
+fn one_word_const(frame: &mut Frame) {
+    frame.push(SimplifiedVType::OneWord);
 }
 
-fn two_word_const(method_frames: &mut MethodFrames, current_index: ByteCodeIndex) {
-    let (frame, mut next_frame) = method_frames.nth_frame_and_next_mut(current_index);
-    let mut operand_stack = frame.operand_stack();
-    operand_stack.push(Some(SimplifiedVType::TwoWord));
-    next_frame.as_mut().unwrap().assert_operand_stack_is(operand_stack);
+fn two_word_const(frame: &mut Frame) {
+    frame.push(SimplifiedVType::TwoWord);
 }
 
-fn one_word_variable_load(method_frames: &mut MethodFrames, n: u8, current_index: ByteCodeIndex) {
-    let (current_frame, mut next_frame) = method_frames.nth_frame_and_next_mut(current_index);
-    current_frame.assert_local_is(n as u16, SimplifiedVType::OneWord);
-    let mut operand_stack = current_frame.operand_stack();
-    operand_stack.push(Some(SimplifiedVType::OneWord));
-    next_frame.as_mut().unwrap().assert_operand_stack_is(operand_stack);
-    next_frame.as_mut().unwrap().assert_operand_stack_entry_is(0, SimplifiedVType::OneWord);
+//loads don't check the local, it may legitimately be Top where frames with different types for it merged
+fn one_word_variable_load(frame: &mut Frame) {
+    frame.push(SimplifiedVType::OneWord);
 }
 
-fn two_word_variable_load(method_frames: &mut MethodFrames, n: u8, current_index: ByteCodeIndex) {
-    let (current_frame, mut next_frame) = method_frames.nth_frame_and_next_mut(current_index);
-    current_frame.assert_local_is(n as u16, SimplifiedVType::TwoWord);
-    let mut operand_stack = current_frame.operand_stack();
-    operand_stack.push(Some(SimplifiedVType::TwoWord));
-    next_frame.as_mut().unwrap().assert_operand_stack_is(operand_stack);
+fn two_word_variable_load(frame: &mut Frame) {
+    frame.push(SimplifiedVType::TwoWord);
 }
 
-fn one_word_variable_store(method_frames: &mut MethodFrames, n: u8, current_index: ByteCodeIndex) {
-    let (current_frame, mut next_frame) = method_frames.nth_frame_and_next_mut(current_index);
-    current_frame.assert_operand_stack_entry_is(0, SimplifiedVType::OneWord);
-    next_frame.as_mut().unwrap().assert_local_is(n as u16, SimplifiedVType::OneWord);
-    let mut operand_stack = current_frame.operand_stack();
-    let _ = operand_stack.pop().unwrap().unwrap();
-    next_frame.as_mut().unwrap().assert_operand_stack_is(operand_stack);
+fn one_word_variable_store(frame: &mut Frame, n: u16) -> Result<(), InferenceError> {
+    frame.pop(SimplifiedVType::OneWord)?;
+    frame.store_local(n, SimplifiedVType::OneWord)
 }
 
-fn two_word_variable_store(method_frames: &mut MethodFrames, n: u8, current_index: ByteCodeIndex) {
-    let (current_frame, mut next_frame) = method_frames.nth_frame_and_next_mut(current_index);
-    current_frame.assert_operand_stack_entry_is(0, SimplifiedVType::TwoWord);
-    next_frame.as_mut().unwrap().assert_local_is(n as u16, SimplifiedVType::TwoWord);
-    let mut operand_stack = current_frame.operand_stack();
-    let _ = operand_stack.pop().unwrap().unwrap();
-    next_frame.as_mut().unwrap().assert_operand_stack_is(operand_stack);
-}
\ No newline at end of file
+fn two_word_variable_store(frame: &mut Frame, n: u16) -> Result<(), InferenceError> {
+    frame.pop(SimplifiedVType::TwoWord)?;
+    frame.store_local(n, SimplifiedVType::TwoWord)
+}
@@ -0,0 +1,66 @@
+use std::env;
+use std::path::PathBuf;
+
+use classfile_view::view::{ClassBackedView, ClassView};
+use jar_manipulation::JarHandle;
+use java5_verifier::type_infer;
+use rust_jvm_common::ByteCodeOffset;
+use rust_jvm_common::compressed_classfile::class_names::CClassName;
+use rust_jvm_common::compressed_classfile::code::CompressedStackMapFrame;
+use rust_jvm_common::compressed_classfile::string_pool::CompressedClassfileStringPool;
+use xtask::load_xtask_config;
+
+fn workspace_dir() -> PathBuf {
+    PathBuf::from(env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR not set?")).parent().unwrap().to_path_buf()
+}
+
+fn stack_map_stack_depths(stack_map_table: &[CompressedStackMapFrame]) -> Vec<(ByteCodeOffset, usize)> {
+    let mut res = vec![];
+    let mut offset: Option<u16> = None;
+    for entry in stack_map_table {
+        let (offset_delta, stack_depth) = match entry {
+            CompressedStackMapFrame::SameFrame(frame) => (frame.offset_delta, 0),
+            CompressedStackMapFrame::SameLocals1StackItemFrame(frame) => (frame.offset_delta, 1),
+            CompressedStackMapFrame::SameLocals1StackItemFrameExtended(frame) => (frame.offset_delta, 1),
+            CompressedStackMapFrame::ChopFrame(frame) => (frame.offset_delta, 0),
+            CompressedStackMapFrame::SameFrameExtended(frame) => (frame.offset_delta, 0),
+            CompressedStackMapFrame::AppendFrame(frame) => (frame.offset_delta, 0),
+            CompressedStackMapFrame::FullFrame(frame) => (frame.offset_delta, frame.stack.len()),
+        };
+        let current_offset = match offset {
+            None => offset_delta,
+            Some(previous) => previous + offset_delta + 1,
+        };
+        offset = Some(current_offset);
+        res.push((ByteCodeOffset(current_offset), stack_depth));
+    }
+    res
+}
+
+#[test]
+pub fn inferred_stack_depths_match_rt_jar_stack_maps() {
+    let xtask = load_xtask_config(&workspace_dir()).unwrap().expect("No xtask config found.");
+    let pool = CompressedClassfileStringPool::new();
+    let mut jar = JarHandle::new(xtask.rt_jar().into_boxed_path()).unwrap();
+    let class_names = jar.zip_archive.file_names()
+        .filter_map(|file_name| file_name.strip_suffix(".class"))
+        .map(|class_name| class_name.to_string())
+        .collect::<Vec<_>>();
+    assert!(!class_names.is_empty());
+    for class_name in class_names {
+        let classfile = jar.lookup(&pool, &CClassName(pool.add_name(class_name.clone(), true))).unwrap().unwrap();
+        let class_view = ClassBackedView::from(classfile, &pool);
+        for method_view in class_view.methods() {
+            let code = match method_view.code_attribute() {
+                None => continue,
+                Some(code) => code,
+            };
+            let inferred_frames = type_infer(&method_view, &pool).unwrap().inferred_frames();
+            assert_eq!(inferred_frames.len(), code.instructions.len());
+            for (offset, stack_depth) in stack_map_stack_depths(code.stack_map_table.as_slice()) {
+                let inferred_stack_depth = inferred_frames.get(&offset).unwrap().operand_stack.len();
+                assert_eq!(inferred_stack_depth, stack_depth, "{}.{}{} at {}", class_name, method_view.name().0.to_str(&pool), method_view.desc_str().to_str(&pool), offset.0);
+            }
+        }
+    }
+}
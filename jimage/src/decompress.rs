@@ -0,0 +1,171 @@
+use std::io::Read;
+
+use flate2::read::ZlibDecoder;
+
+use crate::{corrupt, Endianness, JImage, JImageError};
+
+pub const COMPRESSED_HEADER_MAGIC: u32 = 0xCAFE_FAFA;
+//magic, compressed size, uncompressed size, decompressor name offset, content offset and an is terminal flag
+pub const COMPRESSED_HEADER_SIZE: usize = 4 + 8 + 8 + 4 + 4 + 1;
+
+const CONSTANT_UTF8: u8 = 1;
+const CONSTANT_LONG: u8 = 5;
+const CONSTANT_DOUBLE: u8 = 6;
+//utf8 constants the string sharing plugin moved into the image's strings table
+const EXTERNALIZED_STRING: u8 = 23;
+const EXTERNALIZED_STRING_DESCRIPTOR: u8 = 25;
+
+struct CompressedResourceHeader {
+    compressed_size: u64,
+    decompressor_name_offset: u32,
+}
+
+impl CompressedResourceHeader {
+    fn read(endianness: Endianness, content: &[u8]) -> Option<CompressedResourceHeader> {
+        if content.len() < COMPRESSED_HEADER_SIZE || endianness.u32(content, 0)? != COMPRESSED_HEADER_MAGIC {
+            return None;
+        }
+        Some(CompressedResourceHeader {
+            compressed_size: endianness.u64(content, 4)?,
+            decompressor_name_offset: endianness.u32(content, 20)?,
+        })
+    }
+}
+
+/// Undoes the compression plugins jlink ran over a resource. Plugins can be stacked, in which case what one
+/// decompressor produces starts with the header of the next.
+pub(crate) fn decompress(image: &JImage, mut content: Vec<u8>) -> Result<Vec<u8>, JImageError> {
+    while let Some(header) = CompressedResourceHeader::read(image.endianness, content.as_slice()) {
+        let compressed = content.get(COMPRESSED_HEADER_SIZE..COMPRESSED_HEADER_SIZE + header.compressed_size as usize)
+            .ok_or_else(|| corrupt("compressed resource is shorter than its header"))?;
+        content = match image.string_bytes_at(header.decompressor_name_offset)? {
+            b"zip" => {
+                let mut res = vec![];
+                ZlibDecoder::new(compressed).read_to_end(&mut res)?;
+                res
+            }
+            b"compact-cp" => expand_shared_strings(image, compressed)?,
+            other => return Err(JImageError::UnknownDecompressor(String::from_utf8_lossy(other).into_owned())),
+        };
+    }
+    Ok(content)
+}
+
+fn constant_size(tag: u8) -> Option<usize> {
+    Some(match tag {
+        //class, string, method type, module and package
+        7 | 8 | 16 | 19 | 20 => 2,
+        //method handle
+        15 => 3,
+        //integer, float, field, method and interface method refs, name and type, dynamic and invoke dynamic
+        3 | 4 | 9 | 10 | 11 | 12 | 17 | 18 => 4,
+        CONSTANT_LONG | CONSTANT_DOUBLE => 8,
+        _ => return None,
+    })
+}
+
+/// The compact-cp plugin replaces utf8 constants of a class file with offsets into the image's strings table.
+/// Descriptors are stored as the descriptor with bare `L`s, followed by the package and simple name of each class
+/// in it. This puts the constants back, the rest of the class file is untouched.
+fn expand_shared_strings(image: &JImage, compressed: &[u8]) -> Result<Vec<u8>, JImageError> {
+    let mut reader = Reader { bytes: compressed, position: 0 };
+    let mut res = Vec::with_capacity(compressed.len() * 2);
+    //magic, minor and major version
+    res.extend_from_slice(reader.bytes(8)?);
+    let count = reader.u16()?;
+    res.extend_from_slice(&count.to_be_bytes());
+    let mut i = 1;
+    while i < count {
+        match reader.u8()? {
+            CONSTANT_UTF8 => {
+                let length = reader.u16()?;
+                push_utf8(&mut res, reader.bytes(length as usize)?)?;
+            }
+            EXTERNALIZED_STRING => {
+                push_utf8(&mut res, image.string_bytes_at(reader.compressed_int()?)?)?;
+            }
+            EXTERNALIZED_STRING_DESCRIPTOR => {
+                push_utf8(&mut res, reconstruct_descriptor(image, &mut reader)?.as_slice())?;
+            }
+            tag => {
+                let size = constant_size(tag).ok_or_else(|| corrupt(format!("unknown constant pool tag {} in shared strings class", tag)))?;
+                res.push(tag);
+                res.extend_from_slice(reader.bytes(size)?);
+                //longs and doubles take two constant pool entries
+                if tag == CONSTANT_LONG || tag == CONSTANT_DOUBLE {
+                    i += 1;
+                }
+            }
+        }
+        i += 1;
+    }
+    res.extend_from_slice(reader.rest());
+    Ok(res)
+}
+
+fn push_utf8(res: &mut Vec<u8>, string: &[u8]) -> Result<(), JImageError> {
+    let length = u16::try_from(string.len()).map_err(|_| corrupt("shared string is too long for a utf8 constant"))?;
+    res.push(CONSTANT_UTF8);
+    res.extend_from_slice(&length.to_be_bytes());
+    res.extend_from_slice(string);
+    Ok(())
+}
+
+fn reconstruct_descriptor(image: &JImage, reader: &mut Reader) -> Result<Vec<u8>, JImageError> {
+    let descriptor = image.string_bytes_at(reader.compressed_int()?)?;
+    let indices_length = reader.compressed_int()? as usize;
+    let mut indices = Reader { bytes: reader.bytes(indices_length)?, position: 0 };
+    let mut res = vec![];
+    for byte in descriptor {
+        res.push(*byte);
+        if *byte == b'L' {
+            let package = image.string_bytes_at(indices.compressed_int()?)?;
+            if !package.is_empty() {
+                res.extend_from_slice(package);
+                res.push(b'/');
+            }
+            res.extend_from_slice(image.string_bytes_at(indices.compressed_int()?)?);
+        }
+    }
+    Ok(res)
+}
+
+struct Reader<'l> {
+    bytes: &'l [u8],
+    position: usize,
+}
+
+impl<'l> Reader<'l> {
+    fn bytes(&mut self, len: usize) -> Result<&'l [u8], JImageError> {
+        let res = self.bytes.get(self.position..self.position + len).ok_or_else(|| corrupt("shared strings class is truncated"))?;
+        self.position += len;
+        Ok(res)
+    }
+
+    fn u8(&mut self) -> Result<u8, JImageError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, JImageError> {
+        Ok(u16::from_be_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    // CompressIndexes: with the top bit set the next two bits are the length in bytes and the low five are the top
+    // of the value, otherwise it's a plain big endian u32.
+    fn compressed_int(&mut self) -> Result<u32, JImageError> {
+        let header = self.u8()?;
+        let (length, mut res) = if header & 0x80 != 0 {
+            (((header >> 5) & 0x3) as usize, (header & 0x1F) as u32)
+        } else {
+            (4, header as u32)
+        };
+        for _ in 1..length {
+            res = res << 8 | self.u8()? as u32;
+        }
+        Ok(res)
+    }
+
+    fn rest(&self) -> &'l [u8] {
+        &self.bytes[self.position.min(self.bytes.len())..]
+    }
+}
@@ -0,0 +1,329 @@
+//! Reads the jimage container the JDK has kept its classes and resources in since 9, `lib/modules` of a java home.
+//! The layout follows jdk.internal.jimage.BasicImageReader: a header, a perfect hash of resource names split over a
+//! redirect and an offsets table, the encoded location of each resource, the strings those locations point into,
+//! and then the resources themselves.
+
+use std::{fmt, io};
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt::Formatter;
+use std::fs::File;
+use std::os::unix::fs::FileExt;
+use std::path::Path;
+
+mod decompress;
+pub mod writer;
+
+pub const IMAGE_MAGIC: u32 = 0xCAFE_DADA;
+pub const MAJOR_VERSION: u16 = 1;
+pub const MINOR_VERSION: u16 = 0;
+//magic, version, flags, resource count, table length, locations size and strings size
+pub const HEADER_SIZE: usize = 7 * 4;
+const HASH_MULTIPLIER: u32 = 0x0100_0193;
+
+const ATTRIBUTE_END: u8 = 0;
+const ATTRIBUTE_MODULE: usize = 1;
+const ATTRIBUTE_PARENT: usize = 2;
+const ATTRIBUTE_BASE: usize = 3;
+const ATTRIBUTE_EXTENSION: usize = 4;
+const ATTRIBUTE_OFFSET: usize = 5;
+const ATTRIBUTE_COMPRESSED: usize = 6;
+const ATTRIBUTE_UNCOMPRESSED: usize = 7;
+
+#[derive(Debug)]
+pub enum JImageError {
+    Io(io::Error),
+    NotAnImage,
+    UnsupportedVersion { major: u16, minor: u16 },
+    Corrupt(String),
+    UnknownDecompressor(String),
+}
+
+impl fmt::Display for JImageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            JImageError::Io(err) => write!(f, "{}", err),
+            JImageError::NotAnImage => write!(f, "not a jimage file"),
+            JImageError::UnsupportedVersion { major, minor } => write!(f, "unsupported jimage version {}.{}", major, minor),
+            JImageError::Corrupt(reason) => write!(f, "corrupt jimage: {}", reason),
+            JImageError::UnknownDecompressor(name) => write!(f, "unknown jimage decompressor {}", name),
+        }
+    }
+}
+
+impl Error for JImageError {}
+
+impl From<io::Error> for JImageError {
+    fn from(err: io::Error) -> Self {
+        JImageError::Io(err)
+    }
+}
+
+fn corrupt(reason: impl Into<String>) -> JImageError {
+    JImageError::Corrupt(reason.into())
+}
+
+/// Images are written in the byte order of the platform they were built for, which shows in the magic.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+impl Endianness {
+    fn u32(self, bytes: &[u8], offset: usize) -> Option<u32> {
+        let bytes = bytes.get(offset..offset + 4)?.try_into().unwrap();
+        Some(match self {
+            Endianness::Little => u32::from_le_bytes(bytes),
+            Endianness::Big => u32::from_be_bytes(bytes),
+        })
+    }
+
+    fn u64(self, bytes: &[u8], offset: usize) -> Option<u64> {
+        let bytes = bytes.get(offset..offset + 8)?.try_into().unwrap();
+        Some(match self {
+            Endianness::Little => u64::from_le_bytes(bytes),
+            Endianness::Big => u64::from_be_bytes(bytes),
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct ImageHeader {
+    pub major_version: u16,
+    pub minor_version: u16,
+    pub flags: u32,
+    pub resource_count: u32,
+    pub table_length: u32,
+    pub locations_size: u32,
+    pub strings_size: u32,
+}
+
+impl ImageHeader {
+    fn parse(bytes: &[u8; HEADER_SIZE]) -> Result<(Endianness, ImageHeader), JImageError> {
+        let endianness = [Endianness::Little, Endianness::Big].into_iter()
+            .find(|endianness| endianness.u32(bytes, 0) == Some(IMAGE_MAGIC))
+            .ok_or(JImageError::NotAnImage)?;
+        let word = |i: usize| endianness.u32(bytes, i * 4).unwrap();
+        let version = word(1);
+        let header = ImageHeader {
+            major_version: (version >> 16) as u16,
+            minor_version: version as u16,
+            flags: word(2),
+            resource_count: word(3),
+            table_length: word(4),
+            locations_size: word(5),
+            strings_size: word(6),
+        };
+        if header.major_version != MAJOR_VERSION || header.minor_version != MINOR_VERSION {
+            return Err(JImageError::UnsupportedVersion { major: header.major_version, minor: header.minor_version });
+        }
+        Ok((endianness, header))
+    }
+
+    /// Resource content offsets are relative to the end of the index.
+    pub fn index_size(&self) -> u64 {
+        HEADER_SIZE as u64 + self.table_length as u64 * 4 * 2 + self.locations_size as u64 + self.strings_size as u64
+    }
+}
+
+/// Where a resource lives. Its name is split up as `/module/parent/base.extension`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ImageLocation {
+    pub module: String,
+    pub parent: String,
+    pub base: String,
+    pub extension: String,
+    pub content_offset: u64,
+    //zero if the resource is stored uncompressed
+    pub compressed_size: u64,
+    pub uncompressed_size: u64,
+}
+
+impl ImageLocation {
+    pub fn full_name(&self) -> String {
+        let mut res = String::new();
+        if !self.module.is_empty() {
+            res.push('/');
+            res.push_str(self.module.as_str());
+            res.push('/');
+        }
+        if !self.parent.is_empty() {
+            res.push_str(self.parent.as_str());
+            res.push('/');
+        }
+        res.push_str(self.base.as_str());
+        if !self.extension.is_empty() {
+            res.push('.');
+            res.push_str(self.extension.as_str());
+        }
+        res
+    }
+}
+
+/// ImageStringsReader.hashCode, FNV-1 over the utf-8 bytes of a name.
+pub fn hash_code(name: &[u8], seed: u32) -> u32 {
+    name.iter().fold(seed, |hash, byte| hash.wrapping_mul(HASH_MULTIPLIER) ^ *byte as u32) & 0x7FFF_FFFF
+}
+
+#[derive(Debug)]
+pub struct JImage {
+    pub path: Box<Path>,
+    pub header: ImageHeader,
+    pub endianness: Endianness,
+    file: File,
+    //everything between the header and the first resource
+    index: Vec<u8>,
+}
+
+impl JImage {
+    pub fn open(path: Box<Path>) -> Result<JImage, JImageError> {
+        let file = File::open(&path)?;
+        let mut header_bytes = [0u8; HEADER_SIZE];
+        file.read_exact_at(&mut header_bytes, 0).map_err(|_| JImageError::NotAnImage)?;
+        let (endianness, header) = ImageHeader::parse(&header_bytes)?;
+        let mut index = vec![0u8; (header.index_size() - HEADER_SIZE as u64) as usize];
+        file.read_exact_at(index.as_mut_slice(), HEADER_SIZE as u64).map_err(|_| corrupt("index is truncated"))?;
+        Ok(JImage { path, header, endianness, file, index })
+    }
+
+    fn redirect(&self, i: u32) -> Result<i32, JImageError> {
+        self.endianness.u32(&self.index, i as usize * 4).map(|redirect| redirect as i32).ok_or_else(|| corrupt("redirect table is truncated"))
+    }
+
+    fn location_offset(&self, i: u32) -> Result<u32, JImageError> {
+        let table_start = self.header.table_length as usize * 4;
+        self.endianness.u32(&self.index, table_start + i as usize * 4).ok_or_else(|| corrupt("offsets table is truncated"))
+    }
+
+    fn location_bytes(&self) -> &[u8] {
+        let start = self.header.table_length as usize * 4 * 2;
+        &self.index[start..start + self.header.locations_size as usize]
+    }
+
+    fn string_bytes(&self) -> &[u8] {
+        let start = self.header.table_length as usize * 4 * 2 + self.header.locations_size as usize;
+        &self.index[start..start + self.header.strings_size as usize]
+    }
+
+    /// The nul terminated (modified) utf-8 string at `offset` of the strings table.
+    pub fn string_bytes_at(&self, offset: u32) -> Result<&[u8], JImageError> {
+        let rest = self.string_bytes().get(offset as usize..).ok_or_else(|| corrupt(format!("string offset {} is past the strings table", offset)))?;
+        let end = rest.iter().position(|byte| *byte == 0).ok_or_else(|| corrupt(format!("string at {} is unterminated", offset)))?;
+        Ok(&rest[..end])
+    }
+
+    pub fn string_at(&self, offset: u32) -> Result<Cow<'_, str>, JImageError> {
+        Ok(String::from_utf8_lossy(self.string_bytes_at(offset)?))
+    }
+
+    fn decode_location(&self, location_offset: u32) -> Result<ImageLocation, JImageError> {
+        let location_bytes = self.location_bytes();
+        let mut attributes = [0u64; ATTRIBUTE_UNCOMPRESSED + 1];
+        let mut i = location_offset as usize;
+        loop {
+            let byte = *location_bytes.get(i).ok_or_else(|| corrupt(format!("location at {} runs past the locations table", location_offset)))?;
+            let kind = byte >> 3;
+            if kind == ATTRIBUTE_END {
+                break;
+            }
+            if kind as usize > ATTRIBUTE_UNCOMPRESSED {
+                return Err(corrupt(format!("unknown location attribute {}", kind)));
+            }
+            let length = (byte & 0x7) as usize + 1;
+            let value = location_bytes.get(i + 1..i + 1 + length).ok_or_else(|| corrupt(format!("location at {} runs past the locations table", location_offset)))?;
+            //attribute values are big endian whatever the image's byte order
+            attributes[kind as usize] = value.iter().fold(0u64, |acc, byte| acc << 8 | *byte as u64);
+            i += 1 + length;
+        }
+        let string_attribute = |kind: usize| self.string_at(attributes[kind] as u32).map(|string| string.into_owned());
+        Ok(ImageLocation {
+            module: string_attribute(ATTRIBUTE_MODULE)?,
+            parent: string_attribute(ATTRIBUTE_PARENT)?,
+            base: string_attribute(ATTRIBUTE_BASE)?,
+            extension: string_attribute(ATTRIBUTE_EXTENSION)?,
+            content_offset: attributes[ATTRIBUTE_OFFSET],
+            compressed_size: attributes[ATTRIBUTE_COMPRESSED],
+            uncompressed_size: attributes[ATTRIBUTE_UNCOMPRESSED],
+        })
+    }
+
+    /// Looks a resource up by its full name, e.g. `/java.base/java/lang/Object.class`.
+    pub fn find_location(&self, name: &str) -> Result<Option<ImageLocation>, JImageError> {
+        let table_length = self.header.table_length;
+        if table_length == 0 {
+            return Ok(None);
+        }
+        let name_bytes = name.as_bytes();
+        let index = match self.redirect(hash_code(name_bytes, HASH_MULTIPLIER) % table_length)? {
+            0 => return Ok(None),
+            //a bucket of one names its slot directly, otherwise the redirect is the seed which spreads out the bucket
+            redirect if redirect < 0 => (-1 - redirect) as u32,
+            seed => hash_code(name_bytes, seed as u32) % table_length,
+        };
+        if index >= table_length {
+            return Err(corrupt(format!("redirect to {} is past the offsets table", index)));
+        }
+        let location = self.decode_location(self.location_offset(index)?)?;
+        //the hash is only perfect over names in the image, anything else lands on some other resource
+        Ok(if location.full_name() == name { Some(location) } else { None })
+    }
+
+    /// Every resource in the image, in the order of the offsets table.
+    pub fn locations(&self) -> impl Iterator<Item=Result<ImageLocation, JImageError>> + '_ {
+        (0..self.header.table_length).map(move |i| self.decode_location(self.location_offset(i)?))
+    }
+
+    pub fn read_resource(&self, location: &ImageLocation) -> Result<Vec<u8>, JImageError> {
+        let stored_size = if location.compressed_size != 0 { location.compressed_size } else { location.uncompressed_size };
+        let mut content = vec![0u8; stored_size as usize];
+        self.file.read_exact_at(content.as_mut_slice(), self.header.index_size() + location.content_offset)?;
+        if location.compressed_size == 0 {
+            return Ok(content);
+        }
+        decompress::decompress(self, content)
+    }
+
+    /// The module a package is in, from the `/packages/<package>` resource jlink writes for each package. The
+    /// package is in internal form, like `java/lang`.
+    pub fn package_to_module(&self, package: &str) -> Result<Option<String>, JImageError> {
+        let location = match self.find_location(format!("/packages/{}", package.replace('/', ".")).as_str())? {
+            Some(location) => location,
+            None => return Ok(None),
+        };
+        let content = self.read_resource(&location)?;
+        //an (is_empty, module name offset) pair for each module with the package. Modules can have an empty
+        //directory for a package without any classes in it, so like hotspot the first non empty one wins.
+        let modules = content.chunks_exact(8)
+            .map(|pair| (self.endianness.u32(pair, 0).unwrap() != 0, self.endianness.u32(pair, 4).unwrap()))
+            .collect::<Vec<_>>();
+        let module_name_offset = match modules.iter().find(|(is_empty, _)| !is_empty).or_else(|| modules.first()) {
+            Some((_, module_name_offset)) => *module_name_offset,
+            None => return Ok(None),
+        };
+        Ok(Some(self.string_at(module_name_offset)?.into_owned()))
+    }
+
+    /// The location of a class, by its internal name like `java/lang/Object`. Classes in the unnamed package can't
+    /// be in a module, so are never found.
+    pub fn class_location(&self, class_name: &str) -> Result<Option<ImageLocation>, JImageError> {
+        let package = match class_name.rsplit_once('/') {
+            Some((package, _)) => package,
+            None => return Ok(None),
+        };
+        match self.package_to_module(package)? {
+            Some(module) => self.find_location(format!("/{}/{}.class", module, class_name).as_str()),
+            None => Ok(None),
+        }
+    }
+
+    pub fn lookup_class(&self, class_name: &str) -> Result<Option<Vec<u8>>, JImageError> {
+        match self.class_location(class_name)? {
+            Some(location) => Ok(Some(self.read_resource(&location)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test;
@@ -0,0 +1,34 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+
+use jimage::{JImage, JImageError};
+
+#[derive(Parser)]
+#[clap(about = "lists the resources in a jimage, like the lib/modules of a jdk 9+ java home")]
+pub struct Opts {
+    image: PathBuf,
+    #[clap(long, help = "only list resources in this module")]
+    module: Option<String>,
+    #[clap(long, short, help = "also print the header, and the offset and sizes of each resource")]
+    verbose: bool,
+}
+
+fn main() -> Result<(), JImageError> {
+    let Opts { image, module, verbose } = Opts::parse();
+    let image = JImage::open(image.into_boxed_path())?;
+    if verbose {
+        println!("{:?} {:?}", image.endianness, image.header);
+    }
+    let mut locations = image.locations().collect::<Result<Vec<_>, _>>()?;
+    locations.retain(|location| module.as_ref().map(|module| &location.module == module).unwrap_or(true));
+    locations.sort_by_cached_key(|location| location.full_name());
+    for location in locations {
+        if verbose {
+            println!("{:>10} {:>10} {:>10} {}", location.content_offset, location.compressed_size, location.uncompressed_size, location.full_name());
+        } else {
+            println!("{}", location.full_name());
+        }
+    }
+    Ok(())
+}
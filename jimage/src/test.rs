@@ -0,0 +1,88 @@
+use std::io::Write;
+use std::path::PathBuf;
+
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+
+use crate::JImage;
+use crate::writer::ImageBuilder;
+
+//writes the image to the temp directory
+fn write_image(builder: ImageBuilder, file_name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("{}-{}", file_name, std::process::id()));
+    builder.write(&path).unwrap();
+    path
+}
+
+const OBJECT_CLASS: &[u8] = &[0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, 55, 1, 2, 3];
+
+#[test]
+pub fn test_lookup_class() {
+    let mut builder = ImageBuilder::new();
+    builder.package("java.lang", "java.base");
+    builder.package("java.util", "java.base");
+    builder.resource("/java.base/java/lang/Object.class", OBJECT_CLASS);
+    builder.resource("/java.base/java/util/List.class", &[4, 5, 6]);
+    builder.resource("/java.base/module-info.class", &[7]);
+    let path = write_image(builder, "jimage-lookup-class");
+    let image = JImage::open(path.clone().into_boxed_path()).unwrap();
+    assert_eq!(image.package_to_module("java/lang").unwrap().as_deref(), Some("java.base"));
+    assert_eq!(image.lookup_class("java/lang/Object").unwrap().as_deref(), Some(OBJECT_CLASS));
+    assert_eq!(image.lookup_class("java/util/List").unwrap().as_deref(), Some(&[4u8, 5, 6][..]));
+    assert_eq!(image.lookup_class("java/lang/Missing").unwrap(), None);
+    assert_eq!(image.lookup_class("javax/swing/JFrame").unwrap(), None);
+    let mut names = image.locations().map(|location| location.unwrap().full_name()).collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, vec!["/java.base/java/lang/Object.class", "/java.base/java/util/List.class", "/java.base/module-info.class", "/packages/java.lang", "/packages/java.util"]);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+pub fn test_zip_resource() {
+    let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+    encoder.write_all(OBJECT_CLASS).unwrap();
+    let compressed = encoder.finish().unwrap();
+    let mut builder = ImageBuilder::new();
+    builder.package("java.lang", "java.base");
+    builder.compressed_resource("/java.base/java/lang/Object.class", "zip", compressed.as_slice(), OBJECT_CLASS.len() as u64);
+    let path = write_image(builder, "jimage-zip-resource");
+    let image = JImage::open(path.clone().into_boxed_path()).unwrap();
+    assert_eq!(image.lookup_class("java/lang/Object").unwrap().as_deref(), Some(OBJECT_CLASS));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+pub fn test_shared_strings_resource() {
+    let mut builder = ImageBuilder::new();
+    builder.package("java.lang", "java.base");
+    let hello = builder.string("hello");
+    let descriptor = builder.string("(L;I)L;");
+    let java_lang = builder.string("java/lang");
+    let object = builder.string("Object");
+    let string = builder.string("String");
+    let mut compressed = vec![0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, 55, 0, 6];
+    //an externalized string, as a one byte compressed index
+    compressed.extend_from_slice(&[23, 0xA0 | hello as u8]);
+    //an externalized descriptor, with plain big endian indices
+    compressed.push(25);
+    compressed.extend_from_slice(&descriptor.to_be_bytes());
+    compressed.push(0xA0 | 16);
+    for index in [java_lang, object, java_lang, string] {
+        compressed.extend_from_slice(&index.to_be_bytes());
+    }
+    //a long, which takes two entries, then a class
+    compressed.extend_from_slice(&[5, 0, 0, 0, 0, 0, 0, 0, 42, 7, 0, 1]);
+    compressed.extend_from_slice(&[0xAA, 0xBB]);
+    let mut expected = vec![0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, 55, 0, 6, 1, 0, 5];
+    expected.extend_from_slice(b"hello");
+    let reconstructed: &[u8] = b"(Ljava/lang/Object;I)Ljava/lang/String;";
+    expected.push(1);
+    expected.extend_from_slice(&(reconstructed.len() as u16).to_be_bytes());
+    expected.extend_from_slice(reconstructed);
+    expected.extend_from_slice(&[5, 0, 0, 0, 0, 0, 0, 0, 42, 7, 0, 1, 0xAA, 0xBB]);
+    builder.compressed_resource("/java.base/java/lang/Object.class", "compact-cp", compressed.as_slice(), expected.len() as u64);
+    let path = write_image(builder, "jimage-shared-strings-resource");
+    let image = JImage::open(path.clone().into_boxed_path()).unwrap();
+    assert_eq!(image.lookup_class("java/lang/Object").unwrap(), Some(expected));
+    std::fs::remove_file(path).unwrap();
+}
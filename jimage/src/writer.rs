@@ -0,0 +1,129 @@
+//! Writes little endian images laid out enough like what jlink writes for this crate to read back, with
+//! resources stored as given. For building images in tests.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::{hash_code, HASH_MULTIPLIER, IMAGE_MAGIC, MAJOR_VERSION, MINOR_VERSION};
+use crate::decompress::{COMPRESSED_HEADER_MAGIC, COMPRESSED_HEADER_SIZE};
+
+#[derive(Default)]
+pub struct ImageBuilder {
+    strings: Vec<u8>,
+    string_offsets: HashMap<String, u32>,
+    resources: Vec<(String, Vec<u8>, u64)>,
+}
+
+impl ImageBuilder {
+    pub fn new() -> Self {
+        let mut res = ImageBuilder::default();
+        //offset zero is the empty string, which absent location attributes default to
+        res.string("");
+        res
+    }
+
+    pub fn string(&mut self, string: &str) -> u32 {
+        if let Some(offset) = self.string_offsets.get(string) {
+            return *offset;
+        }
+        let offset = self.strings.len() as u32;
+        self.strings.extend_from_slice(string.as_bytes());
+        self.strings.push(0);
+        self.string_offsets.insert(string.to_string(), offset);
+        offset
+    }
+
+    pub fn resource(&mut self, name: &str, content: &[u8]) {
+        self.resources.push((name.to_string(), content.to_vec(), 0));
+    }
+
+    pub fn compressed_resource(&mut self, name: &str, decompressor: &str, compressed: &[u8], uncompressed_size: u64) {
+        let decompressor_name_offset = self.string(decompressor);
+        let mut content = vec![];
+        content.extend_from_slice(&COMPRESSED_HEADER_MAGIC.to_le_bytes());
+        content.extend_from_slice(&(compressed.len() as u64).to_le_bytes());
+        content.extend_from_slice(&uncompressed_size.to_le_bytes());
+        content.extend_from_slice(&decompressor_name_offset.to_le_bytes());
+        content.extend_from_slice(&0u32.to_le_bytes());
+        content.push(1);
+        assert_eq!(content.len(), COMPRESSED_HEADER_SIZE);
+        content.extend_from_slice(compressed);
+        self.resources.push((name.to_string(), content, uncompressed_size));
+    }
+
+    pub fn package(&mut self, package: &str, module: &str) {
+        let module_name_offset = self.string(module);
+        let mut content = 0u32.to_le_bytes().to_vec();
+        content.extend_from_slice(&module_name_offset.to_le_bytes());
+        self.resource(format!("/packages/{}", package).as_str(), content.as_slice());
+    }
+
+    // ImageLocationWriter's split of a name into module, parent, base and extension
+    fn location(&mut self, name: &str, content_offset: u64, stored_size: u64, uncompressed_size: u64) -> Vec<u8> {
+        let (module, rest) = name.strip_prefix('/').unwrap().split_once('/').unwrap();
+        let (parent, file_name) = rest.rsplit_once('/').unwrap_or(("", rest));
+        let (base, extension) = file_name.rsplit_once('.').unwrap_or((file_name, ""));
+        let (compressed_size, uncompressed_size) = if uncompressed_size == 0 { (0, stored_size) } else { (stored_size, uncompressed_size) };
+        let attributes = [self.string(module) as u64, self.string(parent) as u64, self.string(base) as u64, self.string(extension) as u64, content_offset, compressed_size, uncompressed_size];
+        let mut res = vec![];
+        for (kind, value) in attributes.into_iter().enumerate() {
+            res.push(((kind as u8 + 1) << 3) | 7);
+            res.extend_from_slice(&value.to_be_bytes());
+        }
+        res.push(0);
+        res
+    }
+
+    pub fn write(mut self, path: &Path) -> std::io::Result<()> {
+        let table_length = self.resources.len() as u32;
+        let mut locations = vec![];
+        let mut location_offsets = vec![];
+        let mut content = vec![];
+        for (name, resource, uncompressed_size) in self.resources.clone() {
+            location_offsets.push(locations.len() as u32);
+            locations.extend(self.location(name.as_str(), content.len() as u64, resource.len() as u64, uncompressed_size));
+            content.extend(resource);
+        }
+        //PerfectHashBuilder: buckets of several names get the first seed which spreads them over free slots
+        let mut buckets: HashMap<u32, Vec<usize>> = HashMap::new();
+        for (i, (name, _, _)) in self.resources.iter().enumerate() {
+            buckets.entry(hash_code(name.as_bytes(), HASH_MULTIPLIER) % table_length).or_default().push(i);
+        }
+        let mut buckets = buckets.into_iter().collect::<Vec<_>>();
+        buckets.sort_by_key(|(_, members)| std::cmp::Reverse(members.len()));
+        let mut redirect = vec![0i32; table_length as usize];
+        let mut slots: Vec<Option<usize>> = vec![None; table_length as usize];
+        for (bucket, members) in buckets {
+            if members.len() == 1 {
+                let slot = slots.iter().position(|slot| slot.is_none()).unwrap();
+                slots[slot] = Some(members[0]);
+                redirect[bucket as usize] = -1 - slot as i32;
+                continue;
+            }
+            let seed = (1..).find(|seed| {
+                let mut taken = members.iter().map(|member| hash_code(self.resources[*member].0.as_bytes(), *seed) % table_length).collect::<Vec<_>>();
+                taken.sort();
+                taken.dedup();
+                taken.len() == members.len() && taken.iter().all(|slot| slots[*slot as usize].is_none())
+            }).unwrap();
+            for member in members {
+                slots[(hash_code(self.resources[member].0.as_bytes(), seed) % table_length) as usize] = Some(member);
+            }
+            redirect[bucket as usize] = seed as i32;
+        }
+        let mut res = vec![];
+        for word in [IMAGE_MAGIC, (MAJOR_VERSION as u32) << 16 | MINOR_VERSION as u32, 0, table_length, table_length, locations.len() as u32, self.strings.len() as u32] {
+            res.extend_from_slice(&word.to_le_bytes());
+        }
+        for redirect in redirect {
+            res.extend_from_slice(&redirect.to_le_bytes());
+        }
+        for slot in slots {
+            res.extend_from_slice(&location_offsets[slot.unwrap()].to_le_bytes());
+        }
+        res.extend(locations);
+        res.extend(self.strings);
+        res.extend(content);
+        std::fs::write(path, res)
+    }
+}
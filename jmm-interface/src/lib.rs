@@ -2,18 +2,40 @@
 #![allow(unreachable_code)]
 
 use std::ptr::null_mut;
-use jvmti_jni_bindings::{jboolean, jint, jlong, JMM_VERSION_1_2_2, jmmBoolAttribute, jmmLongAttribute, jmmLongAttribute_JMM_CLASS_INIT_TOTAL_COUNT, jmmLongAttribute_JMM_CLASS_INIT_TOTAL_TIME_MS, jmmLongAttribute_JMM_CLASS_LOADED_BYTES, jmmLongAttribute_JMM_CLASS_LOADED_COUNT, jmmLongAttribute_JMM_CLASS_UNLOADED_BYTES, jmmLongAttribute_JMM_CLASS_UNLOADED_COUNT, jmmLongAttribute_JMM_CLASS_VERIFY_TOTAL_TIME_MS, jmmLongAttribute_JMM_COMPILE_TOTAL_TIME_MS, jmmLongAttribute_JMM_GC_COUNT, jmmLongAttribute_JMM_GC_EXT_ATTRIBUTE_INFO_SIZE, jmmLongAttribute_JMM_GC_TIME_MS, jmmLongAttribute_JMM_INTERNAL_ATTRIBUTE_INDEX, jmmLongAttribute_JMM_JVM_INIT_DONE_TIME_MS, jmmLongAttribute_JMM_JVM_UPTIME_MS, jmmLongAttribute_JMM_METHOD_DATA_SIZE_BYTES, jmmLongAttribute_JMM_OS_ATTRIBUTE_INDEX, jmmLongAttribute_JMM_OS_MEM_TOTAL_PHYSICAL_BYTES, jmmLongAttribute_JMM_OS_PROCESS_ID, jmmLongAttribute_JMM_SAFEPOINT_COUNT, jmmLongAttribute_JMM_SHARED_CLASS_LOADED_BYTES, jmmLongAttribute_JMM_SHARED_CLASS_LOADED_COUNT, jmmLongAttribute_JMM_SHARED_CLASS_UNLOADED_BYTES, jmmLongAttribute_JMM_SHARED_CLASS_UNLOADED_COUNT, jmmLongAttribute_JMM_THREAD_DAEMON_COUNT, jmmLongAttribute_JMM_THREAD_LIVE_COUNT, jmmLongAttribute_JMM_THREAD_PEAK_COUNT, jmmLongAttribute_JMM_THREAD_TOTAL_COUNT, jmmLongAttribute_JMM_TOTAL_APP_TIME_MS, jmmLongAttribute_JMM_TOTAL_CLASSLOAD_TIME_MS, jmmLongAttribute_JMM_TOTAL_SAFEPOINTSYNC_TIME_MS, jmmLongAttribute_JMM_TOTAL_STOPPED_TIME_MS, jmmLongAttribute_JMM_VM_GLOBAL_COUNT, jmmLongAttribute_JMM_VM_THREAD_COUNT, jmmOptionalSupport, JNI_OK, JNIEnv, jobject, jobjectArray};
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use jvmti_jni_bindings::{jboolean, jint, jlong, JMM_VERSION_1_2_2, jmmBoolAttribute, jmmLongAttribute, jmmLongAttribute_JMM_CLASS_INIT_TOTAL_COUNT, jmmLongAttribute_JMM_CLASS_INIT_TOTAL_TIME_MS, jmmLongAttribute_JMM_CLASS_LOADED_BYTES, jmmLongAttribute_JMM_CLASS_LOADED_COUNT, jmmLongAttribute_JMM_CLASS_UNLOADED_BYTES, jmmLongAttribute_JMM_CLASS_UNLOADED_COUNT, jmmLongAttribute_JMM_CLASS_VERIFY_TOTAL_TIME_MS, jmmLongAttribute_JMM_COMPILE_TOTAL_TIME_MS, jmmLongAttribute_JMM_GC_COUNT, jmmLongAttribute_JMM_GC_EXT_ATTRIBUTE_INFO_SIZE, jmmLongAttribute_JMM_GC_TIME_MS, jmmLongAttribute_JMM_INTERNAL_ATTRIBUTE_INDEX, jmmLongAttribute_JMM_JVM_INIT_DONE_TIME_MS, jmmLongAttribute_JMM_JVM_UPTIME_MS, jmmLongAttribute_JMM_METHOD_DATA_SIZE_BYTES, jmmLongAttribute_JMM_OS_ATTRIBUTE_INDEX, jmmLongAttribute_JMM_OS_MEM_TOTAL_PHYSICAL_BYTES, jmmLongAttribute_JMM_OS_PROCESS_ID, jmmLongAttribute_JMM_SAFEPOINT_COUNT, jmmLongAttribute_JMM_SHARED_CLASS_LOADED_BYTES, jmmLongAttribute_JMM_SHARED_CLASS_LOADED_COUNT, jmmLongAttribute_JMM_SHARED_CLASS_UNLOADED_BYTES, jmmLongAttribute_JMM_SHARED_CLASS_UNLOADED_COUNT, jmmLongAttribute_JMM_THREAD_DAEMON_COUNT, jmmLongAttribute_JMM_THREAD_LIVE_COUNT, jmmLongAttribute_JMM_THREAD_PEAK_COUNT, jmmLongAttribute_JMM_THREAD_TOTAL_COUNT, jmmLongAttribute_JMM_TOTAL_APP_TIME_MS, jmmLongAttribute_JMM_TOTAL_CLASSLOAD_TIME_MS, jmmLongAttribute_JMM_TOTAL_SAFEPOINTSYNC_TIME_MS, jmmLongAttribute_JMM_TOTAL_STOPPED_TIME_MS, jmmLongAttribute_JMM_VM_GLOBAL_COUNT, jmmLongAttribute_JMM_VM_THREAD_COUNT, jmmOptionalSupport, JNI_OK, JNIEnv, jlongArray, jobject, jobjectArray};
 use jvmti_jni_bindings::{jmmBoolAttribute_JMM_VERBOSE_GC, jmmBoolAttribute_JMM_VERBOSE_CLASS, jmmBoolAttribute_JMM_THREAD_CONTENTION_MONITORING, jmmBoolAttribute_JMM_THREAD_CPU_TIME, jmmBoolAttribute_JMM_THREAD_ALLOCATED_MEMORY};
 use jvmti_jni_bindings::jmm_interface::JMMInterfaceNamedReservedPointers;
 
-use slow_interpreter::rust_jni::jni_utils::{get_interpreter_state, get_state, new_local_ref_public_new};
+use itertools::Itertools;
+use rust_jvm_common::compressed_classfile::class_names::CClassName;
+use rust_jvm_common::compressed_classfile::compressed_types::CPDType;
+use slow_interpreter::class_loading::check_initing_or_inited_class;
+use slow_interpreter::exceptions::WasException;
+use slow_interpreter::java_values::JavaValue;
+use slow_interpreter::jvm_state::JVMState;
+use slow_interpreter::new_java_values::java_value_common::JavaValueCommon;
+use slow_interpreter::new_java_values::NewJavaValue;
+use slow_interpreter::new_java_values::owned_casts::OwnedCastAble;
+use slow_interpreter::rust_jni::native_util::from_object_new;
+use slow_interpreter::threading::java_thread::JavaThread;
+use slow_interpreter::stdlib::java::lang::illegal_argument_exception::IllegalArgumentException;
+use slow_interpreter::utils::throw_npe;
+use slow_interpreter::rust_jni::jni_utils::{get_interpreter_state, get_state, get_throw, new_local_ref_public_new};
+use slow_interpreter::stdlib::java::NewAsObjectOrJavaValue;
+use slow_interpreter::threading::deadlocks::find_monitor_deadlocks;
 
 pub unsafe extern "C" fn get_version(_env: *mut JNIEnv) -> jint {
     JMM_VERSION_1_2_2 as i32
 }
 
 pub unsafe extern "C" fn get_optional_support(_env: *mut JNIEnv, support_ptr: *mut jmmOptionalSupport) -> jint {
-    support_ptr.write(jmmOptionalSupport { _bitfield_align_1: [], _bitfield_1: Default::default() });
+    let mut support = jmmOptionalSupport { _bitfield_align_1: [], _bitfield_1: Default::default() };
+    support.set_isCurrentThreadCpuTimeSupported(1);
+    support.set_isOtherThreadCpuTimeSupported(1);
+    support.set_isThreadAllocatedMemorySupported(1);
+    support_ptr.write(support);
     JNI_OK as i32
 }
 
@@ -21,8 +43,14 @@ pub unsafe extern "C" fn get_optional_support(_env: *mut JNIEnv, support_ptr: *m
 pub unsafe extern "C" fn get_long_attribute(env: *mut JNIEnv, _obj: jobject, att: jmmLongAttribute) -> jlong {
     let jvm = get_state(env);
     match att {
-        jmmLongAttribute_JMM_CLASS_LOADED_COUNT => todo!(),
-        jmmLongAttribute_JMM_CLASS_UNLOADED_COUNT => todo!(),
+        jmmLongAttribute_JMM_CLASS_LOADED_COUNT => {
+            //total ever loaded, including those since unloaded
+            let classes = jvm.classes.read().unwrap();
+            classes.loaded_class_count() as jlong + classes.unloaded_class_count as jlong
+        }
+        jmmLongAttribute_JMM_CLASS_UNLOADED_COUNT => {
+            jvm.classes.read().unwrap().unloaded_class_count as jlong
+        }
         jmmLongAttribute_JMM_THREAD_TOTAL_COUNT => todo!(),
         jmmLongAttribute_JMM_THREAD_LIVE_COUNT => todo!(),
         jmmLongAttribute_JMM_THREAD_PEAK_COUNT => todo!(),
@@ -63,19 +91,115 @@ pub unsafe extern "C" fn get_long_attribute(env: *mut JNIEnv, _obj: jobject, att
 }
 
 #[allow(non_upper_case_globals)]
-pub unsafe extern "C" fn get_bool_attribute(_env: *mut JNIEnv, att: jmmBoolAttribute) -> jboolean {
+pub unsafe extern "C" fn get_bool_attribute(env: *mut JNIEnv, att: jmmBoolAttribute) -> jboolean {
+    let jvm = get_state(env);
     match att {
         jmmBoolAttribute_JMM_VERBOSE_GC => todo!(),
         jmmBoolAttribute_JMM_VERBOSE_CLASS => todo!(),
         jmmBoolAttribute_JMM_THREAD_CONTENTION_MONITORING => false as jboolean,
-        jmmBoolAttribute_JMM_THREAD_CPU_TIME => false as jboolean,
-        jmmBoolAttribute_JMM_THREAD_ALLOCATED_MEMORY => false as jboolean,
+        jmmBoolAttribute_JMM_THREAD_CPU_TIME => jvm.thread_state.thread_cpu_time_enabled.load(Ordering::SeqCst) as jboolean,
+        jmmBoolAttribute_JMM_THREAD_ALLOCATED_MEMORY => jvm.thread_state.thread_allocated_memory_enabled.load(Ordering::SeqCst) as jboolean,
         _ => {
             panic!()
         }
     }
 }
 
+// returns the previous value
+#[allow(non_upper_case_globals)]
+pub unsafe extern "C" fn set_bool_attribute(env: *mut JNIEnv, att: jmmBoolAttribute, flag: jboolean) -> jboolean {
+    let jvm = get_state(env);
+    match att {
+        jmmBoolAttribute_JMM_THREAD_CPU_TIME => jvm.thread_state.thread_cpu_time_enabled.swap(flag != 0, Ordering::SeqCst) as jboolean,
+        jmmBoolAttribute_JMM_THREAD_ALLOCATED_MEMORY => jvm.thread_state.thread_allocated_memory_enabled.swap(flag != 0, Ordering::SeqCst) as jboolean,
+        //the rest can't be turned on, so setting them changes nothing and they were off before
+        _ => false as jboolean,
+    }
+}
+
+// a thread id of zero means the current thread
+fn thread_by_id<'gc>(jvm: &'gc JVMState<'gc>, thread_id: jlong) -> Option<Arc<JavaThread<'gc>>> {
+    let java_thread = if thread_id == 0 {
+        jvm.thread_state.try_get_current_thread()?
+    } else {
+        jvm.thread_state.try_get_thread_by_tid(thread_id)?
+    };
+    if java_thread.invisible_to_java || !java_thread.is_alive() {
+        return None;
+    }
+    Some(java_thread)
+}
+
+// nanoseconds, or -1 if there is no such live thread
+fn thread_cpu_time<'gc>(jvm: &'gc JVMState<'gc>, thread_id: jlong, user_sys_cpu_time: jboolean) -> jlong {
+    let cpu_time = thread_by_id(jvm, thread_id).and_then(|java_thread| {
+        if user_sys_cpu_time != 0 {
+            java_thread.cpu_time()
+        } else {
+            java_thread.user_cpu_time()
+        }
+    });
+    match cpu_time {
+        None => -1,
+        Some(cpu_time) => cpu_time.as_nanos() as jlong,
+    }
+}
+
+fn thread_allocated_memory<'gc>(jvm: &'gc JVMState<'gc>, thread_id: jlong) -> jlong {
+    match thread_by_id(jvm, thread_id) {
+        None => -1,
+        Some(java_thread) => java_thread.allocated_bytes() as jlong,
+    }
+}
+
+// fills each element of res with the value for the thread with the id at the same index in ids
+unsafe fn for_each_thread_id(env: *mut JNIEnv, ids: jlongArray, res: jlongArray, value_for_id: impl Fn(jlong) -> jlong) {
+    let jvm = get_state(env);
+    let int_state = get_interpreter_state(env);
+    let (ids, res) = match (from_object_new(jvm, ids), from_object_new(jvm, res)) {
+        (Some(ids), Some(res)) => (ids, res),
+        _ => {
+            throw_npe::<()>(jvm, int_state, get_throw(env));
+            return;
+        }
+    };
+    let ids = ids.unwrap_array();
+    let res = res.unwrap_array();
+    if ids.len() != res.len() {
+        match IllegalArgumentException::new(jvm, int_state) {
+            Ok(illegal_arg) => *get_throw(env) = Some(WasException { exception_obj: illegal_arg.object().cast_throwable() }),
+            Err(was_exception) => *get_throw(env) = Some(was_exception),
+        }
+        return;
+    }
+    for i in 0..ids.len() {
+        let value = value_for_id(ids.get_i(i).unwrap_long_strict());
+        res.set_i(i, NewJavaValue::Long(value));
+    }
+}
+
+pub unsafe extern "C" fn get_thread_cpu_time(env: *mut JNIEnv, thread_id: jlong) -> jlong {
+    thread_cpu_time(get_state(env), thread_id, true as jboolean)
+}
+
+pub unsafe extern "C" fn get_thread_cpu_time_with_kind(env: *mut JNIEnv, thread_id: jlong, user_sys_cpu_time: jboolean) -> jlong {
+    thread_cpu_time(get_state(env), thread_id, user_sys_cpu_time)
+}
+
+pub unsafe extern "C" fn get_thread_cpu_times_with_kind(env: *mut JNIEnv, ids: jlongArray, time_array: jlongArray, user_sys_cpu_time: jboolean) {
+    let jvm = get_state(env);
+    for_each_thread_id(env, ids, time_array, |thread_id| thread_cpu_time(jvm, thread_id, user_sys_cpu_time))
+}
+
+pub unsafe extern "C" fn get_one_thread_allocated_memory(env: *mut JNIEnv, thread_id: jlong) -> jlong {
+    thread_allocated_memory(get_state(env), thread_id)
+}
+
+pub unsafe extern "C" fn get_thread_allocated_memory(env: *mut JNIEnv, ids: jlongArray, size_array: jlongArray) {
+    let jvm = get_state(env);
+    for_each_thread_id(env, ids, size_array, |thread_id| thread_allocated_memory(jvm, thread_id))
+}
+
 
 pub unsafe extern "C" fn get_input_argument_array(env: *mut JNIEnv) -> jobjectArray {
     let jvm = get_state(env as *mut JNIEnv);
@@ -83,10 +207,34 @@ pub unsafe extern "C" fn get_input_argument_array(env: *mut JNIEnv) -> jobjectAr
     new_local_ref_public_new(Some(jvm.program_args_array.get().unwrap().as_allocated_obj()), int_state) as jobject
 }
 
+// Thread[] of every thread in a deadlock, or null if there are none. Only object monitors are tracked,
+// so the result is the same whether or not ownable synchronizers are asked for.
+pub unsafe extern "C" fn find_deadlocks(env: *mut JNIEnv, _object_monitors_only: jboolean) -> jobjectArray {
+    let jvm = get_state(env);
+    let int_state = get_interpreter_state(env);
+    let deadlocked = find_monitor_deadlocks(jvm).into_iter()
+        .flatten()
+        .flat_map(|waiting_to_lock| waiting_to_lock.thread.try_thread_object())
+        .collect_vec();
+    if deadlocked.is_empty() {
+        return null_mut();
+    }
+    if let Err(WasException { exception_obj }) = check_initing_or_inited_class(jvm, int_state, CPDType::array(CClassName::thread().into())) {
+        *get_throw(env) = Some(WasException { exception_obj });
+        return null_mut();
+    }
+    let thread_array = JavaValue::new_vec_from_vec(jvm, deadlocked.iter().map(|thread| thread.new_java_value()).collect(), CClassName::thread().into());
+    new_local_ref_public_new(Some(thread_array.as_allocated_obj()), int_state)
+}
+
+pub unsafe extern "C" fn find_circular_blocked_threads(env: *mut JNIEnv) -> jobjectArray {
+    find_deadlocks(env, true as jboolean)
+}
+
 pub fn initial_jmm() -> JMMInterfaceNamedReservedPointers {
     JMMInterfaceNamedReservedPointers {
         jvm_state: null_mut(),
-        GetOneThreadAllocatedMemory: None,
+        GetOneThreadAllocatedMemory: Some(get_one_thread_allocated_memory),
         GetVersion: Some(get_version),
         GetOptionalSupport: Some(get_optional_support),
         GetInputArguments: None,
@@ -96,14 +244,14 @@ pub fn initial_jmm() -> JMMInterfaceNamedReservedPointers {
         GetMemoryManagers: None,
         GetMemoryPoolUsage: None,
         GetPeakMemoryPoolUsage: None,
-        GetThreadAllocatedMemory: None,
+        GetThreadAllocatedMemory: Some(get_thread_allocated_memory),
         GetMemoryUsage: None,
         GetLongAttribute: Some(get_long_attribute),
         GetBoolAttribute: Some(get_bool_attribute),
-        SetBoolAttribute: None,
+        SetBoolAttribute: Some(set_bool_attribute),
         GetLongAttributes: None,
-        FindCircularBlockedThreads: None,
-        GetThreadCpuTime: None,
+        FindCircularBlockedThreads: Some(find_circular_blocked_threads),
+        GetThreadCpuTime: Some(get_thread_cpu_time),
         GetVMGlobalNames: None,
         GetVMGlobals: None,
         GetInternalThreadTimes: None,
@@ -113,10 +261,10 @@ pub fn initial_jmm() -> JMMInterfaceNamedReservedPointers {
         GetPoolCollectionUsage: None,
         GetGCExtAttributeInfo: None,
         GetLastGCStat: None,
-        GetThreadCpuTimeWithKind: None,
-        GetThreadCpuTimesWithKind: None,
+        GetThreadCpuTimeWithKind: Some(get_thread_cpu_time_with_kind),
+        GetThreadCpuTimesWithKind: Some(get_thread_cpu_times_with_kind),
         DumpHeap0: None,
-        FindDeadlocks: None,
+        FindDeadlocks: Some(find_deadlocks),
         SetVMGlobal: None,
         DumpThreadsMaxDepth: None,
         DumpThreads: None,
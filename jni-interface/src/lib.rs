@@ -20,7 +20,7 @@ use jvmti_jni_bindings::{jboolean, jbyte, jchar, jclass, jfieldID, jint, jmethod
 
 use runtime_class_stuff::{ClassStatus, RuntimeClass, RuntimeClassClass};
 use rust_jvm_common::classfile::Classfile;
-use rust_jvm_common::compressed_classfile::class_names::CClassName;
+use rust_jvm_common::compressed_classfile::class_names::{CClassName, CompressedClassName};
 use rust_jvm_common::compressed_classfile::compressed_types::{CMethodDescriptor, CPDType};
 use rust_jvm_common::compressed_classfile::field_names::FieldName;
 use rust_jvm_common::compressed_classfile::method_names::MethodName;
@@ -28,12 +28,10 @@ use rust_jvm_common::compressed_classfile::method_names::MethodName;
 
 use rust_jvm_common::FieldId;
 use rust_jvm_common::loading::{ClassLoadingError, ClassWithLoader, LoaderName};
-use stage0::compiler_common::frame_data::SunkVerifierFrames;
-use verification::{VerifierContext, verify};
-use verification::verifier::TypeSafetyError;
+use verification::VerifierContext;
 
 use slow_interpreter::better_java_stack::opaque_frame::OpaqueFrame;
-use slow_interpreter::class_loading::{check_initing_or_inited_class, ClassIntrinsicsData, create_class_object, get_static_var_types};
+use slow_interpreter::class_loading::{check_initing_or_inited_class, check_loaded_class_force_loader, ClassIntrinsicsData, create_class_object, get_static_var_types, throw_class_loading_error, verify_class};
 use slow_interpreter::class_objects::get_or_create_class_object_force_loader;
 use slow_interpreter::exceptions::WasException;
 use slow_interpreter::interpreter_util::new_object;
@@ -42,9 +40,9 @@ use slow_interpreter::new_java_values::NewJavaValueHandle;
 use slow_interpreter::runtime_class::{initialize_class, prepare_class};
 use slow_interpreter::better_java_stack::frames::PushableFrame;
 use slow_interpreter::jvm_state::JVMState;
+use slow_interpreter::loader_constraints::{impose_overriding_constraints, throw_loader_constraint_violation};
 use slow_interpreter::rust_jni::jni_utils::{get_interpreter_state, get_state, get_throw};
 use slow_interpreter::rust_jni::native_util::{from_jclass, from_object, from_object_new, to_object, to_object_new};
-use slow_interpreter::stdlib::java::lang::class_not_found_exception::ClassNotFoundException;
 use slow_interpreter::stdlib::java::lang::reflect::method::Method;
 use slow_interpreter::stdlib::java::lang::string::JString;
 use slow_interpreter::stdlib::java::NewAsObjectOrJavaValue;
@@ -52,7 +50,6 @@ use slow_interpreter::utils::{field_object_from_view, pushable_frame_todo, throw
 use crate::call::VarargProvider;
 use itertools::Itertools;
 use classfile_parser::parse_class_file;
-use java5_verifier::type_infer;
 use slow_interpreter::new_java_values::owned_casts::OwnedCastAble;
 use slow_interpreter::static_vars::static_vars;
 
@@ -416,8 +413,18 @@ pub fn define_class_safe<'gc, 'l>(
 ) -> Result<NewJavaValueHandle<'gc>, WasException<'gc>> {
     let class_name = class_view.name().unwrap_name();
     let class_view = Arc::new(class_view);
-    let super_class = class_view.super_name().map(|name| check_initing_or_inited_class(jvm, int_state, name.into()).unwrap());
-    let interfaces = class_view.interfaces().map(|interface| check_initing_or_inited_class(jvm, int_state, interface.interface_name().into()).unwrap()).collect_vec();
+    let super_class = match class_view.super_name() {
+        Some(name) => {
+            check_loaded_class_force_loader(jvm, int_state, &name.into(), current_loader)?;
+            Some(check_initing_or_inited_class(jvm, int_state, name.into())?)
+        }
+        None => None,
+    };
+    let mut interfaces = vec![];
+    for interface in class_view.interfaces() {
+        check_loaded_class_force_loader(jvm, int_state, &interface.interface_name().into(), current_loader)?;
+        interfaces.push(check_initing_or_inited_class(jvm, int_state, interface.interface_name().into())?);
+    }
     let static_var_types = get_static_var_types(class_view.deref());
     let runtime_class = Arc::new(RuntimeClass::Object(
         RuntimeClassClass::new_new(&jvm.inheritance_tree, &jvm.all_the_static_fields, &mut jvm.bit_vec_paths.write().unwrap(), class_view.clone(), super_class, interfaces, RwLock::new(ClassStatus::UNPREPARED), &jvm.string_pool, &jvm.class_ids)
@@ -427,51 +434,17 @@ pub fn define_class_safe<'gc, 'l>(
     class_view_cache.insert(ClassWithLoader { class_name, loader: current_loader }, class_view.clone() as Arc<dyn ClassView>);
     let mut vf = VerifierContext {
         live_pool_getter: jvm.get_live_object_pool_getter(),
-        classfile_getter: jvm.get_class_getter(int_state.current_loader(jvm)),
+        classfile_getter: jvm.get_class_getter(),
         string_pool: &jvm.string_pool,
         current_class: class_name,
         class_view_cache: Mutex::new(class_view_cache),
-        current_loader: LoaderName::BootstrapLoader, //todo
+        current_loader,
         verification_types: Default::default(),
         debug: false,
         perf_metrics: &jvm.perf_metrics,
         permissive_types_workaround: false,
     };
-    match verify(&mut vf, class_name, LoaderName::BootstrapLoader /*todo*/) {
-        Ok(_) => {
-            jvm.sink_function_verification_date(&vf.verification_types, runtime_class.clone());
-        }
-        Err(TypeSafetyError::ClassNotFound(ClassLoadingError::ClassNotFoundException(class_name))) => {
-            dbg!(&class_name);
-            let class = JString::from_rust(jvm, pushable_frame_todo(), Wtf8Buf::from_str(class_name.get_referred_name()))?;
-            let to_throw = ClassNotFoundException::new(jvm, int_state, class)?.object().new_java_handle().unwrap_object().unwrap();
-            todo!();// int_state.set_throw(Some(to_throw));
-            return Err(WasException { exception_obj: todo!() });
-        }
-        Err(TypeSafetyError::NotSafe(msg)) => {
-            dbg!(&msg);
-            panic!()
-        }
-        Err(TypeSafetyError::Java5Maybe) => {
-            //todo check for privileged here
-            for method_view in class_view.methods() {
-                let method_id = jvm.method_table.write().unwrap().get_method_id(runtime_class.clone(), method_view.method_i());
-                let code = method_view.code_attribute().unwrap();
-                let instructs = code.instructions.iter().sorted_by_key(|(offset, instruct)| *offset).map(|(_, instruct)| instruct.clone()).collect_vec();
-                let res = type_infer(&method_view);
-                let frames_tops = res.inferred_frames().iter().map(|(offset, frame)| {
-                    (*offset, SunkVerifierFrames::PartialInferredFrame(frame.clone()))
-                }).collect::<HashMap<_, _>>();
-                let frames_no_tops = res.inferred_frames().iter().map(|(offset, frame)| {
-                    (*offset, SunkVerifierFrames::PartialInferredFrame(frame.no_tops()))
-                }).collect::<HashMap<_, _>>();
-                jvm.function_frame_type_data.write().unwrap().no_tops.insert(method_id, frames_no_tops);
-                jvm.function_frame_type_data.write().unwrap().tops.insert(method_id, frames_tops);
-            }
-        }
-        Err(TypeSafetyError::ClassNotFound(ClassLoadingError::ClassFileInvalid(_))) => panic!(),
-        Err(TypeSafetyError::ClassNotFound(ClassLoadingError::ClassVerificationError)) => panic!(),
-    };
+    verify_class(jvm, int_state, &mut vf, class_name, current_loader, &runtime_class, class_view.deref())?;
     let class_object = create_class_object(jvm, int_state, None, current_loader, ClassIntrinsicsData {
         is_array: false,
         is_primitive: false,
@@ -479,11 +452,16 @@ pub fn define_class_safe<'gc, 'l>(
         this_cpdtype: class_name.into(),
     })?;
     let mut classes = jvm.classes.write().unwrap();
+    if let Err(violation) = classes.record_initiating_loader(current_loader, runtime_class.clone()) {
+        drop(classes);
+        return throw_loader_constraint_violation(jvm, int_state, violation);
+    }
+    classes.defining_loaders.insert(ByAddress(runtime_class.clone()), current_loader);
     classes.anon_classes.push(runtime_class.clone());
-    classes.initiating_loaders.insert(class_name.clone().into(), (current_loader, runtime_class.clone()));
-    classes.loaded_classes_by_type.entry(current_loader).or_insert(HashMap::new()).insert(class_name.clone().into(), runtime_class.clone());
     classes.class_object_pool.insert(ByAddressAllocatedObject::Owned(class_object.duplicate_discouraged()), ByAddress(runtime_class.clone()));
+    classes.record_implementor(&runtime_class);
     drop(classes);
+    impose_overriding_constraints(jvm, int_state, &runtime_class, current_loader)?;
     assert_eq!(class_object.runtime_class(jvm).cpdtype(), CClassName::class().into());
     prepare_class(jvm, int_state, Arc::new(ClassBackedView::from(parsed.clone(), &jvm.string_pool)), &mut static_vars(runtime_class.deref(), jvm));
     runtime_class.set_status(ClassStatus::PREPARED);
@@ -496,13 +474,22 @@ pub fn define_class_safe<'gc, 'l>(
 pub unsafe extern "C" fn define_class(env: *mut JNIEnv, name: *const ::std::os::raw::c_char, loader: jobject, buf: *const jbyte, len: jsize) -> jclass {
     let int_state = get_interpreter_state(env);
     let jvm = get_state(env);
-    let _name_string = CStr::from_ptr(name).to_str().unwrap(); //todo unused?
+    let name_string = CStr::from_ptr(name).to_str().unwrap();
     let loader_name = match from_object_new(jvm, loader) {
         Some(loader_obj) => NewJavaValueHandle::Object(loader_obj).cast_class_loader().to_jvm_loader(jvm),
         None => LoaderName::BootstrapLoader,
     };
     let slice = std::slice::from_raw_parts(buf as *const u8, len as usize);
-    let parsed = Arc::new(parse_class_file(&mut Cursor::new(slice)).expect("todo handle invalid"));
+    let parsed = Arc::new(match parse_class_file(&mut Cursor::new(slice)) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            let class_name = CompressedClassName(jvm.string_pool.add_name(name_string.replace('.', "/"), true));
+            if let Err(WasException { exception_obj }) = throw_class_loading_error::<()>(jvm, int_state, class_name, ClassLoadingError::ClassFileInvalid(err)) {
+                *get_throw(env) = Some(WasException { exception_obj });
+            }
+            return null_mut();
+        }
+    });
     let view = Arc::new(ClassBackedView::from(parsed.clone(), &jvm.string_pool));
     if jvm.config.store_generated_classes {
         File::create(format!("{}{:?}.class", PTypeView::from_compressed(view.name().to_cpdtype(), &jvm.string_pool).class_name_representation(), rand())).unwrap().write_all(slice).unwrap();
@@ -511,7 +498,10 @@ pub unsafe extern "C" fn define_class(env: *mut JNIEnv, name: *const ::std::os::
     to_object_new(
         match define_class_safe(jvm, int_state, parsed.clone(), loader_name, ClassBackedView::from(parsed, &jvm.string_pool)) {
             Ok(class_) => class_,
-            Err(_) => todo!(),
+            Err(WasException { exception_obj }) => {
+                *get_throw(env) = Some(WasException { exception_obj });
+                return null_mut();
+            }
         }
             .unwrap_object().unwrap().as_allocated_obj().into(),
     )
@@ -110,7 +110,7 @@ pub unsafe extern "C" fn is_assignable_from<'gc, 'l>(env: *mut JNIEnv, sub: jcla
     //todo should this be current loader?
     let vf = VerifierContext {
         live_pool_getter: jvm.get_live_object_pool_getter(),
-        classfile_getter: jvm.get_class_getter(int_state.current_loader(jvm)),
+        classfile_getter: jvm.get_class_getter(),
         string_pool: &jvm.string_pool,
         current_class: CClassName::invalid(),
         class_view_cache: Mutex::new(Default::default()),
@@ -0,0 +1,83 @@
+use crate::AssertionDirective;
+
+/// What -ea/-da/-esa/-dsa add up to, following hotspot's JavaAssertions. A class or package directive
+/// beats the defaults whatever order they were given in, and among directives for the same class or
+/// package the last one wins.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct AssertionStatus {
+    pub user_default: bool,
+    pub system_default: bool,
+    //in command line order, `.` separated
+    pub classes: Vec<(String, bool)>,
+    pub packages: Vec<(String, bool)>,
+}
+
+impl AssertionStatus {
+    pub fn from_directives(directives: &[AssertionDirective]) -> Self {
+        let mut res = Self::default();
+        for directive in directives {
+            match directive {
+                AssertionDirective::All(enable) => res.user_default = *enable,
+                AssertionDirective::SystemClasses(enable) => res.system_default = *enable,
+                AssertionDirective::Package(package, enable) => res.packages.push((package.clone(), *enable)),
+                AssertionDirective::Class(class, enable) => res.classes.push((class.clone(), *enable)),
+            }
+        }
+        res
+    }
+
+    fn class_directive(&self, class_name: &str) -> Option<bool> {
+        self.classes.iter().rev().find(|(name, _)| name == class_name).map(|(_, enable)| *enable)
+    }
+
+    // The most specific package wins, and a class with no package only matches -ea:...
+    fn package_directive(&self, class_name: &str) -> Option<bool> {
+        let mut package = match class_name.rfind('.') {
+            None => return self.packages.iter().rev().find(|(name, _)| name.is_empty()).map(|(_, enable)| *enable),
+            Some(last_dot) => &class_name[..last_dot],
+        };
+        loop {
+            if let Some((_, enable)) = self.packages.iter().rev().find(|(name, _)| name == package) {
+                return Some(*enable);
+            }
+            match package.rfind('.') {
+                None => return None,
+                Some(last_dot) => package = &package[..last_dot],
+            }
+        }
+    }
+
+    /// class_name is `.` separated, system classes are those loaded by the bootstrap loader.
+    pub fn enabled(&self, class_name: &str, system_class: bool) -> bool {
+        self.class_directive(class_name)
+            .or_else(|| self.package_directive(class_name))
+            .unwrap_or(if system_class { self.system_default } else { self.user_default })
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::assertions::AssertionStatus;
+    use crate::AssertionDirective;
+
+    #[test]
+    pub fn test_enabled() {
+        let status = AssertionStatus::from_directives(&[
+            AssertionDirective::Package("com.example".to_string(), true),
+            AssertionDirective::Class("com.example.internal.Quiet".to_string(), false),
+            AssertionDirective::Package("com.example.internal".to_string(), false),
+            AssertionDirective::Package("com.example.internal".to_string(), true),
+            AssertionDirective::All(false),
+            AssertionDirective::Package("".to_string(), true),
+            AssertionDirective::SystemClasses(true),
+        ]);
+        assert!(status.enabled("com.example.Main", false));
+        assert!(status.enabled("com.example.sub.Helper", false));
+        assert!(status.enabled("com.example.internal.Loud", false));
+        assert!(!status.enabled("com.example.internal.Quiet", false));
+        assert!(!status.enabled("org.other.Main", false));
+        assert!(status.enabled("NoPackage", false));
+        assert!(status.enabled("java.lang.Object", true));
+        assert!(!status.enabled("com.examples.Main", false));
+    }
+}
@@ -0,0 +1,563 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+use crate::{AgentLibrary, AgentOption, AssertionDirective, JVMArgs, ShareMode, VerifyMode};
+
+// Parses the standard `java` launcher syntax, so scripts and tools written for hotspot can start this vm.
+
+pub const DEFAULT_COMPILE_THRESHOLD: u64 = 1000;
+
+#[derive(Debug)]
+pub enum LauncherCommand {
+    Run {
+        jvm_args: Box<JVMArgs>,
+        show_version: bool,
+    },
+    //-version, -help and friends print something and exit without starting the vm
+    Exit {
+        message: String,
+        code: i32,
+    },
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct LauncherError(pub String);
+
+impl Display for LauncherError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for LauncherError {}
+
+pub fn version_message() -> String {
+    format!("java version \"1.8.0\"\nrust-jvm (build {})\n", env!("CARGO_PKG_VERSION"))
+}
+
+pub fn usage_message() -> String {
+    "Usage: java [-options] class [args...]
+           (to execute a class)
+   or  java [-options] -jar jarfile [args...]
+           (to execute a jar file)
+where options include:
+    -cp <class search path of directories and zip/jar files>
+    -classpath <class search path of directories and zip/jar files>
+                  A : separated list of directories, JAR archives,
+                  and ZIP archives to search for class files.
+    -D<name>=<value>
+                  set a system property
+    -verbose:class
+                  enable verbose output
+    -version      print product version and exit
+    -showversion  print product version and continue
+    -? -help      print this help message
+    -X            print help on non-standard options
+    -ea[:<packagename>...|:<classname>]
+    -enableassertions[:<packagename>...|:<classname>]
+                  enable assertions with specified granularity
+    -da[:<packagename>...|:<classname>]
+    -disableassertions[:<packagename>...|:<classname>]
+                  disable assertions with specified granularity
+    -esa | -enablesystemassertions
+                  enable system assertions
+    -dsa | -disablesystemassertions
+                  disable system assertions
+    -agentlib:<libname>[=<options>]
+                  load native agent library <libname>, e.g. -agentlib:jdwp
+    -agentpath:<pathname>[=<options>]
+                  load native agent library by full pathname
+    -javaagent:<jarpath>[=<options>]
+                  load Java programming language agent, see java.lang.instrument
+    @<filepath>   read options from the specified file
+".to_string()
+}
+
+pub fn extended_help_message() -> String {
+    "    -Xint             interpreted mode execution only
+    -Xcomp            compile every method before it first runs
+    -Xmixed           interpret methods until they are hot, then compile them (default)
+    -Xms<size>        set initial Java heap size
+    -Xmx<size>        set maximum Java heap size
+    -Xss<size>        set java thread stack size
+    -Xrunjdwp:<opts>  the same as -agentlib:jdwp=<opts>
+    -Xverify:<none|remote|all>
+                      reject no classes, classes outside the boot classpath (default),
+                      or all classes failing bytecode verification
+    -Xshare:off       do not use a class data sharing archive
+    -Xshare:auto      use the class data sharing archive if it is valid (default)
+    -Xshare:on        require the class data sharing archive
+    -Xshare:dump      write the class data sharing archive for -XX:SharedClassListFile and exit
+
+The -X options are non-standard and subject to change without notice.
+".to_string()
+}
+
+/// Sizes like 512m or 2G, as taken by -Xmx and friends.
+pub fn parse_memory_size(size: &str) -> Option<u64> {
+    let (digits, multiplier) = match size.chars().last()? {
+        'k' | 'K' => (&size[..size.len() - 1], 1024),
+        'm' | 'M' => (&size[..size.len() - 1], 1024 * 1024),
+        'g' | 'G' => (&size[..size.len() - 1], 1024 * 1024 * 1024),
+        't' | 'T' => (&size[..size.len() - 1], 1024 * 1024 * 1024 * 1024),
+        _ => (size, 1),
+    };
+    digits.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+/// java.home is the directory above the bin directory holding the launcher, or the jre directory inside it for a jdk 8.
+pub fn java_home_from_executable(executable: &Path) -> Option<PathBuf> {
+    let home = executable.parent()?.parent()?;
+    java_home_from_dir(home)
+}
+
+fn java_home_from_dir(home: &Path) -> Option<PathBuf> {
+    let jre = home.join("jre");
+    if is_java_home(&jre) {
+        return Some(jre);
+    }
+    if is_java_home(home) {
+        return Some(home.to_path_buf());
+    }
+    None
+}
+
+//jdk 8 keeps the bootstrap classes in lib/rt.jar, later jdks in the lib/modules image
+fn is_java_home(dir: &Path) -> bool {
+    dir.join("lib/rt.jar").is_file() || dir.join("lib/modules").is_file()
+}
+
+// Argument files split on whitespace, with quotes grouping and `#` starting a comment at the start of a token.
+fn tokenize_argfile(contents: &str) -> Vec<String> {
+    let mut res = vec![];
+    let mut chars = contents.chars().peekable();
+    let mut current: Option<String> = None;
+    while let Some(char) = chars.next() {
+        match char {
+            '#' if current.is_none() => {
+                for char in chars.by_ref() {
+                    if char == '\n' || char == '\r' {
+                        break;
+                    }
+                }
+            }
+            '"' | '\'' => {
+                let quote = char;
+                let token = current.get_or_insert_with(String::new);
+                while let Some(char) = chars.next() {
+                    match char {
+                        char if char == quote => break,
+                        '\\' => match chars.next() {
+                            Some('n') => token.push('\n'),
+                            Some('t') => token.push('\t'),
+                            Some('r') => token.push('\r'),
+                            Some('f') => token.push('\u{c}'),
+                            //a backslash at the end of a line continues the quoted token on the next line, minus leading whitespace
+                            Some('\n') | Some('\r') => {
+                                while chars.peek().map(|char| char.is_whitespace()).unwrap_or(false) {
+                                    chars.next();
+                                }
+                            }
+                            Some(other) => token.push(other),
+                            None => {}
+                        },
+                        other => token.push(other),
+                    }
+                }
+            }
+            char if char.is_whitespace() => {
+                if let Some(token) = current.take() {
+                    res.push(token);
+                }
+            }
+            other => current.get_or_insert_with(String::new).push(other),
+        }
+    }
+    if let Some(token) = current.take() {
+        res.push(token);
+    }
+    res
+}
+
+// JAVA_TOOL_OPTIONS split on whitespace, with quotes grouping like hotspot. Unlike argument files there are no
+// comments or escapes.
+fn tokenize_tool_options(options: &str) -> Vec<String> {
+    let mut res = vec![];
+    let mut chars = options.chars();
+    let mut current: Option<String> = None;
+    while let Some(char) = chars.next() {
+        match char {
+            '"' | '\'' => {
+                let quote = char;
+                let token = current.get_or_insert_with(String::new);
+                for char in chars.by_ref() {
+                    if char == quote {
+                        break;
+                    }
+                    token.push(char);
+                }
+            }
+            char if char.is_whitespace() => {
+                if let Some(token) = current.take() {
+                    res.push(token);
+                }
+            }
+            other => current.get_or_insert_with(String::new).push(other),
+        }
+    }
+    if let Some(token) = current.take() {
+        res.push(token);
+    }
+    res
+}
+
+//options only the launcher understands, which JAVA_TOOL_OPTIONS can't contain since the vm reads it
+fn is_launcher_only(arg: &str) -> bool {
+    !arg.starts_with('-') || matches!(arg, "-cp" | "-classpath" | "-jar" | "-version" | "-showversion" | "-help" | "-?" | "-h" | "-X")
+}
+
+fn parse_assertion_target(target: Option<&str>, enable: bool) -> AssertionDirective {
+    match target {
+        None | Some("") => AssertionDirective::All(enable),
+        Some(target) => match target.strip_suffix("...") {
+            //-ea:... is the unnamed package
+            Some(package) => AssertionDirective::Package(package.to_string(), enable),
+            None => AssertionDirective::Class(target.to_string(), enable),
+        },
+    }
+}
+
+fn parse_agent(library: AgentLibrary, spec: &str) -> AgentOption {
+    match spec.split_once('=') {
+        None => AgentOption { library, options: None },
+        Some((_, options)) => AgentOption { library, options: Some(options.to_string()) },
+    }
+}
+
+fn unrecognized(option: &str) -> LauncherError {
+    LauncherError(format!("Unrecognized option: {}\nError: Could not create the Java Virtual Machine.\nError: A fatal exception has occurred. Program will exit.", option))
+}
+
+/// `args` excludes the executable name. JAVA_TOOL_OPTIONS are parsed before the command line, so the command line wins.
+/// They can only hold vm options, not a main class, argument files or options like -jar.
+pub fn parse_launcher_args(args: Vec<String>, java_tool_options: Option<String>, executable: Option<PathBuf>, classpath_env: Option<String>, java_home_env: Option<PathBuf>) -> Result<LauncherCommand, LauncherError> {
+    let tool_options = java_tool_options.map(|options| tokenize_tool_options(options.as_str())).unwrap_or_default();
+    if let Some(option) = tool_options.iter().find(|option| is_launcher_only(option)) {
+        return Err(unrecognized(option));
+    }
+    let mut pending: VecDeque<String> = tool_options.into();
+    pending.extend(args);
+
+    let mut classpath: Option<String> = None;
+    let mut properties = vec![];
+    let mut assertion_directives = vec![];
+    let mut agents = vec![];
+    let mut initial_heap_size = None;
+    let mut max_heap_size = None;
+    let mut thread_stack_size = None;
+    let mut verbose_class = false;
+    let mut show_version = false;
+    let mut compile_threshold = DEFAULT_COMPILE_THRESHOLD;
+    let mut verify = VerifyMode::Remote;
+    let mut verify_bootstrap_classes = false;
+    let mut share = ShareMode::Auto;
+    let mut shared_archive_file = None;
+    let mut shared_class_list_file = None;
+    let mut main = None;
+    let mut jar = None;
+
+    while let Some(arg) = pending.pop_front() {
+        if let Some(escaped) = arg.strip_prefix("@@") {
+            //@@ escapes a leading @, so this is the main class
+            main = Some(format!("@{}", escaped));
+            break;
+        }
+        if let Some(argfile) = arg.strip_prefix('@') {
+            let contents = std::fs::read_to_string(argfile).map_err(|_| LauncherError(format!("Error: could not open `{}'", argfile)))?;
+            for token in tokenize_argfile(contents.as_str()).into_iter().rev() {
+                pending.push_front(token);
+            }
+            continue;
+        }
+        if !arg.starts_with('-') {
+            main = Some(arg);
+            break;
+        }
+        match arg.as_str() {
+            "-cp" | "-classpath" => {
+                classpath = Some(pending.pop_front().ok_or_else(|| LauncherError(format!("Error: {} requires class path specification", arg)))?);
+            }
+            "-jar" => {
+                jar = Some(PathBuf::from(pending.pop_front().ok_or_else(|| LauncherError("Error: -jar requires jar file specification".to_string()))?));
+                break;
+            }
+            "-version" => return Ok(LauncherCommand::Exit { message: version_message(), code: 0 }),
+            "-showversion" => show_version = true,
+            "-help" | "-?" | "-h" => return Ok(LauncherCommand::Exit { message: usage_message(), code: 0 }),
+            "-X" => return Ok(LauncherCommand::Exit { message: extended_help_message(), code: 0 }),
+            "-esa" | "-enablesystemassertions" => assertion_directives.push(AssertionDirective::SystemClasses(true)),
+            "-dsa" | "-disablesystemassertions" => assertion_directives.push(AssertionDirective::SystemClasses(false)),
+            "-verbose" | "-verbose:class" => verbose_class = true,
+            //nothing is ever collected, so there would be nothing to log
+            "-verbose:gc" => return Err(LauncherError("Error: -verbose:gc is not supported, there is no garbage collector".to_string())),
+            "-verbose:jni" => {}
+            "-Xint" => compile_threshold = u64::MAX,
+            "-Xcomp" => compile_threshold = 0,
+            "-Xmixed" => compile_threshold = DEFAULT_COMPILE_THRESHOLD,
+            "-Xverify:none" | "-noverify" => verify = VerifyMode::None,
+            "-Xverify:remote" => verify = VerifyMode::Remote,
+            "-Xverify:all" | "-Xverify" => verify = VerifyMode::All,
+            "-XX:+BytecodeVerificationLocal" => verify_bootstrap_classes = true,
+            "-XX:-BytecodeVerificationLocal" => verify_bootstrap_classes = false,
+            "-Xshare:off" => share = ShareMode::Off,
+            "-Xshare:auto" => share = ShareMode::Auto,
+            "-Xshare:on" => share = ShareMode::On,
+            "-Xshare:dump" => share = ShareMode::Dump,
+            "-client" | "-server" | "-d64" | "-Xbatch" | "-Xdebug" | "-Xnoagent" | "-Xrs" => {}
+            _ => {
+                if let Some(property) = arg.strip_prefix("-D") {
+                    match property.split_once('=') {
+                        None => properties.push((property.to_string(), "".to_string())),
+                        Some((key, value)) => properties.push((key.to_string(), value.to_string())),
+                    }
+                } else if let Some(target) = ["-ea", "-enableassertions"].iter().find_map(|prefix| arg.strip_prefix(prefix)) {
+                    match target.strip_prefix(':') {
+                        Some(target) => assertion_directives.push(parse_assertion_target(Some(target), true)),
+                        None if target.is_empty() => assertion_directives.push(parse_assertion_target(None, true)),
+                        None => return Err(unrecognized(arg.as_str())),
+                    }
+                } else if let Some(target) = ["-da", "-disableassertions"].iter().find_map(|prefix| arg.strip_prefix(prefix)) {
+                    match target.strip_prefix(':') {
+                        Some(target) => assertion_directives.push(parse_assertion_target(Some(target), false)),
+                        None if target.is_empty() => assertion_directives.push(parse_assertion_target(None, false)),
+                        None => return Err(unrecognized(arg.as_str())),
+                    }
+                } else if let Some(size) = arg.strip_prefix("-Xmx") {
+                    max_heap_size = Some(parse_memory_size(size).ok_or_else(|| LauncherError(format!("Invalid maximum heap size: {}", arg)))?);
+                } else if let Some(size) = arg.strip_prefix("-Xms") {
+                    initial_heap_size = Some(parse_memory_size(size).ok_or_else(|| LauncherError(format!("Invalid initial heap size: {}", arg)))?);
+                } else if let Some(size) = arg.strip_prefix("-Xss") {
+                    thread_stack_size = Some(parse_memory_size(size).ok_or_else(|| LauncherError(format!("Invalid thread stack size: {}", arg)))?);
+                } else if let Some(spec) = arg.strip_prefix("-agentlib:") {
+                    let name = spec.split_once('=').map(|(name, _)| name).unwrap_or(spec);
+                    agents.push(parse_agent(AgentLibrary::Name(name.to_string()), spec));
+                } else if let Some(spec) = arg.strip_prefix("-agentpath:") {
+                    let path = spec.split_once('=').map(|(path, _)| path).unwrap_or(spec);
+                    agents.push(parse_agent(AgentLibrary::Path(PathBuf::from(path)), spec));
+                } else if let Some(options) = arg.strip_prefix("-Xrunjdwp:") {
+                    agents.push(AgentOption { library: AgentLibrary::Name("jdwp".to_string()), options: Some(options.to_string()) });
+                } else if let Some(spec) = arg.strip_prefix("-javaagent:") {
+                    //java agents are loaded by the instrument native agent, which is passed the jar and its options
+                    agents.push(AgentOption { library: AgentLibrary::Name("instrument".to_string()), options: Some(spec.to_string()) });
+                } else if let Some(path) = arg.strip_prefix("-XX:SharedArchiveFile=") {
+                    shared_archive_file = Some(PathBuf::from(path));
+                } else if let Some(path) = arg.strip_prefix("-XX:SharedClassListFile=") {
+                    shared_class_list_file = Some(PathBuf::from(path));
+                } else if arg.starts_with("-XX:") {
+                    //hotspot tuning flags have no equivalent here
+                } else {
+                    return Err(unrecognized(arg.as_str()));
+                }
+            }
+        }
+    }
+    //agents are loaded, but jvmti events like VMInit, which every agent relies on, aren't delivered yet
+    if let Some(AgentOption { library, .. }) = agents.first() {
+        let library = match library {
+            AgentLibrary::Name(name) => name.clone(),
+            AgentLibrary::Path(path) => path.display().to_string(),
+        };
+        return Err(LauncherError(format!("Error: could not load agent library {}, jvmti agents are not supported yet", library)));
+    }
+    //dumping the archive runs no main class
+    if main.is_none() && jar.is_none() && share != ShareMode::Dump {
+        return Ok(LauncherCommand::Exit { message: usage_message(), code: 1 });
+    }
+    let java_home = executable.as_deref().and_then(java_home_from_executable)
+        .or_else(|| java_home_env.as_deref().and_then(java_home_from_dir))
+        .ok_or_else(|| LauncherError("Error: could not find java.home, set JAVA_HOME to a jdk or jre".to_string()))?;
+    //like java, -cp wins over CLASSPATH, which wins over the current directory
+    let classpath = classpath.or(classpath_env).unwrap_or_else(|| ".".to_string());
+    Ok(LauncherCommand::Run {
+        jvm_args: Box::new(JVMArgs {
+            java_home,
+            classpath: vec![PathBuf::from(classpath)],
+            jar,
+            main,
+            properties,
+            args: pending.into_iter().collect(),
+            enable_assertions: false,
+            debug_exceptions: false,
+            store_anon_class: false,
+            compile_threshold,
+            verify,
+            verify_bootstrap_classes,
+            share,
+            shared_archive_file,
+            shared_class_list_file,
+            assertion_directives,
+            initial_heap_size,
+            max_heap_size,
+            thread_stack_size,
+            verbose_class,
+            agents,
+        }),
+        show_version,
+    })
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::{AssertionDirective, ShareMode, VerifyMode};
+    use crate::launcher::{java_home_from_executable, LauncherCommand, LauncherError, parse_launcher_args, parse_memory_size, tokenize_argfile, tokenize_tool_options};
+
+    //just enough of a jre to be recognized as java.home, removed again when dropped
+    struct FakeJavaHome(PathBuf);
+
+    impl FakeJavaHome {
+        fn new() -> Self {
+            static NEXT: AtomicUsize = AtomicUsize::new(0);
+            let java_home = std::env::temp_dir().join(format!("launcher-test-java-home-{}-{}", std::process::id(), NEXT.fetch_add(1, Ordering::SeqCst)));
+            std::fs::create_dir_all(java_home.join("lib")).unwrap();
+            std::fs::write(java_home.join("lib/rt.jar"), []).unwrap();
+            Self(java_home)
+        }
+    }
+
+    impl Drop for FakeJavaHome {
+        fn drop(&mut self) {
+            std::fs::remove_dir_all(&self.0).unwrap();
+        }
+    }
+
+    fn try_parse(args: &[&str], java_tool_options: Option<&str>) -> Result<LauncherCommand, LauncherError> {
+        let java_home = FakeJavaHome::new();
+        parse_launcher_args(args.iter().map(|arg| arg.to_string()).collect(), java_tool_options.map(|options| options.to_string()), None, None, Some(java_home.0.clone()))
+    }
+
+    fn parse(args: &[&str], java_tool_options: Option<&str>) -> LauncherCommand {
+        try_parse(args, java_tool_options).unwrap()
+    }
+
+    #[test]
+    pub fn test_memory_size() {
+        assert_eq!(parse_memory_size("512m"), Some(512 * 1024 * 1024));
+        assert_eq!(parse_memory_size("2G"), Some(2 * 1024 * 1024 * 1024));
+        assert_eq!(parse_memory_size("1024"), Some(1024));
+        assert_eq!(parse_memory_size("m"), None);
+    }
+
+    #[test]
+    pub fn test_argfile_tokens() {
+        let tokens = tokenize_argfile("-cp \"a dir/x.jar\" # a comment\n-Dkey='a value' Main\n");
+        assert_eq!(tokens, vec!["-cp", "a dir/x.jar", "-Dkey=a value", "Main"]);
+    }
+
+    #[test]
+    pub fn test_tool_options() {
+        assert_eq!(tokenize_tool_options(" -Dkey=\"a value\"  -Xint '-Dother=x y'"), vec!["-Dkey=a value", "-Xint", "-Dother=x y"]);
+        match parse(&["-Dkey=command line", "Main"], Some("-Dkey='tool options' -Xss2m")) {
+            LauncherCommand::Run { jvm_args, .. } => {
+                assert_eq!(jvm_args.main.as_deref(), Some("Main"));
+                assert_eq!(jvm_args.thread_stack_size, Some(2 * 1024 * 1024));
+                //the command line comes later, so its value wins
+                assert_eq!(jvm_args.properties, vec![("key".to_string(), "tool options".to_string()), ("key".to_string(), "command line".to_string())]);
+            }
+            LauncherCommand::Exit { .. } => panic!(),
+        }
+        for tool_options in ["NotMain", "-jar app.jar", "-cp a.jar", "-version", "@argfile"] {
+            assert!(try_parse(&["Main"], Some(tool_options)).is_err(), "{}", tool_options);
+        }
+    }
+
+    #[test]
+    pub fn test_options() {
+        let command = parse(&["-cp", "a.jar:b", "-Dx=y", "-ea:com.example...", "-da:com.example.Other", "-esa", "-Xmx1g", "-Xss2m", "Main", "-Dnot.an.option"], Some("-Xint"));
+        match command {
+            LauncherCommand::Run { jvm_args, show_version } => {
+                assert!(!show_version);
+                assert_eq!(jvm_args.main.as_deref(), Some("Main"));
+                assert!(jvm_args.java_home.starts_with(std::env::temp_dir()));
+                assert_eq!(jvm_args.classpath, vec![PathBuf::from("a.jar:b")]);
+                assert_eq!(jvm_args.properties, vec![("x".to_string(), "y".to_string())]);
+                assert_eq!(jvm_args.args, vec!["-Dnot.an.option".to_string()]);
+                assert_eq!(jvm_args.assertion_directives, vec![
+                    AssertionDirective::Package("com.example".to_string(), true),
+                    AssertionDirective::Class("com.example.Other".to_string(), false),
+                    AssertionDirective::SystemClasses(true),
+                ]);
+                assert_eq!(jvm_args.max_heap_size, Some(1024 * 1024 * 1024));
+                assert_eq!(jvm_args.thread_stack_size, Some(2 * 1024 * 1024));
+                assert_eq!(jvm_args.compile_threshold, u64::MAX);
+                assert_eq!(jvm_args.agents, vec![]);
+            }
+            LauncherCommand::Exit { .. } => panic!(),
+        }
+    }
+
+    #[test]
+    pub fn test_verify_options() {
+        let verify_settings = |args: &[&str]| match parse(args, None) {
+            LauncherCommand::Run { jvm_args, .. } => (jvm_args.verify, jvm_args.verify_bootstrap_classes),
+            LauncherCommand::Exit { .. } => panic!(),
+        };
+        assert_eq!(verify_settings(&["Main"]), (VerifyMode::Remote, false));
+        assert_eq!(verify_settings(&["-Xverify:none", "Main"]), (VerifyMode::None, false));
+        assert_eq!(verify_settings(&["-Xverify:all", "-XX:+BytecodeVerificationLocal", "Main"]), (VerifyMode::All, true));
+    }
+
+    #[test]
+    pub fn test_share_options() {
+        match parse(&["-Xshare:dump", "-XX:SharedArchiveFile=/tmp/classes.jsa", "-XX:SharedClassListFile=/tmp/classlist"], None) {
+            LauncherCommand::Run { jvm_args, .. } => {
+                assert_eq!(jvm_args.share, ShareMode::Dump);
+                assert_eq!(jvm_args.main, None);
+                assert_eq!(jvm_args.shared_archive_file, Some(PathBuf::from("/tmp/classes.jsa")));
+                assert_eq!(jvm_args.shared_class_list_file, Some(PathBuf::from("/tmp/classlist")));
+            }
+            LauncherCommand::Exit { .. } => panic!(),
+        }
+        match parse(&["-Xshare:off", "Main"], None) {
+            LauncherCommand::Run { jvm_args, .. } => assert_eq!(jvm_args.share, ShareMode::Off),
+            LauncherCommand::Exit { .. } => panic!(),
+        }
+    }
+
+    #[test]
+    pub fn test_java_home_from_executable() {
+        let root = std::env::temp_dir().join(format!("launcher-test-java-homes-{}", std::process::id()));
+        //a jdk 8, where java.home is its jre
+        let jdk8 = root.join("jdk8");
+        std::fs::create_dir_all(jdk8.join("jre/lib")).unwrap();
+        std::fs::write(jdk8.join("jre/lib/rt.jar"), []).unwrap();
+        assert_eq!(java_home_from_executable(&jdk8.join("bin/java")), Some(jdk8.join("jre")));
+        //a later jdk has no jre directory, only the modules image
+        let jdk11 = root.join("jdk11");
+        std::fs::create_dir_all(jdk11.join("lib")).unwrap();
+        std::fs::write(jdk11.join("lib/modules"), []).unwrap();
+        assert_eq!(java_home_from_executable(&jdk11.join("bin/java")), Some(jdk11.clone()));
+        assert_eq!(java_home_from_executable(&root.join("bin/java")), None);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    pub fn test_exits() {
+        assert!(matches!(parse(&["-version"], None), LauncherCommand::Exit { code: 0, .. }));
+        assert!(matches!(parse(&["-X"], None), LauncherCommand::Exit { code: 0, .. }));
+        assert!(matches!(parse(&["-Dx=y"], None), LauncherCommand::Exit { code: 1, .. }));
+        assert!(try_parse(&["-bogus", "Main"], None).is_err());
+        assert!(try_parse(&["-verbose:gc", "Main"], None).is_err());
+    }
+
+    #[test]
+    pub fn test_agents_rejected() {
+        for agent in ["-agentlib:jdwp=transport=dt_socket", "-agentpath:/lib/libagent.so", "-Xrunjdwp:server=y", "-javaagent:agent.jar=opt"] {
+            assert!(try_parse(&[agent, "Main"], None).is_err(), "{}", agent);
+        }
+    }
+}
@@ -1,7 +1,10 @@
 use std::error::Error;
 use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{ArgEnum, Parser};
+
+pub mod assertions;
+pub mod launcher;
 
 /// Parse a single key-value pair
 fn parse_key_val(s: &str) -> Result<(String, String), Box<dyn Error + Send + Sync>>
@@ -13,19 +16,57 @@ fn parse_key_val(s: &str) -> Result<(String, String), Box<dyn Error + Send + Syn
     Ok((s[..pos].to_string(), s[pos + 1..].to_string()))
 }
 
+/// -ea/-da and -esa/-dsa, kept in command line order since later directives override earlier ones.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum AssertionDirective {
+    All(bool),
+    SystemClasses(bool),
+    //dot separated, empty for the unnamed package, covers subpackages too
+    Package(String, bool),
+    Class(String, bool),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum AgentLibrary {
+    //found as lib<name>.so in the jre's native library directory
+    Name(String),
+    Path(PathBuf),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AgentOption {
+    pub library: AgentLibrary,
+    pub options: Option<String>,
+}
+
+/// -Xverify, which classes the bytecode verifier rejects. Remote classes are those not loaded by the bootstrap loader.
+#[derive(ArgEnum, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum VerifyMode {
+    None,
+    Remote,
+    All,
+}
+
+/// -Xshare, whether bootstrap classes come from a class data sharing archive. Auto uses the archive when it exists and matches the java home.
+#[derive(ArgEnum, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ShareMode {
+    Off,
+    Auto,
+    On,
+    Dump,
+}
 
 #[derive(Parser, Debug, Clone)]
 #[clap(version)]
 pub struct JVMArgs {
     #[clap(long, help = "the java home for this jvm")]
     pub java_home: PathBuf,
-    #[clap(long, multiple = true, help = "the classpath")]
+    #[clap(long, multiple = true, help = "the classpath, directories and jar files searched in order. `dir/*` includes every jar in dir")]
     pub classpath: Vec<PathBuf>,
-    /*#[clap(long, help = "the jar to find a manifest in and run", conflicts_with = "main", required_unless = "main")]
-    jar: Option<PathBuf>,*/
-    //, conflicts_with = "jar", required_unless = "jar"
-    #[clap(long, help = "the main class")]
-    pub main: String,
+    #[clap(long, help = "the jar to find a manifest Main-Class in and run. Replaces the classpath", conflicts_with = "main", required_unless_present = "main")]
+    pub jar: Option<PathBuf>,
+    #[clap(long, help = "the main class", conflicts_with = "jar", required_unless_present = "jar")]
+    pub main: Option<String>,
     #[clap(long, help = "properties", parse(try_from_str = parse_key_val), number_of_values = 1)]
     pub properties: Vec<(String, String)>,
     #[clap(long, help = "args for java program")]
@@ -35,5 +76,30 @@ pub struct JVMArgs {
     #[clap(long, help = "Enable exception debug logging")]
     pub debug_exceptions: bool,
     #[clap(long, help = "Store anonymous classes")]
-    pub store_anon_class: bool
+    pub store_anon_class: bool,
+    #[clap(long, default_value = "1000", help = "instructions a method runs in the interpreter before it is compiled. 0 compiles everything up front")]
+    pub compile_threshold: u64,
+    #[clap(long, arg_enum, default_value = "remote", help = "which classes failing bytecode verification are rejected: none, remote (those not loaded by the bootstrap loader) or all")]
+    pub verify: VerifyMode,
+    #[clap(long, help = "reject bootstrap classes failing bytecode verification even when --verify is remote")]
+    pub verify_bootstrap_classes: bool,
+    #[clap(long, arg_enum, default_value = "auto", help = "class data sharing: off, auto (use the archive if it is valid), on (fail if it is not) or dump (write the archive for the class list and exit)")]
+    pub share: ShareMode,
+    #[clap(long, help = "the class data sharing archive, by default lib/rust-jvm-classes.jsa in the java home")]
+    pub shared_archive_file: Option<PathBuf>,
+    #[clap(long, help = "the classes to archive when dumping, one internal class name per line. By default lib/classlist in the java home")]
+    pub shared_class_list_file: Option<PathBuf>,
+    //the rest only come from the java launcher syntax, see launcher.rs
+    #[clap(skip)]
+    pub assertion_directives: Vec<AssertionDirective>,
+    #[clap(skip)]
+    pub initial_heap_size: Option<u64>,
+    #[clap(skip)]
+    pub max_heap_size: Option<u64>,
+    #[clap(skip)]
+    pub thread_stack_size: Option<u64>,
+    #[clap(skip)]
+    pub verbose_class: bool,
+    #[clap(skip)]
+    pub agents: Vec<AgentOption>,
 }
\ No newline at end of file
@@ -16,6 +16,8 @@ use slow_interpreter::rust_jni::jvmti::{get_state};
 // can_maintain_original_method_order      = 1
 // can_generate_monitor_events             = 1
 // can_tag_objects                         = 1
+// can_get_current_thread_cpu_time         = 1
+// can_get_thread_cpu_time                 = 1
 
 pub unsafe extern "C" fn get_potential_capabilities(env: *mut jvmtiEnv, capabilities_ptr: *mut jvmtiCapabilities) -> jvmtiError {
     let jvm = get_state(env);
@@ -65,9 +67,9 @@ pub unsafe extern "C" fn get_potential_capabilities(env: *mut jvmtiEnv, capabili
     //     unsigned int can_redefine_any_class : 1;
     (*capabilities_ptr).set_can_redefine_any_class(0);
     //     unsigned int can_get_current_thread_cpu_time : 1;
-    (*capabilities_ptr).set_can_get_current_thread_cpu_time(0);
+    (*capabilities_ptr).set_can_get_current_thread_cpu_time(1);
     //     unsigned int can_get_thread_cpu_time : 1;
-    (*capabilities_ptr).set_can_get_thread_cpu_time(0);
+    (*capabilities_ptr).set_can_get_thread_cpu_time(1);
     //     unsigned int can_generate_method_entry_events : 1;
     (*capabilities_ptr).set_can_generate_method_entry_events(1);
     //     unsigned int can_generate_method_exit_events : 1;
@@ -125,8 +127,6 @@ pub unsafe extern "C" fn add_capabilities(env: *mut jvmtiEnv, capabilities_ptr:
         (*capabilities_ptr).can_redefine_classes() > 0 ||
         (*capabilities_ptr).can_signal_thread() > 0 ||
         (*capabilities_ptr).can_redefine_any_class() > 0 ||
-        (*capabilities_ptr).can_get_current_thread_cpu_time() > 0 ||
-        (*capabilities_ptr).can_get_thread_cpu_time() > 0 ||
         (*capabilities_ptr).can_generate_all_class_hook_events() > 0 ||
         (*capabilities_ptr).can_generate_compiled_method_load_events() > 0 ||
         (*capabilities_ptr).can_generate_vm_object_alloc_events() > 0 ||
@@ -160,6 +160,8 @@ pub unsafe extern "C" fn add_capabilities(env: *mut jvmtiEnv, capabilities_ptr:
 // can_maintain_original_method_order      = 1
 // can_generate_monitor_events             = 1
 // can_tag_objects                         = 1
+// can_get_current_thread_cpu_time         = 1
+// can_get_thread_cpu_time                 = 1
 pub unsafe extern "C" fn get_capabilities(env: *mut jvmtiEnv, capabilities_ptr: *mut jvmtiCapabilities) -> jvmtiError {
     let jvm = get_state(env);
     let tracing_guard = jvm.config.tracing.trace_jdwp_function_enter(jvm, "GetCapabilities");
@@ -177,5 +179,7 @@ pub unsafe extern "C" fn get_capabilities(env: *mut jvmtiEnv, capabilities_ptr:
     mut_borrow.set_can_maintain_original_method_order(1);
     mut_borrow.set_can_generate_monitor_events(1);
     mut_borrow.set_can_tag_objects(1);
+    mut_borrow.set_can_get_current_thread_cpu_time(1);
+    mut_borrow.set_can_get_thread_cpu_time(1);
     jvm.config.tracing.trace_jdwp_function_exit(tracing_guard, jvmtiError_JVMTI_ERROR_NONE)
 }
@@ -34,8 +34,9 @@ use crate::properties::get_system_property;
 use crate::tags::{get_tag, set_tag};
 use crate::thread_local_storage::{get_thread_local_storage, set_thread_local_storage};
 use crate::threads::{get_all_threads, get_thread_info, get_thread_state};
+use crate::threads::cpu_time::{get_current_thread_cpu_time, get_current_thread_cpu_timer_info, get_thread_cpu_time, get_thread_cpu_timer_info};
 use crate::threads::suspend_resume::{resume_thread, resume_thread_list, suspend_thread, suspend_thread_list};
-use crate::threads::thread_groups::{get_thread_group_info, get_top_thread_groups};
+use crate::threads::thread_groups::{get_thread_group_children, get_thread_group_info, get_top_thread_groups};
 use crate::version::get_version_number;
 use slow_interpreter::rust_jni::jvmti::{get_interpreter_state, get_state};
 
@@ -55,7 +56,7 @@ pub fn initial_jvmti() -> JVMTIInterfaceNamedReservedPointers {
         RunAgentThread: Some(run_agent_thread),
         GetTopThreadGroups: Some(get_top_thread_groups),
         GetThreadGroupInfo: Some(get_thread_group_info),
-        GetThreadGroupChildren: None, //doesn't need impl not in currently supported capabilities
+        GetThreadGroupChildren: Some(get_thread_group_children),
         GetFrameCount: Some(get_frame_count),
         GetThreadState: Some(get_thread_state),
         GetCurrentThread: Some(get_current_thread),
@@ -174,10 +175,10 @@ pub fn initial_jvmti() -> JVMTIInterfaceNamedReservedPointers {
         GetSystemProperty: Some(get_system_property),
         SetSystemProperty: None,            //todo impl
         GetPhase: None,                     //todo impl
-        GetCurrentThreadCpuTimerInfo: None, //doesn't need impl not in currently supported capabilities
-        GetCurrentThreadCpuTime: None,      //doesn't need impl not in currently supported capabilities
-        GetThreadCpuTimerInfo: None,        //doesn't need impl not in currently supported capabilities
-        GetThreadCpuTime: None,             //doesn't need impl not in currently supported capabilities
+        GetCurrentThreadCpuTimerInfo: Some(get_current_thread_cpu_timer_info),
+        GetCurrentThreadCpuTime: Some(get_current_thread_cpu_time),
+        GetThreadCpuTimerInfo: Some(get_thread_cpu_timer_info),
+        GetThreadCpuTime: Some(get_thread_cpu_time),
         GetTimerInfo: None,                 //todo impl
         GetTime: None,                      //todo impl
         GetPotentialCapabilities: Some(get_potential_capabilities),
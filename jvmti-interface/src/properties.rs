@@ -72,7 +72,8 @@ pub unsafe extern "C" fn get_system_property(env: *mut jvmtiEnv, property: *cons
     }
     if property_name == "java.class.path" || property_name == "sun.boot.class.path" {
         let jvm = get_state(env);
-        let leaked_str = jvm.native.native_interface_allocations.allocate_string(jvm.classpath.classpath_string());
+        let classpath = if property_name == "java.class.path" { jvm.user_classpath_string() } else { jvm.boot_classpath_string() };
+        let leaked_str = jvm.native.native_interface_allocations.allocate_string(classpath);
         value_ptr.write(leaked_str);
         return jvm.config.tracing.trace_jdwp_function_exit(tracing_guard, jvmtiError_JVMTI_ERROR_NONE);
         //todo duplication
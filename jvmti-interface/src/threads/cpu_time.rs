@@ -0,0 +1,182 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use jvmti_jni_bindings::{jlong, jthread, jvmtiEnv, jvmtiError, jvmtiError_JVMTI_ERROR_INVALID_THREAD, jvmtiError_JVMTI_ERROR_NONE, jvmtiError_JVMTI_ERROR_THREAD_NOT_ALIVE, jvmtiTimerInfo, jvmtiTimerKind_JVMTI_TIMER_TOTAL_CPU};
+
+use slow_interpreter::jvm_state::JVMState;
+use slow_interpreter::new_java_values::NewJavaValueHandle;
+use slow_interpreter::rust_jni::jvmti::get_state;
+use slow_interpreter::rust_jni::native_util::from_object_new;
+use slow_interpreter::threading::java_thread::JavaThread;
+
+// the thread cpu clocks count nanoseconds and never go backwards, so the timer info is the same for both
+unsafe fn write_cpu_timer_info(info_ptr: *mut jvmtiTimerInfo) {
+    let info = info_ptr.as_mut().unwrap();
+    info.max_value = -1;
+    info.may_skip_forward = 0;
+    info.may_skip_backward = 0;
+    info.kind = jvmtiTimerKind_JVMTI_TIMER_TOTAL_CPU;
+    info.reserved1 = 0;
+    info.reserved2 = 0;
+}
+
+fn duration_to_nanos(duration: Duration) -> jlong {
+    duration.as_nanos() as jlong
+}
+
+unsafe fn thread_or_current<'gc>(jvm: &'gc JVMState<'gc>, thread: jthread) -> Result<Arc<JavaThread<'gc>>, jvmtiError> {
+    if thread.is_null() {
+        return Ok(jvm.thread_state.get_current_thread());
+    }
+    let jthread = match NewJavaValueHandle::from_optional_object(from_object_new(jvm, thread)).try_cast_thread(jvm) {
+        None => return Err(jvmtiError_JVMTI_ERROR_INVALID_THREAD),
+        Some(jthread) => jthread,
+    };
+    jthread.try_get_java_thread(jvm).ok_or(jvmtiError_JVMTI_ERROR_THREAD_NOT_ALIVE)
+}
+
+///Get Current Thread CPU Timer Information
+///
+///     jvmtiError
+///     GetCurrentThreadCpuTimerInfo(jvmtiEnv* env,
+///                 jvmtiTimerInfo* info_ptr)
+///
+/// Get information about the GetCurrentThreadCpuTime timer. The fields of the jvmtiTimerInfo structure are filled in with details about the timer.
+///
+/// Phase	Callback Safe	Position	Since
+/// may only be called during the start or the live phase 	No 	134	1.0
+///
+/// Capabilities
+/// Optional Functionality: might not be implemented for all virtual machines. The following capability (as returned by GetCapabilities) must be true to use this function.
+/// Capability 	Effect
+/// can_get_current_thread_cpu_time	Can get current thread CPU time.
+///
+/// Parameters
+/// Name 	Type 	Description
+/// info_ptr	jvmtiTimerInfo*	On return, filled with information describing the time returned by GetCurrentThreadCpuTime.
+///
+/// Errors
+/// This function returns either a universal error or one of the following errors
+/// Error 	Description
+/// JVMTI_ERROR_MUST_POSSESS_CAPABILITY	The environment does not possess the capability can_get_current_thread_cpu_time.
+/// JVMTI_ERROR_NULL_POINTER	info_ptr is NULL.
+pub unsafe extern "C" fn get_current_thread_cpu_timer_info(env: *mut jvmtiEnv, info_ptr: *mut jvmtiTimerInfo) -> jvmtiError {
+    let jvm = get_state(env);
+    let tracing_guard = jvm.config.tracing.trace_jdwp_function_enter(jvm, "GetCurrentThreadCpuTimerInfo");
+    null_check!(info_ptr);
+    write_cpu_timer_info(info_ptr);
+    jvm.config.tracing.trace_jdwp_function_exit(tracing_guard, jvmtiError_JVMTI_ERROR_NONE)
+}
+
+///Get Current Thread CPU Time
+///
+///     jvmtiError
+///     GetCurrentThreadCpuTime(jvmtiEnv* env,
+///                 jlong* nanos_ptr)
+///
+/// Return the CPU time utilized by the current thread.
+///
+/// Phase	Callback Safe	Position	Since
+/// may only be called during the start or the live phase 	No 	135	1.0
+///
+/// Capabilities
+/// Optional Functionality: might not be implemented for all virtual machines. The following capability (as returned by GetCapabilities) must be true to use this function.
+/// Capability 	Effect
+/// can_get_current_thread_cpu_time	Can get current thread CPU time.
+///
+/// Parameters
+/// Name 	Type 	Description
+/// nanos_ptr	jlong*	On return, points to the CPU time used by this thread in nanoseconds.
+///
+/// Errors
+/// This function returns either a universal error or one of the following errors
+/// Error 	Description
+/// JVMTI_ERROR_MUST_POSSESS_CAPABILITY	The environment does not possess the capability can_get_current_thread_cpu_time.
+/// JVMTI_ERROR_NULL_POINTER	nanos_ptr is NULL.
+pub unsafe extern "C" fn get_current_thread_cpu_time(env: *mut jvmtiEnv, nanos_ptr: *mut jlong) -> jvmtiError {
+    let jvm = get_state(env);
+    let tracing_guard = jvm.config.tracing.trace_jdwp_function_enter(jvm, "GetCurrentThreadCpuTime");
+    null_check!(nanos_ptr);
+    let cpu_time = jvm.thread_state.get_current_thread().cpu_time().unwrap_or(Duration::ZERO);
+    nanos_ptr.write(duration_to_nanos(cpu_time));
+    jvm.config.tracing.trace_jdwp_function_exit(tracing_guard, jvmtiError_JVMTI_ERROR_NONE)
+}
+
+///Get Thread CPU Timer Information
+///
+///     jvmtiError
+///     GetThreadCpuTimerInfo(jvmtiEnv* env,
+///                 jvmtiTimerInfo* info_ptr)
+///
+/// Get information about the GetThreadCpuTime timer. The fields of the jvmtiTimerInfo structure are filled in with details about the timer.
+///
+/// Phase	Callback Safe	Position	Since
+/// may only be called during the live phase 	No 	136	1.0
+///
+/// Capabilities
+/// Optional Functionality: might not be implemented for all virtual machines. The following capability (as returned by GetCapabilities) must be true to use this function.
+/// Capability 	Effect
+/// can_get_thread_cpu_time	Can get thread CPU time.
+///
+/// Parameters
+/// Name 	Type 	Description
+/// info_ptr	jvmtiTimerInfo*	On return, filled with information describing the time returned by GetThreadCpuTime.
+///
+/// Errors
+/// This function returns either a universal error or one of the following errors
+/// Error 	Description
+/// JVMTI_ERROR_MUST_POSSESS_CAPABILITY	The environment does not possess the capability can_get_thread_cpu_time.
+/// JVMTI_ERROR_NULL_POINTER	info_ptr is NULL.
+pub unsafe extern "C" fn get_thread_cpu_timer_info(env: *mut jvmtiEnv, info_ptr: *mut jvmtiTimerInfo) -> jvmtiError {
+    let jvm = get_state(env);
+    let tracing_guard = jvm.config.tracing.trace_jdwp_function_enter(jvm, "GetThreadCpuTimerInfo");
+    null_check!(info_ptr);
+    write_cpu_timer_info(info_ptr);
+    jvm.config.tracing.trace_jdwp_function_exit(tracing_guard, jvmtiError_JVMTI_ERROR_NONE)
+}
+
+///Get Thread CPU Time
+///
+///     jvmtiError
+///     GetThreadCpuTime(jvmtiEnv* env,
+///                 jthread thread,
+///                 jlong* nanos_ptr)
+///
+/// Return the CPU time utilized by the specified thread.
+///
+/// Phase	Callback Safe	Position	Since
+/// may only be called during the live phase 	No 	137	1.0
+///
+/// Capabilities
+/// Optional Functionality: might not be implemented for all virtual machines. The following capability (as returned by GetCapabilities) must be true to use this function.
+/// Capability 	Effect
+/// can_get_thread_cpu_time	Can get thread CPU time.
+///
+/// Parameters
+/// Name 	Type 	Description
+/// thread	jthread	The thread to query. If thread is NULL, the current thread is used.
+/// nanos_ptr	jlong*	On return, points to the CPU time used by the specified thread in nanoseconds.
+///
+/// Errors
+/// This function returns either a universal error or one of the following errors
+/// Error 	Description
+/// JVMTI_ERROR_MUST_POSSESS_CAPABILITY	The environment does not possess the capability can_get_thread_cpu_time.
+/// JVMTI_ERROR_INVALID_THREAD	thread is not a thread object.
+/// JVMTI_ERROR_THREAD_NOT_ALIVE	thread is not live (has not been started or is now dead).
+/// JVMTI_ERROR_NULL_POINTER	nanos_ptr is NULL.
+pub unsafe extern "C" fn get_thread_cpu_time(env: *mut jvmtiEnv, thread: jthread, nanos_ptr: *mut jlong) -> jvmtiError {
+    let jvm = get_state(env);
+    let tracing_guard = jvm.config.tracing.trace_jdwp_function_enter(jvm, "GetThreadCpuTime");
+    assert!(jvm.vm_live());
+    null_check!(nanos_ptr);
+    let java_thread = match thread_or_current(jvm, thread) {
+        Ok(java_thread) => java_thread,
+        Err(err) => return jvm.config.tracing.trace_jdwp_function_exit(tracing_guard, err),
+    };
+    let cpu_time = match java_thread.cpu_time() {
+        None => return jvm.config.tracing.trace_jdwp_function_exit(tracing_guard, jvmtiError_JVMTI_ERROR_THREAD_NOT_ALIVE),
+        Some(cpu_time) => cpu_time,
+    };
+    nanos_ptr.write(duration_to_nanos(cpu_time));
+    jvm.config.tracing.trace_jdwp_function_exit(tracing_guard, jvmtiError_JVMTI_ERROR_NONE)
+}
@@ -19,6 +19,7 @@ macro_rules! get_thread_or_error {
     };
 }
 
+pub mod cpu_time;
 #[macro_use]
 pub mod suspend_resume;
 #[macro_use]
@@ -139,8 +140,7 @@ pub unsafe extern "C" fn get_thread_info(env: *mut jvmtiEnv, thread: jthread, in
         Some(thread) => thread,
     };
 
-    //todo get thread groups other than system thread group working at some point
-    (*info_ptr).thread_group = new_local_ref_public(jvm.thread_state.get_system_thread_group().object().to_gc_managed().into(), todo!()/*int_state*/);
+    (*info_ptr).thread_group = new_local_ref_public_new(thread_object.thread_group(jvm).as_ref().map(|group| group.object_ref().as_allocated_obj()), int_state);
     //todo deal with this whole context loader situation
     let thread_class_object = match thread_object.get_class(jvm, int_state) {
         Ok(thread_class_object) => thread_class_object,
@@ -1,10 +1,11 @@
 use std::ffi::CString;
 
-use jvmti_jni_bindings::{jint, jthreadGroup, jvmtiEnv, jvmtiError, jvmtiError_JVMTI_ERROR_INVALID_THREAD_GROUP, jvmtiError_JVMTI_ERROR_NONE, jvmtiThreadGroupInfo};
+use jvmti_jni_bindings::{jint, jthread, jthreadGroup, jvmtiEnv, jvmtiError, jvmtiError_JVMTI_ERROR_INVALID_THREAD_GROUP, jvmtiError_JVMTI_ERROR_NONE, jvmtiThreadGroupInfo};
 
-use slow_interpreter::java_values::JavaValue;
+use slow_interpreter::new_java_values::NewJavaValueHandle;
 use slow_interpreter::stdlib::java::NewAsObjectOrJavaValue;
-use slow_interpreter::rust_jni::jni_utils::new_local_ref_public;
+use slow_interpreter::rust_jni::jni_utils::new_local_ref_public_new;
+use slow_interpreter::rust_jni::native_util::from_object_new;
 use slow_interpreter::rust_jni::jvmti::{get_interpreter_state, get_state};
 
 ///Get Thread Group Info
@@ -56,7 +57,7 @@ pub unsafe extern "C" fn get_thread_group_info(env: *mut jvmtiEnv, group: jthrea
     let int_state = get_interpreter_state(env);
     let tracing_guard = jvm.config.tracing.trace_jdwp_function_enter(jvm, "GetThreadGroupInfo");
     assert!(jvm.vm_live());
-    let thread_group = match JavaValue::Object(todo!() /*from_jclass(jvm,group)*/).try_cast_thread_group() {
+    let thread_group = match NewJavaValueHandle::from_optional_object(from_object_new(jvm, group)).try_cast_thread_group(jvm) {
         None => return jvm.config.tracing.trace_jdwp_function_exit(tracing_guard, jvmtiError_JVMTI_ERROR_INVALID_THREAD_GROUP),
         Some(thread_group) => thread_group,
     };
@@ -67,7 +68,7 @@ pub unsafe extern "C" fn get_thread_group_info(env: *mut jvmtiEnv, group: jthrea
     info_pointer_writer.name = name;
     info_pointer_writer.is_daemon = thread_group.daemon(jvm);
     info_pointer_writer.max_priority = thread_group.max_priority(jvm);
-    info_pointer_writer.parent = new_local_ref_public(thread_group.parent(jvm).map(|x| x.object().to_gc_managed()), todo!()/*int_state*/);
+    info_pointer_writer.parent = new_local_ref_public_new(thread_group.parent(jvm).as_ref().map(|parent| parent.object_ref().as_allocated_obj()), int_state);
     jvm.config.tracing.trace_jdwp_function_exit(tracing_guard, jvmtiError_JVMTI_ERROR_NONE)
 }
 
@@ -110,9 +111,77 @@ pub unsafe extern "C" fn get_top_thread_groups(env: *mut jvmtiEnv, group_count_p
     //There is only one top level thread group in this JVM.
     group_count_ptr.write(1);
     let system_j_thread_group = jvm.thread_state.get_system_thread_group();
-    let thread_group_object = system_j_thread_group.object().to_gc_managed();
-    let res = new_local_ref_public(thread_group_object.into(), todo!()/*int_state*/);
+    let res = new_local_ref_public_new(Some(system_j_thread_group.object_ref().as_allocated_obj()), int_state);
 
     jvm.native.native_interface_allocations.allocate_and_write_vec(vec![res], group_count_ptr, groups_ptr);
     jvm.config.tracing.trace_jdwp_function_exit(tracing_guard, jvmtiError_JVMTI_ERROR_NONE)
 }
+
+///Get Thread Group Children
+///
+///     jvmtiError
+///     GetThreadGroupChildren(jvmtiEnv* env,
+///                 jthreadGroup group,
+///                 jint* thread_count_ptr,
+///                 jthread** threads_ptr,
+///                 jint* group_count_ptr,
+///                 jthreadGroup** groups_ptr)
+///
+/// Get the live threads and active subgroups in this thread group.
+///
+/// Phase	Callback Safe	Position	Since
+/// may only be called during the live phase 	No 	15	1.0
+///
+/// Capabilities
+/// Required Functionality
+///
+/// Parameters
+/// Name 	Type 	Description
+/// group	jthreadGroup	The group to query.
+/// thread_count_ptr	jint*	On return, points to the number of live threads in this thread group.
+///
+/// Agent passes a pointer to a jint. On return, the jint has been set.
+/// threads_ptr	jthread**	On return, points to an array of the live threads in this thread group.
+///
+/// Agent passes a pointer to a jthread*. On return, the jthread* points to a newly allocated array of size *thread_count_ptr. The array should be freed with Deallocate. The objects returned by threads_ptr are JNI local references and must be managed.
+/// group_count_ptr	jint*	On return, points to the number of active child thread groups
+///
+/// Agent passes a pointer to a jint. On return, the jint has been set.
+/// groups_ptr	jthreadGroup**	On return, points to an array of the active child thread groups.
+///
+/// Agent passes a pointer to a jthreadGroup*. On return, the jthreadGroup* points to a newly allocated array of size *group_count_ptr. The array should be freed with Deallocate. The objects returned by groups_ptr are JNI local references and must be managed.
+///
+/// Errors
+/// This function returns either a universal error or one of the following errors
+/// Error 	Description
+/// JVMTI_ERROR_INVALID_THREAD_GROUP	group is not a thread group object.
+/// JVMTI_ERROR_NULL_POINTER	thread_count_ptr is NULL.
+/// JVMTI_ERROR_NULL_POINTER	threads_ptr is NULL.
+/// JVMTI_ERROR_NULL_POINTER	group_count_ptr is NULL.
+/// JVMTI_ERROR_NULL_POINTER	groups_ptr is NULL.
+pub unsafe extern "C" fn get_thread_group_children(env: *mut jvmtiEnv, group: jthreadGroup, thread_count_ptr: *mut jint, threads_ptr: *mut *mut jthread, group_count_ptr: *mut jint, groups_ptr: *mut *mut jthreadGroup) -> jvmtiError {
+    let jvm = get_state(env);
+    let int_state = get_interpreter_state(env);
+    let tracing_guard = jvm.config.tracing.trace_jdwp_function_enter(jvm, "GetThreadGroupChildren");
+    assert!(jvm.vm_live());
+    let thread_group = match NewJavaValueHandle::from_optional_object(from_object_new(jvm, group)).try_cast_thread_group(jvm) {
+        None => return jvm.config.tracing.trace_jdwp_function_exit(tracing_guard, jvmtiError_JVMTI_ERROR_INVALID_THREAD_GROUP),
+        Some(thread_group) => thread_group,
+    };
+    null_check!(thread_count_ptr);
+    null_check!(threads_ptr);
+    null_check!(group_count_ptr);
+    null_check!(groups_ptr);
+
+    let threads = thread_group.threads_non_null(jvm)
+        .iter()
+        .map(|thread| new_local_ref_public_new(Some(thread.object_ref().as_allocated_obj()), int_state))
+        .collect::<Vec<jthread>>();
+    let groups = thread_group.groups(jvm)
+        .iter()
+        .map(|group| new_local_ref_public_new(Some(group.object_ref().as_allocated_obj()), int_state))
+        .collect::<Vec<jthreadGroup>>();
+    jvm.native.native_interface_allocations.allocate_and_write_vec(threads, thread_count_ptr, threads_ptr);
+    jvm.native.native_interface_allocations.allocate_and_write_vec(groups, group_count_ptr, groups_ptr);
+    jvm.config.tracing.trace_jdwp_function_exit(tracing_guard, jvmtiError_JVMTI_ERROR_NONE)
+}
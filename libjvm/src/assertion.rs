@@ -1,14 +1,35 @@
 use jvmti_jni_bindings::{jboolean, jclass, JNIEnv, jobject};
-use slow_interpreter::rust_jni::jni_utils::get_state;
+use rust_jvm_common::loading::LoaderName;
+use slow_interpreter::exceptions::WasException;
+use slow_interpreter::java_values::ExceptionReturn;
+use slow_interpreter::rust_jni::jni_utils::{get_interpreter_state, get_state, get_throw, new_local_ref_public_new};
+use slow_interpreter::rust_jni::native_util::from_jclass;
+use slow_interpreter::stdlib::java::lang::assertion_status_directives::AssertionStatusDirectives;
+use slow_interpreter::stdlib::java::NewAsObjectOrJavaValue;
 
 
 #[no_mangle]
-unsafe extern "system" fn JVM_DesiredAssertionStatus(env: *mut JNIEnv, _unused: jclass, _cls: jclass) -> jboolean {
+unsafe extern "system" fn JVM_DesiredAssertionStatus(env: *mut JNIEnv, _unused: jclass, cls: jclass) -> jboolean {
     let jvm = get_state(env);
-    u8::from(jvm.config.assertions_enabled)
+    let runtime_class = from_jclass(jvm, cls).as_runtime_class(jvm);
+    let class_name = match runtime_class.cpdtype().try_unwrap_class_type() {
+        Some(class_name) => class_name.0.to_str(&jvm.string_pool).replace('/', "."),
+        None => return u8::from(false),
+    };
+    //like hotspot, system classes are those the bootstrap loader defined, not merely initiated
+    let system_class = jvm.classes.read().unwrap().get_defining_loader(&runtime_class) == LoaderName::BootstrapLoader;
+    u8::from(jvm.config.assertion_status.enabled(class_name.as_str(), system_class))
 }
 
 #[no_mangle]
 unsafe extern "system" fn JVM_AssertionStatusDirectives(env: *mut JNIEnv, _unused: jclass) -> jobject {
-    unimplemented!()
-}
\ No newline at end of file
+    let jvm = get_state(env);
+    let int_state = get_interpreter_state(env);
+    match AssertionStatusDirectives::new(jvm, int_state, &jvm.config.assertion_status) {
+        Ok(directives) => new_local_ref_public_new(Some(directives.full_object_ref()), int_state),
+        Err(WasException { exception_obj }) => {
+            *get_throw(env) = Some(WasException { exception_obj });
+            jobject::invalid_default()
+        }
+    }
+}
@@ -8,14 +8,15 @@ use classfile_parser::parse_class_file;
 use classfile_view::view::ClassBackedView;
 use jni_interface::define_class_safe;
 use jvmti_jni_bindings::{jbyte, jclass, JNIEnv, jobject, jsize};
-use rust_jvm_common::loading::LoaderName;
-use slow_interpreter::better_java_stack::frames::HasFrame;
+use rust_jvm_common::compressed_classfile::class_names::CompressedClassName;
+use rust_jvm_common::loading::{ClassLoadingError, LoaderName};
+use slow_interpreter::class_loading::throw_class_loading_error;
 use slow_interpreter::exceptions::WasException;
 use slow_interpreter::java_values::JavaValue;
 use slow_interpreter::new_java_values::allocated_objects::AllocatedHandle;
 
 
-use slow_interpreter::rust_jni::jni_utils::{get_interpreter_state, get_state};
+use slow_interpreter::rust_jni::jni_utils::{get_interpreter_state, get_state, get_throw};
 use slow_interpreter::rust_jni::native_util::{from_object, from_object_new, to_object, to_object_new};
 
 #[no_mangle]
@@ -26,7 +27,7 @@ unsafe extern "system" fn JVM_DefineClass(env: *mut JNIEnv, name: *const ::std::
 //todo handle source
 //todo what is pd
 #[no_mangle]
-unsafe extern "system" fn JVM_DefineClassWithSource(env: *mut JNIEnv, name: *const c_char, loader: jobject, buf: *const jbyte, len: jsize, _pd: jobject, _source: *const c_char) -> jclass {
+unsafe extern "system" fn JVM_DefineClassWithSource(env: *mut JNIEnv, name: *const c_char, loader: jobject, buf: *const jbyte, len: jsize, _pd: jobject, source: *const c_char) -> jclass {
     let int_state = get_interpreter_state(env);
     let jvm = get_state(env);
     let name_string = CStr::from_ptr(name).to_str().unwrap(); //todo handle bad utf8, with to lossy or something
@@ -41,16 +42,23 @@ unsafe extern "system" fn JVM_DefineClassWithSource(env: *mut JNIEnv, name: *con
     let parsed = Arc::new(match parse_class_file(&mut Cursor::new(slice)) {
         Ok(x) => x,
         Err(err) => {
-            int_state.debug_print_stack_trace(jvm);
-            dbg!(err);
-            todo!()
-        },
+            let class_name = CompressedClassName(jvm.string_pool.add_name(name_string.replace('.', "/"), true));
+            if let Err(WasException { exception_obj }) = throw_class_loading_error::<()>(jvm, int_state, class_name, ClassLoadingError::ClassFileInvalid(err)) {
+                *get_throw(env) = Some(WasException { exception_obj });
+            }
+            return null_mut();
+        }
     });
+    if jvm.config.tracing.trace_class_loads {
+        let source = if source.is_null() { "__JVM_DefineClass__".to_string() } else { CStr::from_ptr(source).to_string_lossy().to_string() };
+        let class_view = ClassBackedView::from(parsed.clone(), &jvm.string_pool);
+        jvm.config.tracing.trace_class_load(&jvm.string_pool, class_view.name().unwrap_name(), source.as_str());
+    }
     to_object_new(
         match define_class_safe(jvm, int_state, parsed.clone(), loader_name, ClassBackedView::from(parsed, &jvm.string_pool)) {
             Ok(res) => res,
             Err(WasException { exception_obj }) => {
-                todo!();
+                *get_throw(env) = Some(WasException { exception_obj });
                 return null_mut();
             }
         }
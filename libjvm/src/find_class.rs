@@ -22,7 +22,7 @@ use rust_jvm_common::runtime_type::RuntimeType;
 use slow_interpreter::better_java_stack::frames::HasFrame;
 use slow_interpreter::better_java_stack::opaque_frame::OpaqueFrame;
 use slow_interpreter::class_loading::bootstrap_load;
-use slow_interpreter::class_objects::get_or_create_class_object;
+use slow_interpreter::class_objects::{get_or_create_class_object, get_or_create_class_object_force_loader};
 use slow_interpreter::exceptions::WasException;
 use slow_interpreter::java_values::{ExceptionReturn, JavaValue};
 
@@ -88,14 +88,17 @@ unsafe extern "system" fn JVM_FindLoadedClass(env: *mut JNIEnv, loader: jobject,
         .to_rust_string(jvm);
     assert_ne!(&name_str, "int");
     // dbg!(&name_str);
-    //todo what if not bl
+    let loader_name = match from_object_new(jvm, loader) {
+        None => BootstrapLoader,
+        Some(loader_obj) => loader_obj.cast_class_loader().to_jvm_loader(jvm)
+    };
     let class_name = CompressedClassName(jvm.string_pool.add_name(name_str.replace(".", "/"), true));
-    let loaded = jvm.classes.write().unwrap().is_loaded(&class_name.clone().into());
+    let loaded = jvm.classes.write().unwrap().is_loaded(loader_name, &class_name.clone().into());
     match loaded {
         None => null_mut(),
-        Some(view) => {
+        Some(_) => {
             // todo what if name is long/int etc.
-            let res = get_or_create_class_object(jvm, class_name.into(), int_state).unwrap(); //todo handle exception
+            let res = get_or_create_class_object_force_loader(jvm, class_name.into(), int_state, loader_name).unwrap(); //todo handle exception
             new_local_ref_public_new(res.as_allocated_obj().into(), int_state)
         }
     }
@@ -41,6 +41,11 @@ use slow_interpreter::utils::{field_object_from_view, pushable_frame_todo, throw
 use slow_interpreter::rust_jni::jni_utils::{get_interpreter_state, get_state};
 //todo lots of duplication here, idk if should fix though
 
+//newer than the jdk 8 jvm.h the bindings are generated from
+const JVM_CONSTANT_DYNAMIC: c_uchar = 17;
+const JVM_CONSTANT_MODULE: c_uchar = 19;
+const JVM_CONSTANT_PACKAGE: c_uchar = 20;
+
 #[no_mangle]
 unsafe extern "system" fn JVM_GetClassConstantPool(env: *mut JNIEnv, cls: jclass) -> jobject {
     let jvm = get_state(env);
@@ -435,6 +440,9 @@ unsafe extern "system" fn JVM_GetClassCPTypes(env: *mut JNIEnv, cb: jclass, type
             ConstantInfoView::MethodHandle(_) => JVM_CONSTANT_MethodHandle,
             ConstantInfoView::MethodType(_) => JVM_CONSTANT_MethodType,
             ConstantInfoView::InvokeDynamic(_) => JVM_CONSTANT_InvokeDynamic,
+            ConstantInfoView::Dynamic(_) => JVM_CONSTANT_DYNAMIC as _,
+            ConstantInfoView::Module(_) => JVM_CONSTANT_MODULE as _,
+            ConstantInfoView::Package(_) => JVM_CONSTANT_PACKAGE as _,
             ConstantInfoView::LiveObject(_) => panic!(),
         } as c_uchar)
     }
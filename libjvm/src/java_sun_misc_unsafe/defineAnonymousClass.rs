@@ -91,7 +91,12 @@ fn patch_all<'gc>(jvm: &'gc JVMState<'gc>, args: &mut Vec<NewJavaValueHandle<'gc
     });
     let old_name_temp = class_name(&unpatched);
     let old_name = old_name_temp.get_referred_name();
-    let new_name = Wtf8Buf::from_string(format!("{}/{}", old_name, jvm.classes.read().unwrap().anon_classes.len()));
+    //unloaded classes leave anon_classes, so they are added back in to keep the suffix unique
+    let anon_class_number = {
+        let classes = jvm.classes.read().unwrap();
+        classes.anon_classes.len() as u64 + classes.unloaded_class_count
+    };
+    let new_name = Wtf8Buf::from_string(format!("{}/{}", old_name, anon_class_number));
     let name_index = unpatched.constant_pool.len() as u16;
     unpatched.constant_pool.push(ConstantInfo { kind: ConstantKind::Utf8(Utf8 { length: new_name.len() as u16, string: new_name }) });
     unpatched.constant_pool.push(ConstantInfo { kind: ConstantKind::Class(Class { name_index }) });
@@ -36,21 +36,29 @@ unsafe extern "system" fn JVM_OnExit(func: Option<unsafe extern "C" fn()>) {
     ON_EXIT.write().unwrap().push(func);
 }
 
-#[no_mangle]
-unsafe extern "system" fn JVM_Exit(code: jint) {
-    //todo run finalizers blocking on gc
-    for func in ON_EXIT.read().unwrap().iter() {
+// Every way out of the vm ends here, the end of main, System.exit and halt. Shutdown hooks have
+// already run by this point, the JVM_OnExit callbacks only run once even if threads race to exit.
+pub fn vm_exit(code: jint) -> ! {
+    let on_exit = std::mem::take(&mut *ON_EXIT.write().unwrap());
+    for func in on_exit.iter() {
         if let Some(func) = func.as_ref() {
-            func();
+            unsafe { func(); }
         };
     }
     exit(code);
 }
 
+#[no_mangle]
+unsafe extern "system" fn JVM_Exit(code: jint) {
+    //todo run finalizers blocking on gc
+    vm_exit(code);
+}
+
 #[no_mangle]
 unsafe extern "system" fn JVM_Halt(code: jint) {
+    // halt means that no cleanup is desired, the hooks have already run if this is from System.exit, and the
+    // JVM_OnExit callbacks are only for an orderly exit
     exit(code);
-    // halt means that no cleanup is desired
 }
 
 #[no_mangle]
@@ -1,23 +1,34 @@
 use jvmti_jni_bindings::jlong;
 
+use slow_interpreter::jvm_state::JVM;
+
+//todo this is hard to implement , so for now make it up
+//so far this seems only used in rng, and for -Xms/-Xmx to be visible through Runtime.
+const DEFAULT_TOTAL_MEMORY: jlong = 100000;
+const DEFAULT_MAX_MEMORY: jlong = 1000000000000;
+
+unsafe fn total_memory() -> jlong {
+    let config = &JVM.as_ref().unwrap().config;
+    let total = config.initial_heap_size.map(|size| size as jlong).unwrap_or(DEFAULT_TOTAL_MEMORY);
+    total.min(max_memory())
+}
+
+unsafe fn max_memory() -> jlong {
+    JVM.as_ref().unwrap().config.max_heap_size.map(|size| size as jlong).unwrap_or(DEFAULT_MAX_MEMORY)
+}
+
 #[no_mangle]
 unsafe extern "system" fn JVM_TotalMemory() -> jlong {
-    //todo this is hard to implement , so for now make it up
-    //so far this seems only used in rng.
-    100000
+    total_memory()
 }
 
 #[no_mangle]
 unsafe extern "system" fn JVM_FreeMemory() -> jlong {
-    //todo this is hard to implement , so for now make it up
-    //so far this seems only used in rng.
     //todo in future will need to implement this for reals
-    1000000000000 - 100000
+    max_memory() - total_memory()
 }
 
 #[no_mangle]
 unsafe extern "system" fn JVM_MaxMemory() -> jlong {
-    //todo this is hard to implement , so for now make it up
-    //so far this seems only used in rng.
-    1000000000000
+    max_memory()
 }
@@ -7,7 +7,8 @@ use jvmti_jni_bindings::{jlong, JNIEnv, jobject};
 use slow_interpreter::better_java_stack::frames::HasFrame;
 
 use slow_interpreter::rust_jni::native_util::{from_object, from_object_new};
-use slow_interpreter::rust_jni::jni_utils::{get_interpreter_state, get_state};
+use slow_interpreter::exceptions::WasException;
+use slow_interpreter::rust_jni::jni_utils::{get_interpreter_state, get_state, get_throw};
 
 #[no_mangle]
 unsafe extern "system" fn JVM_MonitorWait(env: *mut JNIEnv, obj: jobject, ms: jlong) {
@@ -16,7 +17,9 @@ unsafe extern "system" fn JVM_MonitorWait(env: *mut JNIEnv, obj: jobject, ms: jl
     assert_ne!(obj, null_mut());
     let monitor = jvm.monitor_for(obj as *const c_void);
     let duration = if ms == 0 { None } else { Some(Duration::from_millis(ms as u64)) };
-    monitor.wait(jvm, int_state, duration);
+    if let Err(WasException { exception_obj }) = monitor.wait(jvm, int_state, duration) {
+        *get_throw(env) = Some(WasException { exception_obj });
+    }
 }
 
 #[no_mangle]
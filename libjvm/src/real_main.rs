@@ -5,13 +5,13 @@ use std::mem::transmute;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::{Arc, Mutex, RwLock};
-use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread::Scope;
 use std::time::{Duration, Instant};
 
 use argparse::{ArgumentParser, List, Store, StoreTrue};
 use clap::Parser;
-use itertools::all;
+use itertools::{all, Itertools};
 use raw_cpuid::CpuId;
 
 use classfile_view::view::ClassBackedView;
@@ -22,7 +22,8 @@ use inheritance_tree::InheritanceTree;
 use interface_vtable::ITables;
 use interface_vtable::lookup_cache::InvokeInterfaceLookupCache;
 use interfaces::initial_per_stack_interfaces;
-use jvm_args::JVMArgs;
+use jvm_args::{JVMArgs, ShareMode};
+use jvm_args::launcher::{LauncherCommand, parse_launcher_args, version_message};
 use method_table::interface_table::InterfaceTable;
 use method_table::MethodTable;
 use perf_metrics::PerfMetrics;
@@ -39,21 +40,25 @@ use rust_jvm_common::opaque_id_table::OpaqueIDs;
 use sketch_jvm_version_of_utf8::wtf8_pool::Wtf8Pool;
 use slow_interpreter::better_java_stack::frames::HasFrame;
 use slow_interpreter::better_java_stack::remote_frame::RemoteFrame;
+use slow_interpreter::class_data_sharing::SharedArchive;
 use slow_interpreter::field_table::FieldTable;
 use slow_interpreter::function_instruction_count::FunctionInstructionExecutionCount;
 use slow_interpreter::ir_to_java_layer::java_vm_state::JavaVMStateWrapper;
 use slow_interpreter::java_values::GC;
 use slow_interpreter::jvm_state::{Classes, CURRENT_THREAD_INVOKE_INTERFACE, JVM, JVMConfig, JVMState, JVMTIState, Native, NativeLibraries, StringInternment};
 use slow_interpreter::leaked_interface_arrays::InterfaceArrays;
+use slow_interpreter::loader_constraints::LoaderConstraints;
 use slow_interpreter::loading::Classpath;
 use slow_interpreter::native_allocation::NativeAllocator;
-use slow_interpreter::options::{JVMOptions, JVMOptionsStart, SharedLibraryPaths};
+use slow_interpreter::options::{AgentToLoad, JVMOptions, JVMOptionsStart, SharedLibraryPaths};
 use slow_interpreter::rust_jni::jvmti::SharedLibJVMTI;
 use slow_interpreter::rust_jni::mangling::ManglingRegex;
 use slow_interpreter::string_exit_cache::StringExitCache;
 use slow_interpreter::threading::java_thread::JavaThread;
 use slow_interpreter::threading::jvm_startup::{bootstrap_main_thread, MainThreadStartInfo};
 use slow_interpreter::threading::thread_state::ThreadState;
+use slow_interpreter::threading::signals::install_signal_handlers;
+use crate::jvm_management::vm_exit;
 use slow_interpreter::tracing::TracingSettings;
 use stage0::compiler::RecompileConditions;
 use stage0::compiler_common::frame_data::FunctionFrameData;
@@ -76,8 +81,14 @@ fn avx_check() {
 }
 
 pub fn main_<'l, 'env>() {
-    let jvm_args: JVMArgs = JVMArgs::parse();
-    let jvm_options_start = JVMOptionsStart::from_java_home(jvm_args.java_home.clone(), jvm_args);
+    let jvm_args: JVMArgs = parse_args();
+    let jvm_options_start = match JVMOptionsStart::from_java_home(jvm_args.java_home.clone(), jvm_args) {
+        Ok(jvm_options_start) => jvm_options_start,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
     let jvm_options = JVMOptions::from_options_start(jvm_options_start);
     let gc: GC<'l> = GC::new(get_regions());
     std::thread::scope::<'env>(|scope: &Scope<'_, 'env>| {
@@ -85,27 +96,75 @@ pub fn main_<'l, 'env>() {
         let scope_ref: &'l Scope<'l, 'l> = unsafe { transmute(scope) };
         let string_pool = CompressedClassfileStringPool::new();
         let string_pool_ref: &'l CompressedClassfileStringPool = unsafe { transmute(&string_pool) };
-        within_thread_scope(scope_ref, jvm_options, gc_ref, string_pool_ref);
+        let shared_archive = open_shared_archive(&jvm_options, string_pool_ref);
+        within_thread_scope(scope_ref, jvm_options, gc_ref, string_pool_ref, shared_archive);
     });
-    panic!();
+    unreachable!("the vm exits from within the thread scope, since daemon threads never finish")
+}
+
+// Restores the archive's string pool, so this comes before anything else is interned.
+fn open_shared_archive(jvm_options: &JVMOptions, string_pool: &CompressedClassfileStringPool) -> Option<SharedArchive> {
+    match jvm_options.share {
+        ShareMode::Off | ShareMode::Dump => None,
+        ShareMode::Auto | ShareMode::On => match SharedArchive::open(&jvm_options.shared_archive_file, &jvm_options.java_home, &jvm_options.boot_classpath, jvm_options.verify_bootstrap_classes, string_pool) {
+            Ok(shared_archive) => Some(shared_archive),
+            //like hotspot, auto quietly goes without an archive which is missing or stale
+            Err(_) if jvm_options.share == ShareMode::Auto => None,
+            Err(err) => {
+                eprintln!("An error has occurred while processing the shared archive file.\n{}\nError: Could not create the Java Virtual Machine.", err);
+                std::process::exit(1);
+            }
+        },
+    }
+}
+
+// The --long-flag syntax is this vm's own, anything else is taken to be the standard java launcher syntax.
+fn parse_args() -> JVMArgs {
+    let args = std::env::args().collect_vec();
+    if args.get(1).map(|arg| arg.starts_with("--")).unwrap_or(false) {
+        return JVMArgs::parse();
+    }
+    let java_tool_options = std::env::var("JAVA_TOOL_OPTIONS").ok();
+    if let Some(java_tool_options) = java_tool_options.as_ref() {
+        eprintln!("Picked up JAVA_TOOL_OPTIONS: {}", java_tool_options);
+    }
+    let classpath_env = std::env::var("CLASSPATH").ok();
+    let java_home_env = std::env::var_os("JAVA_HOME").map(PathBuf::from);
+    match parse_launcher_args(args.into_iter().skip(1).collect(), java_tool_options, std::env::current_exe().ok(), classpath_env, java_home_env) {
+        Ok(LauncherCommand::Run { jvm_args, show_version }) => {
+            if show_version {
+                eprint!("{}", version_message());
+            }
+            *jvm_args
+        }
+        Ok(LauncherCommand::Exit { message, code }) => {
+            eprint!("{}", message);
+            std::process::exit(code);
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    }
 }
 
-fn within_thread_scope<'l>(scope: &'l Scope<'l, 'l>, jvm_options: JVMOptions, gc: &'l GC<'l>, string_pool: &'l CompressedClassfileStringPool) {
-    let (args, jvm): (Vec<String>, JVMState<'l>) = initial_jvm_state(jvm_options, scope, gc, string_pool);
+fn within_thread_scope<'l>(scope: &'l Scope<'l, 'l>, jvm_options: JVMOptions, gc: &'l GC<'l>, string_pool: &'l CompressedClassfileStringPool, shared_archive: Option<SharedArchive>) {
+    let (args, jvm): (Vec<String>, JVMState<'l>) = initial_jvm_state(jvm_options, scope, gc, string_pool, shared_archive);
 
     let jvm_ref: &'l JVMState<'l> = Box::leak(Box::new(jvm));
-    main_run(args, &jvm_ref);
-    //todo clean jvm shutdown
-    std::process::exit(0);
+    let exit_code = main_run(args, &jvm_ref);
+    vm_exit(exit_code);
 }
 
-pub fn main_run<'gc>(args: Vec<String>, jvm_ref: &'gc JVMState<'gc>) {
+// the exit code of the launcher, once the main thread has shut down the vm
+pub fn main_run<'gc>(args: Vec<String>, jvm_ref: &'gc JVMState<'gc>) -> i32 {
     jvm_ref.java_vm_state.init(jvm_ref);
     unsafe { JVM = Some(transmute(jvm_ref)) }
     jvm_ref.add_class_class_class_object(&jvm_ref.cpdtype_table);
     let thread_state = &jvm_ref.thread_state;
     let main_thread: Arc<JavaThread> = bootstrap_main_thread(jvm_ref, &thread_state.threads, MainThreadStartInfo { args });
     let main_thread_clone = main_thread.clone();
+    install_signal_handlers(jvm_ref);
     // jvm_ref.thread_state.threads.create_thread(Some("stacktracer".to_string())).start_thread(box move |_| unsafe {
     //     loop {
     //         for (jtid, java_thread) in jvm_ref.thread_state.get_all_threads().iter() {
@@ -126,10 +185,14 @@ pub fn main_run<'gc>(args: Vec<String>, jvm_ref: &'gc JVMState<'gc>) {
     //     }
     // }, box ());
     main_thread.get_underlying().join();
-    jvm_ref.thread_state.wait_all_non_daemon_threads(jvm_ref);
+    if jvm_ref.thread_state.main_thread_uncaught_exception.load(Ordering::SeqCst) {
+        1
+    } else {
+        0
+    }
 }
 
-pub fn initial_jvm_state<'gc>(jvm_options: JVMOptions, scope: &'gc Scope<'gc, 'gc>, gc: &'gc GC<'gc>, string_pool: &'gc CompressedClassfileStringPool) -> (Vec<String>, JVMState<'gc>) {
+pub fn initial_jvm_state<'gc>(jvm_options: JVMOptions, scope: &'gc Scope<'gc, 'gc>, gc: &'gc GC<'gc>, string_pool: &'gc CompressedClassfileStringPool, shared_archive: Option<SharedArchive>) -> (Vec<String>, JVMState<'gc>) {
     let JVMOptions {
         main_class_name,
         classpath,
@@ -141,21 +204,42 @@ pub fn initial_jvm_state<'gc>(jvm_options: JVMOptions, scope: &'gc Scope<'gc, 'g
         unittest_mode,
         store_generated_classes,
         debug_print_exceptions,
-        assertions_enabled,
+        assertion_status,
         instruction_trace_options,
         exit_trace_options,
         thread_tracing_options,
         java_home,
         boot_classpath,
+        compile_threshold,
+        agents,
+        initial_heap_size,
+        max_heap_size,
+        thread_stack_size,
+        verbose_class,
+        verify_bootstrap_classes,
+        verify_user_classes,
+        share,
+        shared_archive_file,
+        shared_class_list_file,
     } = jvm_options;
     let SharedLibraryPaths { libjava, libjdwp } = shared_libs;
     let classpath_arc = Arc::new(classpath);
 
-    let tracing = if enable_tracing { TracingSettings::new() } else { TracingSettings::disabled() };
+    let mut tracing = if enable_tracing { TracingSettings::new() } else { TracingSettings::disabled() };
+    tracing.trace_class_loads = verbose_class;
 
     let jvmti_state = if enable_jvmti {
+        let agents = if agents.is_empty() { vec![AgentToLoad { path: libjdwp, options: None }] } else { agents };
+        let agents = agents.into_iter().map(|AgentToLoad { path, options }| match SharedLibJVMTI::load_agent(&path, options) {
+            Ok(agent) => Arc::new(agent),
+            Err(err) => {
+                eprintln!("Error occurred during initialization of VM\n{}", err);
+                std::process::exit(1);
+            }
+        }).collect_vec();
         JVMTIState {
-            built_in_jdwp: Arc::new(SharedLibJVMTI::load_libjdwp(&libjdwp)),
+            built_in_jdwp: agents[0].clone(),
+            agents,
             break_points: RwLock::new(HashMap::new()),
             tags: RwLock::new(HashMap::new()),
         }
@@ -176,11 +260,19 @@ pub fn initial_jvm_state<'gc>(jvm_options: JVMOptions, scope: &'gc Scope<'gc, 'g
         config: JVMConfig {
             store_generated_classes,
             debug_print_exceptions,
-            assertions_enabled,
+            assertion_status,
             compiled_mode_active: true,
             tracing,
             main_class_name,
-            compile_threshold: 1000,
+            compile_threshold,
+            initial_heap_size,
+            max_heap_size,
+            thread_stack_size,
+            verify_bootstrap_classes,
+            verify_user_classes,
+            share,
+            shared_archive_file,
+            shared_class_list_file,
         },
         properties,
         native_libaries: NativeLibraries::new(libjava),
@@ -236,6 +328,7 @@ pub fn initial_jvm_state<'gc>(jvm_options: JVMOptions, scope: &'gc Scope<'gc, 'g
         all_the_static_fields,
         java_home,
         boot_classpath,
+        shared_archive,
     };
     (args, jvm)
 }
@@ -344,6 +437,12 @@ fn init_classes<'gc>(pool: &CompressedClassfileStringPool, all_the_static_fields
         protection_domains: Default::default(),
         class_class_view: class_view,
         object_view: object_class_view,
+        defining_loaders: Default::default(),
+        loader_constraints: LoaderConstraints::new(),
+        unloaded_class_count: 0,
+        unloaded_class_loader_count: 0,
+        resolved_dynamic_constants: Default::default(),
+        implementors: Default::default(),
     });
     classes
 }
\ No newline at end of file
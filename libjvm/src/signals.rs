@@ -1,30 +1,24 @@
 use std::ffi::CStr;
-use std::mem::transmute;
 use std::os::raw::{c_char, c_void};
 
 use jvmti_jni_bindings::{jboolean, jint};
+use slow_interpreter::threading::signals::{find_signal, raise_signal, register_signal};
 
 #[no_mangle]
 unsafe extern "system" fn JVM_RegisterSignal(sig: jint, handler: *mut c_void) -> *mut c_void {
-    //todo unimpl for now
-    transmute(0xdeaddeadbeafdead as usize)
+    register_signal(sig, handler as usize) as *mut c_void
 }
 
 #[no_mangle]
 unsafe extern "system" fn JVM_RaiseSignal(sig: jint) -> jboolean {
-    unimplemented!()
+    raise_signal(sig) as jboolean
 }
 
 #[no_mangle]
 unsafe extern "system" fn JVM_FindSignal(name: *const c_char) -> jint {
     let name = CStr::from_ptr(name);
-    if name.to_bytes() == b"HUP" {
-        1 //todo bindgen signal.h
-    } else if name.to_bytes() == b"INT" {
-        2 //todo bindgen signal.h
-    } else if name.to_bytes() == b"TERM" {
-        15 //todo bindgen signal.h
-    } else {
-        unimplemented!()
+    match name.to_str().ok().and_then(find_signal) {
+        None => -1,
+        Some(signal) => signal,
     }
 }
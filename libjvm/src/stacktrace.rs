@@ -14,7 +14,9 @@ use runtime_class_stuff::RuntimeClass;
 use rust_jvm_common::classfile::{LineNumber, LineNumberTable, LineNumberTableEntry};
 use slow_interpreter::better_java_stack::frame_iter::FrameIterFrameRef;
 use slow_interpreter::better_java_stack::frames::HasFrame;
+use slow_interpreter::better_java_stack::native_frame::NativeFrame;
 use slow_interpreter::exceptions::WasException;
+use slow_interpreter::jvm_state::JVMState;
 use slow_interpreter::new_java_values::allocated_objects::AllocatedObjectHandleByAddress;
 
 use slow_interpreter::rust_jni::jni_utils::get_throw;
@@ -23,61 +25,22 @@ use slow_interpreter::stack_entry::StackEntry;
 use slow_interpreter::stdlib::java::lang::stack_trace_element::StackTraceElement;
 use slow_interpreter::stdlib::java::lang::string::JString;
 use slow_interpreter::stdlib::java::NewAsObjectOrJavaValue;
+use slow_interpreter::threading::thread_dump::DumpedFrame;
 use slow_interpreter::utils::{lookup_line_number, throw_array_out_of_bounds, throw_illegal_arg, throw_npe, throw_npe_res};
 use slow_interpreter::rust_jni::jni_utils::{get_interpreter_state, get_state};
 
-struct OwnedStackEntry<'gc> {
-    declaring_class: Arc<RuntimeClass<'gc>>,
-    line_number: LineNumber,
-    class_name_wtf8: Wtf8Buf,
-    method_name_wtf8: Wtf8Buf,
-    source_file_name_wtf8: Wtf8Buf,
+pub fn current_thread_stack_trace<'gc>(jvm: &'gc JVMState<'gc>, int_state: &mut NativeFrame<'gc, '_>) -> Result<Vec<StackTraceElement<'gc>>, WasException<'gc>> {
+    let frames = DumpedFrame::from_frames(jvm, int_state.frame_iter());
+    frames.iter()
+        .map(|frame| frame.stack_trace_element(jvm, int_state))
+        .collect::<Result<Vec<_>, WasException<'gc>>>()
 }
 
-
 #[no_mangle]
 unsafe extern "system" fn JVM_FillInStackTrace<'gc>(env: *mut JNIEnv, throwable: jobject) {
-    //todo handle opaque frames properly
     let jvm = get_state(env);
     let int_state = get_interpreter_state(env);
-    let stacktrace = int_state.frame_iter().collect_vec();
-    let stack_entry_objs = stacktrace
-        .iter()
-        .map(|stack_entry| {
-            let declaring_class = match stack_entry.try_class_pointer(jvm) {
-                Err(IsOpaque{}) => return Ok(None),
-                Ok(declaring_class) => declaring_class,
-            };
-
-            let declaring_class_view = declaring_class.view();
-            let method_view = declaring_class_view.method_view_i(stack_entry.method_i());
-            let file = match declaring_class_view.sourcefile_attr() {
-                None => Wtf8Buf::from_string("unknown_source".to_string()),
-                Some(sourcefile) => sourcefile.file(),
-            };
-            let line_number = match method_view.line_number_table() {
-                None => LineNumber(u16::MAX),
-                Some(line_number_table) => {
-                    lookup_line_number(line_number_table, stack_entry).unwrap_or(LineNumber(u16::MAX))
-                }
-            };
-            let class_name_wtf8 = Wtf8Buf::from_string(PTypeView::from_compressed(declaring_class_view.type_(), &jvm.string_pool).class_name_representation());
-            let method_name_wtf8 = Wtf8Buf::from_string(method_view.name().0.to_str(&jvm.string_pool));
-            let source_file_name_wtf8 = file;
-            Ok(Some(OwnedStackEntry { declaring_class, line_number, class_name_wtf8, method_name_wtf8, source_file_name_wtf8 }))
-        })
-        .collect::<Result<Vec<Option<_>>, WasException<'gc>>>()
-        .expect("todo")
-        .into_iter()
-        .flatten()
-        .map(|OwnedStackEntry { declaring_class, line_number, class_name_wtf8, method_name_wtf8, source_file_name_wtf8 }| {
-            let declaring_class_name = JString::from_rust(jvm, int_state, class_name_wtf8)?;
-            let method_name = JString::from_rust(jvm, int_state, method_name_wtf8)?;
-            let source_file_name = JString::from_rust(jvm, int_state, source_file_name_wtf8)?;
-
-            Ok(StackTraceElement::new(jvm, int_state, declaring_class_name, method_name, source_file_name, line_number)?)
-        })
-        .collect::<Result<Vec<_>, WasException<'gc>>>().expect("todo");
+    let stack_entry_objs = current_thread_stack_trace(jvm, int_state).expect("todo");
 
     let mut stack_traces_guard = jvm.stacktraces_by_throwable.write().unwrap();
     stack_traces_guard.insert(
@@ -5,7 +5,6 @@ use std::ops::Deref;
 use std::ptr::null_mut;
 use std::rc::Rc;
 use std::sync::{Arc, Condvar, RwLock, RwLockWriteGuard};
-use std::thread::sleep;
 use std::time::Duration;
 use itertools::Itertools;
 
@@ -17,13 +16,19 @@ use wtf8::Wtf8Buf;
 use classfile_view::view::ptype_view::PTypeView;
 use jvmti_jni_bindings::{_jobject, JAVA_THREAD_STATE_BLOCKED, JAVA_THREAD_STATE_NEW, JAVA_THREAD_STATE_RUNNABLE, JAVA_THREAD_STATE_TERMINATED, JAVA_THREAD_STATE_TIMED_WAITING, JAVA_THREAD_STATE_WAITING, jboolean, jclass, jint, jintArray, jlong, JNIEnv, jobject, jobjectArray, jstring, JVM_Available};
 use rust_jvm_common::classnames::ClassName;
+use rust_jvm_common::compressed_classfile::class_names::CClassName;
+use rust_jvm_common::compressed_classfile::compressed_types::CPDType;
 
 use rust_jvm_common::ptype::PType;
 use slow_interpreter::better_java_stack::frames::HasFrame;
+use slow_interpreter::better_java_stack::native_frame::NativeFrame;
+use slow_interpreter::class_loading::check_initing_or_inited_class;
 use slow_interpreter::exceptions::WasException;
 use slow_interpreter::interpreter::run_function;
 use slow_interpreter::interpreter_util::new_object;
 use slow_interpreter::java_values::{JavaValue, Object};
+use slow_interpreter::jvm_state::JVMState;
+use slow_interpreter::new_java_values::allocated_objects::AllocatedHandle;
 use slow_interpreter::new_java_values::NewJavaValueHandle;
 
 
@@ -35,8 +40,11 @@ use slow_interpreter::stdlib::java::lang::thread::JThread;
 use slow_interpreter::stdlib::java::lang::thread_group::JThreadGroup;
 use slow_interpreter::stdlib::java::NewAsObjectOrJavaValue;
 use slow_interpreter::threading::safepoints::Monitor2;
-use slow_interpreter::utils::pushable_frame_todo;
-use slow_interpreter::rust_jni::jni_utils::{get_interpreter_state, get_state};
+use slow_interpreter::threading::thread_dump::{DumpedFrame, full_thread_dump};
+use slow_interpreter::utils::{pushable_frame_todo, throw_npe};
+use slow_interpreter::rust_jni::jni_utils::{get_interpreter_state, get_state, get_throw};
+
+use crate::stacktrace::current_thread_stack_trace;
 
 #[no_mangle]
 unsafe extern "system" fn JVM_StartThread(env: *mut JNIEnv, thread: jobject) {
@@ -112,7 +120,12 @@ unsafe extern "system" fn JVM_Sleep(env: *mut JNIEnv, _threadClass: jclass, mill
         unimplemented!()
     }
     //todo figure out what threadClass is for
-    sleep(Duration::from_millis(millis as u64))
+    let jvm = get_state(env);
+    let int_state = get_interpreter_state(env);
+    let current_thread = jvm.thread_state.get_current_thread();
+    if let Err(WasException { exception_obj }) = current_thread.sleep(jvm, int_state, Duration::from_millis(millis as u64)) {
+        *get_throw(env) = Some(WasException { exception_obj });
+    }
 }
 
 #[no_mangle]
@@ -130,9 +143,11 @@ unsafe extern "system" fn JVM_CurrentThread(env: *mut JNIEnv, threadClass: jclas
 #[no_mangle]
 unsafe extern "system" fn JVM_Interrupt(env: *mut JNIEnv, thread: jobject) {
     let jvm = get_state(env);
-    let int_state = get_interpreter_state(env);
-    int_state.debug_print_stack_trace(jvm);
-    todo!("This seems to need signals or some shit. Seems hard to implement")
+    let thread_object = from_object_new(jvm, thread).unwrap().new_java_value_handle().cast_thread(jvm);
+    // interrupting a thread which has not started yet does nothing
+    if let Some(thread) = thread_object.try_get_java_thread(jvm) {
+        thread.interrupt();
+    }
 }
 
 #[no_mangle]
@@ -140,9 +155,10 @@ unsafe extern "system" fn JVM_IsInterrupted(env: *mut JNIEnv, thread: jobject, c
     let jvm = get_state(env);
     let int_state = get_interpreter_state(env);
     let thread_object = from_object_new(jvm, thread).unwrap().new_java_value_handle().cast_thread(jvm);
-    let thread = thread_object.get_java_thread(jvm);
-    let guard = thread.thread_status.lock().unwrap();
-    guard.interrupted as jboolean
+    match thread_object.try_get_java_thread(jvm) {
+        None => false as jboolean,
+        Some(thread) => thread.is_interrupted(clearInterrupted != 0) as jboolean,
+    }
 }
 
 #[no_mangle]
@@ -155,7 +171,10 @@ unsafe extern "system" fn JVM_HoldsLock(env: *mut JNIEnv, threadClass: jclass, o
 
 #[no_mangle]
 unsafe extern "system" fn JVM_DumpAllStacks(env: *mut JNIEnv, unused: jclass) {
-    unimplemented!()
+    let jvm = get_state(env);
+    let int_state = get_interpreter_state(env);
+    let current_thread_frames = DumpedFrame::from_frames(jvm, int_state.frame_iter());
+    print!("{}", full_thread_dump(jvm, Some(current_thread_frames)));
 }
 
 #[no_mangle]
@@ -163,16 +182,15 @@ unsafe extern "system" fn JVM_GetAllThreads(env: *mut JNIEnv, _dummy: jclass) ->
     //the dummy appears b/c stuff gets called from static native fucntion in jni, and someone didn't want to get rid of param and just have a direct function pointer
     let jvm = get_state(env);
     let int_state = get_interpreter_state(env);
-    let jobjects = jvm
+    let thread_objects = jvm
         .thread_state
         .get_all_alive_threads()
         .into_iter()
-        .map(|java_thread| {
-            JavaValue::Object(todo!() /*java_thread.try_thread_object().map(|jthread| jthread.object())*/)
-        })
-        .collect::<Vec<_>>();
-    let object_array = todo!()/*JavaValue::new_vec_from_vec(jvm, jobjects, CClassName::thread().into()).unwrap_object()*/;
-    new_local_ref_public(todo!()/*object_array*/, int_state)
+        .filter(|java_thread| !java_thread.invisible_to_java)
+        .flat_map(|java_thread| java_thread.try_thread_object())
+        .collect_vec();
+    let object_array = JavaValue::new_vec_from_vec(jvm, thread_objects.iter().map(|thread| thread.new_java_value()).collect(), CClassName::thread().into());
+    new_local_ref_public_new(Some(object_array.as_allocated_obj()), int_state)
 }
 
 #[no_mangle]
@@ -184,7 +202,43 @@ unsafe extern "system" fn JVM_SetNativeThreadName(env: *mut JNIEnv, jthread: job
 
 #[no_mangle]
 unsafe extern "system" fn JVM_DumpThreads(env: *mut JNIEnv, threadClass: jclass, threads: jobjectArray) -> jobjectArray {
-    unimplemented!()
+    let jvm = get_state(env);
+    let int_state = get_interpreter_state(env);
+    let threads = match from_object_new(jvm, threads) {
+        Some(threads) => threads,
+        None => return throw_npe(jvm, int_state, get_throw(env)),
+    };
+    match dump_threads(jvm, int_state, threads) {
+        Ok(stack_traces) => new_local_ref_public_new(Some(stack_traces.as_allocated_obj()), int_state),
+        Err(WasException { exception_obj }) => {
+            *get_throw(env) = Some(WasException { exception_obj });
+            null_mut()
+        }
+    }
+}
+
+//a StackTraceElement[][] with one stack per requested thread
+fn dump_threads<'gc>(jvm: &'gc JVMState<'gc>, int_state: &mut NativeFrame<'gc, '_>, threads: AllocatedHandle<'gc>) -> Result<AllocatedHandle<'gc>, WasException<'gc>> {
+    let stack_trace_array_type = CPDType::array(CClassName::stack_trace_element().into());
+    check_initing_or_inited_class(jvm, int_state, stack_trace_array_type)?;
+    check_initing_or_inited_class(jvm, int_state, CPDType::array(stack_trace_array_type))?;
+    let current_tid = jvm.thread_state.get_current_thread().java_tid;
+    let threads = threads.unwrap_array().array_iterator().collect_vec();
+    let mut stack_traces = vec![];
+    for thread in threads {
+        let stack_trace = match thread.try_cast_thread(jvm) {
+            Some(thread) if thread.tid(jvm) == current_tid => current_thread_stack_trace(jvm, int_state)?,
+            Some(thread) => {
+                //threads which have exited, or which never reached a point where their stack could be viewed get an empty stack trace
+                let frames = thread.try_get_java_thread(jvm).and_then(|java_thread| java_thread.stack_snapshot(jvm)).unwrap_or_default();
+                frames.iter().map(|frame| frame.stack_trace_element(jvm, int_state)).collect::<Result<Vec<_>, WasException<'gc>>>()?
+            }
+            None => vec![],
+        };
+        let stack_trace = JavaValue::new_vec_from_vec(jvm, stack_trace.iter().map(|element| element.new_java_value()).collect(), CClassName::stack_trace_element().into());
+        stack_traces.push(stack_trace);
+    }
+    Ok(JavaValue::new_vec_from_vec(jvm, stack_traces.iter().map(|stack_trace| stack_trace.new_java_value()).collect(), stack_trace_array_type))
 }
 
 #[no_mangle]
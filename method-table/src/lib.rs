@@ -16,7 +16,8 @@ pub fn from_jmethod_id(jmethod: *mut _jmethodID) -> MethodId {
 }
 
 pub struct MethodTable<'gc> {
-    table: Vec<(Arc<RuntimeClass<'gc>>, u16)>,
+    //entries of unloaded classes are None, ids are never reused
+    table: Vec<Option<(Arc<RuntimeClass<'gc>>, u16)>>,
     //at a later date will contain compiled code etc.
     index: HashMap<ByAddress<Arc<RuntimeClass<'gc>>>, HashMap<u16, MethodTableIndex>>,
 }
@@ -40,7 +41,7 @@ impl<'gc> MethodTable<'gc> {
     pub fn register_with_table(&mut self, rc: Arc<RuntimeClass<'gc>>, method_index: u16) -> MethodTableIndex {
         assert_ne!(method_index, u16::MAX);
         let res = self.table.len();
-        self.table.push((rc.clone(), method_index));
+        self.table.push(Some((rc.clone(), method_index)));
         match self.index.get_mut(&ByAddress(rc.clone())) {
             None => {
                 let mut class_methods = HashMap::new();
@@ -55,11 +56,16 @@ impl<'gc> MethodTable<'gc> {
     }
 
     pub fn try_lookup(&self, id: MethodId) -> Option<(Arc<RuntimeClass<'gc>>, u16)> {
-        if id < self.table.len() {
-            self.table[id].clone().into()
-        } else {
-            None
+        self.table.get(id).cloned().flatten()
+    }
+
+    /// Forgets the methods of an unloaded class, returning their ids.
+    pub fn unload_class(&mut self, rc: &Arc<RuntimeClass<'gc>>) -> Vec<MethodId> {
+        let method_ids = self.index.remove(&ByAddress(rc.clone())).map(|class_methods| class_methods.into_values().collect()).unwrap_or_default();
+        for method_id in &method_ids {
+            self.table[*method_id] = None;
         }
+        method_ids
     }
 
     pub fn lookup_method_string(&self, method_id: MethodId, string_pool: &CompressedClassfileStringPool) -> String {
@@ -73,6 +73,17 @@ impl<'gc> AllTheStaticFields<'gc> {
         self.inner.read().unwrap().fields.get(&class_name).unwrap().get(&field_name).unwrap().clone()
     }
 
+    pub fn raw_addresses(&self, class_name: CClassName) -> Vec<NonNull<c_void>> {
+        self.inner.read().unwrap().fields.get(&class_name).map(|fields| fields.values().map(|field| field.raw_address()).collect()).unwrap_or_default()
+    }
+
+    //the fields themselves stay allocated, since compiled code may have their addresses baked in
+    pub fn unload_class(&self, class_name: CClassName) {
+        let mut write_guard = self.inner.write().unwrap();
+        write_guard.fields.remove(&class_name);
+        write_guard.already_loaded.remove(&class_name);
+    }
+
     //ordered by first class is Object and/or interfaces
     pub fn sink_class_load(&self, static_fields: Vec<(CClassName, Vec<(FieldName, CPDType)>)>) {
         let mut write_guard = self.inner.write().unwrap();
@@ -1,50 +1,51 @@
 use std::hash::Hasher;
 
 use num_derive::FromPrimitive;
-use wtf8::Wtf8Buf;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use wtf8::{CodePoint, Wtf8Buf};
 
 use crate::ByteCodeOffset;
 use crate::classnames::class_name;
 use crate::compressed_classfile::code::LiveObjectIndex;
 use crate::ptype::PType;
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct SourceFile {
     pub sourcefile_index: CPIndex,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct InnerClasses {
     pub classes: Vec<InnerClass>,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct EnclosingMethod {
     pub class_index: CPIndex,
     pub method_index: CPIndex,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct SourceDebugExtension {
     pub debug_extension: Vec<u8>,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct BootstrapMethods {
     pub bootstrap_methods: Vec<BootstrapMethod>,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct NestHost {
     pub host_class_index: u16,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct ConstantValue {
     pub constant_value_index: u16,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Code {
     pub attributes: Vec<AttributeInfo>,
     pub max_stack: u16,
@@ -54,7 +55,7 @@ pub struct Code {
     pub exception_table: Vec<ExceptionTableElem>,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct ExceptionTableElem {
     pub start_pc: ByteCodeOffset,
     pub end_pc: ByteCodeOffset,
@@ -63,68 +64,68 @@ pub struct ExceptionTableElem {
 }
 
 //todo what if line number more thatn 64k?
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
 pub struct LineNumber(pub u16);
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct LineNumberTableEntry {
     pub start_pc: ByteCodeOffset,
     pub line_number: LineNumber,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Exceptions {
     pub exception_index_table: Vec<u16>,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct RuntimeVisibleParameterAnnotations {
     pub parameter_annotations: Vec<Vec<Annotation>>,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct RuntimeInvisibleParameterAnnotations {
     pub parameter_annotations: Vec<Vec<Annotation>>,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct AnnotationDefault {
     pub default_value: ElementValue,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct MethodParameter {
     pub name_index: u16,
     pub access_flags: u16,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct MethodParameters {
     pub parameters: Vec<MethodParameter>,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Synthetic {}
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Deprecated {}
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Signature {
     pub signature_index: u16,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct RuntimeVisibleAnnotations {
     pub annotations: Vec<Annotation>,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct RuntimeInvisibleAnnotations {
     pub annotations: Vec<Annotation>,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct LineNumberTable {
     pub line_number_table: Vec<LineNumberTableEntry>,
 }
@@ -141,12 +142,12 @@ impl LineNumberTable{
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct LocalVariableTable {
     pub local_variable_table: Vec<LocalVariableTableEntry>,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct LocalVariableTableEntry {
     pub start_pc: u16,
     pub length: u16,
@@ -155,7 +156,7 @@ pub struct LocalVariableTableEntry {
     pub index: u16,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct LocalVariableTypeTableEntry {
     pub start_pc: u16,
     pub length: u16,
@@ -164,28 +165,28 @@ pub struct LocalVariableTypeTableEntry {
     pub index: u16,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct LocalVariableTypeTable {
     pub type_table: Vec<LocalVariableTypeTableEntry>,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct ObjectVariableInfo {
     pub cpool_index: Option<u16>,
     pub class_name: String,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct ArrayVariableInfo {
     pub array_type: PType,
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct UninitializedVariableInfo {
     pub offset: ByteCodeOffset,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum VerificationTypeInfo {
     Top,
     Integer,
@@ -199,41 +200,41 @@ pub enum VerificationTypeInfo {
     Array(ArrayVariableInfo),
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct SameFrame {
     pub offset_delta: u16,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct SameLocals1StackItemFrame {
     pub offset_delta: u16,
     pub stack: PType,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct SameLocals1StackItemFrameExtended {
     pub offset_delta: u16,
     pub stack: PType,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct ChopFrame {
     pub offset_delta: u16,
     pub k_frames_to_chop: u8,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct SameFrameExtended {
     pub offset_delta: u16,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct AppendFrame {
     pub offset_delta: u16,
     pub locals: Vec<PType>,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct FullFrame {
     pub offset_delta: u16,
     pub number_of_locals: u16,
@@ -242,7 +243,7 @@ pub struct FullFrame {
     pub stack: Vec<PType>,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub enum StackMapFrame {
     SameFrame(SameFrame),
     SameLocals1StackItemFrame(SameLocals1StackItemFrame),
@@ -253,19 +254,19 @@ pub enum StackMapFrame {
     FullFrame(FullFrame),
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct StackMapTable {
     pub entries: Vec<StackMapFrame>,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct LocalVarTargetTableEntry {
     pub start_pc: u16,
     pub length: u16,
     pub index: u16,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub enum TargetInfo {
     TypeParameterTarget { type_parameter_index: u8 },
     SuperTypeTarget { supertype_index: u16 },
@@ -279,18 +280,18 @@ pub enum TargetInfo {
     TypeArgumentTarget { offset: u16, type_argument_index: u8 },
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct TypePathEntry {
     pub type_path_kind: u8,
     pub type_argument_index: u8,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct TypePath {
     pub path: Vec<TypePathEntry>,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct TypeAnnotation {
     pub target_type: TargetInfo,
     pub target_path: TypePath,
@@ -298,22 +299,88 @@ pub struct TypeAnnotation {
     pub element_value_pairs: Vec<ElementValuePair>,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct RuntimeVisibleTypeAnnotations {
     pub annotations: Vec<TypeAnnotation>,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct RuntimeInvisibleTypeAnnotations {
     pub annotations: Vec<TypeAnnotation>,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct NestMembers {
     pub classes: Vec<u16>,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ModuleRequires {
+    pub requires_index: CPIndex,
+    pub requires_flags: u16,
+    pub requires_version_index: CPIndex,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ModuleExports {
+    pub exports_index: CPIndex,
+    pub exports_flags: u16,
+    pub exports_to_index: Vec<CPIndex>,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ModuleOpens {
+    pub opens_index: CPIndex,
+    pub opens_flags: u16,
+    pub opens_to_index: Vec<CPIndex>,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ModuleProvides {
+    pub provides_index: CPIndex,
+    pub provides_with_index: Vec<CPIndex>,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Module {
+    pub module_name_index: CPIndex,
+    pub module_flags: u16,
+    pub module_version_index: CPIndex,
+    pub requires: Vec<ModuleRequires>,
+    pub exports: Vec<ModuleExports>,
+    pub opens: Vec<ModuleOpens>,
+    pub uses_index: Vec<CPIndex>,
+    pub provides: Vec<ModuleProvides>,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ModulePackages {
+    pub package_index: Vec<CPIndex>,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ModuleMainClass {
+    pub main_class_index: CPIndex,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct RecordComponentInfo {
+    pub name_index: CPIndex,
+    pub descriptor_index: CPIndex,
+    pub attributes: Vec<AttributeInfo>,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub components: Vec<RecordComponentInfo>,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PermittedSubclasses {
+    pub classes: Vec<CPIndex>,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub enum AttributeType {
     SourceFile(SourceFile),
     InnerClasses(InnerClasses),
@@ -322,6 +389,11 @@ pub enum AttributeType {
     BootstrapMethods(BootstrapMethods),
     NestHost(NestHost),
     NestMembers(NestMembers),
+    Module(Module),
+    ModulePackages(ModulePackages),
+    ModuleMainClass(ModuleMainClass),
+    Record(Record),
+    PermittedSubclasses(PermittedSubclasses),
     ConstantValue(ConstantValue),
     Code(Code),
     Exceptions(Exceptions),
@@ -343,7 +415,7 @@ pub enum AttributeType {
     Unknown,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct BootstrapMethod {
     pub bootstrap_method_ref: u16,
     pub bootstrap_arguments: Vec<BootstrapArg>,
@@ -351,7 +423,7 @@ pub struct BootstrapMethod {
 
 type BootstrapArg = u16;
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct InnerClass {
     pub inner_class_info_index: CPIndex,
     pub outer_class_info_index: CPIndex,
@@ -361,28 +433,28 @@ pub struct InnerClass {
 
 pub type CPIndex = u16;
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct EnumConstValue {
     pub type_name_index: CPIndex,
     pub const_name_index: CPIndex,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct ClassInfoIndex {
     pub class_info_index: CPIndex,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct AnnotationValue {
     pub annotation: Annotation,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct ArrayValue {
     pub values: Vec<ElementValue>,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub enum ElementValue {
     Byte(CPIndex),
     Char(CPIndex),
@@ -399,99 +471,114 @@ pub enum ElementValue {
     ArrayType(ArrayValue),
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct ElementValuePair {
     pub element_name_index: CPIndex,
     pub value: ElementValue,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Annotation {
     pub type_index: u16,
     pub num_element_value_pairs: u16,
     pub element_value_pairs: Vec<ElementValuePair>,
 }
 
-#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, Serialize, Deserialize)]
 pub struct IInc {
     pub index: u16,
     pub const_: i16,
 }
 
-#[derive(Debug, Eq)]
+#[derive(Debug, Eq, Serialize, Deserialize)]
 pub struct Utf8 {
     pub length: u16,
+    #[serde(serialize_with = "serialize_wtf8", deserialize_with = "deserialize_wtf8")]
     pub string: Wtf8Buf,
 }
 
+//wtf8 has no serde support, so strings go through their code points
+fn serialize_wtf8<S: Serializer>(string: &Wtf8Buf, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(string.code_points().map(|code_point| code_point.to_u32()))
+}
+
+fn deserialize_wtf8<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Wtf8Buf, D::Error> {
+    let code_points = Vec::<u32>::deserialize(deserializer)?;
+    let mut res = Wtf8Buf::new();
+    for code_point in code_points {
+        res.push(CodePoint::from_u32(code_point).ok_or_else(|| serde::de::Error::custom("invalid code point"))?);
+    }
+    Ok(res)
+}
+
 impl PartialEq for Utf8 {
     fn eq(&self, other: &Self) -> bool {
         self.length == other.length && self.string == other.string
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Integer {
     //unimplemented!()
     pub bytes: u32,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Float {
     pub bytes: u32,
     //unimplemented!()
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Long {
     pub low_bytes: u32,
     pub high_bytes: u32,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Double {
     pub low_bytes: u32,
     pub high_bytes: u32,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Class {
     //unimplemented!()
     pub name_index: u16,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct String_ {
     //unimplemented!()
     pub string_index: u16,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Fieldref {
     //unimplemented!()
     pub class_index: CPIndex,
     pub name_and_type_index: CPIndex,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Methodref {
     pub class_index: CPIndex,
     pub name_and_type_index: CPIndex,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct InterfaceMethodref {
     pub class_index: CPIndex,
     pub nt_index: CPIndex,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct NameAndType {
     pub name_index: CPIndex,
     pub descriptor_index: CPIndex,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub enum ReferenceKind {
     //1 REF_getField getfield C.f:T
     // 2 REF_getStatic getstatic C.f:T
@@ -517,27 +604,43 @@ pub enum ReferenceKind {
     InvokeInterface,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct MethodHandle {
     pub reference_kind: ReferenceKind,
     pub reference_index: CPIndex,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct MethodType {
     pub descriptor_index: CPIndex,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct InvokeDynamic {
     pub bootstrap_method_attr_index: CPIndex,
     pub name_and_type_index: CPIndex,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Dynamic {
+    pub bootstrap_method_attr_index: CPIndex,
+    pub name_and_type_index: CPIndex,
+}
+
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Module_ {
+    pub name_index: CPIndex,
+}
+
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Package_ {
+    pub name_index: CPIndex,
+}
+
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct InvalidConstant {}
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
 //#[derive(Copy, Clone)]
 pub enum ConstantKind {
     Utf8(Utf8),
@@ -554,11 +657,14 @@ pub enum ConstantKind {
     MethodHandle(MethodHandle),
     MethodType(MethodType),
     InvokeDynamic(InvokeDynamic),
+    Dynamic(Dynamic),
+    Module(Module_),
+    Package(Package_),
     InvalidConstant(InvalidConstant),
     LiveObject(LiveObjectIndex), //live object pool index
 }
 
-#[derive(Debug, Eq)]
+#[derive(Debug, Eq, Serialize, Deserialize)]
 pub struct ConstantInfo {
     pub kind: ConstantKind,
 }
@@ -569,14 +675,14 @@ impl PartialEq for ConstantInfo {
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct AttributeInfo {
     pub attribute_name_index: u16,
     pub attribute_length: u32,
     pub attribute_type: AttributeType,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct FieldInfo {
     pub access_flags: u16,
     pub name_index: CPIndex,
@@ -584,7 +690,7 @@ pub struct FieldInfo {
     pub attributes: Vec<AttributeInfo>,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct MethodInfo {
     pub access_flags: u16,
     pub name_index: CPIndex,
@@ -592,25 +698,25 @@ pub struct MethodInfo {
     pub attributes: Vec<AttributeInfo>,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct InvokeInterface {
     pub index: u16,
     pub count: u8,
 }
 
-#[derive(Debug, Eq, PartialEq, Hash, Clone)]
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Serialize, Deserialize)]
 pub struct LookupSwitch {
     pub pairs: Vec<(i32, i32)>,
     pub default: i32,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct MultiNewArray {
     pub index: CPIndex,
     pub dims: u8,
 }
 
-#[derive(Debug, Eq, PartialEq, Hash)]
+#[derive(Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 #[repr(u8)]
 #[derive(Copy, Clone, FromPrimitive)]
 pub enum Atype {
@@ -624,7 +730,7 @@ pub enum Atype {
     TLong = 11,
 }
 
-#[derive(Debug, Eq, PartialEq, Hash, Clone)]
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Serialize, Deserialize)]
 pub struct TableSwitch {
     pub default: i32,
     pub low: i32,
@@ -632,7 +738,7 @@ pub struct TableSwitch {
     pub offsets: Vec<i32>,
 }
 
-#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, Serialize, Deserialize)]
 pub enum Wide {
     Iload(WideIload),
     Fload(WideFload),
@@ -650,62 +756,62 @@ pub enum Wide {
 //iload, fload, aload, lload, dload, istore, fstore, astore,
 // lstore, dstore, or ret
 
-#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, Serialize, Deserialize)]
 pub struct WideIload {
     pub index: u16,
 }
 
-#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, Serialize, Deserialize)]
 pub struct WideFload {
     pub index: u16,
 }
 
-#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, Serialize, Deserialize)]
 pub struct WideAload {
     pub index: u16,
 }
 
-#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, Serialize, Deserialize)]
 pub struct WideLload {
     pub index: u16,
 }
 
-#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, Serialize, Deserialize)]
 pub struct WideDload {
     pub index: u16,
 }
 
-#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, Serialize, Deserialize)]
 pub struct WideIstore {
     pub index: u16,
 }
 
-#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, Serialize, Deserialize)]
 pub struct WideFstore {
     pub index: u16,
 }
 
-#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, Serialize, Deserialize)]
 pub struct WideAstore {
     pub index: u16,
 }
 
-#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, Serialize, Deserialize)]
 pub struct WideLstore {
     pub index: u16,
 }
 
-#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, Serialize, Deserialize)]
 pub struct WideDstore {
     pub index: u16,
 }
 
-#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, Serialize, Deserialize)]
 pub struct WideRet {
     pub index: u16,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Instruction {
     pub offset: ByteCodeOffset,
     pub size: u16,
@@ -713,7 +819,7 @@ pub struct Instruction {
 }
 
 #[allow(non_camel_case_types)]
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub enum InstructionInfo {
     aaload,
     aastore,
@@ -1163,7 +1269,7 @@ pub const REF_INVOKE_SPECIAL: u8 = 7;
 pub const REF_NEW_INVOKE_SPECIAL: u8 = 8;
 pub const REF_INVOKE_INTERFACE: u8 = 9;
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Classfile {
     pub magic: u32,
     pub minor_version: u16,
@@ -4,6 +4,8 @@ use std::hash::Hash;
 use std::hash::Hasher;
 use std::sync::Weak;
 
+use serde::{Deserialize, Serialize};
+
 use crate::classfile::Classfile;
 use crate::ptype::ReferenceType;
 
@@ -21,7 +23,7 @@ impl PartialEq for NameReference {
     }
 }
 
-#[derive(Eq)]
+#[derive(Eq, Serialize, Deserialize)]
 pub enum ClassName {
     Str(String),
 }
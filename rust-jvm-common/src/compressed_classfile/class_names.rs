@@ -1,10 +1,11 @@
 use std::fmt::{Debug, Formatter};
 use add_only_static_vec::{AddOnlyId, AddOnlyVecIDType};
+use serde::{Deserialize, Serialize};
 use crate::compressed_classfile::{CompressedClassfileString};
 use crate::compressed_classfile::compressed_types::CompressedParsedRefType;
 use crate::compressed_classfile::names::PredefinedStrings::*;
 
-#[derive(Clone, Copy, Eq, PartialEq, Hash)]
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct CompressedClassName(pub CompressedClassfileString);
 
 impl Debug for CompressedClassName {
@@ -200,6 +201,38 @@ impl CompressedClassName {
         Self::from_raw_id(SUN_MISC_LAUNCHER as AddOnlyVecIDType)
     }
 
+    pub const fn signal() -> Self {
+        Self::from_raw_id(SUN_MISC_SIGNAL as AddOnlyVecIDType)
+    }
+
+    pub const fn shutdown() -> Self {
+        Self::from_raw_id(JAVA_LANG_SHUTDOWN as AddOnlyVecIDType)
+    }
+
+    pub const fn assertion_status_directives() -> Self {
+        Self::from_raw_id(JAVA_LANG_ASSERTION_STATUS_DIRECTIVES as AddOnlyVecIDType)
+    }
+
+    pub const fn verify_error() -> Self {
+        Self::from_raw_id(JAVA_LANG_VERIFY_ERROR as AddOnlyVecIDType)
+    }
+
+    pub const fn class_format_error() -> Self {
+        Self::from_raw_id(JAVA_LANG_CLASS_FORMAT_ERROR as AddOnlyVecIDType)
+    }
+
+    pub const fn no_class_def_found_error() -> Self {
+        Self::from_raw_id(JAVA_LANG_NO_CLASS_DEF_FOUND_ERROR as AddOnlyVecIDType)
+    }
+
+    pub const fn stack_overflow_error() -> Self {
+        Self::from_raw_id(JAVA_LANG_STACK_OVERFLOW_ERROR as AddOnlyVecIDType)
+    }
+
+    pub const fn magic_accessor_impl() -> Self {
+        Self::from_raw_id(SUN_REFLECT_MAGIC_ACCESSOR_IMPL as AddOnlyVecIDType)
+    }
+
     pub const fn reflection() -> Self {
         Self::from_raw_id(SUN_REFLECT_REFLECTION as AddOnlyVecIDType)
     }
@@ -298,6 +331,14 @@ impl CompressedClassName {
     pub const fn index_out_bounds_exception() -> Self{
         Self::from_raw_id(JAVA_LANG_INDEX_OUT_OF_BOUNDS_EXCEPTION as AddOnlyVecIDType)
     }
+
+    pub const fn interrupted_exception() -> Self {
+        Self::from_raw_id(JAVA_LANG_INTERRUPTED_EXCEPTION as AddOnlyVecIDType)
+    }
+
+    pub const fn runtime() -> Self {
+        Self::from_raw_id(JAVA_LANG_RUNTIME as AddOnlyVecIDType)
+    }
 }
 
 impl From<CompressedClassName> for CompressedParsedRefType {
@@ -3,6 +3,7 @@ use std::hash::{Hash, Hasher};
 use std::num::NonZeroU8;
 
 use itertools::Either;
+use serde::{Deserialize, Serialize};
 use wtf8::Wtf8Buf;
 
 use crate::ByteCodeOffset;
@@ -534,6 +535,9 @@ impl CompressedInstructionInfo {
                             CompressedLdcW::LiveObject(_) => {
                                 todo!()
                             }
+                            CompressedLdcW::Dynamic { cp, type_ } => {
+                                format!("ldc:dynamic:#{}:{}", cp, type_.jvm_representation(string_pool))
+                            }
                         }
                     }
                     Either::Right(ldc_type) => {
@@ -544,6 +548,9 @@ impl CompressedInstructionInfo {
                             CompressedLdc2W::Double(double) => {
                                 format!("ldc:double:{}", double)
                             }
+                            CompressedLdc2W::Dynamic { cp, type_ } => {
+                                format!("ldc:dynamic:#{}:{}", cp, type_.jvm_representation(string_pool))
+                            }
                         }
                     }
                 }
@@ -571,6 +578,9 @@ impl CompressedInstructionInfo {
                     CompressedLdcW::LiveObject(_) => {
                         todo!()
                     }
+                    CompressedLdcW::Dynamic { cp, type_ } => {
+                        format!("ldc_w:dynamic:#{}:{}", cp, type_.jvm_representation(string_pool))
+                    }
                 }
             }
             CompressedInstructionInfo::ldc2_w(ldc_type) => {
@@ -581,6 +591,9 @@ impl CompressedInstructionInfo {
                     CompressedLdc2W::Double(double) => {
                         format!("ldc2_w:double:{}", double)
                     }
+                    CompressedLdc2W::Dynamic { cp, type_ } => {
+                        format!("ldc2_w:dynamic:#{}:{}", cp, type_.jvm_representation(string_pool))
+                    }
                 }
             }
             CompressedInstructionInfo::ldiv => {
@@ -1389,7 +1402,7 @@ impl CInstructionInfo {
 }
 
 #[repr(transparent)]
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct LiveObjectIndex(pub usize);
 
 #[derive(Debug, Clone)]
@@ -1401,6 +1414,8 @@ pub enum CompressedLdcW {
     MethodType {},
     MethodHandle {},
     LiveObject(LiveObjectIndex),
+    //resolved lazily through its bootstrap method, so only the pool index and the constant's type are kept
+    Dynamic { cp: u16, type_: CPDType },
 }
 
 impl PartialEq for CompressedLdcW {
@@ -1448,6 +1463,12 @@ impl PartialEq for CompressedLdcW {
                 }
                 false
             }
+            CompressedLdcW::Dynamic { cp, type_ } => {
+                if let CompressedLdcW::Dynamic { cp: other_cp, type_: other_type } = other {
+                    return cp == other_cp && type_ == other_type;
+                }
+                false
+            }
         }
     }
 }
@@ -1466,6 +1487,10 @@ impl Hash for CompressedLdcW {
                 state.write_usize(0);
             }
             CompressedLdcW::LiveObject(LiveObjectIndex(index)) => state.write_usize(*index),
+            CompressedLdcW::Dynamic { cp, type_ } => {
+                state.write_u16(*cp);
+                type_.hash(state);
+            }
         }
     }
 }
@@ -1476,6 +1501,7 @@ impl Eq for CompressedLdcW {}
 pub enum CompressedLdc2W {
     Long(i64),
     Double(f64),
+    Dynamic { cp: u16, type_: CPDType },
 }
 
 impl PartialEq for CompressedLdc2W {
@@ -1,6 +1,7 @@
 use std::cmp::Ordering;
 use std::iter;
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 use crate::classfile::UninitializedVariableInfo;
 use crate::compressed_classfile::class_names::{CClassName, CompressedClassName};
 use crate::compressed_classfile::compressed_descriptors::{CompressedMethodDescriptor, mangling_escape};
@@ -157,7 +158,7 @@ impl CompressedParsedRefType {
 
 pub type CPDType = CompressedParsedDescriptorType;
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum CompressedParsedDescriptorType {
     //make sure this stays in sync with CompressedParsedDescriptorTypeNativeDiscriminant
@@ -177,7 +178,7 @@ pub enum CompressedParsedDescriptorType {
     } = 10,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum NonArrayCompressedParsedDescriptorType {
     BooleanType = 0,
@@ -99,6 +99,12 @@ impl FieldName {
     pub fn field_val() -> Self {
         Self::from_raw_id(field_val)
     }
+    pub fn field_group() -> Self {
+        Self::from_raw_id(field_group)
+    }
+    pub fn field_groups() -> Self {
+        Self::from_raw_id(field_groups)
+    }
     pub fn field_index() -> Self {
         Self::from_raw_id(field_index)
     }
@@ -136,6 +142,12 @@ impl FieldName {
     pub fn field_newInstanceCallerCache() -> Self {
         Self::from_raw_id(field_newInstanceCallerCache)
     }
+    pub fn field_ngroups() -> Self {
+        Self::from_raw_id(field_ngroups)
+    }
+    pub fn field_nthreads() -> Self {
+        Self::from_raw_id(field_nthreads)
+    }
     pub fn field_ordinal() -> Self {
         Self::from_raw_id(field_ordinal)
     }
@@ -235,5 +247,20 @@ impl FieldName {
     pub fn field_formalTypeParams() -> Self {
         Self::from_raw_id(field_formalTypeParams)
     }
+    pub fn field_classes() -> Self {
+        Self::from_raw_id(field_classes)
+    }
+    pub fn field_classEnabled() -> Self {
+        Self::from_raw_id(field_classEnabled)
+    }
+    pub fn field_packages() -> Self {
+        Self::from_raw_id(field_packages)
+    }
+    pub fn field_packageEnabled() -> Self {
+        Self::from_raw_id(field_packageEnabled)
+    }
+    pub fn field_deflt() -> Self {
+        Self::from_raw_id(field_deflt)
+    }
 }
 
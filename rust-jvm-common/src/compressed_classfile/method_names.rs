@@ -226,4 +226,19 @@ impl MethodName {
     pub fn method_freeMemory() -> Self{
         Self::from_raw_id(method_freeMemory)
     }
+    pub fn method_add() -> Self{
+        Self::from_raw_id(method_add)
+    }
+    pub fn method_dispatch() -> Self{
+        Self::from_raw_id(method_dispatch)
+    }
+    pub fn method_shutdown() -> Self{
+        Self::from_raw_id(method_shutdown)
+    }
+    pub fn method_dispatchUncaughtException() -> Self{
+        Self::from_raw_id(method_dispatchUncaughtException)
+    }
+    pub fn method_gc() -> Self{
+        Self::from_raw_id(method_gc)
+    }
 }
@@ -2,7 +2,7 @@ use std::num::NonZeroU8;
 
 use itertools::{Either, Itertools};
 
-use crate::classfile::{AppendFrame, AttributeType, BootstrapMethods, ChopFrame, Class, Classfile, Code, ConstantInfo, ConstantKind, Double, ExceptionTableElem, FieldInfo, Fieldref, Float, FullFrame, Instruction, InstructionInfo, Integer, InterfaceMethodref, InvokeInterface, Long, MethodInfo, Methodref, MultiNewArray, SameFrameExtended, SameLocals1StackItemFrame, SameLocals1StackItemFrameExtended, StackMapFrame, StackMapTable, String_};
+use crate::classfile::{AppendFrame, AttributeType, BootstrapMethods, ChopFrame, Class, Classfile, Code, ConstantInfo, ConstantKind, Double, Dynamic, ExceptionTableElem, FieldInfo, Fieldref, Float, FullFrame, Instruction, InstructionInfo, Integer, InterfaceMethodref, InvokeInterface, Long, MethodInfo, Methodref, MultiNewArray, NameAndType, SameFrameExtended, SameLocals1StackItemFrame, SameLocals1StackItemFrameExtended, StackMapFrame, StackMapTable, String_};
 use crate::classnames::{class_name, ClassName};
 use crate::compressed_classfile::class_names::CompressedClassName;
 use crate::compressed_classfile::code::{CInstructionInfo, CompressedAppendFrame, CompressedChopFrame, CompressedCode, CompressedExceptionTableElem, CompressedFullFrame, CompressedInstruction, CompressedInstructionInfo, CompressedLdc2W, CompressedLdcW, CompressedSameFrameExtended, CompressedSameLocals1StackItemFrame, CompressedSameLocals1StackItemFrameExtended, CompressedStackMapFrame};
@@ -488,6 +488,18 @@ impl CompressedClassfile {
             ConstantKind::MethodHandle(_) => todo!(),
             ConstantKind::MethodType(_) => todo!(),
             ConstantKind::LiveObject(index) => Either::Left(CompressedLdcW::LiveObject(index)),
+            ConstantKind::Dynamic(Dynamic { name_and_type_index, .. }) => {
+                let descriptor_index = match constant_pool[name_and_type_index as usize].kind {
+                    ConstantKind::NameAndType(NameAndType { descriptor_index, .. }) => descriptor_index,
+                    _ => panic!(),
+                };
+                let desc = constant_pool[descriptor_index as usize].extract_string_from_utf8().into_string().expect("should have validated this earlier maybe todo");
+                let type_ = CPDType::from_ptype(&parse_field_descriptor(desc.as_str()).unwrap().field_type, pool);
+                match type_ {
+                    CPDType::LongType | CPDType::DoubleType => Either::Right(CompressedLdc2W::Dynamic { cp, type_ }),
+                    _ => Either::Left(CompressedLdcW::Dynamic { cp, type_ }),
+                }
+            }
             _ => {
                 dbg!(&constant_pool[cp as usize].kind);
                 panic!()
@@ -83,6 +83,7 @@ pub(crate) enum PredefinedStrings {
     JAVA_LANG_NO_SUCH_METHOD_ERROR,
     JAVA_NIO_DIRECT_BYTE_BUFFER,
     JAVA_LANG_INDEX_OUT_OF_BOUNDS_EXCEPTION,
+    JAVA_LANG_INTERRUPTED_EXCEPTION,
     field_annotationData,
     method_and_field_annotationType,
     field_argCounts,
@@ -107,6 +108,8 @@ pub(crate) enum PredefinedStrings {
     field_form,
     field_function,
     field_genericInfo,
+    field_group,
+    field_groups,
     field_ht,
     field_and_method_getContextClassLoader,
     field_key,
@@ -123,6 +126,8 @@ pub(crate) enum PredefinedStrings {
     field_and_method_name,
     field_names,
     field_newInstanceCallerCache,
+    field_ngroups,
+    field_nthreads,
     field_ordinal,
     field_and_method_parameterType,
     field_parameterTypes,
@@ -214,7 +219,26 @@ pub(crate) enum PredefinedStrings {
     method_getIntVolatile,
     method_allocateMemory,
     method_putLong,
-    method_freeMemory
+    method_freeMemory,
+    method_add,
+    SUN_MISC_SIGNAL,
+    method_dispatch,
+    JAVA_LANG_SHUTDOWN,
+    method_shutdown,
+    method_dispatchUncaughtException,
+    JAVA_LANG_ASSERTION_STATUS_DIRECTIVES,
+    field_classes,
+    field_classEnabled,
+    field_packages,
+    field_packageEnabled,
+    field_deflt,
+    JAVA_LANG_VERIFY_ERROR,
+    JAVA_LANG_CLASS_FORMAT_ERROR,
+    JAVA_LANG_NO_CLASS_DEF_FOUND_ERROR,
+    SUN_REFLECT_MAGIC_ACCESSOR_IMPL,
+    JAVA_LANG_RUNTIME,
+    method_gc,
+    JAVA_LANG_STACK_OVERFLOW_ERROR
 }
 
 impl PredefinedStrings {
@@ -292,6 +316,8 @@ impl PredefinedStrings {
             field_form => "form".to_string(),
             field_function => "function".to_string(),
             field_genericInfo => "genericInfo".to_string(),
+            field_group => "group".to_string(),
+            field_groups => "groups".to_string(),
             field_and_method_getContextClassLoader => "getContextClassLoader".to_string(),
             field_index => "index".to_string(),
             field_invokers => "invokers".to_string(),
@@ -304,6 +330,8 @@ impl PredefinedStrings {
             field_and_method_name => "name".to_string(),
             field_names => "names".to_string(),
             field_newInstanceCallerCache => "newInstanceCallerCache".to_string(),
+            field_ngroups => "ngroups".to_string(),
+            field_nthreads => "nthreads".to_string(),
             field_ordinal => "ordinal".to_string(),
             field_and_method_parameterType => "parameterType".to_string(),
             field_parameterTypes => "parameterTypes".to_string(),
@@ -415,11 +443,31 @@ impl PredefinedStrings {
             method_putLong => "putLong".to_string(),
             method_getByte => "getByte".to_string(),
             method_freeMemory => "freeMemory".to_string(),
+            method_add => "add".to_string(),
+            SUN_MISC_SIGNAL => "sun/misc/Signal".to_string(),
+            method_dispatch => "dispatch".to_string(),
+            JAVA_LANG_SHUTDOWN => "java/lang/Shutdown".to_string(),
+            method_shutdown => "shutdown".to_string(),
+            method_dispatchUncaughtException => "dispatchUncaughtException".to_string(),
+            JAVA_LANG_ASSERTION_STATUS_DIRECTIVES => "java/lang/AssertionStatusDirectives".to_string(),
+            field_classes => "classes".to_string(),
+            field_classEnabled => "classEnabled".to_string(),
+            field_packages => "packages".to_string(),
+            field_packageEnabled => "packageEnabled".to_string(),
+            field_deflt => "deflt".to_string(),
+            JAVA_LANG_VERIFY_ERROR => "java/lang/VerifyError".to_string(),
+            JAVA_LANG_CLASS_FORMAT_ERROR => "java/lang/ClassFormatError".to_string(),
+            JAVA_LANG_NO_CLASS_DEF_FOUND_ERROR => "java/lang/NoClassDefFoundError".to_string(),
+            SUN_REFLECT_MAGIC_ACCESSOR_IMPL => "sun/reflect/MagicAccessorImpl".to_string(),
+            JAVA_LANG_RUNTIME => "java/lang/Runtime".to_string(),
+            method_gc => "gc".to_string(),
+            JAVA_LANG_STACK_OVERFLOW_ERROR => "java/lang/StackOverflowError".to_string(),
             JAVA_LANG_CLASS_CAST_EXCEPTION => "java/lang/ClassCastException".to_string(),
             JAVA_LANG_NO_SUCH_METHOD_ERROR => "java/lang/NoSuchMethodError".to_string(),
             method_findConstructor => "findConstructor".to_string(),
             JAVA_NIO_DIRECT_BYTE_BUFFER => "java/nio/DirectByteBuffer".to_string(),
-            JAVA_LANG_INDEX_OUT_OF_BOUNDS_EXCEPTION => "java/lang/IndexOutOfBoundsException".to_string()
+            JAVA_LANG_INDEX_OUT_OF_BOUNDS_EXCEPTION => "java/lang/IndexOutOfBoundsException".to_string(),
+            JAVA_LANG_INTERRUPTED_EXCEPTION => "java/lang/InterruptedException".to_string()
         }
     }
 }
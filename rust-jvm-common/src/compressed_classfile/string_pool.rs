@@ -1,4 +1,5 @@
-use add_only_static_vec::{AddOnlyId, AddOnlyIdMap};
+use add_only_static_vec::{AddOnlyId, AddOnlyIdMap, AddOnlyVecIDType};
+use serde::{Deserialize, Serialize};
 use crate::compressed_classfile::names;
 
 pub struct CompressedClassfileStringPool {
@@ -36,11 +37,28 @@ impl CompressedClassfileStringPool {
     pub fn lookup(&self, id: CompressedClassfileString) -> &String {
         self.pool.lookup(id.id)
     }
+
+    //every string in id order, so that replaying them through restore gives each the same id again
+    pub fn snapshot(&self) -> Vec<String> {
+        (0..self.pool.len()).map(|id| self.pool.lookup(AddOnlyId(id as AddOnlyVecIDType)).clone()).collect()
+    }
+
+    //only possible before anything beyond the predefined names has been added, and only for a snapshot starting with the same predefined names
+    pub fn restore(&self, snapshot: &[String]) -> bool {
+        let current_len = self.pool.len();
+        if snapshot.len() < current_len || snapshot.iter().take(current_len).enumerate().any(|(id, string)| self.pool.lookup(AddOnlyId(id as AddOnlyVecIDType)) != string) {
+            return false;
+        }
+        for (id, string) in snapshot.iter().enumerate().skip(current_len) {
+            assert_eq!(self.pool.push(string.clone()), AddOnlyId(id as AddOnlyVecIDType));
+        }
+        true
+    }
 }
 
 pub type CCString = CompressedClassfileString;
 
-#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug, PartialOrd, Ord)]
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct CompressedClassfileString {
     pub id: AddOnlyId,
 }
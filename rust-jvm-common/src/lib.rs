@@ -6,6 +6,8 @@ use std::ffi::c_void;
 use std::fmt::{Debug};
 use std::marker::PhantomData;
 
+use serde::{Deserialize, Serialize};
+
 pub mod classfile;
 pub mod classnames;
 pub mod compressed_classfile;
@@ -33,7 +35,7 @@ pub type FieldTableIndex = usize;
 pub type FieldId = usize;
 
 
-#[derive(Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
 pub struct ByteCodeOffset(pub u16);//todo unify this with bytecode offset
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
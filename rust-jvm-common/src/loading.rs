@@ -3,8 +3,10 @@ use std::fmt::Debug;
 use std::fmt::Display;
 use std::fmt::Formatter;
 use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
 
 use wtf8::Wtf8Buf;
+use serde::{Deserialize, Serialize};
 
 use sketch_jvm_version_of_utf8::ValidationError;
 
@@ -41,6 +43,8 @@ pub enum ClassfileParsingError {
     WromngCPEntry,
     UTFValidationError(ValidationError),
     WrongDescriptor,
+    //a CONSTANT_InvokeDynamic or CONSTANT_Dynamic without a matching BootstrapMethods entry
+    MissingBootstrapMethod,
 }
 
 impl From<ValidationError> for ClassfileParsingError {
@@ -69,6 +73,8 @@ pub enum ClassLoadingError {
     ClassFileInvalid(ClassfileParsingError),
     // ClassFormatError , UnsupportedClassVersionError
     ClassVerificationError, // java.lang.VerifyError
+    JarInvalid(PathBuf, String), // a corrupt jar on the classpath, along with what the zip reader made of it
+    JImageInvalid(PathBuf, String), // likewise for a corrupt jdk 9+ runtime image
 }
 
 impl From<ClassfileParsingError> for ClassLoadingError {
@@ -86,10 +92,10 @@ impl Display for ClassLoadingError {
 impl std::error::Error for ClassLoadingError {}
 
 #[repr(transparent)]
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct LoaderIndex(pub u32);
 
-#[derive(Debug, PartialEq, Eq, Clone, Hash, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Copy, Serialize, Deserialize)]
 pub enum LoaderName {
     UserDefinedLoader(LoaderIndex),
     BootstrapLoader,
@@ -108,7 +114,7 @@ impl Display for LoaderName {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
 pub struct ClassWithLoader {
     pub class_name: CClassName,
     pub loader: LoaderName,
@@ -2,10 +2,12 @@ use std::fmt::Debug;
 use std::hash::Hash;
 use std::ops::Deref;
 
+use serde::{Deserialize, Serialize};
+
 use crate::classfile::UninitializedVariableInfo;
 use crate::classnames::ClassName;
 
-#[derive(Debug, Eq, PartialEq, Hash)]
+#[derive(Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub enum PType {
     ByteType,
     CharType,
@@ -27,7 +29,7 @@ pub enum PType {
     UninitializedThisOrClass(Box<PType>),
 }
 
-#[derive(Debug, Eq, PartialEq, Hash)]
+#[derive(Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub enum ReferenceType {
     Class(ClassName),
     Array(Box<PType>),
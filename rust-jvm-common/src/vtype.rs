@@ -1,5 +1,8 @@
 use std::ops::Deref;
 
+use serde::{Deserialize, Serialize};
+
+use crate::ByteCodeOffset;
 use crate::classfile::UninitializedVariableInfo;
 use crate::compressed_classfile::class_names::{CClassName, CompressedClassName};
 use crate::compressed_classfile::compressed_types::CPDType;
@@ -8,7 +11,7 @@ use crate::loading::{ClassWithLoader, LoaderName};
 use crate::ptype::{PType, ReferenceType};
 use crate::runtime_type::{RuntimeRefType, RuntimeType};
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum VType {
     //VType for VerificationType
     // this doesn't reside in the verifier b/c class view needs to_verification_type on PTypeView
@@ -26,6 +29,8 @@ pub enum VType {
     //todo hack. so b/c stackmapframes doesn't really know what type to give to UnitialziedThis, b/c invoke special could have happened or not
     // I suspect that Uninitialized might work for this, but making my own anyway
     UninitializedThisOrClass(CPDType),
+    //pushed by jsr, for the subroutine starting at this offset. Only seen in class files older than version 50
+    ReturnAddress(ByteCodeOffset),
     //below here used internally in isAssignable
     TwoWord,
     OneWord,
@@ -60,6 +65,29 @@ impl VType {
         }
     }
 
+    // Names as hotspot prints them in VerifyError messages.
+    pub fn verifier_name(&self, pool: &CompressedClassfileStringPool) -> String {
+        match self {
+            VType::DoubleType => "double".to_string(),
+            VType::FloatType => "float".to_string(),
+            VType::IntType => "integer".to_string(),
+            VType::LongType => "long".to_string(),
+            VType::Class(class) => format!("'{}'", class.class_name.0.to_str(pool)),
+            VType::ArrayReferenceType(elem_type) => format!("'{}'", CPDType::array(*elem_type).jvm_representation(pool)),
+            VType::VoidType => "void".to_string(),
+            VType::TopType => "top".to_string(),
+            VType::NullType => "null".to_string(),
+            VType::Uninitialized(UninitializedVariableInfo { offset }) => format!("uninitialized({})", offset.0),
+            VType::UninitializedThis => "uninitializedThis".to_string(),
+            VType::UninitializedThisOrClass(class) => format!("uninitializedThis or '{}'", class.java_source_representation(pool)),
+            VType::ReturnAddress(_) => "returnAddress".to_string(),
+            VType::TwoWord => "category2".to_string(),
+            VType::OneWord => "category1".to_string(),
+            VType::Reference => "reference".to_string(),
+            VType::UninitializedEmpty => "uninitialized".to_string(),
+        }
+    }
+
     pub fn to_runtime_type(&self) -> RuntimeType {
         match self {
             VType::DoubleType => RuntimeType::DoubleType,
@@ -74,6 +102,7 @@ impl VType {
             VType::Uninitialized(_) => RuntimeType::Ref(RuntimeRefType::Class(CClassName::object())),
             VType::UninitializedThis => RuntimeType::Ref(RuntimeRefType::Class(CClassName::object())),
             VType::UninitializedThisOrClass(_) => RuntimeType::Ref(RuntimeRefType::Class(CClassName::object())),
+            VType::ReturnAddress(_) => RuntimeType::IntType,
             VType::TwoWord => panic!(),
             VType::OneWord => panic!(),
             VType::Reference => panic!(),
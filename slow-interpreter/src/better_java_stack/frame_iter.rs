@@ -159,7 +159,11 @@ impl<'vm, 'k> Iterator for JavaFrameIterRefNew<'vm, 'k> {
             //     // dbg!((method_view.name().0.to_str(&jvm.string_pool), self.current_pc));
             // }
             self.current_rip = Some(prev_rip);
-            match self.java_stack_guard.jvm().java_vm_state.lookup_ip(prev_rip.as_ptr()) {
+            let return_address = match ir_frame_ref.prev_rbp() {
+                Some(prev_rbp) => jvm.java_vm_state.original_return_address(FramePointer(prev_rbp), prev_rip.as_ptr()),
+                None => prev_rip.as_ptr()
+            };
+            match jvm.java_vm_state.lookup_ip(return_address) {
                 Some((_, new_pc)) => {
                     self.current_pc = Some(new_pc);
                 }
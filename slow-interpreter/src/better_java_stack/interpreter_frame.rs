@@ -186,6 +186,11 @@ impl<'gc, 'k> JavaInterpreterFrame<'gc, 'k> {
         self.current_operand_stack_depth = 0;
     }
 
+    pub(crate) fn set_operand_stack_depth(&mut self, stack_depth: u16) {
+        assert!(stack_depth <= self.max_stack);
+        self.current_operand_stack_depth = stack_depth;
+    }
+
     pub fn pop_os(&mut self, expected_type: RuntimeType) -> InterpreterJavaValue {
         if self.current_operand_stack_depth == 0 {
             panic!()
@@ -119,6 +119,19 @@ impl<'vm> JavaStackGuard<'vm> {
         }
     }
 
+    // for viewing the stack of a thread which may be running vm code, and therefore holding its stack
+    pub fn try_new_remote(jvm: &'vm JVMState<'vm>, stack: &'vm Mutex<JavaStack<'vm>>, java_thread: Arc<JavaThread<'vm>>) -> Option<Self> {
+        let guard = stack.try_lock().ok()?;
+        let mmapped_top = guard.owned_ir_stack.native.mmaped_top;
+        Some(Self {
+            stack,
+            guard: Some(guard),
+            jvm,
+            java_thread,
+            current_frame_pointer: FramePointer(mmapped_top),
+        })
+    }
+
     pub fn debug_assert(&self) {
         self.assert_interpreter_frame_operand_stack_depths_sorted();
     }
@@ -142,6 +155,8 @@ impl<'vm> JavaStackGuard<'vm> {
                                   java_stack_entry: JavaFramePush,
                                   within_pushed: impl for<'l> FnOnce(&mut JavaInterpreterFrame<'vm, 'l>) -> Result<T, WasException<'vm>>,
     ) -> Result<T, WasException<'vm>> {
+        //native methods call back into java through jni
+        let was_in_native = self.java_thread.safepoint_state.leave_native();
         let JavaFramePush { method_id, local_vars, operand_stack } = java_stack_entry;
         let jvm = self.jvm();
         let top_level_exit_ptr = get_top_level_exit_ptr(jvm);
@@ -178,6 +193,7 @@ impl<'vm> JavaStackGuard<'vm> {
             within_pushed(within)
         });
         self.notify_frame_pop(next_frame_pointer, "".to_string());
+        self.java_thread.safepoint_state.return_to_native(was_in_native);
         res
     }
 
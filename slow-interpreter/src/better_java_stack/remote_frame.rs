@@ -1,3 +1,4 @@
+use std::ffi::c_void;
 use std::sync::Arc;
 use another_jit_vm_ir::ir_stack::{IRFrameMut, IRFrameRef, IsOpaque};
 use runtime_class_stuff::RuntimeClass;
@@ -10,14 +11,17 @@ use crate::JVMState;
 pub struct RemoteFrame<'gc, 'k> {
     java_stack: &'k mut JavaStackGuard<'gc>,
     frame_ptr: FramePointer,
+    // where the remote thread was stopped, if it was stopped in compiled code
+    rip: Option<*const c_void>,
 }
 // don't have the function call vec thing
 
 impl<'gc, 'k> RemoteFrame<'gc, 'k> {
-    pub fn new(java_stack: &'k mut JavaStackGuard<'gc>, frame_ptr: FramePointer) -> Self {
+    pub fn new(java_stack: &'k mut JavaStackGuard<'gc>, frame_ptr: FramePointer, rip: Option<*const c_void>) -> Self {
         Self {
             java_stack,
             frame_ptr,
+            rip,
         }
     }
 }
@@ -62,8 +66,8 @@ impl<'gc, 'k> HasFrame<'gc> for RemoteFrame<'gc, 'k> {
     }
 
     fn try_current_frame_pc(&self) -> Option<ByteCodeOffset> {
-        //todo we can provide accurate values here by converting the rip to bytecode
-        None
+        let (_, pc) = self.jvm().java_vm_state.lookup_ip(self.rip?)?;
+        Some(pc)
     }
 
     fn java_stack_ref(&self) -> &JavaStackGuard<'gc> {
@@ -33,7 +33,7 @@ pub fn within<'gc>(scope: &'_ Scope<'_, 'gc>, xtask: &XTaskConfig, gc: &GC, stri
     let gc_ref: &'gc GC<'gc> = unsafe { transmute(&gc) };//todo why do I need this?
     let scope_ref: &'gc Scope<'gc, 'gc> = unsafe { transmute(scope) };
     let mut jvm_options = JVMOptions::test_options();
-    jvm_options.classpath = Classpath::from_dirs(vec![xtask.classes().into_boxed_path()]);
+    jvm_options.classpath = Classpath::from_paths(vec![xtask.classes().into_boxed_path()]);
     let (args, jvm): (Vec<String>, JVMState<'gc>) = JVMState::new(jvm_options, scope_ref, gc_ref, string_pool);
     unsafe {
         let jvm: &'gc JVMState<'gc> = transmute(&jvm);
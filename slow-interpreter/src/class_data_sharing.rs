@@ -0,0 +1,424 @@
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::error::Error;
+use std::ffi::c_void;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::ptr::{NonNull, null_mut};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use classfile_view::view::ClassView;
+use runtime_class_stuff::RuntimeClass;
+use rust_jvm_common::ByteCodeOffset;
+use rust_jvm_common::classfile::Classfile;
+use rust_jvm_common::compressed_classfile::class_names::CClassName;
+use rust_jvm_common::compressed_classfile::string_pool::CompressedClassfileStringPool;
+use rust_jvm_common::loading::LoaderName;
+use stage0::compiler_common::frame_data::SunkVerifierFrames;
+
+use crate::better_java_stack::frames::PushableFrame;
+use crate::class_loading::check_loaded_class;
+use crate::jvm_state::JVMState;
+
+// A class data sharing archive holds bootstrap classes already parsed, along with the frames their
+// verification produced, so that startup can skip reading the boot jars and verifying. Compressed names
+// in the frames are string pool ids, which stay valid because the archive also holds the string pool,
+// restored in the same order before anything else is interned.
+//
+// The layout is the magic and format version, each archived class, the header, then the header's offset.
+
+const MAGIC: &[u8; 8] = b"RJVMCDS\0";
+//bump whenever anything serialized here changes shape
+const FORMAT_VERSION: u32 = 3;
+const PREAMBLE_LEN: usize = MAGIC.len() + std::mem::size_of::<u32>();
+const TRAILER_LEN: usize = std::mem::size_of::<u64>();
+
+#[derive(Debug)]
+pub enum SharedArchiveError {
+    Io(io::Error),
+    Serialization(bincode::Error),
+    NotAnArchive,
+    FormatVersion(u32),
+    VmVersion(String),
+    JavaHome(PathBuf),
+    BootClasspathChanged,
+    VerifyBootstrapClasses(bool),
+    StringPool,
+}
+
+impl Display for SharedArchiveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SharedArchiveError::Io(err) => write!(f, "{}", err),
+            SharedArchiveError::Serialization(err) => write!(f, "corrupt archive: {}", err),
+            SharedArchiveError::NotAnArchive => write!(f, "not a class data sharing archive"),
+            SharedArchiveError::FormatVersion(version) => write!(f, "archive format version {} does not match {}", version, FORMAT_VERSION),
+            SharedArchiveError::VmVersion(version) => write!(f, "archive was dumped by vm version {}", version),
+            SharedArchiveError::JavaHome(java_home) => write!(f, "archive was dumped for java home {}", java_home.display()),
+            SharedArchiveError::BootClasspathChanged => write!(f, "the boot classpath has changed since the archive was dumped"),
+            SharedArchiveError::VerifyBootstrapClasses(true) => write!(f, "archive was dumped with bootstrap classes verified"),
+            SharedArchiveError::VerifyBootstrapClasses(false) => write!(f, "archive was dumped without verifying bootstrap classes"),
+            SharedArchiveError::StringPool => write!(f, "archive was dumped with different predefined names"),
+        }
+    }
+}
+
+impl Error for SharedArchiveError {}
+
+impl From<io::Error> for SharedArchiveError {
+    fn from(err: io::Error) -> Self {
+        SharedArchiveError::Io(err)
+    }
+}
+
+impl From<bincode::Error> for SharedArchiveError {
+    fn from(err: bincode::Error) -> Self {
+        SharedArchiveError::Serialization(err)
+    }
+}
+
+//a boot classpath entry which is replaced or rebuilt invalidates the archive
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+struct BootClasspathEntry {
+    path: PathBuf,
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+fn boot_classpath_entries(boot_classpath: &[PathBuf]) -> Vec<BootClasspathEntry> {
+    boot_classpath.iter().map(|path| {
+        let metadata = path.metadata().ok();
+        BootClasspathEntry {
+            path: path.clone(),
+            len: metadata.as_ref().map(|metadata| metadata.len()).unwrap_or(0),
+            modified: metadata.and_then(|metadata| metadata.modified().ok()),
+        }
+    }).collect()
+}
+
+fn canonical_java_home(java_home: &Path) -> PathBuf {
+    java_home.canonicalize().unwrap_or_else(|_| java_home.to_path_buf())
+}
+
+#[derive(Serialize, Deserialize)]
+struct ArchiveHeader {
+    vm_version: String,
+    java_home: PathBuf,
+    boot_classpath: Vec<BootClasspathEntry>,
+    //bootstrap classes failing verification are only rejected with this set, otherwise their frames are inferred
+    verify_bootstrap_classes: bool,
+    string_pool: Vec<String>,
+    //the range of the file each class is serialized in
+    classes: Vec<(String, u64, u64)>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ArchivedMethodFrames {
+    pub method_i: u16,
+    pub no_tops: HashMap<ByteCodeOffset, SunkVerifierFrames>,
+    pub tops: HashMap<ByteCodeOffset, SunkVerifierFrames>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ArchivedClass {
+    pub classfile: Classfile,
+    pub frames: Vec<ArchivedMethodFrames>,
+}
+
+pub struct SharedArchive {
+    mapping: NonNull<c_void>,
+    len: usize,
+    classes: HashMap<String, (usize, usize)>,
+}
+
+//the mapping is read only
+unsafe impl Send for SharedArchive {}
+
+unsafe impl Sync for SharedArchive {}
+
+impl SharedArchive {
+    /// Maps an archive and checks it was dumped for this java home, boot classpath and bootstrap class
+    /// verification setting. On success the archive's string pool has been restored into `string_pool`, so
+    /// this has to happen before anything else is interned.
+    pub fn open(path: &Path, java_home: &Path, boot_classpath: &[PathBuf], verify_bootstrap_classes: bool, string_pool: &CompressedClassfileStringPool) -> Result<SharedArchive, SharedArchiveError> {
+        let (archive, archived_string_pool) = Self::map(path, java_home, boot_classpath, verify_bootstrap_classes)?;
+        if !string_pool.restore(archived_string_pool.as_slice()) {
+            return Err(SharedArchiveError::StringPool);
+        }
+        Ok(archive)
+    }
+
+    //everything open checks except the string pool, which is returned for restoring
+    fn map(path: &Path, java_home: &Path, boot_classpath: &[PathBuf], verify_bootstrap_classes: bool) -> Result<(SharedArchive, Vec<String>), SharedArchiveError> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len() as usize;
+        if len < PREAMBLE_LEN + TRAILER_LEN {
+            return Err(SharedArchiveError::NotAnArchive);
+        }
+        let mapping = unsafe { libc::mmap(null_mut(), len, libc::PROT_READ, libc::MAP_PRIVATE, file.as_raw_fd(), 0) };
+        if mapping == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error().into());
+        }
+        let mut archive = SharedArchive { mapping: NonNull::new(mapping).unwrap(), len, classes: HashMap::new() };
+        let header = archive.header()?;
+        if header.vm_version != env!("CARGO_PKG_VERSION") {
+            return Err(SharedArchiveError::VmVersion(header.vm_version));
+        }
+        if header.java_home != canonical_java_home(java_home) {
+            return Err(SharedArchiveError::JavaHome(header.java_home));
+        }
+        if header.boot_classpath != boot_classpath_entries(boot_classpath) {
+            return Err(SharedArchiveError::BootClasspathChanged);
+        }
+        if header.verify_bootstrap_classes != verify_bootstrap_classes {
+            return Err(SharedArchiveError::VerifyBootstrapClasses(header.verify_bootstrap_classes));
+        }
+        if header.classes.iter().any(|(_, start, end)| start > end || *end as usize > len) {
+            return Err(SharedArchiveError::NotAnArchive);
+        }
+        archive.classes = header.classes.into_iter().map(|(class_name, start, end)| (class_name, (start as usize, end as usize))).collect();
+        Ok((archive, header.string_pool))
+    }
+
+    fn bytes(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.mapping.as_ptr() as *const u8, self.len) }
+    }
+
+    fn header(&self) -> Result<ArchiveHeader, SharedArchiveError> {
+        let bytes = self.bytes();
+        if &bytes[..MAGIC.len()] != MAGIC {
+            return Err(SharedArchiveError::NotAnArchive);
+        }
+        let format_version = u32::from_le_bytes(bytes[MAGIC.len()..PREAMBLE_LEN].try_into().unwrap());
+        if format_version != FORMAT_VERSION {
+            return Err(SharedArchiveError::FormatVersion(format_version));
+        }
+        let header_offset = u64::from_le_bytes(bytes[bytes.len() - TRAILER_LEN..].try_into().unwrap()) as usize;
+        if header_offset < PREAMBLE_LEN || header_offset > bytes.len() - TRAILER_LEN {
+            return Err(SharedArchiveError::NotAnArchive);
+        }
+        Ok(bincode::deserialize(&bytes[header_offset..bytes.len() - TRAILER_LEN])?)
+    }
+
+    /// Classes are only deserialized once something asks for them. One which can't be is loaded from the
+    /// boot classpath as usual.
+    pub fn lookup(&self, class_name: &str) -> Option<ArchivedClass> {
+        let (start, end) = *self.classes.get(class_name)?;
+        bincode::deserialize(&self.bytes()[start..end]).ok()
+    }
+
+    pub fn len(&self) -> usize {
+        self.classes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.classes.is_empty()
+    }
+}
+
+impl Drop for SharedArchive {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.mapping.as_ptr(), self.len); }
+    }
+}
+
+/// The archived form of a bootstrap class, if the archive has it. The parsed class is also put in the
+/// classpath cache, so anything else looking the class up doesn't read it from the boot jars either.
+pub fn lookup_shared_class<'gc>(jvm: &'gc JVMState<'gc>, class_name: CClassName) -> Option<(Arc<Classfile>, Vec<ArchivedMethodFrames>)> {
+    let ArchivedClass { classfile, frames } = jvm.shared_archive.as_ref()?.lookup(class_name.0.to_str(&jvm.string_pool).as_str())?;
+    let classfile = Arc::new(classfile);
+    jvm.classpath.class_cache.write().unwrap().insert(class_name, classfile.clone());
+    Some((classfile, frames))
+}
+
+/// In place of verifying a class, its archived frames are used for the frame types verification would have produced.
+pub fn sink_archived_frames<'gc>(jvm: &'gc JVMState<'gc>, runtime_class: &Arc<RuntimeClass<'gc>>, frames: Vec<ArchivedMethodFrames>) {
+    let mut method_table = jvm.method_table.write().unwrap();
+    let mut function_frame_type_data = jvm.function_frame_type_data.write().unwrap();
+    for ArchivedMethodFrames { method_i, no_tops, tops } in frames {
+        let method_id = method_table.get_method_id(runtime_class.clone(), method_i);
+        function_frame_type_data.no_tops.insert(method_id, no_tops);
+        function_frame_type_data.tops.insert(method_id, tops);
+    }
+}
+
+// None for classes with methods missing frames, like those created at startup before anything can be verified
+fn archived_frames<'gc>(jvm: &'gc JVMState<'gc>, runtime_class: &Arc<RuntimeClass<'gc>>) -> Option<Vec<ArchivedMethodFrames>> {
+    let view = runtime_class.view();
+    let mut method_table = jvm.method_table.write().unwrap();
+    let function_frame_type_data = jvm.function_frame_type_data.read().unwrap();
+    let mut res = vec![];
+    for method_view in view.methods() {
+        if method_view.code_attribute().is_none() {
+            continue;
+        }
+        let method_id = method_table.get_method_id(runtime_class.clone(), method_view.method_i());
+        res.push(ArchivedMethodFrames {
+            method_i: method_view.method_i(),
+            no_tops: function_frame_type_data.no_tops.get(&method_id)?.clone(),
+            tops: function_frame_type_data.tops.get(&method_id)?.clone(),
+        });
+    }
+    Some(res)
+}
+
+/// -Xshare:dump. Loads every class in the class list with the bootstrap loader, then archives every
+/// bootstrap class which came from the boot classpath. Returns the number of classes archived.
+pub fn dump_shared_archive<'gc>(jvm: &'gc JVMState<'gc>, int_state: &mut impl PushableFrame<'gc>) -> Result<usize, SharedArchiveError> {
+    let class_list = std::fs::read_to_string(&jvm.config.shared_class_list_file)?;
+    //array classes are never archived, they have no class file
+    for class_name in class_list.lines().map(|line| line.trim()).filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with('[')) {
+        let class_name = CClassName(jvm.string_pool.add_name(class_name, true));
+        if check_loaded_class(jvm, int_state, class_name.into()).is_err() {
+            eprintln!("Preload Warning: Cannot find {}", class_name.0.to_str(&jvm.string_pool));
+        }
+    }
+    let bootstrap_classes = jvm.classes.read().unwrap().loaded_classes_by_type.get(&LoaderName::BootstrapLoader)
+        .map(|classes| classes.iter().filter_map(|(cpdtype, runtime_class)| Some((cpdtype.try_unwrap_class_type()?, runtime_class.clone()))).collect_vec())
+        .unwrap_or_default();
+    let mut classes = vec![];
+    for (class_name, runtime_class) in bootstrap_classes.into_iter().sorted_by_key(|(class_name, _)| class_name.0.to_str(&jvm.string_pool)) {
+        let from_boot_classpath = jvm.classpath.source(&class_name, &jvm.string_pool).map(|source| jvm.boot_classpath.iter().any(|entry| entry.as_path() == source)).unwrap_or(false);
+        if !from_boot_classpath {
+            continue;
+        }
+        let classfile = match jvm.classpath.class_cache.read().unwrap().get(&class_name) {
+            Some(classfile) => classfile.clone(),
+            None => continue,
+        };
+        let frames = match archived_frames(jvm, &runtime_class) {
+            Some(frames) => frames,
+            None => continue,
+        };
+        classes.push((class_name.0.to_str(&jvm.string_pool), classfile, frames));
+    }
+    write_archive(&jvm.config.shared_archive_file, &jvm.java_home, jvm.boot_classpath.as_slice(), jvm.config.verify_bootstrap_classes, jvm.string_pool.snapshot(), classes.as_slice())?;
+    Ok(classes.len())
+}
+
+// Each class is its internal name, class file and frames. `string_pool` is the snapshot the frames' compressed names are ids in.
+fn write_archive(path: &Path, java_home: &Path, boot_classpath: &[PathBuf], verify_bootstrap_classes: bool, string_pool: Vec<String>, classes: &[(String, Arc<Classfile>, Vec<ArchivedMethodFrames>)]) -> Result<(), SharedArchiveError> {
+    let mut archive = MAGIC.to_vec();
+    archive.extend(FORMAT_VERSION.to_le_bytes());
+    let mut class_ranges = vec![];
+    for (class_name, classfile, frames) in classes {
+        let start = archive.len();
+        bincode::serialize_into(&mut archive, &ArchivedClassRef { classfile, frames })?;
+        class_ranges.push((class_name.clone(), start as u64, archive.len() as u64));
+    }
+    let header = ArchiveHeader {
+        vm_version: env!("CARGO_PKG_VERSION").to_string(),
+        java_home: canonical_java_home(java_home),
+        boot_classpath: boot_classpath_entries(boot_classpath),
+        verify_bootstrap_classes,
+        string_pool,
+        classes: class_ranges,
+    };
+    let header_offset = archive.len() as u64;
+    bincode::serialize_into(&mut archive, &header)?;
+    archive.extend(header_offset.to_le_bytes());
+    std::fs::write(path, archive)?;
+    Ok(())
+}
+
+//ArchivedClass without taking the classfile out of the classpath cache
+#[derive(Serialize)]
+struct ArchivedClassRef<'l> {
+    classfile: &'l Classfile,
+    frames: &'l [ArchivedMethodFrames],
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use rust_jvm_common::classfile::Classfile;
+
+    use crate::class_data_sharing::{ArchivedClass, ArchivedMethodFrames, SharedArchive, SharedArchiveError, write_archive};
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cds-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("jre/lib")).unwrap();
+        dir
+    }
+
+    fn classfile(minor_version: u16) -> Arc<Classfile> {
+        Arc::new(Classfile {
+            magic: 0xCAFEBABE,
+            minor_version,
+            major_version: 52,
+            constant_pool: vec![],
+            access_flags: 0x21,
+            this_class: 0,
+            super_class: 0,
+            interfaces: vec![],
+            fields: vec![],
+            methods: vec![],
+            attributes: vec![],
+        })
+    }
+
+    //an archive of A and B dumped for dir/jre with dir/jre/lib/rt.jar as the boot classpath
+    fn dump(dir: &PathBuf) -> (PathBuf, PathBuf, Vec<PathBuf>) {
+        let java_home = dir.join("jre");
+        let boot_classpath = vec![java_home.join("lib/rt.jar")];
+        std::fs::write(&boot_classpath[0], b"rt").unwrap();
+        let classes = vec![
+            ("A".to_string(), classfile(1), vec![ArchivedMethodFrames { method_i: 0, no_tops: HashMap::new(), tops: HashMap::new() }]),
+            ("B".to_string(), classfile(2), vec![]),
+        ];
+        let path = dir.join("classes.jsa");
+        write_archive(&path, &java_home, boot_classpath.as_slice(), true, vec!["A".to_string(), "B".to_string()], classes.as_slice()).unwrap();
+        (path, java_home, boot_classpath)
+    }
+
+    #[test]
+    pub fn test_round_trip() {
+        let dir = test_dir("round-trip");
+        let (path, java_home, boot_classpath) = dump(&dir);
+        let (archive, string_pool) = SharedArchive::map(&path, &java_home, boot_classpath.as_slice(), true).unwrap();
+        assert_eq!(string_pool, vec!["A".to_string(), "B".to_string()]);
+        assert_eq!(archive.len(), 2);
+        let ArchivedClass { classfile, frames } = archive.lookup("A").unwrap();
+        assert_eq!(classfile.minor_version, 1);
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].method_i, 0);
+        assert_eq!(archive.lookup("B").unwrap().classfile.minor_version, 2);
+        assert!(archive.lookup("C").is_none());
+    }
+
+    #[test]
+    pub fn test_rejected_after_changes() {
+        let dir = test_dir("rejected");
+        let (path, java_home, boot_classpath) = dump(&dir);
+        let other_java_home = dir.join("other");
+        std::fs::create_dir_all(&other_java_home).unwrap();
+        assert!(matches!(SharedArchive::map(&path, &other_java_home, boot_classpath.as_slice(), true), Err(SharedArchiveError::JavaHome(_))));
+        assert!(matches!(SharedArchive::map(&path, &java_home, boot_classpath.as_slice(), false), Err(SharedArchiveError::VerifyBootstrapClasses(true))));
+        assert!(matches!(SharedArchive::map(&path, &java_home, &[], true), Err(SharedArchiveError::BootClasspathChanged)));
+        std::fs::write(&boot_classpath[0], b"rebuilt rt").unwrap();
+        assert!(matches!(SharedArchive::map(&path, &java_home, boot_classpath.as_slice(), true), Err(SharedArchiveError::BootClasspathChanged)));
+    }
+
+    #[test]
+    pub fn test_truncated() {
+        let dir = test_dir("truncated");
+        let (path, java_home, boot_classpath) = dump(&dir);
+        let bytes = std::fs::read(&path).unwrap();
+        for len in [0, 4, bytes.len() / 2, bytes.len() - 1] {
+            std::fs::write(&path, &bytes[..len]).unwrap();
+            assert!(SharedArchive::map(&path, &java_home, boot_classpath.as_slice(), true).is_err());
+        }
+    }
+}
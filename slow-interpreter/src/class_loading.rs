@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ops::Deref;
 use std::sync::{Arc, Mutex};
 use std::sync::atomic::Ordering;
@@ -25,10 +25,12 @@ use verification::verifier::TypeSafetyError;
 
 use crate::{AllocatedHandle, NewAsObjectOrJavaValue, UnAllocatedObject, WasException};
 use crate::better_java_stack::frames::PushableFrame;
+use crate::class_data_sharing::{lookup_shared_class, sink_archived_frames};
 use crate::class_objects::get_or_create_class_object;
 use crate::java_values::ByAddressAllocatedObject;
 use crate::jit::MethodResolverImpl;
 use crate::jvm_state::JVMState;
+use crate::loader_constraints::throw_loader_constraint_violation;
 use crate::new_java_values::allocated_objects::AllocatedNormalObjectHandle;
 use crate::new_java_values::NewJavaValueHandle;
 use crate::new_java_values::owned_casts::OwnedCastAble;
@@ -37,8 +39,11 @@ use crate::runtime_class::{initialize_class, prepare_class};
 use crate::static_vars::static_vars;
 use crate::stdlib::java::lang::class::JClass;
 use crate::stdlib::java::lang::class_loader::ClassLoader;
+use crate::stdlib::java::lang::class_format_error::ClassFormatError;
 use crate::stdlib::java::lang::class_not_found_exception::ClassNotFoundException;
+use crate::stdlib::java::lang::no_class_def_found_error::NoClassDefFoundError;
 use crate::stdlib::java::lang::string::JString;
+use crate::stdlib::java::lang::verify_error::VerifyError;
 
 //todo only use where spec says
 pub fn check_initing_or_inited_class<'gc, 'l>(jvm: &'gc JVMState<'gc>, int_state: &mut impl PushableFrame<'gc>, ptype: CPDType) -> Result<Arc<RuntimeClass<'gc>>, WasException<'gc>> {
@@ -83,7 +88,7 @@ pub fn check_initing_or_inited_class<'gc, 'l>(jvm: &'gc JVMState<'gc>, int_state
         RuntimeClass::Array(a) => {
             check_initing_or_inited_class(jvm, int_state, a.sub_class.cpdtype())?;
             let loader_name = int_state.current_loader(jvm);
-            jvm.classes.write().unwrap().initiating_loaders.insert(ptype, (loader_name, class.clone()));
+            jvm.classes.write().unwrap().initiating_loaders.entry(ptype).or_insert((loader_name, class.clone()));
         }
         _ => {}
     }
@@ -95,6 +100,7 @@ pub fn check_initing_or_inited_class<'gc, 'l>(jvm: &'gc JVMState<'gc>, int_state
         }
         ClassStatus::PREPARED => {
             class.set_status(ClassStatus::INITIALIZING);
+            jvm.java_vm_state.class_hierarchy_changed(jvm, &class);
             if let Some(super_name) = class.view().super_name() {
                 check_initing_or_inited_class(jvm, int_state, super_name.into())?;
             }
@@ -116,7 +122,7 @@ pub fn assert_loaded_class<'gc>(jvm: &'gc JVMState<'gc>, ptype: CPDType) -> Arc<
 }
 
 pub fn try_assert_loaded_class<'gc>(jvm: &'gc JVMState<'gc>, ptype: CPDType) -> Option<Arc<RuntimeClass<'gc>>> {
-    jvm.classes.read().unwrap().is_loaded(&ptype)
+    jvm.classes.read().unwrap().is_loaded_by_any(&ptype)
 }
 
 pub fn check_loaded_class<'l, 'gc>(jvm: &'gc JVMState<'gc>, int_state: &mut impl PushableFrame<'gc>, ptype: CPDType) -> Result<Arc<RuntimeClass<'gc>>, WasException<'gc>> {
@@ -125,9 +131,11 @@ pub fn check_loaded_class<'l, 'gc>(jvm: &'gc JVMState<'gc>, int_state: &mut impl
     check_loaded_class_force_loader(jvm, int_state, &ptype, loader)
 }
 
-pub(crate) fn check_loaded_class_force_loader<'gc, 'l>(jvm: &'gc JVMState<'gc>, int_state: &mut impl PushableFrame<'gc>, ptype: &CPDType, loader: LoaderName) -> Result<Arc<RuntimeClass<'gc>>, WasException<'gc>> {
+pub fn check_loaded_class_force_loader<'gc, 'l>(jvm: &'gc JVMState<'gc>, int_state: &mut impl PushableFrame<'gc>, ptype: &CPDType, loader: LoaderName) -> Result<Arc<RuntimeClass<'gc>>, WasException<'gc>> {
+    //primitive classes are only ever defined by the bootstrap loader
+    let loader = if ptype.is_primitive() { LoaderName::BootstrapLoader } else { loader };
     // todo cleanup how these guards work
-    let is_loaded = jvm.classes.write().unwrap().is_loaded(ptype);
+    let is_loaded = jvm.classes.write().unwrap().is_loaded(loader, ptype);
     let res = match is_loaded {
         None => {
             let res = match loader {
@@ -146,8 +154,20 @@ pub(crate) fn check_loaded_class_force_loader<'gc, 'l>(jvm: &'gc JVMState<'gc>,
                             class_loader.load_class(jvm, int_state, java_string)?.as_runtime_class(jvm)
                         }
                         CPDType::Array { base_type: sub_type, num_nested_arrs } => {
-                            drop(jvm.classes.write().unwrap());
-                            let sub_class = check_loaded_class(jvm, int_state, sub_type.to_cpdtype())?;
+                            let sub_class = check_loaded_class_force_loader(jvm, int_state, &sub_type.to_cpdtype(), loader)?;
+                            //an array class is defined by the loader of its element type
+                            let element_loader = match sub_class.deref() {
+                                RuntimeClass::Object(_) => jvm.classes.read().unwrap().get_defining_loader(&sub_class),
+                                _ => LoaderName::BootstrapLoader,
+                            };
+                            if element_loader != loader {
+                                let res = check_loaded_class_force_loader(jvm, int_state, ptype, element_loader)?;
+                                let recorded = jvm.classes.write().unwrap().record_initiating_loader(loader, res.clone());
+                                if let Err(violation) = recorded {
+                                    return throw_loader_constraint_violation(jvm, int_state, violation);
+                                }
+                                return Ok(res);
+                            }
                             let serializable = check_loaded_class(jvm, int_state, CClassName::serializable().into())?;
                             let cloneable = check_loaded_class(jvm, int_state, CClassName::cloneable().into())?;
                             let component_type = get_or_create_class_object(jvm, CPDType::new_array_or_normal(sub_type, num_nested_arrs.get() - 1), int_state)?.cast_class();
@@ -173,9 +193,10 @@ pub(crate) fn check_loaded_class_force_loader<'gc, 'l>(jvm: &'gc JVMState<'gc>,
                     bootstrap_load(jvm, int_state, ptype.clone())?
                 }
             };
-            let mut guard = jvm.classes.write().unwrap();
-            guard.initiating_loaders.insert(res.cpdtype(), (loader, res.clone()));
-            guard.loaded_classes_by_type.entry(loader).or_insert(HashMap::new()).insert(res.cpdtype(), res.clone());
+            let recorded = jvm.classes.write().unwrap().record_initiating_loader(loader, res.clone());
+            if let Err(violation) = recorded {
+                return throw_loader_constraint_violation(jvm, int_state, violation);
+            }
             Ok(res)
         }
         Some(res) => Ok(res.clone()),
@@ -191,18 +212,35 @@ pub struct DefaultClassfileGetter<'l, 'k> {
 }
 
 impl ClassFileGetter for DefaultClassfileGetter<'_, '_> {
-    fn get_classfile(&self, vf_context: &VerifierContext, _loader: LoaderName, class: CClassName) -> Result<Arc<dyn ClassView>, ClassLoadingError> {
-        //todo verification needs to be better hooked in
-        Ok(match self.jvm.classpath.lookup(&class, &self.jvm.string_pool) {
-            Ok(x) => Arc::new(ClassBackedView::from(x, &self.jvm.string_pool)),
-            Err(err) => {
-                // eprintln!("WARN: CLASS NOT FOUND WHILE VERIFYING:");
-                // dbg!(vf_context.current_class.0.to_str(&vf_context.string_pool));
-                // dbg!(&err);
-                // dbg!(class.0.to_str(&self.jvm.string_pool));
-                return Err(err);
+    fn get_classfile(&self, _vf_context: &VerifierContext, loader: LoaderName, class: CClassName) -> Result<Arc<dyn ClassView>, ClassLoadingError> {
+        match loader {
+            LoaderName::BootstrapLoader => {
+                Ok(Arc::new(ClassBackedView::from(self.jvm.classpath.lookup(&class, &self.jvm.string_pool)?, &self.jvm.string_pool)))
+            }
+            LoaderName::UserDefinedLoader(_) => {
+                //user defined loaders run java code, so only classes they have already loaded can be seen here
+                match self.jvm.classes.read().unwrap().is_loaded(loader, &class.into()) {
+                    Some(runtime_class) => Ok(runtime_class.view()),
+                    None => Err(ClassLoadingError::ClassNotFoundException(ClassName::Str(class.0.to_str(&self.jvm.string_pool)))),
+                }
             }
-        })
+        }
+    }
+
+    fn defining_loader(&self, vf_context: &VerifierContext, initiating_loader: LoaderName, class: CClassName) -> Result<LoaderName, ClassLoadingError> {
+        if class == vf_context.current_class && initiating_loader == vf_context.current_loader {
+            return Ok(initiating_loader);
+        }
+        match initiating_loader {
+            LoaderName::BootstrapLoader => Ok(LoaderName::BootstrapLoader),
+            LoaderName::UserDefinedLoader(_) => {
+                let classes = self.jvm.classes.read().unwrap();
+                match classes.is_loaded(initiating_loader, &class.into()) {
+                    Some(runtime_class) => Ok(classes.get_defining_loader(&runtime_class)),
+                    None => Err(ClassLoadingError::ClassNotFoundException(ClassName::Str(class.0.to_str(&self.jvm.string_pool)))),
+                }
+            }
+        }
     }
 }
 
@@ -214,6 +252,120 @@ impl LivePoolGetter for DefaultLivePoolGetter {
     }
 }
 
+// Throws what hotspot would for a class which exists but can't be loaded.
+pub fn throw_class_loading_error<'gc, T>(jvm: &'gc JVMState<'gc>, int_state: &mut impl PushableFrame<'gc>, class_name: CClassName, err: ClassLoadingError) -> Result<T, WasException<'gc>> {
+    let class_name = class_name.0.to_str(&jvm.string_pool);
+    let exception_obj = match err {
+        ClassLoadingError::ClassNotFoundException(missing) => {
+            //a class needed to verify this one is missing
+            return throw_no_class_def_found_error(jvm, int_state, missing.get_referred_name().to_string());
+        }
+        ClassLoadingError::ClassFileInvalid(parsing_error) => {
+            let message = JString::from_rust(jvm, int_state, Wtf8Buf::from_string(format!("{} (invalid class file: {})", class_name, parsing_error)))?;
+            ClassFormatError::new(jvm, int_state, message)?.full_object().cast_throwable()
+        }
+        ClassLoadingError::ClassVerificationError => {
+            let message = JString::from_rust(jvm, int_state, Wtf8Buf::from_string(class_name))?;
+            VerifyError::new(jvm, int_state, message)?.full_object().cast_throwable()
+        }
+        ClassLoadingError::JarInvalid(path, reason) | ClassLoadingError::JImageInvalid(path, reason) => {
+            return throw_no_class_def_found_error(jvm, int_state, format!("{} (error reading {}: {})", class_name, path.display(), reason));
+        }
+    };
+    Err(WasException { exception_obj })
+}
+
+pub fn throw_no_class_def_found_error<'gc, T>(jvm: &'gc JVMState<'gc>, int_state: &mut impl PushableFrame<'gc>, message: String) -> Result<T, WasException<'gc>> {
+    let message = JString::from_rust(jvm, int_state, Wtf8Buf::from_string(message))?;
+    let exception_obj = NoClassDefFoundError::new(jvm, int_state, message)?.full_object().cast_throwable();
+    Err(WasException { exception_obj })
+}
+
+fn should_verify(jvm: &JVMState, loader: LoaderName) -> bool {
+    match loader {
+        LoaderName::BootstrapLoader => jvm.config.verify_bootstrap_classes,
+        LoaderName::UserDefinedLoader(_) => jvm.config.verify_user_classes,
+    }
+}
+
+/// Verifies a newly loaded class, recording the frame types the interpreter and jit rely on. Classes
+/// failing verification throw VerifyError, unless -Xverify says not to reject classes from this loader,
+/// in which case their frames are inferred without being checked. Classes from user defined loaders are
+/// checked in that loader's namespace, loading whatever the verifier asks for through it.
+pub fn verify_class<'gc>(jvm: &'gc JVMState<'gc>, int_state: &mut impl PushableFrame<'gc>, verifier_context: &mut VerifierContext, class_name: CClassName, loader: LoaderName, runtime_class: &Arc<RuntimeClass<'gc>>, class_view: &ClassBackedView) -> Result<(), WasException<'gc>> {
+    let verifier_loader = verifier_context.current_loader;
+    let mut loaded_for_verifier = HashSet::new();
+    loop {
+        match verify(verifier_context, class_name, verifier_loader) {
+            Ok(()) => {
+                jvm.sink_function_verification_date(&verifier_context.verification_types, runtime_class.clone());
+                return Ok(());
+            }
+            Err(TypeSafetyError::Java5Maybe) => {
+                //no stack map frames to check against
+                return infer_frames(jvm, int_state, runtime_class, class_view);
+            }
+            Err(_) if !should_verify(jvm, loader) || is_reflection_accessor(runtime_class) => {
+                return infer_frames(jvm, int_state, runtime_class, class_view);
+            }
+            Err(TypeSafetyError::ClassNotFound(ClassLoadingError::ClassNotFoundException(missing))) if matches!(verifier_loader, LoaderName::UserDefinedLoader(_)) && loaded_for_verifier.insert(missing.get_referred_name().to_string()) => {
+                let missing = CClassName(jvm.string_pool.add_name(missing.get_referred_name().to_string(), true));
+                if check_loaded_class_force_loader(jvm, int_state, &missing.into(), verifier_loader).is_err() {
+                    return throw_no_class_def_found_error(jvm, int_state, missing.0.to_str(&jvm.string_pool));
+                }
+                verifier_context.verification_types.clear();
+            }
+            Err(TypeSafetyError::NotSafe(message)) => {
+                let message = JString::from_rust(jvm, int_state, Wtf8Buf::from_string(message))?;
+                let exception_obj = VerifyError::new(jvm, int_state, message)?.full_object().cast_throwable();
+                return Err(WasException { exception_obj });
+            }
+            Err(TypeSafetyError::ClassNotFound(err)) => return throw_class_loading_error(jvm, int_state, class_name, err),
+        }
+    }
+}
+
+//the accessors sun.reflect generates don't pass verification, so like hotspot they are never rejected
+fn is_reflection_accessor(runtime_class: &Arc<RuntimeClass>) -> bool {
+    let mut parent = runtime_class.unwrap_class_class().parent.clone();
+    while let Some(class) = parent {
+        if class.cpdtype().try_unwrap_class_type() == Some(CClassName::magic_accessor_impl()) {
+            return true;
+        }
+        parent = class.unwrap_class_class().parent.clone();
+    }
+    false
+}
+
+//code type inference can't follow is code the verifier would reject, so throws VerifyError even for unverified classes
+fn infer_frames<'gc>(jvm: &'gc JVMState<'gc>, int_state: &mut impl PushableFrame<'gc>, runtime_class: &Arc<RuntimeClass<'gc>>, class_view: &ClassBackedView) -> Result<(), WasException<'gc>> {
+    for method_view in class_view.methods() {
+        if method_view.code_attribute().is_none() {
+            continue;
+        }
+        let method_id = jvm.method_table.write().unwrap().get_method_id(runtime_class.clone(), method_view.method_i());
+        let res = match type_infer(&method_view, &jvm.string_pool) {
+            Ok(res) => res,
+            Err(err) => {
+                let class_name = class_view.name().unwrap_name().0.to_str(&jvm.string_pool);
+                let message = format!("(class: {}, method: {} signature: {}) {}", class_name, method_view.name().0.to_str(&jvm.string_pool), method_view.desc_str().to_str(&jvm.string_pool), err);
+                let message = JString::from_rust(jvm, int_state, Wtf8Buf::from_string(message))?;
+                let exception_obj = VerifyError::new(jvm, int_state, message)?.full_object().cast_throwable();
+                return Err(WasException { exception_obj });
+            }
+        };
+        let frames_tops = res.inferred_frames().iter().map(|(offset, frame)| {
+            (*offset, SunkVerifierFrames::PartialInferredFrame(frame.clone()))
+        }).collect::<HashMap<_, _>>();
+        let frames_no_tops = res.inferred_frames().iter().map(|(offset, frame)| {
+            (*offset, SunkVerifierFrames::PartialInferredFrame(frame.no_tops()))
+        }).collect::<HashMap<_, _>>();
+        jvm.function_frame_type_data.write().unwrap().no_tops.insert(method_id, frames_no_tops);
+        jvm.function_frame_type_data.write().unwrap().tops.insert(method_id, frames_tops);
+    }
+    Ok(())
+}
+
 static mut BOOTSRAP_LOAD_COUNT: usize = 0;
 
 pub fn bootstrap_load<'gc, 'l>(jvm: &'gc JVMState<'gc>, int_state: &mut impl PushableFrame<'gc>, ptype: CPDType) -> Result<Arc<RuntimeClass<'gc>>, WasException<'gc>> {
@@ -241,19 +393,37 @@ pub fn bootstrap_load<'gc, 'l>(jvm: &'gc JVMState<'gc>, int_state: &mut impl Pus
         CPDType::BooleanType => (create_class_object(jvm, int_state, Some(ClassName::raw_boolean().get_referred_name().to_string()), LoaderName::BootstrapLoader, class_intrinsics_data_primitive)?, Arc::new(RuntimeClass::Boolean)),
         CPDType::VoidType => (create_class_object(jvm, int_state, Some(ClassName::raw_void().get_referred_name().to_string()), LoaderName::BootstrapLoader, class_intrinsics_data_primitive)?, Arc::new(RuntimeClass::Void)),
         CPDType::Class(class_name) => {
-            let classfile = match jvm.classpath.lookup(&class_name, &jvm.string_pool) {
-                Ok(x) => x,
-                Err(_) => {
-                    let class_name_wtf8 = Wtf8Buf::from_string(class_name.0.to_str(&jvm.string_pool).to_string());
-                    let class_name_string = JString::from_rust(jvm, int_state, class_name_wtf8)?;
-
-                    let exception = ClassNotFoundException::new(jvm, int_state, class_name_string)?.full_object();
-                    let throwable = exception.cast_throwable();
-                    // throwable.print_stack_trace(jvm,int_state).unwrap();
-                    return Err(WasException { exception_obj: throwable });
-                }
+            let (classfile, archived_frames) = match lookup_shared_class(jvm, class_name) {
+                Some((classfile, archived_frames)) => (classfile, Some(archived_frames)),
+                None => match jvm.classpath.lookup(&class_name, &jvm.string_pool) {
+                    Ok(x) => (x, None),
+                    Err(ClassLoadingError::ClassNotFoundException(_)) => {
+                        let class_name_wtf8 = Wtf8Buf::from_string(class_name.0.to_str(&jvm.string_pool).to_string());
+                        let class_name_string = JString::from_rust(jvm, int_state, class_name_wtf8)?;
+
+                        let exception = ClassNotFoundException::new(jvm, int_state, class_name_string)?.full_object();
+                        let throwable = exception.cast_throwable();
+                        // throwable.print_stack_trace(jvm,int_state).unwrap();
+                        return Err(WasException { exception_obj: throwable });
+                    }
+                    Err(err) => return throw_class_loading_error(jvm, int_state, class_name, err),
+                },
             };
+            if jvm.config.tracing.trace_class_loads {
+                let source = match archived_frames {
+                    //as hotspot reports classes from its archive
+                    Some(_) => Some("shared objects file".to_string()),
+                    None => jvm.classpath.source(&class_name, &jvm.string_pool).map(|path| path.display().to_string()),
+                };
+                jvm.config.tracing.trace_class_load(&jvm.string_pool, class_name, source.as_deref().unwrap_or("__JVM_DefineClass__"));
+            }
             let class_view = Arc::new(ClassBackedView::from(classfile.clone(), &jvm.string_pool));
+            if let Some(actual_name) = class_view.name().try_unwrap_name() {
+                if actual_name != class_name {
+                    let message = format!("{} (wrong name: {})", class_name.0.to_str(&jvm.string_pool), actual_name.0.to_str(&jvm.string_pool));
+                    return throw_no_class_def_found_error(jvm, int_state, message);
+                }
+            }
             let parent = match class_view.super_name() {
                 Some(super_name) => Some(check_loaded_class(jvm, int_state, super_name.into())?),
                 None => None,
@@ -266,61 +436,26 @@ pub fn bootstrap_load<'gc, 'l>(jvm: &'gc JVMState<'gc>, int_state: &mut impl Pus
             let res = Arc::new(RuntimeClass::Object(
                 RuntimeClassClass::new_new(&jvm.inheritance_tree, &jvm.all_the_static_fields,&mut jvm.bit_vec_paths.write().unwrap(), class_view.clone(), parent, interfaces, ClassStatus::UNPREPARED.into(), &jvm.string_pool, &jvm.class_ids)
             ));
-            let mut verifier_context = VerifierContext {
-                live_pool_getter: Arc::new(DefaultLivePoolGetter {}) as Arc<dyn LivePoolGetter>,
-                classfile_getter: Arc::new(DefaultClassfileGetter { jvm }) as Arc<dyn ClassFileGetter>,
-                string_pool: &jvm.string_pool,
-                current_class: class_name,
-                class_view_cache: Mutex::new(Default::default()),
-                current_loader: LoaderName::BootstrapLoader,
-                verification_types: Default::default(),
-                debug: class_name == CClassName::string(),
-                perf_metrics: &jvm.perf_metrics,
-                permissive_types_workaround: false,
-            };
-
-
-            match verify(&mut verifier_context, class_name, LoaderName::BootstrapLoader) {
-                Ok(verfied) => {
-                    let verification_types = verifier_context.verification_types;
-                    jvm.sink_function_verification_date(&verification_types, res.clone());
+            match archived_frames {
+                //the frames verification produced when the archive was dumped, or inferred if it failed and bootstrap
+                //classes weren't being verified. SharedArchive::open checks that is still the setting.
+                Some(archived_frames) => sink_archived_frames(jvm, &res, archived_frames),
+                None => {
+                    let mut verifier_context = VerifierContext {
+                        live_pool_getter: Arc::new(DefaultLivePoolGetter {}) as Arc<dyn LivePoolGetter>,
+                        classfile_getter: Arc::new(DefaultClassfileGetter { jvm }) as Arc<dyn ClassFileGetter>,
+                        string_pool: &jvm.string_pool,
+                        current_class: class_name,
+                        class_view_cache: Mutex::new(Default::default()),
+                        current_loader: LoaderName::BootstrapLoader,
+                        verification_types: Default::default(),
+                        debug: class_name == CClassName::string(),
+                        perf_metrics: &jvm.perf_metrics,
+                        permissive_types_workaround: false,
+                    };
+                    verify_class(jvm, int_state, &mut verifier_context, class_name, LoaderName::BootstrapLoader, &res, class_view.deref())?;
                 }
-                Err(TypeSafetyError::ClassNotFound(ClassLoadingError::ClassNotFoundException(class_name))) => {
-                    let jstring = JString::from_rust(jvm, int_state, Wtf8Buf::from_string(class_name.get_referred_name().clone())).unwrap();
-                    let exception = ClassNotFoundException::new(jvm, int_state, jstring)?.full_object();
-                    let throwable = exception.cast_throwable();
-                    return Err(WasException { exception_obj: throwable });
-                }
-                Err(TypeSafetyError::NotSafe(not_safe)) => {
-                    dbg!(class_name.0.to_str(&jvm.string_pool));
-                    dbg!(not_safe);
-                    panic!()
-                }
-                Err(TypeSafetyError::Java5Maybe) => {
-                    //todo major dup
-                    for method_view in class_view.clone().methods() {
-                        let method_id = jvm.method_table.write().unwrap().get_method_id(res.clone(), method_view.method_i());
-                        let code = match method_view.code_attribute() {
-                            Some(x) => x,
-                            None => continue,
-                        };
-                        let instructs = code.instructions.iter().sorted_by_key(|(offset, instruct)| *offset).map(|(_, instruct)| instruct.clone()).collect_vec();
-                        let res = type_infer(&method_view);
-                        let frames_tops = res.inferred_frames().iter().map(|(offset, frame)| {
-                            (*offset, SunkVerifierFrames::PartialInferredFrame(frame.clone()))
-                        }).collect::<HashMap<_, _>>();
-                        let frames_no_tops = res.inferred_frames().iter().map(|(offset, frame)| {
-                            (*offset, SunkVerifierFrames::PartialInferredFrame(frame.no_tops()))
-                        }).collect::<HashMap<_, _>>();
-                        jvm.function_frame_type_data.write().unwrap().no_tops.insert(method_id, frames_no_tops);
-                        jvm.function_frame_type_data.write().unwrap().tops.insert(method_id, frames_tops);
-                    }
-                }
-                Err(TypeSafetyError::ClassNotFound(ClassLoadingError::ClassFileInvalid(_))) => {
-                    panic!()
-                }
-                Err(TypeSafetyError::ClassNotFound(ClassLoadingError::ClassVerificationError)) => panic!(),
-            };
+            }
             let method_resolver = MethodResolverImpl { jvm, loader: LoaderName::BootstrapLoader };
             // for method in class_view.methods() {
             //     if method.code_attribute().is_some() {
@@ -328,7 +463,11 @@ pub fn bootstrap_load<'gc, 'l>(jvm: &'gc JVMState<'gc>, int_state: &mut impl Pus
             //         jvm.java_vm_state.add_method(jvm, &method_resolver, method_id)
             //     }
             // }
-            jvm.classes.write().unwrap().initiating_loaders.entry(ptype).or_insert((LoaderName::BootstrapLoader, res.clone()));
+            let mut classes = jvm.classes.write().unwrap();
+            //visible to the bootstrap loader while its class object is created
+            classes.initiating_loaders.entry(ptype).or_insert((LoaderName::BootstrapLoader, res.clone()));
+            classes.loaded_classes_by_type.entry(LoaderName::BootstrapLoader).or_default().entry(ptype).or_insert(res.clone());
+            drop(classes);
             let class_object = create_class_object(jvm, int_state, class_name.0.to_str(&jvm.string_pool).into(), LoaderName::BootstrapLoader, ClassIntrinsicsData {
                 is_array: false,
                 is_primitive: false,
@@ -336,7 +475,10 @@ pub fn bootstrap_load<'gc, 'l>(jvm: &'gc JVMState<'gc>, int_state: &mut impl Pus
                 this_cpdtype: ptype,
             })?;
             class_object.duplicate_discouraged().cast_class().debug_assert(jvm);
-            jvm.classes.write().unwrap().class_object_pool.insert(ByAddressAllocatedObject::Owned(class_object.clone()), ByAddress(res.clone()));
+            let mut classes = jvm.classes.write().unwrap();
+            classes.class_object_pool.insert(ByAddressAllocatedObject::Owned(class_object.clone()), ByAddress(res.clone()));
+            classes.record_implementor(&res);
+            drop(classes);
             assert_eq!(class_object.runtime_class(jvm).cpdtype(), CClassName::class().into());
             (class_object, res)
         }
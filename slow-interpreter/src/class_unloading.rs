@@ -0,0 +1,418 @@
+use std::collections::{HashMap, HashSet};
+use std::ffi::c_void;
+use std::mem::size_of;
+use std::ops::Deref;
+use std::ptr::NonNull;
+use std::sync::Arc;
+
+use by_address::ByAddress;
+use itertools::Itertools;
+
+use another_jit_vm_ir::ir_stack::IRFrameRef;
+use gc_memory_layout_common::memory_regions::MemoryRegions;
+use runtime_class_stuff::RuntimeClass;
+use rust_jvm_common::compressed_classfile::compressed_types::CPDType;
+use rust_jvm_common::loading::LoaderName;
+
+use crate::{JavaValueCommon, NewJavaValueHandle};
+use crate::better_java_stack::frames::HasFrame;
+use crate::java_values::ByAddressAllocatedObject;
+use crate::jvm_state::{Classes, JVMState};
+use crate::stdlib::java::lang::throwable::Throwable;
+
+/// Unloads the classes of user defined class loaders which can no longer be reached, along with
+/// everything the vm keeps for them. There is no tracing collector yet, so reachability comes from a
+/// conservative scan of the heap, starting from vm handles, static fields and every thread's java
+/// stack. Returns the number of classes unloaded.
+pub fn unload_unreachable_class_loaders<'gc>(jvm: &'gc JVMState<'gc>, int_state: &impl HasFrame<'gc>) -> usize {
+    jvm.thread_state.at_safepoint(|| {
+        let stack_words = match all_stack_words(jvm, int_state) {
+            Some(stack_words) => stack_words,
+            //a thread which isn't blocked could be holding on to anything
+            None => return 0,
+        };
+        let unreachable_loaders = unreachable_loaders(jvm, stack_words);
+        if unreachable_loaders.is_empty() {
+            return 0;
+        }
+        unload_loaders(jvm, unreachable_loaders)
+    })
+}
+
+fn all_stack_words<'gc>(jvm: &'gc JVMState<'gc>, int_state: &impl HasFrame<'gc>) -> Option<Vec<u64>> {
+    let mut stack_words = words_above_frame(jvm, int_state.frame_ref());
+    let current_tid = jvm.thread_state.try_get_current_thread().map(|current_thread| current_thread.java_tid);
+    let other_threads = jvm.thread_state.all_java_threads.read().unwrap().values()
+        .filter(|thread| Some(thread.java_tid) != current_tid && thread.is_alive())
+        .cloned()
+        .collect_vec();
+    for thread in other_threads {
+        stack_words.extend(thread.blocked_remote_view(jvm, |remote_frame| words_above_frame(jvm, remote_frame.frame_ref()))?);
+    }
+    Some(stack_words)
+}
+
+// every word of the stack from the innermost frame's data up to the top of the stack
+fn words_above_frame<'gc>(jvm: &'gc JVMState<'gc>, frame: IRFrameRef) -> Vec<u64> {
+    let top = frame._ir_stack.native.mmaped_top.as_ptr() as *const u64;
+    let mut current = unsafe { frame.frame_ptr().as_ptr().sub(frame.frame_size(&jvm.java_vm_state.ir)) } as *const u64;
+    let mut res = vec![];
+    while current < top {
+        res.push(unsafe { current.read_unaligned() });
+        current = unsafe { current.add(1) };
+    }
+    res
+}
+
+struct Marking<'gc, 'l> {
+    jvm: &'gc JVMState<'gc>,
+    classes: &'l Classes<'gc>,
+    object_sizes: HashMap<usize, usize>,
+    vtable_classes: HashMap<usize, Arc<RuntimeClass<'gc>>>,
+    classes_by_type: HashMap<CPDType, Vec<Arc<RuntimeClass<'gc>>>>,
+    marked_objects: HashSet<usize>,
+    object_worklist: Vec<usize>,
+    live_classes: HashSet<ByAddress<Arc<RuntimeClass<'gc>>>>,
+    live_loaders: HashSet<LoaderName>,
+}
+
+impl<'gc, 'l> Marking<'gc, 'l> {
+    fn mark_word(&mut self, word: u64) {
+        let word = word as usize;
+        if self.object_sizes.contains_key(&word) && self.marked_objects.insert(word) {
+            self.object_worklist.push(word);
+        }
+    }
+
+    fn mark_loader(&mut self, loader: LoaderName) {
+        if !self.live_loaders.insert(loader) {
+            return;
+        }
+        let classes = self.classes;
+        if let LoaderName::UserDefinedLoader(loader_index) = loader {
+            if let Some(loader_object) = classes.class_loaders.get_by_left(&loader_index) {
+                self.mark_word(loader_object.raw_ptr_usize() as u64);
+            }
+        }
+        //a live loader can still hand out any class it initiated
+        for class in classes.loaded_classes_by_type.get(&loader).into_iter().flat_map(|loaded| loaded.values()) {
+            self.mark_class(class.clone());
+        }
+    }
+
+    fn mark_class(&mut self, class: Arc<RuntimeClass<'gc>>) {
+        if !self.live_classes.insert(ByAddress(class.clone())) {
+            return;
+        }
+        let classes = self.classes;
+        self.mark_loader(classes.get_defining_loader(&class));
+        if let Some(class_object) = classes.class_object_pool.get_by_right(&ByAddress(class.clone())) {
+            self.mark_word(class_object.raw_ptr_usize() as u64);
+        }
+        if let Some(protection_domain) = classes.protection_domains.get_by_left(&ByAddress(class.clone())) {
+            self.mark_word(protection_domain.raw_ptr_usize() as u64);
+        }
+        for resolved in resolved_dynamic_constant_objects(classes.resolved_dynamic_constants.get(&ByAddress(class.clone()))) {
+            self.mark_word(resolved as u64);
+        }
+        match class.deref() {
+            RuntimeClass::Array(array) => self.mark_class(array.sub_class.clone()),
+            RuntimeClass::Object(class_class) => {
+                for static_field in self.jvm.all_the_static_fields.raw_addresses(class_class.class_view.name().unwrap_name()) {
+                    self.mark_word(unsafe { static_field.cast::<u64>().as_ptr().read() });
+                }
+                for super_class in class_class.parent.iter().chain(class_class.interfaces.iter()) {
+                    self.mark_class(super_class.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn trace(&mut self) {
+        let classes = self.classes;
+        while let Some(object) = self.object_worklist.pop() {
+            if let Some(class) = classes.class_object_pool.get_by_left(&ByAddressAllocatedObject::LookupOnly(object)) {
+                self.mark_class(class.0.clone());
+            }
+            if let Some(loader_index) = classes.class_loaders.get_by_right(&ByAddressAllocatedObject::LookupOnly(object)) {
+                self.mark_loader(LoaderName::UserDefinedLoader(*loader_index));
+            }
+            let region_header = MemoryRegions::find_object_region_header(NonNull::new(object as *mut c_void).unwrap());
+            if let Some(class) = self.vtable_classes.get(&(region_header.vtable_ptr as usize)).cloned() {
+                self.mark_class(class);
+            }
+            let end = object + self.object_sizes[&object];
+            let mut current = match region_header.array_elem_type {
+                Some(elem_type) if elem_type.is_primitive() => continue,
+                Some(elem_type) => {
+                    //object arrays share a type per element name, so every class of that name is kept
+                    let base_type = match elem_type {
+                        CPDType::Array { base_type, .. } => base_type.to_cpdtype(),
+                        elem_type => elem_type,
+                    };
+                    for class in self.classes_by_type.get(&base_type).cloned().unwrap_or_default() {
+                        self.mark_class(class);
+                    }
+                    object + region_header.array_elem0_offset
+                }
+                None => object,
+            };
+            while current + size_of::<u64>() <= end {
+                self.mark_word(unsafe { (current as *const u64).read_unaligned() });
+                current += size_of::<u64>();
+            }
+        }
+    }
+}
+
+fn resolved_dynamic_constant_objects<'gc>(resolved: Option<&HashMap<u16, Result<NewJavaValueHandle<'gc>, Throwable<'gc>>>>) -> Vec<usize> {
+    resolved.into_iter()
+        .flat_map(|resolved| resolved.values())
+        .flat_map(|resolved| match resolved {
+            Ok(resolved) => resolved.as_njv().try_unwrap_object_alloc().flatten().map(|object| object.raw_ptr_usize()),
+            Err(exception) => Some(exception.normal_object.raw_ptr_usize()),
+        })
+        .collect()
+}
+
+fn unreachable_loaders<'gc>(jvm: &'gc JVMState<'gc>, stack_words: Vec<u64>) -> HashSet<LoaderName> {
+    let classes = jvm.classes.read().unwrap();
+    let candidates: HashSet<LoaderName> = classes.class_loaders.left_values().map(|loader_index| LoaderName::UserDefinedLoader(*loader_index)).collect();
+    if candidates.is_empty() {
+        return HashSet::new();
+    }
+    let object_sizes = jvm.gc.memory_region.lock().unwrap().allocated_objects().into_iter()
+        .map(|(object, size)| (object.as_ptr() as usize, size.get()))
+        .collect();
+    let vtable_classes = jvm.vtables.lock().unwrap().vtable_classes()
+        .map(|(vtable, class)| (vtable.as_ptr() as usize, class.clone()))
+        .collect();
+    let mut classes_by_type: HashMap<CPDType, Vec<Arc<RuntimeClass<'gc>>>> = HashMap::new();
+    for (class, loader) in classes.defining_loaders.iter() {
+        if candidates.contains(loader) {
+            classes_by_type.entry(class.cpdtype()).or_default().push(class.0.clone());
+        }
+    }
+    let mut marking = Marking {
+        jvm,
+        classes: &classes,
+        object_sizes,
+        vtable_classes,
+        classes_by_type,
+        marked_objects: HashSet::new(),
+        object_worklist: vec![],
+        live_classes: HashSet::new(),
+        live_loaders: HashSet::new(),
+    };
+    //handles held by the class registry only keep objects alive through the class or loader they belong to
+    let mut registry_handles: HashMap<usize, usize> = HashMap::new();
+    for held in classes.class_object_pool.left_values().chain(classes.class_loaders.right_values()).chain(classes.protection_domains.right_values()) {
+        *registry_handles.entry(held.raw_ptr_usize()).or_default() += 1;
+    }
+    for resolved in classes.resolved_dynamic_constants.values().flat_map(|resolved| resolved_dynamic_constant_objects(Some(resolved))) {
+        *registry_handles.entry(resolved).or_default() += 1;
+    }
+    for (object, count) in jvm.gc.root_counts() {
+        let object = object.as_ptr() as usize;
+        if count > registry_handles.get(&object).copied().unwrap_or(0) {
+            marking.mark_word(object as u64);
+        }
+    }
+    for word in stack_words {
+        marking.mark_word(word);
+    }
+    marking.mark_loader(LoaderName::BootstrapLoader);
+    for loader in classes.loaded_classes_by_type.keys() {
+        if !candidates.contains(loader) {
+            marking.mark_loader(*loader);
+        }
+    }
+    for class in classes.class_object_pool.right_values() {
+        if !candidates.contains(&classes.get_defining_loader(class)) {
+            marking.mark_class(class.0.clone());
+        }
+    }
+    marking.trace();
+    candidates.difference(&marking.live_loaders).copied().collect()
+}
+
+fn element_class<'gc>(class: &Arc<RuntimeClass<'gc>>) -> &Arc<RuntimeClass<'gc>> {
+    match class.deref() {
+        RuntimeClass::Array(array) => element_class(&array.sub_class),
+        _ => class,
+    }
+}
+
+fn classes_to_unload<'gc>(defining_loaders: &HashMap<ByAddress<Arc<RuntimeClass<'gc>>>, LoaderName>, loaded_classes_by_type: &HashMap<LoaderName, HashMap<CPDType, Arc<RuntimeClass<'gc>>>>, loaders: &HashSet<LoaderName>) -> HashSet<ByAddress<Arc<RuntimeClass<'gc>>>> {
+    let defined: HashSet<ByAddress<Arc<RuntimeClass<'gc>>>> = defining_loaders.iter()
+        .filter(|(_, loader)| loaders.contains(loader))
+        .map(|(class, _)| class.clone())
+        .collect();
+    //array classes aren't in defining_loaders, they go with their element class
+    loaders.iter()
+        .flat_map(|loader| loaded_classes_by_type.get(loader).into_iter().flat_map(|loaded| loaded.values()))
+        .filter(|class| defined.contains(&ByAddress(element_class(class).clone())))
+        .map(|class| ByAddress(class.clone()))
+        .chain(defined.iter().cloned())
+        .collect()
+}
+
+// types whose initiating loader or class is going away are looked up through whichever remaining
+// loader still has them loaded, expects the unloaded loaders to already be gone from loaded_classes_by_type
+fn repoint_initiating_loaders<'gc>(initiating_loaders: &mut HashMap<CPDType, (LoaderName, Arc<RuntimeClass<'gc>>)>, loaded_classes_by_type: &HashMap<LoaderName, HashMap<CPDType, Arc<RuntimeClass<'gc>>>>, loaders: &HashSet<LoaderName>, unloaded_classes: &HashSet<ByAddress<Arc<RuntimeClass<'gc>>>>) {
+    let stale_types = initiating_loaders.iter()
+        .filter(|(_, (loader, class))| loaders.contains(loader) || unloaded_classes.contains(&ByAddress(class.clone())))
+        .map(|(cpdtype, _)| *cpdtype)
+        .collect_vec();
+    for cpdtype in stale_types {
+        initiating_loaders.remove(&cpdtype);
+        let still_loaded = loaded_classes_by_type.iter().find_map(|(loader, loaded)| Some((*loader, loaded.get(&cpdtype)?.clone())));
+        if let Some(still_loaded) = still_loaded {
+            initiating_loaders.insert(cpdtype, still_loaded);
+        }
+    }
+}
+
+fn unload_loaders<'gc>(jvm: &'gc JVMState<'gc>, loaders: HashSet<LoaderName>) -> usize {
+    let mut classes = jvm.classes.write().unwrap();
+    let unloaded_classes = classes_to_unload(&classes.defining_loaders, &classes.loaded_classes_by_type, &loaders);
+    for loader in loaders.iter() {
+        classes.loaded_classes_by_type.remove(loader);
+        if let LoaderName::UserDefinedLoader(loader_index) = loader {
+            classes.class_loaders.remove_by_left(loader_index);
+        }
+    }
+    let Classes { initiating_loaders, loaded_classes_by_type, .. } = &mut *classes;
+    repoint_initiating_loaders(initiating_loaders, loaded_classes_by_type, &loaders, &unloaded_classes);
+    for class in unloaded_classes.iter() {
+        classes.class_object_pool.remove_by_right(class);
+        classes.protection_domains.remove_by_left(class);
+        classes.defining_loaders.remove(class);
+        classes.resolved_dynamic_constants.remove(class);
+    }
+    classes.anon_classes.retain(|class| !unloaded_classes.contains(&ByAddress(class.clone())));
+    for implementors in classes.implementors.values_mut() {
+        implementors.retain(|class| !unloaded_classes.contains(&ByAddress(class.clone())));
+    }
+    classes.loader_constraints.unload(&loaders, &unloaded_classes);
+    classes.unloaded_class_count += unloaded_classes.len() as u64;
+    classes.unloaded_class_loader_count += loaders.len() as u32;
+    let still_loaded_types: HashSet<CPDType> = classes.initiating_loaders.keys().copied().collect();
+    drop(classes);
+    let mut freed_vtables = HashSet::new();
+    let mut freed_itables = HashSet::new();
+    for class in unloaded_classes.iter() {
+        let method_ids = jvm.method_table.write().unwrap().unload_class(class);
+        let mut function_frame_type_data = jvm.function_frame_type_data.write().unwrap();
+        for method_id in method_ids.iter() {
+            function_frame_type_data.no_tops.remove(method_id);
+            function_frame_type_data.tops.remove(method_id);
+        }
+        drop(function_frame_type_data);
+        for method_id in method_ids.iter() {
+            jvm.java_vm_state.unload_method(jvm, *method_id);
+        }
+        jvm.field_table.write().unwrap().unload_class(class);
+        jvm.invoke_virtual_lookup_cache.write().unwrap().unload_class(class);
+        jvm.invoke_interface_lookup_cache.write().unwrap().unload_class(class, method_ids.as_slice());
+        jvm.native_libaries.registered_natives.write().unwrap().remove(class);
+        freed_vtables.extend(jvm.vtables.lock().unwrap().unload_class(class));
+        freed_itables.extend(jvm.itables.lock().unwrap().unload_class(class));
+        if let RuntimeClass::Object(class_class) = class.deref().deref() {
+            let class_name = class_class.class_view.name().unwrap_name();
+            jvm.config.tracing.trace_class_unload(&jvm.string_pool, class_name);
+            if !still_loaded_types.contains(&class_name.into()) {
+                jvm.all_the_static_fields.unload_class(class_name);
+            }
+        }
+    }
+    jvm.gc.memory_region.lock().unwrap().retire_types(|allocated_object_type| {
+        allocated_object_type.vtable().map_or(false, |vtable| freed_vtables.contains(&vtable)) ||
+            allocated_object_type.itable().map_or(false, |itable| freed_itables.contains(&itable))
+    });
+    unloaded_classes.len()
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::collections::{HashMap, HashSet};
+    use std::sync::Arc;
+
+    use bimap::BiMap;
+    use by_address::ByAddress;
+
+    use runtime_class_stuff::{RuntimeClass, RuntimeClassArray};
+    use rust_jvm_common::compressed_classfile::class_names::CClassName;
+    use rust_jvm_common::compressed_classfile::compressed_types::CPDType;
+    use rust_jvm_common::loading::{LoaderIndex, LoaderName};
+
+    use crate::class_unloading::{classes_to_unload, repoint_initiating_loaders};
+    use crate::java_values::ByAddressAllocatedObject;
+    use crate::jvm_state::new_loader_index;
+
+    fn loader(idx: u32) -> LoaderName {
+        LoaderName::UserDefinedLoader(LoaderIndex(idx))
+    }
+
+    //primitive classes stand in for classes with real views, only their addresses matter here
+    fn class<'gc>() -> Arc<RuntimeClass<'gc>> {
+        Arc::new(RuntimeClass::Int)
+    }
+
+    #[test]
+    pub fn test_classes_to_unload() {
+        let defined = class();
+        let array = Arc::new(RuntimeClass::Array(RuntimeClassArray { sub_class: defined.clone(), serializable: class(), cloneable: class() }));
+        let initiated = class();
+        let object: CPDType = CClassName::object().into();
+        let string: CPDType = CClassName::string().into();
+        let defining_loaders = HashMap::from([(ByAddress(defined.clone()), loader(1)), (ByAddress(initiated.clone()), loader(0))]);
+        let loaded_classes_by_type = HashMap::from([
+            (loader(0), HashMap::from([(string, initiated.clone())])),
+            (loader(1), HashMap::from([(object, defined.clone()), (CPDType::array(object), array.clone()), (string, initiated.clone())])),
+        ]);
+        let unloaded = classes_to_unload(&defining_loaders, &loaded_classes_by_type, &HashSet::from([loader(1)]));
+        //arrays go with their element class, classes loader 1 only initiated stay
+        assert_eq!(unloaded.len(), 2);
+        assert!(unloaded.contains(&ByAddress(defined)));
+        assert!(unloaded.contains(&ByAddress(array)));
+    }
+
+    #[test]
+    pub fn test_repoint_initiating_loaders() {
+        let defined = class();
+        let still_loaded = class();
+        let untouched = class();
+        let object: CPDType = CClassName::object().into();
+        let string: CPDType = CClassName::string().into();
+        let class_type: CPDType = CClassName::class().into();
+        let mut initiating_loaders = HashMap::from([
+            (object, (loader(1), defined.clone())),
+            (string, (loader(1), still_loaded.clone())),
+            (class_type, (loader(0), untouched.clone())),
+        ]);
+        //loader 1 has already been removed
+        let loaded_classes_by_type = HashMap::from([(loader(0), HashMap::from([(string, still_loaded.clone()), (class_type, untouched.clone())]))]);
+        repoint_initiating_loaders(&mut initiating_loaders, &loaded_classes_by_type, &HashSet::from([loader(1)]), &HashSet::from([ByAddress(defined)]));
+        assert!(!initiating_loaders.contains_key(&object));
+        let (string_loader, string_class) = &initiating_loaders[&string];
+        assert_eq!(*string_loader, loader(0));
+        assert!(Arc::ptr_eq(string_class, &still_loaded));
+        let (class_loader, class_class) = &initiating_loaders[&class_type];
+        assert_eq!(*class_loader, loader(0));
+        assert!(Arc::ptr_eq(class_class, &untouched));
+    }
+
+    #[test]
+    pub fn test_new_loader_index() {
+        let mut class_loaders: BiMap<LoaderIndex, ByAddressAllocatedObject> = BiMap::new();
+        assert_eq!(new_loader_index(&class_loaders, 0), LoaderIndex(0));
+        class_loaders.insert(LoaderIndex(0), ByAddressAllocatedObject::LookupOnly(8));
+        class_loaders.insert(LoaderIndex(1), ByAddressAllocatedObject::LookupOnly(16));
+        assert_eq!(new_loader_index(&class_loaders, 0), LoaderIndex(2));
+        //with loader 0 unloaded only one loader is left, but index 1 is still taken
+        class_loaders.remove_by_left(&LoaderIndex(0));
+        assert_eq!(new_loader_index(&class_loaders, 1), LoaderIndex(2));
+    }
+}
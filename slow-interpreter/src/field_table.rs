@@ -7,7 +7,8 @@ use runtime_class_stuff::RuntimeClass;
 use rust_jvm_common::{FieldId, FieldTableIndex};
 
 pub struct FieldTable<'gc> {
-    table: Vec<(Arc<RuntimeClass<'gc>>, u16)>,
+    //entries of unloaded classes are None, ids are never reused
+    table: Vec<Option<(Arc<RuntimeClass<'gc>>, u16)>>,
     //todo at a later date will contain compiled code data etc.
     index: HashMap<ByAddress<Arc<RuntimeClass<'gc>>>, HashMap<u16, FieldTableIndex>>,
 }
@@ -30,7 +31,7 @@ impl<'gc> FieldTable<'gc> {
 
     pub fn register_with_table(&mut self, rc: Arc<RuntimeClass<'gc>>, field_index: u16) -> FieldTableIndex {
         let res = self.table.len();
-        self.table.push((rc.clone(), field_index));
+        self.table.push(Some((rc.clone(), field_index)));
         match self.index.get_mut(&rc.clone().into()) {
             None => {
                 let mut class_methods = HashMap::new();
@@ -45,7 +46,15 @@ impl<'gc> FieldTable<'gc> {
     }
 
     pub fn lookup(&self, id: FieldId) -> (Arc<RuntimeClass<'gc>>, u16) {
-        self.table[id].clone()
+        self.table[id].clone().unwrap()
+    }
+
+    pub fn unload_class(&mut self, rc: &Arc<RuntimeClass<'gc>>) -> Vec<FieldId> {
+        let field_ids = self.index.remove(&rc.clone().into()).map(|class_fields| class_fields.into_values().collect()).unwrap_or_default();
+        for field_id in &field_ids {
+            self.table[*field_id] = None;
+        }
+        field_ids
     }
 
     pub fn new() -> Self {
@@ -31,4 +31,8 @@ impl FunctionCallTargetsByFunction {
             }
         }
     }
+
+    pub fn remove_targets(&mut self, method_id: MethodId) {
+        self.inner.remove(&method_id);
+    }
 }
\ No newline at end of file
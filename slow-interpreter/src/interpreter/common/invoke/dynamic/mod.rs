@@ -1,14 +1,18 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use by_address::ByAddress;
 use itertools::Itertools;
 use wtf8::Wtf8Buf;
 
-use classfile_view::view::attribute_view::BootstrapArgView;
+use classfile_view::view::attribute_view::{BootstrapArgView, BootstrapMethodView};
 use classfile_view::view::ClassView;
-use classfile_view::view::constant_info_view::{ConstantInfoView, InvokeSpecial, InvokeStatic, MethodHandleView, MethodrefView, ReferenceInvokeKind};
+use classfile_view::view::constant_info_view::{ConstantInfoView, InvokeSpecial, InvokeStatic, MethodHandleView, NameAndTypeView, ReferenceInvokeKind};
 use rust_jvm_common::ByteCodeOffset;
 use rust_jvm_common::compressed_classfile::class_names::CClassName;
-use rust_jvm_common::compressed_classfile::compressed_types::CMethodDescriptor;
+use rust_jvm_common::compressed_classfile::compressed_types::{CMethodDescriptor, CPDType};
 use rust_jvm_common::compressed_classfile::method_names::MethodName;
-use rust_jvm_common::descriptor_parser::parse_method_descriptor;
 use rust_jvm_common::runtime_type::RuntimeType;
 
 use crate::{JavaValueCommon, JVMState, NewJavaValueHandle, PushableFrame, WasException};
@@ -24,11 +28,18 @@ use crate::stdlib::java::lang::invoke::method_handle::MethodHandle;
 use crate::stdlib::java::lang::invoke::method_handles::lookup::Lookup;
 use crate::stdlib::java::lang::invoke::method_type::MethodType;
 use crate::stdlib::java::lang::member_name::MemberName;
+use crate::stdlib::java::lang::stack_overflow_error::StackOverflowError;
 use crate::stdlib::java::lang::string::JString;
+use crate::stdlib::java::lang::throwable::Throwable;
 use crate::stdlib::java::NewAsObjectOrJavaValue;
 
 pub mod resolvers;
 
+thread_local! {
+    //CONSTANT_Dynamic entries this thread is running bootstrap methods for, by class address and constant pool index
+    static RESOLVING_DYNAMIC_CONSTANTS: RefCell<HashSet<(usize, u16)>> = RefCell::new(HashSet::new());
+}
+
 pub fn invoke_dynamic<'l, 'gc, 'k>(jvm: &'gc JVMState<'gc>, int_state: &'_ mut RealInterpreterStateGuard<'gc, 'l, 'k>, cp: u16, current_pc: ByteCodeOffset) -> PostInstructionAction<'gc> {
     match invoke_dynamic_impl(jvm, int_state, cp, current_pc) {
         Ok(res) => {
@@ -58,41 +69,7 @@ fn invoke_dynamic_impl<'l, 'gc, 'k>(jvm: &'gc JVMState<'gc>, int_state: &'_ mut
     let bootstrap_method_view = invoke_dynamic_view.bootstrap_method();
     let method_ref = bootstrap_method_view.bootstrap_method_ref();
     let bootstrap_method_handle = method_handle_from_method_view(jvm, int_state.inner(), &method_ref)?;
-    let arg_iterator = bootstrap_method_view.bootstrap_args();
-    let mut args = vec![];
-
-    for x in arg_iterator {
-        args.push(match x {
-            BootstrapArgView::String(s) => JString::from_rust(jvm, int_state.inner(), s.string())?.new_java_value_handle(),
-            BootstrapArgView::Class(c) => JClass::from_type(jvm, int_state.inner(), c.type_())?.new_java_value_handle(),
-            BootstrapArgView::Integer(i) => NewJavaValueHandle::Int(i.int),
-            BootstrapArgView::Long(_) => unimplemented!(),
-            BootstrapArgView::Float(_) => unimplemented!(),
-            BootstrapArgView::Double(_) => unimplemented!(),
-            BootstrapArgView::MethodHandle(mh) => method_handle_from_method_view(jvm, int_state.inner(), &mh)?.new_java_value_handle(),
-            BootstrapArgView::MethodType(mt) => desc_from_rust_str(jvm, int_state.inner(), mt.get_descriptor())?,
-        })
-    }
-
-    //A call site specifier gives a symbolic reference to a method handle which is to serve as
-    // the bootstrap method for a dynamic call site (§4.7.23).The method handle is resolved to
-    // obtain a reference to an instance of java.lang.invoke.MethodHandle (§5.4.3.5)
-    let ref_data = method_ref.get_reference_data();
-    let desc_str = match ref_data {
-        ReferenceInvokeKind::InvokeStatic(is) => match is {
-            InvokeStatic::Interface(_) => unimplemented!(),
-            InvokeStatic::Method(m) => m.name_and_type().desc_str(&jvm.string_pool),
-        },
-        ReferenceInvokeKind::InvokeSpecial(is) => match is {
-            InvokeSpecial::Interface(_) => todo!(),
-            InvokeSpecial::Method(_) => todo!(),
-        },
-        ReferenceInvokeKind::NewInvokeSpecial(nis) => match nis {
-            MethodrefView { .. } => {
-                todo!()
-            }
-        }
-    };
+    let args = bootstrap_args(jvm, int_state.inner(), &bootstrap_method_view)?;
 
     //todo this trusted lookup is wrong. should use whatever the current class is for determining caller class
     let lookup_for_this = Lookup::trusted_lookup(jvm, int_state.inner());
@@ -114,8 +91,8 @@ fn invoke_dynamic_impl<'l, 'gc, 'k>(jvm: &'gc JVMState<'gc>, int_state: &'_ mut
     assert_eq!(lookup_res.len(), 1);
     let invoke = lookup_res.iter().next().unwrap();
     //todo theres a MHN native for this upcall
-    let from_legacy_desc = CMethodDescriptor::from_legacy(parse_method_descriptor(&desc_str.to_str(&jvm.string_pool)).unwrap(), &jvm.string_pool);
-    let call_site = invoke_virtual_method_i(jvm, int_state.inner(), &from_legacy_desc, method_handle_class.clone(), invoke, next_invoke_virtual_args)?.unwrap();
+    let bootstrap_desc = bootstrap_method_desc(jvm, &bootstrap_method_handle);
+    let call_site = invoke_virtual_method_i(jvm, int_state.inner(), &bootstrap_desc, method_handle_class.clone(), invoke, next_invoke_virtual_args)?.unwrap();
     let call_site = call_site.cast_call_site();
     let target = call_site.get_target(jvm, int_state.inner())?;
     let lookup_res = method_handle_view.lookup_method_name(MethodName::method_invokeExact()); //todo need safe java wrapper way of doing this
@@ -155,6 +132,111 @@ fn invoke_dynamic_impl<'l, 'gc, 'k>(jvm: &'gc JVMState<'gc>, int_state: &'_ mut
     Ok(())
 }
 
+/// Resolves the CONSTANT_Dynamic at `cp` of the current class. The bootstrap method runs at most
+/// once per entry: every later ldc of the entry sees the same value, or rethrows the same exception.
+/// An entry whose bootstrap arguments depend on itself throws StackOverflowError.
+pub fn resolve_dynamic_constant<'gc, 'l>(jvm: &'gc JVMState<'gc>, int_state: &mut JavaInterpreterFrame<'gc, 'l>, cp: u16) -> Result<NewJavaValueHandle<'gc>, WasException<'gc>> {
+    let class = int_state.class_pointer(jvm);
+    if let Some(resolved) = jvm.classes.read().unwrap().resolved_dynamic_constants.get(&ByAddress(class.clone())).and_then(|constants| constants.get(&cp)) {
+        return resolved_dynamic_constant(resolved);
+    }
+    let in_progress = (Arc::as_ptr(&class) as usize, cp);
+    if !RESOLVING_DYNAMIC_CONSTANTS.with(|resolving| resolving.borrow_mut().insert(in_progress)) {
+        let class_name = class.cpdtype().java_source_representation(&jvm.string_pool);
+        let message = JString::from_rust(jvm, int_state, Wtf8Buf::from_string(format!("recursive resolution of dynamic constant #{} in {}", cp, class_name)))?;
+        let exception_obj = StackOverflowError::new(jvm, int_state, message)?.full_object().cast_throwable();
+        return Err(WasException { exception_obj });
+    }
+    let res = run_dynamic_constant_bootstrap(jvm, int_state, cp);
+    RESOLVING_DYNAMIC_CONSTANTS.with(|resolving| resolving.borrow_mut().remove(&in_progress));
+    let res = res.map_err(|WasException { exception_obj }| exception_obj);
+    let mut classes = jvm.classes.write().unwrap();
+    let resolved = classes.resolved_dynamic_constants.entry(ByAddress(class)).or_default().entry(cp).or_insert(res);
+    resolved_dynamic_constant(resolved)
+}
+
+fn resolved_dynamic_constant<'gc>(resolved: &Result<NewJavaValueHandle<'gc>, Throwable<'gc>>) -> Result<NewJavaValueHandle<'gc>, WasException<'gc>> {
+    match resolved {
+        Ok(resolved) => Ok(resolved.as_njv().to_handle_discouraged()),
+        Err(exception_obj) => Err(WasException { exception_obj: exception_obj.clone() }),
+    }
+}
+
+fn run_dynamic_constant_bootstrap<'gc, 'l>(jvm: &'gc JVMState<'gc>, int_state: &mut JavaInterpreterFrame<'gc, 'l>, cp: u16) -> Result<NewJavaValueHandle<'gc>, WasException<'gc>> {
+    let method_handle_class = check_initing_or_inited_class(jvm, int_state, CClassName::method_handle().into())?;
+    let class_pointer_view = int_state.current_class_view(jvm).clone();
+    let dynamic_view = match class_pointer_view.constant_pool_view(cp as usize) {
+        ConstantInfoView::Dynamic(dynamic) => dynamic,
+        _ => panic!(),
+    };
+    let name = dynamic_view.name_and_type().name(&jvm.string_pool);
+    let type_ = dynamic_view.field_type(&jvm.string_pool);
+    let bootstrap_method_view = dynamic_view.bootstrap_method();
+    let method_ref = bootstrap_method_view.bootstrap_method_ref();
+    let bootstrap_method_handle = method_handle_from_method_view(jvm, int_state, &method_ref)?;
+    let args = bootstrap_args(jvm, int_state, &bootstrap_method_view)?;
+    //todo same trusted lookup problem as invokedynamic
+    let lookup_for_this = Lookup::trusted_lookup(jvm, int_state);
+    let name_jstring = JString::from_rust(jvm, int_state, Wtf8Buf::from_string(name.to_str(&jvm.string_pool)))?.new_java_value_handle();
+    let type_class = JClass::from_type(jvm, int_state, type_)?.new_java_value_handle();
+
+    //a dynamic constant's bootstrap method takes a Class where an invokedynamic one takes a MethodType
+    let mut bootstrap_invoke_args = vec![bootstrap_method_handle.new_java_value(), lookup_for_this.new_java_value(), name_jstring.as_njv(), type_class.as_njv()];
+    bootstrap_invoke_args.extend(args.iter().map(|arg| arg.as_njv()));
+    let method_handle_view = method_handle_class.view();
+    let lookup_res = method_handle_view.lookup_method_name(MethodName::method_invoke());
+    let invoke = lookup_res.iter().next().unwrap();
+    let desc = bootstrap_method_desc(jvm, &bootstrap_method_handle);
+    let res = invoke_virtual_method_i(jvm, int_state, &desc, method_handle_class.clone(), invoke, bootstrap_invoke_args)?.unwrap();
+    Ok(if type_.is_primitive() && !desc.return_type.is_primitive() {
+        unbox_dynamic_constant(jvm, res, type_)
+    } else {
+        res
+    })
+}
+
+//todo dupe with unwrap_boxed_java_value in libjvm
+fn unbox_dynamic_constant<'gc>(jvm: &'gc JVMState<'gc>, boxed: NewJavaValueHandle<'gc>, type_: CPDType) -> NewJavaValueHandle<'gc> {
+    match type_ {
+        CPDType::BooleanType => NewJavaValueHandle::Boolean(boxed.cast_boolean().inner_value(jvm)),
+        CPDType::ByteType => NewJavaValueHandle::Byte(boxed.cast_byte().inner_value(jvm)),
+        CPDType::ShortType => NewJavaValueHandle::Short(boxed.cast_short().inner_value(jvm)),
+        CPDType::CharType => NewJavaValueHandle::Char(boxed.cast_char().inner_value(jvm)),
+        CPDType::IntType => NewJavaValueHandle::Int(boxed.cast_int().inner_value(jvm)),
+        CPDType::LongType => NewJavaValueHandle::Long(boxed.cast_long().inner_value(jvm)),
+        CPDType::FloatType => NewJavaValueHandle::Float(boxed.cast_float().inner_value(jvm)),
+        CPDType::DoubleType => NewJavaValueHandle::Double(boxed.cast_double().inner_value(jvm)),
+        _ => boxed,
+    }
+}
+
+fn bootstrap_args<'gc, 'l>(jvm: &'gc JVMState<'gc>, int_state: &mut JavaInterpreterFrame<'gc, 'l>, bootstrap_method_view: &BootstrapMethodView) -> Result<Vec<NewJavaValueHandle<'gc>>, WasException<'gc>> {
+    let mut args = vec![];
+    for x in bootstrap_method_view.bootstrap_args() {
+        args.push(match x {
+            BootstrapArgView::String(s) => JString::from_rust(jvm, int_state, s.string())?.new_java_value_handle(),
+            BootstrapArgView::Class(c) => JClass::from_type(jvm, int_state, c.type_())?.new_java_value_handle(),
+            BootstrapArgView::Integer(i) => NewJavaValueHandle::Int(i.int),
+            BootstrapArgView::Long(l) => NewJavaValueHandle::Long(l.long),
+            BootstrapArgView::Float(f) => NewJavaValueHandle::Float(f.float),
+            BootstrapArgView::Double(d) => NewJavaValueHandle::Double(d.double),
+            BootstrapArgView::MethodHandle(mh) => method_handle_from_method_view(jvm, int_state, &mh)?.new_java_value_handle(),
+            BootstrapArgView::MethodType(mt) => desc_from_rust_str(jvm, int_state, mt.get_descriptor())?,
+            BootstrapArgView::Dynamic(dynamic) => resolve_dynamic_constant(jvm, int_state, dynamic.i as u16)?,
+        })
+    }
+    Ok(args)
+}
+
+//A call site specifier gives a symbolic reference to a method handle which is to serve as
+// the bootstrap method for a dynamic call site (§4.7.23).The method handle is resolved to
+// obtain a reference to an instance of java.lang.invoke.MethodHandle (§5.4.3.5)
+//the resolved handle's type already accounts for receivers of invokespecial and the result of newinvokespecial
+fn bootstrap_method_desc<'gc>(jvm: &'gc JVMState<'gc>, bootstrap_method_handle: &MethodHandle<'gc>) -> CMethodDescriptor {
+    let method_type = bootstrap_method_handle.type__(jvm);
+    CMethodDescriptor { arg_types: method_type.get_ptypes_as_types(jvm), return_type: method_type.get_rtype_as_type(jvm) }
+}
+
 //todo this should go in MethodType or something.
 fn desc_from_rust_str<'gc, 'l>(jvm: &'gc JVMState<'gc>, int_state: &mut impl PushableFrame<'gc>, desc_str: String) -> Result<NewJavaValueHandle<'gc>, WasException<'gc>> {
     let desc_str = JString::from_rust(jvm, int_state, Wtf8Buf::from_string(desc_str))?;
@@ -163,49 +245,41 @@ fn desc_from_rust_str<'gc, 'l>(jvm: &'gc JVMState<'gc>, int_state: &mut impl Pus
 }
 
 fn method_handle_from_method_view<'gc, 'l>(jvm: &'gc JVMState<'gc>, int_state: &'_ mut JavaInterpreterFrame<'gc, 'l>, method_ref: &MethodHandleView) -> Result<MethodHandle<'gc>, WasException<'gc>> {
-    let methodref_view = method_ref.clone();
-    Ok(match methodref_view.get_reference_data() {
+    // let lookup = MethodHandle::lookup(jvm, int_state);//todo use public
+    let lookup = Lookup::trusted_lookup(jvm, int_state);
+    Ok(match method_ref.get_reference_data() {
         ReferenceInvokeKind::InvokeStatic(is) => {
-            match is {
-                InvokeStatic::Interface(_) => unimplemented!(),
-                InvokeStatic::Method(mr) => {
-                    // let lookup = MethodHandle::lookup(jvm, int_state);//todo use public
-                    let lookup = Lookup::trusted_lookup(jvm, int_state);
-                    let name = JString::from_rust(jvm, int_state, Wtf8Buf::from_string(mr.name_and_type().name(&jvm.string_pool).to_str(&jvm.string_pool)))?;
-                    let desc = JString::from_rust(jvm, int_state, Wtf8Buf::from_string(mr.name_and_type().desc_str(&jvm.string_pool).to_str(&jvm.string_pool)))?;
-                    let method_type = MethodType::from_method_descriptor_string(jvm, int_state, desc, None)?;
-                    let target_class = JClass::from_type(jvm, int_state, mr.class(&jvm.string_pool).to_cpdtype())?;
-                    lookup.find_static(jvm, int_state, target_class, name, method_type)?
-                }
-            }
+            let (target_class, name_and_type) = match is {
+                InvokeStatic::Interface(imr) => (imr.class().to_cpdtype(), imr.name_and_type()),
+                InvokeStatic::Method(mr) => (mr.class(&jvm.string_pool).to_cpdtype(), mr.name_and_type()),
+            };
+            let (target_class, name, method_type) = lookup_args(jvm, int_state, target_class, &name_and_type)?;
+            lookup.find_static(jvm, int_state, target_class, name, method_type)?
         }
         ReferenceInvokeKind::InvokeSpecial(is) => {
-            match is {
-                InvokeSpecial::Interface(_) => todo!(),
-                InvokeSpecial::Method(mr) => {
-                    //todo dupe
-                    let lookup = Lookup::trusted_lookup(jvm, int_state);
-                    let name = JString::from_rust(jvm, int_state, Wtf8Buf::from_string(mr.name_and_type().name(&jvm.string_pool).to_str(&jvm.string_pool)))?;
-                    let desc = JString::from_rust(jvm, int_state, Wtf8Buf::from_string(mr.name_and_type().desc_str(&jvm.string_pool).to_str(&jvm.string_pool)))?;
-                    let method_type = MethodType::from_method_descriptor_string(jvm, int_state, desc, None)?;
-                    let target_class = JClass::from_type(jvm, int_state, mr.class(&jvm.string_pool).to_cpdtype())?;
-                    let not_sure_if_correct_at_all = int_state.class_pointer(jvm).cpdtype();
-                    let special_caller = JClass::from_type(jvm, int_state, not_sure_if_correct_at_all)?;
-                    lookup.find_special(jvm, int_state, target_class, name, method_type, special_caller)?
-                }
-            }
+            let (target_class, name_and_type) = match is {
+                InvokeSpecial::Interface(imr) => (imr.class().to_cpdtype(), imr.name_and_type()),
+                InvokeSpecial::Method(mr) => (mr.class(&jvm.string_pool).to_cpdtype(), mr.name_and_type()),
+            };
+            let (target_class, name, method_type) = lookup_args(jvm, int_state, target_class, &name_and_type)?;
+            let not_sure_if_correct_at_all = int_state.class_pointer(jvm).cpdtype();
+            let special_caller = JClass::from_type(jvm, int_state, not_sure_if_correct_at_all)?;
+            lookup.find_special(jvm, int_state, target_class, name, method_type, special_caller)?
         }
         ReferenceInvokeKind::NewInvokeSpecial(mr) => {
-            //todo dupe
-            let lookup = Lookup::trusted_lookup(jvm,int_state);
-            let name = JString::from_rust(jvm, int_state, Wtf8Buf::from_string(mr.name_and_type().name(&jvm.string_pool).to_str(&jvm.string_pool)))?;
-            let desc = JString::from_rust(jvm, int_state, Wtf8Buf::from_string(mr.name_and_type().desc_str(&jvm.string_pool).to_str(&jvm.string_pool)))?;
-            let method_type = MethodType::from_method_descriptor_string(jvm, int_state, desc, None)?;
-            let target_class = JClass::from_type(jvm, int_state, mr.class(&jvm.string_pool).to_cpdtype())?;
+            let (target_class, _name, method_type) = lookup_args(jvm, int_state, mr.class(&jvm.string_pool).to_cpdtype(), &mr.name_and_type())?;
             lookup.find_constructor(jvm, int_state, target_class, method_type)?
         }
     })
 }
+
+fn lookup_args<'gc, 'l>(jvm: &'gc JVMState<'gc>, int_state: &'_ mut JavaInterpreterFrame<'gc, 'l>, target_class: CPDType, name_and_type: &NameAndTypeView) -> Result<(JClass<'gc>, JString<'gc>, MethodType<'gc>), WasException<'gc>> {
+    let name = JString::from_rust(jvm, int_state, Wtf8Buf::from_string(name_and_type.name(&jvm.string_pool).to_str(&jvm.string_pool)))?;
+    let desc = JString::from_rust(jvm, int_state, Wtf8Buf::from_string(name_and_type.desc_str(&jvm.string_pool).to_str(&jvm.string_pool)))?;
+    let method_type = MethodType::from_method_descriptor_string(jvm, int_state, desc, None)?;
+    let target_class = JClass::from_type(jvm, int_state, target_class)?;
+    Ok((target_class, name, method_type))
+}
 /*
 fn resolved_class<'gc, 'l>(jvm: &'gc JVMState<'gc>, int_state: &mut JavaInterpreterFrame<'gc,'l>, cp: u16) -> Result<Option<(Arc<RuntimeClass<'gc>>, MethodName, CMethodDescriptor)>, WasException<'gc>> {
     let view = int_state.current_class_view(jvm);
@@ -6,11 +6,14 @@ use libc::c_void;
 use classfile_view::view::HasAccessFlags;
 use classfile_view::view::method_view::MethodView;
 use runtime_class_stuff::RuntimeClass;
+use rust_jvm_common::compressed_classfile::class_names::CClassName;
+use rust_jvm_common::compressed_classfile::method_names::MethodName;
 
 use crate::{JVMState, NewAsObjectOrJavaValue, NewJavaValue, WasException};
 use crate::better_java_stack::frames::{HasFrame, PushableFrame};
 use crate::better_java_stack::native_frame::NativeFrame;
 use crate::class_loading::{assert_inited_or_initing_class, check_initing_or_inited_class};
+use crate::class_unloading::unload_unreachable_class_loaders;
 use crate::interpreter::common::invoke::native::mhn_temp::{Java_java_lang_invoke_MethodHandleNatives_getMembers, Java_java_lang_invoke_MethodHandleNatives_objectFieldOffset, MHN_getConstant};
 use crate::interpreter::common::invoke::native::mhn_temp::init::MHN_init;
 use crate::interpreter::common::invoke::native::mhn_temp::resolve::MHN_resolve;
@@ -71,6 +74,10 @@ pub fn run_native_method<'gc, 'l, 'k>(
             m.lock(jvm, native_frame).unwrap();
         }
         let prev_rip = native_frame.frame_ref().prev_rip();
+        //JVM_GC has no way back to the interpreter state, so unloading happens on the way to it
+        if class.cpdtype() == CClassName::runtime().into() && method.name() == MethodName::method_gc() {
+            unload_unreachable_class_loaders(jvm, &*native_frame);
+        }
         let result: Option<NewJavaValueHandle<'gc>> = if jvm.native_libaries.registered_natives.read().unwrap().contains_key(&ByAddress(class.clone())) && jvm.native_libaries.registered_natives.read().unwrap().get(&ByAddress(class.clone())).unwrap().read().unwrap().contains_key(&(method_i as u16)) {
             //todo dup
             let res_fn = {
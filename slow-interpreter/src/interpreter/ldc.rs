@@ -9,6 +9,7 @@ use rust_jvm_common::compressed_classfile::compressed_types::CPDType;
 use crate::{AllocatedHandle, JVMState, NewAsObjectOrJavaValue, NewJavaValueHandle, WasException};
 use crate::better_java_stack::frames::PushableFrame;
 use crate::class_objects::get_or_create_class_object;
+use crate::interpreter::common::invoke::dynamic::resolve_dynamic_constant;
 use crate::interpreter::PostInstructionAction;
 use crate::interpreter::real_interpreter_state::{InterpreterJavaValue, RealInterpreterStateGuard};
 use crate::stdlib::java::lang::string::JString;
@@ -60,13 +61,22 @@ fn load_class_constant<'gc, 'l>(jvm: &'gc JVMState<'gc>, int_state: &mut impl Pu
 // }
 //
 pub fn ldc2_w<'gc, 'l, 'k>(jvm: &'gc JVMState<'gc>, int_state: &'_ mut RealInterpreterStateGuard<'gc, 'l, 'k>, ldc2w: &CompressedLdc2W) -> PostInstructionAction<'gc> {
-    let mut current_frame = int_state.current_frame_mut();
     match ldc2w {
         CompressedLdc2W::Long(l) => {
-            current_frame.push(InterpreterJavaValue::Long(*l));
+            int_state.current_frame_mut().push(InterpreterJavaValue::Long(*l));
         }
         CompressedLdc2W::Double(d) => {
-            current_frame.push(InterpreterJavaValue::Double(*d));
+            int_state.current_frame_mut().push(InterpreterJavaValue::Double(*d));
+        }
+        CompressedLdc2W::Dynamic { cp, .. } => {
+            match resolve_dynamic_constant(jvm, int_state.inner(), *cp) {
+                Err(WasException { exception_obj }) => {
+                    return PostInstructionAction::Exception { exception: WasException { exception_obj } };
+                }
+                Ok(res) => {
+                    int_state.current_frame_mut().push(res.to_interpreter_jv());
+                }
+            }
         }
     }
     PostInstructionAction::Next {}
@@ -104,6 +114,16 @@ pub fn ldc_w<'gc, 'l, 'k>(jvm: &'gc JVMState<'gc>, int_state: &'_ mut RealInterp
                     let obj = classes_guard.lookup_live_object_pool(live_object_index);
                     int_state.current_frame_mut().push(obj.new_java_value_handle().to_interpreter_jv());
                 }
+                CompressedLdcW::Dynamic { cp, .. } => {
+                    match resolve_dynamic_constant(jvm, int_state.inner(), *cp) {
+                        Err(WasException { exception_obj }) => {
+                            return PostInstructionAction::Exception { exception: WasException { exception_obj } };
+                        }
+                        Ok(res) => {
+                            int_state.current_frame_mut().push(res.to_interpreter_jv());
+                        }
+                    }
+                }
                 _ => {
                     // dbg!(cp);
                     todo!();/*int_state.inner().debug_print_stack_trace(jvm);*/
@@ -129,6 +129,17 @@ pub enum PostInstructionAction<'gc> {
 static mut INDENT:usize = 10;
 
 pub fn run_function_interpreted<'l, 'gc>(jvm: &'gc JVMState<'gc>, interpreter_state: &mut JavaInterpreterFrame<'gc, 'l>) -> Result<Option<NewJavaValueHandle<'gc>>, WasException<'gc>> {
+    run_function_interpreted_from(jvm, interpreter_state, None)
+}
+
+pub struct InterpreterResumePoint {
+    pub pc: ByteCodeOffset,
+    pub stack_depth: u16,
+}
+
+//resume_point is used by deoptimization to continue a frame which was previously running compiled code.
+//frames being resumed already hold their monitor if synchronized
+pub fn run_function_interpreted_from<'l, 'gc>(jvm: &'gc JVMState<'gc>, interpreter_state: &mut JavaInterpreterFrame<'gc, 'l>, resume_point: Option<InterpreterResumePoint>) -> Result<Option<NewJavaValueHandle<'gc>>, WasException<'gc>> {
     // eprintln!("{}",Backtrace::force_capture().to_string());
     let rc = interpreter_state.class_pointer(jvm);
     let method_i = interpreter_state.current_method_i(jvm);
@@ -163,21 +174,27 @@ pub fn run_function_interpreted<'l, 'gc>(jvm: &'gc JVMState<'gc>, interpreter_st
     let resolver = MethodResolverImpl { jvm, loader: interpreter_state.current_loader(jvm) };
     jvm.java_vm_state.add_method_if_needed(jvm, &resolver, method_id, true);
     let function_counter = jvm.function_execution_count.for_function(method_id);
-    let mut current_offset = ByteCodeOffset(0);
-    let mut real_interpreter_state = RealInterpreterStateGuard::new(jvm, interpreter_state);
+    let is_resume = resume_point.is_some();
+    let (mut current_offset, mut real_interpreter_state) = match resume_point {
+        None => (ByteCodeOffset(0), RealInterpreterStateGuard::new(jvm, interpreter_state)),
+        Some(InterpreterResumePoint { pc, stack_depth }) => {
+            assert!(stack_depth <= code.max_stack);
+            (pc, RealInterpreterStateGuard::new_at_stack_depth(jvm, interpreter_state, stack_depth))
+        }
+    };
     let should_sync = if method.is_synchronized() {
-        if method.is_static() {
+        let monitor = if method.is_static() {
             //todo
             let class_obj = jvm.classes.read().unwrap().get_class_obj_from_runtime_class(rc.clone());
-            let monitor = jvm.monitor_for(class_obj.ptr.as_ptr() as *const c_void);
-            monitor.lock(jvm, real_interpreter_state.inner()).unwrap();
-            Some(monitor)
+            jvm.monitor_for(class_obj.ptr.as_ptr() as *const c_void)
         } else {
             let obj = real_interpreter_state.current_frame_mut().local_get(0, RuntimeType::object());
-            let monitor = jvm.monitor_for(obj.unwrap_object().unwrap().as_ptr() as *const c_void);
+            jvm.monitor_for(obj.unwrap_object().unwrap().as_ptr() as *const c_void)
+        };
+        if !is_resume {
             monitor.lock(jvm, real_interpreter_state.inner()).unwrap();
-            Some(monitor)
         }
+        Some(monitor)
     } else {
         None
     };
@@ -148,6 +148,15 @@ impl<'gc, 'l, 'k> RealInterpreterStateGuard<'gc, 'l, 'k> {
         }
     }
 
+    pub fn new_at_stack_depth(jvm: &'gc JVMState<'gc>, interpreter_state: &'k mut JavaInterpreterFrame<'gc, 'l>, stack_depth: u16) -> Self {
+        interpreter_state.set_operand_stack_depth(stack_depth);
+        Self {
+            interpreter_state,
+            jvm,
+            current_stack_depth_from_start: stack_depth,
+        }
+    }
+
     pub fn current_frame_mut(&mut self) -> InterpreterFrame<'gc, 'l, 'k, '_> {
         InterpreterFrame {
             inner: self
@@ -0,0 +1,101 @@
+use std::ffi::c_void;
+use std::ptr::NonNull;
+
+use another_jit_vm::saved_registers_utils::{SavedRegistersWithIPDiff, SavedRegistersWithoutIPDiff};
+use another_jit_vm_ir::compiler::RestartPointID;
+use another_jit_vm_ir::IRVMExitAction;
+use classfile_view::view::ClassView;
+use classfile_view::view::constant_info_view::ConstantInfoView;
+use rust_jvm_common::{ByteCodeOffset, MethodId};
+use rust_jvm_common::compressed_classfile::code::CompressedInstructionInfo;
+
+use crate::{JVMState, WasException};
+use crate::better_java_stack::exit_frame::JavaExitFrame;
+use crate::better_java_stack::FramePointer;
+use crate::better_java_stack::frames::HasFrame;
+use crate::interpreter::{InterpreterResumePoint, run_function_interpreted_from};
+use crate::ir_to_java_layer::exit_impls::throw_impl;
+
+// Compiled and interpreted frames share a layout, and at the start of a bytecode instruction the
+// compiled frame holds exactly the locals and operand stack the interpreter expects. So turning a
+// compiled frame into an interpreter frame only requires knowing the pc and the operand stack depth.
+
+#[inline(never)]
+pub fn deoptimize_exit<'gc, 'k>(jvm: &'gc JVMState<'gc>, int_state: &mut JavaExitFrame<'gc, 'k>, method_id: MethodId, pc: ByteCodeOffset, stack_depth: u16) -> IRVMExitAction {
+    if jvm.exit_tracing_options.tracing_enabled() {
+        eprintln!("Deoptimize");
+    }
+    //compiled code checked an assumption which no longer holds. the method was already invalidated when the
+    //assumption broke, so just finish this frame in the interpreter
+    deoptimize_frame(jvm, int_state, method_id, InterpreterResumePoint { pc, stack_depth })
+}
+
+#[inline(never)]
+pub fn deoptimize_return<'gc, 'k>(jvm: &'gc JVMState<'gc>, int_state: &mut JavaExitFrame<'gc, 'k>, return_value: u64) -> IRVMExitAction {
+    if jvm.exit_tracing_options.tracing_enabled() {
+        eprintln!("DeoptimizeReturn");
+    }
+    let frame_pointer = FramePointer(NonNull::new(int_state.frame_ref().frame_ptr().as_ptr() as *mut c_void).unwrap());
+    let original_return_address = jvm.java_vm_state.take_deoptimize_return_target(frame_pointer);
+    let (method_id, invoke_pc) = jvm.java_vm_state.lookup_return_address(original_return_address);
+    let (rc, method_i) = jvm.method_table.read().unwrap().try_lookup(method_id).unwrap();
+    let view = rc.view();
+    let method_view = view.method_view_i(method_i);
+    let code = method_view.code_attribute().unwrap();
+    let invoke_instr = code.instructions.get(&invoke_pc).unwrap();
+    let returns_value = match &invoke_instr.info {
+        CompressedInstructionInfo::invokevirtual { descriptor, .. } |
+        CompressedInstructionInfo::invokespecial { descriptor, .. } |
+        CompressedInstructionInfo::invokestatic { descriptor, .. } |
+        CompressedInstructionInfo::invokeinterface { descriptor, .. } => {
+            !descriptor.return_type.is_void()
+        }
+        CompressedInstructionInfo::invokedynamic(cp) => {
+            match view.constant_pool_view(*cp as usize) {
+                ConstantInfoView::InvokeDynamic(invoke_dynamic) => !invoke_dynamic.name_and_type().desc_method(&jvm.string_pool).return_type.is_void(),
+                _ => panic!("invokedynamic should reference an InvokeDynamic constant"),
+            }
+        }
+        other => panic!("return addresses are only recorded for invokes, not {:?}", other)
+    };
+    let next_pc = ByteCodeOffset(invoke_pc.0 + invoke_instr.instruction_size);
+    let stack_depth = jvm.java_function_frame_data.read().unwrap().get(&method_id).unwrap().operand_stack_depth_at_offset(next_pc);
+    if returns_value {
+        int_state.to_interpreter_frame(|java_interpreter_frame| {
+            java_interpreter_frame.os_set_from_start_raw(stack_depth - 1, return_value);
+        });
+    }
+    deoptimize_frame(jvm, int_state, method_id, InterpreterResumePoint { pc: next_pc, stack_depth })
+}
+
+pub fn deoptimize_at_restart_point<'gc, 'k>(jvm: &'gc JVMState<'gc>, int_state: &mut JavaExitFrame<'gc, 'k>, method_id: MethodId, restart_point: RestartPointID) -> IRVMExitAction {
+    let ir_method_id = int_state.frame_ref().ir_method_id().unwrap();
+    let pc = jvm.java_vm_state.restart_point_byte_code_offset(ir_method_id, restart_point);
+    let stack_depth = jvm.java_function_frame_data.read().unwrap().get(&method_id).unwrap().operand_stack_depth_at_offset(pc);
+    deoptimize_frame(jvm, int_state, method_id, InterpreterResumePoint { pc, stack_depth })
+}
+
+// runs the rest of the current frame in the interpreter, then returns from it the same way compiled code would
+pub fn deoptimize_frame<'gc, 'k>(jvm: &'gc JVMState<'gc>, int_state: &mut JavaExitFrame<'gc, 'k>, method_id: MethodId, resume_point: InterpreterResumePoint) -> IRVMExitAction {
+    let frame_ref = int_state.frame_ref();
+    assert_eq!(frame_ref.method_id(), Ok(method_id));
+    let return_to_ptr = frame_ref.prev_rip();
+    let return_to_rbp = frame_ref.prev_rbp().unwrap();
+    let current_rbp = frame_ref.frame_ptr();
+    return match int_state.to_interpreter_frame(|java_interpreter_frame| {
+        let res = run_function_interpreted_from(jvm, java_interpreter_frame, Some(resume_point))?;
+        let mut saved_registers_without_ipdiff = SavedRegistersWithoutIPDiff::no_change();
+        saved_registers_without_ipdiff.rax = res.map(|res| res.to_interpreter_jv().to_raw());
+        saved_registers_without_ipdiff.rbp = Some(return_to_rbp.as_ptr() as u64);
+        saved_registers_without_ipdiff.rsp = Some(current_rbp.as_ptr() as u64);
+        let diff = SavedRegistersWithIPDiff { rip: Some(return_to_ptr), saved_registers_without_ip: saved_registers_without_ipdiff };
+        Ok(IRVMExitAction::RestartWithRegisterState { diff })
+    }) {
+        Ok(ir_vm_exit_action) => {
+            ir_vm_exit_action
+        }
+        Err(WasException { exception_obj }) => {
+            throw_impl(jvm, int_state, exception_obj, true)
+        }
+    };
+}
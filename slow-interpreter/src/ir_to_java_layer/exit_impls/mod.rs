@@ -32,7 +32,7 @@ use sketch_jvm_version_of_utf8::wtf8_pool::CompressedWtf8String;
 use stage0::compiler_common::MethodResolver;
 use vtable::{RawNativeVTable, ResolvedVTableEntry, VTable, VTableEntry};
 
-use crate::{check_initing_or_inited_class, JavaValueCommon, JString, JVMState, MethodResolverImpl, NewAsObjectOrJavaValue, NewJavaValueHandle};
+use crate::{check_initing_or_inited_class, JavaValueCommon, JString, JVMState, MethodResolverImpl, NewAsObjectOrJavaValue, NewJavaValueHandle, WasException};
 use crate::better_java_stack::exit_frame::JavaExitFrame;
 use crate::better_java_stack::frames::{HasFrame, PushableFrame};
 use crate::better_java_stack::opaque_frame::OpaqueFrame;
@@ -40,6 +40,8 @@ use crate::class_loading::assert_inited_or_initing_class;
 use crate::interpreter::common::fields::get_static_impl;
 use crate::interpreter::common::invoke::virtual_::virtual_method_lookup;
 use crate::interpreter::common::special::{instance_of_exit_impl, instance_of_exit_impl_impl};
+use crate::interpreter::{InterpreterResumePoint, safepoint_check};
+use crate::ir_to_java_layer::deoptimization::{deoptimize_at_restart_point, deoptimize_frame};
 use crate::ir_to_java_layer::dump_frame::dump_frame_contents;
 use crate::ir_to_java_layer::java_stack::OpaqueFrameIdOrMethodID;
 use crate::java_values::{native_to_new_java_value_rtype};
@@ -231,6 +233,29 @@ pub fn get_static<'gc, 'k>(jvm: &'gc JVMState<'gc>, int_state: &mut JavaExitFram
     IRVMExitAction::RestartAtPtr { ptr: return_to_ptr }
 }
 
+#[inline(never)]
+pub fn safepoint_poll<'gc, 'k>(jvm: &'gc JVMState<'gc>, int_state: &mut JavaExitFrame<'gc, 'k>, return_to_ptr: *const c_void, pc: ByteCodeOffset) -> IRVMExitAction {
+    if jvm.exit_tracing_options.tracing_enabled() {
+        eprintln!("SafePointPoll");
+    }
+    let res = int_state.to_interpreter_frame(|interpreter_frame| {
+        safepoint_check(jvm, interpreter_frame)
+    });
+    match res {
+        Ok(()) => {
+            //a frame spinning in invalidated code would otherwise never return to be deoptimized
+            let ir_method_id = int_state.frame_ref().ir_method_id().unwrap();
+            if jvm.java_vm_state.is_invalidated(ir_method_id) {
+                let method_id = int_state.frame_ref().method_id().unwrap();
+                let stack_depth = jvm.java_function_frame_data.read().unwrap().get(&method_id).unwrap().operand_stack_depth_at_offset(pc);
+                return deoptimize_frame(jvm, int_state, method_id, InterpreterResumePoint { pc, stack_depth });
+            }
+            IRVMExitAction::RestartAtPtr { ptr: return_to_ptr }
+        }
+        Err(WasException { exception_obj }) => throw_impl(jvm, int_state, exception_obj, false),
+    }
+}
+
 #[inline(never)]
 pub fn monitor_exit<'gc, 'k>(jvm: &'gc JVMState<'gc>, int_state: &mut JavaExitFrame<'gc, 'k>, obj_ptr: *const c_void, return_to_ptr: *const c_void) -> IRVMExitAction {
     if jvm.exit_tracing_options.tracing_enabled() {
@@ -449,6 +474,8 @@ pub fn allocate_object<'gc>(jvm: &'gc JVMState<'gc>, current_loader: LoaderName,
         memset(allocated_object.as_ptr(), 0, object_size.get());
     }//todo do correct initing of fields
     unsafe { res_address.write(allocated_object) }
+    drop(memory_region_guard);
+    jvm.thread_state.get_current_thread().record_allocation(object_size.get());
     drop(guard);
     IRVMExitAction::RestartAtPtr { ptr: return_to_ptr }
 }
@@ -516,14 +543,16 @@ pub fn init_class_and_recompile<'gc, 'l>(jvm: &'gc JVMState<'gc>, int_state: &mu
     assert!(jvm.classes.read().unwrap().is_inited_or_initing(&cpdtype).is_some());
     let method_resolver = MethodResolverImpl { jvm, loader: int_state.current_loader(jvm) };
     jvm.java_vm_state.add_method_if_needed(jvm, &method_resolver, current_method_id, false);
-    let restart_point = jvm.java_vm_state.lookup_restart_point(
-        current_method_id,
-        restart_point,
-    );
+    let restart_point_ptr = match jvm.java_vm_state.try_lookup_restart_point(current_method_id, restart_point) {
+        Some(restart_point_ptr) => restart_point_ptr,
+        None => {
+            return deoptimize_at_restart_point(jvm, int_state, current_method_id, restart_point);
+        }
+    };
     if jvm.exit_tracing_options.tracing_enabled() {
         eprintln!("InitClassAndRecompile done");
     }
-    IRVMExitAction::RestartAtPtr { ptr: restart_point }
+    IRVMExitAction::RestartAtPtr { ptr: restart_point_ptr }
 }
 
 #[inline(never)]
@@ -586,6 +615,8 @@ pub fn allocate_object_array<'gc, 'k>(jvm: &'gc JVMState<'gc>, int_state: &mut J
     }//todo init this properly according to type
     unsafe { *allocated_object.cast::<jint>().as_mut() = len }//init the length
     assert!(memory_region_guard.find_object_allocated_type(allocated_object).as_cpdtype().is_array());
+    drop(memory_region_guard);
+    jvm.thread_state.get_current_thread().record_allocation(object_array.size.get());
     IRVMExitAction::RestartAtPtr { ptr: return_to_ptr }
 }
 
@@ -3,16 +3,20 @@ use std::ffi::c_void;
 use std::mem::size_of;
 use std::ptr::NonNull;
 use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicU32, Ordering};
 
 use another_jit_vm::{IRMethodID, Register};
 use another_jit_vm::code_modification::GlobalCodeEditingLock;
+use another_jit_vm_ir::ir_stack::IRFrameRef;
 use another_jit_vm_ir::{ExitHandlerType, HasRBPAndRSP, IRInstructIndex, IRVMExitAction, IRVMExitEvent, IRVMState, RBPAndRSP};
 use another_jit_vm_ir::compiler::{IRInstr, RestartPointID};
 use another_jit_vm_ir::vm_exit_abi::IRVMExitType;
-use gc_memory_layout_common::frame_layout::{FRAME_HEADER_END_OFFSET, FrameHeader, NativeStackframeMemoryLayout};
+use gc_memory_layout_common::frame_layout::{FRAME_HEADER_END_OFFSET, FRAME_HEADER_PREV_RIP_OFFSET, FrameHeader, NativeStackframeMemoryLayout};
 use interface_vtable::ResolvedInterfaceVTableEntry;
+use itertools::Itertools;
 use rust_jvm_common::{ByteCodeOffset, MethodId};
-use stage0::compiler::{compile_to_ir, Labeler, native_to_ir, NeedsRecompileIf};
+use runtime_class_stuff::RuntimeClass;
+use stage0::compiler::{compilable, compile_to_ir, Labeler, native_to_ir, NeedsRecompileIf};
 use stage0::compiler_common::{JavaCompilerMethodAndFrameData, MethodResolver};
 
 use crate::{JVMState, MethodResolverImpl};
@@ -45,6 +49,10 @@ impl<'vm> JavaVMStateWrapper<'vm> {
             inner: RwLock::new(JavaVMStateWrapperInner {
                 most_up_to_date_ir_method_id_for_method_id: Default::default(),
                 methods: Default::default(),
+                deoptimize_return_ir_method_id: None,
+                invalidated_ir_method_ids: Default::default(),
+                deoptimize_return_targets: Default::default(),
+                invalidation_words: Default::default(),
             }),
             labeler: Labeler::new(),
             function_call_targets: RwLock::new(FunctionCallTargetsByFunction::new()),
@@ -62,16 +70,31 @@ impl<'vm> JavaVMStateWrapper<'vm> {
             ir_exit_handler
         });
         self.add_top_level_vm_exit();
+        self.add_deoptimize_return_vm_exit();
     }
 
     pub fn add_top_level_vm_exit(&'vm self) {
         //&IRVMExitEvent, IRStackMut, &IRVMState<'vm, ExtraData>, &mut ExtraData
         let ir_method_id = self.ir.reserve_method_id();
-        let (ir_method_id, restart_points, _) = self.ir.add_function(vec![IRInstr::VMExit2 { exit_type: IRVMExitType::TopLevelReturn {} }], FRAME_HEADER_END_OFFSET, ir_method_id, self.modication_lock.acquire());
+        let (ir_method_id, restart_points, _) = self.ir.add_function(vec![IRInstr::VMExit2 { exit_type: IRVMExitType::TopLevelReturn {} }], FRAME_HEADER_END_OFFSET, ir_method_id, None, self.modication_lock.acquire());
         assert!(restart_points.is_empty());
         self.ir.init_top_level_exit_id(ir_method_id)
     }
 
+    pub fn add_deoptimize_return_vm_exit(&'vm self) {
+        let ir_method_id = self.ir.reserve_method_id();
+        let (ir_method_id, restart_points, _) = self.ir.add_function(vec![IRInstr::VMExit2 { exit_type: IRVMExitType::DeoptimizeReturn {} }], FRAME_HEADER_END_OFFSET, ir_method_id, None, self.modication_lock.acquire());
+        assert!(restart_points.is_empty());
+        let mut write_guard = self.inner.write().unwrap();
+        assert!(write_guard.deoptimize_return_ir_method_id.is_none());
+        write_guard.deoptimize_return_ir_method_id = Some(ir_method_id);
+    }
+
+    pub fn get_deoptimize_return_ir_pointer(&self) -> NonNull<c_void> {
+        let deoptimize_return_ir_method_id = self.inner.read().unwrap().deoptimize_return_ir_method_id.unwrap();
+        self.ir.lookup_ir_method_id_pointer(deoptimize_return_ir_method_id)
+    }
+
     pub fn run_method<'l>(&'vm self, jvm: &'vm JVMState<'vm>, int_state: &mut JavaInterpreterFrame<'vm, 'l>, method_id: MethodId) -> Result<u64, WasException<'vm>> {
         // let (rc, method_i) = jvm.method_table.read().unwrap().try_lookup(method_id).unwrap();
         // let view = rc.view();
@@ -152,11 +175,23 @@ impl<'vm> JavaVMStateWrapper<'vm> {
     }
 
     pub fn lookup_restart_point(&self, method_id: MethodId, restart_point_id: RestartPointID) -> *const c_void {
+        self.try_lookup_restart_point(method_id, restart_point_id).unwrap()
+    }
+
+    // None if the most recent compilation of method_id doesn't have this restart point, e.g. because it is now interpreted
+    pub fn try_lookup_restart_point(&self, method_id: MethodId, restart_point_id: RestartPointID) -> Option<*const c_void> {
         let read_guard = self.inner.read().unwrap();
         let ir_method_id = *read_guard.most_up_to_date_ir_method_id_for_method_id.get(&method_id).unwrap();
-        let ir_instruct_index = read_guard.restart_location(ir_method_id, restart_point_id);
+        let ir_instruct_index = read_guard.try_restart_location(ir_method_id, restart_point_id)?;
         drop(read_guard);
-        self.ir.lookup_location_of_ir_instruct(ir_method_id, ir_instruct_index).0
+        Some(self.ir.lookup_location_of_ir_instruct(ir_method_id, ir_instruct_index).0)
+    }
+
+    pub fn restart_point_byte_code_offset(&self, ir_method_id: IRMethodID, restart_point_id: RestartPointID) -> ByteCodeOffset {
+        let read_guard = self.inner.read().unwrap();
+        let ir_instruct_index = read_guard.restart_location(ir_method_id, restart_point_id);
+        let method = read_guard.java_method_for_ir_method_id(ir_method_id);
+        *method.byte_code_ir_mapping.as_ref().unwrap().ir_index_to_bytecode_pc.get(&ir_instruct_index).unwrap()
     }
 
     pub fn lookup_ip(&self, ip: *const c_void) -> Option<(MethodId, ByteCodeOffset)> {
@@ -165,12 +200,25 @@ impl<'vm> JavaVMStateWrapper<'vm> {
             return None;
         }
         let guard = self.inner.read().unwrap();
+        if Some(ir_method_id) == guard.deoptimize_return_ir_method_id {
+            return None;
+        }
         let method = guard.methods.get(&ir_method_id).unwrap();
         let method_id = method.associated_method_id;
         let pc = *method.byte_code_ir_mapping.as_ref()?.ir_index_to_bytecode_pc.get(&ir_instruct_index).unwrap();
         Some((method_id, pc))
     }
 
+    // like lookup_ip, but for the return address of a call from compiled code, which is looked up in
+    // the return addresses recorded when the call was assembled
+    pub fn lookup_return_address(&self, return_address: *const c_void) -> (MethodId, ByteCodeOffset) {
+        let (ir_method_id, ir_instruct_index) = self.ir.lookup_call_return_address(return_address).expect("not the return address of a call from compiled code");
+        let guard = self.inner.read().unwrap();
+        let method = guard.methods.get(&ir_method_id).unwrap();
+        let pc = *method.byte_code_ir_mapping.as_ref().unwrap().ir_index_to_bytecode_pc.get(&ir_instruct_index).unwrap();
+        (method.associated_method_id, pc)
+    }
+
     pub fn lookup_byte_code_offset(&self, ir_method_id: IRMethodID, java_pc: ByteCodeOffset) -> *const c_void {
         let read_guard = self.inner.read().unwrap();
         let ir_instruct_index = *read_guard.methods.get(&ir_method_id).unwrap().byte_code_ir_mapping.as_ref().unwrap().bytecode_pc_to_start_ir_index.get(&java_pc).unwrap();
@@ -178,6 +226,120 @@ impl<'vm> JavaVMStateWrapper<'vm> {
     }
 }
 
+impl<'vm> JavaVMStateWrapper<'vm> {
+    // Compiled code for method_id can no longer be relied upon. The next invocation recompiles, and
+    // frames currently running the invalidated code are moved to the interpreter at their next
+    // guarded call site or safepoint poll, or when they are returned to.
+    pub fn invalidate_method(&'vm self, jvm: &'vm JVMState<'vm>, method_id: MethodId) {
+        jvm.recompilation_conditions.write().unwrap().invalidate(method_id);
+        let mut write_guard = self.inner.write().unwrap();
+        if let Some(invalidation_word) = write_guard.invalidation_words.remove(&method_id) {
+            invalidation_word.store(1, Ordering::SeqCst);
+        }
+        let ir_method_id = match write_guard.most_up_to_date_ir_method_id_for_method_id.get(&method_id) {
+            None => return,
+            Some(ir_method_id) => *ir_method_id
+        };
+        if !write_guard.invalidated_ir_method_ids.insert(ir_method_id) {
+            return;
+        }
+        drop(write_guard);
+        for java_thread in jvm.thread_state.all_java_threads.read().unwrap().values() {
+            java_thread.safepoint_state.set_deoptimize_requested();
+        }
+    }
+
+    // The class of method_id was unloaded. The machine code stays in the code region, which can't hand
+    // space back, but nothing refers to it any longer.
+    pub fn unload_method(&'vm self, jvm: &'vm JVMState<'vm>, method_id: MethodId) {
+        jvm.recompilation_conditions.write().unwrap().invalidate(method_id);
+        jvm.java_function_frame_data.write().unwrap().remove(&method_id);
+        self.function_call_targets.write().unwrap().remove_targets(method_id);
+        let mut write_guard = self.inner.write().unwrap();
+        let JavaVMStateWrapperInner { most_up_to_date_ir_method_id_for_method_id, methods, invalidated_ir_method_ids, .. } = &mut *write_guard;
+        most_up_to_date_ir_method_id_for_method_id.remove(&method_id);
+        methods.retain(|ir_method_id, method| {
+            if method.associated_method_id != method_id {
+                return true;
+            }
+            invalidated_ir_method_ids.remove(ir_method_id);
+            false
+        });
+    }
+
+    //a newly initialized class may override methods which compiled code assumed had a single implementation
+    pub fn class_hierarchy_changed(&'vm self, jvm: &'vm JVMState<'vm>, class: &Arc<RuntimeClass<'vm>>) {
+        let mut supertypes = vec![];
+        let mut to_visit = vec![class.clone()];
+        while let Some(current) = to_visit.pop() {
+            supertypes.push(current.cpdtype());
+            if let Some(class_class) = current.try_unwrap_class_class() {
+                to_visit.extend(class_class.parent.iter().cloned());
+                to_visit.extend(class_class.interfaces.iter().cloned());
+            }
+        }
+        let invalidated = {
+            let recompilation_conditions = jvm.recompilation_conditions.read().unwrap();
+            recompilation_conditions.devirtualization_dependents(supertypes.as_slice()).into_iter().filter(|method_id| {
+                //receiver types in a method resolve through the loader which defined that method's class
+                let (rc, _) = jvm.method_table.read().unwrap().try_lookup(*method_id).unwrap();
+                let loader = jvm.classes.read().unwrap().get_defining_loader(&rc);
+                recompilation_conditions.devirtualization_invalidated(*method_id, &MethodResolverImpl { jvm, loader })
+            }).collect_vec()
+        };
+        for method_id in invalidated {
+            self.invalidate_method(jvm, method_id);
+        }
+    }
+
+    pub fn invalidation_word_address(&self, method_id: MethodId) -> *const AtomicU32 {
+        let mut write_guard = self.inner.write().unwrap();
+        let invalidation_word: &'static AtomicU32 = *write_guard.invalidation_words.entry(method_id).or_insert_with(|| Box::leak(Box::new(AtomicU32::new(0))));
+        invalidation_word as *const AtomicU32
+    }
+
+    pub fn is_invalidated(&self, ir_method_id: IRMethodID) -> bool {
+        self.inner.read().unwrap().invalidated_ir_method_ids.contains(&ir_method_id)
+    }
+
+    // Walks the current thread's stack and redirects returns into invalidated compiled code to the
+    // deoptimize return exit.
+    pub fn patch_invalidated_return_addresses(&self, current_frame: IRFrameRef) {
+        let deoptimize_return = self.get_deoptimize_return_ir_pointer().as_ptr() as *const c_void;
+        let top_level_return = self.ir.get_top_level_return_ir_pointer().as_ptr() as *const c_void;
+        let ir_stack = current_frame._ir_stack;
+        let mmaped_top = ir_stack.native.mmaped_top;
+        let mut callee = current_frame;
+        while callee.ptr.as_ptr() != mmaped_top.as_ptr() {
+            let prev_rip = callee.prev_rip();
+            let caller_frame_pointer = callee.prev_rbp().unwrap();
+            if prev_rip != deoptimize_return && prev_rip != top_level_return {
+                let (return_ir_method_id, _) = self.ir.lookup_ip(prev_rip);
+                if self.is_invalidated(return_ir_method_id) {
+                    let mut write_guard = self.inner.write().unwrap();
+                    write_guard.deoptimize_return_targets.insert(caller_frame_pointer.as_ptr() as usize, prev_rip as usize);
+                    unsafe { (callee.ptr.as_ptr().sub(FRAME_HEADER_PREV_RIP_OFFSET) as *mut *const c_void).write(deoptimize_return); }
+                }
+            }
+            callee = unsafe { ir_stack.frame_at(caller_frame_pointer.into()) };
+        }
+    }
+
+    pub fn take_deoptimize_return_target(&self, frame_pointer: FramePointer) -> *const c_void {
+        let mut write_guard = self.inner.write().unwrap();
+        write_guard.deoptimize_return_targets.remove(&(frame_pointer.as_ptr() as usize)).unwrap() as *const c_void
+    }
+
+    // return address as it was before being redirected for deoptimization
+    pub fn original_return_address(&self, caller_frame_pointer: FramePointer, return_address: *const c_void) -> *const c_void {
+        let read_guard = self.inner.read().unwrap();
+        if Some(return_address) != read_guard.deoptimize_return_ir_method_id.map(|it| self.ir.lookup_ir_method_id_pointer(it).as_ptr() as *const c_void) {
+            return return_address;
+        }
+        *read_guard.deoptimize_return_targets.get(&(caller_frame_pointer.as_ptr() as usize)).unwrap() as *const c_void
+    }
+}
+
 //todo rework so that we always recompile but sometimes recompile to exit and interpret
 
 impl<'vm> JavaVMStateWrapper<'vm> {
@@ -209,8 +371,12 @@ impl<'vm> JavaVMStateWrapper<'vm> {
                             method_id
                         )
                     });
-                let ir_instructions_and_offsets = if resolver.compile_interpreted(method_id) {
-                    recompile_conditions.add_condition(NeedsRecompileIf::Interpreted { method_id });
+                let is_compilable = compilable(java_frame_data);
+                let ir_instructions_and_offsets = if !is_compilable || resolver.compile_interpreted(method_id) {
+                    //methods which can't be compiled have no recompile conditions, so stay interpreted
+                    if is_compilable {
+                        recompile_conditions.add_condition(NeedsRecompileIf::Interpreted { method_id });
+                    }
                     vec![
                         (ByteCodeOffset(0), IRInstr::IRStart {
                             temp_register: Register(0),
@@ -258,7 +424,7 @@ impl<'vm> JavaVMStateWrapper<'vm> {
                     bytecode_pc_to_start_ir_index,
                 }))
             };
-            let (ir_method_id, restart_points, function_call_targets) = self.ir.add_function(ir_instructions, full_frame_size, reserved_method_id, self.modication_lock.acquire());
+            let (ir_method_id, restart_points, function_call_targets) = self.ir.add_function(ir_instructions, full_frame_size, reserved_method_id, byte_code_ir_mapping.as_ref().map(|mapping| &mapping.ir_index_to_bytecode_pc), self.modication_lock.acquire());
             self.function_call_targets.write().unwrap().sink_targets(function_call_targets);
             let mut write_guard = self.inner.write().unwrap();
             write_guard.most_up_to_date_ir_method_id_for_method_id.insert(method_id, ir_method_id);
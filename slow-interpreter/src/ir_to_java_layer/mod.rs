@@ -1,6 +1,7 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ffi::c_void;
 use std::hash::Hash;
+use std::sync::atomic::AtomicU32;
 
 use another_jit_vm::IRMethodID;
 use another_jit_vm::saved_registers_utils::{SavedRegistersWithIPDiff, SavedRegistersWithoutIPDiff};
@@ -41,6 +42,13 @@ pub struct JavaVMStateMethod {
 pub struct JavaVMStateWrapperInner {
     most_up_to_date_ir_method_id_for_method_id: HashMap<MethodId, IRMethodID>,
     methods: HashMap<IRMethodID, JavaVMStateMethod>,
+    deoptimize_return_ir_method_id: Option<IRMethodID>,
+    invalidated_ir_method_ids: HashSet<IRMethodID>,
+    // frame pointer of frame to be deoptimized on return -> original return address
+    deoptimize_return_targets: HashMap<usize, usize>,
+    // set when the method's most recent compilation is invalidated, and replaced for the next one. Leaked, since
+    // frames still running invalidated code read it.
+    invalidation_words: HashMap<MethodId, &'static AtomicU32>,
 }
 
 impl JavaVMStateWrapperInner {
@@ -53,8 +61,12 @@ impl JavaVMStateWrapperInner {
     }
 
     pub fn restart_location(&self, ir_method_id: IRMethodID, restart_point: RestartPointID) -> IRInstructIndex {
+        self.try_restart_location(ir_method_id, restart_point).unwrap()
+    }
+
+    pub fn try_restart_location(&self, ir_method_id: IRMethodID, restart_point: RestartPointID) -> Option<IRInstructIndex> {
         let restart_points = &self.methods.get(&ir_method_id).unwrap().restart_points;
-        *restart_points.get(&restart_point).unwrap()
+        restart_points.get(&restart_point).cloned()
     }
 }
 
@@ -221,10 +233,20 @@ impl JavaVMStateWrapperInner {
             RuntimeVMExitInput::AllocateObjectArrayIntrinsic { type_, len, return_to_ptr, res_address } => {
                 return exit_impls::allocate_object_array(jvm, int_state.unwrap(), *type_, *len, *return_to_ptr, *res_address);
             }
+            RuntimeVMExitInput::Deoptimize { method_id, stack_depth, pc } => {
+                deoptimization::deoptimize_exit(jvm, int_state.unwrap(), *method_id, *pc, *stack_depth)
+            }
+            RuntimeVMExitInput::DeoptimizeReturn { return_value } => {
+                deoptimization::deoptimize_return(jvm, int_state.unwrap(), *return_value)
+            }
+            RuntimeVMExitInput::SafePointPoll { return_to_ptr, pc } => {
+                exit_impls::safepoint_poll(jvm, int_state.unwrap(), *return_to_ptr, *pc)
+            }
         }
     }
 }
 
 pub mod exit_impls;
+pub mod deoptimization;
 pub mod dump_frame;
 pub mod java_vm_state;
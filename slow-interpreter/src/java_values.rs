@@ -68,6 +68,11 @@ impl<'gc> GC<'gc> {
         }
     }
 
+    //objects held by vm handles, with how many handles hold each
+    pub fn root_counts(&self) -> Vec<(NonNull<c_void>, usize)> {
+        self.vm_temp_owned_roots.read().unwrap().iter().map(|(ptr, count)| (*ptr, count.load(Ordering::SeqCst))).collect()
+    }
+
     pub fn handle_lives_for_gc_life(&'gc self, handle: AllocatedNormalObjectHandle<'gc>) -> &'gc AllocatedNormalObjectHandle<'gc> {
         let index = self.objects_that_live_for_gc_life.len();
         self.objects_that_live_for_gc_life.push(handle);
@@ -92,6 +97,9 @@ impl<'gc> GC<'gc> {
         let (allocated, allocated_size) = guard.allocate_with_size(&allocated_object_type);
         unsafe { libc::memset(allocated.as_ptr(), 0, allocated_size.get()); }
         drop(guard);
+        if let Some(current_thread) = jvm.thread_state.try_get_current_thread() {
+            current_thread.record_allocation(allocated_size.get());
+        }
         jvm.thread_state.debug_assert(jvm);
         let handle = self.register_root_reentrant(jvm, allocated);//should register before putting in all objects so can't be gced
         Self::init_allocated(object, allocated);
@@ -1,7 +1,7 @@
 use std::ffi::c_void;
 use std::ptr::NonNull;
 use std::sync::Arc;
-use std::sync::atomic::AtomicPtr;
+use std::sync::atomic::{AtomicPtr, AtomicU32};
 
 use wtf8::Wtf8Buf;
 
@@ -105,6 +105,34 @@ impl<'gc> MethodResolverImpl<'gc> {
         None
     }
 
+    // all inited or initing non-abstract classes which could be the receiver of a call on `on`
+    fn instantiable_sub_classes(&self, on: CPDType) -> Option<Vec<Arc<RuntimeClass<'gc>>>> {
+        let classes_guard = self.jvm.classes.read().unwrap();
+        let on_rc = classes_guard.is_inited_or_initing(&on)?;
+        let candidates = match on_rc.unwrap_class_class().class_id_path.as_ref() {
+            Some(class_id_path) if !on_rc.view().is_interface() => {
+                let mut candidates = vec![on_rc.clone()];
+                for sub_class_id in self.jvm.inheritance_tree.sub_class_ids(&class_id_path.clone().into()) {
+                    let sub_class_type = self.jvm.class_ids.lookup(sub_class_id);
+                    if let Some(sub_class) = classes_guard.is_inited_or_initing(&sub_class_type) {
+                        candidates.push(sub_class);
+                    }
+                }
+                candidates
+            }
+            _ => {
+                classes_guard.implementors.get(&on).into_iter().flatten()
+                    .filter(|rc| classes_guard.is_inited_or_initing(&rc.cpdtype()).is_some())
+                    .cloned()
+                    .collect()
+            }
+        };
+        Some(candidates.into_iter().filter(|rc| {
+            let view = rc.view();
+            !view.is_abstract() && !view.is_interface()
+        }).collect())
+    }
+
     fn lookup_method_number_recurse(&self, rc: &RuntimeClassClass, method_shape: MethodShape) -> MethodNumber {
         *match rc.method_numbers.get(&method_shape) {
             Some(x) => x,
@@ -175,6 +203,32 @@ impl<'gc> MethodResolver<'gc> for MethodResolverImpl<'gc> {
         }
     }
 
+    fn lookup_unique_implementation(&self, on: CPDType, method_shape: &MethodShape) -> Option<(MethodId, bool)> {
+        if on.is_array() {
+            return None;
+        }
+        let class_name = on.unwrap_class_type();
+        if class_name == CClassName::method_handle() {
+            //signature polymorphic
+            return None;
+        }
+        let mut res = None;
+        for sub_class in self.instantiable_sub_classes(on)? {
+            let (method_id, is_native) = self.lookup_special_impl(method_shape.name, &method_shape.desc, sub_class)?;
+            let (rc, method_i) = self.jvm.method_table.read().unwrap().try_lookup(method_id).unwrap();
+            if rc.view().method_view_i(method_i).is_abstract() {
+                //will throw AbstractMethodError, leave that to the regular path
+                return None;
+            }
+            match res {
+                None => res = Some((method_id, is_native)),
+                Some((existing, _)) if existing == method_id => {}
+                Some(_) => return None,
+            }
+        }
+        res
+    }
+
     fn lookup_interface_id(&self, interface: CPDType) -> Option<InterfaceID> {
         let classes_guard = self.jvm.classes.read().unwrap();
         let (_, rc) = classes_guard.get_loader_and_runtime_class(&interface)?;
@@ -222,6 +276,14 @@ impl<'gc> MethodResolver<'gc> for MethodResolverImpl<'gc> {
         self.jvm.gc.memory_region.lock().unwrap().get_region_header_raw_ptr(id)
     }
 
+    fn safepoint_poll_address(&self) -> *const AtomicU32 {
+        self.jvm.thread_state.safepoint_poll_address()
+    }
+
+    fn invalidation_word_address(&self, method_id: MethodId) -> *const AtomicU32 {
+        self.jvm.java_vm_state.invalidation_word_address(method_id)
+    }
+
     fn lookup_method_layout(&self, method_id: usize) -> YetAnotherLayoutImpl {
         let (rc, method_i) = self.jvm.method_table.read().unwrap().try_lookup(method_id).unwrap();
         let view = rc.view();
@@ -24,6 +24,8 @@ use interface_vtable::ITables;
 use interface_vtable::lookup_cache::InvokeInterfaceLookupCache;
 use jvmti_jni_bindings::{jint, jlong, JNI_VERSION_1_1, jobject};
 use jvmti_jni_bindings::invoke_interface::JNIInvokeInterfaceNamedReservedPointers;
+use jvm_args::assertions::AssertionStatus;
+use jvm_args::ShareMode;
 use method_table::interface_table::InterfaceTable;
 use method_table::MethodTable;
 use perf_metrics::PerfMetrics;
@@ -37,7 +39,7 @@ use rust_jvm_common::compressed_classfile::code::LiveObjectIndex;
 use rust_jvm_common::compressed_classfile::compressed_types::{CPDType, CPRefType};
 use rust_jvm_common::compressed_classfile::string_pool::CompressedClassfileStringPool;
 use rust_jvm_common::cpdtype_table::CPDTypeTable;
-use rust_jvm_common::loading::{ClassLoadingError, LivePoolGetter, LoaderIndex, LoaderName};
+use rust_jvm_common::loading::{LivePoolGetter, LoaderIndex, LoaderName};
 use rust_jvm_common::method_shape::{MethodShape, MethodShapeIDs, ShapeOrderWrapperOwned};
 use rust_jvm_common::opaque_id_table::OpaqueIDs;
 use rust_jvm_common::vtype::VType;
@@ -50,15 +52,17 @@ use verification::verifier::Frame;
 use vtable::lookup_cache::InvokeVirtualLookupCache;
 use vtable::VTables;
 
-use crate::{AllocatedHandle, NewAsObjectOrJavaValue, UnAllocatedObject};
+use crate::{AllocatedHandle, NewAsObjectOrJavaValue, NewJavaValueHandle, UnAllocatedObject};
 use crate::better_java_stack::frames::PushableFrame;
 use crate::better_java_stack::opaque_frame::OpaqueFrame;
+use crate::class_data_sharing::SharedArchive;
 use crate::class_loading::{ClassIntrinsicsData, DefaultClassfileGetter, DefaultLivePoolGetter};
 use crate::field_table::FieldTable;
 use crate::function_instruction_count::FunctionInstructionExecutionCount;
 use crate::ir_to_java_layer::java_vm_state::JavaVMStateWrapper;
 use crate::java_values::{ByAddressAllocatedObject, GC, JavaValue};
 use crate::leaked_interface_arrays::InterfaceArrays;
+use crate::loader_constraints::{LoaderConstraints, LoaderConstraintViolation};
 use crate::loading::Classpath;
 use crate::native_allocation::NativeAllocator;
 use crate::new_java_values::allocated_objects::{AllocatedNormalObjectHandle, AllocatedObjectHandleByAddress};
@@ -70,6 +74,7 @@ use crate::rust_jni::mangling::ManglingRegex;
 use crate::rust_jni::PerStackInterfaces;
 use crate::stdlib::java::lang::class_loader::ClassLoader;
 use crate::stdlib::java::lang::stack_trace_element::StackTraceElement;
+use crate::stdlib::java::lang::throwable::Throwable;
 use crate::string_exit_cache::StringExitCache;
 use crate::threading::safepoints::Monitor2;
 use crate::threading::thread_state::ThreadState;
@@ -81,10 +86,21 @@ pub struct JVMConfig {
     pub compiled_mode_active: bool,
     pub store_generated_classes: bool,
     pub debug_print_exceptions: bool,
-    pub assertions_enabled: bool,
+    pub assertion_status: AssertionStatus,
     pub tracing: TracingSettings,
     pub main_class_name: CClassName,
     pub compile_threshold: u64,
+    //-Xms and -Xmx are reported through Runtime, but the heap isn't limited by them yet.
+    pub initial_heap_size: Option<u64>,
+    pub max_heap_size: Option<u64>,
+    //-Xss, the native stack size of java threads
+    pub thread_stack_size: Option<u64>,
+    pub verify_bootstrap_classes: bool,
+    pub verify_user_classes: bool,
+    pub share: ShareMode,
+    //written when dumping, the archive in use is JVMState::shared_archive
+    pub shared_archive_file: PathBuf,
+    pub shared_class_list_file: PathBuf,
 }
 
 thread_local!(pub static CURRENT_THREAD_INVOKE_INTERFACE: RefCell<Option<*const JNIInvokeInterfaceNamedReservedPointers>> = RefCell::new(None));
@@ -146,6 +162,7 @@ pub struct JVMState<'gc> {
     pub all_the_static_fields: AllTheStaticFields<'gc>,
     pub java_home: PathBuf,
     pub boot_classpath: Vec<PathBuf>,
+    pub shared_archive: Option<SharedArchive>,
 }
 
 
@@ -161,6 +178,17 @@ pub struct Classes<'gc> {
     pub protection_domains: BiMap<ByAddress<Arc<RuntimeClass<'gc>>>, ByAddressAllocatedObject<'gc>>,
     pub class_class_view: Arc<ClassBackedView>,
     pub object_view: Arc<ClassBackedView>,
+    //classes absent here were defined by the bootstrap loader
+    pub defining_loaders: HashMap<ByAddress<Arc<RuntimeClass<'gc>>>, LoaderName>,
+    pub loader_constraints: LoaderConstraints<'gc>,
+    pub unloaded_class_count: u64,
+    //loader indices are never reused, so unloaded loaders still count towards the next index
+    pub unloaded_class_loader_count: u32,
+    //keyed by constant pool index, holds the value of each CONSTANT_Dynamic entry whose bootstrap method has returned,
+    //or the exception its resolution failed with
+    pub resolved_dynamic_constants: HashMap<ByAddress<Arc<RuntimeClass<'gc>>>, HashMap<u16, Result<NewJavaValueHandle<'gc>, Throwable<'gc>>>>,
+    //the loaded non-interface classes implementing each interface, directly or through super classes and interfaces
+    pub implementors: HashMap<CPDType, Vec<Arc<RuntimeClass<'gc>>>>,
 }
 
 impl<'gc> Classes<'gc> {
@@ -175,7 +203,18 @@ impl<'gc> Classes<'gc> {
         self.loaded_classes_by_type.iter().flat_map(|(l, rc)| rc.keys().map(move |ptype| (*l, ptype.clone()))).collect_vec()
     }
 
-    pub fn is_loaded(&self, ptype: &CPDType) -> Option<Arc<RuntimeClass<'gc>>> {
+    //each class counts once, however many loaders initiated it
+    pub fn loaded_class_count(&self) -> usize {
+        self.loaded_classes_by_type.values().flat_map(|classes| classes.values()).map(|class| ByAddress(class.clone())).unique().count()
+    }
+
+    //each loader has its own namespace, so a class is only loaded for the loaders which initiated it
+    pub fn is_loaded(&self, loader: LoaderName, ptype: &CPDType) -> Option<Arc<RuntimeClass<'gc>>> {
+        self.loaded_classes_by_type.get(&loader)?.get(ptype).cloned()
+    }
+
+    //the class loaded for ptype by whichever loader first initiated it
+    pub fn is_loaded_by_any(&self, ptype: &CPDType) -> Option<Arc<RuntimeClass<'gc>>> {
         self.initiating_loaders.get(&ptype)?.1.clone().into()
     }
 
@@ -204,6 +243,26 @@ impl<'gc> Classes<'gc> {
         *res
     }
 
+    pub fn get_defining_loader(&self, class_: &Arc<RuntimeClass<'gc>>) -> LoaderName {
+        self.defining_loaders.get(&ByAddress(class_.clone())).copied().unwrap_or(LoaderName::BootstrapLoader)
+    }
+
+    /// Makes `loader` an initiating loader of `class`, unless that would violate a loader constraint.
+    pub fn record_initiating_loader(&mut self, loader: LoaderName, class: Arc<RuntimeClass<'gc>>) -> Result<(), LoaderConstraintViolation> {
+        if let Some(class_name) = class.cpdtype().try_unwrap_class_type() {
+            self.loader_constraints.check_class_loaded(class_name, loader, &class)?;
+        }
+        //keeps the first initiating loader, later ones are only recorded in their own namespace
+        self.initiating_loaders.entry(class.cpdtype()).or_insert((loader, class.clone()));
+        self.loaded_classes_by_type.entry(loader).or_default().insert(class.cpdtype(), class);
+        Ok(())
+    }
+
+    pub fn add_loader_constraint(&mut self, class_name: CClassName, first: LoaderName, second: LoaderName) -> Result<(), LoaderConstraintViolation> {
+        let loaded_classes_by_type = &self.loaded_classes_by_type;
+        self.loader_constraints.add_constraint(class_name, first, second, |loader| loaded_classes_by_type.get(&loader)?.get(&class_name.into()).cloned())
+    }
+
     pub fn get_class_obj(&self, ptypeview: CPDType, loader: Option<LoaderName>) -> Option<AllocatedNormalObjectHandle<'gc>> {
         if loader.is_some() {
             todo!()
@@ -250,13 +309,13 @@ impl<'gc> Classes<'gc> {
     }
 
     pub fn lookup_or_add_classloader(&mut self, obj: AllocatedNormalObjectHandle<'gc>) -> LoaderName {
+        let unloaded_class_loader_count = self.unloaded_class_loader_count;
         let loaders_guard = &mut self.class_loaders;
         let loader_index_lookup = loaders_guard.get_by_right(&ByAddressAllocatedObject::LookupOnly(obj.raw_ptr_usize()));
         LoaderName::UserDefinedLoader(match loader_index_lookup {
             Some(x) => *x,
             None => {
-                let new_loader_id = LoaderIndex(loaders_guard.len() as u32);
-                assert!(!loaders_guard.contains_left(&new_loader_id));
+                let new_loader_id = new_loader_index(loaders_guard, unloaded_class_loader_count);
                 loaders_guard.insert(new_loader_id, ByAddressAllocatedObject::Owned(obj));
                 //todo this whole mess needs a register class loader function which addes to approprate classes data structure
                 new_loader_id
@@ -271,6 +330,36 @@ impl<'gc> Classes<'gc> {
     pub fn get_loader_and_runtime_class(&self, cpdtype: &CPDType) -> Option<(LoaderName, Arc<RuntimeClass<'gc>>)> {
         Some(self.initiating_loaders.get(cpdtype)?.clone())
     }
+
+    // called once for each newly loaded class
+    pub fn record_implementor(&mut self, class: &Arc<RuntimeClass<'gc>>) {
+        if class.view().is_interface() {
+            return;
+        }
+        let mut interfaces = HashSet::new();
+        implemented_interfaces(class, &mut interfaces);
+        for interface in interfaces {
+            self.implementors.entry(interface).or_default().push(class.clone());
+        }
+    }
+}
+
+fn implemented_interfaces<'gc>(class: &Arc<RuntimeClass<'gc>>, res: &mut HashSet<CPDType>) {
+    let class_class = class.unwrap_class_class();
+    for interface in class_class.interfaces.iter() {
+        if res.insert(interface.cpdtype()) {
+            implemented_interfaces(interface, res);
+        }
+    }
+    if let Some(parent) = class_class.parent.as_ref() {
+        implemented_interfaces(parent, res);
+    }
+}
+
+pub(crate) fn new_loader_index(class_loaders: &BiMap<LoaderIndex, ByAddressAllocatedObject>, unloaded_class_loader_count: u32) -> LoaderIndex {
+    let new_loader_id = LoaderIndex(class_loaders.len() as u32 + unloaded_class_loader_count);
+    assert!(!class_loaders.contains_left(&new_loader_id));
+    new_loader_id
 }
 
 
@@ -279,6 +368,15 @@ impl<'gc> JVMState<'gc> {
         self.boot_classpath.iter().map(|path|path.to_str().unwrap()).join(":")
     }
 
+    // java.class.path, the boot jars are also searched by the classpath but aren't part of it
+    pub fn user_classpath_string(&self) -> String {
+        self.classpath.entries.iter()
+            .map(|entry| entry.path())
+            .filter(|path| !self.boot_classpath.iter().any(|boot_path| boot_path.as_path() == *path))
+            .map(|path| path.to_str().unwrap())
+            .join(":")
+    }
+
     pub fn sink_function_verification_date(&self, verification_types: &HashMap<u16, HashMap<ByteCodeOffset, Frame>>, rc: Arc<RuntimeClass<'gc>>) {
         let mut method_table = self.method_table.write().unwrap();
         let view = rc.view();
@@ -361,6 +459,7 @@ impl<'gc> JVMState<'gc> {
         let mut classes = self.classes.write().unwrap();
         classes.class_object_pool.insert(ByAddressAllocatedObject::Owned(class_object.duplicate_discouraged()), ByAddress(class.clone()));
         classes.loaded_classes_by_type.entry(LoaderName::BootstrapLoader).or_default().insert(class.clone().cpdtype(), class.clone());
+        classes.record_implementor(&class);
     }
 
     pub fn add_class_class_class_object(&'gc self, cpd_type_table: &RwLock<CPDTypeTable>) {
@@ -453,7 +552,10 @@ impl<'gc> JVMState<'gc> {
 }
 
 pub struct JVMTIState {
+    //the first agent. there is only one jvmtiEnv so far, so the event callbacks of every agent end up here
     pub built_in_jdwp: Arc<SharedLibJVMTI>,
+    //every agent, in the order they are loaded
+    pub agents: Vec<Arc<SharedLibJVMTI>>,
     pub break_points: RwLock<HashMap<MethodId, HashSet<ByteCodeOffset>>>,
     pub tags: RwLock<HashMap<jobject, jlong>>,
 }
@@ -549,9 +651,8 @@ impl<'gc> JVMState<'gc> {
         Arc::new(LivePoolGetterImpl { jvm: self })
     }
 
-    pub fn get_class_getter<'l>(&'l self, loader: LoaderName) -> Arc<dyn ClassFileGetter + 'l> {
-        assert_eq!(loader, LoaderName::BootstrapLoader);
-        Arc::new(BootstrapLoaderClassGetter { jvm: self })
+    pub fn get_class_getter<'l>(&'l self) -> Arc<dyn ClassFileGetter + 'l> {
+        Arc::new(DefaultClassfileGetter { jvm: self })
     }
 
     //todo change this to nonnull
@@ -571,17 +672,6 @@ impl<'gc> JVMState<'gc> {
     }
 }
 
-pub struct BootstrapLoaderClassGetter<'vm, 'l> {
-    jvm: &'l JVMState<'vm>,
-}
-
-impl ClassFileGetter for BootstrapLoaderClassGetter<'_, '_> {
-    fn get_classfile(&self, vf_context: &VerifierContext, loader: LoaderName, class: CClassName) -> Result<Arc<dyn ClassView>, ClassLoadingError> {
-        assert_eq!(loader, LoaderName::BootstrapLoader);
-        Ok(Arc::new(ClassBackedView::from(self.jvm.classpath.lookup(&class, &self.jvm.string_pool)?, &self.jvm.string_pool)))
-    }
-}
-
 pub struct StringInternment<'gc> {
     pub strings: HashMap<Vec<u16>, AllocatedHandle<'gc>>,
 }
\ No newline at end of file
@@ -15,11 +15,11 @@
 #![feature(allocator_api)]
 #![feature(print_internals)]
 #![feature(fmt_internals)]
+#![feature(inline_const)]
 
 extern crate alloc;
 extern crate core;
 
-use std::error::Error;
 use std::sync::Arc;
 use std::sync::atomic::Ordering;
 
@@ -58,6 +58,9 @@ pub mod function_call_targets_updating;
 pub mod java_values;
 pub mod utils;
 pub mod class_loading;
+pub mod loader_constraints;
+pub mod class_unloading;
+pub mod class_data_sharing;
 pub mod class_objects;
 pub mod field_table;
 pub mod interpreter;
@@ -87,7 +90,8 @@ pub mod extra_intrinsics;
 pub mod static_vars;
 pub mod accessor_ext;
 
-pub fn run_main<'gc, 'l>(args: Vec<String>, jvm: &'gc JVMState<'gc>, int_state: &mut impl PushableFrame<'gc>) -> Result<(), Box<dyn Error>> {
+// An exception thrown out of main, or initializing the main class, is returned for the main thread to dispatch as uncaught.
+pub fn run_main<'gc, 'l>(args: Vec<String>, jvm: &'gc JVMState<'gc>, int_state: &mut impl PushableFrame<'gc>) -> Result<(), WasException<'gc>> {
     let launcher = Launcher::get_launcher(jvm, int_state).expect("todo");
     let loader_obj = launcher.get_loader(jvm, int_state).expect("todo");
     let main_loader = loader_obj.to_jvm_loader(jvm);
@@ -95,13 +99,7 @@ pub fn run_main<'gc, 'l>(args: Vec<String>, jvm: &'gc JVMState<'gc>, int_state:
     ThreadState::debug_assertions(jvm, int_state, loader_obj);
 
     let main = check_loaded_class_force_loader(jvm, int_state, &jvm.config.main_class_name.clone().into(), main_loader).expect("failed to load main class");
-    let main = match check_initing_or_inited_class(jvm, int_state, main.cpdtype()) {
-        Ok(main) => main,
-        Err(WasException{exception_obj}) => {
-            exception_obj.print_stack_trace(jvm, int_state).expect("exception printing exception");
-            panic!("failed to load main class");
-        },
-    };
+    let main = check_initing_or_inited_class(jvm, int_state, main.cpdtype())?;
     check_loaded_class(jvm, int_state, main.cpdtype()).expect("failed to init main class");
     let main_view = main.view();
     let main_i = locate_main_method(&jvm.string_pool, &main_view);
@@ -115,30 +113,14 @@ pub fn run_main<'gc, 'l>(args: Vec<String>, jvm: &'gc JVMState<'gc>, int_state:
     jvm.program_args_array.set(local_var_array.duplicate_discouraged()).unwrap();
     initial_local_var_array[0] = local_var_array.new_java_value();
     let java_frame_push = StackEntryPush::new_java_frame(jvm, main.clone(), main_i as u16, initial_local_var_array);
-    let _: Result<(), WasException<'gc>> = int_state.push_frame_java(java_frame_push, |java_native| {
+    int_state.push_frame_java(java_frame_push, |java_native| {
         jvm.include_name_field.store(true, Ordering::SeqCst);
-        match run_function(&jvm, java_native) {
-            Ok(_) => {
-                if !jvm.config.compiled_mode_active {
-                    todo!()// int_state.pop_frame(jvm, main_frame_guard, false);
-                }
-
-                return Ok(())
-                // panic!();
-            }
-            Err(WasException { exception_obj }) => {
-                //todo should be allowing catching in main
-                let exception_string = exception_obj.to_string(jvm,java_native).unwrap().unwrap().to_rust_string(jvm);
-                dbg!(exception_string);
-                exception_obj.print_stack_trace(jvm, java_native).unwrap();
-                dbg!("main exited with exception");
-                // dbg!(throwable.to_string(jvm, int_state).unwrap().unwrap().to_rust_string(jvm));
-                // int_state.debug_print_stack_trace(jvm);
-                todo!()
-            }
+        run_function(&jvm, java_native)?;
+        if !jvm.config.compiled_mode_active {
+            todo!()// int_state.pop_frame(jvm, main_frame_guard, false);
         }
-    });
-    Ok(())
+        Ok(())
+    })
 }
 
 fn setup_program_args<'gc, 'l>(jvm: &'gc JVMState<'gc>, int_state: &mut impl PushableFrame<'gc>, args: Vec<String>) -> AllocatedHandle<'gc> {
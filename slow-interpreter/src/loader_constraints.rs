@@ -0,0 +1,214 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use by_address::ByAddress;
+use wtf8::Wtf8Buf;
+
+use classfile_view::view::{ClassView, HasAccessFlags};
+use runtime_class_stuff::RuntimeClass;
+use rust_jvm_common::compressed_classfile::class_names::CClassName;
+use rust_jvm_common::compressed_classfile::compressed_types::{CMethodDescriptor, CPDType};
+use rust_jvm_common::compressed_classfile::method_names::MethodName;
+use rust_jvm_common::compressed_classfile::string_pool::CompressedClassfileStringPool;
+use rust_jvm_common::loading::LoaderName;
+
+use crate::{NewAsObjectOrJavaValue, WasException};
+use crate::better_java_stack::frames::PushableFrame;
+use crate::jvm_state::JVMState;
+use crate::stdlib::java::lang::linkage_error::LinkageError;
+use crate::stdlib::java::lang::string::JString;
+
+// JVMS 5.3.4: loaders which must agree on the class a name refers to.
+struct LoaderConstraint<'gc> {
+    loaders: HashSet<LoaderName>,
+    class: Option<Arc<RuntimeClass<'gc>>>,
+}
+
+#[derive(Debug)]
+pub struct LoaderConstraintViolation {
+    pub class_name: CClassName,
+    pub loader: LoaderName,
+    pub other_loader: LoaderName,
+}
+
+impl LoaderConstraintViolation {
+    pub fn message(&self, string_pool: &CompressedClassfileStringPool) -> String {
+        format!("loader constraint violation: loaders {} and {} have different Class objects for the type {}", self.loader, self.other_loader, self.class_name.0.to_str(string_pool))
+    }
+}
+
+pub struct LoaderConstraints<'gc> {
+    constraints: HashMap<CClassName, Vec<LoaderConstraint<'gc>>>,
+}
+
+impl<'gc> LoaderConstraints<'gc> {
+    pub fn new() -> Self {
+        Self { constraints: HashMap::new() }
+    }
+
+    /// Requires `first` and `second` to resolve `class_name` to the same class. `loaded` gives the class
+    /// a loader has already loaded for the name, if any.
+    pub fn add_constraint(&mut self, class_name: CClassName, first: LoaderName, second: LoaderName, loaded: impl Fn(LoaderName) -> Option<Arc<RuntimeClass<'gc>>>) -> Result<(), LoaderConstraintViolation> {
+        if first == second {
+            return Ok(());
+        }
+        let constraints = self.constraints.entry(class_name).or_default();
+        let is_merged = |constraint: &LoaderConstraint<'gc>| constraint.loaders.contains(&first) || constraint.loaders.contains(&second);
+        let mut loaders = HashSet::from([first, second]);
+        let mut known_classes = vec![];
+        for constraint in constraints.iter().filter(|constraint| is_merged(constraint)) {
+            loaders.extend(constraint.loaders.iter().copied());
+            known_classes.extend(constraint.class.iter().cloned());
+        }
+        known_classes.extend(loaders.iter().flat_map(|loader| loaded(*loader)));
+        let class = known_classes.first().cloned();
+        if let Some(class) = class.as_ref() {
+            if known_classes.iter().any(|known_class| !Arc::ptr_eq(class, known_class)) {
+                return Err(LoaderConstraintViolation { class_name, loader: first, other_loader: second });
+            }
+        }
+        constraints.retain(|constraint| !is_merged(constraint));
+        constraints.push(LoaderConstraint { loaders, class });
+        Ok(())
+    }
+
+    /// Called as `loader` becomes an initiating loader of `class`.
+    pub fn check_class_loaded(&mut self, class_name: CClassName, loader: LoaderName, class: &Arc<RuntimeClass<'gc>>) -> Result<(), LoaderConstraintViolation> {
+        let constraint = match self.constraints.get_mut(&class_name).and_then(|constraints| constraints.iter_mut().find(|constraint| constraint.loaders.contains(&loader))) {
+            None => return Ok(()),
+            Some(constraint) => constraint,
+        };
+        match &constraint.class {
+            Some(constrained_class) if !Arc::ptr_eq(constrained_class, class) => {
+                let other_loader = constraint.loaders.iter().copied().find(|other| *other != loader).unwrap_or(loader);
+                Err(LoaderConstraintViolation { class_name, loader, other_loader })
+            }
+            _ => {
+                constraint.class = Some(class.clone());
+                Ok(())
+            }
+        }
+    }
+
+    /// Drops unloaded loaders from every constraint, and forgets unloaded classes so that the remaining
+    /// loaders can go on to load other classes of the same names.
+    pub fn unload(&mut self, loaders: &HashSet<LoaderName>, unloaded_classes: &HashSet<ByAddress<Arc<RuntimeClass<'gc>>>>) {
+        for constraints in self.constraints.values_mut() {
+            for constraint in constraints.iter_mut() {
+                constraint.loaders.retain(|loader| !loaders.contains(loader));
+                if constraint.class.as_ref().map_or(false, |class| unloaded_classes.contains(&ByAddress(class.clone()))) {
+                    constraint.class = None;
+                }
+            }
+            constraints.retain(|constraint| constraint.loaders.len() > 1);
+        }
+        self.constraints.retain(|_, constraints| !constraints.is_empty());
+    }
+}
+
+pub fn throw_loader_constraint_violation<'gc, T>(jvm: &'gc JVMState<'gc>, int_state: &mut impl PushableFrame<'gc>, violation: LoaderConstraintViolation) -> Result<T, WasException<'gc>> {
+    let message = JString::from_rust(jvm, int_state, Wtf8Buf::from_string(violation.message(&jvm.string_pool)))?;
+    let exception_obj = LinkageError::new(jvm, int_state, message)?.full_object().cast_throwable();
+    Err(WasException { exception_obj })
+}
+
+/// JVMS 5.4.5: a method overriding one from a class with a different defining loader constrains both
+/// loaders to agree on every class named in the method's descriptor.
+pub fn impose_overriding_constraints<'gc>(jvm: &'gc JVMState<'gc>, int_state: &mut impl PushableFrame<'gc>, runtime_class: &Arc<RuntimeClass<'gc>>, defining_loader: LoaderName) -> Result<(), WasException<'gc>> {
+    let view = runtime_class.view();
+    let mut supers = vec![];
+    collect_supers(runtime_class, &mut supers);
+    for method in view.methods() {
+        let name = method.name();
+        if method.is_static() || method.is_private() || name == MethodName::constructor_init() || name == MethodName::constructor_clinit() {
+            continue;
+        }
+        for super_class in supers.iter() {
+            if super_class.view().lookup_method(name, method.desc()).is_none() {
+                continue;
+            }
+            let super_loader = jvm.classes.read().unwrap().get_defining_loader(super_class);
+            if super_loader == defining_loader {
+                continue;
+            }
+            for class_name in descriptor_class_names(method.desc()) {
+                let res = jvm.classes.write().unwrap().add_loader_constraint(class_name, defining_loader, super_loader);
+                if let Err(violation) = res {
+                    return throw_loader_constraint_violation(jvm, int_state, violation);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn collect_supers<'gc>(runtime_class: &Arc<RuntimeClass<'gc>>, res: &mut Vec<Arc<RuntimeClass<'gc>>>) {
+    let class_class = runtime_class.unwrap_class_class();
+    for super_class in class_class.parent.iter().chain(class_class.interfaces.iter()) {
+        if res.iter().any(|seen| Arc::ptr_eq(seen, super_class)) {
+            continue;
+        }
+        res.push(super_class.clone());
+        collect_supers(super_class, res);
+    }
+}
+
+fn descriptor_class_names(desc: &CMethodDescriptor) -> Vec<CClassName> {
+    desc.arg_types.iter().chain(std::iter::once(&desc.return_type))
+        .filter_map(|ptype| match ptype {
+            CPDType::Array { base_type, .. } => base_type.to_cpdtype().try_unwrap_class_type(),
+            ptype => ptype.try_unwrap_class_type(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::sync::Arc;
+
+    use runtime_class_stuff::RuntimeClass;
+    use rust_jvm_common::compressed_classfile::class_names::CClassName;
+    use rust_jvm_common::loading::{LoaderIndex, LoaderName};
+
+    use crate::loader_constraints::LoaderConstraints;
+
+    fn loader(idx: u32) -> LoaderName {
+        LoaderName::UserDefinedLoader(LoaderIndex(idx))
+    }
+
+    #[test]
+    pub fn test_add_constraint() {
+        let class_name = CClassName::object();
+        let class: Arc<RuntimeClass> = Arc::new(RuntimeClass::Int);
+        let other_class: Arc<RuntimeClass> = Arc::new(RuntimeClass::Int);
+        let mut constraints = LoaderConstraints::new();
+        assert!(constraints.add_constraint(class_name, loader(0), loader(0), |_| Some(other_class.clone())).is_ok());
+        assert!(constraints.add_constraint(class_name, loader(0), loader(1), |loader_name| (loader_name == loader(0)).then(|| class.clone())).is_ok());
+        //loader 2 joins the constraint through loader 1, so must agree with loader 0
+        let violation = constraints.add_constraint(class_name, loader(1), loader(2), |loader_name| (loader_name == loader(2)).then(|| other_class.clone())).unwrap_err();
+        assert_eq!(violation.loader, loader(1));
+        assert_eq!(violation.other_loader, loader(2));
+        assert!(constraints.add_constraint(class_name, loader(1), loader(2), |loader_name| (loader_name == loader(2)).then(|| class.clone())).is_ok());
+        //loaders which have already loaded different classes of a name can't be constrained on it
+        assert!(constraints.add_constraint(CClassName::string(), loader(0), loader(2), |loader_name| Some(if loader_name == loader(0) { class.clone() } else { other_class.clone() })).is_err());
+        assert!(constraints.add_constraint(CClassName::string(), loader(0), loader(2), |_| None).is_ok());
+    }
+
+    #[test]
+    pub fn test_check_class_loaded() {
+        let class_name = CClassName::object();
+        let class: Arc<RuntimeClass> = Arc::new(RuntimeClass::Int);
+        let other_class: Arc<RuntimeClass> = Arc::new(RuntimeClass::Int);
+        let mut constraints = LoaderConstraints::new();
+        assert!(constraints.check_class_loaded(class_name, loader(0), &class).is_ok());
+        constraints.add_constraint(class_name, loader(0), loader(1), |_| None).unwrap();
+        //the first load under the constraint fixes the class
+        assert!(constraints.check_class_loaded(class_name, loader(0), &class).is_ok());
+        assert!(constraints.check_class_loaded(class_name, loader(1), &class).is_ok());
+        let violation = constraints.check_class_loaded(class_name, loader(1), &other_class).unwrap_err();
+        assert_eq!(violation.loader, loader(1));
+        assert_eq!(violation.other_loader, loader(0));
+        assert!(constraints.check_class_loaded(class_name, loader(2), &other_class).is_ok());
+        assert!(constraints.check_class_loaded(CClassName::string(), loader(1), &other_class).is_ok());
+    }
+}
@@ -1,25 +1,56 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 
+use itertools::Itertools;
+
 use classfile_parser::parse_class_file;
 use jar_manipulation::JarHandle;
+use jimage::JImage;
 use rust_jvm_common::classfile::Classfile;
 use rust_jvm_common::classnames::ClassName;
 use rust_jvm_common::compressed_classfile::class_names::CClassName;
 use rust_jvm_common::compressed_classfile::string_pool::CompressedClassfileStringPool;
 
 
-use rust_jvm_common::loading::ClassLoadingError;
+use rust_jvm_common::loading::{ClassfileParsingError, ClassLoadingError};
 use rust_jvm_common::loading::ClassLoadingError::ClassNotFoundException;
 
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ClasspathEntry {
+    Directory(Box<Path>),
+    Jar(Box<Path>),
+    //the lib/modules runtime image of a jdk 9+ java home
+    JImage(Box<Path>),
+}
+
+impl ClasspathEntry {
+    pub fn path(&self) -> &Path {
+        match self {
+            ClasspathEntry::Directory(path) => path,
+            ClasspathEntry::Jar(path) => path,
+            ClasspathEntry::JImage(path) => path,
+        }
+    }
+}
+
+fn is_jar(path: &Path) -> bool {
+    path.extension().map(|extension| extension.eq_ignore_ascii_case("jar") || extension.eq_ignore_ascii_case("zip")).unwrap_or(false)
+}
+
+// like hotspot's ClassLoader::is_modules_image, a directory called modules is still a directory
+fn is_jimage(path: &Path) -> bool {
+    path.file_name().map(|file_name| file_name == "modules").unwrap_or(false) && path.is_file()
+}
+
 #[derive(Debug)]
 pub struct Classpath {
-    //base directories to search for a file in.
-    pub classpath_base: Vec<Box<Path>>,
+    //searched in order, the first entry containing a class wins.
+    pub entries: Vec<ClasspathEntry>,
     jar_cache: RwLock<HashMap<Box<Path>, Box<JarHandle<File>>>>,
-    pub class_cache: RwLock<HashMap<CClassName, Arc<Classfile>>>, //todo deal with multiple entries with same name
+    jimage_cache: RwLock<HashMap<Box<Path>, Arc<JImage>>>,
+    pub class_cache: RwLock<HashMap<CClassName, Arc<Classfile>>>,
 }
 
 impl Classpath {
@@ -38,71 +69,300 @@ impl Classpath {
     }
 
     pub fn lookup_cache_miss(&self, class_name: &CClassName, pool: &CompressedClassfileStringPool) -> Result<Arc<Classfile>, ClassLoadingError> {
-        for x in &self.classpath_base {
-            for dir_member in match x.read_dir() {
-                Ok(dir) => dir,
-                Err(_) => continue, //java ignores invalid classpath entries
-            } {
-                let dir_member = match dir_member {
-                    Ok(dir_member) => dir_member,
-                    Err(_) => continue,
-                };
-                let is_jar = dir_member.path().extension().map(|x| &x.to_string_lossy() == "jar").unwrap_or(false);
-                if is_jar {
+        self.lookup_by_name(class_name.0.to_str(pool).as_str())
+    }
+
+    // `class_name` is an internal name like java/lang/Object.
+    fn lookup_by_name(&self, class_name: &str) -> Result<Arc<Classfile>, ClassLoadingError> {
+        let class_file_name = format!("{}.class", class_name);
+        for entry in &self.entries {
+            match entry {
+                ClasspathEntry::Directory(dir) => {
+                    let class_path = dir.join(class_file_name.as_str());
+                    if class_path.is_file() {
+                        let file_read = &mut match File::open(class_path) {
+                            Ok(file_read) => file_read,
+                            Err(_) => continue,
+                        };
+                        let classfile = parse_class_file(file_read)?;
+                        return Ok(Arc::new(classfile));
+                    }
+                }
+                ClasspathEntry::Jar(jar_path) => {
+                    if !jar_path.is_file() {
+                        continue; //java ignores classpath entries which don't exist
+                    }
                     let mut cache_write_guard = self.jar_cache.write().unwrap();
-                    let boxed_path = dir_member.path().into_boxed_path();
-                    if cache_write_guard.get(&boxed_path).is_none() {
-                        cache_write_guard.insert(boxed_path.clone(), Box::new(match JarHandle::new(boxed_path.clone()) {
-                            Ok(x) => x,
-                            Err(err) => {
-                                dbg!(err);
-                                dbg!(boxed_path);
-                                todo!()
-                            },
-                        }));
+                    let jar = Self::open_jar(&mut cache_write_guard, jar_path)?;
+                    match jar.lookup_by_name(class_name) {
+                        Ok(Some(classfile)) => return Ok(classfile),
+                        Ok(None) => {}
+                        Err(err) => {
+                            return Err(match err.downcast::<ClassfileParsingError>() {
+                                Ok(parsing_error) => ClassLoadingError::ClassFileInvalid(*parsing_error),
+                                Err(err) => ClassLoadingError::JarInvalid(jar_path.to_path_buf(), err.to_string()),
+                            });
+                        }
+                    }
+                }
+                ClasspathEntry::JImage(jimage_path) => {
+                    if !jimage_path.is_file() {
+                        continue;
+                    }
+                    let jimage = self.open_jimage(jimage_path)?;
+                    match jimage.lookup_class(class_name) {
+                        Ok(Some(bytes)) => return Ok(Arc::new(parse_class_file(&mut bytes.as_slice())?)),
+                        Ok(None) => {}
+                        Err(err) => return Err(ClassLoadingError::JImageInvalid(jimage_path.to_path_buf(), err.to_string())),
                     }
                 }
             }
         }
-        let mut cache_read_guard = self.jar_cache.write().unwrap();
-        for jar in cache_read_guard.values_mut() {
-            if let Ok(c) = jar.lookup(pool, class_name) {
-                return Result::Ok(c);
+        Result::Err(ClassNotFoundException(ClassName::Str(class_name.to_string())))
+    }
+
+    // The entry a class is found in, for -verbose:class.
+    pub fn source(&self, class_name: &CClassName, pool: &CompressedClassfileStringPool) -> Option<&Path> {
+        let class_file_name = format!("{}.class", class_name.0.to_str(pool));
+        self.entries.iter().find(|entry| match entry {
+            ClasspathEntry::Directory(dir) => dir.join(class_file_name.as_str()).is_file(),
+            ClasspathEntry::Jar(jar_path) => {
+                let mut cache_write_guard = self.jar_cache.write().unwrap();
+                match Self::open_jar(&mut cache_write_guard, jar_path) {
+                    Ok(jar) => jar.zip_archive.by_name(class_file_name.as_str()).is_ok(),
+                    Err(_) => false,
+                }
             }
+            ClasspathEntry::JImage(jimage_path) => {
+                match self.open_jimage(jimage_path) {
+                    Ok(jimage) => matches!(jimage.class_location(class_name.0.to_str(pool).as_str()), Ok(Some(_))),
+                    Err(_) => false,
+                }
+            }
+        }).map(|entry| entry.path())
+    }
+
+    fn open_jimage(&self, jimage_path: &Path) -> Result<Arc<JImage>, ClassLoadingError> {
+        let mut jimage_cache = self.jimage_cache.write().unwrap();
+        if let Some(jimage) = jimage_cache.get(jimage_path) {
+            return Ok(jimage.clone());
         }
-        for path in &self.classpath_base {
-            let mut new_path = path.clone().into_path_buf();
-            new_path.push(format!("{}.class", class_name.0.to_str(pool)));
-            if new_path.is_file() {
-                let file_read = &mut File::open(new_path).unwrap();
-                let classfile = parse_class_file(file_read)?;
-                return Result::Ok(Arc::new(classfile));
+        let jimage = Arc::new(JImage::open(jimage_path.into()).map_err(|err| ClassLoadingError::JImageInvalid(jimage_path.to_path_buf(), err.to_string()))?);
+        jimage_cache.insert(jimage_path.into(), jimage.clone());
+        Ok(jimage)
+    }
+
+    fn open_jar<'cache>(jar_cache: &'cache mut HashMap<Box<Path>, Box<JarHandle<File>>>, jar_path: &Path) -> Result<&'cache mut JarHandle<File>, ClassLoadingError> {
+        if !jar_cache.contains_key(jar_path) {
+            let jar = JarHandle::new(jar_path.into()).map_err(|err| ClassLoadingError::JarInvalid(jar_path.to_path_buf(), err.to_string()))?;
+            jar_cache.insert(jar_path.into(), Box::new(jar));
+        }
+        Ok(jar_cache.get_mut(jar_path).unwrap())
+    }
+
+    // A trailing `*` means every jar directly inside that directory, like the java launcher. Jars
+    // listed in a jar's manifest Class-Path come straight after that jar, and each entry is only
+    // searched the first time it appears.
+    fn expand(paths: Vec<Box<Path>>, jar_cache: &mut HashMap<Box<Path>, Box<JarHandle<File>>>) -> Vec<ClasspathEntry> {
+        let mut res = vec![];
+        let mut seen = HashSet::new();
+        for path in paths {
+            if path.file_name().map(|file_name| file_name == "*").unwrap_or(false) {
+                let dir = path.parent().map(|parent| parent.to_path_buf()).unwrap_or_else(|| PathBuf::from("."));
+                let jars = match dir.read_dir() {
+                    Ok(dir) => dir.flatten().map(|dir_member| dir_member.path()).filter(|member| is_jar(member)).sorted().collect_vec(),
+                    Err(_) => continue, //java ignores invalid classpath entries
+                };
+                for jar in jars {
+                    Self::add_entry(&mut res, &mut seen, jar_cache, jar.into_boxed_path());
+                }
+            } else {
+                Self::add_entry(&mut res, &mut seen, jar_cache, path);
             }
         }
-        Result::Err(ClassNotFoundException(ClassName::Str(class_name.0.to_str(&pool))))
+        res
     }
 
-    pub fn from_dirs(dirs: Vec<Box<Path>>) -> Self {
-        Self {
-            classpath_base: dirs,
-            jar_cache: RwLock::new(HashMap::new()),
-            class_cache: Default::default(),
+    fn add_entry(res: &mut Vec<ClasspathEntry>, seen: &mut HashSet<Box<Path>>, jar_cache: &mut HashMap<Box<Path>, Box<JarHandle<File>>>, path: Box<Path>) {
+        if !seen.insert(path.clone()) {
+            return;
+        }
+        if is_jimage(&path) {
+            res.push(ClasspathEntry::JImage(path));
+            return;
+        }
+        if !is_jar(&path) {
+            res.push(ClasspathEntry::Directory(path));
+            return;
+        }
+        res.push(ClasspathEntry::Jar(path.clone()));
+        if !path.is_file() {
+            return;
+        }
+        //a corrupt jar is reported when a lookup first reaches it
+        let manifest = match Self::open_jar(jar_cache, &path).ok().and_then(|jar| jar.manifest().ok().flatten()) {
+            Some(manifest) => manifest,
+            None => return,
+        };
+        let jar_dir = path.parent().map(|parent| parent.to_path_buf()).unwrap_or_default();
+        for url in manifest.class_path() {
+            //only relative file urls are supported
+            let relative = url.strip_prefix("file:").unwrap_or(url.as_str());
+            Self::add_entry(res, seen, jar_cache, jar_dir.join(relative).into_boxed_path());
         }
     }
 
-    pub fn from_dirs_with_cache(dirs: Vec<Box<Path>>, class_cache: HashMap<CClassName, Arc<Classfile>>) -> Self {
+    pub fn from_paths(paths: Vec<Box<Path>>) -> Self {
+        Self::from_paths_with_cache(paths, HashMap::new())
+    }
+
+    pub fn from_paths_with_cache(paths: Vec<Box<Path>>, class_cache: HashMap<CClassName, Arc<Classfile>>) -> Self {
+        let mut jar_cache = HashMap::new();
+        let entries = Self::expand(paths, &mut jar_cache);
         Self {
-            classpath_base: dirs,
-            jar_cache: RwLock::new(HashMap::new()),
+            entries,
+            jar_cache: RwLock::new(jar_cache),
+            jimage_cache: RwLock::new(HashMap::new()),
             class_cache: RwLock::new(class_cache),
         }
     }
 
     pub fn classpath_string(&self) -> String {
-        let mut res = String::new();
-        for p in &self.classpath_base {
-            res.push_str(format!("{}:", p.to_str().unwrap()).as_str());
+        self.entries.iter().map(|entry| entry.path().to_str().unwrap()).join(":")
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::fs::File;
+    use std::io::Write;
+    use std::path::{Path, PathBuf};
+
+    use zip::CompressionMethod;
+    use zip::write::FileOptions;
+
+    use jimage::writer::ImageBuilder;
+    use rust_jvm_common::loading::ClassLoadingError;
+
+    use crate::loading::{Classpath, ClasspathEntry};
+
+    //an empty class, told apart from others of the same name by its minor version
+    fn class_bytes(name: &str, minor_version: u16) -> Vec<u8> {
+        let mut res = vec![0xCA, 0xFE, 0xBA, 0xBE];
+        for word in [minor_version, 52, 5] {
+            res.extend(word.to_be_bytes());
+        }
+        for (name_index, name) in [(1u16, name), (3, "java/lang/Object")] {
+            res.push(1);
+            res.extend((name.len() as u16).to_be_bytes());
+            res.extend(name.as_bytes());
+            res.push(7);
+            res.extend(name_index.to_be_bytes());
+        }
+        //access flags, this, super, then no interfaces, fields, methods or attributes
+        for word in [0x21, 2, 4, 0, 0, 0, 0u16] {
+            res.extend(word.to_be_bytes());
         }
         res
     }
-}
\ No newline at end of file
+
+    //a jar of the given classes, with a manifest if there is a Class-Path
+    fn write_jar(path: &Path, classes: &[(&str, u16)], class_path: Option<&str>) {
+        let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+        let options = FileOptions::default().compression_method(CompressionMethod::Stored);
+        if let Some(class_path) = class_path {
+            zip.start_file("META-INF/MANIFEST.MF", options).unwrap();
+            write!(zip, "Manifest-Version: 1.0\r\nClass-Path: {}\r\n", class_path).unwrap();
+        }
+        for (name, minor_version) in classes {
+            zip.start_file(format!("{}.class", name), options).unwrap();
+            zip.write_all(class_bytes(name, *minor_version).as_slice()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    fn jar_entries(paths: &[PathBuf]) -> Vec<ClasspathEntry> {
+        paths.iter().map(|path| ClasspathEntry::Jar(path.clone().into_boxed_path())).collect()
+    }
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("classpath-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    pub fn test_jimage() {
+        let java_home = test_dir("jimage");
+        std::fs::create_dir_all(java_home.join("lib")).unwrap();
+        let modules = java_home.join("lib/modules");
+        let mut builder = ImageBuilder::new();
+        builder.package("java.lang", "java.base");
+        builder.resource("/java.base/java/lang/Object.class", class_bytes("java/lang/Object", 1).as_slice());
+        builder.write(&modules).unwrap();
+        let classpath = Classpath::from_paths(vec![modules.clone().into_boxed_path()]);
+        assert_eq!(classpath.entries, vec![ClasspathEntry::JImage(modules.into_boxed_path())]);
+        assert_eq!(classpath.lookup_by_name("java/lang/Object").unwrap().minor_version, 1);
+        assert!(classpath.lookup_by_name("java/lang/Missing").is_err());
+        std::fs::remove_dir_all(java_home).unwrap();
+    }
+
+    #[test]
+    pub fn test_first_match_wins() {
+        let dir = test_dir("first-match");
+        let first = dir.join("first.jar");
+        let second = dir.join("second.jar");
+        write_jar(&first, &[("A", 1)], None);
+        write_jar(&second, &[("A", 2), ("B", 3)], None);
+        let classpath = Classpath::from_paths(vec![first.into_boxed_path(), second.into_boxed_path()]);
+        assert_eq!(classpath.lookup_by_name("A").unwrap().minor_version, 1);
+        assert_eq!(classpath.lookup_by_name("B").unwrap().minor_version, 3);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    pub fn test_wildcard_order() {
+        let dir = test_dir("wildcard");
+        for name in ["c.jar", "a.jar", "b.zip"] {
+            write_jar(&dir.join(name), &[("A", 1)], None);
+        }
+        std::fs::write(dir.join("readme.txt"), b"not a jar").unwrap();
+        //a jar already reached through the wildcard is not searched again
+        let classpath = Classpath::from_paths(vec![dir.join("*").into_boxed_path(), dir.join("a.jar").into_boxed_path()]);
+        assert_eq!(classpath.entries, jar_entries(&[dir.join("a.jar"), dir.join("b.zip"), dir.join("c.jar")]));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    pub fn test_manifest_class_path() {
+        let dir = test_dir("manifest");
+        std::fs::create_dir_all(dir.join("lib")).unwrap();
+        let main = dir.join("main.jar");
+        let dependency = dir.join("lib/dependency.jar");
+        let other = dir.join("other.jar");
+        write_jar(&main, &[("Main", 1)], Some("lib/dependency.jar"));
+        write_jar(&dependency, &[("A", 2)], None);
+        write_jar(&other, &[("A", 3)], None);
+        let classpath = Classpath::from_paths(vec![main.clone().into_boxed_path(), other.clone().into_boxed_path()]);
+        assert_eq!(classpath.entries, jar_entries(&[main, dependency, other]));
+        assert_eq!(classpath.lookup_by_name("A").unwrap().minor_version, 2);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    pub fn test_corrupt_jar() {
+        let dir = test_dir("corrupt");
+        let good = dir.join("good.jar");
+        let corrupt = dir.join("corrupt.jar");
+        write_jar(&good, &[("A", 1)], None);
+        std::fs::write(&corrupt, b"not a zip file").unwrap();
+        let classpath = Classpath::from_paths(vec![good.into_boxed_path(), corrupt.clone().into_boxed_path()]);
+        assert_eq!(classpath.lookup_by_name("A").unwrap().minor_version, 1);
+        match classpath.lookup_by_name("B") {
+            Err(ClassLoadingError::JarInvalid(path, _)) => assert_eq!(path, corrupt),
+            res => panic!("expected JarInvalid, got {:?}", res),
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,10 +1,15 @@
 use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
 use std::iter::FromIterator;
-use std::path::{PathBuf};
+use std::path::{Path, PathBuf};
 
 use itertools::Itertools;
 
-use jvm_args::JVMArgs;
+use jar_manipulation::JarHandle;
+use jvm_args::{AgentLibrary, AgentOption, AssertionDirective, JVMArgs, ShareMode, VerifyMode};
+use jvm_args::assertions::AssertionStatus;
 use rust_jvm_common::classnames::ClassName;
 use rust_jvm_common::MethodId;
 
@@ -16,6 +21,12 @@ pub struct SharedLibraryPaths {
     pub libjdwp: PathBuf,
 }
 
+pub struct AgentToLoad {
+    pub path: PathBuf,
+    //passed to Agent_OnLoad, the options after the `=` in -agentlib:name=options
+    pub options: Option<String>,
+}
+
 pub struct JVMOptions {
     pub main_class_name: ClassName,
     pub classpath: Classpath,
@@ -27,12 +38,25 @@ pub struct JVMOptions {
     pub unittest_mode: bool,
     pub store_generated_classes: bool,
     pub debug_print_exceptions: bool,
-    pub assertions_enabled: bool,
+    pub assertion_status: AssertionStatus,
     pub instruction_trace_options: InstructionTraceOptions,
     pub exit_trace_options: ExitTracingOptions,
     pub thread_tracing_options: ThreadTracingOptions,
     pub java_home: PathBuf,
     pub boot_classpath: Vec<PathBuf>,
+    pub compile_threshold: u64,
+    //loaded in command line order. if jvmti is enabled without any, shared_libs.libjdwp is loaded instead
+    pub agents: Vec<AgentToLoad>,
+    pub initial_heap_size: Option<u64>,
+    pub max_heap_size: Option<u64>,
+    pub thread_stack_size: Option<u64>,
+    pub verbose_class: bool,
+    //whether classes failing verification are rejected, they are run through the verifier either way for their frame types
+    pub verify_bootstrap_classes: bool,
+    pub verify_user_classes: bool,
+    pub share: ShareMode,
+    pub shared_archive_file: PathBuf,
+    pub shared_class_list_file: PathBuf,
 }
 
 pub struct JVMOptionsStart {
@@ -43,15 +67,26 @@ pub struct JVMOptionsStart {
     ext_classpath: Vec<PathBuf>,
     properties: Vec<(String, String)>,
     args: Vec<String>,
-    enable_assertions: bool,
+    assertion_directives: Vec<AssertionDirective>,
     store_anon_class: bool,
     debug_print_exceptions: bool,
+    compile_threshold: u64,
+    agents: Vec<AgentOption>,
+    initial_heap_size: Option<u64>,
+    max_heap_size: Option<u64>,
+    thread_stack_size: Option<u64>,
+    verbose_class: bool,
+    verify: VerifyMode,
+    verify_bootstrap_classes: bool,
+    share: ShareMode,
+    shared_archive_file: PathBuf,
+    shared_class_list_file: PathBuf,
 }
 
 impl JVMOptionsStart {
     pub fn classpath_format() -> impl Iterator<Item=&'static str> {
-        // basically from hotspot/src/share/vm/runtime/os.cpp
-        vec!["lib/resources.jar", "lib/rt.jar", "lib/sunrsasign.jar", "lib/jsse.jar", "lib/jce.jar", "lib/charsets.jar", "lib/jfr.jar", "classes"].into_iter()
+        // basically from hotspot/src/share/vm/runtime/os.cpp, plus the lib/modules image which replaces all of these from jdk 9
+        vec!["lib/modules", "lib/resources.jar", "lib/rt.jar", "lib/sunrsasign.jar", "lib/jsse.jar", "lib/jce.jar", "lib/charsets.jar", "lib/jfr.jar", "classes"].into_iter()
     }
 
     pub fn ext_classpath_format() -> impl Iterator<Item=&'static str> {
@@ -59,32 +94,58 @@ impl JVMOptionsStart {
         vec!["/lib/ext"].into_iter()
     }
 
-    pub fn from_java_home(java_home: PathBuf, parsed: JVMArgs) -> JVMOptionsStart {
+    pub fn from_java_home(java_home: PathBuf, parsed: JVMArgs) -> Result<JVMOptionsStart, JarLaunchError> {
         let JVMArgs {
             java_home,
             classpath,
+            jar,
             main,
             properties,
             args,
             enable_assertions,
             debug_exceptions,
-            store_anon_class
+            store_anon_class,
+            compile_threshold,
+            verify,
+            verify_bootstrap_classes,
+            share,
+            shared_archive_file,
+            shared_class_list_file,
+            assertion_directives,
+            initial_heap_size,
+            max_heap_size,
+            thread_stack_size,
+            verbose_class,
+            agents,
         } = parsed.clone();
+        //--enable-assertions is a leading -ea
+        let assertion_directives = enable_assertions.then_some(AssertionDirective::All(true)).into_iter().chain(assertion_directives).collect_vec();
+        //like java -jar, the jar is the whole user classpath, its manifest Class-Path is added when the classpath is searched
+        let (main, classpath) = match jar {
+            Some(jar) => (main_class_from_jar(&jar)?, vec![jar]),
+            //-Xshare:dump exits before a main class would run
+            None if share == ShareMode::Dump => (main.unwrap_or_default(), classpath),
+            None => (main.unwrap(), classpath),
+        };
         let classpath = Self::classpath_format()
             .map(|classpath_elem| java_home.join(classpath_elem))
             .filter(|elem|elem.exists())
-            .chain(classpath.into_iter().map(PathBuf::from))
+            //each --classpath value can itself be a `:` separated list, as with java -cp
+            .chain(classpath.iter().flat_map(|classpath_elem| std::env::split_paths(classpath_elem)))
             .collect_vec();
         let boot_classpath = Self::classpath_format()
             .map(|classpath_elem| java_home.join(classpath_elem))
             .filter(|elem|elem.exists())
             .collect_vec();
 
+        let shared_archive_file = shared_archive_file.unwrap_or_else(|| java_home.join("lib/rust-jvm-classes.jsa"));
+        let shared_class_list_file = shared_class_list_file.unwrap_or_else(|| java_home.join("lib/classlist"));
+
         let ext_classpath = Self::ext_classpath_format()
             .map(|classpath_elem| java_home.join(classpath_elem))
             .collect_vec();
 
-        JVMOptionsStart {
+        Ok(JVMOptionsStart {
             main,
             java_home,
             classpath,
@@ -92,13 +153,61 @@ impl JVMOptionsStart {
             ext_classpath,
             properties,
             args,
-            enable_assertions,
+            assertion_directives,
             store_anon_class,
             debug_print_exceptions: debug_exceptions,
+            compile_threshold,
+            agents,
+            initial_heap_size,
+            max_heap_size,
+            thread_stack_size,
+            verbose_class,
+            verify,
+            verify_bootstrap_classes,
+            share,
+            shared_archive_file,
+            shared_class_list_file,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum JarLaunchError {
+    InvalidJar(PathBuf),
+    NoMainClass(PathBuf),
+}
+
+impl Display for JarLaunchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        //the same messages as the java launcher
+        match self {
+            JarLaunchError::InvalidJar(jar) => write!(f, "Error: Invalid or corrupt jarfile {}", jar.display()),
+            JarLaunchError::NoMainClass(jar) => write!(f, "no main manifest attribute, in {}", jar.display()),
         }
     }
 }
 
+impl Error for JarLaunchError {}
+
+//jdk 8 keeps native libraries in lib/amd64, modular jdks directly in lib
+fn native_library_dir(java_home: &Path) -> PathBuf {
+    let arch_dir = java_home.join("lib/amd64");
+    if arch_dir.is_dir() {
+        arch_dir
+    } else {
+        java_home.join("lib")
+    }
+}
+
+fn main_class_from_jar(jar: &Path) -> Result<String, JarLaunchError> {
+    let mut jar_handle = JarHandle::new(jar.into()).map_err(|_| JarLaunchError::InvalidJar(jar.to_path_buf()))?;
+    let manifest = jar_handle.manifest().map_err(|_| JarLaunchError::InvalidJar(jar.to_path_buf()))?;
+    match manifest.as_ref().and_then(|manifest| manifest.attribute("Main-Class")) {
+        Some(main_class) => Ok(main_class.trim().to_string()),
+        None => Err(JarLaunchError::NoMainClass(jar.to_path_buf())),
+    }
+}
+
 pub struct ThreadTracingOptions {
     pub trace_monitor_wait_enter: bool,
     pub trace_monitor_wait_exit: bool,
@@ -164,24 +273,44 @@ impl InstructionTraceOptions {
 
 impl JVMOptions {
     pub fn from_options_start(options_start: JVMOptionsStart) -> JVMOptions {
-        let JVMOptionsStart { main, java_home, classpath, boot_classpath, ext_classpath, properties, args, enable_assertions, store_anon_class, debug_print_exceptions } = options_start;
-        let classpath = Classpath::from_dirs(classpath.into_iter().map(|path|path.into_boxed_path()).collect_vec());
-        Self::new(
+        let JVMOptionsStart { main, java_home, classpath, boot_classpath, ext_classpath, properties, args, assertion_directives, store_anon_class, debug_print_exceptions, compile_threshold, agents, initial_heap_size, max_heap_size, thread_stack_size, verbose_class, verify, verify_bootstrap_classes, share, shared_archive_file, shared_class_list_file } = options_start;
+        let classpath = Classpath::from_paths(classpath.into_iter().map(|path|path.into_boxed_path()).collect_vec());
+        let native_library_dir = native_library_dir(&java_home);
+        let agents = agents.into_iter().map(|AgentOption { library, options }| {
+            let path = match library {
+                AgentLibrary::Name(name) => native_library_dir.join(format!("lib{}.so", name)),
+                AgentLibrary::Path(path) => path,
+            };
+            AgentToLoad { path, options }
+        }).collect_vec();
+        let mut res = Self::new(
             ClassName::Str(main.replace('.', "/")),
             java_home.clone(),
             classpath,
             boot_classpath,
             args,
-            java_home.join("lib/amd64/libjava.so"),
-            java_home.join("lib/amd64/libjdwp.so"),
-            false,
+            native_library_dir.join("libjava.so"),
+            native_library_dir.join("libjdwp.so"),
             false,
+            !agents.is_empty(),
             properties,
             false,
             store_anon_class,
             debug_print_exceptions,
-            enable_assertions,
-        )
+            AssertionStatus::from_directives(assertion_directives.as_slice()),
+            compile_threshold,
+        );
+        res.agents = agents;
+        res.initial_heap_size = initial_heap_size;
+        res.max_heap_size = max_heap_size;
+        res.thread_stack_size = thread_stack_size;
+        res.verbose_class = verbose_class;
+        res.verify_bootstrap_classes = verify == VerifyMode::All || verify_bootstrap_classes;
+        res.verify_user_classes = verify != VerifyMode::None;
+        res.share = share;
+        res.shared_archive_file = shared_archive_file;
+        res.shared_class_list_file = shared_class_list_file;
+        res
     }
 
     pub fn new(
@@ -198,7 +327,8 @@ impl JVMOptions {
         unittest_mode: bool,
         store_generated_classes: bool,
         debug_print_exceptions: bool,
-        assertions_enabled: bool,
+        assertion_status: AssertionStatus,
+        compile_threshold: u64,
     ) -> Self {
         let trace_set = HashSet::from_iter(vec![
             //     /* MethodToTrace {
@@ -268,12 +398,23 @@ impl JVMOptions {
             unittest_mode,
             store_generated_classes,
             debug_print_exceptions,
-            assertions_enabled,
+            assertion_status,
             instruction_trace_options: trace_options,
             exit_trace_options: ExitTracingOptions::TraceNone,
             thread_tracing_options,
+            shared_archive_file: java_home.join("lib/rust-jvm-classes.jsa"),
+            shared_class_list_file: java_home.join("lib/classlist"),
             java_home,
             boot_classpath,
+            compile_threshold,
+            agents: vec![],
+            initial_heap_size: None,
+            max_heap_size: None,
+            thread_stack_size: None,
+            verbose_class: false,
+            verify_bootstrap_classes: false,
+            verify_user_classes: true,
+            share: ShareMode::Auto,
         }
     }
 
@@ -281,7 +422,7 @@ impl JVMOptions {
     pub fn test_options() -> JVMOptions {
         JVMOptions {
             main_class_name: ClassName::Str("Main".to_string()),
-            classpath: Classpath::from_dirs(vec![]),
+            classpath: Classpath::from_paths(vec![]),
             args: vec![],
             shared_libs: SharedLibraryPaths { libjava: Default::default(), libjdwp: Default::default() },
             enable_tracing: false,
@@ -290,12 +431,84 @@ impl JVMOptions {
             unittest_mode: false,
             store_generated_classes: false,
             debug_print_exceptions: false,
-            assertions_enabled: false,
+            assertion_status: AssertionStatus::default(),
             instruction_trace_options: InstructionTraceOptions::TraceNone,
             exit_trace_options: ExitTracingOptions::TraceNone,
             thread_tracing_options: todo!(),
             java_home: todo!(),
             boot_classpath: todo!(),
+            compile_threshold: 1000,
+            agents: vec![],
+            initial_heap_size: None,
+            max_heap_size: None,
+            thread_stack_size: None,
+            verbose_class: false,
+            verify_bootstrap_classes: false,
+            verify_user_classes: true,
+            share: ShareMode::Off,
+            shared_archive_file: todo!(),
+            shared_class_list_file: todo!(),
         }
     }
 }
+
+#[cfg(test)]
+pub mod test {
+    use std::fs::File;
+    use std::io::Write;
+    use std::path::{Path, PathBuf};
+
+    use zip::CompressionMethod;
+    use zip::write::FileOptions;
+
+    use crate::options::{JarLaunchError, main_class_from_jar, native_library_dir};
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("options-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_jar(path: &Path, manifest: Option<&str>) {
+        let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+        let options = FileOptions::default().compression_method(CompressionMethod::Stored);
+        if let Some(manifest) = manifest {
+            zip.start_file("META-INF/MANIFEST.MF", options).unwrap();
+            zip.write_all(manifest.as_bytes()).unwrap();
+        }
+        zip.start_file("Main.class", options).unwrap();
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    pub fn test_main_class_from_jar() {
+        let dir = test_dir("main-class");
+        let jar = dir.join("main.jar");
+        write_jar(&jar, Some("Manifest-Version: 1.0\r\nMain-Class: com.example.Main \r\n"));
+        assert_eq!(main_class_from_jar(&jar).unwrap(), "com.example.Main");
+        let no_manifest = dir.join("no-manifest.jar");
+        write_jar(&no_manifest, None);
+        assert!(matches!(main_class_from_jar(&no_manifest), Err(JarLaunchError::NoMainClass(path)) if path == no_manifest));
+        let no_main_class = dir.join("no-main-class.jar");
+        write_jar(&no_main_class, Some("Manifest-Version: 1.0\r\nClass-Path: lib.jar\r\n"));
+        assert!(matches!(main_class_from_jar(&no_main_class), Err(JarLaunchError::NoMainClass(path)) if path == no_main_class));
+        let corrupt = dir.join("corrupt.jar");
+        std::fs::write(&corrupt, b"not a zip file").unwrap();
+        assert!(matches!(main_class_from_jar(&corrupt), Err(JarLaunchError::InvalidJar(path)) if path == corrupt));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    pub fn test_native_library_dir() {
+        let jdk8 = test_dir("jdk8");
+        std::fs::create_dir_all(jdk8.join("lib/amd64")).unwrap();
+        assert_eq!(native_library_dir(&jdk8), jdk8.join("lib/amd64"));
+        let modular = test_dir("modular");
+        std::fs::create_dir_all(modular.join("lib")).unwrap();
+        std::fs::write(modular.join("lib/modules"), b"").unwrap();
+        assert_eq!(native_library_dir(&modular), modular.join("lib"));
+        std::fs::remove_dir_all(jdk8).unwrap();
+        std::fs::remove_dir_all(modular).unwrap();
+    }
+}
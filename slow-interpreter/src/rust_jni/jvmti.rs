@@ -3,14 +3,18 @@ use std::mem::size_of;
 use std::ops::Deref;
 use std::os::raw::c_char;
 use std::path::PathBuf;
+use std::ptr::null_mut;
 use std::sync::{Arc, RwLock};
 use libloading::Library;
-use jvmti_jni_bindings::{_jmethodID, _jobject, JavaVM, jboolean, jclass, jint, jmethodID, JNIEnv, JNIInvokeInterface_, JNINativeInterface_, jthread, jvmtiEnv, jvmtiError, jvmtiError_JVMTI_ERROR_NONE, jvmtiEventBreakpoint, jvmtiEventCallbacks, jvmtiEventClassLoad, jvmtiEventClassPrepare, jvmtiEventException, jvmtiEventExceptionCatch, jvmtiEventFieldAccess, jvmtiEventFieldModification, jvmtiEventFramePop, jvmtiEventGarbageCollectionFinish, jvmtiEventMethodEntry, jvmtiEventMethodExit, jvmtiEventMonitorContendedEnter, jvmtiEventMonitorContendedEntered, jvmtiEventMonitorWait, jvmtiEventMonitorWaited, jvmtiEventSingleStep, jvmtiEventThreadEnd, jvmtiEventThreadStart, jvmtiEventVMDeath, jvmtiEventVMInit, jvmtiInterface_1_};
+use jvmti_jni_bindings::{_jmethodID, _jobject, JavaVM, jboolean, jclass, jint, jmethodID, JNI_OK, JNI_VERSION_1_8, JNIEnv, JNIInvokeInterface_, JNINativeInterface_, jthread, jvmtiEnv, jvmtiError, jvmtiError_JVMTI_ERROR_NONE, jvmtiEventBreakpoint, jvmtiEventCallbacks, jvmtiEventClassLoad, jvmtiEventClassPrepare, jvmtiEventException, jvmtiEventExceptionCatch, jvmtiEventFieldAccess, jvmtiEventFieldModification, jvmtiEventFramePop, jvmtiEventGarbageCollectionFinish, jvmtiEventMethodEntry, jvmtiEventMethodExit, jvmtiEventMonitorContendedEnter, jvmtiEventMonitorContendedEntered, jvmtiEventMonitorWait, jvmtiEventMonitorWaited, jvmtiEventSingleStep, jvmtiEventThreadEnd, jvmtiEventThreadStart, jvmtiEventVMDeath, jvmtiEventVMInit, jvmtiInterface_1_};
+use jvmti_jni_bindings::invoke_interface::JNIInvokeInterfaceNamedReservedPointers;
 use rust_jvm_common::MethodId;
 use crate::better_java_stack::native_frame::NativeFrame;
 use crate::{JVMState, OpaqueFrame, PushableFrame};
+use crate::better_java_stack::frames::HasFrame;
 use crate::better_java_stack::java_stack_guard::JavaStackGuard;
 use crate::rust_jni::dlopen::RTLD_NOW;
+use crate::rust_jni::invoke_interface::get_invoke_interface_new;
 use crate::stdlib::java::lang::thread::JThread;
 use crate::threading::java_thread::JavaThread;
 use crate::tracing::TracingSettings;
@@ -52,6 +56,9 @@ impl Default for ThreadJVMTIEnabledStatus {
 //todo technically speaking the RwLock needs to be less fine grain b/c setting callbacks is meant to be atomic
 pub struct SharedLibJVMTI {
     lib: Arc<Library>,
+    path: PathBuf,
+    //the options after the `=` in -agentlib:name=options
+    options: Option<String>,
     vm_init_callback: RwLock<jvmtiEventVMInit>,
     vm_init_enabled: RwLock<bool>,
     vm_death_callback: RwLock<jvmtiEventVMDeath>,
@@ -134,7 +141,7 @@ pub struct ExceptionEvent {
 }
 
 impl SharedLibJVMTI {
-    pub fn vm_inited<'gc, 'l>(&self, jvm: &'gc JVMState<'gc>, int_state: &mut NativeFrame<'gc, 'l>, main_thread: Arc<JavaThread<'gc>>) {
+    pub fn vm_inited<'gc, 'l>(&self, jvm: &'gc JVMState<'gc>, int_state: &mut OpaqueFrame<'gc, 'l>, main_thread: Arc<JavaThread<'gc>>) {
         if *self.vm_init_enabled.read().unwrap() {
             todo!()
             /*let frame_for_event = int_state.push_frame(todo!()/*StackEntryPush::new_completely_opaque_frame(jvm,LoaderName::BootstrapLoader, vec![],"vm_inited")*/);
@@ -146,6 +153,35 @@ impl SharedLibJVMTI {
         }
     }
 
+    pub fn vm_death<'gc, 'l>(&self, jvm: &'gc JVMState<'gc>, opaque_frame: &mut OpaqueFrame<'gc, 'l>) {
+        if !*self.vm_death_enabled.read().unwrap() {
+            return;
+        }
+        let jvmti_interface = get_jvmti_interface(jvm, opaque_frame.java_stack_mut());
+        //the JNIEnv comes from GetEnv on the same invoke interface Agent_OnUnload gets
+        let vm = agent_java_vm(jvm, opaque_frame);
+        unsafe {
+            let get_env = match (**vm).GetEnv {
+                Some(get_env) => get_env,
+                None => return,
+            };
+            let mut jni_env: *mut c_void = null_mut();
+            if get_env(vm, &mut jni_env, JNI_VERSION_1_8 as jint) != JNI_OK as jint {
+                return;
+            }
+            self.VMDeath(jvmti_interface, jni_env as *mut JNIEnv)
+        }
+    }
+
+    // Agent_OnUnload is optional, agents without it have nothing to tear down
+    pub fn agent_unload<'gc, 'l>(&self, jvm: &'gc JVMState<'gc>, opaque_frame: &mut OpaqueFrame<'gc, 'l>) {
+        unsafe {
+            if let Ok(agent_unload_symbol) = self.lib.get::<unsafe extern "C" fn(vm: *mut JavaVM)>("Agent_OnUnload".as_bytes()) {
+                agent_unload_symbol.deref()(agent_java_vm(jvm, opaque_frame));
+            }
+        }
+    }
+
     pub fn thread_start<'gc, 'l>(&self, jvm: &'gc JVMState<'gc>, int_state: &mut OpaqueFrame<'gc, 'l>, jthread: JThread<'gc>) {
         if *self.thread_start_enabled.read().unwrap() {
             todo!();
@@ -275,7 +311,10 @@ impl DebuggerEventConsumer for SharedLibJVMTI {
 
     unsafe fn VMDeath(&self, jvmti_env: *mut *const jvmtiInterface_1_, jni_env: *mut *const JNINativeInterface_) {
         if *self.vm_death_enabled.read().unwrap() {
-            (self.vm_death_callback.read().unwrap().as_ref().unwrap())(jvmti_env, jni_env);
+            //enabling an event doesn't require setting a callback for it
+            if let Some(callback) = self.vm_death_callback.read().unwrap().as_ref() {
+                callback(jvmti_env, jni_env);
+            }
         }
     }
 
@@ -449,23 +488,41 @@ impl DebuggerEventConsumer for SharedLibJVMTI {
     }
 }
 
+// The JavaVM handed to agents. GetEnv on it also hands out jvmti and jmm environments for this thread.
+fn agent_java_vm<'gc, 'l>(jvm: &'gc JVMState<'gc>, opaque_frame: &mut OpaqueFrame<'gc, 'l>) -> *mut JavaVM {
+    let invoke_interface = get_invoke_interface_new(jvm, opaque_frame) as *mut JNIInvokeInterfaceNamedReservedPointers;
+    let interfaces = opaque_frame.java_stack_mut().stack_jni_interface();
+    let other_native_interfaces = (interfaces.jni_inner_mut_raw(), interfaces.jvmti_inner_mut_raw(), interfaces.jmm_inner_mut_raw());
+    unsafe { (*invoke_interface).other_native_interfaces_this_thread = Box::into_raw(Box::new(other_native_interfaces)); }
+    //agents may hold on to the JavaVM for as long as the vm runs
+    Box::leak(Box::new(invoke_interface as *const JNIInvokeInterface_)) as *mut JavaVM
+}
+
 impl SharedLibJVMTI {
-    pub fn agent_load<'gc, 'l>(&self, jvm: &'gc JVMState<'gc>, int_state: &'_ mut impl PushableFrame<'gc>) -> jvmtiError {
+    // Errors are reported like hotspot does, for the caller to exit with.
+    pub fn agent_load<'gc, 'l>(&self, jvm: &'gc JVMState<'gc>, opaque_frame: &mut OpaqueFrame<'gc, 'l>) -> Result<(), String> {
         unsafe {
-            let agent_load_symbol = self.lib.get::<fn(vm: *mut JavaVM, options: *mut c_char, reserved: *mut c_void) -> jint>("Agent_OnLoad".as_bytes()).unwrap();
-            let agent_load_fn_ptr = agent_load_symbol.deref();
-            let args = CString::new("transport=dt_socket,server=y,suspend=y,address=5005").unwrap().into_raw(); //todo parse these at jvm startup
-            let interface: *const JNIInvokeInterface_ = todo!()/*get_invoke_interface(jvm, todo!()/*int_state*/)*/;
-            agent_load_fn_ptr(Box::leak(Box::new(interface)) as *mut *const JNIInvokeInterface_, args, std::ptr::null_mut()) as jvmtiError
-            //todo leak
+            let agent_load_symbol = self.lib.get::<unsafe extern "C" fn(vm: *mut JavaVM, options: *mut c_char, reserved: *mut c_void) -> jint>("Agent_OnLoad".as_bytes())
+                .map_err(|_| format!("Could not find Agent_OnLoad function in the agent library: {}", self.path.display()))?;
+            let options = CString::new(self.options.clone().unwrap_or_default()).map_err(|_| format!("Invalid agent options for {}", self.path.display()))?;
+            //agents may keep the options string
+            let options = options.into_raw();
+            if agent_load_symbol.deref()(agent_java_vm(jvm, opaque_frame), options, null_mut()) != 0 {
+                return Err(format!("agent library failed to init: {}", self.path.display()));
+            }
+            Ok(())
         }
     }
 }
 
 impl SharedLibJVMTI {
-    pub fn load_libjdwp(jdwp_path: &PathBuf) -> SharedLibJVMTI {
-        SharedLibJVMTI {
-            lib: Arc::new(Library::new(jdwp_path, RTLD_NOW as c_int).unwrap()),
+    pub fn load_agent(agent_path: &PathBuf, options: Option<String>) -> Result<SharedLibJVMTI, String> {
+        let lib = Library::new(agent_path, RTLD_NOW as c_int)
+            .map_err(|err| format!("Could not find agent library {} on the library path, with error: {}", agent_path.display(), err))?;
+        Ok(SharedLibJVMTI {
+            lib: Arc::new(lib),
+            path: agent_path.clone(),
+            options,
             vm_init_callback: RwLock::new(None),
             vm_init_enabled: RwLock::new(false),
             vm_death_callback: RwLock::new(None),
@@ -489,7 +546,7 @@ impl SharedLibJVMTI {
             monitor_waited_callback: Default::default(),
             monitor_conteded_enter_callback: Default::default(),
             monitor_conteded_entered_callback: Default::default(),
-        }
+        })
     }
 }
 
@@ -25,6 +25,7 @@ use rust_jvm_common::compressed_classfile::compressed_types::{CMethodDescriptor,
 
 
 use crate::{JavaValueCommon, JVMState, NewJavaValue, WasException};
+use crate::better_java_stack::frames::HasFrame;
 use crate::better_java_stack::native_frame::NativeFrame;
 use crate::interpreter::common::ldc::load_class_constant_by_type;
 use crate::jvm_state::NativeLibraries;
@@ -159,6 +160,7 @@ pub fn call_impl<'gc, 'l, 'k>(
         args_type.push(to_native_type(&t));
         c_args.push(to_native(int_state, &mut arg_boxes, (*j).clone(), &t));
     }
+    let java_thread = int_state.java_thread();
     let res = with_jni_interface(jvm, int_state, &mut exception, |env| {
         c_args[0] = Arg::new(&env);
         let cif = Cif::new(args_type.into_iter(), match &md.return_type {
@@ -175,7 +177,9 @@ pub fn call_impl<'gc, 'l, 'k>(
             CompressedParsedDescriptorType::Array { .. } => Type::pointer()
         });
         let fn_ptr = CodePtr::from_fun(*raw);
+        let was_in_native = java_thread.safepoint_state.enter_native();
         let cif_res: *mut c_void = unsafe { cif.call(fn_ptr, c_args.as_slice()) };
+        java_thread.safepoint_state.exit_native(was_in_native);
         Ok(match &md.return_type {
             CPDType::VoidType => None,
             CPDType::ByteType => Some(NewJavaValueHandle::Byte(cif_res as i8)),
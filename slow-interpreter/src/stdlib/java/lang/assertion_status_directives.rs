@@ -0,0 +1,52 @@
+use itertools::Itertools;
+use wtf8::Wtf8Buf;
+
+use jvm_args::assertions::AssertionStatus;
+use rust_jvm_common::compressed_classfile::class_names::CClassName;
+use rust_jvm_common::compressed_classfile::compressed_types::CPDType;
+use rust_jvm_common::compressed_classfile::field_names::FieldName;
+
+use crate::{JavaValue, JVMState, NewJavaValue, PushableFrame, WasException};
+use crate::class_loading::check_initing_or_inited_class;
+use crate::interpreter_util::new_object;
+use crate::new_java_values::allocated_objects::AllocatedNormalObjectHandle;
+use crate::stdlib::java::lang::string::JString;
+use crate::stdlib::java::NewAsObjectOrJavaValue;
+
+pub struct AssertionStatusDirectives<'gc> {
+    normal_object: AllocatedNormalObjectHandle<'gc>,
+}
+
+impl<'gc> AssertionStatusDirectives<'gc> {
+    // ClassLoader builds its assertion maps from this, so it is what decides assertion status for every class not loaded by the bootstrap loader
+    pub fn new(jvm: &'gc JVMState<'gc>, int_state: &mut impl PushableFrame<'gc>, assertion_status: &AssertionStatus) -> Result<AssertionStatusDirectives<'gc>, WasException<'gc>> {
+        let directives_class = check_initing_or_inited_class(jvm, int_state, CClassName::assertion_status_directives().into())?;
+        check_initing_or_inited_class(jvm, int_state, CPDType::array(CClassName::string().into()))?;
+        check_initing_or_inited_class(jvm, int_state, CPDType::array(CPDType::BooleanType))?;
+        let AssertionStatus { user_default, system_default: _, classes, packages } = assertion_status;
+        let class_names = classes.iter().map(|(class, _)| JString::from_rust(jvm, int_state, Wtf8Buf::from_str(class))).collect::<Result<Vec<_>, _>>()?;
+        let package_names = packages.iter().map(|(package, _)| JString::from_rust(jvm, int_state, Wtf8Buf::from_str(package))).collect::<Result<Vec<_>, _>>()?;
+        let class_names = JavaValue::new_vec_from_vec(jvm, class_names.iter().map(|class| class.new_java_value()).collect_vec(), CClassName::string().into());
+        let class_enabled = JavaValue::new_vec_from_vec(jvm, classes.iter().map(|(_, enabled)| NewJavaValue::Boolean(*enabled as u8)).collect_vec(), CPDType::BooleanType);
+        let package_names = JavaValue::new_vec_from_vec(jvm, package_names.iter().map(|package| package.new_java_value()).collect_vec(), CClassName::string().into());
+        let package_enabled = JavaValue::new_vec_from_vec(jvm, packages.iter().map(|(_, enabled)| NewJavaValue::Boolean(*enabled as u8)).collect_vec(), CPDType::BooleanType);
+        //like hotspot, the fields are filled in directly without running the constructor
+        let normal_object = new_object(jvm, int_state, &directives_class, false);
+        normal_object.set_var(&directives_class, FieldName::field_classes(), class_names.new_java_value());
+        normal_object.set_var(&directives_class, FieldName::field_classEnabled(), class_enabled.new_java_value());
+        normal_object.set_var(&directives_class, FieldName::field_packages(), package_names.new_java_value());
+        normal_object.set_var(&directives_class, FieldName::field_packageEnabled(), package_enabled.new_java_value());
+        normal_object.set_var(&directives_class, FieldName::field_deflt(), NewJavaValue::Boolean(*user_default as u8));
+        Ok(AssertionStatusDirectives { normal_object })
+    }
+}
+
+impl<'gc> NewAsObjectOrJavaValue<'gc> for AssertionStatusDirectives<'gc> {
+    fn object(self) -> AllocatedNormalObjectHandle<'gc> {
+        self.normal_object
+    }
+
+    fn object_ref(&self) -> &'_ AllocatedNormalObjectHandle<'gc> {
+        &self.normal_object
+    }
+}
@@ -27,7 +27,7 @@ impl<'gc> AllocatedHandle<'gc> {
 impl<'gc> IllegalArgumentException<'gc> {
     pub fn new<'l>(jvm: &'gc JVMState<'gc>, int_state: &mut impl PushableFrame<'gc>) -> Result<IllegalArgumentException<'gc>, WasException<'gc>> {
         let class_not_found_class = check_initing_or_inited_class(jvm, int_state, CClassName::illegal_argument_exception().into())?;
-        let this = new_object_full(jvm, pushable_frame_todo()/*int_state*/, &class_not_found_class);
+        let this = new_object_full(jvm, int_state, &class_not_found_class);
         run_constructor(jvm, int_state, class_not_found_class, vec![this.new_java_value()], &CMethodDescriptor::void_return(vec![]))?;
         Ok(this.cast_illegal_argument_exception())
     }
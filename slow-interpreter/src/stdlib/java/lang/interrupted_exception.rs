@@ -0,0 +1,43 @@
+use rust_jvm_common::compressed_classfile::class_names::CClassName;
+use rust_jvm_common::compressed_classfile::compressed_types::CMethodDescriptor;
+
+use crate::{NewAsObjectOrJavaValue, WasException};
+use crate::better_java_stack::frames::PushableFrame;
+use crate::class_loading::check_initing_or_inited_class;
+use crate::interpreter_util::{new_object, run_constructor};
+use crate::jvm_state::JVMState;
+use crate::new_java_values::allocated_objects::AllocatedNormalObjectHandle;
+use crate::new_java_values::owned_casts::OwnedCastAble;
+
+pub struct InterruptedException<'gc> {
+    pub(crate) normal_object: AllocatedNormalObjectHandle<'gc>,
+}
+
+impl<'gc> AllocatedNormalObjectHandle<'gc> {
+    pub fn cast_interrupted_exception(self) -> InterruptedException<'gc> {
+        InterruptedException {
+            normal_object: self.normal_object(),
+        }
+    }
+}
+
+impl<'gc> InterruptedException<'gc> {
+    pub fn new<'l>(jvm: &'gc JVMState<'gc>, int_state: &mut impl PushableFrame<'gc>) -> Result<InterruptedException<'gc>, WasException<'gc>> {
+        let interrupted_exception_class = check_initing_or_inited_class(jvm, int_state, CClassName::interrupted_exception().into())?;
+        let this = new_object(jvm, int_state, &interrupted_exception_class, false);
+        let desc = CMethodDescriptor::void_return(vec![]);
+        let args = vec![this.new_java_value()];
+        run_constructor(jvm, int_state, interrupted_exception_class, args, &desc)?;
+        Ok(this.cast_interrupted_exception())
+    }
+}
+
+impl<'gc> NewAsObjectOrJavaValue<'gc> for InterruptedException<'gc> {
+    fn object(self) -> AllocatedNormalObjectHandle<'gc> {
+        self.normal_object
+    }
+
+    fn object_ref(&self) -> &'_ AllocatedNormalObjectHandle<'gc> {
+        &self.normal_object
+    }
+}
@@ -24,4 +24,12 @@ pub mod reflect;
 pub mod system;
 pub mod class_cast_exception;
 pub mod no_such_method_exception;
-pub mod index_out_of_bounds_exception;
\ No newline at end of file
+pub mod index_out_of_bounds_exception;
+pub mod interrupted_exception;
+pub mod shutdown;
+pub mod assertion_status_directives;
+pub mod verify_error;
+pub mod class_format_error;
+pub mod no_class_def_found_error;
+pub mod linkage_error;
+pub mod stack_overflow_error;
\ No newline at end of file
@@ -0,0 +1,23 @@
+use std::marker::PhantomData;
+
+use rust_jvm_common::compressed_classfile::class_names::CClassName;
+use rust_jvm_common::compressed_classfile::compressed_descriptors::CompressedMethodDescriptor;
+use rust_jvm_common::compressed_classfile::compressed_types::CPDType;
+use rust_jvm_common::compressed_classfile::method_names::MethodName;
+
+use crate::{JVMState, PushableFrame, WasException};
+use crate::class_loading::check_initing_or_inited_class;
+use crate::utils::run_static_or_virtual;
+
+pub struct Shutdown<'gc> {
+    phantom: PhantomData<&'gc ()>,
+}
+
+impl<'gc> Shutdown<'gc> {
+    // runs the shutdown hooks, called once the last non-daemon thread has exited
+    pub fn shutdown<'l>(jvm: &'gc JVMState<'gc>, int_state: &mut impl PushableFrame<'gc>) -> Result<(), WasException<'gc>> {
+        let shutdown_class = check_initing_or_inited_class(jvm, int_state, CClassName::shutdown().into())?;
+        run_static_or_virtual(jvm, int_state, &shutdown_class, MethodName::method_shutdown(), &CompressedMethodDescriptor::empty_args(CPDType::VoidType), vec![])?;
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use rust_jvm_common::compressed_classfile::class_names::CClassName;
+use rust_jvm_common::compressed_classfile::compressed_types::CMethodDescriptor;
+use crate::{AllocatedHandle, NewAsObjectOrJavaValue, WasException};
+use crate::better_java_stack::frames::PushableFrame;
+use crate::class_loading::check_initing_or_inited_class;
+use crate::interpreter_util::{new_object_full, run_constructor};
+use crate::jvm_state::JVMState;
+use crate::new_java_values::allocated_objects::AllocatedNormalObjectHandle;
+use crate::stdlib::java::lang::string::JString;
+
+pub struct StackOverflowError<'gc> {
+    normal_object: AllocatedNormalObjectHandle<'gc>,
+}
+
+impl<'gc> AllocatedHandle<'gc> {
+    pub fn cast_stack_overflow_error(self) -> StackOverflowError<'gc> {
+        StackOverflowError { normal_object: self.unwrap_normal_object() }
+    }
+}
+
+impl<'gc> StackOverflowError<'gc> {
+    pub fn new<'l>(jvm: &'gc JVMState<'gc>, int_state: &mut impl PushableFrame<'gc>, message: JString<'gc>) -> Result<StackOverflowError<'gc>, WasException<'gc>> {
+        let stack_overflow_error_class = check_initing_or_inited_class(jvm, int_state, CClassName::stack_overflow_error().into())?;
+        let this = new_object_full(jvm, int_state, &stack_overflow_error_class);
+        run_constructor(jvm, int_state, stack_overflow_error_class, vec![this.new_java_value(), message.new_java_value()], &CMethodDescriptor::void_return(vec![CClassName::string().into()]))?;
+        Ok(this.cast_stack_overflow_error())
+    }
+}
+
+impl<'gc> NewAsObjectOrJavaValue<'gc> for StackOverflowError<'gc> {
+    fn object(self) -> AllocatedNormalObjectHandle<'gc> {
+        self.normal_object
+    }
+
+    fn object_ref(&self) -> &'_ AllocatedNormalObjectHandle<'gc> {
+        &self.normal_object
+    }
+}
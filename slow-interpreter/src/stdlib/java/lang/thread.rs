@@ -24,6 +24,7 @@ use crate::new_java_values::NewJavaValueHandle;
 use crate::stdlib::java::lang::class_loader::ClassLoader;
 use crate::stdlib::java::lang::string::JString;
 use crate::stdlib::java::lang::thread_group::JThreadGroup;
+use crate::stdlib::java::lang::throwable::Throwable;
 use crate::stdlib::java::NewAsObjectOrJavaValue;
 use crate::threading::java_thread::JavaThread;
 use crate::utils::run_static_or_virtual;
@@ -103,6 +104,15 @@ impl<'gc> JThread<'gc> {
         Ok(())
     }
 
+    // prints the stack trace through the thread's uncaught exception handler, by default its thread group
+    pub fn dispatch_uncaught_exception<'l>(&self, jvm: &'gc JVMState<'gc>, int_state: &mut impl PushableFrame<'gc>, exception: Throwable<'gc>) -> Result<(), WasException<'gc>> {
+        let thread_class = assert_inited_or_initing_class(jvm, CClassName::thread().into());
+        let args = vec![self.new_java_value(), exception.new_java_value()];
+        let desc = CMethodDescriptor::void_return(vec![CClassName::throwable().into()]);
+        run_static_or_virtual(jvm, int_state, &thread_class, MethodName::method_dispatchUncaughtException(), &desc, args)?;
+        Ok(())
+    }
+
     pub fn try_name(&self, jvm: &'gc JVMState<'gc>) -> Option<JString<'gc>> {
         let thread_class = assert_inited_or_initing_class(jvm, CClassName::thread().into());
         self.normal_object.get_var(jvm, &thread_class, FieldName::field_name()).cast_string()
@@ -112,9 +122,15 @@ impl<'gc> JThread<'gc> {
         self.try_name(jvm).unwrap()
     }
 
+    //null once the thread has terminated
+    pub fn thread_group(&self, jvm: &'gc JVMState<'gc>) -> Option<JThreadGroup<'gc>> {
+        let thread_class = assert_inited_or_initing_class(jvm, CClassName::thread().into());
+        self.normal_object.get_var(jvm, &thread_class, FieldName::field_group()).try_cast_thread_group(jvm)
+    }
+
     pub fn priority(&self, jvm: &'gc JVMState<'gc>) -> i32 {
         let thread_class = assert_inited_or_initing_class(jvm, CClassName::thread().into());
-        todo!()/*self.normal_object.unwrap_normal_object().get_var(jvm, thread_class, FieldName::field_priority()).unwrap_int()*/
+        self.normal_object.get_var(jvm, &thread_class, FieldName::field_priority()).unwrap_int()
     }
 
     fn top_level_rc(&self) -> Arc<RuntimeClass<'gc>> {
@@ -135,6 +151,11 @@ impl<'gc> JThread<'gc> {
         self.normal_object.get_var(jvm, &thread_class, FieldName::field_daemon()).unwrap_int() != 0
     }
 
+    pub fn set_daemon(&self, daemon: bool) {
+        let thread_class = self.thread_class();
+        self.normal_object.set_var(&thread_class, FieldName::field_daemon(), NewJavaValue::Boolean(daemon as u8));
+    }
+
     pub fn set_thread_status(&self, jvm: &'gc JVMState<'gc>, thread_status: jint) {
         let thread_class = assert_inited_or_initing_class(jvm, CClassName::thread().into());
         self.normal_object.set_var(&thread_class, FieldName::field_threadStatus(), NewJavaValue::Int(thread_status));
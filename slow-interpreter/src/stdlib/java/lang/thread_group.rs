@@ -1,12 +1,19 @@
 use std::sync::Arc;
 
+use wtf8::Wtf8Buf;
+
 use jvmti_jni_bindings::{jboolean, jint};
 use runtime_class_stuff::RuntimeClass;
+use rust_jvm_common::compressed_classfile::class_names::CClassName;
 use rust_jvm_common::compressed_classfile::compressed_types::CMethodDescriptor;
+use rust_jvm_common::compressed_classfile::field_names::FieldName;
+use rust_jvm_common::compressed_classfile::method_names::MethodName;
 
 
 use crate::{AllocatedHandle, JavaValueCommon, JVMState, WasException};
 use crate::better_java_stack::frames::PushableFrame;
+use crate::class_loading::assert_inited_or_initing_class;
+use crate::interpreter::common::special::instance_of_exit_impl;
 use crate::interpreter_util::{new_object, run_constructor};
 use crate::java_values::JavaValue;
 use crate::new_java_values::allocated_objects::AllocatedNormalObjectHandle;
@@ -14,6 +21,7 @@ use crate::new_java_values::NewJavaValueHandle;
 use crate::stdlib::java::lang::string::JString;
 use crate::stdlib::java::lang::thread::JThread;
 use crate::stdlib::java::NewAsObjectOrJavaValue;
+use crate::utils::run_static_or_virtual;
 
 pub struct JThreadGroup<'gc> {
     normal_object: AllocatedNormalObjectHandle<'gc>,
@@ -34,17 +42,13 @@ impl<'gc> NewJavaValueHandle<'gc> {
         JThreadGroup { normal_object: self.unwrap_object_nonnull().unwrap_normal_object() }
     }
 
-    pub fn try_cast_thread_group(self) -> Option<JThreadGroup<'gc>> {
-        /*match self.try_unwrap_normal_object() {
-            Some(normal_object) => {
-                if normal_object.objinfo.class_pointer.view().name() == CClassName::thread_group().into() {
-                    return JThreadGroup { normal_object: self.unwrap_object_nonnull() }.into();
-                }
-                None
-            }
-            None => None,
-        }*/
-        todo!()
+    //None for null, and for objects which aren't thread groups
+    pub fn try_cast_thread_group(self, jvm: &'gc JVMState<'gc>) -> Option<JThreadGroup<'gc>> {
+        let obj = self.unwrap_object()?;
+        if instance_of_exit_impl(jvm, CClassName::thread_group().into(), Some(&obj)) == 0 {
+            return None;
+        }
+        Some(JThreadGroup { normal_object: obj.unwrap_normal_object() })
     }
 }
 
@@ -61,41 +65,76 @@ impl<'gc> JThreadGroup<'gc> {
         Ok(thread_group_object.cast_thread_group())
     }
 
+    pub fn new(jvm: &'gc JVMState<'gc>, int_state: &mut impl PushableFrame<'gc>, parent: JThreadGroup<'gc>, name: String) -> Result<JThreadGroup<'gc>, WasException<'gc>> {
+        let thread_group_class = assert_inited_or_initing_class(jvm, CClassName::thread_group().into());
+        let thread_group_object = NewJavaValueHandle::Object(AllocatedHandle::NormalObject(new_object(jvm, int_state, &thread_group_class, false)));
+        let name = JString::from_rust(jvm, int_state, Wtf8Buf::from_string(name))?;
+        run_constructor(jvm, int_state, thread_group_class, vec![thread_group_object.as_njv(), parent.new_java_value_handle().as_njv(), name.new_java_value_handle().as_njv()], &CMethodDescriptor::void_return(vec![CClassName::thread_group().into(), CClassName::string().into()]))?;
+        Ok(thread_group_object.cast_thread_group())
+    }
+
+    //for threads which are running without having gone through Thread.start
+    pub fn add(&self, jvm: &'gc JVMState<'gc>, int_state: &mut impl PushableFrame<'gc>, thread: JThread<'gc>) -> Result<(), WasException<'gc>> {
+        let thread_group_class = assert_inited_or_initing_class(jvm, CClassName::thread_group().into());
+        let args = vec![self.new_java_value(), thread.new_java_value()];
+        let desc = CMethodDescriptor::void_return(vec![CClassName::thread().into()]);
+        run_static_or_virtual(jvm, int_state, &thread_group_class, MethodName::method_add(), &desc, args)?;
+        Ok(())
+    }
+
+    fn thread_group_class(&self) -> Arc<RuntimeClass<'gc>> {
+        assert_inited_or_initing_class(self.normal_object.jvm, CClassName::thread_group().into())
+    }
+
+    //the backing arrays grow ahead of their counts, so only the first n elements are meaningful
+    fn first_n_of_array(&self, jvm: &'gc JVMState<'gc>, array: FieldName, count: FieldName) -> Vec<NewJavaValueHandle<'gc>> {
+        let thread_group_class = self.thread_group_class();
+        let count = self.normal_object.get_var(jvm, &thread_group_class, count).unwrap_int();
+        match self.normal_object.get_var(jvm, &thread_group_class, array).unwrap_object() {
+            None => vec![],
+            Some(array) => {
+                let array = array.unwrap_array();
+                array.array_iterator().take(count as usize).collect()
+            }
+        }
+    }
+
     pub fn threads(&self, jvm: &'gc JVMState<'gc>) -> Vec<Option<JThread<'gc>>> {
-        /*let threads_field = self.normal_object.lookup_field(jvm, FieldName::field_threads());
-        let array = threads_field.unwrap_array();
-        array
-            .array_iterator(jvm)
-            .map(|thread| match thread.unwrap_object() {
-                None => None,
-                Some(t) => JavaValue::Object(t.into()).cast_thread().into(),
-            })
-            .collect()*/
-        todo!()
+        self.first_n_of_array(jvm, FieldName::field_threads(), FieldName::field_nthreads())
+            .into_iter()
+            .map(|thread| thread.try_cast_thread(jvm))
+            .collect()
     }
 
     pub fn threads_non_null(&self, jvm: &'gc JVMState<'gc>) -> Vec<JThread<'gc>> {
         self.threads(jvm).into_iter().flatten().collect()
     }
 
+    pub fn groups(&self, jvm: &'gc JVMState<'gc>) -> Vec<JThreadGroup<'gc>> {
+        self.first_n_of_array(jvm, FieldName::field_groups(), FieldName::field_ngroups())
+            .into_iter()
+            .flat_map(|group| group.try_cast_thread_group(jvm))
+            .collect()
+    }
+
     pub fn name(&self, jvm: &'gc JVMState<'gc>) -> JString<'gc> {
-        /*self.normal_object.lookup_field(jvm, FieldName::field_name()).cast_string().expect("thread group null name")*/
-        todo!()
+        let thread_group_class = self.thread_group_class();
+        self.normal_object.get_var(jvm, &thread_group_class, FieldName::field_name()).cast_string().expect("thread group null name")
     }
 
     pub fn daemon(&self, jvm: &'gc JVMState<'gc>) -> jboolean {
-        /*self.normal_object.lookup_field(jvm, FieldName::field_daemon()).unwrap_boolean()*/
-        todo!()
+        let thread_group_class = self.thread_group_class();
+        self.normal_object.get_var(jvm, &thread_group_class, FieldName::field_daemon()).unwrap_int() as jboolean
     }
 
     pub fn max_priority(&self, jvm: &'gc JVMState<'gc>) -> jint {
-        /*self.normal_object.lookup_field(jvm, FieldName::field_maxPriority()).unwrap_int()*/
-        todo!()
+        let thread_group_class = self.thread_group_class();
+        self.normal_object.get_var(jvm, &thread_group_class, FieldName::field_maxPriority()).unwrap_int()
     }
 
     pub fn parent(&self, jvm: &'gc JVMState<'gc>) -> Option<JThreadGroup<'gc>> {
-        /*self.normal_object.lookup_field(jvm, FieldName::field_parent()).try_cast_thread_group()*/
-        todo!()
+        let thread_group_class = self.thread_group_class();
+        self.normal_object.get_var(jvm, &thread_group_class, FieldName::field_parent()).try_cast_thread_group(jvm)
     }
 
     // as_object_or_java_value!();
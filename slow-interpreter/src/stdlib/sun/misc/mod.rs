@@ -1,3 +1,5 @@
 pub mod unsafe_;
 
-pub mod launcher;
\ No newline at end of file
+pub mod launcher;
+
+pub mod signal;
\ No newline at end of file
@@ -0,0 +1,15 @@
+use rust_jvm_common::compressed_classfile::class_names::CClassName;
+use rust_jvm_common::compressed_classfile::compressed_types::{CMethodDescriptor, CPDType};
+use rust_jvm_common::compressed_classfile::method_names::MethodName;
+
+use crate::{NewJavaValue, PushableFrame, WasException};
+use crate::class_loading::check_initing_or_inited_class;
+use crate::jvm_state::JVMState;
+use crate::utils::run_static_or_virtual;
+
+// Signal.dispatch starts a new thread running the handler registered for the signal
+pub fn dispatch<'gc>(jvm: &'gc JVMState<'gc>, int_state: &mut impl PushableFrame<'gc>, number: i32) -> Result<(), WasException<'gc>> {
+    let signal = check_initing_or_inited_class(jvm, int_state, CClassName::signal().into())?;
+    run_static_or_virtual(jvm, int_state, &signal, MethodName::method_dispatch(), &CMethodDescriptor::void_return(vec![CPDType::IntType]), vec![NewJavaValue::Int(number)])?;
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use rust_jvm_common::JavaThreadId;
+
+use crate::JVMState;
+use crate::threading::java_thread::JavaThread;
+use crate::threading::safepoints::{BlockedOnMonitor, MonitorID};
+
+// An edge of the wait-for graph, a thread blocked entering a monitor held by another thread.
+#[derive(Clone)]
+pub struct WaitingToLock<'gc> {
+    pub thread: Arc<JavaThread<'gc>>,
+    pub monitor: MonitorID,
+    pub owner: JavaThreadId,
+}
+
+fn waiting_to_lock<'gc>(jvm: &'gc JVMState<'gc>, thread: &Arc<JavaThread<'gc>>) -> Option<WaitingToLock<'gc>> {
+    let monitor = match thread.safepoint_state.blocked_on_monitor()? {
+        BlockedOnMonitor::Enter(monitor) => monitor,
+        //a waiting thread is waiting for a notify, not another thread. Once notified it reenters the monitor.
+        BlockedOnMonitor::Wait(_) => return None,
+    };
+    let owner = jvm.thread_state.monitors.read().unwrap()[monitor].owner()?;
+    if owner == thread.java_tid {
+        return None;
+    }
+    Some(WaitingToLock { thread: thread.clone(), monitor, owner })
+}
+
+// Cycles in the wait-for graph between java monitors. Each cycle is in wait-for order, so each
+// thread waits for a monitor held by the next thread, and the last by the first.
+//
+// Only object monitors are tracked, so deadlocks involving java.util.concurrent locks aren't found.
+pub fn find_monitor_deadlocks<'gc>(jvm: &'gc JVMState<'gc>) -> Vec<Vec<WaitingToLock<'gc>>> {
+    // Threads only block on one monitor at a time, so every thread has at most one outgoing edge,
+    // and a cycle can be found by following edges until a thread repeats.
+    let edges: HashMap<JavaThreadId, WaitingToLock<'gc>> = jvm.thread_state.get_all_alive_threads().iter()
+        .flat_map(|thread| waiting_to_lock(jvm, thread))
+        .map(|edge| (edge.thread.java_tid, edge))
+        .collect();
+    let mut visited = HashSet::new();
+    let mut res = vec![];
+    let mut start_tids = edges.keys().cloned().collect::<Vec<_>>();
+    start_tids.sort();
+    for start in start_tids {
+        let mut path = vec![];
+        let mut current = start;
+        while visited.insert(current) {
+            match edges.get(&current) {
+                None => break,
+                Some(edge) => {
+                    path.push(current);
+                    current = edge.owner;
+                }
+            }
+        }
+        // a new cycle is only found if the walk ran into itself, rather than an earlier walk
+        if let Some(cycle_start) = path.iter().position(|tid| *tid == current) {
+            let cycle = path[cycle_start..].iter().map(|tid| edges[tid].clone()).collect::<Vec<_>>();
+            if still_deadlocked(jvm, &cycle) {
+                res.push(cycle);
+            }
+        }
+    }
+    res
+}
+
+// The graph is built from per thread state read at different times, so a cycle may just be a
+// snapshot of threads moving between monitors. Edges of a real deadlock never change.
+fn still_deadlocked<'gc>(jvm: &'gc JVMState<'gc>, cycle: &[WaitingToLock<'gc>]) -> bool {
+    cycle.iter().all(|edge| {
+        match waiting_to_lock(jvm, &edge.thread) {
+            None => false,
+            Some(WaitingToLock { monitor, owner, .. }) => monitor == edge.monitor && owner == edge.owner,
+        }
+    })
+}
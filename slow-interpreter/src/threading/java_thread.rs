@@ -3,6 +3,7 @@ use std::mem::transmute;
 use std::ops::Deref;
 use std::ptr::{NonNull, null_mut};
 use std::sync::{Arc, Condvar, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 
 use num_integer::Integer;
@@ -17,13 +18,17 @@ use threads::Thread;
 
 use crate::{JVMState, OpaqueFrame, pushable_frame_todo, WasException};
 use crate::better_java_stack::{FramePointer, JavaStack};
-use crate::better_java_stack::frames::{HasFrame};
+use crate::better_java_stack::frames::{HasFrame, PushableFrame};
 use crate::better_java_stack::java_stack_guard::JavaStackGuard;
 use crate::better_java_stack::remote_frame::RemoteFrame;
 use crate::interpreter::safepoint_check;
 use crate::rust_jni::jvmti::ThreadJVMTIEnabledStatus;
 use crate::stdlib::java::lang::thread::JThread;
-use crate::threading::safepoints::SafePoint;
+use crate::threading::safepoints::{interrupted_exception, SafePoint};
+use crate::threading::thread_dump::DumpedFrame;
+
+//how often a stack snapshot rechecks whether the thread it is waiting on has exited
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(10);
 
 
 pub struct JavaThread<'vm> {
@@ -37,7 +42,8 @@ pub struct JavaThread<'vm> {
     jvmti_events_enabled: RwLock<ThreadJVMTIEnabledStatus>,
     pub thread_local_storage: RwLock<*mut c_void>,
     pub thread_status: Mutex<ThreadStatus>,
-    pub thread_status_change_condvar: Condvar
+    pub thread_status_change_condvar: Condvar,
+    allocated_bytes: AtomicU64,
 }
 
 impl<'gc> JavaThread<'gc> {
@@ -95,7 +101,8 @@ impl<'gc> JavaThread<'gc> {
                 (thread_obj.tid(jvm), thread_obj.name(jvm).to_rust_string(jvm))
             }
         };
-        let underlying = jvm.thread_state.threads.create_thread(name.clone().into());
+        //-Xss sizes the native stack, which is what the interpreter recurses on
+        let underlying = jvm.thread_state.threads.create_thread(name.clone().into(), jvm.config.thread_stack_size.map(|size| size as usize));
         let java_stack = Mutex::new(JavaStack::new(jvm, owned_ir_stack, stack_signal_safe_data.clone(), name.clone()));
         let res = Arc::new(JavaThread {
             java_tid,
@@ -106,9 +113,10 @@ impl<'gc> JavaThread<'gc> {
             invisible_to_java,
             jvmti_events_enabled: RwLock::new(ThreadJVMTIEnabledStatus::default()),
             thread_local_storage: RwLock::new(null_mut()),
-            safepoint_state: SafePoint::new(),
-            thread_status: Mutex::new(ThreadStatus { terminated: false, alive: false, interrupted: false }),
-            thread_status_change_condvar: Condvar::new()
+            safepoint_state: SafePoint::new(jvm.thread_state.safepoint_poll_word()),
+            thread_status: Mutex::new(ThreadStatus { terminated: false, alive: false }),
+            thread_status_change_condvar: Condvar::new(),
+            allocated_bytes: AtomicU64::new(0),
         });
         jvm.thread_state.all_java_threads.write().unwrap().insert(res.java_tid, res.clone());
         Ok(res)
@@ -126,6 +134,29 @@ impl<'gc> JavaThread<'gc> {
         &self.underlying_thread
     }
 
+    pub fn record_allocation(&self, bytes: usize) {
+        self.allocated_bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    // approximate while the thread is running, since allocations by the thread are only relaxed atomic adds
+    pub fn allocated_bytes(&self) -> u64 {
+        self.allocated_bytes.load(Ordering::Relaxed)
+    }
+
+    pub fn cpu_time(&self) -> Option<Duration> {
+        if !self.is_alive() {
+            return None;
+        }
+        self.underlying_thread.cpu_time()
+    }
+
+    pub fn user_cpu_time(&self) -> Option<Duration> {
+        if !self.is_alive() {
+            return None;
+        }
+        self.underlying_thread.user_cpu_time()
+    }
+
     pub fn thread_object(&self) -> JThread<'gc> {
         self.try_thread_object().unwrap()
     }
@@ -173,6 +204,24 @@ impl<'gc> JavaThread<'gc> {
         self.safepoint_state.check(jvm, int_state)
     }
 
+    pub fn sleep(&self, jvm: &'gc JVMState<'gc>, int_state: &mut impl PushableFrame<'gc>, duration: Duration) -> Result<(), WasException<'gc>> {
+        unsafe { assert!(self.underlying_thread.is_this_thread()) }
+        self.safepoint_state.set_sleeping(duration);
+        self.safepoint_state.check(jvm, int_state)?;
+        if self.safepoint_state.is_interrupted(true) {
+            return Err(interrupted_exception(jvm, int_state));
+        }
+        Ok(())
+    }
+
+    pub fn interrupt(&self) {
+        self.safepoint_state.set_interrupted();
+    }
+
+    pub fn is_interrupted(&self, clear_interrupted: bool) -> bool {
+        self.safepoint_state.is_interrupted(clear_interrupted)
+    }
+
     pub fn unpark<'l>(&self, jvm: &'gc JVMState<'gc>, int_state: &mut impl HasFrame<'gc>) -> Result<(), WasException<'gc>> {
         self.safepoint_state.set_unpark();
         self.safepoint_state.check(jvm, int_state)
@@ -208,9 +257,10 @@ impl<'gc> JavaThread<'gc> {
         unsafe { self.underlying_thread.is_this_thread() }
     }
 
-    pub fn pause_and_remote_view<T>(self: Arc<Self>, jvm: &'gc JVMState<'gc>, with_frame: impl for<'k> FnOnce(RemoteFrame<'gc,'k>) -> T) /*-> T*/ {
+    pub fn pause_and_remote_view<T>(self: Arc<Self>, jvm: &'gc JVMState<'gc>, with_frame: impl for<'k> FnOnce(RemoteFrame<'gc,'k>) -> T) -> Option<T> {
         let pthread_id = self.underlying_thread.pthread_id();
         let signal_safe_data = self.stack_signal_safe_data.deref();
+        let mut res = None;
         jvm.thread_state.interrupter.perform_remote_query(pthread_id, RemoteQuery::GetGuestFrameStackInstructionPointer { }, signal_safe_data, |answer| {
             match answer {
                 RemoteQueryAnswer::GetGuestFrameStackInstructionPointer(inner) => {
@@ -218,8 +268,8 @@ impl<'gc> JavaThread<'gc> {
                         GetGuestFrameStackInstructionPointer::InGuest { rbp, rsp, rip } => {
                             let frame_pointer = FramePointer(NonNull::new(rbp as *mut c_void).unwrap());
                             let mut java_stack = JavaStackGuard::new_remote_with_frame_pointer(jvm, unsafe { transmute(&self.java_stack) }, self.clone(), frame_pointer);
-                            let remote_frame = RemoteFrame::new(&mut java_stack, frame_pointer);
-                            with_frame(remote_frame);
+                            let remote_frame = RemoteFrame::new(&mut java_stack, frame_pointer, Some(rip as *const c_void));
+                            res = Some(with_frame(remote_frame));
                         }
                         GetGuestFrameStackInstructionPointer::InVM { rbp, rsp, rip } => {
                             // dbg!("in vm");
@@ -237,6 +287,48 @@ impl<'gc> JavaThread<'gc> {
                 }
             }
         });
+        res
+    }
+
+    // views the stack of a thread blocked in a safepoint check, without pausing it. None if the thread isn't blocked.
+    pub fn blocked_remote_view<T>(self: Arc<Self>, jvm: &'gc JVMState<'gc>, with_frame: impl for<'k> FnOnce(RemoteFrame<'gc,'k>) -> T) -> Option<T> {
+        let java_stack: &'gc Mutex<JavaStack<'gc>> = unsafe { transmute(&self.java_stack) };
+        let java_stack = JavaStackGuard::try_new_remote(jvm, java_stack, self.clone())?;
+        //blocked frame can only be read with the stack held, since the thread can't leave the blocked frame without reacquiring the stack
+        let frame_pointer = self.safepoint_state.blocked_frame()?;
+        let mut java_stack = JavaStackGuard::new_from_prev_with_new_frame_pointer(java_stack, frame_pointer);
+        Some(with_frame(RemoteFrame::new(&mut java_stack, frame_pointer, None)))
+    }
+
+    // The java stack of another thread. A thread which isn't already blocked is asked to pause at its
+    // next safepoint, and viewed once it has. None for threads which exit first, or are running native
+    // code, whose stack can't be viewed until they return.
+    pub fn stack_snapshot(self: &Arc<Self>, jvm: &'gc JVMState<'gc>) -> Option<Vec<DumpedFrame>> {
+        assert!(!self.is_this_thread());
+        //most threads in a dump are blocked already, and requesting a pause would wake them
+        if let Some(frames) = self.clone().blocked_remote_view(jvm, |remote_frame| DumpedFrame::from_frames(jvm, remote_frame.frame_iter())) {
+            return Some(frames);
+        }
+        self.safepoint_state.request_pause();
+        //counts as in native while waiting, so that two threads snapshotting each other don't wait on each other forever
+        let current_thread = jvm.thread_state.try_get_current_thread();
+        let was_in_native = current_thread.as_ref().map(|current_thread| current_thread.safepoint_state.enter_native());
+        let res = loop {
+            if !self.is_alive() {
+                break None;
+            }
+            if let Some(frames) = self.clone().blocked_remote_view(jvm, |remote_frame| DumpedFrame::from_frames(jvm, remote_frame.frame_iter())) {
+                break Some(frames);
+            }
+            if !self.safepoint_state.wait_until_paused(PAUSE_POLL_INTERVAL) {
+                break None;
+            }
+        };
+        self.safepoint_state.clear_pause_request();
+        if let (Some(current_thread), Some(was_in_native)) = (current_thread, was_in_native) {
+            current_thread.safepoint_state.exit_native(was_in_native);
+        }
+        res
     }
 
     pub fn wait_thread_exit(&self) {
@@ -263,7 +355,6 @@ impl<'gc> JavaThread<'gc> {
 pub struct ThreadStatus {
     pub terminated: bool,
     pub alive: bool,
-    pub interrupted: bool,
 }
 
 #[derive(Debug)]
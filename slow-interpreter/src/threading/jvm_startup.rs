@@ -6,19 +6,23 @@ use libloading::Symbol;
 use wtf8::Wtf8Buf;
 use jvmti_jni_bindings::{JVMTI_THREAD_NORM_PRIORITY};
 use jvmti_jni_bindings::invoke_interface::JNIInvokeInterfaceNamedReservedPointers;
+use jvm_args::ShareMode;
 use rust_jvm_common::compressed_classfile::class_names::CClassName;
 use rust_jvm_common::compressed_classfile::method_names::MethodName;
 
 
 use rust_jvm_common::loading::LoaderName;
 use threads::Threads;
-use crate::{check_initing_or_inited_class, check_loaded_class,  JString, JVMState, MethodResolverImpl, NewJavaValue, NewJavaValueHandle, PushableFrame, run_function, run_main, set_properties, StackEntryPush, System, WasException};
+use crate::{check_initing_or_inited_class, check_loaded_class,  JString, JVMState, MethodResolverImpl, NewJavaValue, NewJavaValueHandle, OpaqueFrame, PushableFrame, run_function, run_main, set_properties, StackEntryPush, System, WasException};
+use crate::class_data_sharing::dump_shared_archive;
 use crate::class_loading::assert_inited_or_initing_class;
 use crate::interpreter_util::new_object_full;
 use crate::rust_jni::invoke_interface::get_invoke_interface_new;
 use crate::stdlib::java::lang::thread::JThread;
 use crate::stdlib::java::lang::thread_group::JThreadGroup;
 use crate::threading::java_thread::JavaThread;
+use crate::stdlib::java::lang::shutdown::Shutdown;
+use crate::threading::signals::start_signal_dispatcher;
 
 pub struct MainThreadStartInfo {
     pub args: Vec<String>,
@@ -54,6 +58,7 @@ fn jvm_init_from_main_thread<'l, 'gc>(jvm: &'gc JVMState<'gc>, int_state: &mut i
         Ok(())
     });
     set_properties(jvm, int_state).expect("todo");
+    start_signal_dispatcher(jvm, int_state);
     //todo read and copy props here
     // let key = JString::from_rust(jvm, int_state, Wtf8Buf::from_string("java.home".to_string())).expect("todo");
     // let value = JString::from_rust(jvm, int_state, Wtf8Buf::from_string("/home/francis/builds/jvm-dep-dir/jdk8u/build/linux-x86_64-normal-server-fastdebug/jdk/".to_string())).expect("todo");
@@ -66,6 +71,22 @@ fn jvm_init_from_main_thread<'l, 'gc>(jvm: &'gc JVMState<'gc>, int_state: &mut i
 }
 
 
+// Like hotspot's DestroyJavaVM, the main thread leaves its group once main returns, then waits for
+// every other non-daemon thread before running the shutdown hooks and tearing down agents.
+fn shutdown_from_main_thread<'gc, 'l>(jvm: &'gc JVMState<'gc>, main_thread: &Arc<JavaThread<'gc>>, opaque_frame: &mut OpaqueFrame<'gc, 'l>) {
+    let _ = main_thread.thread_object().exit(jvm, opaque_frame);
+    jvm.thread_state.wait_all_non_daemon_threads(jvm);
+    if let Err(WasException { exception_obj }) = Shutdown::shutdown(jvm, opaque_frame) {
+        exception_obj.print_stack_trace(jvm, opaque_frame).expect("exception printing exception");
+    }
+    if let Some(jvmti) = jvm.jvmti_state() {
+        jvmti.built_in_jdwp.vm_death(jvm, opaque_frame);
+        for agent in jvmti.agents.iter() {
+            agent.agent_unload(jvm, opaque_frame);
+        }
+    }
+}
+
 pub fn bootstrap_main_thread<'vm>(jvm: &'vm JVMState<'vm>, threads: &'vm Threads<'vm>, main_thread_start_info: MainThreadStartInfo) -> Arc<JavaThread<'vm>> {
     let main_jthread = JavaThread::new_with_stack_on_this_thread(jvm, None, true, move |bootstrap_thread, opaque_frame| {
         unsafe {
@@ -89,7 +110,10 @@ pub fn bootstrap_main_thread<'vm>(jvm: &'vm JVMState<'vm>, threads: &'vm Threads
             let thread_group_class = check_initing_or_inited_class(jvm, java_stack_guard, CClassName::thread_group().into()).expect("couldn't load thread group class");
             let system_thread_group = JThreadGroup::init(jvm, java_stack_guard, thread_group_class).expect("todo");
             *jvm.thread_state.system_thread_group.write().unwrap() = system_thread_group.clone().into();
-            let main_jthread = JThread::new(jvm, java_stack_guard, system_thread_group, "Main".to_string()).expect("todo");
+            let main_thread_group = JThreadGroup::new(jvm, java_stack_guard, system_thread_group, "main".to_string()).expect("todo");
+            let main_jthread = JThread::new(jvm, java_stack_guard, main_thread_group.clone(), "main".to_string()).expect("todo");
+            //the main thread never goes through Thread.start, which is what normally adds a thread to its group
+            main_thread_group.add(jvm, java_stack_guard, main_jthread.clone()).expect("todo");
             Ok(main_jthread)
         })
     }).unwrap();
@@ -98,13 +122,21 @@ pub fn bootstrap_main_thread<'vm>(jvm: &'vm JVMState<'vm>, threads: &'vm Threads
         *jvm.thread_state.main_thread.write().unwrap() = main_thread.clone().into();
         main_thread.thread_object.read().unwrap().as_ref().unwrap().set_priority(JVMTI_THREAD_NORM_PRIORITY as i32);
         main_thread.notify_alive(jvm); //is this too early?
-        jvm.jvmti_state().map(|jvmti| jvmti.built_in_jdwp.agent_load(jvm, opaque_frame)); // technically this is to late and should have been called earlier, but needs to be on this thread.
+        // technically this is to late and should have been called earlier, but needs to be on this thread.
+        if let Some(jvmti) = jvm.jvmti_state() {
+            for agent in jvmti.agents.iter() {
+                if let Err(err) = agent.agent_load(jvm, opaque_frame) {
+                    eprintln!("Error occurred during initialization of VM\n{}", err);
+                    std::process::exit(1);
+                }
+            }
+        }
         jvm_init_from_main_thread(jvm, opaque_frame);
 
         assert!(!jvm.live.load(Ordering::SeqCst));
         jvm.live.store(true, Ordering::SeqCst);
         if let Some(jvmti) = jvm.jvmti_state() {
-            jvmti.built_in_jdwp.vm_inited(jvm, todo!()/*&mut int_state*/, main_thread.clone())
+            jvmti.built_in_jdwp.vm_inited(jvm, opaque_frame, main_thread.clone())
         }
         let MainThreadStartInfo { args } = main_recv.recv().unwrap();
         //from the jvmti_interface spec:
@@ -113,10 +145,22 @@ pub fn bootstrap_main_thread<'vm>(jvm: &'vm JVMState<'vm>, threads: &'vm Threads
             jvmti.built_in_jdwp.thread_start(jvm, opaque_frame, main_thread.thread_object())
         }
         opaque_frame.push_frame_opaque(StackEntryPush::new_completely_opaque_frame(jvm, LoaderName::BootstrapLoader, vec![], "main thread main frame"), |opaque_frame| {
-            run_main(args, jvm, opaque_frame).unwrap();
+            if jvm.config.share == ShareMode::Dump {
+                match dump_shared_archive(jvm, opaque_frame) {
+                    Ok(num_classes) => eprintln!("Dumped {} classes to {}", num_classes, jvm.config.shared_archive_file.display()),
+                    Err(err) => {
+                        eprintln!("Error: failed to dump the shared archive: {}", err);
+                        //exits with 1 like an uncaught exception would
+                        jvm.thread_state.main_thread_uncaught_exception.store(true, Ordering::SeqCst);
+                    }
+                }
+            } else if let Err(WasException { exception_obj }) = run_main(args, jvm, opaque_frame) {
+                jvm.thread_state.main_thread_uncaught_exception.store(true, Ordering::SeqCst);
+                let _ = main_thread.thread_object().dispatch_uncaught_exception(jvm, opaque_frame, exception_obj);
+            }
+            shutdown_from_main_thread(jvm, &main_thread, opaque_frame);
             Ok(())
         }).unwrap();
-        //todo handle exception exit from main
         main_thread.notify_terminated(jvm);
         Ok(())
     }).expect("todo");
@@ -1,4 +1,7 @@
 pub mod safepoints;
 pub mod jvm_startup;
 pub mod thread_state;
-pub mod java_thread;
\ No newline at end of file
+pub mod java_thread;
+pub mod thread_dump;
+pub mod deadlocks;
+pub mod signals;
\ No newline at end of file
@@ -1,6 +1,7 @@
 use std::collections::HashSet;
 use std::ops::Add;
-use std::sync::{Condvar, Mutex, RwLock};
+use std::sync::{Condvar, Mutex, MutexGuard, RwLock};
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::thread::current;
 use std::time::{Duration, Instant};
 
@@ -8,10 +9,14 @@ use jvmti_jni_bindings::{jint, JVMTI_THREAD_STATE_ALIVE, JVMTI_THREAD_STATE_BLOC
 use rust_jvm_common::JavaThreadId;
 
 use crate::WasException;
-use crate::better_java_stack::frames::{HasFrame};
+use crate::better_java_stack::FramePointer;
+use crate::better_java_stack::frames::{HasFrame, PushableFrame};
 use crate::interpreter::safepoint_check;
 use crate::jvm_state::JVMState;
+use crate::new_java_values::owned_casts::OwnedCastAble;
+use crate::stdlib::java::lang::interrupted_exception::InterruptedException;
 use crate::stdlib::java::lang::throwable::Throwable;
+use crate::stdlib::java::NewAsObjectOrJavaValue;
 use crate::threading::java_thread::{ResumeError, SuspendError, ThreadStatus};
 
 // new approach to safepoints
@@ -37,6 +42,12 @@ use crate::threading::java_thread::{ResumeError, SuspendError, ThreadStatus};
 
 pub type MonitorID = usize;
 
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BlockedOnMonitor {
+    Enter(MonitorID),
+    Wait(MonitorID),
+}
+
 #[derive(Debug)]
 pub struct MonitorWait {
     wait_until: Option<Instant>,
@@ -49,10 +60,24 @@ pub struct SafePointStopReasonState<'gc> {
     pub(crate) waiting_monitor_notify: Option<MonitorWait>,
     suspended: bool,
     gc_suspended: bool,
+    // threads waiting to view this thread's stack, see JavaThread::stack_snapshot
+    pause_requests: usize,
     parks: isize,
     park_until: Option<Instant>,
     throw_exception: Option<Throwable<'gc>>,
     sleep_until: Option<Instant>,
+    deoptimize_requested: bool,
+    at_safepoint: bool,
+    // lives here rather than in ThreadStatus so that setting it can wake the wait in check
+    interrupted: bool,
+    // set by set_interrupted until the next check, so that compiled code polls for it
+    interrupt_unobserved: bool,
+    // the top frame of a thread blocked in check, which releases its java stack while blocked
+    blocked_frame: Option<FramePointer>,
+    // running a native method, which can't be stopped but doesn't touch the java stack either
+    in_native: bool,
+    // whether this thread currently contributes to the shared poll word
+    poll_raised: bool,
 }
 
 impl<'gc> Default for SafePointStopReasonState<'gc> {
@@ -62,10 +87,18 @@ impl<'gc> Default for SafePointStopReasonState<'gc> {
             waiting_monitor_notify: None,
             suspended: false,
             gc_suspended: false,
+            pause_requests: 0,
             parks: 0,
             park_until: None,
             throw_exception: None,
             sleep_until: None,
+            deoptimize_requested: false,
+            at_safepoint: false,
+            interrupted: false,
+            interrupt_unobserved: false,
+            blocked_frame: None,
+            in_native: false,
+            poll_raised: false,
         }
     }
 }
@@ -73,11 +106,74 @@ impl<'gc> Default for SafePointStopReasonState<'gc> {
 pub struct SafePoint<'gc> {
     pub(crate) state: Mutex<SafePointStopReasonState<'gc>>,
     waiton: Condvar,
+    reached_safepoint: Condvar,
+    // shared by all threads, see ThreadState::safepoint_poll_address
+    poll_word: &'gc AtomicU32,
 }
 
 impl<'gc> SafePoint<'gc> {
-    pub fn new() -> Self {
-        Self { state: Mutex::new(Default::default()), waiton: Default::default() }
+    pub fn new(poll_word: &'gc AtomicU32) -> Self {
+        Self { state: Mutex::new(Default::default()), waiton: Default::default(), reached_safepoint: Default::default(), poll_word }
+    }
+
+    // Compiled code only calls check when the poll word is nonzero, so each thread keeps it raised for as long
+    // as it has a reason to stop which it could otherwise miss while running compiled code.
+    fn stop_reasons_changed(&self, state: &mut SafePointStopReasonState<'gc>) {
+        let should_poll = state.gc_suspended || state.pause_requests > 0 || state.suspended || state.deoptimize_requested || state.throw_exception.is_some() || state.interrupt_unobserved;
+        if should_poll != state.poll_raised {
+            if should_poll {
+                self.poll_word.fetch_add(1, Ordering::SeqCst);
+            } else {
+                self.poll_word.fetch_sub(1, Ordering::SeqCst);
+            }
+            state.poll_raised = should_poll;
+        }
+        self.waiton.notify_one();
+    }
+
+    // Threads blocked in check or running native code count as stopped, they check for gc_suspended before
+    // touching the java stack again.
+    pub fn wait_until_at_safepoint(&self) {
+        let guard = self.state.lock().unwrap();
+        let guard = self.reached_safepoint.wait_while(guard, |state| {
+            state.gc_suspended && !state.at_safepoint && state.blocked_frame.is_none() && !state.in_native
+        }).unwrap();
+        drop(guard);
+    }
+
+    // returns whether the thread was already in native code, for exit_native. Native methods can call back into
+    // java which calls native methods again.
+    pub fn enter_native(&self) -> bool {
+        let mut guard = self.state.lock().unwrap();
+        let was_in_native = guard.in_native;
+        guard.in_native = true;
+        self.reached_safepoint.notify_all();
+        was_in_native
+    }
+
+    pub fn exit_native(&self, was_in_native: bool) {
+        let mut guard = self.state.lock().unwrap();
+        guard.in_native = was_in_native;
+        let guard = self.waiton.wait_while(guard, |state| state.gc_suspended && !state.in_native).unwrap();
+        drop(guard);
+    }
+
+    // Native code calling back into java touches the java stack again, so stops counting as in native until the
+    // java code returns, waiting out any gc which stopped it first. Returns whether it was in native, for
+    // return_to_native.
+    pub fn leave_native(&self) -> bool {
+        let mut guard = self.state.lock().unwrap();
+        let was_in_native = guard.in_native;
+        guard.in_native = false;
+        let guard = self.waiton.wait_while(guard, |state| state.gc_suspended).unwrap();
+        drop(guard);
+        was_in_native
+    }
+
+    pub fn return_to_native(&self, was_in_native: bool) {
+        let mut guard = self.state.lock().unwrap();
+        guard.in_native = was_in_native;
+        self.reached_safepoint.notify_all();
     }
 
     pub fn set_monitor_unlocked(&self) {
@@ -101,21 +197,16 @@ impl<'gc> SafePoint<'gc> {
         self.waiton.notify_one();
     }
 
+    // the waiting thread may already have given up on the notify because of a timeout or interrupt, and
+    // not yet removed itself from the monitor's waiters
     pub fn set_notified_once(&self) {
         let mut guard = self.state.lock().unwrap();
-        assert!(guard.waiting_monitor_notify.is_some());
-        let waiting_monitor_notify = guard.waiting_monitor_notify.as_mut().unwrap();
-        let prev_count = &mut waiting_monitor_notify.prev_count; //todo wtf is this, we need more types for monitor wait
-        *prev_count -= 1;
-        if *prev_count == 0 {
-            guard.waiting_monitor_notify = None
-        }
+        guard.waiting_monitor_notify = None;
         self.waiton.notify_one();
     }
 
     pub fn set_notified_all(&self) {
         let mut guard = self.state.lock().unwrap();
-        assert!(guard.waiting_monitor_notify.is_some());
         guard.waiting_monitor_notify = None;
         self.waiton.notify_one();
     }
@@ -126,7 +217,7 @@ impl<'gc> SafePoint<'gc> {
             return Err(SuspendError::AlreadySuspended);
         }
         guard.suspended = true;
-        self.waiton.notify_one();
+        self.stop_reasons_changed(&mut guard);
         Ok(())
     }
 
@@ -136,17 +227,40 @@ impl<'gc> SafePoint<'gc> {
             return Err(SuspendError::AlreadySuspended);
         }
         guard.gc_suspended = true;
-        self.waiton.notify_one();
+        self.stop_reasons_changed(&mut guard);
         Ok(())
     }
 
+    pub fn request_pause(&self) {
+        let mut guard = self.state.lock().unwrap();
+        guard.pause_requests += 1;
+        self.stop_reasons_changed(&mut guard);
+    }
+
+    pub fn clear_pause_request(&self) {
+        let mut guard = self.state.lock().unwrap();
+        assert!(guard.pause_requests > 0);
+        guard.pause_requests -= 1;
+        self.stop_reasons_changed(&mut guard);
+    }
+
+    // Waits up to timeout for the thread to block with its stack released, which a thread asked to pause does at
+    // its next safepoint. Returns false for a thread running native code, which may never come back to one.
+    pub fn wait_until_paused(&self, timeout: Duration) -> bool {
+        let guard = self.state.lock().unwrap();
+        let (guard, _) = self.reached_safepoint.wait_timeout_while(guard, timeout, |state| {
+            !state.at_safepoint && state.blocked_frame.is_none() && !state.in_native
+        }).unwrap();
+        !guard.in_native || guard.blocked_frame.is_some()
+    }
+
     pub fn set_gc_unsuspended(&self) -> Result<(), ResumeError> {
         let mut guard = self.state.lock().unwrap();
         if !guard.gc_suspended {
             return Err(ResumeError::NotSuspended);
         }
         guard.gc_suspended = false;
-        self.waiton.notify_one();
+        self.stop_reasons_changed(&mut guard);
         Ok(())
     }
 
@@ -156,10 +270,16 @@ impl<'gc> SafePoint<'gc> {
             return Err(ResumeError::NotSuspended);
         }
         guard.suspended = false;
-        self.waiton.notify_one(); // technically I don't need this to be a condvar since I only use notify_one I could have this be a lock
+        self.stop_reasons_changed(&mut guard);
         Ok(())
     }
 
+    pub fn set_deoptimize_requested(&self) {
+        let mut guard = self.state.lock().unwrap();
+        guard.deoptimize_requested = true;
+        self.stop_reasons_changed(&mut guard);
+    }
+
     pub fn set_sleeping(&self, to_sleep: Duration) {
         let mut guard = self.state.lock().unwrap();
         guard.sleep_until = Some(Instant::now().add(to_sleep));
@@ -176,6 +296,22 @@ impl<'gc> SafePoint<'gc> {
         self.waiton.notify_one()
     }
 
+    pub fn set_interrupted(&self) {
+        let mut guard = self.state.lock().unwrap();
+        guard.interrupted = true;
+        guard.interrupt_unobserved = true;
+        self.stop_reasons_changed(&mut guard);
+    }
+
+    pub fn is_interrupted(&self, clear_interrupted: bool) -> bool {
+        let mut guard = self.state.lock().unwrap();
+        let interrupted = guard.interrupted;
+        if clear_interrupted {
+            guard.interrupted = false;
+        }
+        interrupted
+    }
+
     pub fn set_unpark(&self) {
         let mut guard = self.state.lock().unwrap();
         assert!(guard.parks <= 1);
@@ -184,13 +320,25 @@ impl<'gc> SafePoint<'gc> {
         self.waiton.notify_one()
     }
 
+    pub fn blocked_frame(&self) -> Option<FramePointer> {
+        self.state.lock().unwrap().blocked_frame
+    }
+
+    pub fn blocked_on_monitor(&self) -> Option<BlockedOnMonitor> {
+        let guard = self.state.lock().unwrap();
+        if let Some(monitor) = guard.waiting_monitor_lock {
+            return Some(BlockedOnMonitor::Enter(monitor));
+        }
+        guard.waiting_monitor_notify.as_ref().map(|MonitorWait { monitor, .. }| BlockedOnMonitor::Wait(*monitor))
+    }
+
     pub(crate) fn get_thread_status_number(&self, thread_status: &ThreadStatus) -> jint {
         let mut res = 0;
         let guard = self.state.lock().unwrap();
         if thread_status.alive {
             res |= JVMTI_THREAD_STATE_ALIVE;
             //todo is in native code
-            if thread_status.interrupted {
+            if guard.interrupted {
                 res |= JVMTI_THREAD_STATE_INTERRUPTED;
             }
             if guard.suspended {
@@ -231,16 +379,61 @@ impl<'gc> SafePoint<'gc> {
 }
 
 impl<'gc> SafePoint<'gc> {
+    // Publishes the blocked frame and releases the java stack for the duration of the wait, so that
+    // thread dumps can walk the stack of a blocked thread without having to pause it.
+    fn wait_with_stack_released<'l>(&'l self, int_state: &mut impl HasFrame<'gc>, mut guard: MutexGuard<'l, SafePointStopReasonState<'gc>>, timeout: Option<Duration>) -> MutexGuard<'l, SafePointStopReasonState<'gc>> {
+        guard.blocked_frame = Some(int_state.frame_ref().frame_ptr().into());
+        int_state.java_stack_mut().drop_guard();
+        self.reached_safepoint.notify_all();
+        let mut guard = match timeout {
+            None => self.waiton.wait(guard).unwrap(),
+            Some(timeout) => self.waiton.wait_timeout(guard, timeout).unwrap().0,
+        };
+        guard.blocked_frame = None;
+        // a thread dump holds the java stack while reading blocked_frame, so the stack can't be reacquired with this held
+        drop(guard);
+        int_state.java_stack_mut().reacquire();
+        self.state.lock().unwrap()
+    }
+
     pub fn check<'l>(&self, jvm: &'gc JVMState<'gc>, int_state: &mut impl HasFrame<'gc>) -> Result<(), WasException<'gc>> {
-        let guard = self.state.lock().unwrap();
+        let mut guard = self.state.lock().unwrap();
+        if guard.interrupt_unobserved {
+            guard.interrupt_unobserved = false;
+            self.stop_reasons_changed(&mut guard);
+        }
 
         if guard.gc_suspended {
             // dbg!("gc suspended");
-            let guard = self.waiton.wait(guard).unwrap();
+            guard.at_safepoint = true;
+            self.reached_safepoint.notify_all();
+            while guard.gc_suspended {
+                guard = self.wait_with_stack_released(int_state, guard, None);
+            }
+            guard.at_safepoint = false;
+            drop(guard);
+            return self.check(jvm, int_state);
+        }
+
+        if guard.pause_requests > 0 {
+            guard.at_safepoint = true;
+            self.reached_safepoint.notify_all();
+            while guard.pause_requests > 0 {
+                guard = self.wait_with_stack_released(int_state, guard, None);
+            }
+            guard.at_safepoint = false;
             drop(guard);
             return self.check(jvm, int_state);
         }
 
+        if guard.deoptimize_requested {
+            guard.deoptimize_requested = false;
+            self.stop_reasons_changed(&mut guard);
+            drop(guard);
+            jvm.java_vm_state.patch_invalidated_return_addresses(int_state.frame_ref());
+            return self.check(jvm, int_state);
+        }
+
         if let Some(exception) = &guard.throw_exception {
             todo!();
             // int_state.set_throw(Some(exception.clone().to_allocated_object().into()));
@@ -248,8 +441,7 @@ impl<'gc> SafePoint<'gc> {
         }
         if guard.suspended {
             // dbg!("regular suspended");
-            let _unused = self.waiton.wait(guard).unwrap();
-            let current_thread = jvm.thread_state.get_current_thread();
+            let _unused = self.wait_with_stack_released(int_state, guard, None);
             drop(_unused);
             return self.check(jvm, int_state);
         }
@@ -258,7 +450,16 @@ impl<'gc> SafePoint<'gc> {
             // dbg!("parked wait");
             // assert!(guard.waiting_monitor_notify.is_none());
             // assert!(guard.waiting_monitor_lock.is_none());
-            let _unused = self.waiton.wait(guard).unwrap();
+            // park returns on interrupt without clearing it
+            let park_expired = guard.park_until.map(|park_until| Instant::now() >= park_until).unwrap_or(false);
+            if guard.interrupted || park_expired {
+                guard.parks = 0;
+                guard.park_until = None;
+                drop(guard);
+                return self.check(jvm, int_state);
+            }
+            let park_for = guard.park_until.map(|park_until| park_until.saturating_duration_since(Instant::now()));
+            let _unused = self.wait_with_stack_released(int_state, guard, park_for);
             // dbg!(_unused.parks);
             // assert!(_unused.waiting_monitor_notify.is_none());
             // assert!(_unused.waiting_monitor_lock.is_none());
@@ -270,8 +471,18 @@ impl<'gc> SafePoint<'gc> {
             drop(guard);
             return self.check(jvm, int_state);
         }
+        if let Some(sleep_until) = guard.sleep_until {
+            if guard.interrupted || Instant::now() >= sleep_until {
+                guard.sleep_until = None;
+                drop(guard);
+                return self.check(jvm, int_state);
+            }
+            let guard = self.wait_with_stack_released(int_state, guard, Some(sleep_until.saturating_duration_since(Instant::now())));
+            drop(guard);
+            return self.check(jvm, int_state);
+        }
         if let Some(_) = &guard.waiting_monitor_lock {
-            let guard = self.waiton.wait(guard).unwrap();
+            let guard = self.wait_with_stack_released(int_state, guard, None);
             drop(guard);
             return self.check(jvm, int_state);
         }
@@ -285,21 +496,26 @@ impl<'gc> SafePoint<'gc> {
                 None => Duration::new(0, 0),
                 Some(time_to_wait) => time_to_wait,
             });
-            let (mut guard, should_reacquire) = match time_to_wait {
-                None => {
-                    let guard = self.waiton.wait(guard).unwrap();
-                    let should_reacquire = guard.waiting_monitor_notify.is_none();
-                    (guard, should_reacquire)
-                }
-                Some(time_to_wait) => {
-                    let (guard, timeout) = self.waiton.wait_timeout(guard, time_to_wait).unwrap();
-                    let should_reacquire = if timeout.timed_out() { wait_until.unwrap().checked_duration_since(Instant::now()).is_none() } else { guard.waiting_monitor_notify.is_none() };
-                    (guard, should_reacquire)
+            let (mut guard, should_reacquire) = if guard.interrupted {
+                (guard, true)
+            } else {
+                match time_to_wait {
+                    None => {
+                        let guard = self.wait_with_stack_released(int_state, guard, None);
+                        let should_reacquire = guard.waiting_monitor_notify.is_none() || guard.interrupted;
+                        (guard, should_reacquire)
+                    }
+                    Some(time_to_wait) => {
+                        let guard = self.wait_with_stack_released(int_state, guard, Some(time_to_wait));
+                        let timed_out = wait_until.unwrap().checked_duration_since(Instant::now()).is_none();
+                        let should_reacquire = timed_out || guard.waiting_monitor_notify.is_none() || guard.interrupted;
+                        (guard, should_reacquire)
+                    }
                 }
             };
 
-            guard.waiting_monitor_notify = None;
             return if should_reacquire {
+                guard.waiting_monitor_notify = None;
                 let monitors_gaurd = jvm.thread_state.monitors.read().unwrap();
                 let monitor = &monitors_gaurd[monitor].clone();
                 drop(guard);
@@ -307,7 +523,8 @@ impl<'gc> SafePoint<'gc> {
                 monitor.notify_reacquire(jvm, int_state, prev_count)?;
                 self.check(jvm, int_state)
             } else {
-                drop(guard); //shouldn't need these but they are here for now b/c I'm paranoid
+                // spurious wakeup, keep waiting
+                drop(guard);
                 self.check(jvm, int_state)
             };
         }
@@ -315,6 +532,13 @@ impl<'gc> SafePoint<'gc> {
     }
 }
 
+pub fn interrupted_exception<'gc>(jvm: &'gc JVMState<'gc>, int_state: &mut impl PushableFrame<'gc>) -> WasException<'gc> {
+    match InterruptedException::new(jvm, int_state) {
+        Ok(interrupted_exception) => WasException { exception_obj: interrupted_exception.object().cast_throwable() },
+        Err(was_exception) => was_exception,
+    }
+}
+
 pub struct Monitor2 {
     pub id: MonitorID,
     monitor2_priv: RwLock<Monitor2Priv>,
@@ -335,6 +559,10 @@ impl Monitor2 {
         }
     }
 
+    pub fn owner(&self) -> Option<JavaThreadId> {
+        self.monitor2_priv.read().unwrap().owner
+    }
+
     pub fn lock<'l, 'gc>(&self, jvm: &'gc JVMState<'gc>, int_state: &mut impl HasFrame<'gc>) -> Result<(), WasException<'gc>> {
         let mut guard = self.monitor2_priv.write().unwrap();
         let current_thread = jvm.thread_state.get_current_thread();
@@ -414,7 +642,7 @@ impl Monitor2 {
         Ok(())
     }
 
-    pub fn wait<'gc, 'k>(&self, jvm: &'gc JVMState<'gc>, int_state: &mut impl HasFrame<'gc>, wait_duration: Option<Duration>) -> Result<(), WasException<'gc>> {
+    pub fn wait<'gc, 'k>(&self, jvm: &'gc JVMState<'gc>, int_state: &mut impl PushableFrame<'gc>, wait_duration: Option<Duration>) -> Result<(), WasException<'gc>> {
         if jvm.thread_tracing_options.trace_monitor_wait_enter {
             eprintln!("[{}] Monitor Wait: {}", current().name().unwrap_or("Unknown Thread"), self.id);
         }
@@ -427,6 +655,10 @@ impl Monitor2 {
         let current_thread = jvm.thread_state.get_current_thread();
         let prev_count = guard.count;
         if guard.owner == current_thread.java_tid.into() {
+            if current_thread.safepoint_state.is_interrupted(true) {
+                drop(guard);
+                return Err(interrupted_exception(jvm, int_state));
+            }
             guard.owner = None;
             guard.waiting_notify.insert(current_thread.java_tid);
             current_thread.safepoint_state.set_waiting_notify(self.id, wait_until, prev_count);
@@ -438,9 +670,15 @@ impl Monitor2 {
         safepoint_check(jvm, int_state).unwrap();
         assert_eq!(self.monitor2_priv.read().unwrap().owner, current_thread.java_tid.into());
         assert_eq!(self.monitor2_priv.read().unwrap().count, prev_count);
+        // still a waiter if woken by a timeout or interrupt rather than a notify. A notify which raced
+        // with an interrupt wins, and the interrupt is left pending.
+        let notified = !self.monitor2_priv.write().unwrap().waiting_notify.remove(&current_thread.java_tid);
         if jvm.thread_tracing_options.trace_monitor_wait_exit {
             eprintln!("[{}] Monitor Wait Exit: {}", current().name().unwrap_or("Unknown Thread"), self.id);
         }
+        if !notified && current_thread.safepoint_state.is_interrupted(true) {
+            return Err(interrupted_exception(jvm, int_state));
+        }
         Ok(())
     }
 
@@ -0,0 +1,230 @@
+use std::ffi::c_void;
+use std::io::Write;
+use std::mem::MaybeUninit;
+use std::ptr::null_mut;
+use std::sync::atomic::{AtomicI32, AtomicU32, Ordering};
+
+use libc::c_int;
+
+use jvmti_jni_bindings::JVMTI_THREAD_MAX_PRIORITY;
+use thread_signal_handler::{THREAD_PAUSE_SIGNAL_RAW, THREAD_RESTART_SIGNAL_RAW};
+
+use crate::{JVMState, PushableFrame};
+use crate::stdlib::java::lang::thread::JThread;
+use crate::stdlib::sun::misc::signal;
+use crate::threading::java_thread::JavaThread;
+use crate::threading::thread_dump::full_thread_dump;
+
+// Signals reach java in three steps. The handler only counts the signal and writes to a pipe,
+// since that is all that is async signal safe. The listener thread wakes on the pipe, prints
+// thread dumps for SIGQUIT itself, and unparks the Signal Dispatcher java thread for anything
+// else, which calls sun.misc.Signal.dispatch for each signal raised.
+
+const NSIG: usize = 65;
+
+// the special handler values passed to and returned from JVM_RegisterSignal
+pub const DEFAULT_HANDLER: usize = 0;
+pub const IGNORE_HANDLER: usize = 1;
+pub const JAVA_HANDLER: usize = 2;
+pub const RESERVED_HANDLER: usize = usize::MAX;
+
+static PENDING_SIGNALS: [AtomicU32; NSIG] = [const { AtomicU32::new(0) }; NSIG];
+static SIGNAL_PIPE_WRITE_FD: AtomicI32 = AtomicI32::new(-1);
+
+const SIGNAL_NAMES: [(&str, c_int); 33] = [
+    ("HUP", libc::SIGHUP),
+    ("INT", libc::SIGINT),
+    ("QUIT", libc::SIGQUIT),
+    ("ILL", libc::SIGILL),
+    ("TRAP", libc::SIGTRAP),
+    ("ABRT", libc::SIGABRT),
+    ("IOT", libc::SIGIOT),
+    ("BUS", libc::SIGBUS),
+    ("FPE", libc::SIGFPE),
+    ("KILL", libc::SIGKILL),
+    ("USR1", libc::SIGUSR1),
+    ("SEGV", libc::SIGSEGV),
+    ("USR2", libc::SIGUSR2),
+    ("PIPE", libc::SIGPIPE),
+    ("ALRM", libc::SIGALRM),
+    ("TERM", libc::SIGTERM),
+    ("STKFLT", libc::SIGSTKFLT),
+    ("CHLD", libc::SIGCHLD),
+    ("CONT", libc::SIGCONT),
+    ("STOP", libc::SIGSTOP),
+    ("TSTP", libc::SIGTSTP),
+    ("TTIN", libc::SIGTTIN),
+    ("TTOU", libc::SIGTTOU),
+    ("URG", libc::SIGURG),
+    ("XCPU", libc::SIGXCPU),
+    ("XFSZ", libc::SIGXFSZ),
+    ("VTALRM", libc::SIGVTALRM),
+    ("PROF", libc::SIGPROF),
+    ("WINCH", libc::SIGWINCH),
+    ("POLL", libc::SIGPOLL),
+    ("IO", libc::SIGIO),
+    ("PWR", libc::SIGPWR),
+    ("SYS", libc::SIGSYS),
+];
+
+pub fn find_signal(name: &str) -> Option<c_int> {
+    SIGNAL_NAMES.iter().find(|(signal_name, _)| *signal_name == name).map(|(_, signal)| *signal)
+}
+
+// used by the vm itself, so java can't handle them
+fn is_reserved(signal: c_int) -> bool {
+    signal == libc::SIGQUIT ||
+        signal == libc::SIGSEGV ||
+        signal == libc::SIGBUS ||
+        signal == libc::SIGFPE ||
+        signal == libc::SIGILL ||
+        signal == libc::SIGKILL ||
+        signal == libc::SIGSTOP ||
+        signal == THREAD_PAUSE_SIGNAL_RAW ||
+        signal == THREAD_RESTART_SIGNAL_RAW
+}
+
+// the signals which run shutdown hooks, which shouldn't be handled if the launching shell ignores them, e.g. with nohup
+fn is_shutdown_signal(signal: c_int) -> bool {
+    signal == libc::SIGHUP || signal == libc::SIGINT || signal == libc::SIGTERM
+}
+
+fn in_range(signal: c_int) -> bool {
+    signal > 0 && (signal as usize) < NSIG
+}
+
+// only async signal safe things in here
+extern "C" fn signal_handler(signal: c_int) {
+    PENDING_SIGNALS[signal as usize].fetch_add(1, Ordering::SeqCst);
+    let fd = SIGNAL_PIPE_WRITE_FD.load(Ordering::SeqCst);
+    if fd >= 0 {
+        let request = 0u8;
+        unsafe { libc::write(fd, &request as *const u8 as *const c_void, 1); }
+    }
+}
+
+unsafe fn current_handler(signal: c_int) -> usize {
+    let mut old = MaybeUninit::<libc::sigaction>::zeroed();
+    libc::sigaction(signal, null_mut(), old.as_mut_ptr());
+    old.assume_init().sa_sigaction
+}
+
+unsafe fn set_handler(signal: c_int, handler: usize) -> Option<usize> {
+    let mut new: libc::sigaction = MaybeUninit::zeroed().assume_init();
+    new.sa_sigaction = handler;
+    new.sa_flags = libc::SA_RESTART;
+    libc::sigemptyset(&mut new.sa_mask);
+    let mut old = MaybeUninit::<libc::sigaction>::zeroed();
+    if libc::sigaction(signal, &new, old.as_mut_ptr()) != 0 {
+        return None;
+    }
+    Some(old.assume_init().sa_sigaction)
+}
+
+// Installs handler, one of the special handler values or a native handler address, returning
+// the previous handler in the same form, or RESERVED_HANDLER if the signal can't be handled.
+pub fn register_signal(signal: c_int, handler: usize) -> usize {
+    if !in_range(signal) || is_reserved(signal) {
+        return RESERVED_HANDLER;
+    }
+    unsafe {
+        if is_shutdown_signal(signal) && current_handler(signal) == libc::SIG_IGN {
+            return IGNORE_HANDLER;
+        }
+        let handler = if handler == JAVA_HANDLER { signal_handler as usize } else { handler };
+        match set_handler(signal, handler) {
+            None => RESERVED_HANDLER,
+            Some(old) if old == signal_handler as usize => JAVA_HANDLER,
+            Some(old) => old,
+        }
+    }
+}
+
+pub fn raise_signal(signal: c_int) -> bool {
+    if !in_range(signal) {
+        return false;
+    }
+    unsafe {
+        if is_shutdown_signal(signal) && current_handler(signal) == libc::SIG_IGN {
+            return false;
+        }
+        libc::raise(signal) == 0
+    }
+}
+
+fn take_pending(signal: c_int) -> u32 {
+    PENDING_SIGNALS[signal as usize].swap(0, Ordering::SeqCst)
+}
+
+fn print_thread_dump<'gc>(jvm: &'gc JVMState<'gc>) {
+    let dump = full_thread_dump(jvm, None);
+    let stdout = std::io::stdout();
+    let mut stdout = stdout.lock();
+    stdout.write_all(dump.as_bytes()).unwrap();
+    stdout.flush().unwrap();
+}
+
+// kill -3 prints a thread dump to stdout, like hotspot. Signals java registered handlers for
+// before the Signal Dispatcher starts stay pending until it does.
+pub fn install_signal_handlers<'gc>(jvm: &'gc JVMState<'gc>) {
+    let mut fds = [0 as c_int; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        panic!("unable to create signal pipe");
+    }
+    let [read_fd, write_fd] = fds;
+    //a full pipe already has a wakeup queued, and the handler mustn't block waiting for the listener
+    if unsafe { libc::fcntl(write_fd, libc::F_SETFL, libc::O_NONBLOCK) } != 0 {
+        panic!("unable to make signal pipe non-blocking");
+    }
+    SIGNAL_PIPE_WRITE_FD.store(write_fd, Ordering::SeqCst);
+    let listener = jvm.thread_state.threads.create_thread(Some("Signal Listener".to_string()), None);
+    listener.start_thread(Box::new(move |_| {
+        loop {
+            let mut request = 0u8;
+            let read = unsafe { libc::read(read_fd, &mut request as *mut u8 as *mut c_void, 1) };
+            if read < 0 && std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted {
+                continue;
+            }
+            if read != 1 {
+                break;
+            }
+            for _ in 0..take_pending(libc::SIGQUIT) {
+                print_thread_dump(jvm);
+            }
+            if let Some(dispatcher) = jvm.thread_state.signal_dispatcher.read().unwrap().as_ref() {
+                dispatcher.safepoint_state.set_unpark();
+            }
+        }
+    }), Box::new(()));
+    unsafe { set_handler(libc::SIGQUIT, signal_handler as usize).unwrap(); }
+}
+
+// Started during vm init, on the main thread, like hotspot's Signal Dispatcher it is a daemon thread in the system group.
+pub fn start_signal_dispatcher<'gc>(jvm: &'gc JVMState<'gc>, int_state: &mut impl PushableFrame<'gc>) {
+    let system_thread_group = jvm.thread_state.get_system_thread_group();
+    let thread_object = JThread::new(jvm, int_state, system_thread_group.clone(), "Signal Dispatcher".to_string()).expect("todo");
+    thread_object.set_daemon(true);
+    thread_object.set_priority(JVMTI_THREAD_MAX_PRIORITY as i32);
+    //never goes through Thread.start, which is what normally adds a thread to its group
+    system_thread_group.add(jvm, int_state, thread_object.clone()).expect("todo");
+    JavaThread::background_new_with_stack(jvm, Some(thread_object), false, move |dispatcher, opaque_frame| {
+        // set before the first scan of pending signals, so the listener can't miss unparking it
+        *jvm.thread_state.signal_dispatcher.write().unwrap() = Some(dispatcher.clone());
+        if let Some(jvmti) = jvm.jvmti_state() {
+            jvmti.built_in_jdwp.thread_start(jvm, opaque_frame, dispatcher.thread_object())
+        }
+        loop {
+            for number in 1..NSIG as c_int {
+                if number == libc::SIGQUIT {
+                    continue;
+                }
+                for _ in 0..take_pending(number) {
+                    if let Err(exception) = signal::dispatch(jvm, opaque_frame, number) {
+                        exception.exception_obj.print_stack_trace(jvm, opaque_frame).expect("Exception occurred while printing exception. Something is pretty messed up");
+                    }
+                }
+            }
+            dispatcher.park(jvm, opaque_frame, None)?;
+        }
+    }).expect("todo");
+}
@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::fmt::Write;
+use std::ptr::NonNull;
+
+use itertools::Itertools;
+use wtf8::Wtf8Buf;
+
+use classfile_view::view::{ClassView, HasAccessFlags};
+use classfile_view::view::attribute_view::SourceFileView;
+use classfile_view::view::ptype_view::PTypeView;
+use jvmti_jni_bindings::{JVMTI_THREAD_STATE_ALIVE, JVMTI_THREAD_STATE_BLOCKED_ON_MONITOR_ENTER, JVMTI_THREAD_STATE_IN_OBJECT_WAIT, JVMTI_THREAD_STATE_PARKED, JVMTI_THREAD_STATE_SLEEPING, JVMTI_THREAD_STATE_TERMINATED, JVMTI_THREAD_STATE_WAITING_WITH_TIMEOUT};
+use rust_jvm_common::classfile::LineNumber;
+use rust_jvm_common::JavaThreadId;
+
+use crate::better_java_stack::frame_iter::FrameIterFrameRef;
+use crate::better_java_stack::frames::{HasFrame, PushableFrame};
+use crate::exceptions::WasException;
+use crate::JVMState;
+use crate::stdlib::java::lang::stack_trace_element::StackTraceElement;
+use crate::stdlib::java::lang::string::JString;
+use crate::threading::deadlocks::{find_monitor_deadlocks, WaitingToLock};
+use crate::threading::java_thread::JavaThread;
+use crate::threading::safepoints::{BlockedOnMonitor, MonitorID};
+use crate::utils::lookup_line_number;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DumpedFrameKind {
+    Interpreted,
+    Compiled,
+    Native,
+}
+
+// A java frame copied out of a stack, so that it outlives the view of the stack it came from.
+#[derive(Clone, Debug)]
+pub struct DumpedFrame {
+    pub class_name: String,
+    pub method_name: String,
+    pub source_file: Option<Wtf8Buf>,
+    pub line_number: Option<LineNumber>,
+    pub kind: DumpedFrameKind,
+}
+
+impl DumpedFrame {
+    // None for opaque frames
+    pub fn from_frame<'gc>(jvm: &'gc JVMState<'gc>, frame: &FrameIterFrameRef<'gc, '_>) -> Option<Self> {
+        let declaring_class = frame.try_class_pointer(jvm).ok()?;
+        let declaring_class_view = declaring_class.view();
+        let method_view = declaring_class_view.method_view_i(frame.method_i());
+        let kind = if method_view.is_native() {
+            DumpedFrameKind::Native
+        } else if frame.is_interpreted() {
+            DumpedFrameKind::Interpreted
+        } else {
+            DumpedFrameKind::Compiled
+        };
+        Some(Self {
+            class_name: PTypeView::from_compressed(declaring_class_view.type_(), &jvm.string_pool).class_name_representation(),
+            method_name: method_view.name().0.to_str(&jvm.string_pool),
+            source_file: declaring_class_view.sourcefile_attr().map(|sourcefile: SourceFileView| sourcefile.file()),
+            line_number: method_view.line_number_table().and_then(|line_number_table| lookup_line_number(line_number_table, frame)),
+            kind,
+        })
+    }
+
+    pub fn from_frames<'gc, 'k>(jvm: &'gc JVMState<'gc>, frames: impl Iterator<Item=FrameIterFrameRef<'gc, 'k>>) -> Vec<Self> {
+        //todo handle opaque frames properly
+        frames.filter_map(|frame| Self::from_frame(jvm, &frame)).collect_vec()
+    }
+
+    pub fn stack_trace_element<'gc>(&self, jvm: &'gc JVMState<'gc>, int_state: &mut impl PushableFrame<'gc>) -> Result<StackTraceElement<'gc>, WasException<'gc>> {
+        let declaring_class_name = JString::from_rust(jvm, int_state, Wtf8Buf::from_string(self.class_name.clone()))?;
+        let method_name = JString::from_rust(jvm, int_state, Wtf8Buf::from_string(self.method_name.clone()))?;
+        let source_file = self.source_file.clone().unwrap_or_else(|| Wtf8Buf::from_string("unknown_source".to_string()));
+        let source_file_name = JString::from_rust(jvm, int_state, source_file)?;
+        StackTraceElement::new(jvm, int_state, declaring_class_name, method_name, source_file_name, self.line_number.unwrap_or(LineNumber(u16::MAX)))
+    }
+
+    fn location(&self) -> String {
+        if self.kind == DumpedFrameKind::Native {
+            return "Native Method".to_string();
+        }
+        let source_file = match self.source_file.as_ref() {
+            None => return "Unknown Source".to_string(),
+            Some(source_file) => source_file.to_string_lossy().to_string(),
+        };
+        match self.line_number {
+            None => source_file,
+            Some(LineNumber(line_number)) => format!("{}:{}", source_file, line_number),
+        }
+    }
+}
+
+fn java_thread_state(status: u32) -> &'static str {
+    if status & JVMTI_THREAD_STATE_ALIVE == 0 {
+        return if status & JVMTI_THREAD_STATE_TERMINATED != 0 { "TERMINATED" } else { "NEW" };
+    }
+    let timed = status & JVMTI_THREAD_STATE_WAITING_WITH_TIMEOUT != 0;
+    if status & JVMTI_THREAD_STATE_BLOCKED_ON_MONITOR_ENTER != 0 {
+        "BLOCKED (on object monitor)"
+    } else if status & JVMTI_THREAD_STATE_IN_OBJECT_WAIT != 0 {
+        if timed { "TIMED_WAITING (on object monitor)" } else { "WAITING (on object monitor)" }
+    } else if status & JVMTI_THREAD_STATE_SLEEPING != 0 {
+        "TIMED_WAITING (sleeping)"
+    } else if status & JVMTI_THREAD_STATE_PARKED != 0 {
+        if timed { "TIMED_WAITING (parking)" } else { "WAITING (parking)" }
+    } else {
+        "RUNNABLE"
+    }
+}
+
+// object monitors are keyed by object, so finding the object for a monitor requires a reverse lookup.
+// raw monitors have no object
+pub(crate) fn monitor_objects<'gc>(jvm: &'gc JVMState<'gc>) -> HashMap<MonitorID, *const c_void> {
+    jvm.object_monitors.read().unwrap().iter().map(|(object, monitor)| (monitor.id, *object)).collect()
+}
+
+pub(crate) fn describe_object<'gc>(jvm: &'gc JVMState<'gc>, object: *const c_void) -> String {
+    let allocated_type = jvm.gc.memory_region.lock().unwrap().find_object_allocated_type(NonNull::new(object as *mut c_void).unwrap()).as_cpdtype();
+    let class_name = PTypeView::from_compressed(allocated_type, &jvm.string_pool).class_name_representation();
+    format!("<{:#018x}> (a {})", object as usize, class_name)
+}
+
+fn thread_name<'gc>(jvm: &'gc JVMState<'gc>, java_thread: &JavaThread<'gc>) -> String {
+    java_thread.try_thread_object()
+        .and_then(|thread_object| thread_object.try_name(jvm))
+        .map(|name| name.to_rust_string(jvm))
+        .unwrap_or_else(|| "<unnamed>".to_string())
+}
+
+fn write_frames<'gc>(jvm: &'gc JVMState<'gc>, res: &mut String, java_thread: &JavaThread<'gc>, frames: Option<&Vec<DumpedFrame>>, monitor_objects: &HashMap<MonitorID, *const c_void>) {
+    let frames = match frames {
+        None => {
+            writeln!(res, "\t<stack unavailable, thread did not reach a point where it could be viewed>").unwrap();
+            return;
+        }
+        Some(frames) => frames,
+    };
+    for (i, frame) in frames.iter().enumerate() {
+        let compiled = if frame.kind == DumpedFrameKind::Compiled { " (compiled)" } else { "" };
+        writeln!(res, "\tat {}.{}({}){}", frame.class_name, frame.method_name, frame.location(), compiled).unwrap();
+        if i == 0 {
+            match java_thread.safepoint_state.blocked_on_monitor() {
+                Some(BlockedOnMonitor::Enter(monitor)) => if let Some(object) = monitor_objects.get(&monitor) {
+                    writeln!(res, "\t- waiting to lock {}", describe_object(jvm, *object)).unwrap();
+                },
+                Some(BlockedOnMonitor::Wait(monitor)) => if let Some(object) = monitor_objects.get(&monitor) {
+                    writeln!(res, "\t- waiting on {}", describe_object(jvm, *object)).unwrap();
+                },
+                None => {}
+            }
+        }
+    }
+}
+
+fn dump_thread<'gc>(jvm: &'gc JVMState<'gc>, res: &mut String, java_thread: &JavaThread<'gc>, frames: Option<&Vec<DumpedFrame>>, monitor_objects: &HashMap<MonitorID, *const c_void>) {
+    let thread_object = match java_thread.try_thread_object() {
+        None => return,
+        Some(thread_object) => thread_object,
+    };
+    let daemon = if thread_object.daemon(jvm) { " daemon" } else { "" };
+    writeln!(res, "\"{}\" #{}{} prio={}", thread_name(jvm, java_thread), java_thread.java_tid, daemon, thread_object.priority(jvm)).unwrap();
+    writeln!(res, "   java.lang.Thread.State: {}", java_thread_state(java_thread.status_number() as u32)).unwrap();
+    write_frames(jvm, res, java_thread, frames, monitor_objects);
+    let locked = owned_monitors(jvm, java_thread.java_tid).into_iter()
+        .filter_map(|monitor| monitor_objects.get(&monitor))
+        .map(|object| describe_object(jvm, *object))
+        .collect_vec();
+    writeln!(res, "\n   Locked monitors:").unwrap();
+    if locked.is_empty() {
+        writeln!(res, "\t- None").unwrap();
+    }
+    for object in locked {
+        writeln!(res, "\t- {}", object).unwrap();
+    }
+    writeln!(res).unwrap();
+}
+
+fn write_deadlocks<'gc>(jvm: &'gc JVMState<'gc>, res: &mut String, frames: &HashMap<JavaThreadId, Option<Vec<DumpedFrame>>>, monitor_objects: &HashMap<MonitorID, *const c_void>) {
+    let deadlocks = find_monitor_deadlocks(jvm);
+    for cycle in deadlocks.iter() {
+        writeln!(res, "Found one Java-level deadlock:\n=============================").unwrap();
+        for WaitingToLock { thread, monitor, owner } in cycle.iter() {
+            let object = monitor_objects.get(monitor).map(|object| describe_object(jvm, *object)).unwrap_or_else(|| format!("raw monitor {}", monitor));
+            let owner_name = jvm.thread_state.try_get_thread_by_tid(*owner).map(|owner| thread_name(jvm, &owner)).unwrap_or_else(|| "<exited>".to_string());
+            writeln!(res, "\"{}\":\n  waiting to lock {},\n  which is held by \"{}\"", thread_name(jvm, thread), object, owner_name).unwrap();
+        }
+        writeln!(res, "\nJava stack information for the threads listed above:\n===================================================").unwrap();
+        for WaitingToLock { thread, .. } in cycle.iter() {
+            writeln!(res, "\"{}\":", thread_name(jvm, thread)).unwrap();
+            write_frames(jvm, res, thread, frames.get(&thread.java_tid).and_then(|frames| frames.as_ref()), monitor_objects);
+        }
+        writeln!(res).unwrap();
+    }
+    if !deadlocks.is_empty() {
+        writeln!(res, "Found {} deadlock{}.\n", deadlocks.len(), if deadlocks.len() == 1 { "" } else { "s" }).unwrap();
+    }
+}
+
+pub(crate) fn owned_monitors<'gc>(jvm: &'gc JVMState<'gc>, java_tid: JavaThreadId) -> Vec<MonitorID> {
+    jvm.thread_state.monitors.read().unwrap().iter()
+        .filter(|monitor| monitor.owner() == Some(java_tid))
+        .map(|monitor| monitor.id)
+        .collect()
+}
+
+// The current thread can't view its own stack remotely, so its frames are passed in if it is a java thread.
+pub fn full_thread_dump<'gc>(jvm: &'gc JVMState<'gc>, current_thread_frames: Option<Vec<DumpedFrame>>) -> String {
+    let monitor_objects = monitor_objects(jvm);
+    let mut res = String::new();
+    writeln!(res, "Full thread dump:\n").unwrap();
+    let threads = jvm.thread_state.get_all_alive_threads().into_iter()
+        .filter(|thread| !thread.invisible_to_java)
+        .sorted_by_key(|thread| thread.java_tid)
+        .collect_vec();
+    let mut current_thread_frames = current_thread_frames;
+    let mut frames = HashMap::new();
+    for java_thread in threads.iter() {
+        let thread_frames = if java_thread.is_this_thread() {
+            current_thread_frames.take()
+        } else {
+            java_thread.stack_snapshot(jvm)
+        };
+        dump_thread(jvm, &mut res, java_thread, thread_frames.as_ref(), &monitor_objects);
+        frames.insert(java_thread.java_tid, thread_frames);
+    }
+    write_deadlocks(jvm, &mut res, &frames, &monitor_objects);
+    res
+}
@@ -1,7 +1,8 @@
 use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 use std::mem::transmute;
-use std::sync::{Arc, RwLock, RwLockReadGuard};
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard};
+use std::sync::atomic::{AtomicBool, AtomicU32};
 use std::sync::mpsc::channel;
 use std::thread::{LocalKey, Scope};
 use itertools::{Itertools};
@@ -29,10 +30,19 @@ pub struct ThreadState<'gc> {
     pub interrupter: ThreadSignalBasedInterrupter,
     // threads_locals: RwLock<HashMap<ThreadId, Arc<FastPerThreadData>>>,
     pub(crate) main_thread: RwLock<Option<Arc<JavaThread<'gc>>>>,
+    pub(crate) signal_dispatcher: RwLock<Option<Arc<JavaThread<'gc>>>>,
     pub(crate) all_java_threads: RwLock<HashMap<JavaThreadId, Arc<JavaThread<'gc>>>>,
     current_java_thread: &'static LocalKey<RefCell<Option<Arc<JavaThread<'static>>>>>,
     pub system_thread_group: RwLock<Option<JThreadGroup<'gc>>>,
     pub(crate) monitors: RwLock<Vec<Arc<Monitor2>>>,
+    // polled by compiled code on loop back edges and returns, the number of threads with a reason to stop
+    safepoint_poll_word: AtomicU32,
+    safepoint_operation_lock: Mutex<()>,
+    // both are always measured, these only control whether management reports them
+    pub thread_cpu_time_enabled: AtomicBool,
+    pub thread_allocated_memory_enabled: AtomicBool,
+    // the launcher exits with 1 if main threw
+    pub main_thread_uncaught_exception: AtomicBool,
 }
 
 
@@ -42,13 +52,48 @@ impl<'gc> ThreadState<'gc> {
             threads: Threads::new(scope),
             interrupter: ThreadSignalBasedInterrupter::sigaction_setup(),
             main_thread: RwLock::new(None),
+            signal_dispatcher: RwLock::new(None),
             all_java_threads: RwLock::new(HashMap::new()),
             current_java_thread: &CURRENT_JAVA_THREAD,
             system_thread_group: RwLock::new(None),
             monitors: RwLock::new(vec![]),
+            safepoint_poll_word: AtomicU32::new(0),
+            safepoint_operation_lock: Mutex::new(()),
+            thread_cpu_time_enabled: AtomicBool::new(true),
+            thread_allocated_memory_enabled: AtomicBool::new(true),
+            main_thread_uncaught_exception: AtomicBool::new(false),
         }
     }
 
+    pub fn safepoint_poll_address(&self) -> *const AtomicU32 {
+        &self.safepoint_poll_word as *const AtomicU32
+    }
+
+    pub(crate) fn safepoint_poll_word(&self) -> &AtomicU32 {
+        &self.safepoint_poll_word
+    }
+
+    // Stops every other java thread at a safepoint, runs vm_operation, then lets them continue.
+    pub fn at_safepoint<T>(&self, vm_operation: impl FnOnce() -> T) -> T {
+        let _operation_guard = self.safepoint_operation_lock.lock().unwrap();
+        let current_tid = self.try_get_current_thread().map(|current_thread| current_thread.java_tid);
+        let threads = self.all_java_threads.read().unwrap().values()
+            .filter(|thread| Some(thread.java_tid) != current_tid && thread.is_alive())
+            .cloned()
+            .collect_vec();
+        for thread in threads.iter() {
+            thread.safepoint_state.set_gc_suspended().unwrap();
+        }
+        for thread in threads.iter() {
+            thread.safepoint_state.wait_until_at_safepoint();
+        }
+        let res = vm_operation();
+        for thread in threads.iter() {
+            thread.safepoint_state.set_gc_unsuspended().unwrap();
+        }
+        res
+    }
+
     pub(crate) fn debug_assert(&self, jvm: &'gc JVMState<'gc>){
         self.all_java_threads.read().unwrap().values().for_each(|thread|{
             let normal_object = match thread.thread_object.read().unwrap().as_ref() {
@@ -227,9 +272,8 @@ impl<'gc> ThreadState<'gc> {
 
         //todo fix loader
         if let Err(WasException { exception_obj }) = java_thread.thread_object().run(jvm, opaque_frame) {
-            exception_obj.print_stack_trace(jvm, opaque_frame)
-                .expect("Exception occurred while printing exception. Something is pretty messed up");
-            todo!()
+            //like hotspot, an exception thrown by the handler itself is ignored
+            let _ = java_thread.thread_object().dispatch_uncaught_exception(jvm, opaque_frame, exception_obj);
         };
         if let Err(WasException { exception_obj }) = java_thread.thread_object().exit(jvm, opaque_frame) {
             eprintln!("Exception occurred exiting thread, something is pretty messed up");
@@ -248,19 +292,16 @@ impl<'gc> ThreadState<'gc> {
             .read()
             .unwrap()
             .values()
-            .filter(|_thread| {
-                //don't use is_alive for this
-                // todo!()
-                true
-                // thread.thread_object().is_alive(jvm, int_state) != 0
+            .filter(|thread| {
+                //don't use Thread.isAlive for this, the thread object may not be set yet
+                thread.is_alive()
             })
             .cloned()
             .collect::<Vec<_>>()
     }
 
     pub fn get_system_thread_group(&self) -> JThreadGroup<'gc> {
-        todo!()
-        /*self.system_thread_group.read().unwrap().as_ref().unwrap().clone()*/
+        self.system_thread_group.read().unwrap().as_ref().unwrap().clone()
     }
 
     pub fn wait_all_non_daemon_threads(&self, jvm: &'gc JVMState<'gc>) {
@@ -269,7 +310,8 @@ impl<'gc> ThreadState<'gc> {
             let all_threads = self.all_java_threads.read().unwrap().values().cloned().collect_vec();
             drop(self.all_java_threads.read().unwrap());
             for thread in all_threads.iter(){
-                if !thread.invisible_to_java && !thread.is_daemon(jvm) && thread.is_alive(){
+                //the thread shutting down the vm doesn't wait for itself
+                if !thread.invisible_to_java && !thread.is_daemon(jvm) && thread.is_alive() && !thread.is_this_thread(){
                     thread.wait_thread_exit();
                 }
             }
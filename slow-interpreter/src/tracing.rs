@@ -2,6 +2,7 @@ use std::sync::RwLock;
 
 use jvmti_jni_bindings::{jvmtiError, jvmtiError_JVMTI_ERROR_NONE};
 use rust_jvm_common::classnames::ClassName;
+use rust_jvm_common::compressed_classfile::class_names::CClassName;
 use rust_jvm_common::compressed_classfile::compressed_types::CPDType;
 use rust_jvm_common::compressed_classfile::method_names::MethodName;
 use rust_jvm_common::compressed_classfile::string_pool::CompressedClassfileStringPool;
@@ -17,8 +18,8 @@ pub struct TracingSettings {
     pub trace_function_start: RwLock<bool>,
     trace_jni_register: bool,
     _trace_jni_dynamic_link: bool,
-    //todo implement this trace
-    // trace_class_loads: bool,
+    //-verbose:class
+    pub trace_class_loads: bool,
     trace_jdwp_events: bool,
     trace_jdwp_function_enter: bool,
     trace_jdwp_function_exit: bool,
@@ -36,7 +37,7 @@ impl TracingSettings {
             trace_function_start: RwLock::new(false),
             trace_jni_register: false,
             _trace_jni_dynamic_link: false,
-            // trace_class_loads: false,
+            trace_class_loads: false,
             trace_jdwp_events: true,
             trace_jdwp_function_enter: true,
             trace_jdwp_function_exit: true, //todo parse this from options in future
@@ -54,7 +55,7 @@ impl TracingSettings {
             trace_function_start: RwLock::new(false),
             trace_jni_register: false,
             _trace_jni_dynamic_link: false,
-            // trace_class_loads: false,
+            trace_class_loads: false,
             trace_jdwp_events: false,
             trace_jdwp_function_enter: false,
             trace_jdwp_function_exit: false,
@@ -66,6 +67,19 @@ impl TracingSettings {
         }
     }
 
+    pub fn trace_class_load(&self, pool: &CompressedClassfileStringPool, class_name: CClassName, source: &str) {
+        if self.trace_class_loads {
+            //same format as hotspot 8
+            println!("[Loaded {} from {}]", class_name.0.to_str(pool).replace('/', "."), source);
+        }
+    }
+
+    pub fn trace_class_unload(&self, pool: &CompressedClassfileStringPool, class_name: CClassName) {
+        if self.trace_class_loads {
+            println!("[Unloading class {}]", class_name.0.to_str(pool).replace('/', "."));
+        }
+    }
+
     pub fn trace_function_enter<'l>(&self, pool: &'l CompressedClassfileStringPool, classname: &'l CPDType, meth_name: &'l MethodName, method_desc: &'l str, current_depth: usize, threadtid: JavaThreadId) -> FunctionEnterExitTraceGuard<'l> {
         // unsafe {
         // if TIMES > 25000000 && !classname.class_name_representation().contains("java") && !classname.class_name_representation().contains("google")
@@ -0,0 +1,32 @@
+use another_jit_vm::Register;
+use another_jit_vm_ir::compiler::{IRInstr, IRLabel, Size};
+use another_jit_vm_ir::vm_exit_abi::IRVMExitType;
+
+use crate::compiler::{array_into_iter, CurrentInstructionCompilerData};
+use crate::compiler_common::{JavaCompilerMethodAndFrameData, MethodResolver};
+
+// uncommon trap. frame state at the start of an instruction matches the interpreter's, so the
+// interpreter can continue from the current instruction with the current stack depth.
+fn deoptimize(method_frame_data: &JavaCompilerMethodAndFrameData, current_instr_data: &CurrentInstructionCompilerData) -> IRInstr {
+    IRInstr::VMExit2 {
+        exit_type: IRVMExitType::Deoptimize {
+            method_id: method_frame_data.current_method_id,
+            java_pc: current_instr_data.current_offset,
+            stack_depth: method_frame_data.operand_stack_depth(current_instr_data.current_index),
+        }
+    }
+}
+
+// guards code relying on an assumption class loading can break, must be at the start of an instruction
+pub fn deoptimize_if_invalidated<'vm>(resolver: &impl MethodResolver<'vm>, method_frame_data: &JavaCompilerMethodAndFrameData, current_instr_data: &mut CurrentInstructionCompilerData) -> impl Iterator<Item=IRInstr> {
+    let invalidated = Register(1);
+    let invalidation_word_address = Register(2);
+    let still_valid = current_instr_data.compiler_labeler.local_label();
+    array_into_iter([
+        IRInstr::Const64bit { to: invalidation_word_address, const_: resolver.invalidation_word_address(method_frame_data.current_method_id) as u64 },
+        IRInstr::Load { to: invalidated, from_address: invalidation_word_address, size: Size::int() },
+        IRInstr::BranchEqualVal { a: invalidated, const_: 0, label: still_valid, size: Size::int() },
+        deoptimize(method_frame_data, current_instr_data),
+        IRInstr::Label(IRLabel { name: still_valid }),
+    ])
+}
@@ -3,11 +3,12 @@ use std::mem::size_of;
 use itertools::Either;
 
 use another_jit_vm::{FramePointerOffset, Register};
-use another_jit_vm_ir::compiler::{IRCallTarget, IRInstr, RestartPointGenerator};
+use another_jit_vm_ir::compiler::{IRCallTarget, IRInstr, RestartPointGenerator, RestartPointID, Size};
 use another_jit_vm_ir::vm_exit_abi::IRVMExitType;
 use another_jit_vm_ir::vm_exit_abi::register_structs::{InvokeInterfaceResolve, InvokeVirtualResolve};
 use gc_memory_layout_common::frame_layout::{FRAME_HEADER_END_OFFSET, FrameHeader};
 use jvmti_jni_bindings::jlong;
+use rust_jvm_common::MethodId;
 use rust_jvm_common::compressed_classfile::compressed_types::{CMethodDescriptor, CompressedParsedDescriptorType, CPRefType};
 use rust_jvm_common::compressed_classfile::method_names::MethodName;
 
@@ -15,6 +16,7 @@ use rust_jvm_common::compressed_classfile::method_names::MethodName;
 use rust_jvm_common::method_shape::MethodShape;
 
 use crate::compiler::{array_into_iter, CurrentInstructionCompilerData, MethodRecompileConditions, NeedsRecompileIf};
+use crate::compiler::deoptimize::deoptimize_if_invalidated;
 use crate::compiler_common::{JavaCompilerMethodAndFrameData, MethodResolver};
 
 pub fn invokespecial<'vm>(
@@ -165,7 +167,7 @@ pub fn invokestatic<'vm>(
 pub fn invokevirtual<'vm>(
     resolver: &impl MethodResolver<'vm>,
     method_frame_data: &JavaCompilerMethodAndFrameData,
-    current_instr_data: CurrentInstructionCompilerData,
+    mut current_instr_data: CurrentInstructionCompilerData,
     restart_point_generator: &mut RestartPointGenerator,
     recompile_conditions: &mut MethodRecompileConditions,
     method_name: MethodName,
@@ -187,7 +189,7 @@ pub fn invokevirtual<'vm>(
         None => {
             recompile_conditions.add_condition(NeedsRecompileIf::ClassLoaded { class: target_class_type });
             //todo this should never happen?
-            return Either::Left(array_into_iter([restart_point,
+            return Either::Left(Either::Left(array_into_iter([restart_point,
                 IRInstr::VMExit2 {
                     exit_type: IRVMExitType::InitClassAndRecompile {
                         class: target_class_type_id,
@@ -195,9 +197,15 @@ pub fn invokevirtual<'vm>(
                         restart_point_id,
                         java_pc: current_instr_data.current_offset,
                     },
-                }, after_call_restart_point]));
+                }, after_call_restart_point])));
         }
     };
+    let method_shape = MethodShape { name: method_name, desc: descriptor.clone() };
+    //natives are run by the resolve exit rather than called directly, so only devirtualize calls to java code
+    if let Some((method_id, false)) = resolver.lookup_unique_implementation(target_class_type, &method_shape) {
+        recompile_conditions.add_condition(NeedsRecompileIf::ImplementationNotUnique { on: target_class_type, method_shape, method_id });
+        return Either::Left(Either::Right(devirtualized_invoke(resolver, method_frame_data, &mut current_instr_data, recompile_conditions, restart_point_id, after_call_restart_point_id, descriptor, method_id)));
+    }
     let num_args = descriptor.arg_types.len();
 
     // todo investigate size of table for invokevirtual without tagging.
@@ -239,7 +247,7 @@ pub fn invokevirtual<'vm>(
 pub fn invoke_interface<'vm>(
     resolver: &impl MethodResolver<'vm>,
     method_frame_data: &JavaCompilerMethodAndFrameData,
-    current_instr_data: &CurrentInstructionCompilerData,
+    current_instr_data: &mut CurrentInstructionCompilerData,
     restart_point_generator: &mut RestartPointGenerator,
     recompile_conditions: &mut MethodRecompileConditions,
     method_name: &MethodName,
@@ -256,6 +264,13 @@ pub fn invoke_interface<'vm>(
     let after_call_restart_point = IRInstr::RestartPoint(after_call_restart_point_id);
     let method_shape = MethodShape { name: *method_name, desc: descriptor.clone() };
     let method_shape_id = resolver.lookup_method_shape(method_shape.clone());
+    if resolver.lookup_interface_id(target_class_cpdtype).is_some() {
+        //same as invokevirtual, natives go through the resolve exit
+        if let Some((method_id, false)) = resolver.lookup_unique_implementation(target_class_cpdtype, &method_shape) {
+            recompile_conditions.add_condition(NeedsRecompileIf::ImplementationNotUnique { on: target_class_cpdtype, method_shape, method_id });
+            return Either::Left(Either::Right(devirtualized_invoke(resolver, method_frame_data, current_instr_data, recompile_conditions, restart_point_id, after_call_restart_point_id, descriptor, method_id)));
+        }
+    }
     match resolver.lookup_interface_id(target_class_cpdtype) {
         None => {
             recompile_conditions.add_condition(NeedsRecompileIf::ClassLoaded { class: target_class_cpdtype });//todo this could be part of method resolver so that stuff always gets recompiled as needed
@@ -270,7 +285,7 @@ pub fn invoke_interface<'vm>(
                 }, after_call_restart_point]))
         }
         Some(interface_id) => {
-            Either::Left(
+            Either::Left(Either::Left(
                 array_into_iter([
                     restart_point,
                     IRInstr::ITableLookupOrExit {
@@ -291,7 +306,7 @@ pub fn invoke_interface<'vm>(
                     IRInstr::IRCall {
                         temp_register_1: Register(1),
                         temp_register_2: Register(2),
-                        arg_from_to_offsets: virtual_and_special_arg_offsets(method_frame_data, &current_instr_data, descriptor),
+                        arg_from_to_offsets: virtual_and_special_arg_offsets(method_frame_data, current_instr_data, descriptor),
                         return_value: if descriptor.return_type.is_void() {
                             None
                         } else {
@@ -306,7 +321,68 @@ pub fn invoke_interface<'vm>(
                         current_frame_size: method_frame_data.full_frame_size(),
                     }
                     , after_call_restart_point])
-            )
+            ))
+        }
+    }
+}
+
+// Direct call to the only loaded implementation, see NeedsRecompileIf::ImplementationNotUnique. A frame still
+// running this code after a new implementation was loaded deoptimizes instead of making the call.
+fn devirtualized_invoke<'vm>(
+    resolver: &impl MethodResolver<'vm>,
+    method_frame_data: &JavaCompilerMethodAndFrameData,
+    current_instr_data: &mut CurrentInstructionCompilerData,
+    recompile_conditions: &mut MethodRecompileConditions,
+    restart_point_id: RestartPointID,
+    after_call_restart_point_id: RestartPointID,
+    descriptor: &CMethodDescriptor,
+    method_id: MethodId,
+) -> impl Iterator<Item=IRInstr> {
+    let restart_point = IRInstr::RestartPoint(restart_point_id);
+    let after_call_restart_point = IRInstr::RestartPoint(after_call_restart_point_id);
+    match resolver.lookup_ir_method_id_and_address(method_id) {
+        None => {
+            recompile_conditions.add_condition(NeedsRecompileIf::FunctionCompiled { method_id });
+            Either::Left(array_into_iter([restart_point,
+                IRInstr::VMExit2 {
+                    exit_type: IRVMExitType::CompileFunctionAndRecompileCurrent {
+                        current_method_id: method_frame_data.current_method_id,
+                        target_method_id: method_id,
+                        restart_point_id,
+                        java_pc: current_instr_data.current_offset,
+                    }
+                }, after_call_restart_point]))
+        }
+        Some((_ir_method_id, address)) => {
+            let num_args = descriptor.arg_types.len() as u16;
+            let receiver = Register(1);
+            let assumption_check = deoptimize_if_invalidated(resolver, method_frame_data, current_instr_data);
+            Either::Right(array_into_iter([restart_point]).chain(assumption_check).chain(array_into_iter([
+                IRInstr::LoadFPRelative {
+                    from: method_frame_data.operand_stack_entry(current_instr_data.current_index, num_args),
+                    to: receiver,
+                    size: Size::pointer(),
+                },
+                IRInstr::NPECheck {
+                    possibly_null: receiver,
+                    temp_register: Register(2),
+                    npe_exit_type: IRVMExitType::NPE { java_pc: current_instr_data.current_offset },
+                },
+                IRInstr::IRCall {
+                    temp_register_1: Register(1),
+                    temp_register_2: Register(2),
+                    arg_from_to_offsets: virtual_and_special_arg_offsets(method_frame_data, current_instr_data, descriptor),
+                    return_value: if descriptor.return_type.is_void() {
+                        None
+                    } else {
+                        Some(method_frame_data.operand_stack_entry(current_instr_data.next_index, 0))
+                    },
+                    target_address: IRCallTarget::Constant {
+                        address,
+                        method_id,
+                    },
+                    current_frame_size: method_frame_data.full_frame_size(),
+                }, after_call_restart_point])))
         }
     }
 }
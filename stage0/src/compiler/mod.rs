@@ -3,7 +3,7 @@ use std::hash::Hash;
 use std::iter::repeat;
 use std::sync::atomic::{AtomicU32, Ordering};
 
-use itertools::Either;
+use itertools::{Either, Itertools};
 
 use another_jit_vm::{IRMethodID, Register};
 use another_jit_vm_ir::compiler::{IRInstr, IRLabel, LabelName, RestartPointGenerator, Size};
@@ -14,6 +14,7 @@ use rust_jvm_common::{ByteCodeIndex, ByteCodeOffset, MethodId};
 use rust_jvm_common::classfile::{IInc, LookupSwitch, TableSwitch, Wide};
 use rust_jvm_common::compressed_classfile::code::{CompressedInstructionInfo, CompressedLdc2W, CompressedLdcW};
 use rust_jvm_common::compressed_classfile::compressed_types::CPDType;
+use rust_jvm_common::method_shape::MethodShape;
 
 
 use crate::compiler::allocate::{anewarray, multianewarray, new, newarray};
@@ -37,6 +38,7 @@ use crate::compiler::local_var_loads::{aload_n, dload_n, fload_n, iload_n, lload
 use crate::compiler::local_var_stores::{astore_n, dstore_n, fstore_n, istore_n, lstore_n};
 use crate::compiler::monitors::{monitor_enter, monitor_exit};
 use crate::compiler::returns::{areturn, dreturn, freturn, ireturn, lreturn, return_void};
+use crate::compiler::safepoint_poll::{needs_safepoint_poll, safepoint_poll};
 use crate::compiler::static_fields::{getstatic, putstatic};
 use crate::compiler::throw::athrow;
 use crate::compiler_common::{JavaCompilerMethodAndFrameData, MethodResolver};
@@ -96,12 +98,16 @@ impl<'l> CompilerLabeler<'l> {
 
 pub struct RecompileConditions {
     conditions: HashMap<MethodId, HashSet<NeedsRecompileIf>>,
+    //receiver type of each devirtualized call site to the methods containing it, so that initializing a class only
+    //rechecks call sites on its supertypes. may contain methods which have since been invalidated.
+    devirtualized_call_sites: HashMap<CPDType, HashSet<MethodId>>,
 }
 
 impl RecompileConditions {
     pub fn new() -> Self {
         Self {
-            conditions: HashMap::new()
+            conditions: HashMap::new(),
+            devirtualized_call_sites: HashMap::new(),
         }
     }
 
@@ -124,21 +130,53 @@ impl RecompileConditions {
         false
     }
 
+    //forces a recompile on next entry, used when compiled code for method_id has been deoptimized
+    pub fn invalidate(&mut self, method_id: MethodId) {
+        self.conditions.remove(&method_id);
+    }
+
+    //methods with call sites devirtualized on one of receiver_types, which a newly initialized subtype may invalidate
+    pub fn devirtualization_dependents(&self, receiver_types: &[CPDType]) -> Vec<MethodId> {
+        receiver_types.iter()
+            .flat_map(|receiver_type| self.devirtualized_call_sites.get(receiver_type).into_iter().flatten())
+            .filter(|method_id| self.conditions.contains_key(method_id))
+            .copied()
+            .unique()
+            .collect()
+    }
+
+    //whether a devirtualized call site in method_id is no longer valid. method_resolver should use method_id's loader
+    pub fn devirtualization_invalidated<'gc>(&self, method_id: MethodId, method_resolver: &impl MethodResolver<'gc>) -> bool {
+        match self.conditions.get(&method_id) {
+            None => false,
+            Some(needs_recompiling) => {
+                needs_recompiling.iter().any(|condition| matches!(condition, NeedsRecompileIf::ImplementationNotUnique {..}) && condition.should_recompile(method_resolver))
+            }
+        }
+    }
+
     pub fn recompile_conditions(&mut self, method_id: MethodId) -> MethodRecompileConditions<'_> {
         self.conditions.insert(method_id, HashSet::new());
         MethodRecompileConditions {
-            conditions: self.conditions.get_mut(&method_id).unwrap()
+            method_id,
+            conditions: self.conditions.get_mut(&method_id).unwrap(),
+            devirtualized_call_sites: &mut self.devirtualized_call_sites,
         }
     }
 }
 
 
 pub struct MethodRecompileConditions<'l> {
+    method_id: MethodId,
     conditions: &'l mut HashSet<NeedsRecompileIf>,
+    devirtualized_call_sites: &'l mut HashMap<CPDType, HashSet<MethodId>>,
 }
 
 impl<'l> MethodRecompileConditions<'l> {
     pub fn add_condition(&mut self, condition: NeedsRecompileIf) {
+        if let NeedsRecompileIf::ImplementationNotUnique { on, .. } = &condition {
+            self.devirtualized_call_sites.entry(*on).or_default().insert(self.method_id);
+        }
         self.conditions.insert(condition);
     }
 }
@@ -159,6 +197,12 @@ pub enum NeedsRecompileIf {
     Interpreted {
         method_id: MethodId
     },
+    // call sites devirtualized by class hierarchy analysis
+    ImplementationNotUnique {
+        on: CPDType,
+        method_shape: MethodShape,
+        method_id: MethodId,
+    },
 }
 
 impl NeedsRecompileIf {
@@ -177,6 +221,9 @@ impl NeedsRecompileIf {
             NeedsRecompileIf::Interpreted { method_id } => {
                 !method_resolver.compile_interpreted(*method_id)
             }
+            NeedsRecompileIf::ImplementationNotUnique { on, method_shape, method_id } => {
+                method_resolver.lookup_unique_implementation(*on, method_shape).map(|(unique_method_id, _)| unique_method_id) != Some(*method_id)
+            }
         }
     }
 }
@@ -227,6 +274,30 @@ pub fn native_to_ir<'vm>(resolver: &impl MethodResolver<'vm>, labeler: &Labeler,
     res
 }
 
+// whether compile_to_ir can handle every instruction in the method. methods which it can't stay interpreted.
+pub fn compilable(method_frame_data: &JavaCompilerMethodAndFrameData) -> bool {
+    method_frame_data.layout.code_by_index.iter().all(|instruction| {
+        match &instruction.info {
+            CompressedInstructionInfo::ldc(Either::Left(ldc)) |
+            CompressedInstructionInfo::ldc_w(ldc) => {
+                matches!(ldc, CompressedLdcW::String { .. } | CompressedLdcW::Class { .. } | CompressedLdcW::Float { .. } | CompressedLdcW::Integer { .. })
+            }
+            CompressedInstructionInfo::ldc(Either::Right(_)) => false,
+            CompressedInstructionInfo::ldc2_w(ldc2) => !matches!(ldc2, CompressedLdc2W::Dynamic { .. }),
+            CompressedInstructionInfo::wide(wide) => matches!(wide, Wide::IInc(_)),
+            CompressedInstructionInfo::invokedynamic(_) |
+            CompressedInstructionInfo::f2l |
+            CompressedInstructionInfo::dup2_x2 |
+            CompressedInstructionInfo::goto_w(_) |
+            CompressedInstructionInfo::jsr(_) |
+            CompressedInstructionInfo::jsr_w(_) |
+            CompressedInstructionInfo::ret(_) |
+            CompressedInstructionInfo::nop => false,
+            _ => true
+        }
+    })
+}
+
 pub fn compile_to_ir<'vm>(resolver: &impl MethodResolver<'vm>, labeler: &Labeler, method_frame_data: &JavaCompilerMethodAndFrameData, recompile_conditions: &mut MethodRecompileConditions, reserved_ir_method_id: IRMethodID) -> Vec<(ByteCodeOffset, IRInstr)> {
     let cinstructions = method_frame_data.layout.code_by_index.as_slice();
     let class_cpdtype = resolver.using_method_view_impl(method_frame_data.current_method_id, |method_view| {
@@ -271,7 +342,7 @@ pub fn compile_to_ir<'vm>(resolver: &impl MethodResolver<'vm>, labeler: &Labeler
         let current_offset = compressed_instruction.offset;
         let current_index = ByteCodeIndex(i as u16);
         let next_index = ByteCodeIndex((i + 1) as u16);
-        let current_instr_data = CurrentInstructionCompilerData {
+        let mut current_instr_data = CurrentInstructionCompilerData {
             current_index,
             next_index,
             current_offset,
@@ -286,6 +357,9 @@ pub fn compile_to_ir<'vm>(resolver: &impl MethodResolver<'vm>, labeler: &Labeler
         if method_frame_data.should_trace_instructions {
             this_function_ir.push(IRInstr::VMExit2 { exit_type: IRVMExitType::TraceInstructionBefore { method_id: method_frame_data.current_method_id, offset: current_offset, java_pc: current_instr_data.current_offset } });
         }
+        if needs_safepoint_poll(&compressed_instruction.info) {
+            this_function_ir.extend(safepoint_poll(resolver.safepoint_poll_address(), &mut current_instr_data));
+        }
         match &compressed_instruction.info {
             CompressedInstructionInfo::invokestatic { method_name, descriptor, classname_ref_type } => {
                 this_function_ir.extend(invokestatic(resolver, method_frame_data, current_instr_data, &mut restart_point_generator, recompile_conditions, *method_name, descriptor, classname_ref_type));
@@ -421,12 +495,14 @@ pub fn compile_to_ir<'vm>(resolver: &impl MethodResolver<'vm>, labeler: &Labeler
                             CompressedLdcW::MethodType { .. } => todo!(),
                             CompressedLdcW::MethodHandle { .. } => todo!(),
                             CompressedLdcW::LiveObject(_) => todo!(),
+                            CompressedLdcW::Dynamic { .. } => unreachable!("dynamic constants are not compilable"),
                         }
                     }
                     Either::Right(right) => {
                         match right {
                             CompressedLdc2W::Long(_) => todo!(),
                             CompressedLdc2W::Double(_) => todo!(),
+                            CompressedLdc2W::Dynamic { .. } => unreachable!("dynamic constants are not compilable"),
                         }
                     }
                 }
@@ -510,6 +586,7 @@ pub fn compile_to_ir<'vm>(resolver: &impl MethodResolver<'vm>, labeler: &Labeler
                     CompressedLdc2W::Double(double) => {
                         this_function_ir.extend(ldc_double(method_frame_data, &current_instr_data, *double))
                     }
+                    CompressedLdc2W::Dynamic { .. } => unreachable!("dynamic constants are not compilable"),
                 }
             }
             CompressedInstructionInfo::sipush(val) => {
@@ -744,7 +821,7 @@ pub fn compile_to_ir<'vm>(resolver: &impl MethodResolver<'vm>, labeler: &Labeler
                 this_function_ir.extend(fload_n(method_frame_data, &current_instr_data, *index as u16))
             }
             CompressedInstructionInfo::invokeinterface { method_name, descriptor, classname_ref_type, count: _ } => {
-                this_function_ir.extend(invoke_interface(resolver, method_frame_data, &current_instr_data, &mut restart_point_generator, recompile_conditions, method_name, descriptor, classname_ref_type))
+                this_function_ir.extend(invoke_interface(resolver, method_frame_data, &mut current_instr_data, &mut restart_point_generator, recompile_conditions, method_name, descriptor, classname_ref_type))
             }
             CompressedInstructionInfo::pop => {
                 this_function_ir.extend(array_into_iter([]))
@@ -910,6 +987,9 @@ pub fn compile_to_ir<'vm>(resolver: &impl MethodResolver<'vm>, labeler: &Labeler
                     CompressedLdcW::LiveObject(_) => {
                         todo!()
                     }
+                    CompressedLdcW::Dynamic { .. } => {
+                        unreachable!("dynamic constants are not compilable")
+                    }
                 }
             }
             CompressedInstructionInfo::lookupswitch(LookupSwitch { pairs, default }) => {
@@ -952,7 +1032,7 @@ pub fn compile_to_ir<'vm>(resolver: &impl MethodResolver<'vm>, labeler: &Labeler
                 todo!()
             }
             CompressedInstructionInfo::invokedynamic(_) => {
-                todo!()
+                unreachable!("invokedynamic is not compilable")
             }
             CompressedInstructionInfo::dup2_x2 => {
                 todo!()
@@ -1106,7 +1186,36 @@ pub mod ldc;
 pub mod arithmetic;
 pub mod bitmanip;
 pub mod int_convert;
+pub mod deoptimize;
+pub mod safepoint_poll;
 
 pub fn array_into_iter<T, const N: usize>(array: [T; N]) -> impl Iterator<Item=T> {
     <[T; N]>::into_iter(array)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+pub mod test {
+    use rust_jvm_common::compressed_classfile::class_names::CClassName;
+    use rust_jvm_common::compressed_classfile::compressed_descriptors::CompressedMethodDescriptor;
+    use rust_jvm_common::compressed_classfile::compressed_types::CPDType;
+    use rust_jvm_common::compressed_classfile::method_names::MethodName;
+    use rust_jvm_common::method_shape::MethodShape;
+
+    use crate::compiler::{NeedsRecompileIf, RecompileConditions};
+
+    #[test]
+    pub fn devirtualized_call_sites_indexed_by_receiver() {
+        let receiver: CPDType = CClassName::object().into();
+        let unrelated: CPDType = CClassName::string().into();
+        let method_shape = MethodShape { name: MethodName::method_hashCode(), desc: CompressedMethodDescriptor { arg_types: vec![], return_type: CPDType::IntType } };
+        let mut recompile_conditions = RecompileConditions::new();
+        recompile_conditions.recompile_conditions(1).add_condition(NeedsRecompileIf::ImplementationNotUnique { on: receiver, method_shape, method_id: 7 });
+        recompile_conditions.recompile_conditions(2).add_condition(NeedsRecompileIf::FunctionCompiled { method_id: 7 });
+        assert_eq!(recompile_conditions.devirtualization_dependents(&[receiver]), vec![1]);
+        assert_eq!(recompile_conditions.devirtualization_dependents(&[unrelated, receiver, receiver]), vec![1]);
+        assert!(recompile_conditions.devirtualization_dependents(&[unrelated]).is_empty());
+        //recompiling re-adds the condition if the call site is still devirtualized
+        recompile_conditions.invalidate(1);
+        assert!(recompile_conditions.devirtualization_dependents(&[receiver]).is_empty());
+    }
+}
@@ -0,0 +1,128 @@
+use std::sync::atomic::AtomicU32;
+
+use another_jit_vm::Register;
+use another_jit_vm_ir::compiler::{IRInstr, IRLabel, Size};
+use another_jit_vm_ir::vm_exit_abi::IRVMExitType;
+use rust_jvm_common::compressed_classfile::code::CompressedInstructionInfo;
+
+use crate::compiler::{array_into_iter, CurrentInstructionCompilerData};
+
+// compiled code only reaches the runtime at vm exits, so loops and returns need to check whether
+// a safepoint has been requested, otherwise a thread spinning in compiled code can never be stopped.
+pub fn needs_safepoint_poll(instruction_info: &CompressedInstructionInfo) -> bool {
+    match instruction_info {
+        CompressedInstructionInfo::areturn |
+        CompressedInstructionInfo::dreturn |
+        CompressedInstructionInfo::freturn |
+        CompressedInstructionInfo::ireturn |
+        CompressedInstructionInfo::lreturn |
+        CompressedInstructionInfo::return_ => true,
+        CompressedInstructionInfo::goto_(offset) |
+        CompressedInstructionInfo::if_acmpeq(offset) |
+        CompressedInstructionInfo::if_acmpne(offset) |
+        CompressedInstructionInfo::if_icmpeq(offset) |
+        CompressedInstructionInfo::if_icmpne(offset) |
+        CompressedInstructionInfo::if_icmplt(offset) |
+        CompressedInstructionInfo::if_icmpge(offset) |
+        CompressedInstructionInfo::if_icmpgt(offset) |
+        CompressedInstructionInfo::if_icmple(offset) |
+        CompressedInstructionInfo::ifeq(offset) |
+        CompressedInstructionInfo::ifne(offset) |
+        CompressedInstructionInfo::iflt(offset) |
+        CompressedInstructionInfo::ifge(offset) |
+        CompressedInstructionInfo::ifgt(offset) |
+        CompressedInstructionInfo::ifle(offset) |
+        CompressedInstructionInfo::ifnonnull(offset) |
+        CompressedInstructionInfo::ifnull(offset) => *offset <= 0,
+        CompressedInstructionInfo::goto_w(offset) => *offset <= 0,
+        CompressedInstructionInfo::tableswitch(table_switch) => {
+            table_switch.default <= 0 || table_switch.offsets.iter().any(|offset| *offset <= 0)
+        }
+        CompressedInstructionInfo::lookupswitch(lookup_switch) => {
+            lookup_switch.default <= 0 || lookup_switch.pairs.iter().any(|(_, offset)| *offset <= 0)
+        }
+        _ => false
+    }
+}
+
+// poll_word_address is MethodResolver::safepoint_poll_address
+pub fn safepoint_poll(poll_word_address: *const AtomicU32, current_instr_data: &mut CurrentInstructionCompilerData) -> impl Iterator<Item=IRInstr> {
+    let poll_word = Register(1);
+    let poll_word_address_register = Register(2);
+    let no_safepoint_requested = current_instr_data.compiler_labeler.local_label();
+    array_into_iter([
+        IRInstr::Const64bit { to: poll_word_address_register, const_: poll_word_address as u64 },
+        IRInstr::Load { to: poll_word, from_address: poll_word_address_register, size: Size::int() },
+        IRInstr::BranchEqualVal { a: poll_word, const_: 0, label: no_safepoint_requested, size: Size::int() },
+        IRInstr::VMExit2 { exit_type: IRVMExitType::SafePointPoll { java_pc: current_instr_data.current_offset } },
+        IRInstr::Label(IRLabel { name: no_safepoint_requested }),
+    ])
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::collections::HashMap;
+    use std::sync::atomic::AtomicU32;
+
+    use another_jit_vm::{FramePointerOffset, Register};
+    use another_jit_vm_ir::compiler::{IRInstr, IRLabel, Size};
+    use another_jit_vm_ir::text::runner::run_function_until_safepoint;
+    use another_jit_vm_ir::text::TextIRFunction;
+    use gc_memory_layout_common::frame_layout::FRAME_HEADER_END_OFFSET;
+    use rust_jvm_common::{ByteCodeIndex, ByteCodeOffset};
+
+    use crate::compiler::{CompilerLabeler, CurrentInstructionCompilerData, Labeler};
+    use crate::compiler::safepoint_poll::safepoint_poll;
+
+    // counts down from iterations, with a poll on the back edge at java_pc 4
+    fn counted_loop(poll_word: &'static AtomicU32, iterations: u64) -> TextIRFunction {
+        let labeler = Labeler::new();
+        let index_by_bytecode_offset = HashMap::new();
+        let mut compiler_labeler = CompilerLabeler {
+            labeler: &labeler,
+            labels_vec: vec![],
+            label_to_index: HashMap::new(),
+            index_by_bytecode_offset: &index_by_bytecode_offset,
+        };
+        let loop_start = compiler_labeler.local_label();
+        let loop_end = compiler_labeler.local_label();
+        let mut current_instr_data = CurrentInstructionCompilerData {
+            current_index: ByteCodeIndex(2),
+            next_index: ByteCodeIndex(3),
+            current_offset: ByteCodeOffset(4),
+            compiler_labeler: &mut compiler_labeler,
+        };
+        let counter = FramePointerOffset(FRAME_HEADER_END_OFFSET);
+        let frame_size = FRAME_HEADER_END_OFFSET + 8;
+        let mut instructions = vec![
+            IRInstr::Const64bit { to: Register(3), const_: iterations },
+            IRInstr::StoreFPRelative { from: Register(3), to: counter, size: Size::long() },
+            IRInstr::Label(IRLabel { name: loop_start }),
+            IRInstr::LoadFPRelative { from: counter, to: Register(3), size: Size::long() },
+            IRInstr::BranchEqualVal { a: Register(3), const_: 0, label: loop_end, size: Size::long() },
+            IRInstr::AddConst { res: Register(3), a: -1 },
+            IRInstr::StoreFPRelative { from: Register(3), to: counter, size: Size::long() },
+        ];
+        instructions.extend(safepoint_poll(poll_word as *const AtomicU32, &mut current_instr_data));
+        instructions.extend([
+            IRInstr::BranchToLabel { label: loop_start },
+            IRInstr::Label(IRLabel { name: loop_end }),
+            IRInstr::LoadFPRelative { from: counter, to: Register(3), size: Size::long() },
+            IRInstr::Return { return_val: Some(Register(3)), temp_register_1: Register(1), temp_register_2: Register(2), temp_register_3: Register(4), temp_register_4: Register(5), frame_size },
+        ]);
+        TextIRFunction { frame_size, instructions }
+    }
+
+    #[test]
+    pub fn back_edge_poll_stops_loop() {
+        let poll_word: &'static AtomicU32 = Box::leak(Box::new(AtomicU32::new(0)));
+        let run = run_function_until_safepoint(&counted_loop(poll_word, 1000));
+        assert_eq!(run.return_value, Some(0));
+        assert!(run.exits.is_empty());
+
+        let poll_word: &'static AtomicU32 = Box::leak(Box::new(AtomicU32::new(1)));
+        let run = run_function_until_safepoint(&counted_loop(poll_word, u64::MAX));
+        assert_eq!(run.return_value, None);
+        assert_eq!(run.exits, vec!["SafePointPoll@4".to_string()]);
+    }
+}
@@ -1,10 +1,12 @@
 use std::collections::HashMap;
 
 use java5_verifier::{InferredFrame, SimplifiedVType};
+use serde::{Deserialize, Serialize};
 use rust_jvm_common::{ByteCodeOffset, MethodId};
 use rust_jvm_common::vtype::VType;
 use verification::verifier::Frame;
 
+#[derive(Clone, Serialize, Deserialize)]
 pub enum SunkVerifierFrames {
     FullFrame(Frame),
     PartialInferredFrame(java5_verifier::InferredFrame),
@@ -78,6 +80,7 @@ fn is_type_2_computational_type(vtype: &VType) -> bool {
         VType::Uninitialized(_) => false,
         VType::UninitializedThis => false,
         VType::UninitializedThisOrClass(_) => false,
+        VType::ReturnAddress(_) => false,
         VType::TwoWord => true,
         VType::OneWord => false,
         VType::Reference => false,
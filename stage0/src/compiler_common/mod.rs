@@ -3,7 +3,7 @@ use std::ffi::c_void;
 use std::mem::size_of;
 use std::ptr::NonNull;
 use std::sync::Arc;
-use std::sync::atomic::AtomicPtr;
+use std::sync::atomic::{AtomicPtr, AtomicU32};
 
 use itertools::Itertools;
 use wtf8::Wtf8Buf;
@@ -84,6 +84,14 @@ impl JavaCompilerMethodAndFrameData {
     pub fn num_local_vars(&self) -> usize {
         self.local_vars
     }
+
+    pub fn operand_stack_depth(&self, index: ByteCodeIndex) -> u16 {
+        self.layout.operand_stack_depth(index)
+    }
+
+    pub fn operand_stack_depth_at_offset(&self, offset: ByteCodeOffset) -> u16 {
+        self.operand_stack_depth(*self.index_by_bytecode_offset.get(&offset).unwrap())
+    }
 }
 
 
@@ -129,6 +137,10 @@ impl YetAnotherLayoutImpl {
         FramePointerOffset(FRAME_HEADER_END_OFFSET + (self.max_locals + self.stack_depth_by_index[index.0 as usize] - from_end - 1) as usize * size_of::<u64>())//-1 b/c stack depth is a len
     }
 
+    pub fn operand_stack_depth(&self, index: ByteCodeIndex) -> u16 {
+        self.stack_depth_by_index[index.0 as usize]
+    }
+
     pub fn is_category_2(&self, index: ByteCodeIndex, from_end: u16) -> bool {
         let category_2_array = &self.is_type_2_computational_type[index.0 as usize];
         *category_2_array.iter().nth(from_end as usize).unwrap()
@@ -179,9 +191,15 @@ pub trait MethodResolver<'gc> {
     fn lookup_interface_class_id(&self, interface: CPDType) -> ClassID;
     fn lookup_interface_method_number(&self, interface: CPDType, method_shape: MethodShape) -> Option<MethodNumber>;
     fn lookup_special(&self, on: &CPDType, name: MethodName, desc: CMethodDescriptor) -> Option<(MethodId, bool)>;
+    // the only implementation any currently initialized subtype of on could dispatch to, if there is exactly one.
+    fn lookup_unique_implementation(&self, on: CPDType, method_shape: &MethodShape) -> Option<(MethodId, bool)>;
     fn lookup_type_inited_initing(&self, cpdtype: &CPDType) -> Option<(Arc<RuntimeClass<'gc>>, LoaderName)>;
     fn allocated_object_type_id(&self, rc: Arc<RuntimeClass<'gc>>, loader: LoaderName, arr_len: Option<jint>) -> AllocatedTypeID;
     fn allocated_object_region_header_pointer(&self, id: AllocatedTypeID) -> *const AtomicPtr<RegionHeader>;
+    // nonzero while a safepoint is requested
+    fn safepoint_poll_address(&self) -> *const AtomicU32;
+    // nonzero once the code being compiled for method_id has been invalidated, see deoptimize_if_invalidated
+    fn invalidation_word_address(&self, method_id: MethodId) -> *const AtomicU32;
     fn lookup_method_layout(&self, method_id: usize) -> YetAnotherLayoutImpl;
     fn lookup_native_method_layout(&self, method_id: usize) -> NativeStackframeMemoryLayout;
     fn lookup_partial_method_layout(&self, method_id: usize) -> PartialYetAnotherLayoutImpl;
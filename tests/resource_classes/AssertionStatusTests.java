@@ -0,0 +1,35 @@
+// Run with: -ea -da:AssertionStatusQuiet -dsa AssertionStatusTests
+// Expected output: "loud asserts", "quiet doesn't", "system classes don't".
+public class AssertionStatusTests {
+    public static void main(String[] args) {
+        if (!AssertionStatusLoud.asserts()) {
+            throw new RuntimeException("AssertionStatusLoud should have assertions enabled");
+        }
+        System.out.println("loud asserts");
+        if (AssertionStatusQuiet.asserts()) {
+            throw new RuntimeException("AssertionStatusQuiet should have assertions disabled");
+        }
+        System.out.println("quiet doesn't");
+        if (Object.class.desiredAssertionStatus()) {
+            throw new RuntimeException("system classes should have assertions disabled");
+        }
+        System.out.println("system classes don't");
+    }
+}
+
+// nested classes share the assertion status of their top level class, so these can't be nested
+class AssertionStatusLoud {
+    static boolean asserts() {
+        boolean asserts = false;
+        assert asserts = true;
+        return asserts;
+    }
+}
+
+class AssertionStatusQuiet {
+    static boolean asserts() {
+        boolean asserts = false;
+        assert asserts = true;
+        return asserts;
+    }
+}
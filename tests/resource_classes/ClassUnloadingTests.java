@@ -0,0 +1,62 @@
+import java.io.InputStream;
+import java.lang.management.ClassLoadingMXBean;
+import java.lang.management.ManagementFactory;
+import java.net.URL;
+import java.net.URLClassLoader;
+import java.nio.file.Files;
+import java.nio.file.Path;
+import java.nio.file.StandardCopyOption;
+import java.util.concurrent.Callable;
+
+public class ClassUnloadingTests {
+    public static class Payload implements Callable<String> {
+        static String loadedBy = "payload";
+
+        public String call() {
+            return loadedBy + ":" + getClass().getClassLoader().getClass().getName();
+        }
+    }
+
+    public static void main(String[] args) throws Exception {
+        Path classDir = Files.createTempDirectory("class-unloading-tests");
+        try (InputStream payload = ClassUnloadingTests.class.getResourceAsStream("ClassUnloadingTests$Payload.class")) {
+            Files.copy(payload, classDir.resolve("ClassUnloadingTests$Payload.class"), StandardCopyOption.REPLACE_EXISTING);
+        }
+        URL[] urls = new URL[]{classDir.toUri().toURL()};
+        droppedLoaderUnloads(urls);
+        Files.delete(classDir.resolve("ClassUnloadingTests$Payload.class"));
+        Files.delete(classDir);
+    }
+
+    static void droppedLoaderUnloads(URL[] urls) throws Exception {
+        ClassLoadingMXBean classLoading = ManagementFactory.getClassLoadingMXBean();
+        long unloadedBefore = classLoading.getUnloadedClassCount();
+        runPayload(urls);
+        long unloadedAfter = unloadedBefore;
+        for (int i = 0; i < 10 && unloadedAfter == unloadedBefore; i++) {
+            System.gc();
+            unloadedAfter = classLoading.getUnloadedClassCount();
+        }
+        if (unloadedAfter <= unloadedBefore) {
+            throw new AssertionError();
+        }
+        //the vm keeps going, defining the class again in a new loader
+        runPayload(urls);
+    }
+
+    // kept out of droppedLoaderUnloads, so nothing in its frame refers to the loader once this returns
+    static void runPayload(URL[] urls) throws Exception {
+        //the bootstrap loader as parent, so the payload isn't found on the application class path
+        URLClassLoader loader = new URLClassLoader(urls, null);
+        Class<?> payloadClass = loader.loadClass("ClassUnloadingTests$Payload");
+        if (payloadClass.getClassLoader() != loader) {
+            throw new AssertionError();
+        }
+        @SuppressWarnings("unchecked")
+        Callable<String> payload = (Callable<String>) payloadClass.newInstance();
+        if (!payload.call().equals("payload:java.net.URLClassLoader")) {
+            throw new AssertionError();
+        }
+        loader.close();
+    }
+}
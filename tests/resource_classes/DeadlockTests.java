@@ -0,0 +1,53 @@
+import java.lang.management.ManagementFactory;
+import java.lang.management.ThreadMXBean;
+import java.util.Arrays;
+import java.util.concurrent.CountDownLatch;
+
+public class DeadlockTests {
+    public static void main(String[] args) throws Exception {
+        ThreadMXBean threadMXBean = ManagementFactory.getThreadMXBean();
+        if (threadMXBean.findMonitorDeadlockedThreads() != null) {
+            throw new AssertionError();
+        }
+        final Object first = new Object();
+        final Object second = new Object();
+        final CountDownLatch bothLocked = new CountDownLatch(2);
+        Thread a = lockInOrder(first, second, bothLocked);
+        Thread b = lockInOrder(second, first, bothLocked);
+        long[] expected = new long[]{a.getId(), b.getId()};
+        Arrays.sort(expected);
+        long[] deadlocked;
+        while ((deadlocked = threadMXBean.findMonitorDeadlockedThreads()) == null) {
+            Thread.sleep(10);
+        }
+        Arrays.sort(deadlocked);
+        if (!Arrays.equals(expected, deadlocked)) {
+            throw new AssertionError(Arrays.toString(deadlocked));
+        }
+        long[] allDeadlocked = threadMXBean.findDeadlockedThreads();
+        Arrays.sort(allDeadlocked);
+        if (!Arrays.equals(expected, allDeadlocked)) {
+            throw new AssertionError(Arrays.toString(allDeadlocked));
+        }
+    }
+
+    // daemon, so the deadlocked threads don't keep the vm alive
+    static Thread lockInOrder(final Object outer, final Object inner, final CountDownLatch bothLocked) {
+        Thread thread = new Thread(() -> {
+            synchronized (outer) {
+                bothLocked.countDown();
+                try {
+                    bothLocked.await();
+                } catch (InterruptedException e) {
+                    return;
+                }
+                synchronized (inner) {
+                    throw new AssertionError();
+                }
+            }
+        });
+        thread.setDaemon(true);
+        thread.start();
+        return thread;
+    }
+}
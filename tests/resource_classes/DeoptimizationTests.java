@@ -0,0 +1,117 @@
+// Runs loops long enough to be compiled with Base.value() devirtualized, then initializes a second implementation
+// while the compiled loop is still running. The running frame has to continue in the interpreter and dispatch
+// virtually from then on.
+public class DeoptimizationTests {
+    static abstract class Base {
+        abstract int value();
+    }
+
+    static class One extends Base {
+        int value() {
+            return 1;
+        }
+    }
+
+    static class Two extends Base {
+        int value() {
+            return 2;
+        }
+    }
+
+    static class Three extends Base {
+        int value() {
+            return 3;
+        }
+    }
+
+    static abstract class Other {
+        abstract int value();
+    }
+
+    static class OtherOne extends Other {
+        int value() {
+            return 1;
+        }
+    }
+
+    static class OtherTwo extends Other {
+        int value() {
+            return 2;
+        }
+    }
+
+    static volatile boolean stop;
+    static volatile Base shared = new One();
+
+    public static void main(String[] args) throws Exception {
+        invalidatedByOwnFrame();
+        invalidatedByOtherThread();
+        recompiledAfterSecondImplementation();
+    }
+
+    static int callValue(Other other) {
+        return other.value();
+    }
+
+    // callValue is compiled with a direct call to OtherOne.value, so once OtherTwo is initialized it has to be
+    // recompiled before the next call.
+    static void recompiledAfterSecondImplementation() throws Exception {
+        Other one = new OtherOne();
+        long sum = 0;
+        for (int i = 0; i < 200_000; i++) {
+            sum += callValue(one);
+        }
+        if (sum != 200_000) {
+            throw new AssertionError("expected 200000 but got " + sum);
+        }
+        Other two = (Other) Class.forName("DeoptimizationTests$OtherTwo").newInstance();
+        if (callValue(two) != 2 || callValue(one) != 1) {
+            throw new AssertionError("stale devirtualized call in callValue");
+        }
+    }
+
+    static void invalidatedByOwnFrame() throws Exception {
+        final int iterations = 200_000;
+        Base base = new One();
+        long sum = 0;
+        for (int i = 0; i < iterations; i++) {
+            if (i == iterations / 2) {
+                base = (Base) Class.forName("DeoptimizationTests$Two").newInstance();
+            }
+            sum += base.value();
+        }
+        long expected = iterations / 2 + 2L * (iterations / 2);
+        if (sum != expected) {
+            throw new AssertionError("expected " + expected + " but got " + sum);
+        }
+    }
+
+    static void invalidatedByOtherThread() throws Exception {
+        final long[] sums = new long[2];
+        Thread spinner = new Thread(() -> {
+            long ones = 0;
+            long threes = 0;
+            while (!stop) {
+                int value = shared.value();
+                if (value == 1) {
+                    ones++;
+                } else if (value == 3) {
+                    threes++;
+                } else {
+                    throw new AssertionError("unexpected value " + value);
+                }
+            }
+            sums[0] = ones;
+            sums[1] = threes;
+        });
+        spinner.start();
+        Thread.sleep(500);
+        shared = (Base) Class.forName("DeoptimizationTests$Three").newInstance();
+        Thread.sleep(500);
+        stop = true;
+        spinner.join();
+        if (sums[0] == 0 || sums[1] == 0) {
+            throw new AssertionError("expected both implementations to be called, got " + sums[0] + " and " + sums[1]);
+        }
+    }
+}
@@ -0,0 +1,242 @@
+import java.io.ByteArrayOutputStream;
+import java.io.DataOutputStream;
+import java.io.IOException;
+import java.lang.invoke.MethodHandles;
+import java.lang.reflect.InvocationTargetException;
+import java.util.ArrayList;
+import java.util.List;
+
+// javac here can't emit CONSTANT_Dynamic, so the class doing the ldc is assembled by hand.
+public class DynamicConstantTests {
+    static final String BOOTSTRAP_TYPE = "(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/Class;)Ljava/lang/Object;";
+    static final String ARG_BOOTSTRAP_TYPE = "(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/Class;Ljava/lang/Object;)Ljava/lang/Object;";
+
+    static int bootstrapCalls = 0;
+    static int failingBootstrapCalls = 0;
+
+    public static void main(String[] args) throws Exception {
+        Class<?> holder = new HolderLoader().define(holderClass());
+        resolvedOnce(holder);
+        primitiveConstant(holder);
+        failedResolutionIsCached(holder);
+        recursiveResolutionOverflows(holder);
+    }
+
+    public static Object bootstrap(MethodHandles.Lookup lookup, String name, Class<?> type) {
+        bootstrapCalls++;
+        return new StringBuilder(name);
+    }
+
+    public static Object intBootstrap(MethodHandles.Lookup lookup, String name, Class<?> type) {
+        return 42;
+    }
+
+    public static Object failingBootstrap(MethodHandles.Lookup lookup, String name, Class<?> type) {
+        failingBootstrapCalls++;
+        throw new IllegalStateException(name);
+    }
+
+    public static Object argBootstrap(MethodHandles.Lookup lookup, String name, Class<?> type, Object arg) {
+        return arg;
+    }
+
+    static void resolvedOnce(Class<?> holder) throws Exception {
+        Object first = holder.getMethod("value").invoke(null);
+        Object second = holder.getMethod("value").invoke(null);
+        if (first != second || !first.toString().equals("value") || bootstrapCalls != 1) {
+            throw new AssertionError();
+        }
+    }
+
+    static void primitiveConstant(Class<?> holder) throws Exception {
+        if ((Integer) holder.getMethod("intValue").invoke(null) != 42) {
+            throw new AssertionError();
+        }
+    }
+
+    static void failedResolutionIsCached(Class<?> holder) throws Exception {
+        Throwable first = thrownBy(holder, "failing");
+        Throwable second = thrownBy(holder, "failing");
+        //the same error every time, without running the bootstrap method again
+        if (first == null || second == null || first.getClass() != second.getClass() || !String.valueOf(first.getMessage()).equals(String.valueOf(second.getMessage())) || failingBootstrapCalls != 1) {
+            throw new AssertionError();
+        }
+    }
+
+    static void recursiveResolutionOverflows(Class<?> holder) throws Exception {
+        if (!(thrownBy(holder, "cycle") instanceof StackOverflowError)) {
+            throw new AssertionError();
+        }
+    }
+
+    static Throwable thrownBy(Class<?> holder, String method) throws Exception {
+        try {
+            holder.getMethod(method).invoke(null);
+            return null;
+        } catch (InvocationTargetException e) {
+            return e.getCause();
+        }
+    }
+
+    static byte[] holderClass() throws IOException {
+        ClassWriter writer = new ClassWriter();
+        int thisClass = writer.classRef("DynamicConstantHolder");
+        int superClass = writer.classRef("java/lang/Object");
+
+        int value = writer.dynamic(writer.bootstrapMethod(writer.bootstrapHandle("bootstrap", BOOTSTRAP_TYPE)), writer.nameAndType("value", "Ljava/lang/Object;"));
+        writer.ldcMethod("value", "()Ljava/lang/Object;", value, 0xb0);
+
+        int intValue = writer.dynamic(writer.bootstrapMethod(writer.bootstrapHandle("intBootstrap", BOOTSTRAP_TYPE)), writer.nameAndType("intValue", "I"));
+        writer.ldcMethod("intValue", "()I", intValue, 0xac);
+
+        int failing = writer.dynamic(writer.bootstrapMethod(writer.bootstrapHandle("failingBootstrap", BOOTSTRAP_TYPE)), writer.nameAndType("failing", "Ljava/lang/Object;"));
+        writer.ldcMethod("failing", "()Ljava/lang/Object;", failing, 0xb0);
+
+        //the constant is its own bootstrap argument
+        int argBootstrap = writer.bootstrapHandle("argBootstrap", ARG_BOOTSTRAP_TYPE);
+        int cycleNameAndType = writer.nameAndType("cycle", "Ljava/lang/Object;");
+        int cycle = writer.dynamic(writer.bootstrapMethod(argBootstrap, writer.nextConstant()), cycleNameAndType);
+        writer.ldcMethod("cycle", "()Ljava/lang/Object;", cycle, 0xb0);
+
+        return writer.bytes(thisClass, superClass);
+    }
+
+    static class HolderLoader extends ClassLoader {
+        HolderLoader() {
+            super(DynamicConstantTests.class.getClassLoader());
+        }
+
+        Class<?> define(byte[] bytes) {
+            return defineClass("DynamicConstantHolder", bytes, 0, bytes.length);
+        }
+    }
+
+    static class ClassWriter {
+        final List<byte[]> constants = new ArrayList<>();
+        final List<int[]> bootstrapMethods = new ArrayList<>();
+        final ByteArrayOutputStream methods = new ByteArrayOutputStream();
+        int methodCount = 0;
+        final int code;
+        final int bootstrapMethodsName;
+
+        ClassWriter() throws IOException {
+            code = utf8("Code");
+            bootstrapMethodsName = utf8("BootstrapMethods");
+        }
+
+        int nextConstant() {
+            return constants.size() + 1;
+        }
+
+        int constant(ByteArrayOutputStream bytes) {
+            constants.add(bytes.toByteArray());
+            return constants.size();
+        }
+
+        int utf8(String string) throws IOException {
+            ByteArrayOutputStream bytes = new ByteArrayOutputStream();
+            DataOutputStream out = new DataOutputStream(bytes);
+            out.writeByte(1);
+            out.writeUTF(string);
+            return constant(bytes);
+        }
+
+        int u2s(int tag, int... values) throws IOException {
+            ByteArrayOutputStream bytes = new ByteArrayOutputStream();
+            DataOutputStream out = new DataOutputStream(bytes);
+            out.writeByte(tag);
+            for (int value : values) {
+                out.writeShort(value);
+            }
+            return constant(bytes);
+        }
+
+        int classRef(String name) throws IOException {
+            return u2s(7, utf8(name));
+        }
+
+        int nameAndType(String name, String descriptor) throws IOException {
+            return u2s(12, utf8(name), utf8(descriptor));
+        }
+
+        int bootstrapHandle(String name, String descriptor) throws IOException {
+            int methodref = u2s(10, classRef("DynamicConstantTests"), nameAndType(name, descriptor));
+            ByteArrayOutputStream bytes = new ByteArrayOutputStream();
+            DataOutputStream out = new DataOutputStream(bytes);
+            out.writeByte(15);
+            //REF_invokeStatic
+            out.writeByte(6);
+            out.writeShort(methodref);
+            return constant(bytes);
+        }
+
+        int bootstrapMethod(int handle, int... args) {
+            int[] method = new int[args.length + 1];
+            method[0] = handle;
+            System.arraycopy(args, 0, method, 1, args.length);
+            bootstrapMethods.add(method);
+            return bootstrapMethods.size() - 1;
+        }
+
+        int dynamic(int bootstrapMethod, int nameAndType) throws IOException {
+            return u2s(17, bootstrapMethod, nameAndType);
+        }
+
+        //public static method doing ldc_w of constant, then returning it
+        void ldcMethod(String name, String descriptor, int constant, int returnOpcode) throws IOException {
+            int nameIndex = utf8(name);
+            int descriptorIndex = utf8(descriptor);
+            DataOutputStream out = new DataOutputStream(methods);
+            out.writeShort(0x0009);
+            out.writeShort(nameIndex);
+            out.writeShort(descriptorIndex);
+            out.writeShort(1);
+            out.writeShort(code);
+            out.writeInt(2 + 2 + 4 + 4 + 2 + 2);
+            out.writeShort(1);
+            out.writeShort(0);
+            out.writeInt(4);
+            out.writeByte(0x13);
+            out.writeShort(constant);
+            out.writeByte(returnOpcode);
+            out.writeShort(0);
+            out.writeShort(0);
+            methodCount++;
+        }
+
+        byte[] bytes(int thisClass, int superClass) throws IOException {
+            ByteArrayOutputStream bytes = new ByteArrayOutputStream();
+            DataOutputStream out = new DataOutputStream(bytes);
+            out.writeInt(0xCAFEBABE);
+            out.writeShort(0);
+            out.writeShort(55);
+            out.writeShort(constants.size() + 1);
+            for (byte[] constant : constants) {
+                out.write(constant);
+            }
+            out.writeShort(0x0021);
+            out.writeShort(thisClass);
+            out.writeShort(superClass);
+            out.writeShort(0);
+            out.writeShort(0);
+            out.writeShort(methodCount);
+            out.write(methods.toByteArray());
+            out.writeShort(1);
+            out.writeShort(bootstrapMethodsName);
+            int length = 2;
+            for (int[] method : bootstrapMethods) {
+                length += 2 + 2 + 2 * (method.length - 1);
+            }
+            out.writeInt(length);
+            out.writeShort(bootstrapMethods.size());
+            for (int[] method : bootstrapMethods) {
+                out.writeShort(method[0]);
+                out.writeShort(method.length - 1);
+                for (int i = 1; i < method.length; i++) {
+                    out.writeShort(method[i]);
+                }
+            }
+            return bytes.toByteArray();
+        }
+    }
+}
@@ -0,0 +1,89 @@
+import java.util.concurrent.CountDownLatch;
+import java.util.concurrent.locks.LockSupport;
+
+public class InterruptTests {
+    public static void main(String[] args) throws Exception {
+        interruptSleep();
+        interruptWait();
+        interruptPark();
+        interruptBeforeSleep();
+        interruptedClearsFlag();
+    }
+
+    static void interruptSleep() throws Exception {
+        final boolean[] threw = new boolean[1];
+        Thread sleeper = new Thread(() -> {
+            try {
+                Thread.sleep(60_000);
+            } catch (InterruptedException e) {
+                threw[0] = !Thread.currentThread().isInterrupted();
+            }
+        });
+        sleeper.start();
+        Thread.sleep(100);
+        sleeper.interrupt();
+        sleeper.join();
+        if (!threw[0]) {
+            throw new AssertionError();
+        }
+    }
+
+    static void interruptWait() throws Exception {
+        final Object lock = new Object();
+        final boolean[] threw = new boolean[1];
+        final CountDownLatch waiting = new CountDownLatch(1);
+        Thread waiter = new Thread(() -> {
+            synchronized (lock) {
+                waiting.countDown();
+                try {
+                    lock.wait();
+                } catch (InterruptedException e) {
+                    threw[0] = Thread.holdsLock(lock);
+                }
+            }
+        });
+        waiter.start();
+        waiting.await();
+        synchronized (lock) {
+            waiter.interrupt();
+        }
+        waiter.join();
+        if (!threw[0]) {
+            throw new AssertionError();
+        }
+    }
+
+    static void interruptPark() throws Exception {
+        final boolean[] stillInterrupted = new boolean[1];
+        Thread parker = new Thread(() -> {
+            LockSupport.park();
+            stillInterrupted[0] = Thread.currentThread().isInterrupted();
+        });
+        parker.start();
+        Thread.sleep(100);
+        parker.interrupt();
+        parker.join();
+        if (!stillInterrupted[0]) {
+            throw new AssertionError();
+        }
+    }
+
+    static void interruptBeforeSleep() {
+        Thread.currentThread().interrupt();
+        try {
+            Thread.sleep(60_000);
+            throw new AssertionError();
+        } catch (InterruptedException e) {
+            if (Thread.currentThread().isInterrupted()) {
+                throw new AssertionError();
+            }
+        }
+    }
+
+    static void interruptedClearsFlag() {
+        Thread.currentThread().interrupt();
+        if (!Thread.interrupted() || Thread.interrupted()) {
+            throw new AssertionError();
+        }
+    }
+}
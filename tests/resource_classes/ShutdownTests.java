@@ -0,0 +1,55 @@
+import java.util.ArrayList;
+import java.util.Arrays;
+import java.util.List;
+
+// Checks that the vm waits for non-daemon threads after main returns and only then runs the hooks. The hook halts
+// with 2 if anything ran out of order, otherwise the vm exits with 0.
+// Run with any argument for main to throw, which should print the stack trace and otherwise behave the same, except
+// for exiting with 1.
+public class ShutdownTests {
+    static final List<String> events = new ArrayList<>();
+
+    static void event(String event) {
+        synchronized (events) {
+            events.add(event);
+        }
+        System.out.println(event);
+    }
+
+    public static void main(String[] args) throws Exception {
+        Runtime.getRuntime().addShutdownHook(new Thread(() -> {
+            event("hook ran");
+            List<String> expected = Arrays.asList("main done", "worker done", "hook ran");
+            synchronized (events) {
+                if (!events.equals(expected)) {
+                    System.out.println("expected " + expected + " but got " + events);
+                    //exit would block on the shutdown in progress
+                    Runtime.getRuntime().halt(2);
+                }
+            }
+        }));
+        Thread daemon = new Thread(() -> {
+            while (true) {
+                try {
+                    Thread.sleep(1000);
+                } catch (InterruptedException ignored) {
+                }
+            }
+        });
+        daemon.setDaemon(true);
+        daemon.start();
+        Thread worker = new Thread(() -> {
+            try {
+                Thread.sleep(200);
+            } catch (InterruptedException e) {
+                throw new AssertionError(e);
+            }
+            event("worker done");
+        });
+        worker.start();
+        event("main done");
+        if (args.length > 0) {
+            throw new IllegalStateException("thrown out of main");
+        }
+    }
+}
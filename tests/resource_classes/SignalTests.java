@@ -0,0 +1,69 @@
+import java.util.concurrent.CountDownLatch;
+import java.util.concurrent.TimeUnit;
+
+import sun.misc.Signal;
+import sun.misc.SignalHandler;
+
+public class SignalTests {
+    public static void main(String[] args) throws Exception {
+        javaHandler();
+        replaceHandler();
+        reservedSignal();
+        unknownSignal();
+    }
+
+    static void javaHandler() throws Exception {
+        final CountDownLatch handled = new CountDownLatch(1);
+        final String[] handlerThread = new String[1];
+        Signal hup = new Signal("HUP");
+        if (hup.getNumber() != 1) {
+            throw new AssertionError();
+        }
+        Signal.handle(hup, signal -> {
+            handlerThread[0] = Thread.currentThread().getName();
+            handled.countDown();
+        });
+        Signal.raise(hup);
+        if (!handled.await(10, TimeUnit.SECONDS)) {
+            throw new AssertionError();
+        }
+        if (!"SIGHUP handler".equals(handlerThread[0])) {
+            throw new AssertionError(handlerThread[0]);
+        }
+    }
+
+    static void replaceHandler() throws Exception {
+        final CountDownLatch handled = new CountDownLatch(1);
+        Signal winch = new Signal("WINCH");
+        SignalHandler first = signal -> {
+            throw new AssertionError();
+        };
+        Signal.handle(winch, first);
+        SignalHandler previous = Signal.handle(winch, signal -> handled.countDown());
+        if (previous != first) {
+            throw new AssertionError();
+        }
+        Signal.raise(winch);
+        if (!handled.await(10, TimeUnit.SECONDS)) {
+            throw new AssertionError();
+        }
+        Signal.handle(winch, SignalHandler.SIG_DFL);
+    }
+
+    static void reservedSignal() {
+        try {
+            Signal.handle(new Signal("QUIT"), signal -> {
+            });
+            throw new AssertionError();
+        } catch (IllegalArgumentException expected) {
+        }
+    }
+
+    static void unknownSignal() {
+        try {
+            new Signal("NOT_A_SIGNAL");
+            throw new AssertionError();
+        } catch (IllegalArgumentException expected) {
+        }
+    }
+}
@@ -0,0 +1,92 @@
+import java.lang.management.ManagementFactory;
+
+public class ThreadCpuTimeTests {
+    static volatile long sink;
+
+    public static void main(String[] args) throws Exception {
+        com.sun.management.ThreadMXBean threadMXBean = (com.sun.management.ThreadMXBean) ManagementFactory.getThreadMXBean();
+        if (!threadMXBean.isThreadCpuTimeSupported() || !threadMXBean.isCurrentThreadCpuTimeSupported() || !threadMXBean.isThreadAllocatedMemorySupported()) {
+            throw new AssertionError();
+        }
+        currentThreadCpuTime(threadMXBean);
+        otherThreadCpuTime(threadMXBean);
+        allocatedBytes(threadMXBean);
+        disabled(threadMXBean);
+    }
+
+    static void busy(long millis) {
+        long start = System.nanoTime();
+        while (System.nanoTime() - start < millis * 1_000_000) {
+            sink++;
+        }
+    }
+
+    static void currentThreadCpuTime(com.sun.management.ThreadMXBean threadMXBean) {
+        long before = threadMXBean.getCurrentThreadCpuTime();
+        busy(200);
+        long after = threadMXBean.getCurrentThreadCpuTime();
+        if (before < 0 || after <= before) {
+            throw new AssertionError(before + " " + after);
+        }
+        if (threadMXBean.getCurrentThreadUserTime() > after) {
+            throw new AssertionError();
+        }
+    }
+
+    static void otherThreadCpuTime(com.sun.management.ThreadMXBean threadMXBean) throws Exception {
+        final Object lock = new Object();
+        Thread worker = new Thread(() -> {
+            busy(200);
+            synchronized (lock) {
+                try {
+                    lock.wait();
+                } catch (InterruptedException ignored) {
+                }
+            }
+        });
+        worker.setDaemon(true);
+        worker.start();
+        while (worker.getState() != Thread.State.WAITING) {
+            Thread.sleep(10);
+        }
+        long[] times = threadMXBean.getThreadCpuTime(new long[]{worker.getId(), Long.MAX_VALUE});
+        if (times[0] <= 0 || times[1] != -1 || threadMXBean.getThreadCpuTime(worker.getId()) < times[0]) {
+            throw new AssertionError(times[0] + " " + times[1]);
+        }
+        worker.interrupt();
+        worker.join();
+        if (threadMXBean.getThreadCpuTime(worker.getId()) != -1) {
+            throw new AssertionError();
+        }
+    }
+
+    static void allocatedBytes(com.sun.management.ThreadMXBean threadMXBean) {
+        long id = Thread.currentThread().getId();
+        long before = threadMXBean.getThreadAllocatedBytes(id);
+        Object[] allocated = new Object[1000];
+        for (int i = 0; i < allocated.length; i++) {
+            allocated[i] = new long[16];
+        }
+        long after = threadMXBean.getThreadAllocatedBytes(id);
+        if (before < 0 || after - before < 1000 * 16 * 8) {
+            throw new AssertionError(before + " " + after);
+        }
+        long[] both = threadMXBean.getThreadAllocatedBytes(new long[]{id, Long.MAX_VALUE});
+        if (both[0] < after || both[1] != -1) {
+            throw new AssertionError();
+        }
+    }
+
+    static void disabled(com.sun.management.ThreadMXBean threadMXBean) {
+        threadMXBean.setThreadCpuTimeEnabled(false);
+        if (threadMXBean.isThreadCpuTimeEnabled() || threadMXBean.getCurrentThreadCpuTime() != -1) {
+            throw new AssertionError();
+        }
+        threadMXBean.setThreadCpuTimeEnabled(true);
+        threadMXBean.setThreadAllocatedMemoryEnabled(false);
+        if (threadMXBean.getThreadAllocatedBytes(Thread.currentThread().getId()) != -1) {
+            throw new AssertionError();
+        }
+        threadMXBean.setThreadAllocatedMemoryEnabled(true);
+    }
+}
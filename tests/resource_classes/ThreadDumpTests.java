@@ -0,0 +1,93 @@
+import java.util.Arrays;
+import java.util.Map;
+import java.util.concurrent.CountDownLatch;
+
+public class ThreadDumpTests {
+    public static void main(String[] args) throws Exception {
+        sleepingThreadStack();
+        blockedThreadStack();
+        allStackTracesIncludesOtherThreads();
+        terminatedThreadHasEmptyStack();
+    }
+
+    static void sleepingInner() {
+        try {
+            Thread.sleep(60_000);
+        } catch (InterruptedException e) {
+        }
+    }
+
+    static void sleepingThreadStack() throws Exception {
+        Thread sleeper = new Thread(ThreadDumpTests::sleepingInner);
+        sleeper.start();
+        waitForState(sleeper, Thread.State.TIMED_WAITING);
+        if (!hasFrame(sleeper.getStackTrace(), "sleepingInner")) {
+            throw new AssertionError(Arrays.toString(sleeper.getStackTrace()));
+        }
+        sleeper.interrupt();
+        sleeper.join();
+    }
+
+    static void blockedInner(Object lock) {
+        synchronized (lock) {
+            lock.hashCode();
+        }
+    }
+
+    static void blockedThreadStack() throws Exception {
+        final Object lock = new Object();
+        Thread blocked = new Thread(() -> blockedInner(lock));
+        synchronized (lock) {
+            blocked.start();
+            waitForState(blocked, Thread.State.BLOCKED);
+            if (!hasFrame(blocked.getStackTrace(), "blockedInner")) {
+                throw new AssertionError(Arrays.toString(blocked.getStackTrace()));
+            }
+        }
+        blocked.join();
+    }
+
+    static void allStackTracesIncludesOtherThreads() throws Exception {
+        final CountDownLatch done = new CountDownLatch(1);
+        Thread waiter = new Thread(() -> {
+            try {
+                done.await();
+            } catch (InterruptedException e) {
+            }
+        });
+        waiter.start();
+        waitForState(waiter, Thread.State.WAITING);
+        Map<Thread, StackTraceElement[]> all = Thread.getAllStackTraces();
+        StackTraceElement[] waiterStack = all.get(waiter);
+        if (waiterStack == null || !hasFrame(waiterStack, "await")) {
+            throw new AssertionError(Arrays.toString(waiterStack));
+        }
+        done.countDown();
+        waiter.join();
+    }
+
+    static void terminatedThreadHasEmptyStack() throws Exception {
+        Thread finished = new Thread(() -> {
+        });
+        finished.start();
+        finished.join();
+        if (finished.getStackTrace().length != 0) {
+            throw new AssertionError();
+        }
+    }
+
+    static void waitForState(Thread thread, Thread.State state) throws InterruptedException {
+        while (thread.getState() != state) {
+            Thread.sleep(10);
+        }
+    }
+
+    static boolean hasFrame(StackTraceElement[] stack, String methodName) {
+        for (StackTraceElement element : stack) {
+            if (element.getMethodName().equals(methodName)) {
+                return true;
+            }
+        }
+        return false;
+    }
+}
@@ -0,0 +1,65 @@
+import java.util.Map;
+import java.util.concurrent.CountDownLatch;
+
+public class ThreadGroupTests {
+    public static void main(String[] args) throws Exception {
+        mainThreadGroup();
+        startedThreadsJoinTheirGroup();
+        allStackTraces();
+    }
+
+    static void mainThreadGroup() {
+        Thread current = Thread.currentThread();
+        ThreadGroup main = current.getThreadGroup();
+        if (!current.getName().equals("main") || !main.getName().equals("main")) {
+            throw new AssertionError();
+        }
+        ThreadGroup system = main.getParent();
+        if (system == null || !system.getName().equals("system") || system.getParent() != null) {
+            throw new AssertionError();
+        }
+        if (!system.parentOf(main) || main.activeCount() < 1) {
+            throw new AssertionError();
+        }
+    }
+
+    static void startedThreadsJoinTheirGroup() throws Exception {
+        ThreadGroup group = new ThreadGroup("workers");
+        CountDownLatch started = new CountDownLatch(1);
+        CountDownLatch finish = new CountDownLatch(1);
+        Thread worker = new Thread(group, () -> {
+            started.countDown();
+            try {
+                finish.await();
+            } catch (InterruptedException e) {
+                throw new RuntimeException(e);
+            }
+        }, "worker");
+        worker.start();
+        started.await();
+        Thread[] threads = new Thread[4];
+        if (group.enumerate(threads) != 1 || threads[0] != worker) {
+            throw new AssertionError();
+        }
+        finish.countDown();
+        worker.join();
+        if (group.activeCount() != 0) {
+            throw new AssertionError();
+        }
+    }
+
+    static void allStackTraces() {
+        Map<Thread, StackTraceElement[]> traces = Thread.getAllStackTraces();
+        StackTraceElement[] mine = traces.get(Thread.currentThread());
+        if (mine == null) {
+            throw new AssertionError();
+        }
+        boolean found = false;
+        for (StackTraceElement element : mine) {
+            found |= element.getMethodName().equals("allStackTraces");
+        }
+        if (!found) {
+            throw new AssertionError();
+        }
+    }
+}
@@ -8,6 +8,7 @@ use std::sync::{Arc, Condvar, Mutex, RwLock};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::Sender;
 use std::thread::{Builder, LocalKey, Scope, ScopedJoinHandle};
+use std::time::Duration;
 
 use nix::sys::signal::{sigaction, SigAction, SigHandler, SigSet};
 use nix::sys::signal::Signal;
@@ -24,6 +25,8 @@ pub struct Threads<'vm> {
 
 static mut THERE_CAN_ONLY_BE_ONE_THREADS: bool = false;
 
+const DEFAULT_STACK_SIZE: usize = 1024 * 1024 * 256;
+
 thread_local! {
     static THIS_THREAD: RefCell<Option<Arc<Thread<'static>>>> = RefCell::new(None);
 }
@@ -46,7 +49,9 @@ impl<'vm> Threads<'vm> {
         res
     }
 
-    pub fn create_thread(&'vm self, name: Option<String>) -> Thread<'vm> {
+    /// `stack_size` is the size of the new thread's native stack, which defaults to something large since the
+    /// verifier makes heavy use of recursion.
+    pub fn create_thread(&'vm self, name: Option<String>, stack_size: Option<usize>) -> Thread<'vm> {
         let join_status = Arc::new(RwLock::new(JoinStatus {
             finished_mutex: Mutex::new(()),
             alive: AtomicBool::new(false),
@@ -58,6 +63,7 @@ impl<'vm> Threads<'vm> {
             join_status: join_status.clone(),
             pause: PauseStatus { paused_mutex: Mutex::new(false), paused: Condvar::new() },
             pthread_id: None,
+            os_tid: None,
             rust_join_handle: None,
             thread_start_channel_send: None,
         };
@@ -69,17 +75,19 @@ impl<'vm> Threads<'vm> {
             Some(name) => builder.name(name.chars().take(15).collect::<String>()),
         };
         let join_handle = builder
-            .stack_size(1024 * 1024 * 256) // verifier makes heavy use of recursion.
+            .stack_size(stack_size.unwrap_or(DEFAULT_STACK_SIZE))
             .spawn_scoped(self.scope, move || unsafe {
                 join_status.write().unwrap().alive.store(true, Ordering::SeqCst);
-                thread_info_channel_send.send(pthread_self()).unwrap();
+                thread_info_channel_send.send((pthread_self(), libc::syscall(libc::SYS_gettid) as libc::pid_t)).unwrap();
                 let ThreadStartInfo { func, data } = thread_start_channel_recv.recv().unwrap();
                 func(data);
                 join_status.read().unwrap().thread_finished.notify_all();
             })
             .unwrap();
         res.thread_start_channel_send = Mutex::new(thread_start_channel_send).into();
-        res.pthread_id = thread_info_channel_recv.recv().unwrap().into();
+        let (pthread_id, os_tid) = thread_info_channel_recv.recv().unwrap();
+        res.pthread_id = pthread_id.into();
+        res.os_tid = os_tid.into();
         res.rust_join_handle = Some(join_handle);
         res
     }
@@ -100,6 +108,8 @@ pub struct Thread<'vm> {
     join_status: Arc<RwLock<JoinStatus>>,
     pause: PauseStatus,
     pthread_id: Option<pthread_t>,
+    // needed to find the thread in /proc
+    os_tid: Option<libc::pid_t>,
     rust_join_handle: Option<ScopedJoinHandle<'vm, ()>>,
     thread_start_channel_send: Option<Mutex<Sender<ThreadStartInfo<'vm>>>>,
 }
@@ -160,6 +170,38 @@ impl<'vm> Thread<'vm> {
     pub fn pthread_id(&self) -> pthread_t{
         self.pthread_id.unwrap()
     }
+
+    // user and system cpu time used by this thread, None if the thread has exited
+    pub fn cpu_time(&self) -> Option<Duration> {
+        if !self.is_alive() {
+            return None;
+        }
+        let mut clock_id: libc::clockid_t = 0;
+        if unsafe { libc::pthread_getcpuclockid(self.pthread_id() as libc::pthread_t, &mut clock_id) } != 0 {
+            return None;
+        }
+        let mut time = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+        if unsafe { libc::clock_gettime(clock_id, &mut time) } != 0 {
+            return None;
+        }
+        Some(Duration::new(time.tv_sec as u64, time.tv_nsec as u32))
+    }
+
+    // there is no clock for just user time, so this comes from /proc like in hotspot. Only has clock tick precision.
+    pub fn user_cpu_time(&self) -> Option<Duration> {
+        if !self.is_alive() {
+            return None;
+        }
+        let stat = std::fs::read_to_string(format!("/proc/self/task/{}/stat", self.os_tid?)).ok()?;
+        // the command name is in parentheses and may contain spaces, so skip past it. utime is the 14th field
+        let after_command = &stat[stat.rfind(')')? + 1..];
+        let user_ticks = after_command.split_whitespace().nth(11)?.parse::<u64>().ok()?;
+        let ticks_per_second = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+        if ticks_per_second <= 0 {
+            return None;
+        }
+        Some(Duration::from_nanos(user_ticks * 1_000_000_000 / ticks_per_second as u64))
+    }
 }
 
 pub enum SignalReason<'vm> {
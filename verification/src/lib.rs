@@ -2,6 +2,8 @@ use std::collections::HashMap;
 use std::collections::vec_deque::VecDeque;
 use std::sync::{Arc, Mutex};
 
+use serde::{Deserialize, Serialize};
+
 use classfile_view::view::ClassView;
 use perf_metrics::PerfMetrics;
 use rust_jvm_common::ByteCodeOffset;
@@ -50,6 +52,8 @@ pub struct VerifierContext<'l> {
 
 pub trait ClassFileGetter {
     fn get_classfile(&self, vf_context: &VerifierContext, loader: LoaderName, class: CClassName) -> Result<Arc<dyn ClassView>, ClassLoadingError>;
+    /// the loader which defined `class` when it is requested through `initiating_loader`
+    fn defining_loader(&self, vf_context: &VerifierContext, initiating_loader: LoaderName, class: CClassName) -> Result<LoaderName, ClassLoadingError>;
 }
 
 pub struct NoopClassFileGetter;
@@ -58,9 +62,13 @@ impl ClassFileGetter for NoopClassFileGetter {
     fn get_classfile(&self, _vf_context: &VerifierContext, loader: LoaderName, class: CClassName) -> Result<Arc<dyn ClassView>, ClassLoadingError> {
         todo!("{:?}{:?}", loader, class)
     }
+
+    fn defining_loader(&self, _vf_context: &VerifierContext, initiating_loader: LoaderName, class: CClassName) -> Result<LoaderName, ClassLoadingError> {
+        todo!("{:?}{:?}", initiating_loader, class)
+    }
 }
 
-#[derive(Eq, Debug)]
+#[derive(Eq, Debug, Serialize, Deserialize)]
 pub struct OperandStack {
     pub data: VecDeque<VType>,
 }
@@ -1,3 +1,4 @@
+use std::cell::RefCell;
 use std::option::Option::Some;
 use std::rc::Rc;
 
@@ -17,12 +18,12 @@ use crate::{StackMap, VerifierContext};
 use crate::OperandStack;
 use crate::verifier::{ClassWithLoaderMethod, Frame, get_class};
 use crate::verifier::filecorrectness::{does_not_override_final_method, is_assignable, super_class_chain};
-use crate::verifier::instructions::{FrameResult, handlers_are_legal};
+use crate::verifier::instructions::{empty_stack, FrameResult, handlers_are_legal};
 use crate::verifier::instructions::merged_code_is_type_safe;
 use crate::verifier::stackmapframes::get_stack_map_frames;
+use crate::verifier::typeinference::method_is_type_safe_by_inference;
 use crate::verifier::TypeSafetyError;
 
-#[allow(unreachable_code)]
 pub fn valid_type_transition(env: &Environment, expected_types_on_stack: Vec<VType>, result_type: VType, input_frame: Frame) -> Result<Frame, TypeSafetyError> {
     let Frame { locals, stack_map: input_operand_stack, flag_this_uninit } = input_frame;
     let interim_operand_stack = pop_matching_list(&env.vf, input_operand_stack, expected_types_on_stack)?;
@@ -30,7 +31,7 @@ pub fn valid_type_transition(env: &Environment, expected_types_on_stack: Vec<VTy
     if operand_stack_has_legal_length(env, &next_operand_stack) {
         Result::Ok(Frame { locals, stack_map: next_operand_stack, flag_this_uninit })
     } else {
-        Result::Err(todo!()/*TypeSafetyError::NotSafe("Operand stack did not have legal length".to_string())*/)
+        Result::Err(TypeSafetyError::NotSafe("Operand stack did not have legal length".to_string()))
     }
 }
 
@@ -49,13 +50,19 @@ pub fn pop_matching_list_impl(vf: &VerifierContext, mut pop_from: OperandStack,
 }
 
 pub fn pop_matching_type<'l>(vf: &VerifierContext, operand_stack: &'l mut OperandStack, type_: &VType) -> Result<VType, TypeSafetyError> {
+    if operand_stack.len() < size_of(vf, type_) as usize {
+        return Result::Err(empty_stack());
+    }
     if size_of(vf, type_) == 1 {
         let actual_type = operand_stack.peek();
         is_assignable(vf, &actual_type, type_, true)?;
         operand_stack.operand_pop();
         Result::Ok(actual_type)
     } else if size_of(vf, type_) == 2 {
-        assert!(matches!(&operand_stack.peek(), VType::TopType));
+        if !matches!(&operand_stack.peek(), VType::TopType) {
+            //a category 1 value where a long or double was expected
+            return Result::Err(TypeSafetyError::NotSafe(format!("Type {} is not assignable to {}", operand_stack.peek().verifier_name(vf.string_pool), type_.verifier_name(vf.string_pool))));
+        }
         let top = operand_stack.operand_pop();
         let actual_type = &operand_stack.peek();
         if let Err(err) = is_assignable(vf, actual_type, type_, true) {
@@ -112,38 +119,43 @@ pub fn can_pop(vf: &VerifierContext, input_frame: Frame, types: Vec<VType>) -> R
 }
 
 pub fn frame_is_assignable(vf: &VerifierContext, left: &Frame, right: &Frame) -> Result<(), TypeSafetyError> {
-    let locals_assignable_res: Result<Vec<_>, _> = left.locals.iter().zip(right.locals.iter()).map(|(left_, right_)| is_assignable(vf, left_, right_, true)).collect();
-    let locals_assignable = locals_assignable_res.is_ok();
-    let stack_assignable_res: Result<Vec<_>, _> = left.stack_map.iter().zip(right.stack_map.iter()).map(|(left_, right_)| is_assignable(vf, left_, right_, true)).collect();
-    let stack_assignable = stack_assignable_res.is_ok();
-    if left.stack_map.len() == right.stack_map.len()
-        && locals_assignable
-        && stack_assignable
-        && if left.flag_this_uninit {
-        true
-        // right.flag_this_uninit//todo wtf going on here
-    } else {
-        true
+    if left.stack_map.len() != right.stack_map.len() {
+        return Result::Err(TypeSafetyError::NotSafe(format!("Inconsistent stack height {} != {}", left.stack_map.len(), right.stack_map.len())));
     }
-    {
-        Result::Ok(())
-    } else {
-        dbg!(left.stack_map.len() == right.stack_map.len());
-        dbg!(locals_assignable);
-        dbg!(stack_assignable);
-        Result::Err(unknown_error_verifying!())
+    for (i, (left_, right_)) in left.locals.iter().zip(right.locals.iter()).enumerate() {
+        if is_assignable(vf, left_, right_, true).is_err() {
+            return Result::Err(TypeSafetyError::NotSafe(format!("Type {} (current frame, locals[{}]) is not assignable to {} (stack map, locals[{}])", left_.verifier_name(vf.string_pool), i, right_.verifier_name(vf.string_pool), i)));
+        }
     }
+    //operand stacks are stored top first, but hotspot numbers them from the bottom
+    for (i, (left_, right_)) in left.stack_map.iter().zip(right.stack_map.iter()).enumerate() {
+        if is_assignable(vf, left_, right_, true).is_err() {
+            let stack_index = left.stack_map.len() - 1 - i;
+            return Result::Err(TypeSafetyError::NotSafe(format!("Type {} (current frame, stack[{}]) is not assignable to {} (stack map, stack[{}])", left_.verifier_name(vf.string_pool), stack_index, right_.verifier_name(vf.string_pool), stack_index)));
+        }
+    }
+    //todo check flag_this_uninit, right.flag_this_uninit doesn't seem to be set correctly
+    Result::Ok(())
 }
 
 pub fn method_is_type_safe(vf: &mut VerifierContext, class: &ClassWithLoader, method: &ClassWithLoaderMethod) -> Result<(), TypeSafetyError> {
     let method_class = get_class(vf, &method.class)?;
     let method_view = method_class.method_view_i(method.method_index as u16);
-    does_not_override_final_method(vf, class, method)?;
-    if method_view.is_native() || method_view.is_abstract() {
-        Result::Ok(())
-    } else {
-        method_with_code_is_type_safe(vf, class.clone(), method.clone())
-    }
+    let res = does_not_override_final_method(vf, class, method).and_then(|_| {
+        if method_view.is_native() || method_view.is_abstract() {
+            Result::Ok(())
+        } else {
+            method_with_code_is_type_safe(vf, class.clone(), method.clone())
+        }
+    });
+    //so the VerifyError says where things went wrong, in the same style as hotspot's old verifier
+    res.map_err(|err| match err {
+        TypeSafetyError::NotSafe(msg) => {
+            let pool = vf.string_pool;
+            TypeSafetyError::NotSafe(format!("(class: {}, method: {} signature: {}) {}", class.class_name.0.to_str(pool), method_view.name().0.to_str(pool), method_view.desc_str().to_str(pool), msg))
+        }
+        err => err,
+    })
 }
 
 pub struct ParsedCodeAttribute {
@@ -183,7 +195,7 @@ pub fn method_with_code_is_type_safe<'l, 'k>(vf: &'l mut VerifierContext<'k>, cl
     instructs.push(&end_of_code);
     let handlers = get_handlers(vf, &class, code);
     let stack_map: Vec<StackMap> = get_stack_map_frames(vf, &class, method_info);
-    let merged = merge_stack_map_and_code(instructs, stack_map.iter().collect());
+    let merged = merge_stack_map_and_code(instructs.clone(), stack_map.iter().collect());
     let (frame, return_type) = method_initial_stack_frame(vf, &class, &method, frame_size)?;
     let debug = vf.debug && method_info.name() == MethodName::constructor_clinit();
     let mut env = Environment {
@@ -197,10 +209,21 @@ pub fn method_with_code_is_type_safe<'l, 'k>(vf: &'l mut VerifierContext<'k>, cl
         vf,
         debug,
         max_locals: frame_size as u16,
+        inferred_targets: None,
     };
     handlers_are_legal(&env)?;
-    merged_code_is_type_safe(&mut env, merged.as_slice(), FrameResult::Regular(frame))?;
-    Result::Ok(())
+    let major_version = method_class.major_version();
+    if major_version < 50 {
+        //no stack maps before version 50
+        return method_is_type_safe_by_inference(&mut env, instructs.as_slice(), frame);
+    }
+    match merged_code_is_type_safe(&mut env, merged.as_slice(), FrameResult::Regular(frame.clone())) {
+        //like hotspot, version 50 class files fail over to inference
+        Err(TypeSafetyError::NotSafe(_) | TypeSafetyError::Java5Maybe) if major_version == 50 => method_is_type_safe_by_inference(&mut env, instructs.as_slice(), frame),
+        //later versions must have a stack map frame wherever one is needed
+        Err(TypeSafetyError::Java5Maybe) => Err(TypeSafetyError::NotSafe("Expecting a stackmap frame".to_string())),
+        res => res,
+    }
 }
 
 #[derive(Debug)]
@@ -251,6 +274,9 @@ pub struct Environment<'l, 'k> {
     pub handlers: Vec<Handler>,
     pub vf: &'l mut VerifierContext<'k>,
     pub debug: bool,
+    //set when verifying by type inference, where branch targets have no stack map frame to check against.
+    //the frames flowing into each target are collected here instead, and merged by typeinference
+    pub inferred_targets: Option<RefCell<Vec<(ByteCodeOffset, Frame)>>>,
 }
 
 #[derive(Debug)]
@@ -46,16 +46,9 @@ pub fn class_is_final(vf: &VerifierContext, class: &ClassWithLoader) -> Result<b
     Ok(get_class(vf, class)?.is_final())
 }
 
-pub fn loaded_class(_vf: &VerifierContext, class_name: CClassName, loader: LoaderName) -> Result<ClassWithLoader, TypeSafetyError> {
-    Result::Ok(ClassWithLoader { class_name, loader })
-    // if vf.classes.class_loaded_by(&class_name, &loader) {
-    //     Result::Ok(ClassWithLoader { class_name, loader })
-    // } else {
-    //     match vf.classes.pre_load(class_name.clone(), loader.clone()) {
-    //         Ok(_) => Result::Ok(ClassWithLoader { class_name, loader }),
-    //         Err(_) => unimplemented!(),
-    //     }
-    // }
+pub fn loaded_class(vf: &VerifierContext, class_name: CClassName, loader: LoaderName) -> Result<ClassWithLoader, TypeSafetyError> {
+    let defining_loader = vf.classfile_getter.defining_loader(vf, loader, class_name)?;
+    Result::Ok(ClassWithLoader { class_name, loader: defining_loader })
 }
 
 pub fn class_is_interface(vf: &VerifierContext, class: &ClassWithLoader) -> Result<bool, TypeSafetyError> {
@@ -85,29 +78,36 @@ pub fn is_java_sub_class_of(vf: &VerifierContext, from: &ClassWithLoader, to: &C
 
 //todo why is this in this file?
 pub fn is_assignable(vf: &VerifierContext, from: &VType, to: &VType, from_verifier: bool) -> Result<(), TypeSafetyError> {
+    is_assignable_impl(vf, from, to, from_verifier).map_err(|err| match err {
+        TypeSafetyError::NotSafe(_) => TypeSafetyError::NotSafe(format!("Type {} is not assignable to {}", from.verifier_name(vf.string_pool), to.verifier_name(vf.string_pool))),
+        err => err,
+    })
+}
+
+fn is_assignable_impl(vf: &VerifierContext, from: &VType, to: &VType, from_verifier: bool) -> Result<(), TypeSafetyError> {
     match from {
         VType::DoubleType => match to {
             VType::DoubleType => Result::Ok(()),
-            _ => is_assignable(vf, &VType::TwoWord, to, true),
+            _ => is_assignable_impl(vf, &VType::TwoWord, to, true),
         },
         VType::LongType => match to {
             VType::LongType => Result::Ok(()),
-            _ => is_assignable(vf, &VType::TwoWord, to, true),
+            _ => is_assignable_impl(vf, &VType::TwoWord, to, true),
         },
         VType::FloatType => match to {
             VType::FloatType => Result::Ok(()),
-            _ => is_assignable(vf, &VType::OneWord, to, true),
+            _ => is_assignable_impl(vf, &VType::OneWord, to, true),
         },
         VType::IntType => match to {
             VType::IntType => Result::Ok(()),
-            _ => is_assignable(vf, &VType::OneWord, to, true),
+            _ => is_assignable_impl(vf, &VType::OneWord, to, true),
         },
         VType::Reference => match to {
             VType::Reference => Result::Ok(()),
-            _ => is_assignable(vf, &VType::OneWord, to, true),
+            _ => is_assignable_impl(vf, &VType::OneWord, to, true),
         },
         VType::Class(c) => match to {
-            VType::UninitializedThisOrClass(c2) => is_assignable(vf, &VType::Class(c.clone()), &c2.to_verification_type(BootstrapLoader), true), //todo bootstrap loader
+            VType::UninitializedThisOrClass(c2) => is_assignable_impl(vf, &VType::Class(c.clone()), &c2.to_verification_type(BootstrapLoader), true), //todo bootstrap loader
             VType::Class(c2) => {
                 if c == c2 {
                     Result::Ok(())
@@ -115,7 +115,7 @@ pub fn is_assignable(vf: &VerifierContext, from: &VType, to: &VType, from_verifi
                     is_java_assignable_class(vf, c, c2)
                 }
             }
-            _ => is_assignable(vf, &VType::Reference, to, true),
+            _ => is_assignable_impl(vf, &VType::Reference, to, true),
         },
         VType::ArrayReferenceType(a) => match to {
             VType::ArrayReferenceType(a2) => {
@@ -130,45 +130,47 @@ pub fn is_assignable(vf: &VerifierContext, from: &VType, to: &VType, from_verifi
                 if is_java_assignable(vf, from, to).is_ok() {
                     return Result::Ok(());
                 }
-                if is_assignable(vf, &VType::Reference, to, true).is_err() {
+                if is_assignable_impl(vf, &VType::Reference, to, true).is_err() {
                     if c.class_name == CClassName::object() && c.loader == LoaderName::BootstrapLoader {
                         return Result::Ok(());
                     }
                 }
-                is_assignable(vf, &VType::Reference, to, true)
+                is_assignable_impl(vf, &VType::Reference, to, true)
             }
-            _ => is_assignable(vf, &VType::Reference, to, true),
+            _ => is_assignable_impl(vf, &VType::Reference, to, true),
         },
         VType::TopType => match to {
             VType::TopType => Result::Ok(()),
-            vtype => {
-                dbg!(&vtype);
-                panic!("This might be a bug. It's a weird edge case")
-            }
+            //e.g. reading a local which was never stored to
+            _ => Result::Err(unknown_error_verifying!()),
         },
         VType::UninitializedEmpty => match to {
             VType::UninitializedEmpty => Result::Ok(()),
-            _ => is_assignable(vf, &VType::Reference, to, true),
+            _ => is_assignable_impl(vf, &VType::Reference, to, true),
         },
         VType::Uninitialized(u1) => match to {
             VType::Uninitialized(u2) => {
                 if u1.offset == u2.offset {
                     return Result::Ok(());
                 }
-                is_assignable(vf, &VType::UninitializedEmpty, to, true)
+                is_assignable_impl(vf, &VType::UninitializedEmpty, to, true)
             }
-            _ => is_assignable(vf, &VType::UninitializedEmpty, to, true),
+            _ => is_assignable_impl(vf, &VType::UninitializedEmpty, to, true),
         },
         VType::UninitializedThis => match to {
             VType::UninitializedThis => Result::Ok(()),
             VType::UninitializedThisOrClass(_) => Result::Ok(()),
-            _ => is_assignable(vf, &VType::UninitializedEmpty, to, true),
+            _ => is_assignable_impl(vf, &VType::UninitializedEmpty, to, true),
         },
         VType::NullType => match to {
             VType::NullType => Result::Ok(()),
             VType::Class(_) => Result::Ok(()),
             VType::ArrayReferenceType(_) => Result::Ok(()),
-            _ => is_assignable(vf, &VType::Class(ClassWithLoader { class_name: CClassName::object(), loader: vf.current_loader.clone() }), to, true),
+            _ => is_assignable_impl(vf, &VType::Class(ClassWithLoader { class_name: CClassName::object(), loader: vf.current_loader.clone() }), to, true),
+        },
+        VType::ReturnAddress(subroutine) => match to {
+            VType::ReturnAddress(subroutine2) if subroutine == subroutine2 => Result::Ok(()),
+            _ => is_assignable_impl(vf, &VType::OneWord, to, true),
         },
         VType::OneWord => match to {
             VType::OneWord => Result::Ok(()),
@@ -179,15 +181,12 @@ pub fn is_assignable(vf: &VerifierContext, from: &VType, to: &VType, from_verifi
         VType::TwoWord => match to {
             VType::TwoWord => Result::Ok(()),
             VType::TopType => Result::Ok(()),
-            _ => {
-                dbg!(to);
-                Result::Err(unknown_error_verifying!())
-            }
+            _ => Result::Err(unknown_error_verifying!()),
         },
         VType::UninitializedThisOrClass(c) => {
             match to {
                 VType::UninitializedThis => Result::Ok(()),
-                _ => is_assignable(vf, &c.to_verification_type(BootstrapLoader), to, true), //todo bootstrap loader
+                _ => is_assignable_impl(vf, &c.to_verification_type(BootstrapLoader), to, true), //todo bootstrap loader
             }
         }
         _ => {
@@ -192,8 +192,16 @@ pub fn instruction_is_type_safe(instruction: &CInstruction, env: &mut Environmen
         CInstructionInfo::isub => instruction_is_type_safe_iadd(env, stack_frame),
         CInstructionInfo::iushr => instruction_is_type_safe_iadd(env, stack_frame),
         CInstructionInfo::ixor => instruction_is_type_safe_iadd(env, stack_frame),
-        CInstructionInfo::jsr(_) => instruction_is_type_safe_nop(stack_frame),
-        CInstructionInfo::jsr_w(_) => instruction_is_type_safe_nop(stack_frame),
+        CInstructionInfo::jsr(target) => {
+            let final_target = (*target as isize) + (instruction.offset.0 as isize);
+            assert!(final_target >= 0);
+            instruction_is_type_safe_jsr(ByteCodeOffset(final_target as u16), env, stack_frame)
+        }
+        CInstructionInfo::jsr_w(target) => {
+            let final_target = (*target as isize) + (instruction.offset.0 as isize);
+            assert!(final_target >= 0);
+            instruction_is_type_safe_jsr(ByteCodeOffset(final_target as u16), env, stack_frame)
+        }
         CInstructionInfo::l2d => instruction_is_type_safe_l2d(env, stack_frame),
         CInstructionInfo::l2f => instruction_is_type_safe_l2f(env, stack_frame),
         CInstructionInfo::l2i => instruction_is_type_safe_l2i(env, stack_frame),
@@ -216,7 +224,8 @@ pub fn instruction_is_type_safe(instruction: &CInstruction, env: &mut Environmen
         CInstructionInfo::lmul => instruction_is_type_safe_ladd(env, stack_frame),
         CInstructionInfo::lneg => instruction_is_type_safe_lneg(env, stack_frame),
         CInstructionInfo::lookupswitch(s) => {
-            let targets: Vec<ByteCodeOffset> = s.pairs.iter().map(|(_, x)| ByteCodeOffset((offset.0 as i32 + *x as i32) as u16)).collect();
+            let mut targets: Vec<ByteCodeOffset> = s.pairs.iter().map(|(_, x)| ByteCodeOffset((offset.0 as i32 + *x as i32) as u16)).collect();
+            targets.push(ByteCodeOffset((offset.0 as i32 + s.default) as u16));
             let keys = s.pairs.iter().map(|(x, _)| *x).collect();
             instruction_is_type_safe_lookupswitch(targets, keys, env, stack_frame)
         }
@@ -243,7 +252,7 @@ pub fn instruction_is_type_safe(instruction: &CInstruction, env: &mut Environmen
         CInstructionInfo::pop2 => instruction_is_type_safe_pop2(env, stack_frame),
         CInstructionInfo::putfield { name, desc, target_class } => instruction_is_type_safe_putfield((*target_class).into(), *name, desc, env, stack_frame),
         CInstructionInfo::putstatic { name: _, desc, target_class: _ } => instruction_is_type_safe_putstatic(desc, env, stack_frame),
-        CInstructionInfo::ret(_) => instruction_is_type_safe_nop(stack_frame),
+        CInstructionInfo::ret(i) => instruction_is_type_safe_ret(*i as u16, env, stack_frame),
         CInstructionInfo::return_ => instruction_is_type_safe_return(env, stack_frame),
         CInstructionInfo::saload => instruction_is_type_safe_saload(env, stack_frame),
         CInstructionInfo::sastore => instruction_is_type_safe_sastore(env, stack_frame),
@@ -268,7 +277,7 @@ pub fn instruction_is_type_safe(instruction: &CInstruction, env: &mut Environmen
             Wide::Astore(WideAstore { index }) => instruction_is_type_safe_astore(*index as u16, env, stack_frame),
             Wide::Lstore(WideLstore { index }) => instruction_is_type_safe_lstore(*index as u16, env, stack_frame),
             Wide::Dstore(WideDstore { index }) => instruction_is_type_safe_dstore(*index as u16, env, stack_frame),
-            Wide::Ret(WideRet { index: _ }) => instruction_is_type_safe_nop(stack_frame),
+            Wide::Ret(WideRet { index }) => instruction_is_type_safe_ret(*index, env, stack_frame),
             Wide::IInc(iinc) => instruction_is_type_safe_iinc(iinc.index as u16, env, stack_frame),
         },
         CInstructionInfo::EndOfCode => Result::Err(unknown_error_verifying!()),
@@ -107,14 +107,15 @@ pub fn merged_code_is_type_safe(env: &mut Environment, merged_code: &[MergedCode
                     } /**///todo deal with java 5 bs
                 },
             };
-            match instruction_is_type_safe(&i, env, i.offset, f)? {
+            let at_instruction = |err| at_offset(err, i.offset, Some(&i.info));
+            match instruction_is_type_safe(&i, env, i.offset, f).map_err(at_instruction)? {
                 InstructionTypeSafe::Safe(s) => {
                     let ResultFrames { next_frame, exception_frame } = s;
-                    let _exception_stack_frame1 = instruction_satisfies_handlers(env, i.offset, &exception_frame)?;
+                    let _exception_stack_frame1 = instruction_satisfies_handlers(env, i.offset, &exception_frame).map_err(at_instruction)?;
                     merged_code_is_type_safe(env, rest, FrameResult::Regular(next_frame))
                 }
                 InstructionTypeSafe::AfterGoto(ag) => {
-                    let _exception_stack_frame1 = instruction_satisfies_handlers(env, i.offset, &ag.exception_frame)?;
+                    let _exception_stack_frame1 = instruction_satisfies_handlers(env, i.offset, &ag.exception_frame).map_err(at_instruction)?;
                     merged_code_is_type_safe(env, rest, FrameResult::AfterGoto)
                 }
             }
@@ -127,7 +128,7 @@ pub fn merged_code_is_type_safe(env: &mut Environment, merged_code: &[MergedCode
             };
             match after_frame {
                 FrameResult::Regular(f) => {
-                    frame_is_assignable(&env.vf, &f, &map_frame)?;
+                    frame_is_assignable(&env.vf, &f, &map_frame).map_err(|err| at_offset(err, s.offset, None))?;
                     merged_code_is_type_safe(env, rest, FrameResult::Regular(map_frame))
                 }
                 FrameResult::AfterGoto => merged_code_is_type_safe(env, rest, FrameResult::Regular(map_frame)),
@@ -136,6 +137,16 @@ pub fn merged_code_is_type_safe(env: &mut Environment, merged_code: &[MergedCode
     }
 }
 
+pub fn at_offset(err: TypeSafetyError, offset: ByteCodeOffset, instruction: Option<&CInstructionInfo>) -> TypeSafetyError {
+    match err {
+        TypeSafetyError::NotSafe(msg) => match instruction {
+            Some(instruction) => TypeSafetyError::NotSafe(format!("@{}: {}: {}", offset.0, instruction.instruction_to_string_without_meta(), msg)),
+            None => TypeSafetyError::NotSafe(format!("@{}: stack map frame: {}", offset.0, msg)),
+        },
+        err => err,
+    }
+}
+
 fn offset_stack_frame(env: &Environment, offset: ByteCodeOffset) -> Result<Frame, TypeSafetyError> {
     match env
         .merged_code
@@ -162,6 +173,10 @@ fn offset_stack_frame(env: &Environment, offset: ByteCodeOffset) -> Result<Frame
 }
 
 fn target_is_type_safe(env: &Environment, stack_frame: &Frame, target: ByteCodeOffset) -> Result<(), TypeSafetyError> {
+    if let Some(inferred_targets) = &env.inferred_targets {
+        inferred_targets.borrow_mut().push((target, stack_frame.clone()));
+        return Result::Ok(());
+    }
     let frame = match offset_stack_frame(env, target) {
         Ok(frame) => frame,
         Err(TypeSafetyError::Java5Maybe) => {
@@ -174,7 +189,7 @@ fn target_is_type_safe(env: &Environment, stack_frame: &Frame, target: ByteCodeO
     Result::Ok(())
 }
 
-fn instruction_satisfies_handlers(env: &Environment, offset: ByteCodeOffset, exception_stack_frame: &Frame) -> Result<(), TypeSafetyError> {
+pub fn instruction_satisfies_handlers(env: &Environment, offset: ByteCodeOffset, exception_stack_frame: &Frame) -> Result<(), TypeSafetyError> {
     let handlers = &env.handlers;
     let applicable_handler = handlers.iter().filter(|h| is_applicable_handler(offset, h));
     let res: Result<Vec<_>, _> = applicable_handler.map(|h| instruction_satisfies_handler(env, exception_stack_frame, h)).collect();
@@ -192,7 +207,6 @@ fn class_to_type(vf: &VerifierContext, class: &ClassWithLoader) -> Result<VType,
     Ok(class_name.to_verification_type(class.loader))
 }
 
-#[allow(unreachable_code)]
 fn instruction_satisfies_handler(env: &Environment, exc_stack_frame: &Frame, handler: &Handler) -> Result<(), TypeSafetyError> {
     let target = handler.target;
     let _class_loader = &env.class_loader;
@@ -205,7 +219,7 @@ fn instruction_satisfies_handler(env: &Environment, exc_stack_frame: &Frame, han
     if operand_stack_has_legal_length(env, &stack_map) {
         target_is_type_safe(env, &true_exc_stack_frame, target)
     } else {
-        Result::Err(todo!()/*TypeSafetyError::NotSafe("operand stack does not have legal length".to_string())*/)
+        Result::Err(TypeSafetyError::NotSafe("operand stack does not have legal length".to_string()))
     }
 }
 
@@ -230,7 +244,6 @@ pub fn start_is_member_of(start: ByteCodeOffset, merged_instructs: &[MergedCodeI
     })
 }
 
-#[allow(unreachable_code)]
 pub fn handler_is_legal(env: &Environment, h: &Handler) -> Result<(), TypeSafetyError> {
     if h.start < h.end {
         if start_is_member_of(h.start, env.merged_code.unwrap()) {
@@ -244,13 +257,13 @@ pub fn handler_is_legal(env: &Environment, h: &Handler) -> Result<(), TypeSafety
                 is_assignable(&env.vf, &VType::Class(ClassWithLoader { class_name: exception_class.class_name, loader: env.class_loader.clone() }), &VType::Class(ClassWithLoader { class_name: CClassName::throwable(), loader: LoaderName::BootstrapLoader }), true)?;
                 init_handler_is_legal(env, h)
             } else {
-                Result::Err(todo!()/*TypeSafetyError::NotSafe("Instructions do not include handler end".to_string())*/)
+                Result::Err(TypeSafetyError::NotSafe("Instructions do not include handler end".to_string()))
             }
         } else {
-            Result::Err(todo!()/*TypeSafetyError::NotSafe("No instruction found at handler start.".to_string())*/)
+            Result::Err(TypeSafetyError::NotSafe("No instruction found at handler start.".to_string()))
         }
     } else {
-        Result::Err(todo!()/*TypeSafetyError::NotSafe("Handler start not less than end".to_string())*/)
+        Result::Err(TypeSafetyError::NotSafe("Handler start not less than end".to_string()))
     }
 }
 
@@ -278,7 +291,14 @@ pub fn can_safely_push(env: &Environment, input_operand_stack: OperandStack, typ
     }
 }
 
+pub fn empty_stack() -> TypeSafetyError {
+    TypeSafetyError::NotSafe("Unable to pop operand off an empty stack".to_string())
+}
+
 pub fn pop_category1(vf: &VerifierContext, input: &mut OperandStack) -> Result<VType, TypeSafetyError> {
+    if input.len() < 1 {
+        return Result::Err(empty_stack());
+    }
     if size_of(vf, &input.peek()) == 1 {
         let type_ = input.operand_pop();
         return Result::Ok(type_);
@@ -287,6 +307,9 @@ pub fn pop_category1(vf: &VerifierContext, input: &mut OperandStack) -> Result<V
 }
 
 pub fn peek_category1(vf: &VerifierContext, input: &OperandStack) -> Result<VType, TypeSafetyError> {
+    if input.len() < 1 {
+        return Result::Err(empty_stack());
+    }
     if size_of(vf, &input.peek()) == 1 {
         let type_ = input.peek();
         return Result::Ok(type_);
@@ -295,8 +318,13 @@ pub fn peek_category1(vf: &VerifierContext, input: &OperandStack) -> Result<VTyp
 }
 
 pub fn pop_category2(vf: &VerifierContext, input: &mut OperandStack) -> Result<VType, TypeSafetyError> {
-    let top = input.operand_pop();
-    assert_eq!(top, VType::TopType);
+    if input.len() < 2 {
+        return Result::Err(empty_stack());
+    }
+    if input.peek() != VType::TopType {
+        return Result::Err(unknown_error_verifying!());
+    }
+    input.operand_pop();
     if size_of(vf, &input.peek()) == 2 {
         let type_ = input.operand_pop();
         return Result::Ok(type_);
@@ -305,6 +333,9 @@ pub fn pop_category2(vf: &VerifierContext, input: &mut OperandStack) -> Result<V
 }
 
 pub fn peek_category2(vf: &VerifierContext, input: &mut OperandStack) -> Result<VType, TypeSafetyError> {
+    if input.len() < 2 {
+        return Result::Err(empty_stack());
+    }
     let top = input.operand_pop();
     // assert_eq!(top, VType::TopType);
     let valid_size = size_of(vf, &input.peek()) == 2;
@@ -630,13 +661,15 @@ pub fn loadable_constant(vf: &VerifierContext, c: &CompressedLdcW) -> VType {
         CompressedLdcW::MethodHandle {} => VType::Class(ClassWithLoader { class_name: CClassName::method_handle(), loader: vf.current_loader.clone() }),
         CompressedLdcW::MethodType {} => VType::Class(ClassWithLoader { class_name: CClassName::method_type(), loader: vf.current_loader.clone() }),
         CompressedLdcW::LiveObject(idx) => vf.live_pool_getter.elem_type(*idx).to_verification_type(vf.current_loader),
+        CompressedLdcW::Dynamic { type_, .. } => type_.to_verification_type(vf.current_loader),
     }
 }
 
-pub fn loadable_constant_w(_vf: &VerifierContext, c: &CompressedLdc2W) -> VType {
+pub fn loadable_constant_w(vf: &VerifierContext, c: &CompressedLdc2W) -> VType {
     match c {
         CompressedLdc2W::Long(_) => VType::LongType,
         CompressedLdc2W::Double(_) => VType::DoubleType,
+        CompressedLdc2W::Dynamic { type_, .. } => type_.to_verification_type(vf.current_loader),
     }
 }
 
@@ -664,6 +697,7 @@ pub fn instruction_is_type_safe_ldc_w(const_: &CompressedLdcW, env: &Environment
             VType::Class(ClassWithLoader { class_name: CClassName::object(), loader: LoaderName::BootstrapLoader })
             //todo loader
         }
+        CompressedLdcW::Dynamic { type_, .. } => type_.to_verification_type(env.vf.current_loader),
     };
     type_transition(env, stack_frame, vec![], type_)
 }
@@ -53,7 +53,11 @@ pub fn instruction_is_type_safe_aastore(env: &Environment, stack_frame: Frame) -
 pub fn instruction_is_type_safe_astore(index: u16, env: &Environment, stack_frame: Frame) -> Result<InstructionTypeSafe, TypeSafetyError> {
     let locals = stack_frame.locals.clone();
     let flag = stack_frame.flag_this_uninit;
-    let next_frame = store_is_type_safe(env, index, &VType::Reference, stack_frame)?;
+    let next_frame = match stack_frame.stack_map.iter().next() {
+        //subroutines save their return address with astore
+        Some(VType::ReturnAddress(_)) => store_is_type_safe(env, index, &VType::OneWord, stack_frame)?,
+        _ => store_is_type_safe(env, index, &VType::Reference, stack_frame)?,
+    };
     standard_exception_frame(locals, flag, next_frame)
 }
 
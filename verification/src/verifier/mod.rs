@@ -1,6 +1,8 @@
 use std::rc::Rc;
 use std::sync::Arc;
 
+use serde::{Deserialize, Serialize};
+
 use classfile_view::view::ClassView;
 use rust_jvm_common::ByteCodeOffset;
 use rust_jvm_common::compressed_classfile::class_names::CClassName;
@@ -29,6 +31,7 @@ macro_rules! unknown_error_verifying {
 pub mod codecorrectness;
 pub mod filecorrectness;
 pub mod instructions;
+pub mod typeinference;
 
 pub struct InternalFrame {
     pub locals: Vec<VType>,
@@ -73,7 +76,7 @@ pub struct ClassWithLoaderMethod {
     pub method_index: usize,
 }
 
-#[derive(Eq, PartialEq, Debug)]
+#[derive(Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Frame {
     pub locals: Rc<Vec<VType>>,
     pub stack_map: OperandStack,
@@ -104,22 +107,21 @@ impl From<ClassLoadingError> for TypeSafetyError {
     }
 }
 
-#[allow(unreachable_code)]
 pub fn class_is_type_safe(vf: &mut VerifierContext, class: &ClassWithLoader) -> Result<(), TypeSafetyError> {
     if class.class_name == CClassName::object() {
         if !is_bootstrap_loader(&class.loader) {
-            return Result::Err(todo!()/*TypeSafetyError::NotSafe("Loading object with something other than bootstrap loader".to_string())*/);
+            return Result::Err(TypeSafetyError::NotSafe("Loading object with something other than bootstrap loader".to_string()));
         }
     } else {
         let mut chain = vec![];
         super_class_chain(vf, class, class.loader.clone(), &mut chain)?;
         if chain.is_empty() {
-            return Result::Err(todo!()/*TypeSafetyError::NotSafe("No superclass but object is not Object".to_string())*/);
+            return Result::Err(TypeSafetyError::NotSafe("No superclass but object is not Object".to_string()));
         }
         let super_class_name = get_class(vf, class)?.super_name();
-        let super_class = loaded_class(vf, super_class_name.unwrap(), vf.current_loader.clone()).unwrap();
+        let super_class = loaded_class(vf, super_class_name.unwrap(), vf.current_loader.clone())?;
         if class_is_final(vf, &super_class)? {
-            return Result::Err(todo!()/*TypeSafetyError::NotSafe("Superclass is final".to_string())*/);
+            return Result::Err(TypeSafetyError::NotSafe("Cannot inherit from final class".to_string()));
         }
     }
     let methods = get_class_methods(vf, class.clone())?;
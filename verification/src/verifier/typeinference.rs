@@ -0,0 +1,507 @@
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::rc::Rc;
+
+use rust_jvm_common::ByteCodeOffset;
+use rust_jvm_common::classfile::{IInc, Wide, WideAstore, WideDstore, WideFstore, WideIstore, WideLstore, WideRet};
+use rust_jvm_common::compressed_classfile::class_names::CClassName;
+use rust_jvm_common::compressed_classfile::code::{CInstruction, CInstructionInfo};
+use rust_jvm_common::compressed_classfile::compressed_types::CPDType;
+use rust_jvm_common::loading::ClassWithLoader;
+use rust_jvm_common::vtype::VType;
+
+use crate::OperandStack;
+use crate::verifier::codecorrectness::Environment;
+use crate::verifier::filecorrectness::{class_is_interface, is_java_sub_class_of, super_class_chain};
+use crate::verifier::Frame;
+use crate::verifier::instructions::{AfterGotoFrames, at_offset, InstructionTypeSafe, instruction_satisfies_handlers, ResultFrames};
+use crate::verifier::instructions::big_match::instruction_is_type_safe;
+use crate::verifier::TypeSafetyError;
+use crate::VerifierContext;
+
+/// Verification by type inference (JVMS §4.10.2), for class files without stack maps. The frame at each
+/// instruction starts out unknown and is found by running the usual instruction rules over the method,
+/// merging the frames flowing into each branch target and handler until none of them change.
+pub fn method_is_type_safe_by_inference(env: &mut Environment, instructions: &[&CInstruction], initial_frame: Frame) -> Result<(), TypeSafetyError> {
+    let code = instructions.iter().filter(|instruction| !matches!(instruction.info, CInstructionInfo::EndOfCode)).cloned().collect::<Vec<_>>();
+    let next_offsets = code.iter().enumerate().map(|(i, instruction)| (instruction.offset, code.get(i + 1).map(|next| next.offset))).collect::<HashMap<_, _>>();
+    let by_offset = code.iter().map(|instruction| (instruction.offset, *instruction)).collect::<HashMap<_, _>>();
+    let subroutines = Subroutines::new(&code, &next_offsets);
+    env.vf.verification_types.remove(&(env.method.method_index as u16));
+    env.inferred_targets = Some(RefCell::new(vec![]));
+    let mut frames = InferredFrames { frames: HashMap::new(), changed: BTreeSet::new(), next_offsets: &next_offsets };
+    frames.merge(&env.vf, code[0].offset, initial_frame)?;
+    //the rets seen so far for each subroutine
+    let mut rets: HashMap<ByteCodeOffset, BTreeSet<ByteCodeOffset>> = HashMap::new();
+    while let Some(offset) = frames.changed.pop_first() {
+        let instruction = by_offset[&offset];
+        let frame = frames.frames[&offset].clone();
+        let at_instruction = |err| at_offset(err, offset, Some(&instruction.info));
+        let next_frame = match instruction_is_type_safe(instruction, env, offset, frame.clone()).map_err(at_instruction)? {
+            InstructionTypeSafe::Safe(ResultFrames { next_frame, exception_frame }) => {
+                instruction_satisfies_handlers(env, offset, &exception_frame).map_err(at_instruction)?;
+                Some(next_frame)
+            }
+            InstructionTypeSafe::AfterGoto(AfterGotoFrames { exception_frame }) => {
+                instruction_satisfies_handlers(env, offset, &exception_frame).map_err(at_instruction)?;
+                None
+            }
+        };
+        let mut successors = env.inferred_targets.as_ref().unwrap().take();
+        if let Some(next_frame) = next_frame {
+            successors.push((fall_through(&next_offsets, offset).map_err(at_instruction)?, next_frame));
+        }
+        if let Some(subroutine) = jsr_target(instruction) {
+            //the subroutine may already have returned to its other callers
+            for ret in rets.get(&subroutine).into_iter().flatten() {
+                let return_frame = subroutines.return_frame(subroutine, &frame, &frames.frames[ret]);
+                successors.push((fall_through(&next_offsets, offset).map_err(at_instruction)?, return_frame));
+            }
+        }
+        if let Some(index) = ret_index(instruction) {
+            let subroutine = match frame.locals.get(index as usize) {
+                Some(VType::ReturnAddress(subroutine)) => *subroutine,
+                _ => return Result::Err(at_instruction(TypeSafetyError::NotSafe(format!("Expected return address in locals[{}]", index)))),
+            };
+            rets.entry(subroutine).or_default().insert(offset);
+            for jsr in subroutines.callers(subroutine) {
+                if let Some(jsr_frame) = frames.frames.get(jsr) {
+                    let return_frame = subroutines.return_frame(subroutine, jsr_frame, &frame);
+                    successors.push((fall_through(&next_offsets, *jsr).map_err(at_instruction)?, return_frame));
+                }
+            }
+        }
+        for (target, frame) in successors {
+            frames.merge(&env.vf, target, frame).map_err(at_instruction)?;
+        }
+    }
+    //unreachable code is never checked, but the jit still wants a frame for every instruction
+    let max_locals = env.max_locals as usize;
+    let method_frames = env.vf.verification_types.entry(env.method.method_index as u16).or_default();
+    for instruction in code.iter() {
+        method_frames.entry(instruction.offset).or_insert_with(|| Frame {
+            locals: Rc::new(vec![VType::TopType; max_locals]),
+            stack_map: OperandStack::empty(),
+            flag_this_uninit: false,
+        });
+    }
+    Result::Ok(())
+}
+
+struct InferredFrames<'l> {
+    frames: HashMap<ByteCodeOffset, Frame>,
+    //instructions whose frame changed since they were last checked
+    changed: BTreeSet<ByteCodeOffset>,
+    next_offsets: &'l HashMap<ByteCodeOffset, Option<ByteCodeOffset>>,
+}
+
+impl InferredFrames<'_> {
+    fn merge(&mut self, vf: &VerifierContext, target: ByteCodeOffset, incoming: Frame) -> Result<(), TypeSafetyError> {
+        if !self.next_offsets.contains_key(&target) {
+            return Result::Err(TypeSafetyError::NotSafe(format!("Illegal target of jump or branch: {}", target.0)));
+        }
+        let merged = match self.frames.get(&target) {
+            None => incoming,
+            Some(existing) => {
+                let merged = merge_frames(vf, existing, &incoming)?;
+                if &merged == existing {
+                    return Result::Ok(());
+                }
+                merged
+            }
+        };
+        self.frames.insert(target, merged);
+        self.changed.insert(target);
+        Result::Ok(())
+    }
+}
+
+fn fall_through(next_offsets: &HashMap<ByteCodeOffset, Option<ByteCodeOffset>>, offset: ByteCodeOffset) -> Result<ByteCodeOffset, TypeSafetyError> {
+    match next_offsets[&offset] {
+        None => Result::Err(TypeSafetyError::NotSafe("Falling off the end of the code".to_string())),
+        Some(next) => Result::Ok(next),
+    }
+}
+
+fn merge_frames(vf: &VerifierContext, existing: &Frame, incoming: &Frame) -> Result<Frame, TypeSafetyError> {
+    if existing.stack_map.len() != incoming.stack_map.len() {
+        return Result::Err(TypeSafetyError::NotSafe(format!("Inconsistent stack height {} != {}", existing.stack_map.len(), incoming.stack_map.len())));
+    }
+    let mut locals = vec![];
+    for (existing_, incoming_) in existing.locals.iter().zip(incoming.locals.iter()) {
+        //locals which don't agree just become unusable
+        locals.push(merge_types(vf, existing_, incoming_)?.unwrap_or(VType::TopType));
+    }
+    let mut stack = VecDeque::new();
+    for (i, (existing_, incoming_)) in existing.stack_map.iter().zip(incoming.stack_map.iter()).enumerate() {
+        match merge_types(vf, existing_, incoming_)? {
+            Some(merged) => stack.push_back(merged),
+            None => {
+                let stack_index = existing.stack_map.len() - 1 - i;
+                return Result::Err(TypeSafetyError::NotSafe(format!("Mismatched stack types {} and {} at stack[{}]", existing_.verifier_name(vf.string_pool), incoming_.verifier_name(vf.string_pool), stack_index)));
+            }
+        }
+    }
+    Result::Ok(Frame {
+        locals: Rc::new(locals),
+        stack_map: OperandStack { data: stack },
+        flag_this_uninit: existing.flag_this_uninit || incoming.flag_this_uninit,
+    })
+}
+
+fn merge_types(vf: &VerifierContext, left: &VType, right: &VType) -> Result<Option<VType>, TypeSafetyError> {
+    if left == right {
+        return Result::Ok(Some(left.clone()));
+    }
+    match (left, right) {
+        (VType::NullType, VType::Class(_) | VType::ArrayReferenceType(_)) => Result::Ok(Some(right.clone())),
+        (VType::Class(_) | VType::ArrayReferenceType(_), VType::NullType) => Result::Ok(Some(left.clone())),
+        (VType::Class(_) | VType::ArrayReferenceType(_), VType::Class(_) | VType::ArrayReferenceType(_)) => Result::Ok(Some(merge_references(vf, left, right)?)),
+        _ => Result::Ok(None),
+    }
+}
+
+fn merge_references(vf: &VerifierContext, left: &VType, right: &VType) -> Result<VType, TypeSafetyError> {
+    let object = VType::Class(ClassWithLoader { class_name: CClassName::object(), loader: vf.current_loader });
+    match (left, right) {
+        (VType::ArrayReferenceType(left_elem), VType::ArrayReferenceType(right_elem)) => {
+            //arrays of references merge by element type, arrays of different primitives only have Object in common
+            if left_elem.try_unwrap_ref_type().is_none() || right_elem.try_unwrap_ref_type().is_none() {
+                return Result::Ok(object);
+            }
+            let merged_elem = merge_references(vf, &left_elem.to_verification_type(vf.current_loader), &right_elem.to_verification_type(vf.current_loader))?;
+            Result::Ok(match merged_elem {
+                VType::Class(class) => VType::ArrayReferenceType(CPDType::Class(class.class_name)),
+                VType::ArrayReferenceType(elem) => VType::ArrayReferenceType(CPDType::array(elem)),
+                _ => object,
+            })
+        }
+        (VType::Class(left_class), VType::Class(right_class)) => {
+            //like the old verifier, interfaces are treated as Object
+            if class_is_interface(vf, left_class)? || class_is_interface(vf, right_class)? {
+                return Result::Ok(object);
+            }
+            let mut chain = vec![left_class.clone()];
+            super_class_chain(vf, left_class, left_class.loader, &mut chain)?;
+            for candidate in chain {
+                match is_java_sub_class_of(vf, right_class, &candidate) {
+                    Ok(()) => return Result::Ok(VType::Class(candidate)),
+                    Err(TypeSafetyError::NotSafe(_)) => {}
+                    Err(err) => return Result::Err(err),
+                }
+            }
+            Result::Ok(object)
+        }
+        _ => Result::Ok(object),
+    }
+}
+
+fn jsr_target(instruction: &CInstruction) -> Option<ByteCodeOffset> {
+    match instruction.info {
+        CInstructionInfo::jsr(target) => Some(branch_target(instruction.offset, target as i32)),
+        CInstructionInfo::jsr_w(target) => Some(branch_target(instruction.offset, target)),
+        _ => None,
+    }
+}
+
+fn ret_index(instruction: &CInstruction) -> Option<u16> {
+    match instruction.info {
+        CInstructionInfo::ret(index) => Some(index as u16),
+        CInstructionInfo::wide(Wide::Ret(WideRet { index })) => Some(index),
+        _ => None,
+    }
+}
+
+fn branch_target(offset: ByteCodeOffset, delta: i32) -> ByteCodeOffset {
+    ByteCodeOffset((offset.0 as i32 + delta) as u16)
+}
+
+/// Which jsrs call each subroutine, and which locals each subroutine might store to. After a ret those
+/// locals come from the subroutine, and every other local is as it was before that particular jsr.
+struct Subroutines {
+    callers: HashMap<ByteCodeOffset, Vec<ByteCodeOffset>>,
+    modified_locals: HashMap<ByteCodeOffset, HashSet<u16>>,
+}
+
+impl Subroutines {
+    fn new(code: &[&CInstruction], next_offsets: &HashMap<ByteCodeOffset, Option<ByteCodeOffset>>) -> Self {
+        let by_offset = code.iter().map(|instruction| (instruction.offset, *instruction)).collect::<HashMap<_, _>>();
+        let mut callers: HashMap<ByteCodeOffset, Vec<ByteCodeOffset>> = HashMap::new();
+        for instruction in code {
+            if let Some(subroutine) = jsr_target(instruction) {
+                callers.entry(subroutine).or_default().push(instruction.offset);
+            }
+        }
+        let mut modified_locals = HashMap::new();
+        let mut nested = HashMap::new();
+        for subroutine in callers.keys() {
+            let (stores, called) = subroutine_body(*subroutine, &by_offset, next_offsets);
+            modified_locals.insert(*subroutine, stores);
+            nested.insert(*subroutine, called);
+        }
+        //a subroutine also modifies whatever the subroutines it calls modify
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (subroutine, called) in nested.iter() {
+                let mut stores = modified_locals[subroutine].clone();
+                for called in called {
+                    stores.extend(modified_locals[called].iter().cloned());
+                }
+                if stores.len() != modified_locals[subroutine].len() {
+                    modified_locals.insert(*subroutine, stores);
+                    changed = true;
+                }
+            }
+        }
+        Self { callers, modified_locals }
+    }
+
+    fn callers(&self, subroutine: ByteCodeOffset) -> &[ByteCodeOffset] {
+        self.callers.get(&subroutine).map(|callers| callers.as_slice()).unwrap_or(&[])
+    }
+
+    fn return_frame(&self, subroutine: ByteCodeOffset, jsr_frame: &Frame, ret_frame: &Frame) -> Frame {
+        let modified_locals = &self.modified_locals[&subroutine];
+        let locals = jsr_frame.locals.iter().zip(ret_frame.locals.iter()).enumerate().map(|(i, (before_jsr, at_ret))| {
+            if modified_locals.contains(&(i as u16)) {
+                at_ret.clone()
+            } else {
+                before_jsr.clone()
+            }
+        }).collect();
+        Frame {
+            locals: Rc::new(locals),
+            stack_map: ret_frame.stack_map.clone(),
+            flag_this_uninit: ret_frame.flag_this_uninit,
+        }
+    }
+}
+
+//the locals stored to by instructions reachable from the start of a subroutine without returning from it,
+//and the subroutines it calls
+fn subroutine_body(subroutine: ByteCodeOffset, by_offset: &HashMap<ByteCodeOffset, &CInstruction>, next_offsets: &HashMap<ByteCodeOffset, Option<ByteCodeOffset>>) -> (HashSet<u16>, HashSet<ByteCodeOffset>) {
+    let mut stores = HashSet::new();
+    let mut called = HashSet::new();
+    let mut visited = HashSet::new();
+    let mut to_visit = vec![subroutine];
+    while let Some(offset) = to_visit.pop() {
+        let instruction = match by_offset.get(&offset) {
+            Some(instruction) => instruction,
+            None => continue,
+        };
+        if !visited.insert(offset) {
+            continue;
+        }
+        stores.extend(stored_locals(&instruction.info));
+        let next = next_offsets[&offset];
+        match &instruction.info {
+            CInstructionInfo::goto_(target) => to_visit.push(branch_target(offset, *target as i32)),
+            CInstructionInfo::goto_w(target) => to_visit.push(branch_target(offset, *target)),
+            CInstructionInfo::if_acmpeq(target) | CInstructionInfo::if_acmpne(target) |
+            CInstructionInfo::if_icmpeq(target) | CInstructionInfo::if_icmpne(target) |
+            CInstructionInfo::if_icmplt(target) | CInstructionInfo::if_icmpge(target) |
+            CInstructionInfo::if_icmpgt(target) | CInstructionInfo::if_icmple(target) |
+            CInstructionInfo::ifeq(target) | CInstructionInfo::ifne(target) |
+            CInstructionInfo::iflt(target) | CInstructionInfo::ifge(target) |
+            CInstructionInfo::ifgt(target) | CInstructionInfo::ifle(target) |
+            CInstructionInfo::ifnonnull(target) | CInstructionInfo::ifnull(target) => {
+                to_visit.push(branch_target(offset, *target as i32));
+                to_visit.extend(next);
+            }
+            CInstructionInfo::tableswitch(switch) => {
+                to_visit.extend(switch.offsets.iter().map(|target| branch_target(offset, *target)));
+                to_visit.push(branch_target(offset, switch.default));
+            }
+            CInstructionInfo::lookupswitch(switch) => {
+                to_visit.extend(switch.pairs.iter().map(|(_, target)| branch_target(offset, *target)));
+                to_visit.push(branch_target(offset, switch.default));
+            }
+            CInstructionInfo::jsr(_) | CInstructionInfo::jsr_w(_) => {
+                called.insert(jsr_target(instruction).unwrap());
+                to_visit.extend(next);
+            }
+            CInstructionInfo::ret(_) | CInstructionInfo::wide(Wide::Ret(_)) |
+            CInstructionInfo::areturn | CInstructionInfo::dreturn | CInstructionInfo::freturn |
+            CInstructionInfo::ireturn | CInstructionInfo::lreturn | CInstructionInfo::return_ |
+            CInstructionInfo::athrow => {}
+            _ => to_visit.extend(next),
+        }
+    }
+    (stores, called)
+}
+
+fn stored_locals(info: &CInstructionInfo) -> Vec<u16> {
+    match info {
+        CInstructionInfo::istore(i) | CInstructionInfo::fstore(i) | CInstructionInfo::astore(i) => vec![*i as u16],
+        CInstructionInfo::lstore(i) | CInstructionInfo::dstore(i) => vec![*i as u16, *i as u16 + 1],
+        CInstructionInfo::istore_0 | CInstructionInfo::fstore_0 | CInstructionInfo::astore_0 => vec![0],
+        CInstructionInfo::istore_1 | CInstructionInfo::fstore_1 | CInstructionInfo::astore_1 => vec![1],
+        CInstructionInfo::istore_2 | CInstructionInfo::fstore_2 | CInstructionInfo::astore_2 => vec![2],
+        CInstructionInfo::istore_3 | CInstructionInfo::fstore_3 | CInstructionInfo::astore_3 => vec![3],
+        CInstructionInfo::lstore_0 | CInstructionInfo::dstore_0 => vec![0, 1],
+        CInstructionInfo::lstore_1 | CInstructionInfo::dstore_1 => vec![1, 2],
+        CInstructionInfo::lstore_2 | CInstructionInfo::dstore_2 => vec![2, 3],
+        CInstructionInfo::lstore_3 | CInstructionInfo::dstore_3 => vec![3, 4],
+        CInstructionInfo::iinc(IInc { index, .. }) => vec![*index],
+        CInstructionInfo::wide(wide) => match wide {
+            Wide::Istore(WideIstore { index }) | Wide::Fstore(WideFstore { index }) | Wide::Astore(WideAstore { index }) => vec![*index],
+            Wide::Lstore(WideLstore { index }) | Wide::Dstore(WideDstore { index }) => vec![*index, *index + 1],
+            Wide::IInc(IInc { index, .. }) => vec![*index],
+            _ => vec![],
+        },
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::cell::RefCell;
+    use std::collections::{HashMap, VecDeque};
+    use std::rc::Rc;
+    use std::sync::{Arc, Mutex};
+
+    use perf_metrics::PerfMetrics;
+    use rust_jvm_common::ByteCodeOffset;
+    use rust_jvm_common::compressed_classfile::class_names::CClassName;
+    use rust_jvm_common::compressed_classfile::code::{CInstruction, CInstructionInfo};
+    use rust_jvm_common::compressed_classfile::compressed_types::CPDType;
+    use rust_jvm_common::compressed_classfile::string_pool::CompressedClassfileStringPool;
+    use rust_jvm_common::loading::{ClassWithLoader, LoaderName, NoopLivePoolGetter};
+    use rust_jvm_common::vtype::VType;
+
+    use crate::{NoopClassFileGetter, OperandStack, VerifierContext};
+    use crate::verifier::{ClassWithLoaderMethod, Frame, TypeSafetyError};
+    use crate::verifier::codecorrectness::Environment;
+    use crate::verifier::typeinference::{merge_frames, method_is_type_safe_by_inference};
+
+    const MAX_LOCALS: u16 = 5;
+
+    fn verifier_context<'l>(string_pool: &'l CompressedClassfileStringPool, perf_metrics: &'l PerfMetrics) -> VerifierContext<'l> {
+        VerifierContext {
+            live_pool_getter: Arc::new(NoopLivePoolGetter {}),
+            classfile_getter: Arc::new(NoopClassFileGetter),
+            string_pool,
+            current_class: CClassName::object(),
+            class_view_cache: Mutex::new(HashMap::new()),
+            current_loader: LoaderName::BootstrapLoader,
+            verification_types: HashMap::new(),
+            debug: false,
+            perf_metrics,
+            permissive_types_workaround: false,
+        }
+    }
+
+    fn frame(locals: Vec<VType>, stack: Vec<VType>) -> Frame {
+        Frame { locals: Rc::new(locals), stack_map: OperandStack { data: VecDeque::from(stack) }, flag_this_uninit: false }
+    }
+
+    //lays out the instructions one after another, starting at offset 0
+    fn code(instructions: Vec<(u16, CInstructionInfo)>) -> Vec<CInstruction> {
+        let mut offset = 0;
+        instructions.into_iter().map(|(instruction_size, info)| {
+            let instruction = CInstruction { offset: ByteCodeOffset(offset), instruction_size, info };
+            offset += instruction_size;
+            instruction
+        }).collect()
+    }
+
+    fn infer(vf: &mut VerifierContext, code: &[CInstruction]) -> Result<(), TypeSafetyError> {
+        let mut env = Environment {
+            method: ClassWithLoaderMethod { class: ClassWithLoader { class_name: CClassName::object(), loader: LoaderName::BootstrapLoader }, method_index: 0 },
+            return_type: VType::VoidType,
+            frame_size: MAX_LOCALS,
+            max_stack: 2,
+            max_locals: MAX_LOCALS,
+            merged_code: None,
+            class_loader: LoaderName::BootstrapLoader,
+            handlers: vec![],
+            vf,
+            debug: false,
+            inferred_targets: Some(RefCell::new(vec![])),
+        };
+        let instructions = code.iter().collect::<Vec<_>>();
+        method_is_type_safe_by_inference(&mut env, instructions.as_slice(), frame(vec![VType::TopType; MAX_LOCALS as usize], vec![]))
+    }
+
+    fn string() -> VType {
+        VType::Class(ClassWithLoader { class_name: CClassName::string(), loader: LoaderName::BootstrapLoader })
+    }
+
+    //try { i = 1; } finally { j = 2; try {} finally { k = 3; } }, compiled with jsr/ret
+    fn nested_subroutines(vf: &mut VerifierContext) {
+        let code = code(vec![
+            (1, CInstructionInfo::iconst_1),
+            (1, CInstructionInfo::istore_0),
+            (3, CInstructionInfo::jsr(6)),
+            (1, CInstructionInfo::iload_0),
+            (1, CInstructionInfo::istore_3),
+            (1, CInstructionInfo::return_),
+            //outer subroutine, at 8
+            (1, CInstructionInfo::astore_1),
+            (1, CInstructionInfo::iconst_2),
+            (1, CInstructionInfo::istore_2),
+            (3, CInstructionInfo::jsr(5)),
+            (2, CInstructionInfo::ret(1)),
+            //inner subroutine, at 16
+            (2, CInstructionInfo::astore(4)),
+            (1, CInstructionInfo::iconst_3),
+            (1, CInstructionInfo::istore_3),
+            (2, CInstructionInfo::ret(4)),
+        ]);
+        infer(vf, &code).unwrap();
+        //both subroutines have returned, and the locals they stored to are visible after the outer jsr
+        let after_jsr = &vf.verification_types[&0][&ByteCodeOffset(5)];
+        assert_eq!(after_jsr, &frame(vec![VType::IntType, VType::ReturnAddress(ByteCodeOffset(8)), VType::IntType, VType::IntType, VType::ReturnAddress(ByteCodeOffset(16))], vec![]));
+    }
+
+    fn ret_without_return_address(vf: &mut VerifierContext) {
+        let code = code(vec![
+            (1, CInstructionInfo::iconst_0),
+            (1, CInstructionInfo::istore_0),
+            (2, CInstructionInfo::ret(0)),
+        ]);
+        assert!(matches!(infer(vf, &code), Err(TypeSafetyError::NotSafe(_))));
+        let code = code(vec![(2, CInstructionInfo::ret(MAX_LOCALS as u8))]);
+        assert!(matches!(infer(vf, &code), Err(TypeSafetyError::NotSafe(_))));
+    }
+
+    fn inconsistent_stack_height(vf: &mut VerifierContext) {
+        //the branch reaches return with an empty stack, falling through leaves an int on it
+        let code = code(vec![
+            (1, CInstructionInfo::iconst_0),
+            (3, CInstructionInfo::ifeq(4)),
+            (1, CInstructionInfo::iconst_1),
+            (1, CInstructionInfo::return_),
+        ]);
+        match infer(vf, &code) {
+            Err(TypeSafetyError::NotSafe(msg)) => assert!(msg.contains("Inconsistent stack height"), "{}", msg),
+            other => panic!("expected inconsistent stack height, got {:?}", other),
+        }
+    }
+
+    fn reference_merges(vf: &mut VerifierContext) {
+        let object = VType::Class(ClassWithLoader { class_name: CClassName::object(), loader: LoaderName::BootstrapLoader });
+        let int_array = VType::ArrayReferenceType(CPDType::IntType);
+        let string_array = VType::ArrayReferenceType(CPDType::Class(CClassName::string()));
+        let existing = frame(vec![VType::NullType, int_array.clone(), string_array.clone(), string(), VType::IntType], vec![string()]);
+        let incoming = frame(vec![string(), string_array.clone(), VType::NullType, VType::IntType, VType::IntType], vec![VType::NullType]);
+        let merged = merge_frames(vf, &existing, &incoming).unwrap();
+        assert_eq!(merged, frame(vec![string(), object, string_array, VType::TopType, VType::IntType], vec![string()]));
+        //a reference and a primitive can't be merged on the stack
+        let incoming = frame(vec![VType::TopType; MAX_LOCALS as usize], vec![VType::IntType]);
+        assert!(matches!(merge_frames(vf, &existing, &incoming), Err(TypeSafetyError::NotSafe(_))));
+    }
+
+    //there can only be one string pool per process, so everything shares this test
+    #[test]
+    pub fn type_inference() {
+        let string_pool = CompressedClassfileStringPool::new();
+        let perf_metrics = PerfMetrics::new();
+        let mut vf = verifier_context(&string_pool, &perf_metrics);
+        nested_subroutines(&mut vf);
+        ret_without_return_address(&mut vf);
+        inconsistent_stack_height(&mut vf);
+        reference_merges(&mut vf);
+    }
+}
@@ -148,6 +148,24 @@ impl<'gc> VTables<'gc> {
         }
     }
 
+    pub fn vtable_classes(&self) -> impl Iterator<Item=(NonNull<RawNativeVTable>, &Arc<RuntimeClass<'gc>>)> {
+        self.inner.iter().map(|(rc, vtable)| (*vtable, &rc.0))
+    }
+
+    /// Frees the vtable of an unloaded class, returning it so that nothing is left pointing at it.
+    pub fn unload_class(&mut self, rc: &Arc<RuntimeClass<'gc>>) -> Option<NonNull<RawNativeVTable>> {
+        let raw_native_vtable = self.inner.remove(&ByAddress(rc.clone()))?;
+        self.resolved_to_entry.retain(|_, entries| {
+            entries.retain(|(entry_rc, _)| !Arc::ptr_eq(entry_rc, rc));
+            !entries.is_empty()
+        });
+        unsafe {
+            let RawNativeVTable { capacity, len, ptr } = *Box::from_raw(raw_native_vtable.as_ptr());
+            drop(Vec::from_raw_parts(ptr, len, capacity));
+        }
+        Some(raw_native_vtable)
+    }
+
     pub fn vtable_register_entry(&mut self, rc: Arc<RuntimeClass<'gc>>, method_number: MethodNumber, entry: VTableEntry) -> NonNull<RawNativeVTable> {
         self.resolved_to_entry.entry(entry.address.unwrap()).or_default().push((rc.clone(), method_number));
         let raw_native_table = self.lookup_or_new_vtable(rc);
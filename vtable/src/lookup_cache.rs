@@ -26,4 +26,8 @@ impl<'gc> InvokeVirtualLookupCache<'gc> {
     pub fn lookup(&self, rc: Arc<RuntimeClass<'gc>>, name: MethodName, desc: CompressedMethodDescriptor) -> Option<(Arc<RuntimeClass<'gc>>, u16)> {
         self.inner.get(&(ByAddress(rc), name, desc)).cloned()
     }
+
+    pub fn unload_class(&mut self, rc: &Arc<RuntimeClass<'gc>>) {
+        self.inner.retain(|(key_rc, _, _), (res_rc, _)| !Arc::ptr_eq(&key_rc.0, rc) && !Arc::ptr_eq(res_rc, rc));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,426 @@
+use std::fmt::Write;
+
+// The generated method works on a fixed set of variables: one parameter, LOCALS_PER_TYPE locals and
+// one array of ARRAY_LENGTH elements for each type, so any statement can be dropped or rewritten by
+// the shrinker without ever producing source javac rejects.
+pub const LOCALS_PER_TYPE: usize = 3;
+pub const ARRAY_LENGTH: usize = 4;
+pub const CLASS_NAME: &str = "Generated";
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Type {
+    Int,
+    Long,
+    Float,
+    Double,
+}
+
+impl Type {
+    pub const ALL: [Type; 4] = [Type::Int, Type::Long, Type::Float, Type::Double];
+
+    pub fn java_name(&self) -> &'static str {
+        match self {
+            Type::Int => "int",
+            Type::Long => "long",
+            Type::Float => "float",
+            Type::Double => "double",
+        }
+    }
+
+    fn short_name(&self) -> &'static str {
+        match self {
+            Type::Int => "i",
+            Type::Long => "l",
+            Type::Float => "f",
+            Type::Double => "d",
+        }
+    }
+
+    pub fn is_integral(&self) -> bool {
+        matches!(self, Type::Int | Type::Long)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Const {
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+}
+
+impl Const {
+    pub fn zero(type_: Type) -> Const {
+        match type_ {
+            Type::Int => Const::Int(0),
+            Type::Long => Const::Long(0),
+            Type::Float => Const::Float(0.0),
+            Type::Double => Const::Double(0.0),
+        }
+    }
+
+    pub fn type_(&self) -> Type {
+        match self {
+            Const::Int(_) => Type::Int,
+            Const::Long(_) => Type::Long,
+            Const::Float(_) => Type::Float,
+            Const::Double(_) => Type::Double,
+        }
+    }
+
+    // bitwise, so that -0.0 is not zero and NaN is equal to itself
+    pub fn is_zero(&self) -> bool {
+        match self {
+            Const::Int(int) => *int == 0,
+            Const::Long(long) => *long == 0,
+            Const::Float(float) => float.to_bits() == 0,
+            Const::Double(double) => double.to_bits() == 0,
+        }
+    }
+
+    fn java_source(&self) -> String {
+        match *self {
+            Const::Int(int) => format!("({})", int),
+            Const::Long(long) => format!("({}L)", long),
+            Const::Float(float) if float.is_nan() => "Float.NaN".to_string(),
+            Const::Float(float) if float == f32::INFINITY => "Float.POSITIVE_INFINITY".to_string(),
+            Const::Float(float) if float == f32::NEG_INFINITY => "Float.NEGATIVE_INFINITY".to_string(),
+            Const::Float(float) => format!("({:?}f)", float),
+            Const::Double(double) if double.is_nan() => "Double.NaN".to_string(),
+            Const::Double(double) if double == f64::INFINITY => "Double.POSITIVE_INFINITY".to_string(),
+            Const::Double(double) if double == f64::NEG_INFINITY => "Double.NEGATIVE_INFINITY".to_string(),
+            Const::Double(double) => format!("({:?}d)", double),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    // integral only
+    And,
+    Or,
+    Xor,
+    // integral only, the right hand side is always an int
+    Shl,
+    Shr,
+    Ushr,
+}
+
+impl BinaryOp {
+    pub const ARITHMETIC: [BinaryOp; 5] = [BinaryOp::Add, BinaryOp::Sub, BinaryOp::Mul, BinaryOp::Div, BinaryOp::Rem];
+    pub const INTEGRAL: [BinaryOp; 6] = [BinaryOp::And, BinaryOp::Or, BinaryOp::Xor, BinaryOp::Shl, BinaryOp::Shr, BinaryOp::Ushr];
+
+    pub fn is_shift(&self) -> bool {
+        matches!(self, BinaryOp::Shl | BinaryOp::Shr | BinaryOp::Ushr)
+    }
+
+    fn java_operator(&self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+            BinaryOp::And => "&",
+            BinaryOp::Or => "|",
+            BinaryOp::Xor => "^",
+            BinaryOp::Shl => "<<",
+            BinaryOp::Shr => ">>",
+            BinaryOp::Ushr => ">>>",
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Narrowing {
+    Byte,
+    Short,
+    Char,
+}
+
+impl Narrowing {
+    pub const ALL: [Narrowing; 3] = [Narrowing::Byte, Narrowing::Short, Narrowing::Char];
+
+    fn java_name(&self) -> &'static str {
+        match self {
+            Narrowing::Byte => "byte",
+            Narrowing::Short => "short",
+            Narrowing::Char => "char",
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CompareOp {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+}
+
+impl CompareOp {
+    pub const ALL: [CompareOp; 6] = [CompareOp::Less, CompareOp::LessEqual, CompareOp::Greater, CompareOp::GreaterEqual, CompareOp::Equal, CompareOp::NotEqual];
+
+    fn java_operator(&self) -> &'static str {
+        match self {
+            CompareOp::Less => "<",
+            CompareOp::LessEqual => "<=",
+            CompareOp::Greater => ">",
+            CompareOp::GreaterEqual => ">=",
+            CompareOp::Equal => "==",
+            CompareOp::NotEqual => "!=",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Const(Const),
+    Param(Type),
+    Local(Type, usize),
+    Negate(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Convert(Type, Box<Expr>),
+    // an int narrowed to byte/short/char and widened back to int
+    Narrow(Narrowing, Box<Expr>),
+    // the index is always an int
+    ArrayLoad(Type, Box<Expr>),
+}
+
+impl Expr {
+    pub fn type_(&self) -> Type {
+        match self {
+            Expr::Const(const_) => const_.type_(),
+            Expr::Param(type_) |
+            Expr::Local(type_, _) |
+            Expr::Convert(type_, _) |
+            Expr::ArrayLoad(type_, _) => *type_,
+            Expr::Negate(expr) => expr.type_(),
+            Expr::Binary(_, lhs, _) => lhs.type_(),
+            Expr::Narrow(_, _) => Type::Int,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        1 + match self {
+            Expr::Const(_) | Expr::Param(_) | Expr::Local(_, _) => 0,
+            Expr::Negate(expr) |
+            Expr::Convert(_, expr) |
+            Expr::Narrow(_, expr) |
+            Expr::ArrayLoad(_, expr) => expr.size(),
+            Expr::Binary(_, lhs, rhs) => lhs.size() + rhs.size(),
+        }
+    }
+
+    fn java_source(&self) -> String {
+        match self {
+            Expr::Const(const_) => const_.java_source(),
+            Expr::Param(type_) => param_name(*type_),
+            Expr::Local(type_, index) => local_name(*type_, *index),
+            Expr::Negate(expr) => format!("(-{})", expr.java_source()),
+            Expr::Binary(op, lhs, rhs) => format!("({} {} {})", lhs.java_source(), op.java_operator(), rhs.java_source()),
+            Expr::Convert(type_, expr) => format!("(({}) {})", type_.java_name(), expr.java_source()),
+            Expr::Narrow(narrowing, expr) => format!("((int) ({}) {})", narrowing.java_name(), expr.java_source()),
+            Expr::ArrayLoad(type_, index) => format!("{}[{}]", array_name(*type_), index.java_source()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Condition {
+    pub op: CompareOp,
+    pub lhs: Expr,
+    pub rhs: Expr,
+}
+
+impl Condition {
+    fn java_source(&self) -> String {
+        format!("{} {} {}", self.lhs.java_source(), self.op.java_operator(), self.rhs.java_source())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Stmt {
+    Assign(Type, usize, Expr),
+    ArrayStore(Type, Expr, Expr),
+    If(Condition, Vec<Stmt>, Vec<Stmt>),
+    Loop(u32, Vec<Stmt>),
+}
+
+impl Stmt {
+    pub fn size(&self) -> usize {
+        match self {
+            Stmt::Assign(_, _, expr) => 1 + expr.size(),
+            Stmt::ArrayStore(_, index, value) => 1 + index.size() + value.size(),
+            Stmt::If(condition, then, else_) => 1 + condition.lhs.size() + condition.rhs.size() + block_size(then) + block_size(else_),
+            Stmt::Loop(_, body) => 1 + block_size(body),
+        }
+    }
+
+    fn write_java_source(&self, out: &mut String, depth: usize) {
+        let indent = "    ".repeat(depth + 2);
+        match self {
+            Stmt::Assign(type_, index, expr) => {
+                writeln!(out, "{}{} = {};", indent, local_name(*type_, *index), expr.java_source()).unwrap();
+            }
+            Stmt::ArrayStore(type_, index, value) => {
+                writeln!(out, "{}{}[{}] = {};", indent, array_name(*type_), index.java_source(), value.java_source()).unwrap();
+            }
+            Stmt::If(condition, then, else_) => {
+                writeln!(out, "{}if ({}) {{", indent, condition.java_source()).unwrap();
+                write_block(out, then, depth + 1);
+                writeln!(out, "{}}} else {{", indent).unwrap();
+                write_block(out, else_, depth + 1);
+                writeln!(out, "{}}}", indent).unwrap();
+            }
+            Stmt::Loop(trip_count, body) => {
+                // loop counters are only named by depth, nested loops can't see each other's
+                writeln!(out, "{}for (int c{depth} = 0; c{depth} < {}; c{depth}++) {{", indent, trip_count, depth = depth).unwrap();
+                write_block(out, body, depth + 1);
+                writeln!(out, "{}}}", indent).unwrap();
+            }
+        }
+    }
+}
+
+pub fn block_size(block: &[Stmt]) -> usize {
+    block.iter().map(|stmt| stmt.size()).sum()
+}
+
+fn write_block(out: &mut String, block: &[Stmt], depth: usize) {
+    for stmt in block {
+        stmt.write_java_source(out, depth);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Program {
+    // one argument per type, in Type::ALL order
+    pub args: [Const; 4],
+    pub body: Vec<Stmt>,
+}
+
+impl Program {
+    pub fn size(&self) -> usize {
+        block_size(&self.body)
+    }
+
+    // A class whose main calls run and prints either the checksum of every variable afterwards or the
+    // class of the exception it threw.
+    pub fn java_source(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "public class {} {{", CLASS_NAME).unwrap();
+        let params = Type::ALL.iter().map(|type_| format!("{} {}", type_.java_name(), param_name(*type_))).collect::<Vec<_>>().join(", ");
+        writeln!(out, "    static long run({}) {{", params).unwrap();
+        for type_ in Type::ALL {
+            let locals = (0..LOCALS_PER_TYPE).map(|index| format!("{} = {}", local_name(type_, index), Const::zero(type_).java_source())).collect::<Vec<_>>().join(", ");
+            writeln!(out, "        {} {};", type_.java_name(), locals).unwrap();
+            writeln!(out, "        {}[] {} = new {}[{}];", type_.java_name(), array_name(type_), type_.java_name(), ARRAY_LENGTH).unwrap();
+        }
+        write_block(&mut out, &self.body, 0);
+        writeln!(out, "        long checksum = 0;").unwrap();
+        for type_ in Type::ALL {
+            for index in 0..LOCALS_PER_TYPE {
+                writeln!(out, "        checksum = checksum * 31 + {};", checksum_term(type_, local_name(type_, index))).unwrap();
+            }
+            writeln!(out, "        for (int i = 0; i < {}; i++) {{", ARRAY_LENGTH).unwrap();
+            writeln!(out, "            checksum = checksum * 31 + {};", checksum_term(type_, format!("{}[i]", array_name(type_)))).unwrap();
+            writeln!(out, "        }}").unwrap();
+        }
+        writeln!(out, "        return checksum;").unwrap();
+        writeln!(out, "    }}").unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "    public static void main(String[] args) {{").unwrap();
+        writeln!(out, "        try {{").unwrap();
+        let args = self.args.iter().map(|arg| arg.java_source()).collect::<Vec<_>>().join(", ");
+        writeln!(out, "            System.out.println(run({}));", args).unwrap();
+        writeln!(out, "        }} catch (Throwable throwable) {{").unwrap();
+        writeln!(out, "            System.out.println(\"threw \" + throwable.getClass().getName());").unwrap();
+        writeln!(out, "        }}").unwrap();
+        writeln!(out, "    }}").unwrap();
+        writeln!(out, "}}").unwrap();
+        out
+    }
+}
+
+fn param_name(type_: Type) -> String {
+    format!("p{}", type_.short_name())
+}
+
+fn local_name(type_: Type, index: usize) -> String {
+    format!("v{}{}", type_.short_name(), index)
+}
+
+fn array_name(type_: Type) -> String {
+    format!("a{}", type_.short_name())
+}
+
+// NaNs are canonicalized, since which NaN an operation produces is not specified
+fn checksum_term(type_: Type, value: String) -> String {
+    match type_ {
+        Type::Int | Type::Long => value,
+        Type::Float => format!("Float.floatToIntBits({})", value),
+        Type::Double => format!("Double.doubleToLongBits({})", value),
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::differential::ast::{BinaryOp, Expr, LOCALS_PER_TYPE, Program, Stmt, Type};
+
+    // Everything javac would reject in a program built from these types: mistyped operands and
+    // locals which don't exist.
+    pub fn assert_well_typed(program: &Program) {
+        for (arg, type_) in program.args.iter().zip(Type::ALL) {
+            assert_eq!(arg.type_(), type_);
+        }
+        program.body.iter().for_each(assert_stmt_well_typed);
+    }
+
+    fn assert_stmt_well_typed(stmt: &Stmt) {
+        match stmt {
+            Stmt::Assign(type_, index, expr) => {
+                assert!(*index < LOCALS_PER_TYPE);
+                assert_eq!(expr_type(expr), *type_);
+            }
+            Stmt::ArrayStore(type_, index, value) => {
+                assert_eq!(expr_type(index), Type::Int);
+                assert_eq!(expr_type(value), *type_);
+            }
+            Stmt::If(condition, then, else_) => {
+                assert_eq!(expr_type(&condition.lhs), expr_type(&condition.rhs));
+                then.iter().chain(else_.iter()).for_each(assert_stmt_well_typed);
+            }
+            Stmt::Loop(trip_count, body) => {
+                assert!(*trip_count > 0);
+                body.iter().for_each(assert_stmt_well_typed);
+            }
+        }
+    }
+
+    fn expr_type(expr: &Expr) -> Type {
+        match expr {
+            Expr::Const(_) | Expr::Param(_) => {}
+            Expr::Local(_, index) => assert!(*index < LOCALS_PER_TYPE),
+            Expr::Negate(inner) => assert_eq!(expr_type(inner), expr.type_()),
+            Expr::Binary(op, lhs, rhs) => {
+                let lhs_type = expr_type(lhs);
+                assert!(lhs_type.is_integral() || !BinaryOp::INTEGRAL.contains(op), "{:?} of {:?}", op, lhs_type);
+                assert_eq!(expr_type(rhs), if op.is_shift() { Type::Int } else { lhs_type });
+            }
+            Expr::Convert(_, inner) => {
+                expr_type(inner);
+            }
+            Expr::Narrow(_, inner) |
+            Expr::ArrayLoad(_, inner) => assert_eq!(expr_type(inner), Type::Int),
+        }
+        expr.type_()
+    }
+}
@@ -0,0 +1,153 @@
+use crate::differential::ast::{ARRAY_LENGTH, BinaryOp, CompareOp, Condition, Const, Expr, LOCALS_PER_TYPE, Narrowing, Program, Stmt, Type};
+
+const MAX_BLOCK_LENGTH: usize = 6;
+const MAX_NESTING: usize = 3;
+const MAX_EXPR_DEPTH: usize = 4;
+const MAX_TRIP_COUNT: u32 = 8;
+
+// splitmix64, so that a seed reproduces the same programs everywhere
+pub struct Random(u64);
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    pub fn one_in(&mut self, n: usize) -> bool {
+        self.below(n) == 0
+    }
+
+    pub fn pick<T: Copy>(&mut self, choices: &[T]) -> T {
+        choices[self.below(choices.len())]
+    }
+}
+
+pub fn generate_program(random: &mut Random) -> Program {
+    let args = Type::ALL.map(|type_| random_const(random, type_));
+    let body = generate_block(random, 0);
+    Program { args, body }
+}
+
+fn generate_block(random: &mut Random, nesting: usize) -> Vec<Stmt> {
+    let length = 1 + random.below(MAX_BLOCK_LENGTH);
+    (0..length).map(|_| generate_stmt(random, nesting)).collect()
+}
+
+fn generate_stmt(random: &mut Random, nesting: usize) -> Stmt {
+    let choice = if nesting < MAX_NESTING { random.below(10) } else { random.below(7) };
+    match choice {
+        0..=4 => {
+            let type_ = random.pick(&Type::ALL);
+            Stmt::Assign(type_, random.below(LOCALS_PER_TYPE), generate_expr(random, type_, 0))
+        }
+        5 | 6 => {
+            let type_ = random.pick(&Type::ALL);
+            Stmt::ArrayStore(type_, generate_index(random), generate_expr(random, type_, 0))
+        }
+        7 | 8 => {
+            let type_ = random.pick(&Type::ALL);
+            let condition = Condition {
+                op: random.pick(&CompareOp::ALL),
+                lhs: generate_expr(random, type_, 1),
+                rhs: generate_expr(random, type_, 1),
+            };
+            let else_ = if random.one_in(2) { generate_block(random, nesting + 1) } else { vec![] };
+            Stmt::If(condition, generate_block(random, nesting + 1), else_)
+        }
+        _ => Stmt::Loop(1 + random.below(MAX_TRIP_COUNT as usize) as u32, generate_block(random, nesting + 1)),
+    }
+}
+
+fn generate_expr(random: &mut Random, type_: Type, depth: usize) -> Expr {
+    if depth >= MAX_EXPR_DEPTH || random.one_in(3) {
+        return generate_leaf(random, type_);
+    }
+    match random.below(10) {
+        0..=4 => {
+            let op = if type_.is_integral() && random.one_in(3) {
+                random.pick(&BinaryOp::INTEGRAL)
+            } else {
+                random.pick(&BinaryOp::ARITHMETIC)
+            };
+            let rhs_type = if op.is_shift() { Type::Int } else { type_ };
+            let mut rhs = generate_expr(random, rhs_type, depth + 1);
+            // locals start at zero, so most integral divisions would otherwise just throw ArithmeticException
+            if type_.is_integral() && matches!(op, BinaryOp::Div | BinaryOp::Rem) && !random.one_in(4) {
+                let one = Expr::Const(if type_ == Type::Int { Const::Int(1) } else { Const::Long(1) });
+                rhs = Expr::Binary(BinaryOp::Or, Box::new(rhs), Box::new(one));
+            }
+            Expr::Binary(op, Box::new(generate_expr(random, type_, depth + 1)), Box::new(rhs))
+        }
+        5 => Expr::Negate(Box::new(generate_expr(random, type_, depth + 1))),
+        6 | 7 => {
+            let from = random.pick(&Type::ALL);
+            Expr::Convert(type_, Box::new(generate_expr(random, from, depth + 1)))
+        }
+        8 if type_ == Type::Int => Expr::Narrow(random.pick(&Narrowing::ALL), Box::new(generate_expr(random, Type::Int, depth + 1))),
+        _ => Expr::ArrayLoad(type_, Box::new(generate_index(random))),
+    }
+}
+
+// mostly masked into bounds, the rest of the time free to throw ArrayIndexOutOfBoundsException
+fn generate_index(random: &mut Random) -> Expr {
+    if random.one_in(8) {
+        generate_expr(random, Type::Int, MAX_EXPR_DEPTH - 1)
+    } else {
+        let mask = Expr::Const(Const::Int(ARRAY_LENGTH as i32 - 1));
+        Expr::Binary(BinaryOp::And, Box::new(generate_expr(random, Type::Int, MAX_EXPR_DEPTH - 1)), Box::new(mask))
+    }
+}
+
+fn generate_leaf(random: &mut Random, type_: Type) -> Expr {
+    match random.below(4) {
+        0 => Expr::Const(random_const(random, type_)),
+        1 => Expr::Param(type_),
+        _ => Expr::Local(type_, random.below(LOCALS_PER_TYPE)),
+    }
+}
+
+// biased towards the edge cases conversions and arithmetic most often get wrong
+fn random_const(random: &mut Random, type_: Type) -> Const {
+    let interesting = random.one_in(2);
+    match type_ {
+        Type::Int if interesting => Const::Int(random.pick(&[0, 1, -1, 2, 31, 32, i32::MIN, i32::MAX, i8::MIN as i32, u16::MAX as i32])),
+        Type::Int => Const::Int(random.next_u64() as i32 >> random.below(32)),
+        Type::Long if interesting => Const::Long(random.pick(&[0, 1, -1, 63, 64, i64::MIN, i64::MAX, i32::MIN as i64, i32::MAX as i64 + 1])),
+        Type::Long => Const::Long(random.next_u64() as i64 >> random.below(64)),
+        Type::Float if interesting => Const::Float(random.pick(&[0.0, -0.0, 1.0, -1.5, f32::NAN, f32::INFINITY, f32::NEG_INFINITY, f32::MAX, f32::MIN_POSITIVE, 2147483648.0, 1e-45])),
+        Type::Float => Const::Float((random.next_u64() as i32 >> random.below(32)) as f32 / 8.0),
+        Type::Double if interesting => Const::Double(random.pick(&[0.0, -0.0, 1.0, -1.5, f64::NAN, f64::INFINITY, f64::NEG_INFINITY, f64::MAX, f64::MIN_POSITIVE, 9223372036854775808.0, 4.9e-324])),
+        Type::Double => Const::Double((random.next_u64() as i64 >> random.below(64)) as f64 / 8.0),
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::differential::ast::test::assert_well_typed;
+    use crate::differential::generate::{generate_program, Random};
+
+    #[test]
+    pub fn test_same_seed_same_programs() {
+        let mut first = Random::new(17);
+        let mut second = Random::new(17);
+        //compared as source, since NaN constants are never equal
+        for _ in 0..100 {
+            let program = generate_program(&mut first);
+            assert_eq!(program.java_source(), generate_program(&mut second).java_source());
+            assert_well_typed(&program);
+        }
+        assert_ne!(generate_program(&mut Random::new(17)).java_source(), generate_program(&mut Random::new(18)).java_source());
+    }
+}
@@ -0,0 +1,126 @@
+use std::fs;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use anyhow::anyhow;
+use wait_timeout::ChildExt;
+
+use crate::differential::ast::{CLASS_NAME, Program};
+use crate::differential::generate::{generate_program, Random};
+use crate::differential::shrink::shrink;
+use crate::java_compilation::{compile, JavaCLocation, javac_location};
+use crate::XTaskConfig;
+
+pub mod ast;
+pub mod generate;
+pub mod shrink;
+
+// Runs randomly generated methods once with everything interpreted and once with everything
+// compiled before it first runs, and reports any program where the two disagree on the value
+// computed or the exception thrown, shrunk down to a minimal program which still disagrees.
+
+// never reached, so nothing leaves the RunInterpreted path
+const INTERPRETED_THRESHOLD: u64 = u64::MAX;
+// compiled before the first instruction runs
+const COMPILED_THRESHOLD: u64 = 0;
+const RUN_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RunOutcome {
+    pub stdout: String,
+    // None if killed by a signal or timed out
+    pub exit_code: Option<i32>,
+}
+
+pub struct DifferentialTester {
+    javac: JavaCLocation,
+    java_binary: PathBuf,
+    java_home: PathBuf,
+    boot_classpath: Vec<PathBuf>,
+    work_dir: PathBuf,
+}
+
+impl DifferentialTester {
+    pub fn new(workspace_dir: &Path, config: &XTaskConfig) -> anyhow::Result<Self> {
+        let jdk_dir = config.dep_dir.join("jdk8u/build/linux-x86_64-normal-server-fastdebug/jdk");
+        let work_dir = config.dep_dir.join("differential");
+        fs::create_dir_all(&work_dir)?;
+        Ok(Self {
+            javac: javac_location(config),
+            java_binary: build_jvm(workspace_dir)?,
+            java_home: jdk_dir.clone(),
+            boot_classpath: vec![jdk_dir.join("classes"), jdk_dir.join("classes_security")],
+            work_dir,
+        })
+    }
+
+    // The two outcomes if they differ
+    pub fn compare(&self, program: &Program) -> anyhow::Result<Option<(RunOutcome, RunOutcome)>> {
+        let case_dir = self.work_dir.join("case");
+        if case_dir.exists() {
+            fs::remove_dir_all(&case_dir)?;
+        }
+        fs::create_dir_all(&case_dir)?;
+        let source_file = case_dir.join(format!("{}.java", CLASS_NAME));
+        fs::write(&source_file, program.java_source())?;
+        compile(&self.javac, vec![source_file], case_dir.as_path())?;
+        let interpreted = self.run(&case_dir, INTERPRETED_THRESHOLD)?;
+        let compiled = self.run(&case_dir, COMPILED_THRESHOLD)?;
+        Ok(if interpreted == compiled { None } else { Some((interpreted, compiled)) })
+    }
+
+    fn run(&self, case_dir: &Path, compile_threshold: u64) -> anyhow::Result<RunOutcome> {
+        let stdout_file = case_dir.join(format!("stdout_{}", compile_threshold));
+        let stderr_file = case_dir.join(format!("stderr_{}", compile_threshold));
+        let mut child = Command::new(&self.java_binary)
+            .arg("--main").arg(CLASS_NAME)
+            .arg("--java-home").arg(&self.java_home)
+            .arg("--classpath").arg(case_dir).args(&self.boot_classpath)
+            .arg("--compile-threshold").arg(compile_threshold.to_string())
+            .current_dir(case_dir)
+            .stdout(Stdio::from(File::create(&stdout_file)?))
+            .stderr(Stdio::from(File::create(&stderr_file)?))
+            .spawn()?;
+        let exit_code = match child.wait_timeout(RUN_TIMEOUT)? {
+            None => {
+                child.kill()?;
+                child.wait()?;
+                None
+            }
+            Some(status) => status.code(),
+        };
+        Ok(RunOutcome { stdout: fs::read_to_string(&stdout_file)?, exit_code })
+    }
+}
+
+fn build_jvm(workspace_dir: &Path) -> anyhow::Result<PathBuf> {
+    Command::new("cargo")
+        .arg("build")
+        .arg("--manifest-path").arg(workspace_dir.join("Cargo.toml"))
+        .arg("--release")
+        .spawn()?.wait()?.exit_ok()?;
+    Ok(workspace_dir.join("target/release/java"))
+}
+
+pub fn differential_test(workspace_dir: &Path, config: &XTaskConfig, seed: u64, iterations: u64) -> anyhow::Result<()> {
+    let tester = DifferentialTester::new(workspace_dir, config)?;
+    let mut random = Random::new(seed);
+    for iteration in 0..iterations {
+        let program = generate_program(&mut random);
+        if let Some((interpreted, compiled)) = tester.compare(&program)? {
+            println!("Mismatch in iteration {} of seed {}, shrinking program of size {}", iteration, seed, program.size());
+            let minimal = shrink(program, |candidate| Ok(tester.compare(candidate)?.is_some()))?;
+            let (interpreted, compiled) = tester.compare(&minimal)?.unwrap_or((interpreted, compiled));
+            let minimal_file = tester.work_dir.join(format!("{}_{}_{}.java", CLASS_NAME, seed, iteration));
+            fs::write(&minimal_file, minimal.java_source())?;
+            println!("{}", minimal.java_source());
+            println!("Interpreted: {:?}", interpreted);
+            println!("Compiled: {:?}", compiled);
+            return Err(anyhow!("interpreter and compiled code disagree, minimal program written to {}", minimal_file.display()));
+        }
+    }
+    println!("{} programs agreed between interpreter and compiled code", iterations);
+    Ok(())
+}
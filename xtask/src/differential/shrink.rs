@@ -0,0 +1,173 @@
+use crate::differential::ast::{Condition, Const, Expr, Program, Stmt};
+
+// Greedily applies the first one step reduction which still fails, until none does. Reductions are
+// tried roughly biggest first, and every one either makes the program smaller or replaces a leaf,
+// argument or trip count with zero or one, which are never reduced further, so this terminates.
+pub fn shrink(program: Program, mut still_fails: impl FnMut(&Program) -> anyhow::Result<bool>) -> anyhow::Result<Program> {
+    let mut current = program;
+    'outer: loop {
+        for candidate in reductions(&current) {
+            if still_fails(&candidate)? {
+                current = candidate;
+                continue 'outer;
+            }
+        }
+        return Ok(current);
+    }
+}
+
+fn reductions(program: &Program) -> Vec<Program> {
+    let mut res = block_reductions(&program.body).into_iter()
+        .map(|body| Program { args: program.args, body })
+        .collect::<Vec<_>>();
+    for (i, arg) in program.args.iter().enumerate() {
+        if !arg.is_zero() {
+            let mut args = program.args;
+            args[i] = Const::zero(arg.type_());
+            res.push(Program { args, body: program.body.clone() });
+        }
+    }
+    res
+}
+
+fn block_reductions(block: &[Stmt]) -> Vec<Vec<Stmt>> {
+    let mut res = vec![];
+    for (i, stmt) in block.iter().enumerate() {
+        let mut without = block.to_vec();
+        without.remove(i);
+        res.push(without);
+        // hoist the contents of control flow into the enclosing block
+        let spliced: Vec<Vec<Stmt>> = match stmt {
+            Stmt::If(_, then, else_) => vec![then.clone(), else_.clone()],
+            Stmt::Loop(_, body) => vec![body.clone()],
+            Stmt::Assign(_, _, _) | Stmt::ArrayStore(_, _, _) => vec![],
+        };
+        for replacement in spliced {
+            let mut spliced_block = block[..i].to_vec();
+            spliced_block.extend(replacement);
+            spliced_block.extend_from_slice(&block[i + 1..]);
+            res.push(spliced_block);
+        }
+    }
+    for (i, stmt) in block.iter().enumerate() {
+        for reduced in stmt_reductions(stmt) {
+            let mut reduced_block = block.to_vec();
+            reduced_block[i] = reduced;
+            res.push(reduced_block);
+        }
+    }
+    res
+}
+
+fn stmt_reductions(stmt: &Stmt) -> Vec<Stmt> {
+    match stmt {
+        Stmt::Assign(type_, index, expr) => {
+            expr_reductions(expr).into_iter().map(|expr| Stmt::Assign(*type_, *index, expr)).collect()
+        }
+        Stmt::ArrayStore(type_, index, value) => {
+            let mut res = expr_reductions(index).into_iter().map(|index| Stmt::ArrayStore(*type_, index, value.clone())).collect::<Vec<_>>();
+            res.extend(expr_reductions(value).into_iter().map(|value| Stmt::ArrayStore(*type_, index.clone(), value)));
+            res
+        }
+        Stmt::If(condition, then, else_) => {
+            let mut res = vec![];
+            for lhs in expr_reductions(&condition.lhs) {
+                res.push(Stmt::If(Condition { op: condition.op, lhs, rhs: condition.rhs.clone() }, then.clone(), else_.clone()));
+            }
+            for rhs in expr_reductions(&condition.rhs) {
+                res.push(Stmt::If(Condition { op: condition.op, lhs: condition.lhs.clone(), rhs }, then.clone(), else_.clone()));
+            }
+            for then in block_reductions(then) {
+                res.push(Stmt::If(condition.clone(), then, else_.clone()));
+            }
+            for else_ in block_reductions(else_) {
+                res.push(Stmt::If(condition.clone(), then.clone(), else_));
+            }
+            res
+        }
+        Stmt::Loop(trip_count, body) => {
+            let mut res = vec![];
+            if *trip_count > 1 {
+                res.push(Stmt::Loop(1, body.clone()));
+            }
+            res.extend(block_reductions(body).into_iter().map(|body| Stmt::Loop(*trip_count, body)));
+            res
+        }
+    }
+}
+
+fn expr_reductions(expr: &Expr) -> Vec<Expr> {
+    let mut res = vec![];
+    match expr {
+        Expr::Const(const_) if const_.is_zero() => {}
+        _ => res.push(Expr::Const(Const::zero(expr.type_()))),
+    }
+    // replace with a subexpression of the same type
+    for child in children(expr) {
+        if child.type_() == expr.type_() {
+            res.push(child.clone());
+        }
+    }
+    match expr {
+        Expr::Const(_) | Expr::Param(_) | Expr::Local(_, _) => {}
+        Expr::Negate(inner) => res.extend(expr_reductions(inner).into_iter().map(|inner| Expr::Negate(Box::new(inner)))),
+        Expr::Convert(type_, inner) => res.extend(expr_reductions(inner).into_iter().map(|inner| Expr::Convert(*type_, Box::new(inner)))),
+        Expr::Narrow(narrowing, inner) => res.extend(expr_reductions(inner).into_iter().map(|inner| Expr::Narrow(*narrowing, Box::new(inner)))),
+        Expr::ArrayLoad(type_, index) => res.extend(expr_reductions(index).into_iter().map(|index| Expr::ArrayLoad(*type_, Box::new(index)))),
+        Expr::Binary(op, lhs, rhs) => {
+            res.extend(expr_reductions(lhs).into_iter().map(|lhs| Expr::Binary(*op, Box::new(lhs), rhs.clone())));
+            res.extend(expr_reductions(rhs).into_iter().map(|rhs| Expr::Binary(*op, lhs.clone(), Box::new(rhs))));
+        }
+    }
+    res
+}
+
+fn children(expr: &Expr) -> Vec<&Expr> {
+    match expr {
+        Expr::Const(_) | Expr::Param(_) | Expr::Local(_, _) => vec![],
+        Expr::Negate(inner) |
+        Expr::Convert(_, inner) |
+        Expr::Narrow(_, inner) |
+        Expr::ArrayLoad(_, inner) => vec![inner.as_ref()],
+        Expr::Binary(_, lhs, rhs) => vec![lhs.as_ref(), rhs.as_ref()],
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::differential::ast::{Const, Program, Stmt, Type};
+    use crate::differential::ast::test::assert_well_typed;
+    use crate::differential::generate::{generate_program, Random};
+    use crate::differential::shrink::shrink;
+
+    fn has_loop(block: &[Stmt]) -> bool {
+        block.iter().any(|stmt| match stmt {
+            Stmt::Loop(_, _) => true,
+            Stmt::If(_, then, else_) => has_loop(then) || has_loop(else_),
+            Stmt::Assign(_, _, _) | Stmt::ArrayStore(_, _, _) => false,
+        })
+    }
+
+    // Shrinking a failure which only needs a loop to be present ends at the smallest loop, and every
+    // program tried on the way is one javac accepts.
+    #[test]
+    pub fn test_shrinks_to_minimal() {
+        let mut random = Random::new(3);
+        let mut shrunk = 0;
+        while shrunk < 20 {
+            let program = generate_program(&mut random);
+            if !has_loop(&program.body) {
+                continue;
+            }
+            let mut tried = 0;
+            let minimal = shrink(program, |candidate| {
+                tried += 1;
+                assert!(tried < 100_000, "shrinking did not terminate");
+                assert_well_typed(candidate);
+                Ok(has_loop(&candidate.body))
+            }).unwrap();
+            assert_eq!(minimal, Program { args: Type::ALL.map(Const::zero), body: vec![Stmt::Loop(1, vec![])] });
+            shrunk += 1;
+        }
+    }
+}
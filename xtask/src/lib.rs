@@ -18,6 +18,7 @@ pub mod build_steps;
 pub mod all;
 pub mod execution;
 pub mod file_hash;
+pub mod differential;
 
 fn default_deps_dir(workspace_dir: &Path) -> PathBuf {
     workspace_dir.join("deps")
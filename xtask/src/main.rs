@@ -5,6 +5,7 @@ use std::collections::HashSet;
 use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use anyhow::anyhow;
 use clap::Parser;
@@ -15,6 +16,7 @@ use xshell::{cmd, Shell};
 
 use xtask::{clean, deps, load_or_create_xtask_config, write_xtask_config, XTaskConfig};
 use xtask::all::all_tests;
+use xtask::differential::differential_test;
 use xtask::java_compilation::{compile, CompiledClass, javac_location};
 
 #[derive(Parser)]
@@ -48,6 +50,13 @@ pub enum OptsInner {
     OpenJDKTest {},
     #[clap(about = "run all openjdk tests")]
     OpenJDKAll {},
+    #[clap(about = "compare interpreted and compiled execution of randomly generated methods")]
+    DiffTest {
+        #[clap(long)]
+        seed: Option<u64>,
+        #[clap(long, default_value = "100")]
+        iterations: u64,
+    },
 }
 
 fn change_config_option(workspace_dir: &Path, changer: impl FnOnce(&mut XTaskConfig)) -> anyhow::Result<()> {
@@ -193,20 +202,26 @@ fn main() -> anyhow::Result<()> {
                 all_tests(workspace_dir).await
             })?
         }
+        OptsInner::DiffTest { seed, iterations } => {
+            let config = load_or_create_xtask_config(workspace_dir)?;
+            let seed = seed.unwrap_or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs());
+            differential_test(workspace_dir, &config, seed, iterations)?;
+        }
     }
     Ok(())
 }
 
 
 fn run_classes(jdk_dir: PathBuf, compilation_dir: PathBuf, class_files: Vec<CompiledClass>, exclude: HashSet<String>) -> anyhow::Result<()> {
-    let classpath = format!("{}/build/linux-x86_64-normal-server-fastdebug/jdk/classes {}/build/linux-x86_64-normal-server-fastdebug/jdk/classes_security", jdk_dir.display(), jdk_dir.display());
-    let libjava = jdk_dir.join("build/linux-x86_64-normal-server-fastdebug/jdk/lib/amd64/libjava.so");
+    //the built jdk's image, whose lib/amd64 has libjava.so
+    let java_home = jdk_dir.join("build/linux-x86_64-normal-server-fastdebug/jdk");
+    let classpath = format!("{}/classes {}/classes_security", java_home.display(), java_home.display());
 
     class_files.into_iter().try_for_each(|main| {
         if !exclude.contains(&main.name()) {
             let mut args = vec![];
-            args.extend(shell_words::split(format!("run --release -- --main {} --libjava {} --classpath {} {}", main.name(), libjava.display(), compilation_dir.display(), classpath).as_str())?);
-            Command::new("cargo").args(args).spawn()?.wait()?;
+            args.extend(shell_words::split(format!("run --release -- --main {} --java-home {} --classpath {} {}", main.name(), java_home.display(), compilation_dir.display(), classpath).as_str())?);
+            Command::new("cargo").args(args).spawn()?.wait()?.exit_ok()?;
         }
         Ok::<_, anyhow::Error>(())
     })?;